@@ -176,6 +176,59 @@ fn bench_broadcast_channel(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark exporting a file with different transfer chunk sizes
+///
+/// Mirrors the chunked read/write loop used when exporting a blob to disk.
+fn bench_export_chunk_size(c: &mut Criterion) {
+    use std::io::Read;
+
+    let mut group = c.benchmark_group("export_chunk_size");
+    let size = 16 * 1024 * 1024; // 16 MB
+
+    let data = generate_test_data(size);
+    let mut source = NamedTempFile::new().unwrap();
+    source.write_all(&data).unwrap();
+    source.flush().unwrap();
+    let dest_dir = tempfile::tempdir().unwrap();
+
+    group.throughput(Throughput::Bytes(size as u64));
+    for chunk_size in [
+        4 * 1024,        // 4 KB (minimum)
+        64 * 1024,       // 64 KB (default)
+        256 * 1024,      // 256 KB
+        1024 * 1024,     // 1 MB
+        4 * 1024 * 1024, // 4 MB
+    ] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format_size(chunk_size)),
+            &chunk_size,
+            |b, &chunk_size| {
+                b.iter(|| {
+                    let mut input = std::fs::File::open(source.path()).unwrap();
+                    let mut output = std::fs::File::create(dest_dir.path().join("out")).unwrap();
+                    let mut buffer = vec![0u8; chunk_size];
+                    let mut written = 0usize;
+
+                    while written < size {
+                        let len = chunk_size.min(size - written);
+                        let read = input.read(&mut buffer[..len]).unwrap();
+                        if read == 0 {
+                            break;
+                        }
+                        output.write_all(&buffer[..read]).unwrap();
+                        written += read;
+                    }
+
+                    output.flush().unwrap();
+                    black_box(written)
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
 /// Format size in human-readable form
 fn format_size(bytes: usize) -> String {
     if bytes >= 1024 * 1024 {
@@ -194,6 +247,7 @@ criterion_group!(
     bench_file_read_streaming,
     bench_encryption_streaming,
     bench_broadcast_channel,
+    bench_export_chunk_size,
 );
 
 criterion_main!(benches);
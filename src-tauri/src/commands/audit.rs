@@ -5,6 +5,7 @@
 
 use crate::core::{AuditEntryDto, AuditFilter, AuditLogger};
 use std::sync::Arc;
+use tauri::ipc::Channel;
 use tauri::State;
 
 /// Get audit log entries with optional filters
@@ -83,3 +84,38 @@ pub async fn get_denied_access_log(
 
     Ok(entries.into_iter().map(AuditEntryDto::from).collect())
 }
+
+/// Stream new audit entries to the frontend as they are written
+///
+/// Only entries matching the optional filters are sent over `on_entry`.
+/// Returns a subscription ID for `unsubscribe_audit`. A subscriber that
+/// can't keep up skips entries instead of slowing down logging.
+#[tauri::command]
+pub async fn subscribe_audit(
+    drive_id: Option<String>,
+    event_type: Option<String>,
+    user_id: Option<String>,
+    on_entry: Channel<AuditEntryDto>,
+    audit_logger: State<'_, Arc<AuditLogger>>,
+) -> Result<String, String> {
+    let filter = AuditFilter {
+        drive_id,
+        event_type,
+        user_id,
+        ..Default::default()
+    };
+    let subscription_id = audit_logger.subscribe_live(filter, on_entry);
+    tracing::info!(subscription_id = %subscription_id, "Frontend subscribed to audit log");
+    Ok(subscription_id)
+}
+
+/// Stop streaming audit entries
+///
+/// Returns false if the subscription was unknown or already closed.
+#[tauri::command]
+pub async fn unsubscribe_audit(
+    subscription_id: String,
+    audit_logger: State<'_, Arc<AuditLogger>>,
+) -> Result<bool, String> {
+    Ok(audit_logger.unsubscribe_live(&subscription_id))
+}
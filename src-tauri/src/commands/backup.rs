@@ -0,0 +1,167 @@
+//! Backup and restore commands
+//!
+//! Back up all app data to one passphrase-encrypted archive, and restore it
+//! into a fresh data directory on another machine.
+
+use crate::core::AppError;
+use crate::crypto::passphrase::validate_passphrase;
+use crate::instance::InstanceInfo;
+use crate::state::AppState;
+use crate::storage::{backup, BackupManifest};
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::State;
+
+/// Result of restoring a backup
+#[derive(Clone, Debug, Serialize)]
+pub struct RestoreResult {
+    #[serde(flatten)]
+    pub manifest: BackupManifest,
+    /// Data directory the backup was restored into
+    pub data_dir: String,
+}
+
+fn absolute_path(field: &str, path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path.trim());
+    if !path.is_absolute() {
+        return Err(AppError::ValidationFailed {
+            field: field.to_string(),
+            reason: "must be an absolute path".to_string(),
+        }
+        .to_string());
+    }
+    Ok(path)
+}
+
+/// Back up identity, drives, ACLs, keys, metadata and stored content
+///
+/// Writes one archive encrypted under `passphrase` to `path`. The database
+/// is snapshotted in a single transaction. Gossip sync and downloads are
+/// paused and the blob store flushed while the docs and blob stores are
+/// copied; downloads in progress are left out.
+/// Keep the passphrase safe: the archive can't be opened without it.
+#[tauri::command]
+pub async fn backup_all(
+    path: String,
+    passphrase: String,
+    state: State<'_, AppState>,
+    instance: State<'_, InstanceInfo>,
+) -> Result<BackupManifest, String> {
+    validate_passphrase(&passphrase).map_err(|e| {
+        AppError::ValidationFailed {
+            field: "passphrase".to_string(),
+            reason: e.to_string(),
+        }
+        .to_string()
+    })?;
+    let out = absolute_path("path", &path)?;
+    let node_id = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+
+    let paused = match state.sync_engine {
+        Some(ref sync_engine) => sync_engine.pause_all().await,
+        None => false,
+    };
+    let transfers_paused = match state.file_transfer {
+        Some(ref transfer) => {
+            let was_paused = transfer.is_paused();
+            transfer.set_paused(true);
+            if let Err(e) = transfer.flush_store().await {
+                tracing::warn!("{:#}", e);
+            }
+            !was_paused
+        }
+        None => false,
+    };
+
+    // Plaintext snapshots are staged in a private directory inside the
+    // data directory and removed once the archive is written
+    let db = state.db.clone();
+    let data_dir = instance.data_dir.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let staging = backup::create_staging_dir(&data_dir)?;
+        let snapshot = staging.join(backup::DATABASE_FILE);
+        let result = db.snapshot_to(&snapshot).and_then(|_| {
+            backup::write_backup(
+                &data_dir,
+                &staging,
+                &snapshot,
+                &node_id.to_hex(),
+                &out,
+                &passphrase,
+            )
+        });
+        let _ = std::fs::remove_dir_all(&staging);
+        result
+    })
+    .await;
+
+    // Resume before reporting any error, including a panic in the backup task
+    if paused {
+        if let Some(ref sync_engine) = state.sync_engine {
+            sync_engine.resume_all().await;
+        }
+    }
+    if transfers_paused {
+        if let Some(ref transfer) = state.file_transfer {
+            transfer.set_paused(false);
+        }
+    }
+
+    let manifest = result
+        .map_err(|e| AppError::Internal(e.to_string()).to_string())?
+        .map_err(|e| AppError::BackupFailed(format!("{:#}", e)).to_string())?;
+    tracing::info!(
+        path = %path,
+        files = manifest.file_count,
+        bytes = manifest.total_bytes,
+        "Backed up app data"
+    );
+    Ok(manifest)
+}
+
+/// Restore a backup into a new data directory
+///
+/// `data_dir` must not exist or be empty, and can't be the directory this
+/// instance is using. Nothing is written if the passphrase is wrong. Launch
+/// the app with `--data-dir <data_dir>` (or move the directory into place)
+/// to run as the restored identity.
+#[tauri::command]
+pub async fn restore_all(
+    archive_path: String,
+    passphrase: String,
+    data_dir: String,
+    instance: State<'_, InstanceInfo>,
+) -> Result<RestoreResult, String> {
+    let archive = absolute_path("archive_path", &archive_path)?;
+    let target = absolute_path("data_dir", &data_dir)?;
+    if target == instance.data_dir {
+        return Err(AppError::ValidationFailed {
+            field: "data_dir".to_string(),
+            reason: "can't restore over the data directory in use".to_string(),
+        }
+        .to_string());
+    }
+
+    let restore_target = target.clone();
+    let manifest = tokio::task::spawn_blocking(move || {
+        backup::restore_backup(&archive, &passphrase, &restore_target)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()).to_string())?
+    .map_err(|e| AppError::BackupFailed(format!("{:#}", e)).to_string())?;
+
+    tracing::info!(
+        data_dir = ?target,
+        node_id = %manifest.node_id,
+        files = manifest.file_count,
+        "Restored backup"
+    );
+    Ok(RestoreResult {
+        manifest,
+        data_dir: target.to_string_lossy().to_string(),
+    })
+}
@@ -6,11 +6,19 @@
 //! - Validates drive IDs before operations
 //! - Validates paths to prevent directory traversal attacks
 
+use crate::commands::security::SecurityStore;
+use crate::core::conflict::MAX_MERGE_SIZE;
 use crate::core::error::AppError;
 use crate::core::validation::{validate_drive_id, validate_path};
-use crate::core::{ConflictManager, FileConflictDto, ResolutionStrategy};
+use crate::core::{
+    file, ConflictManager, ConflictPolicy, DriveEvent, DriveInfo, FileConflictDto, MergeResult,
+    ResolutionStrategy,
+};
+use crate::crypto::Permission;
+use crate::network::FileTransferManager;
 use crate::state::AppState;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::sync::Arc;
 use tauri::State;
 
@@ -20,6 +28,21 @@ fn parse_drive_id(drive_id: &str) -> Result<crate::core::drive::DriveId, String>
     crate::core::drive::DriveId::from_hex(drive_id).map_err(|e| e.to_string())
 }
 
+/// Parse a resolution strategy name, accepting the spellings the UI uses
+fn parse_strategy(strategy: &str) -> Result<ResolutionStrategy, String> {
+    match strategy.to_lowercase().as_str() {
+        "keeplocal" | "keep_local" | "local" => Ok(ResolutionStrategy::KeepLocal),
+        "keepremote" | "keep_remote" | "remote" => Ok(ResolutionStrategy::KeepRemote),
+        "keepboth" | "keep_both" | "both" => Ok(ResolutionStrategy::KeepBoth),
+        "manualmerge" | "manual_merge" | "merge" => Ok(ResolutionStrategy::ManualMerge),
+        _ => Err(AppError::ValidationError(format!(
+            "Invalid resolution strategy: {}. Use: keeplocal, keepremote, keepboth, or manualmerge",
+            strategy
+        ))
+        .to_string()),
+    }
+}
+
 /// DTO for resolution request
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[allow(dead_code)]
@@ -42,6 +65,41 @@ pub async fn list_conflicts(
     Ok(conflicts.iter().map(FileConflictDto::from).collect())
 }
 
+/// A conflict together with the drive it belongs to
+#[derive(Clone, Debug, Serialize)]
+pub struct DriveConflictDto {
+    pub drive_id: String,
+    #[serde(flatten)]
+    pub conflict: FileConflictDto,
+}
+
+/// List unresolved conflicts on every drive, oldest first
+///
+/// Backs a global "attention needed" badge so conflicts on drives that
+/// aren't open still get noticed.
+#[tauri::command]
+pub async fn list_all_conflicts(
+    conflict_manager: State<'_, Arc<ConflictManager>>,
+) -> Result<Vec<DriveConflictDto>, String> {
+    Ok(conflict_manager
+        .list_all_conflicts()
+        .await
+        .iter()
+        .map(|(drive_id, conflict)| DriveConflictDto {
+            drive_id: drive_id.clone(),
+            conflict: FileConflictDto::from(conflict),
+        })
+        .collect())
+}
+
+/// Get the number of unresolved conflicts across all drives
+#[tauri::command]
+pub async fn get_total_conflict_count(
+    conflict_manager: State<'_, Arc<ConflictManager>>,
+) -> Result<usize, String> {
+    Ok(conflict_manager.total_conflict_count().await)
+}
+
 /// Get a specific conflict by path
 #[tauri::command]
 pub async fn get_conflict(
@@ -84,15 +142,7 @@ pub async fn resolve_conflict(
     let validated_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
     drop(drives);
     
-    let strategy = match strategy.to_lowercase().as_str() {
-        "keeplocal" | "keep_local" | "local" => ResolutionStrategy::KeepLocal,
-        "keepremote" | "keep_remote" | "remote" => ResolutionStrategy::KeepRemote,
-        "keepboth" | "keep_both" | "both" => ResolutionStrategy::KeepBoth,
-        "manualmerge" | "manual_merge" | "merge" => ResolutionStrategy::ManualMerge,
-        _ => return Err(AppError::ValidationError(
-            format!("Invalid resolution strategy: {}. Use: keeplocal, keepremote, keepboth, or manualmerge", strategy)
-        ).to_string()),
-    };
+    let strategy = parse_strategy(&strategy)?;
 
     let resolved = conflict_manager
         .resolve_conflict(&drive_id, &validated_path, strategy)
@@ -110,6 +160,131 @@ pub async fn resolve_conflict(
     Ok(resolved.map(|c| FileConflictDto::from(&c)))
 }
 
+/// Set how a drive resolves conflicts as they're detected
+///
+/// `manual` (the default) leaves them for the user. `last_writer_wins`
+/// keeps the newer version, breaking ties by node ID so every peer keeps
+/// the same one; `keep_local` and `keep_remote` always keep that side.
+/// Auto-resolved conflicts emit a `ConflictAutoResolved` event.
+#[tauri::command]
+pub async fn set_conflict_policy(
+    drive_id: String,
+    policy: ConflictPolicy,
+    state: State<'_, AppState>,
+    conflict_manager: State<'_, Arc<ConflictManager>>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.conflict_policy = policy;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+    conflict_manager.set_policy(&drive_id, policy).await;
+
+    tracing::info!(drive_id = %drive_id, policy = ?policy, "Updated conflict policy");
+    Ok(DriveInfo::from(&*drive))
+}
+
+/// Three-way merge a text conflict into the local file
+///
+/// Uses the conflict's common ancestor, or else the version the synced
+/// metadata says was replaced. Non-overlapping edits merge cleanly and
+/// resolve the conflict; overlapping hunks are written with
+/// `<<<<<<<`/`=======`/`>>>>>>>` markers and the conflict stays open.
+/// Needs Write permission on the path.
+#[tauri::command]
+pub async fn merge_text_conflict(
+    drive_id: String,
+    path: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    conflict_manager: State<'_, Arc<ConflictManager>>,
+) -> Result<MergeResult, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    // Validate path against drive root
+    let drives = state.drives.read().await;
+    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+        AppError::DriveNotFound { drive_id: drive_id.clone() }.to_string()
+    })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+    let validated_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
+    let owner_hex = drive.owner.to_hex();
+    drop(drives);
+
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, &path, Permission::Write) {
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to merge conflict".to_string(),
+        }
+        .to_string());
+    }
+
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+    let ancestor = match state.docs_manager.as_ref() {
+        Some(docs_manager) => docs_manager
+            .get_file_metadata(&id, &file::path_key(Path::new(&path)))
+            .await
+            .and_then(|meta| meta.previous_hash),
+        None => None,
+    };
+
+    let read_version = |hash: String| read_text_version(file_transfer, &validated_path, hash);
+    conflict_manager
+        .merge_text_conflict(&drive_id, &validated_path, ancestor, read_version)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Read one version of a text file from the blob store
+async fn read_text_version(
+    file_transfer: &FileTransferManager,
+    path: &Path,
+    hash: String,
+) -> Result<String, AppError> {
+    let blob = hash
+        .parse()
+        .map_err(|_| AppError::InvalidHash(hash.clone()))?;
+    let (data, size) = file_transfer
+        .read_blob_prefix(blob, MAX_MERGE_SIZE)
+        .await
+        .map_err(|e| AppError::TransferFailed(format!("Version {} unavailable: {}", hash, e)))?;
+    if size > MAX_MERGE_SIZE {
+        return Err(AppError::FileTooLarge {
+            path: path.display().to_string(),
+            size,
+            max: MAX_MERGE_SIZE,
+        });
+    }
+    String::from_utf8(data).map_err(|_| {
+        AppError::ValidationError(format!(
+            "Version {} of {} is not text",
+            hash,
+            path.display()
+        ))
+    })
+}
+
 /// Get total conflict count for a drive
 #[tauri::command]
 pub async fn get_conflict_count(
@@ -154,3 +329,146 @@ pub async fn dismiss_conflict(
 
     Ok(resolved.is_some())
 }
+
+/// Outcome of resolving one conflict in `resolve_all_conflicts`
+#[derive(Clone, Debug, Serialize)]
+pub struct ConflictResolutionResult {
+    pub conflict_id: String,
+    pub path: String,
+    pub resolved: bool,
+    /// Why the conflict was left unresolved
+    pub error: Option<String>,
+    /// Hash of the version given up by the strategy, if one was
+    pub discarded_hash: Option<String>,
+}
+
+/// Result of resolving every conflict in a drive
+#[derive(Clone, Debug, Serialize)]
+pub struct ResolveAllConflictsResult {
+    pub drive_id: String,
+    pub strategy: ResolutionStrategy,
+    pub results: Vec<ConflictResolutionResult>,
+    pub resolved_count: usize,
+    pub failed_count: usize,
+    /// What the strategy threw away, if anything
+    pub warning: Option<String>,
+}
+
+/// Resolve every outstanding conflict in a drive with one strategy
+///
+/// `strategy` is parsed like `resolve_conflict`'s. `keeplocal` discards
+/// every remote version and `keepremote` every local one; the result lists
+/// the discarded hashes. `manualmerge` can't be applied in bulk. Each conflict needs Write permission on its path;
+/// conflicts that fail are reported and the rest are still resolved. A
+/// `ConflictResolved` event is emitted for each resolved file.
+#[tauri::command]
+pub async fn resolve_all_conflicts(
+    drive_id: String,
+    strategy: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    conflict_manager: State<'_, Arc<ConflictManager>>,
+) -> Result<ResolveAllConflictsResult, String> {
+    let id = parse_drive_id(&drive_id)?;
+    let strategy = parse_strategy(&strategy)?;
+
+    if strategy == ResolutionStrategy::ManualMerge {
+        return Err(AppError::ValidationFailed {
+            field: "strategy".to_string(),
+            reason: "manual merge must be done one conflict at a time".to_string(),
+        }
+        .to_string());
+    }
+
+    let owner_hex = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+            AppError::DriveNotFound { drive_id: drive_id.clone() }.to_string()
+        })?;
+        drive.ensure_active().map_err(|e| e.to_string())?;
+        drive.owner.to_hex()
+    };
+
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+
+    let mut conflicts = conflict_manager.list_conflicts(&drive_id).await;
+    conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let mut results = Vec::with_capacity(conflicts.len());
+    for conflict in conflicts {
+        let path = conflict.path.to_string_lossy().replace('\\', "/");
+        let mut result = ConflictResolutionResult {
+            conflict_id: conflict.id.clone(),
+            path: path.clone(),
+            resolved: false,
+            error: None,
+            discarded_hash: None,
+        };
+
+        if !acl.check_permission(&caller_hex, &path, Permission::Write) {
+            result.error = Some("insufficient permission to resolve conflict".to_string());
+            results.push(result);
+            continue;
+        }
+
+        match conflict_manager
+            .resolve_conflict(&drive_id, &conflict.path, strategy)
+            .await
+        {
+            Some(resolved) => {
+                result.resolved = true;
+                result.discarded_hash = strategy.discarded(&resolved).map(|v| v.hash.clone());
+                if let Some(ref broadcaster) = state.event_broadcaster {
+                    broadcaster.emit_local(
+                        &id,
+                        &DriveEvent::ConflictResolved {
+                            path: resolved.path.clone(),
+                            strategy,
+                            resolved_by: caller,
+                            timestamp: chrono::Utc::now(),
+                        },
+                    );
+                }
+            }
+            None => result.error = Some("conflict was already resolved".to_string()),
+        }
+        results.push(result);
+    }
+
+    let resolved_count = results.iter().filter(|r| r.resolved).count();
+    let failed_count = results.len() - resolved_count;
+    let warning = match strategy {
+        ResolutionStrategy::KeepLocal if resolved_count > 0 => Some(format!(
+            "Remote versions of {} file(s) were discarded",
+            resolved_count
+        )),
+        ResolutionStrategy::KeepRemote if resolved_count > 0 => Some(format!(
+            "Local versions of {} file(s) were discarded",
+            resolved_count
+        )),
+        _ => None,
+    };
+
+    tracing::info!(
+        drive_id = %drive_id,
+        strategy = ?strategy,
+        resolved = resolved_count,
+        failed = failed_count,
+        "Resolved all conflicts"
+    );
+
+    Ok(ResolveAllConflictsResult {
+        drive_id,
+        strategy,
+        results,
+        resolved_count,
+        failed_count,
+        warning,
+    })
+}
@@ -0,0 +1,65 @@
+//! Development commands
+//!
+//! Tools for reproducing sync issues on a single machine. They refuse to
+//! run in release builds.
+
+use crate::commands::SecurityStore;
+use crate::core::{validate_drive_id, AppError, DriveId};
+use crate::state::AppState;
+use crate::test_peer::{TestPeerInfo, TestPeerManager};
+use std::sync::Arc;
+use tauri::State;
+
+fn ensure_dev_build() -> Result<(), String> {
+    if TestPeerManager::is_available() {
+        return Ok(());
+    }
+    Err(AppError::AccessDenied {
+        reason: "test peers are only available in debug builds".to_string(),
+    }
+    .to_string())
+}
+
+/// Start an in-process test peer that joins a drive with write access
+///
+/// The peer has its own identity, database and endpoint, and syncs the
+/// drive into `local_path` of the returned info; editing files there acts
+/// as the peer. Sync must already be running for the drive. Debug builds
+/// only.
+#[tauri::command]
+pub async fn spawn_test_peer(
+    drive_id: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    test_peers: State<'_, Arc<TestPeerManager>>,
+) -> Result<TestPeerInfo, String> {
+    ensure_dev_build()?;
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    test_peers
+        .spawn(&state, &security, DriveId(id_arr))
+        .await
+        .map_err(|e| AppError::SyncFailed(format!("Failed to spawn test peer: {}", e)).to_string())
+}
+
+/// List running test peers
+#[tauri::command]
+pub async fn list_test_peers(
+    test_peers: State<'_, Arc<TestPeerManager>>,
+) -> Result<Vec<TestPeerInfo>, String> {
+    ensure_dev_build()?;
+    Ok(test_peers.list().await)
+}
+
+/// Stop a test peer, revoke its access and delete its data
+///
+/// Returns false if no test peer has that node ID.
+#[tauri::command]
+pub async fn stop_test_peer(
+    node_id: String,
+    security: State<'_, Arc<SecurityStore>>,
+    test_peers: State<'_, Arc<TestPeerManager>>,
+) -> Result<bool, String> {
+    ensure_dev_build()?;
+    Ok(test_peers.stop(&node_id, &security).await)
+}
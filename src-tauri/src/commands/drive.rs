@@ -3,13 +3,30 @@
 //! All commands include proper input validation, path sanitization,
 //! and structured error handling.
 
-use crate::core::{file, validate_drive_id, validate_name, AppError, DriveInfo, SharedDrive};
+use crate::commands::security::SecurityStore;
+use crate::core::conflict::{ConflictVersion, FileConflict};
+use crate::core::drive::load_deleted_drives;
+use crate::core::{
+    file, validate_custom_metadata, validate_drive_id, validate_name, ActivityRetention, AppError,
+    CleanupManager, ConflictManager,
+    ConflictSensitivity, DeletedDriveInfo, DriveEvent, DriveEventDto, DriveId, DriveInfo,
+    EventSubscriptions,
+    NamePolicy, NewFilePolicy, OperationRegistry, SharedDrive,
+};
+use crate::crypto::Permission;
 use crate::state::AppState;
-use tauri::State;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
 
 /// Maximum file count for initial indexing (prevent DoS)
 const MAX_INDEX_FILES: usize = 100_000;
 
+/// Emit a merge progress event every this many files
+const MERGE_PROGRESS_INTERVAL: usize = 25;
+
 /// Create a new shared drive from a local folder
 #[tauri::command]
 pub async fn create_drive(
@@ -95,10 +112,25 @@ pub async fn create_drive(
 }
 
 /// List all owned drives
+///
+/// With `metadata_filter`, only drives whose custom metadata has every
+/// given key set to the given value are returned.
 #[tauri::command]
-pub async fn list_drives(state: State<'_, AppState>) -> Result<Vec<DriveInfo>, String> {
+pub async fn list_drives(
+    metadata_filter: Option<HashMap<String, String>>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DriveInfo>, String> {
+    let filter = metadata_filter.unwrap_or_default();
     let drives = state.drives.read().await;
-    let infos: Vec<DriveInfo> = drives.values().map(DriveInfo::from).collect();
+    let mut infos: Vec<DriveInfo> = drives
+        .values()
+        .filter(|drive| drive.matches_metadata(&filter))
+        .map(DriveInfo::from)
+        .collect();
+    // Active drives first, archived ones grouped after them
+    infos.sort_by(|a, b| {
+        (a.archived_at.is_some(), &a.name).cmp(&(b.archived_at.is_some(), &b.name))
+    });
     
     tracing::debug!(count = infos.len(), "Listed drives");
     Ok(infos)
@@ -121,37 +153,154 @@ pub async fn get_drive(drive_id: String, state: State<'_, AppState>) -> Result<D
 }
 
 /// Delete a drive by ID
+///
+/// The drive stops all activity and disappears from `list_drives`, but its
+/// configuration is kept for the cleanup manager's retention period so it
+/// can be brought back with `restore_drive`; after that the cleanup manager
+/// removes it for good. `permanent` removes it immediately, including a drive that
+/// was already deleted. Deleting only affects this device: peers of a
+/// shared drive keep it.
 #[tauri::command]
-pub async fn delete_drive(drive_id: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn delete_drive(
+    drive_id: String,
+    permanent: Option<bool>,
+    state: State<'_, AppState>,
+    cleanup: State<'_, Arc<CleanupManager>>,
+) -> Result<(), String> {
     let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let id = DriveId(id_arr);
 
     // Stop any active sync/watching first
     if let Some(ref sync_engine) = state.sync_engine {
-        sync_engine.stop_sync(&crate::core::DriveId(id_arr)).await;
+        sync_engine.stop_sync(&id).await;
     }
     if let Some(ref file_watcher) = state.file_watcher {
-        file_watcher.unwatch(&crate::core::DriveId(id_arr)).await;
+        file_watcher.unwatch(&id).await;
     }
 
-    // Remove from database
-    let removed = state.db.delete_drive(&id_arr).map_err(|e| {
-        AppError::DatabaseError(format!("Failed to delete drive: {}", e)).to_string()
-    })?;
+    if permanent.unwrap_or(false) {
+        // Remove from database
+        let removed = state.db.delete_drive(&id_arr).map_err(|e| {
+            AppError::DatabaseError(format!("Failed to delete drive: {}", e)).to_string()
+        })?;
+
+        if !removed {
+            return Err(AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string());
+        }
+
+        // Remove from in-memory cache
+        state.drives.write().await.remove(&id_arr);
 
-    if !removed {
-        return Err(AppError::DriveNotFound {
+        tracing::info!(drive_id = %drive_id, "Permanently deleted drive");
+        return Ok(());
+    }
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
             drive_id: drive_id.clone(),
         }
-        .to_string());
+        .to_string()
+    })?;
+
+    if let Some(ref docs_manager) = state.docs_manager {
+        if let Err(e) = docs_manager.set_doc_syncing(&id, false).await {
+            tracing::warn!(drive_id = %drive_id, error = %e, "Failed to stop doc sync");
+        }
     }
 
-    // Remove from in-memory cache
-    state.drives.write().await.remove(&id_arr);
+    drive.deleted_at = Some(chrono::Utc::now());
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    // Deleted drives live only in the database until restored
+    drives.remove(&id_arr);
 
-    tracing::info!(drive_id = %drive_id, "Deleted drive");
+    tracing::info!(
+        drive_id = %drive_id,
+        retention_days = cleanup.deleted_drive_retention().num_days(),
+        "Deleted drive"
+    );
     Ok(())
 }
 
+/// List deleted drives that can still be restored, most recent first
+#[tauri::command]
+pub async fn list_deleted_drives(
+    state: State<'_, AppState>,
+    cleanup: State<'_, Arc<CleanupManager>>,
+) -> Result<Vec<DeletedDriveInfo>, String> {
+    let deleted = load_deleted_drives(&state.db).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to load deleted drives: {}", e)).to_string()
+    })?;
+    let retention = cleanup.deleted_drive_retention();
+    Ok(deleted
+        .iter()
+        .map(|drive| DeletedDriveInfo::new(drive, retention))
+        .collect())
+}
+
+/// Undo `delete_drive` while the drive is in its recovery window
+///
+/// Doc sync is re-enabled; gossip and file watching start again through
+/// `start_sync` and `start_watching` as for any other drive.
+#[tauri::command]
+pub async fn restore_drive(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    if let Some(drive) = drives.get(&id_arr) {
+        return Ok(DriveInfo::from(drive));
+    }
+
+    let not_found = || {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    };
+    let bytes = state
+        .db
+        .get_drive(&id_arr)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?
+        .ok_or_else(not_found)?;
+    let mut drive: SharedDrive = serde_json::from_slice(&bytes)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    if !drive.is_deleted() {
+        return Err(not_found());
+    }
+
+    drive.deleted_at = None;
+    let drive_bytes = serde_json::to_vec(&drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    if let Some(ref docs_manager) = state.docs_manager {
+        if let Err(e) = docs_manager.set_doc_syncing(&DriveId(id_arr), true).await {
+            tracing::warn!(drive_id = %drive_id, error = %e, "Failed to restart doc sync");
+        }
+    }
+
+    let info = DriveInfo::from(&drive);
+    drives.insert(id_arr, drive);
+
+    tracing::info!(drive_id = %drive_id, "Restored deleted drive");
+    Ok(info)
+}
+
 /// Rename a drive
 #[tauri::command]
 pub async fn rename_drive(
@@ -192,3 +341,660 @@ pub async fn rename_drive(
 
     Ok(DriveInfo::from(&*drive))
 }
+
+/// Archive a drive: keep its files but stop all activity
+///
+/// Stops gossip, doc sync, and file watching, and makes the drive read-only
+/// until `unarchive_drive`. Nothing is deleted. Archived drives are listed
+/// after active ones and refuse writes, transfers, and `start_sync`.
+#[tauri::command]
+pub async fn archive_drive(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let id = DriveId(id_arr);
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    if drive.is_archived() {
+        return Ok(DriveInfo::from(&*drive));
+    }
+
+    // Stop all activity before marking the drive archived
+    if let Some(ref sync_engine) = state.sync_engine {
+        sync_engine.stop_sync(&id).await;
+    }
+    if let Some(ref docs_manager) = state.docs_manager {
+        if let Err(e) = docs_manager.set_doc_syncing(&id, false).await {
+            tracing::warn!(drive_id = %drive_id, error = %e, "Failed to stop doc sync");
+        }
+    }
+    if let Some(ref file_watcher) = state.file_watcher {
+        file_watcher.unwatch(&id).await;
+    }
+
+    drive.archived_at = Some(chrono::Utc::now());
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(drive_id = %drive_id, "Archived drive");
+    Ok(DriveInfo::from(&*drive))
+}
+
+/// Return an archived drive to normal use
+///
+/// Doc sync is re-enabled; gossip and file watching start again through
+/// `start_sync` and `start_watching` as for any other drive.
+#[tauri::command]
+pub async fn unarchive_drive(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    if !drive.is_archived() {
+        return Ok(DriveInfo::from(&*drive));
+    }
+
+    drive.archived_at = None;
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    if let Some(ref docs_manager) = state.docs_manager {
+        if let Err(e) = docs_manager.set_doc_syncing(&DriveId(id_arr), true).await {
+            tracing::warn!(drive_id = %drive_id, error = %e, "Failed to restart doc sync");
+        }
+    }
+
+    tracing::info!(drive_id = %drive_id, "Unarchived drive");
+    Ok(DriveInfo::from(&*drive))
+}
+
+/// Get a drive's custom key/value metadata
+#[tauri::command]
+pub async fn get_drive_metadata(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, String>, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let drives = state.drives.read().await;
+    let drive = drives.get(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    Ok(drive.custom_metadata.clone())
+}
+
+/// Replace a drive's custom key/value metadata
+///
+/// The whole map is replaced; pass an empty map to clear it. Metadata is
+/// local to this device and is not synced to peers.
+#[tauri::command]
+pub async fn set_drive_metadata(
+    drive_id: String,
+    metadata: HashMap<String, String>,
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, String>, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let metadata = validate_custom_metadata(metadata).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.custom_metadata = metadata;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        entries = drive.custom_metadata.len(),
+        "Updated drive metadata"
+    );
+    Ok(drive.custom_metadata.clone())
+}
+
+/// Set the largest file allowed in a drive
+///
+/// Writes, imports and downloads of larger files fail with `FileTooLarge`.
+/// Files already in the drive are left alone. `0` removes the limit.
+#[tauri::command]
+pub async fn set_max_file_size(
+    drive_id: String,
+    max_bytes: u64,
+    state: State<'_, AppState>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.max_file_size = max_bytes;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(drive_id = %drive_id, max_bytes = max_bytes, "Updated max file size");
+    Ok(DriveInfo::from(&*drive))
+}
+
+/// Set how file names are canonicalized in a drive
+///
+/// Applies to writes, imports and renames from now on; existing files keep
+/// their names. Every member should use the same policy so all peers store
+/// the same names.
+#[tauri::command]
+pub async fn set_name_policy(
+    drive_id: String,
+    policy: NamePolicy,
+    state: State<'_, AppState>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.name_policy = policy;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(drive_id = %drive_id, policy = ?policy, "Updated name policy");
+    Ok(DriveInfo::from(&*drive))
+}
+
+/// Set how different two versions of a file must be to conflict
+///
+/// With `ignore_whitespace`, text files that differ only in line endings or
+/// trailing whitespace are treated as the same; binaries are always compared
+/// by hash. Off (`exact`) by default.
+#[tauri::command]
+pub async fn set_conflict_sensitivity(
+    drive_id: String,
+    sensitivity: ConflictSensitivity,
+    state: State<'_, AppState>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.conflict_sensitivity = sensitivity;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        sensitivity = ?sensitivity,
+        "Updated conflict sensitivity"
+    );
+    Ok(DriveInfo::from(&*drive))
+}
+
+/// Set how much of a drive's activity feed is kept
+///
+/// The cleanup job prunes the oldest entries beyond `max_entries` or older
+/// than `max_age_days` (the cleanup default of one week if not set).
+/// Defaults to the last 1000 entries.
+#[tauri::command]
+pub async fn set_activity_retention(
+    drive_id: String,
+    retention: ActivityRetention,
+    state: State<'_, AppState>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    retention.validate().map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.activity_retention = retention;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        max_entries = retention.max_entries,
+        max_age_days = ?retention.max_age_days,
+        "Updated activity retention"
+    );
+    Ok(DriveInfo::from(&*drive))
+}
+
+/// Set what happens when a peer adds a file this device doesn't have
+///
+/// `auto_download` (the default) fetches it right away, `on_demand` leaves
+/// it remote-only until opened, and `prompt` sends a `NewFileAvailable`
+/// event so the user can choose.
+#[tauri::command]
+pub async fn set_new_file_policy(
+    drive_id: String,
+    policy: NewFilePolicy,
+    state: State<'_, AppState>,
+) -> Result<DriveInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.new_file_policy = policy;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(drive_id = %drive_id, policy = ?policy, "Updated new file policy");
+    Ok(DriveInfo::from(&*drive))
+}
+
+/// Result of merging one drive into another
+#[derive(Clone, Debug, Serialize)]
+pub struct MergeDrivesResult {
+    pub target: DriveInfo,
+    pub files_copied: usize,
+    /// Files already present in the target with identical content
+    pub files_identical: usize,
+    /// Name collisions registered as conflicts on the target drive
+    pub conflicts: usize,
+    /// Files that could not be copied ("path: reason")
+    pub failed: Vec<String>,
+    pub metadata_merged: usize,
+    pub acl_entries_merged: usize,
+    pub source_deleted: bool,
+    /// True if the merge was cancelled before every file was copied
+    pub cancelled: bool,
+}
+
+/// Merge a source drive into a target drive
+///
+/// Copies source files into the target without overwriting anything: when a
+/// file exists in both with different content, the source copy is written
+/// as `name (from <source>).ext` and a conflict is registered on the target.
+/// Synced metadata is merged the same way. Progress is emitted as
+/// `MergeProgress` drive events on the target.
+///
+/// With `merge_acl`, users granted access to the source are granted the same
+/// access to the target. With `delete_source`, the source drive is deleted
+/// once every file was copied, the same way as `delete_drive`: it can be
+/// recovered with `restore_drive` until its retention period ends, and its
+/// files on disk are left in place.
+///
+/// The merge can be stopped with `cancel_operation(operation_id)`. Files
+/// copied so far stay in the target and their collisions are registered as
+/// conflicts, but metadata, ACL entries, and the source drive are left as
+/// they were.
+///
+/// # Security
+/// - Requires Read on the source and Write on the target
+/// - ACL merge requires Admin on both drives
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn merge_drives(
+    source_drive_id: String,
+    target_drive_id: String,
+    merge_acl: Option<bool>,
+    delete_source: Option<bool>,
+    operation_id: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    conflict_manager: State<'_, Arc<ConflictManager>>,
+    operations: State<'_, Arc<OperationRegistry>>,
+    cleanup: State<'_, Arc<CleanupManager>>,
+) -> Result<MergeDrivesResult, String> {
+    let source_arr = validate_drive_id(&source_drive_id).map_err(|e| e.to_string())?;
+    let target_arr = validate_drive_id(&target_drive_id).map_err(|e| e.to_string())?;
+    if source_arr == target_arr {
+        return Err(
+            AppError::ValidationError("Cannot merge a drive into itself".to_string()).to_string(),
+        );
+    }
+    let merge_acl = merge_acl.unwrap_or(false);
+
+    let (source, target) = {
+        let drives = state.drives.read().await;
+        let lookup = |id: &[u8; 32], hex: &str| {
+            drives.get(id).cloned().ok_or_else(|| {
+                AppError::DriveNotFound {
+                    drive_id: hex.to_string(),
+                }
+                .to_string()
+            })
+        };
+        (
+            lookup(&source_arr, &source_drive_id)?,
+            lookup(&target_arr, &target_drive_id)?,
+        )
+    };
+    source.ensure_active().map_err(|e| e.to_string())?;
+    target.ensure_active().map_err(|e| e.to_string())?;
+
+    // Check permissions on both drives
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+    let source_acl = security
+        .get_or_create_acl(&source_drive_id, &source.owner.to_hex())
+        .await;
+    let mut target_acl = security
+        .get_or_create_acl(&target_drive_id, &target.owner.to_hex())
+        .await;
+
+    let (source_required, target_required) = if merge_acl {
+        (Permission::Admin, Permission::Admin)
+    } else {
+        (Permission::Read, Permission::Write)
+    };
+    if !source_acl.check_permission(&caller_hex, "/", source_required)
+        || !target_acl.check_permission(&caller_hex, "/", target_required)
+    {
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to merge these drives".to_string(),
+        }
+        .to_string());
+    }
+
+    let operation = operations
+        .start(operation_id, "merge_drives")
+        .map_err(|e| e.to_string())?;
+
+    tracing::info!(
+        source = %source_drive_id,
+        target = %target_drive_id,
+        merge_acl = merge_acl,
+        operation_id = %operation.id(),
+        "Merging drives"
+    );
+
+    // Silence the target's watcher while we write into it; the guard
+    // resumes it (with a reconciliation pass) even if we bail out early
+    let watch_suspension = match state.file_watcher.as_ref() {
+        Some(watcher) => Some(watcher.suspend_watch(DriveId(target_arr)).await),
+        None => None,
+    };
+
+    // Copy files on a blocking thread, emitting progress as we go
+    let outcome = {
+        let source_root = source.local_path.clone();
+        let target_root = target.local_path.clone();
+        let tag = format!("from {}", source.name);
+        let app = app.clone();
+        let source_hex = source_drive_id.clone();
+        let target_hex = target_drive_id.clone();
+        let cancel = operation.token();
+        let sensitivity = target.conflict_sensitivity;
+
+        tokio::task::spawn_blocking(move || {
+            file::merge_directory(&source_root, &target_root, &tag, sensitivity, |done, total| {
+                if cancel.is_cancelled() {
+                    return ControlFlow::Break(());
+                }
+                if done % MERGE_PROGRESS_INTERVAL != 0 && done != total {
+                    return ControlFlow::Continue(());
+                }
+                let event = DriveEvent::MergeProgress {
+                    source_drive: source_hex.clone(),
+                    files_processed: done,
+                    total_files: total,
+                };
+                let dto = DriveEventDto::from_event(&target_hex, &event);
+                if let Some(subscriptions) = app.try_state::<Arc<EventSubscriptions>>() {
+                    if let Err(e) = subscriptions.publish(&app, &dto) {
+                        tracing::warn!("Failed to emit merge progress: {}", e);
+                    }
+                }
+                ControlFlow::Continue(())
+            })
+        })
+        .await
+        .map_err(|e| format!("Merge task failed: {}", e))?
+        .map_err(|e| format!("Failed to merge drive files: {}", e))?
+    };
+
+    // Register collisions as conflicts rather than overwriting
+    let conflicts = conflict_manager.get_drive_conflicts(&target_drive_id).await;
+    for collision in &outcome.collisions {
+        let local = ConflictVersion {
+            hash: collision.target_hash.clone(),
+            size: collision.target_size,
+            modified_at: collision.target_modified_at,
+            modified_by: target.owner,
+            preview: None,
+            normalized_hash: None,
+        };
+        let remote = ConflictVersion {
+            hash: collision.source_hash.clone(),
+            size: collision.source_size,
+            modified_at: collision.source_modified_at,
+            modified_by: source.owner,
+            preview: None,
+            normalized_hash: None,
+        };
+        let path = target.local_path.join(&collision.path);
+        conflicts
+            .add_conflict(FileConflict::new(path, local, remote, None))
+            .await;
+    }
+
+    // Merge synced metadata, including remote-only files in the source
+    let mut metadata_merged = 0;
+    if let (false, Some(docs_manager)) = (outcome.cancelled, state.docs_manager.as_ref()) {
+        let source_meta = docs_manager
+            .get_all_metadata(&DriveId(source_arr))
+            .await
+            .unwrap_or_default();
+        let target_paths: HashSet<String> = docs_manager
+            .get_all_metadata(&DriveId(target_arr))
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|m| m.path)
+            .collect();
+
+        for mut meta in source_meta {
+            if target_paths.contains(&meta.path) {
+                // Collisions are tracked under the copy's path
+                let Some(collision) = outcome
+                    .collisions
+                    .iter()
+                    .find(|c| file::path_key(&c.path) == meta.path)
+                else {
+                    continue;
+                };
+                meta.path = file::path_key(&collision.copy_path);
+                meta.name = collision
+                    .copy_path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+            }
+
+            match docs_manager
+                .set_file_metadata(&DriveId(target_arr), &meta)
+                .await
+            {
+                Ok(()) => {
+                    metadata_merged += 1;
+                    // Already announced by its metadata; don't report it as ours
+                    if let (Some(suspension), Some(hash)) = (&watch_suspension, meta.content_hash) {
+                        let path = target.local_path.join(&meta.path);
+                        suspension.record(path, hash, source.owner).await;
+                    }
+                }
+                Err(e) => tracing::warn!(path = %meta.path, error = %e, "Failed to merge metadata"),
+            }
+        }
+    }
+
+    // Optionally carry over access grants
+    let mut acl_entries_merged = 0;
+    if merge_acl && !outcome.cancelled {
+        let target_owner = target.owner.to_hex();
+        for user in source_acl.users() {
+            if user == target_owner || target_acl.get_rule(user).is_some() {
+                continue;
+            }
+            if let Some(rule) = source_acl.get_rule(user) {
+                target_acl.grant(user, rule.clone());
+                acl_entries_merged += 1;
+            }
+        }
+        if acl_entries_merged > 0 {
+            security.update_acl(&target_drive_id, target_acl).await;
+        }
+    }
+
+    if let Some(suspension) = watch_suspension {
+        let reconciled = suspension.resume().await;
+        tracing::debug!(reconciled = reconciled, "Resumed target watch after merge");
+    }
+
+    // Refresh target stats now that it holds more files
+    let target_info = {
+        let target_root = target.local_path.clone();
+        let entries = tokio::task::spawn_blocking(move || file::index_directory(&target_root))
+            .await
+            .map_err(|e| format!("Indexing task failed: {}", e))?
+            .map_err(|e| format!("Failed to index directory: {}", e))?;
+        let total_size: u64 = entries.iter().map(|e| e.size).sum();
+        let file_count = entries.iter().filter(|e| !e.is_dir).count() as u64;
+
+        let mut drives = state.drives.write().await;
+        let drive = drives.get_mut(&target_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: target_drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.update_stats(total_size, file_count);
+
+        let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+            AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+        })?;
+        state.db.save_drive(&target_arr, &drive_bytes).map_err(|e| {
+            AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+        })?;
+        DriveInfo::from(&*drive)
+    };
+
+    // Only drop the source once everything made it across
+    let mut source_deleted = false;
+    if delete_source.unwrap_or(false) {
+        if outcome.failed.is_empty() && !outcome.cancelled {
+            // The source's ACL is kept so a restored drive keeps its members
+            delete_drive(source_drive_id.clone(), None, state.clone(), cleanup).await?;
+            source_deleted = true;
+        } else {
+            tracing::warn!(
+                source = %source_drive_id,
+                failed = outcome.failed.len(),
+                cancelled = outcome.cancelled,
+                "Keeping source drive because the merge did not complete"
+            );
+        }
+    }
+
+    tracing::info!(
+        source = %source_drive_id,
+        target = %target_drive_id,
+        copied = outcome.copied.len(),
+        identical = outcome.identical,
+        conflicts = outcome.collisions.len(),
+        failed = outcome.failed.len(),
+        source_deleted = source_deleted,
+        cancelled = outcome.cancelled,
+        "Merged drives"
+    );
+
+    Ok(MergeDrivesResult {
+        target: target_info,
+        files_copied: outcome.copied.len(),
+        files_identical: outcome.identical,
+        conflicts: outcome.collisions.len(),
+        failed: outcome
+            .failed
+            .iter()
+            .map(|(path, reason)| format!("{}: {}", path.display(), reason))
+            .collect(),
+        metadata_merged,
+        acl_entries_merged,
+        source_deleted,
+        cancelled: outcome.cancelled,
+    })
+}
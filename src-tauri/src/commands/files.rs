@@ -5,10 +5,15 @@
 //! Supports optional E2E encryption via EncryptionManager.
 
 use crate::commands::security::SecurityStore;
-use crate::core::{file, validate_drive_id, validate_path, AppError, DriveId, FileEntryDto};
-use crate::crypto::{EncryptionManager, Permission};
+use crate::core::{
+    file, validate_drive_id, validate_path, ActivityEntryDto, AppError, ConflictManager, DriveId,
+    FileConflictDto, FileEntryDto, PresenceManager,
+};
+use crate::crypto::{payload_plaintext_len, EncryptionManager, Permission};
+use crate::network::docs::{build_file_tree, FileMetadata, FileTree};
 use crate::state::AppState;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
 
@@ -84,7 +89,8 @@ pub async fn list_files(
                         meta.size,
                         meta.modified_at.clone(),
                         meta.content_hash.clone(),
-                    );
+                    )
+                    .with_content_type(meta.content_type.clone());
                     files_map.insert(meta.path.clone(), dto);
                 }
                 tracing::debug!(
@@ -105,22 +111,49 @@ pub async fn list_files(
     }
 
     // 2. Then, get local files from filesystem and merge (override remote entries)
+    merge_local_entries(&mut files_map, &local_path, &drive_id, &path)?;
+    flag_permission_errors(&mut files_map, &state, &drive_id_obj);
+    let dtos = sorted_entries(files_map);
+
+    tracing::debug!(
+        drive_id = %drive_id,
+        path = %path,
+        total_count = dtos.len(),
+        local_count = dtos.iter().filter(|f| f.is_local).count(),
+        remote_count = dtos.iter().filter(|f| !f.is_local).count(),
+        "Listed files (merged local + synced)"
+    );
+
+    Ok(dtos)
+}
+
+/// Merge local files of a directory into a listing
+///
+/// Local entries override synced ones for the same path but keep their
+/// content hash.
+fn merge_local_entries(
+    files_map: &mut HashMap<String, FileEntryDto>,
+    local_path: &std::path::Path,
+    drive_id: &str,
+    path: &str,
+) -> Result<(), String> {
     // Validate path is safe (prevents directory traversal)
-    let safe_path = validate_path(&local_path, &path).map_err(|e| e.to_string())?;
+    let safe_path = validate_path(local_path, path).map_err(|e| e.to_string())?;
 
     // Check if local directory exists
     if safe_path.exists() && safe_path.is_dir() {
-        match file::list_directory(&local_path, &path) {
+        match file::list_directory(local_path, path) {
             Ok(entries) => {
                 for entry in entries {
                     let entry_path = entry.path.to_string_lossy().to_string();
                     let mut dto = FileEntryDto::from(&entry);
-                    
+
                     // If we have synced metadata for this file, copy the content_hash
                     if let Some(synced) = files_map.get(&entry_path) {
                         dto.content_hash = synced.content_hash.clone();
+                        dto.content_type = synced.content_type.clone();
                     }
-                    
+
                     // Local file - is_local is already true from From impl
                     files_map.insert(entry_path, dto);
                 }
@@ -136,10 +169,29 @@ pub async fn list_files(
         }
     }
 
-    // Convert map to sorted vector
+    Ok(())
+}
+
+/// Flag entries sync was denied access to
+fn flag_permission_errors(
+    files_map: &mut HashMap<String, FileEntryDto>,
+    state: &AppState,
+    drive_id: &DriveId,
+) {
+    let Some(sync_engine) = state.sync_engine.as_ref() else {
+        return;
+    };
+    let errors = sync_engine.permission_errors();
+    for (path, dto) in files_map.iter_mut() {
+        if let Some(error) = errors.get(drive_id, std::path::Path::new(path)) {
+            dto.permission_error = Some(error.message);
+        }
+    }
+}
+
+/// Sort a listing: directories first, then by name (case-insensitive)
+fn sorted_entries(files_map: HashMap<String, FileEntryDto>) -> Vec<FileEntryDto> {
     let mut dtos: Vec<FileEntryDto> = files_map.into_values().collect();
-    
-    // Sort: directories first, then by name (case-insensitive)
     dtos.sort_by(|a, b| {
         match (a.is_dir, b.is_dir) {
             (true, false) => std::cmp::Ordering::Less,
@@ -147,134 +199,1008 @@ pub async fn list_files(
             _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
         }
     });
+    dtos
+}
+
+/// A directory listing fetched on demand
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LazyListing {
+    pub entries: Vec<FileEntryDto>,
+    /// True if peers couldn't be reached or haven't sent everything yet;
+    /// the entries are whatever was cached
+    pub partial: bool,
+    /// ISO 8601 time the synced part of the listing was fetched
+    pub fetched_at: String,
+}
+
+/// List a drive directory, fetching its synced metadata on demand
+///
+/// Like `list_files`, but only reads the requested directory's metadata
+/// instead of refreshing the whole drive, and caches it until one of its
+/// entries changes. Suited to browsing large drives a level at a time.
+/// When no peer is reachable, or some of the directory's metadata hasn't
+/// arrived yet, the cached entries are returned with `partial` set.
+///
+/// # Arguments
+/// * `refresh` - Ignore the cached listing and fetch again
+///
+/// # Security
+/// - Validates drive ID format
+/// - Prevents directory traversal attacks
+/// - Enforces ACL permission checks (requires Read permission)
+#[tauri::command]
+pub async fn list_files_lazy(
+    drive_id: String,
+    path: String,
+    refresh: Option<bool>,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<LazyListing, String> {
+    // Validate drive ID
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let drive_id_obj = DriveId(id_arr);
+
+    // Get drive
+    let drives = state.drives.read().await;
+    let drive = drives.get(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    let local_path = drive.local_path.clone();
+    let owner_hex = drive.owner.to_hex();
+    drop(drives);
+
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, &path, Permission::Read) {
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to list files".to_string(),
+        }
+        .to_string());
+    }
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let listing = docs_manager
+        .fetch_directory(&drive_id_obj, &path, refresh.unwrap_or(false))
+        .await
+        .map_err(|e| AppError::SyncFailed(e.to_string()).to_string())?;
+
+    let mut files_map: HashMap<String, FileEntryDto> = HashMap::new();
+    for meta in listing.entries {
+        let dto = FileEntryDto::from_metadata(
+            meta.name,
+            meta.path.clone(),
+            meta.is_dir,
+            meta.size,
+            meta.modified_at,
+            meta.content_hash,
+        )
+        .with_content_type(meta.content_type);
+        files_map.insert(meta.path, dto);
+    }
+    merge_local_entries(&mut files_map, &local_path, &drive_id, &path)?;
+    flag_permission_errors(&mut files_map, &state, &drive_id_obj);
 
     tracing::debug!(
         drive_id = %drive_id,
         path = %path,
-        total_count = dtos.len(),
-        local_count = dtos.iter().filter(|f| f.is_local).count(),
-        remote_count = dtos.iter().filter(|f| !f.is_local).count(),
-        "Listed files (merged local + synced)"
+        partial = listing.partial,
+        "Listed files lazily"
+    );
+
+    Ok(LazyListing {
+        entries: sorted_entries(files_map),
+        partial: listing.partial,
+        fetched_at: listing.fetched_at,
+    })
+}
+
+/// Default depth for recursive listings (levels below the root)
+const DEFAULT_RECURSIVE_DEPTH: usize = 8;
+
+/// Maximum depth a recursive listing may request
+const MAX_RECURSIVE_DEPTH: usize = 32;
+
+/// Maximum entries collected by a recursive listing before truncating
+const MAX_RECURSIVE_ENTRIES: usize = 20_000;
+
+/// Default and maximum page size for recursive listings
+const DEFAULT_RECURSIVE_LIMIT: usize = 500;
+const MAX_RECURSIVE_LIMIT: usize = 2_000;
+
+/// An entry in a recursive listing
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RecursiveFileEntry {
+    /// Path relative to the listing root (forward slashes)
+    pub relative_path: String,
+    /// Levels below the listing root (1 = direct child)
+    pub depth: usize,
+    #[serde(flatten)]
+    pub entry: FileEntryDto,
+}
+
+/// One page of a recursive listing
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct RecursiveListing {
+    pub entries: Vec<RecursiveFileEntry>,
+    /// Entries available across all pages (after capping)
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+    /// Depth limit that was applied
+    pub max_depth: usize,
+    /// True if entries were dropped because the listing hit the entry cap
+    pub truncated: bool,
+}
+
+/// List all files under a directory, recursively
+///
+/// Returns the same merged local + synced view as `list_files`, flattened
+/// and sorted by path so pages are stable. Each entry carries its path
+/// relative to `path` as well as its drive path.
+///
+/// # Arguments
+/// * `max_depth` - Levels to descend (default: 8, max: 32)
+/// * `limit` - Maximum number of entries to return (default: 500, max: 2000)
+/// * `offset` - Number of entries to skip (for pagination)
+///
+/// # Security
+/// - Validates drive ID format
+/// - Prevents directory traversal attacks
+/// - Enforces ACL permission checks (requires Read permission on the root)
+#[tauri::command]
+pub async fn list_files_recursive(
+    drive_id: String,
+    path: String,
+    max_depth: Option<usize>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<RecursiveListing, String> {
+    // Validate drive ID
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let drive_id_obj = DriveId(id_arr);
+
+    // Get drive
+    let drives = state.drives.read().await;
+    let drive = drives.get(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    let local_path = drive.local_path.clone();
+    let owner_hex = drive.owner.to_hex();
+    drop(drives);
+
+    // Get caller identity and check permission
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, &path, Permission::Read) {
+        tracing::warn!(
+            drive_id = %drive_id,
+            user = %caller_hex,
+            path = %path,
+            "Access denied: insufficient permission to list files"
+        );
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to list files".to_string(),
+        }
+        .to_string());
+    }
+
+    // Validate path is safe (prevents directory traversal)
+    let safe_path = validate_path(&local_path, &path).map_err(|e| e.to_string())?;
+
+    let max_depth = max_depth
+        .unwrap_or(DEFAULT_RECURSIVE_DEPTH)
+        .clamp(1, MAX_RECURSIVE_DEPTH);
+    let limit = limit
+        .unwrap_or(DEFAULT_RECURSIVE_LIMIT)
+        .clamp(1, MAX_RECURSIVE_LIMIT);
+    let offset = offset.unwrap_or(0);
+
+    let root = path.replace('\\', "/").trim_matches('/').to_string();
+    let to_drive_path = |relative: &str| {
+        if root.is_empty() {
+            relative.to_string()
+        } else {
+            format!("{}/{}", root, relative)
+        }
+    };
+
+    // Keyed by path relative to the root
+    let mut files_map: HashMap<String, FileEntryDto> = HashMap::new();
+
+    // 1. Synced metadata from DocsManager (remote files)
+    if let Some(docs_manager) = state.docs_manager.as_ref() {
+        match docs_manager.get_all_metadata(&drive_id_obj).await {
+            Ok(metadata) => {
+                for meta in metadata {
+                    let meta_path = meta.path.trim_start_matches('/');
+                    let relative = if root.is_empty() {
+                        meta_path
+                    } else {
+                        match meta_path
+                            .strip_prefix(root.as_str())
+                            .and_then(|rest| rest.strip_prefix('/'))
+                        {
+                            Some(rest) => rest,
+                            None => continue,
+                        }
+                    };
+                    if relative.is_empty() || relative.split('/').count() > max_depth {
+                        continue;
+                    }
+                    let dto = FileEntryDto::from_metadata(
+                        meta.name.clone(),
+                        meta.path.clone(),
+                        meta.is_dir,
+                        meta.size,
+                        meta.modified_at.clone(),
+                        meta.content_hash.clone(),
+                    )
+                    .with_content_type(meta.content_type.clone());
+                    files_map.insert(relative.to_string(), dto);
+                }
+            }
+            Err(e) => {
+                tracing::debug!(
+                    drive_id = %drive_id,
+                    error = %e,
+                    "No synced metadata available (this is normal for new drives)"
+                );
+            }
+        }
+    }
+
+    // 2. Local files override remote entries
+    if safe_path.is_dir() {
+        let walk_root = safe_path.clone();
+        let entries = tokio::task::spawn_blocking(move || {
+            file::index_directory_to_depth(&walk_root, max_depth)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        match entries {
+            Ok(entries) => {
+                for entry in entries {
+                    let relative = file::path_key(&entry.path);
+                    let mut dto = FileEntryDto::from(&entry);
+                    dto.path = to_drive_path(&relative);
+                    if let Some(synced) = files_map.get(&relative) {
+                        dto.content_hash = synced.content_hash.clone();
+                        dto.content_type = synced.content_type.clone();
+                    }
+                    files_map.insert(relative, dto);
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    drive_id = %drive_id,
+                    path = %path,
+                    error = %e,
+                    "Failed to index local directory"
+                );
+            }
+        }
+    }
+
+    let mut entries: Vec<RecursiveFileEntry> = files_map
+        .into_iter()
+        .map(|(relative_path, entry)| RecursiveFileEntry {
+            depth: relative_path.split('/').count(),
+            relative_path,
+            entry,
+        })
+        .collect();
+    entries.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+    let truncated = entries.len() > MAX_RECURSIVE_ENTRIES;
+    entries.truncate(MAX_RECURSIVE_ENTRIES);
+    let total = entries.len();
+
+    let entries: Vec<RecursiveFileEntry> = entries.into_iter().skip(offset).take(limit).collect();
+
+    tracing::debug!(
+        drive_id = %drive_id,
+        path = %path,
+        total,
+        returned = entries.len(),
+        truncated,
+        "Listed files recursively (merged local + synced)"
+    );
+
+    Ok(RecursiveListing {
+        entries,
+        total,
+        offset,
+        limit,
+        max_depth,
+        truncated,
+    })
+}
+
+/// List files modified after a point in time
+///
+/// Reads the synced metadata cache and returns files whose `modified_at` is
+/// strictly newer than `since` (an RFC 3339 timestamp with an offset, e.g.
+/// `2024-05-01T12:00:00Z`), oldest first. Useful for incremental backups and
+/// "what's new" views without diffing full snapshots.
+///
+/// # Security
+/// - Validates drive ID format and timestamp
+/// - Enforces ACL permission checks (requires Read permission per file)
+#[tauri::command]
+pub async fn list_files_since(
+    drive_id: String,
+    since: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<Vec<FileEntryDto>, String> {
+    // Validate drive ID
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let drive_id_obj = DriveId(id_arr);
+
+    let since = chrono::DateTime::parse_from_rfc3339(since.trim()).map_err(|e| {
+        AppError::ValidationFailed {
+            field: "since".to_string(),
+            reason: format!("expected an RFC 3339 timestamp with a UTC offset ({})", e),
+        }
+        .to_string()
+    })?;
+
+    // Get drive
+    let drives = state.drives.read().await;
+    let drive = drives.get(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    let local_path = drive.local_path.clone();
+    let owner_hex = drive.owner.to_hex();
+    drop(drives);
+
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let metadata = docs_manager
+        .get_all_metadata(&drive_id_obj)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut changed: Vec<(chrono::DateTime<chrono::FixedOffset>, FileEntryDto)> = Vec::new();
+    for meta in metadata {
+        if meta.is_dir || !acl.check_permission(&caller_hex, &meta.path, Permission::Read) {
+            continue;
+        }
+        let modified = match chrono::DateTime::parse_from_rfc3339(&meta.modified_at) {
+            Ok(modified) => modified,
+            Err(e) => {
+                tracing::debug!(
+                    drive_id = %drive_id,
+                    path = %meta.path,
+                    error = %e,
+                    "Skipping metadata with unparseable timestamp"
+                );
+                continue;
+            }
+        };
+        if modified <= since {
+            continue;
+        }
+
+        let mut dto = FileEntryDto::from_metadata(
+            meta.name,
+            meta.path,
+            meta.is_dir,
+            meta.size,
+            meta.modified_at,
+            meta.content_hash,
+        )
+        .with_content_type(meta.content_type);
+        dto.is_local = validate_path(&local_path, &dto.path).is_ok_and(|p| p.is_file());
+        changed.push((modified, dto));
+    }
+
+    changed.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.path.cmp(&b.1.path)));
+
+    tracing::debug!(
+        drive_id = %drive_id,
+        since = %since.to_rfc3339(),
+        count = changed.len(),
+        "Listed files changed since timestamp"
+    );
+
+    Ok(changed.into_iter().map(|(_, dto)| dto).collect())
+}
+
+/// File content response
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct FileContent {
+    /// Base64 encoded file content
+    pub content: String,
+    /// File size in bytes
+    pub size: u64,
+    /// Detected MIME type (optional)
+    pub mime_type: Option<String>,
+}
+
+/// Read file content from a drive
+///
+/// # Security
+/// - Validates drive ID format
+/// - Prevents directory traversal attacks
+/// - Ensures path stays within drive root
+/// - Enforces ACL permission checks (requires Read permission)
+#[tauri::command]
+pub async fn read_file(
+    drive_id: String,
+    path: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<FileContent, String> {
+    use base64::Engine;
+
+    // Validate drive ID
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    // Get drive
+    let drives = state.drives.read().await;
+    let drive = drives.get(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+
+    // Get caller identity and check permission
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+    let owner_hex = drive.owner.to_hex();
+
+    // Enforce ACL permission check
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, &path, Permission::Read) {
+        tracing::warn!(
+            drive_id = %drive_id,
+            user = %caller_hex,
+            path = %path,
+            "Access denied: insufficient permission to read file"
+        );
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to read file".to_string(),
+        }
+        .to_string());
+    }
+
+    // Validate path is safe (prevents directory traversal)
+    let safe_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
+
+    // Ensure the path exists
+    if !safe_path.exists() {
+        return Err(AppError::PathNotFound { path: path.clone() }.to_string());
+    }
+
+    // Ensure it's a file, not a directory
+    if safe_path.is_dir() {
+        return Err(AppError::NotAFile { path }.to_string());
+    }
+
+    // Read file content
+    let content = std::fs::read(&safe_path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let size = content.len() as u64;
+
+    // Detect MIME type from extension
+    let mime_type = mime_type_for(&safe_path);
+
+    // Encode content as base64
+    let encoded = base64::engine::general_purpose::STANDARD.encode(&content);
+
+    tracing::debug!(
+        drive_id = %drive_id,
+        path = %path,
+        size = size,
+        "Read file content"
     );
 
-    Ok(dtos)
+    Ok(FileContent {
+        content: encoded,
+        size,
+        mime_type,
+    })
+}
+
+/// Default number of bytes returned by a preview
+const DEFAULT_PREVIEW_BYTES: u64 = 1024 * 1024;
+
+/// Maximum number of bytes a preview may request
+const MAX_PREVIEW_BYTES: u64 = 16 * 1024 * 1024;
+
+/// In-memory preview of a file's content
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct FilePreview {
+    /// Base64 encoded content (possibly cut short)
+    pub content: String,
+    /// Full size of the file in bytes
+    pub size: u64,
+    /// Detected MIME type (optional)
+    pub mime_type: Option<String>,
+    /// True if only the first part of the file is included
+    pub truncated: bool,
+}
+
+/// Preview a file's content from the blob store without downloading it
+///
+/// The content is read into memory from the blob recorded in the file's
+/// metadata. Nothing is written to the drive folder, so the file stays
+/// remote-only and no sync is triggered.
+///
+/// # Arguments
+/// * `max_bytes` - Bytes to return (default: 1 MiB, max: 16 MiB)
+///
+/// # Security
+/// - Validates drive ID format
+/// - Enforces ACL permission checks (requires Read permission)
+#[tauri::command]
+pub async fn fetch_file_preview(
+    drive_id: String,
+    path: String,
+    max_bytes: Option<u64>,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<FilePreview, String> {
+    use base64::Engine;
+
+    // Validate drive ID
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let drive_id_obj = DriveId(id_arr);
+
+    let owner_hex = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.owner.to_hex()
+    };
+
+    // Get caller identity and check permission
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+
+    // Enforce ACL permission check
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, &path, Permission::Read) {
+        tracing::warn!(
+            drive_id = %drive_id,
+            user = %caller_hex,
+            path = %path,
+            "Access denied: insufficient permission to preview file"
+        );
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to read file".to_string(),
+        }
+        .to_string());
+    }
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    let key = path.replace('\\', "/").trim_start_matches('/').to_string();
+    let meta = docs_manager
+        .get_all_metadata(&drive_id_obj)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|meta| meta.path.trim_start_matches('/') == key)
+        .ok_or_else(|| AppError::PathNotFound { path: path.clone() }.to_string())?;
+
+    if meta.is_dir {
+        return Err(AppError::NotAFile { path }.to_string());
+    }
+    let hash = meta
+        .content_hash
+        .as_deref()
+        .ok_or_else(|| AppError::InvalidHash(format!("No content hash for {}", path)).to_string())?
+        .parse::<iroh_blobs::Hash>()
+        .map_err(|e| AppError::InvalidHash(format!("Invalid hash: {}", e)).to_string())?;
+
+    let max_bytes = max_bytes
+        .unwrap_or(DEFAULT_PREVIEW_BYTES)
+        .clamp(1, MAX_PREVIEW_BYTES);
+    let manifest_hash = meta
+        .manifest_hash
+        .as_deref()
+        .and_then(|m| m.parse::<iroh_blobs::Hash>().ok());
+    let (content, size) = file_transfer
+        .read_content_prefix(hash, manifest_hash, max_bytes)
+        .await
+        .map_err(|e| AppError::TransferFailed(format!("Preview failed: {}", e)).to_string())?;
+
+    tracing::debug!(
+        drive_id = %drive_id,
+        path = %path,
+        size = size,
+        returned = content.len(),
+        "Fetched file preview"
+    );
+
+    Ok(FilePreview {
+        truncated: (content.len() as u64) < size,
+        content: base64::engine::general_purpose::STANDARD.encode(&content),
+        size,
+        mime_type: meta
+            .content_type
+            .clone()
+            .or_else(|| mime_type_for(std::path::Path::new(&meta.path))),
+    })
+}
+
+/// Detect a MIME type from a file extension
+fn mime_type_for(path: &std::path::Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| match ext.to_lowercase().as_str() {
+            "txt" | "md" | "rs" | "js" | "ts" | "py" | "json" | "toml" | "yaml" | "yml" => {
+                "text/plain"
+            }
+            "html" | "htm" => "text/html",
+            "css" => "text/css",
+            "png" => "image/png",
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "svg" => "image/svg+xml",
+            "pdf" => "application/pdf",
+            "zip" => "application/zip",
+            _ => "application/octet-stream",
+        })
+        .map(String::from)
+}
+
+/// Most activity entries returned by `get_file_details`
+const MAX_DETAIL_HISTORY: usize = 200;
+
+/// Everything known about one file, for a details panel or support export
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct FileDetails {
+    pub drive_id: String,
+    pub path: String,
+    /// Synced metadata (current version, hash, size), if the file has any
+    pub metadata: Option<FileMetadata>,
+    /// Whether the file exists in the local drive folder
+    pub is_local: bool,
+    /// Size on disk, if local
+    pub local_size: Option<u64>,
+    /// ISO 8601 local modification time, if local
+    pub local_modified_at: Option<String>,
+    /// Whether the blob for the current content hash is stored locally
+    pub blob_available: bool,
+    /// Size of the stored blob, if available
+    pub blob_size: Option<u64>,
+    /// Activity recorded for the file, newest first (who changed it and when)
+    pub history: Vec<ActivityEntryDto>,
+    /// Active conflict first, then resolved ones, newest first
+    pub conflicts: Vec<FileConflictDto>,
+    /// Why sync can't access the file locally, if it can't
+    pub permission_error: Option<String>,
+    /// Drive peers that may serve the blob, most reliable first
+    ///
+    /// Only filled when `include_providers` is set. These are the document's
+    /// sync peers; none of them is asked whether it holds the blob.
+    pub providers: Option<Vec<String>>,
+}
+
+/// Gather a file's metadata, history, local state, and conflicts in one call
+///
+/// Annotations are not included: the tree has no annotation store yet.
+///
+/// # Security
+/// - Validates drive ID format
+/// - Prevents directory traversal attacks
+/// - Enforces ACL permission checks (requires Read permission)
+#[tauri::command]
+pub async fn get_file_details(
+    drive_id: String,
+    path: String,
+    include_providers: Option<bool>,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    conflict_manager: State<'_, Arc<ConflictManager>>,
+    presence_manager: State<'_, Arc<PresenceManager>>,
+) -> Result<FileDetails, String> {
+    // Validate drive ID
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let drive_id_obj = DriveId(id_arr);
+
+    let (owner_hex, local_path) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        (drive.owner.to_hex(), drive.local_path.clone())
+    };
+
+    // Get caller identity and check permission
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+
+    // Enforce ACL permission check
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, &path, Permission::Read) {
+        tracing::warn!(
+            drive_id = %drive_id,
+            user = %caller_hex,
+            path = %path,
+            "Access denied: insufficient permission to view file details"
+        );
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to read file".to_string(),
+        }
+        .to_string());
+    }
+
+    // Validate path and stat the local copy
+    let safe_path = validate_path(&local_path, &path).map_err(|e| e.to_string())?;
+    let local_meta = tokio::fs::metadata(&safe_path).await.ok().filter(|m| m.is_file());
+    let key = path.replace('\\', "/").trim_start_matches('/').to_string();
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let metadata = docs_manager.get_file_metadata(&drive_id_obj, &key).await;
+
+    let mut blob_available = false;
+    let mut blob_size = None;
+    if let (Some(file_transfer), Some(hash)) = (
+        state.file_transfer.as_ref(),
+        metadata.as_ref().and_then(|m| m.content_hash.as_deref()),
+    ) {
+        if let Ok(hash) = hash.parse::<iroh_blobs::Hash>() {
+            // Content uploaded in chunks isn't stored as one blob
+            let manifest_hash = metadata
+                .as_ref()
+                .and_then(|m| m.manifest_hash.as_deref())
+                .and_then(|m| m.parse::<iroh_blobs::Hash>().ok());
+            blob_size = match file_transfer.blob_size(&hash).await.ok().flatten() {
+                Some(size) => Some(size),
+                None => file_transfer.content_size(hash, manifest_hash).await,
+            };
+            blob_available = blob_size.is_some();
+        }
+    }
+
+    let node_id = presence_manager.node_id();
+    let history = presence_manager
+        .get_drive_presence(&drive_id)
+        .await
+        .activities_for_path(&PathBuf::from(&key), MAX_DETAIL_HISTORY)
+        .await
+        .iter()
+        .map(|a| ActivityEntryDto::from_entry(a, node_id))
+        .collect();
+
+    // Conflicts are keyed by absolute path
+    let conflicts = conflict_manager
+        .get_drive_conflicts(&drive_id)
+        .await
+        .conflicts_for_path(&safe_path)
+        .await
+        .iter()
+        .map(FileConflictDto::from)
+        .collect();
+
+    let permission_error = state.sync_engine.as_ref().and_then(|engine| {
+        engine
+            .permission_errors()
+            .get(&drive_id_obj, std::path::Path::new(&key))
+            .map(|e| e.message)
+    });
+
+    let providers = if include_providers.unwrap_or(false) {
+        let peers: Vec<iroh::NodeId> = docs_manager
+            .get_sync_peers(&drive_id_obj)
+            .await
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|bytes| iroh::NodeId::from_bytes(bytes).ok())
+            .collect();
+        let ranked = match state.file_transfer.as_ref() {
+            Some(file_transfer) => file_transfer.reliability().rank(&peers).await,
+            None => peers,
+        };
+        Some(ranked.iter().map(|p| p.to_string()).collect())
+    } else {
+        None
+    };
+
+    Ok(FileDetails {
+        drive_id,
+        path,
+        metadata,
+        is_local: local_meta.is_some(),
+        local_size: local_meta.as_ref().map(|m| m.len()),
+        local_modified_at: local_meta
+            .and_then(|m| m.modified().ok())
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339()),
+        blob_available,
+        blob_size,
+        history,
+        conflicts,
+        permission_error,
+        providers,
+    })
 }
 
-/// File content response
+/// Default depth for exported file trees (levels below the root)
+const DEFAULT_TREE_DEPTH: usize = 16;
+
+/// Maximum depth an exported file tree may request
+const MAX_TREE_DEPTH: usize = 64;
+
+/// Default and maximum number of nodes in an exported file tree
+const DEFAULT_TREE_ENTRIES: usize = 10_000;
+const MAX_TREE_ENTRIES: usize = 100_000;
+
+/// A drive's file tree, for external tooling
 #[derive(Clone, Debug, serde::Serialize)]
-pub struct FileContent {
-    /// Base64 encoded file content
-    pub content: String,
-    /// File size in bytes
-    pub size: u64,
-    /// Detected MIME type (optional)
-    pub mime_type: Option<String>,
+pub struct FileTreeExport {
+    pub drive_id: String,
+    /// ISO 8601 time the tree was built
+    pub exported_at: String,
+    #[serde(flatten)]
+    pub tree: FileTree,
 }
 
-/// Read file content from a drive
+/// Export a drive's structure as a nested tree of directories and files
+///
+/// Built from synced metadata, so it includes files not downloaded here.
+/// Each node has its path, size, content hash, and modified time;
+/// directories list their children by name. Unlike the flat metadata
+/// listings this is meant for tree-walking consumers such as backup
+/// scripts and indexers.
+///
+/// # Arguments
+/// * `path` - Subdirectory to root the tree at (default: the drive root)
+/// * `max_depth` - Levels to include below the root (default: 16, max: 64)
+/// * `max_entries` - Nodes to include (default: 10000, max: 100000)
 ///
 /// # Security
 /// - Validates drive ID format
-/// - Prevents directory traversal attacks
-/// - Ensures path stays within drive root
-/// - Enforces ACL permission checks (requires Read permission)
+/// - Enforces ACL permission checks (requires Read permission on the root;
+///   entries the caller can't read are left out)
 #[tauri::command]
-pub async fn read_file(
+pub async fn export_file_tree(
     drive_id: String,
-    path: String,
+    path: Option<String>,
+    max_depth: Option<usize>,
+    max_entries: Option<usize>,
     state: State<'_, AppState>,
     security: State<'_, Arc<SecurityStore>>,
-) -> Result<FileContent, String> {
-    use base64::Engine;
-
-    // Validate drive ID
+) -> Result<FileTreeExport, String> {
     let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let drive_id_obj = DriveId(id_arr);
 
-    // Get drive
-    let drives = state.drives.read().await;
-    let drive = drives.get(&id_arr).ok_or_else(|| {
-        AppError::DriveNotFound {
-            drive_id: drive_id.clone(),
-        }
-        .to_string()
-    })?;
+    let (owner_hex, local_path) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        (drive.owner.to_hex(), drive.local_path.clone())
+    };
 
-    // Get caller identity and check permission
     let caller = state
         .identity_manager
         .node_id()
         .await
         .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
     let caller_hex = caller.to_hex();
-    let owner_hex = drive.owner.to_hex();
 
-    // Enforce ACL permission check
+    let root = path.unwrap_or_default().replace('\\', "/").trim_matches('/').to_string();
     let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
-    if !acl.check_permission(&caller_hex, &path, Permission::Read) {
+    if !acl.check_permission(&caller_hex, &root, Permission::Read) {
         tracing::warn!(
             drive_id = %drive_id,
             user = %caller_hex,
-            path = %path,
-            "Access denied: insufficient permission to read file"
+            path = %root,
+            "Access denied: insufficient permission to export file tree"
         );
         return Err(AppError::AccessDenied {
-            reason: "insufficient permission to read file".to_string(),
+            reason: "insufficient permission to list files".to_string(),
         }
         .to_string());
     }
-
-    // Validate path is safe (prevents directory traversal)
-    let safe_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
-
-    // Ensure the path exists
-    if !safe_path.exists() {
-        return Err(AppError::PathNotFound { path: path.clone() }.to_string());
-    }
-
-    // Ensure it's a file, not a directory
-    if safe_path.is_dir() {
-        return Err(AppError::NotAFile { path }.to_string());
+    if !root.is_empty() {
+        validate_path(&local_path, &root).map_err(|e| e.to_string())?;
     }
 
-    // Read file content
-    let content = std::fs::read(&safe_path).map_err(|e| format!("Failed to read file: {}", e))?;
-
-    let size = content.len() as u64;
-
-    // Detect MIME type from extension
-    let mime_type = safe_path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| match ext.to_lowercase().as_str() {
-            "txt" | "md" | "rs" | "js" | "ts" | "py" | "json" | "toml" | "yaml" | "yml" => {
-                "text/plain"
-            }
-            "html" | "htm" => "text/html",
-            "css" => "text/css",
-            "png" => "image/png",
-            "jpg" | "jpeg" => "image/jpeg",
-            "gif" => "image/gif",
-            "svg" => "image/svg+xml",
-            "pdf" => "application/pdf",
-            "zip" => "application/zip",
-            _ => "application/octet-stream",
-        })
-        .map(String::from);
-
-    // Encode content as base64
-    let encoded = base64::engine::general_purpose::STANDARD.encode(&content);
+    let max_depth = max_depth
+        .unwrap_or(DEFAULT_TREE_DEPTH)
+        .clamp(1, MAX_TREE_DEPTH);
+    let max_entries = max_entries
+        .unwrap_or(DEFAULT_TREE_ENTRIES)
+        .clamp(1, MAX_TREE_ENTRIES);
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let mut metadata = docs_manager
+        .get_all_metadata(&drive_id_obj)
+        .await
+        .map_err(|e| AppError::SyncFailed(e.to_string()).to_string())?;
+    metadata.retain(|meta| acl.check_permission(&caller_hex, &meta.path, Permission::Read));
 
+    let tree = build_file_tree(&metadata, &root, max_depth, max_entries);
     tracing::debug!(
         drive_id = %drive_id,
-        path = %path,
-        size = size,
-        "Read file content"
+        files = tree.file_count,
+        dirs = tree.dir_count,
+        truncated = tree.truncated,
+        "Exported file tree"
     );
-
-    Ok(FileContent {
-        content: encoded,
-        size,
-        mime_type,
+    Ok(FileTreeExport {
+        drive_id,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        tree,
     })
 }
 
@@ -307,6 +1233,10 @@ pub async fn write_file(
         }
         .to_string()
     })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+
+    // Store the name the drive's policy makes canonical
+    let path = drive.name_policy.apply(&path);
 
     // Get caller identity and check permission
     let caller = state
@@ -344,6 +1274,9 @@ pub async fn write_file(
     let decoded = base64::engine::general_purpose::STANDARD
         .decode(&content)
         .map_err(|e| format!("Invalid base64 content: {}", e))?;
+    drive
+        .check_file_size(&path, decoded.len() as u64)
+        .map_err(|e| e.to_string())?;
 
     // Create parent directories if needed
     if let Some(parent) = safe_path.parent() {
@@ -389,6 +1322,7 @@ pub async fn delete_path(
         }
         .to_string()
     })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
 
     // Get caller identity and check permission
     let caller = state
@@ -470,6 +1404,10 @@ pub async fn rename_path(
         }
         .to_string()
     })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+
+    // Store the name the drive's policy makes canonical
+    let new_path = drive.name_policy.apply(&new_path);
 
     // Get caller identity and check permission
     let caller = state
@@ -543,6 +1481,253 @@ pub async fn rename_path(
     Ok(())
 }
 
+/// Maximum number of unencrypted paths listed in an overhead report
+const MAX_FLAGGED_PATHS: usize = 100;
+
+/// Plaintext vs stored size of an encrypted drive
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct EncryptionOverhead {
+    /// Sum of plaintext sizes of encrypted local files
+    pub plaintext_bytes: u64,
+    /// Sum of on-disk sizes of encrypted local files
+    pub stored_bytes: u64,
+    pub overhead_bytes: u64,
+    /// Overhead relative to plaintext (0 if there is no plaintext)
+    pub overhead_percent: f64,
+    pub encrypted_files: u64,
+    /// Local files that are not in the encrypted format
+    pub unencrypted_files: u64,
+    pub unencrypted_bytes: u64,
+    /// Up to 100 unencrypted paths
+    pub unencrypted_paths: Vec<String>,
+    /// Files in the drive metadata that have no local copy (not counted)
+    pub remote_only_files: u64,
+}
+
+/// Report how much extra disk an encrypted drive uses
+///
+/// Each encrypted file carries a version byte, nonce, and authentication tag
+/// on top of its plaintext. Sizes come from the local files; a file counts as
+/// encrypted if its header and length fit the encrypted format, which is a
+/// structural check rather than a decryption. Files that don't fit are
+/// flagged, since a properly encrypted drive should have none.
+///
+/// # Security
+/// - Validates drive ID format
+/// - Enforces ACL permission checks (requires Read permission)
+#[tauri::command]
+pub async fn get_encryption_overhead(
+    drive_id: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    encryption: State<'_, Arc<EncryptionManager>>,
+) -> Result<EncryptionOverhead, String> {
+    use std::io::Read;
+
+    // Validate drive ID
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let drive_id_obj = DriveId(id_arr);
+
+    let (owner_hex, local_path) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        (drive.owner.to_hex(), drive.local_path.clone())
+    };
+
+    // Get caller identity and check permission
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, "/", Permission::Read) {
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to read drive".to_string(),
+        }
+        .to_string());
+    }
+
+    if !encryption.has_key(&drive_id).await.map_err(|e| e.to_string())? {
+        return Err(AppError::ValidationError(format!(
+            "Drive {} is not encrypted on this device",
+            drive_id
+        ))
+        .to_string());
+    }
+
+    let root = local_path.clone();
+    let entries = tokio::task::spawn_blocking(move || file::index_directory(&root))
+        .await
+        .map_err(|e| format!("Failed to index drive: {}", e))?
+        .map_err(|e| format!("Failed to index drive: {}", e))?;
+
+    let mut report = EncryptionOverhead {
+        plaintext_bytes: 0,
+        stored_bytes: 0,
+        overhead_bytes: 0,
+        overhead_percent: 0.0,
+        encrypted_files: 0,
+        unencrypted_files: 0,
+        unencrypted_bytes: 0,
+        unencrypted_paths: Vec::new(),
+        remote_only_files: 0,
+    };
+
+    let mut local_paths = std::collections::HashSet::new();
+    for entry in entries.iter().filter(|e| !e.is_dir) {
+        let relative = entry.path.to_string_lossy().replace('\\', "/");
+        local_paths.insert(relative.clone());
+
+        let mut first_byte = [0u8; 1];
+        let header = std::fs::File::open(local_path.join(&entry.path))
+            .and_then(|mut f| f.read_exact(&mut first_byte))
+            .ok()
+            .map(|_| first_byte[0]);
+
+        match header.and_then(|b| payload_plaintext_len(b, entry.size)) {
+            Some(plaintext_len) => {
+                report.encrypted_files += 1;
+                report.plaintext_bytes += plaintext_len;
+                report.stored_bytes += entry.size;
+            }
+            None => {
+                report.unencrypted_files += 1;
+                report.unencrypted_bytes += entry.size;
+                if report.unencrypted_paths.len() < MAX_FLAGGED_PATHS {
+                    report.unencrypted_paths.push(relative);
+                }
+            }
+        }
+    }
+
+    if let Some(docs_manager) = state.docs_manager.as_ref() {
+        if let Ok(metadata) = docs_manager.get_all_metadata(&drive_id_obj).await {
+            report.remote_only_files = metadata
+                .iter()
+                .filter(|m| !m.is_dir && !local_paths.contains(m.path.trim_start_matches('/')))
+                .count() as u64;
+        }
+    }
+
+    report.overhead_bytes = report.stored_bytes - report.plaintext_bytes;
+    if report.plaintext_bytes > 0 {
+        report.overhead_percent =
+            report.overhead_bytes as f64 / report.plaintext_bytes as f64 * 100.0;
+    }
+    if report.unencrypted_files > 0 {
+        tracing::warn!(
+            drive_id = %drive_id,
+            count = report.unencrypted_files,
+            "Encrypted drive contains unencrypted files"
+        );
+    }
+
+    Ok(report)
+}
+
+/// An entry that resolves outside its drive
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PathEscapeDto {
+    /// Drive-relative path (forward slashes)
+    pub path: String,
+    /// Resolved location, or the raw link target if it is dangling
+    pub target: String,
+    pub dangling: bool,
+}
+
+/// Result of scanning a drive for paths that escape its root
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DriveSafetyReport {
+    pub drive_id: String,
+    pub entries_scanned: usize,
+    pub escapes: Vec<PathEscapeDto>,
+    /// ISO 8601 time of the scan
+    pub scanned_at: String,
+}
+
+/// Scan a drive for entries that resolve outside its root
+///
+/// Walks every entry (including hidden ones) without following symlinks
+/// and reports links whose target lies outside the drive, a way for files
+/// to be read from or written to elsewhere on disk. Dangling links that
+/// would point outside are reported too. Nothing is modified.
+///
+/// # Security
+/// - Validates drive ID format
+/// - Enforces ACL permission checks (requires Read permission)
+#[tauri::command]
+pub async fn scan_drive_safety(
+    drive_id: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<DriveSafetyReport, String> {
+    // Validate drive ID
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let (owner_hex, local_path) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        (drive.owner.to_hex(), drive.local_path.clone())
+    };
+
+    // Get caller identity and check permission
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, "/", Permission::Read) {
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to read drive".to_string(),
+        }
+        .to_string());
+    }
+
+    let (escapes, entries_scanned) =
+        tokio::task::spawn_blocking(move || file::find_path_escapes(&local_path))
+            .await
+            .map_err(|e| format!("Scan task failed: {}", e))?
+            .map_err(|e| format!("Failed to scan drive: {}", e))?;
+
+    if !escapes.is_empty() {
+        tracing::warn!(
+            drive_id = %drive_id,
+            count = escapes.len(),
+            "Drive contains entries that resolve outside its root"
+        );
+    }
+
+    Ok(DriveSafetyReport {
+        drive_id,
+        entries_scanned,
+        escapes: escapes
+            .into_iter()
+            .map(|escape| PathEscapeDto {
+                path: file::path_key(&escape.path),
+                target: escape.target.to_string_lossy().to_string(),
+                dangling: escape.dangling,
+            })
+            .collect(),
+        scanned_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
 /// Read encrypted file content from a drive
 ///
 /// # Security
@@ -681,6 +1866,10 @@ pub async fn write_file_encrypted(
         }
         .to_string()
     })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+
+    // Store the name the drive's policy makes canonical
+    let path = drive.name_policy.apply(&path);
 
     // Get caller identity and check permission
     let caller = state
@@ -717,6 +1906,9 @@ pub async fn write_file_encrypted(
     let plaintext = base64::engine::general_purpose::STANDARD
         .decode(&content)
         .map_err(|e| format!("Invalid base64 content: {}", e))?;
+    drive
+        .check_file_size(&path, plaintext.len() as u64)
+        .map_err(|e| e.to_string())?;
 
     // Encrypt the content
     let encrypted_content = encryption
@@ -744,3 +1936,217 @@ pub async fn write_file_encrypted(
 
     Ok(())
 }
+
+/// Outcome of one self-test step
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SelfTestStep {
+    pub name: String,
+    pub ok: bool,
+    /// Error message if the step failed
+    pub detail: Option<String>,
+}
+
+/// Result of a write-then-read self-test on a drive
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DriveSelfTest {
+    pub drive_id: String,
+    pub ok: bool,
+    /// True if the test went through the drive's encryption key
+    pub encrypted: bool,
+    /// Name of the first step that failed
+    pub failed_step: Option<String>,
+    pub steps: Vec<SelfTestStep>,
+}
+
+/// Record a step's result, returning true if it passed
+fn record_step(steps: &mut Vec<SelfTestStep>, name: &str, result: Result<(), String>) -> bool {
+    let ok = result.is_ok();
+    steps.push(SelfTestStep {
+        name: name.to_string(),
+        ok,
+        detail: result.err(),
+    });
+    ok
+}
+
+/// Write a probe file, read it back and compare, stopping at the first failure
+async fn self_test_roundtrip(
+    drive_id: &str,
+    rel_path: &str,
+    file_path: &std::path::Path,
+    encryption: Option<&EncryptionManager>,
+    steps: &mut Vec<SelfTestStep>,
+) {
+    let payload = format!("gix self-test {}", chrono::Utc::now().to_rfc3339()).into_bytes();
+
+    let stored = match encryption {
+        Some(encryption) => match encryption.encrypt_file(drive_id, rel_path, &payload).await {
+            Ok(ciphertext) => {
+                record_step(steps, "encrypt", Ok(()));
+                ciphertext
+            }
+            Err(e) => {
+                record_step(steps, "encrypt", Err(format!("Encryption failed: {}", e)));
+                return;
+            }
+        },
+        None => payload.clone(),
+    };
+
+    let written = std::fs::write(file_path, &stored)
+        .map_err(|e| format!("Failed to write file: {}", e));
+    if !record_step(steps, "write", written) {
+        return;
+    }
+
+    let metadata = std::fs::metadata(file_path)
+        .map_err(|e| format!("Failed to read metadata: {}", e))
+        .and_then(|meta| {
+            if !meta.is_file() {
+                Err("test path is not a regular file".to_string())
+            } else if meta.len() != stored.len() as u64 {
+                Err(format!(
+                    "size mismatch: wrote {} bytes, metadata reports {}",
+                    stored.len(),
+                    meta.len()
+                ))
+            } else {
+                Ok(())
+            }
+        });
+    if !record_step(steps, "metadata", metadata) {
+        return;
+    }
+
+    let read_back = match std::fs::read(file_path) {
+        Ok(bytes) => {
+            record_step(steps, "read", Ok(()));
+            bytes
+        }
+        Err(e) => {
+            record_step(steps, "read", Err(format!("Failed to read file: {}", e)));
+            return;
+        }
+    };
+
+    let content = match encryption {
+        Some(encryption) => match encryption.decrypt_file(drive_id, rel_path, &read_back).await {
+            Ok(plaintext) => {
+                record_step(steps, "decrypt", Ok(()));
+                plaintext
+            }
+            Err(e) => {
+                record_step(steps, "decrypt", Err(format!("Decryption failed: {}", e)));
+                return;
+            }
+        },
+        None => read_back,
+    };
+
+    let compared = if content != payload {
+        Err("content read back differs from content written".to_string())
+    } else if blake3::hash(&content) != blake3::hash(&payload) {
+        Err("hash of content read back differs from hash written".to_string())
+    } else {
+        Ok(())
+    };
+    record_step(steps, "compare", compared);
+}
+
+/// Check a drive can be written and read back correctly
+///
+/// Writes a small temporary file at the drive root (through the drive's
+/// encryption key if this device has one), reads it back and verifies size,
+/// content and hash, then deletes it. The file is always removed, even if
+/// an earlier step failed. Requires Write permission.
+#[tauri::command]
+pub async fn self_test_drive(
+    drive_id: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    encryption: State<'_, Arc<EncryptionManager>>,
+) -> Result<DriveSelfTest, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let (owner_hex, local_path) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.ensure_active().map_err(|e| e.to_string())?;
+        (drive.owner.to_hex(), drive.local_path.clone())
+    };
+
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+
+    // Named *.tmp so the file watcher ignores it and it is never synced
+    let rel_path = format!(".gix-self-test-{}.tmp", hex::encode(rand::random::<[u8; 8]>()));
+    let encrypted = encryption
+        .has_key(&drive_id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut steps = Vec::new();
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    let access = if !acl.check_permission(&caller_hex, &rel_path, Permission::Write) {
+        Err("insufficient permission to write to drive".to_string())
+    } else if !local_path.is_dir() {
+        Err(format!("drive folder {} is not a directory", local_path.display()))
+    } else {
+        Ok(())
+    };
+
+    if record_step(&mut steps, "access", access) {
+        match validate_path(&local_path, &rel_path) {
+            Ok(file_path) => {
+                self_test_roundtrip(
+                    &drive_id,
+                    &rel_path,
+                    &file_path,
+                    encrypted.then_some(encryption.inner().as_ref()),
+                    &mut steps,
+                )
+                .await;
+
+                // Always clean up, whatever happened above
+                let cleanup = match std::fs::remove_file(&file_path) {
+                    Ok(()) => Ok(()),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                    Err(e) => Err(format!("Failed to delete test file: {}", e)),
+                };
+                record_step(&mut steps, "cleanup", cleanup);
+            }
+            Err(e) => {
+                record_step(&mut steps, "path", Err(e.to_string()));
+            }
+        }
+    }
+
+    let failed_step = steps.iter().find(|s| !s.ok).map(|s| s.name.clone());
+    let ok = failed_step.is_none();
+    if ok {
+        tracing::info!(drive_id = %drive_id, encrypted = encrypted, "Drive self-test passed");
+    } else {
+        tracing::warn!(
+            drive_id = %drive_id,
+            failed_step = ?failed_step,
+            "Drive self-test failed"
+        );
+    }
+
+    Ok(DriveSelfTest {
+        drive_id,
+        ok,
+        encrypted,
+        failed_step,
+        steps,
+    })
+}
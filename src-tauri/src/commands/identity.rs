@@ -1,4 +1,4 @@
-use crate::network::ConnectionInfo;
+use crate::network::{ConnectionInfo, RelayConnectivity};
 use crate::state::AppState;
 use serde::Serialize;
 use tauri::State;
@@ -30,3 +30,24 @@ pub async fn get_connection_status(state: State<'_, AppState>) -> Result<Connect
     let info = state.endpoint.get_connection_info().await;
     Ok(info)
 }
+
+/// Check whether the relay servers can be reached
+///
+/// Probes the relay the endpoint is actually connected through, then the
+/// default relays, and reports reachability and latency for each. Helps
+/// tell relay problems apart from peer or permission problems when sync
+/// fails. Works before the endpoint is up; there is no home relay then.
+#[tauri::command]
+pub async fn test_relay_connectivity(
+    state: State<'_, AppState>,
+) -> Result<RelayConnectivity, String> {
+    let report = state.endpoint.test_relays().await;
+    let reachable = report.relays.iter().filter(|r| r.reachable).count();
+    tracing::info!(
+        home_relay = ?report.home_relay,
+        reachable = reachable,
+        tested = report.relays.len(),
+        "Tested relay connectivity"
+    );
+    Ok(report)
+}
@@ -10,7 +10,9 @@
 use crate::commands::security::SecurityStore;
 use crate::core::error::AppError;
 use crate::core::validation::{validate_drive_id, validate_path};
-use crate::core::{DriveEvent, FileLock, FileLockDto, LockManager, LockResult, LockType};
+use crate::core::{
+    DriveEvent, FileLock, FileLockDto, LockManager, LockQueueEntryDto, LockResult, LockType,
+};
 use crate::crypto::Permission;
 use crate::state::AppState;
 use chrono::Utc;
@@ -31,17 +33,34 @@ pub struct AcquireLockResult {
     pub lock: Option<FileLockDto>,
     pub error: Option<String>,
     pub warning: Option<String>,
+    /// Position in the lock queue if the request was queued
+    pub queue_position: Option<usize>,
 }
 
 /// Acquire a lock on a file
+///
+/// If the file is already locked and `wait` is true, the request is
+/// queued and its position is returned instead of failing outright. The
+/// lock is granted automatically when it is released or expires, with a
+/// `LockGranted` event. `queue` is the older name for `wait`.
+///
+/// `lock_type` is `advisory`, `read` or `write` (`exclusive` is accepted
+/// for `write`). Many nodes can hold read locks at once; a write lock
+/// needs every reader gone. To upgrade our own read lock while others
+/// still read, pass `upgrade` to wait ahead of the queue, or the request
+/// is denied.
 /// 
 /// # Security
 /// - Validates path is within drive root
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn acquire_lock(
     drive_id: String,
     path: String,
     lock_type: String,
+    wait: Option<bool>,
+    queue: Option<bool>,
+    upgrade: Option<bool>,
     state: State<'_, AppState>,
     lock_manager: State<'_, Arc<LockManager>>,
 ) -> Result<AcquireLockResult, String> {
@@ -55,12 +74,18 @@ pub async fn acquire_lock(
     let validated_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
     drop(drives);
     
-    let lock_type = match lock_type.as_str() {
-        "exclusive" => LockType::Exclusive,
-        _ => LockType::Advisory,
-    };
+    let lock_type = LockType::parse(&lock_type);
 
-    let result = lock_manager.acquire_lock(&drive_id, validated_path.clone(), lock_type).await;
+    let wait = wait.or(queue).unwrap_or(false);
+    let result = lock_manager
+        .acquire_lock(
+            &drive_id,
+            validated_path.clone(),
+            lock_type,
+            wait,
+            upgrade.unwrap_or(false),
+        )
+        .await;
     let node_id = lock_manager.node_id();
 
     match result {
@@ -80,6 +105,7 @@ pub async fn acquire_lock(
                 lock: Some(FileLockDto::from_lock(&lock, node_id)),
                 error: None,
                 warning: None,
+                queue_position: None,
             })
         }
         LockResult::AcquiredWithWarning { lock, warning } => {
@@ -97,6 +123,7 @@ pub async fn acquire_lock(
                 lock: Some(FileLockDto::from_lock(&lock, node_id)),
                 error: None,
                 warning: Some(warning),
+                queue_position: None,
             })
         }
         LockResult::Denied { existing_lock, reason } => {
@@ -112,6 +139,27 @@ pub async fn acquire_lock(
                 lock: Some(FileLockDto::from_lock(&existing_lock, node_id)),
                 error: Some(reason),
                 warning: None,
+                queue_position: None,
+            })
+        }
+        LockResult::Queued { position } => {
+            tracing::info!(
+                drive_id = %drive_id,
+                path = %path,
+                position = position,
+                "Lock request queued"
+            );
+
+            let holders = lock_manager.get_locks(&drive_id, &validated_path).await;
+            Ok(AcquireLockResult {
+                success: false,
+                lock: holders
+                    .iter()
+                    .find(|lock| !lock.is_held_by(node_id))
+                    .map(|lock| FileLockDto::from_lock(lock, node_id)),
+                error: None,
+                warning: None,
+                queue_position: Some(position),
             })
         }
     }
@@ -145,14 +193,16 @@ pub async fn release_lock(
     }
 }
 
-/// Get lock status for a specific file
+/// Get the current holders of a file's lock
+///
+/// Several nodes can be listed when they share read or advisory locks.
 #[tauri::command]
 pub async fn get_lock_status(
     drive_id: String,
     path: String,
     state: State<'_, AppState>,
     lock_manager: State<'_, Arc<LockManager>>,
-) -> Result<Option<FileLockDto>, String> {
+) -> Result<Vec<FileLockDto>, String> {
     let id = parse_drive_id(&drive_id)?;
     
     // Validate path against drive root
@@ -166,9 +216,11 @@ pub async fn get_lock_status(
     let node_id = lock_manager.node_id();
 
     Ok(lock_manager
-        .get_lock(&drive_id, &validated_path)
+        .get_locks(&drive_id, &validated_path)
         .await
-        .map(|lock| FileLockDto::from_lock(&lock, node_id)))
+        .iter()
+        .map(|lock| FileLockDto::from_lock(lock, node_id))
+        .collect())
 }
 
 /// List all locks for a drive
@@ -230,7 +282,7 @@ pub async fn extend_lock(
     }
 }
 
-/// Force release a lock (admin only)
+/// Force release every lock on a file (admin only)
 ///
 /// # Security
 /// - Validates path is within drive root
@@ -280,20 +332,145 @@ pub async fn force_release_lock(
         .to_string());
     }
 
-    let manager = lock_manager.get_drive_locks(&drive_id).await;
-
-    if let Some(released) = manager.force_release(&validated_path).await {
-        broadcast_lock_released(&state, &drive_id, &released).await;
+    let released = lock_manager
+        .force_release_lock(&drive_id, &validated_path)
+        .await;
+    for lock in &released {
+        broadcast_lock_released(&state, &drive_id, lock).await;
         tracing::warn!(
             drive_id = %drive_id,
             path = %path,
-            holder = %released.holder,
+            holder = %lock.holder,
             "Lock force released"
         );
-        Ok(true)
-    } else {
-        Ok(false)
     }
+    Ok(!released.is_empty())
+}
+
+/// List queued lock requests for a drive, or for a single file
+#[tauri::command]
+pub async fn list_lock_queue(
+    drive_id: String,
+    path: Option<String>,
+    state: State<'_, AppState>,
+    lock_manager: State<'_, Arc<LockManager>>,
+) -> Result<Vec<LockQueueEntryDto>, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    // Validate path against drive root
+    let validated_path = match path {
+        Some(ref path) => {
+            let drives = state.drives.read().await;
+            let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+                AppError::DriveNotFound { drive_id: drive_id.clone() }.to_string()
+            })?;
+            Some(validate_path(&drive.local_path, path).map_err(|e| e.to_string())?)
+        }
+        None => None,
+    };
+
+    let node_id = lock_manager.node_id();
+    let queue = lock_manager
+        .list_queue(&drive_id, validated_path.as_deref())
+        .await;
+
+    Ok(queue
+        .iter()
+        .map(|(position, request)| LockQueueEntryDto::from_request(request, *position, node_id))
+        .collect())
+}
+
+/// List the requests waiting for one file's lock, first in line first
+#[tauri::command]
+pub async fn get_lock_queue(
+    drive_id: String,
+    path: String,
+    state: State<'_, AppState>,
+    lock_manager: State<'_, Arc<LockManager>>,
+) -> Result<Vec<LockQueueEntryDto>, String> {
+    list_lock_queue(drive_id, Some(path), state, lock_manager).await
+}
+
+/// Withdraw our queued request for a file
+#[tauri::command]
+pub async fn cancel_lock_request(
+    drive_id: String,
+    path: String,
+    state: State<'_, AppState>,
+    lock_manager: State<'_, Arc<LockManager>>,
+) -> Result<bool, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    // Validate path against drive root
+    let drives = state.drives.read().await;
+    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+        AppError::DriveNotFound { drive_id: drive_id.clone() }.to_string()
+    })?;
+    let validated_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
+    drop(drives);
+
+    let cancelled = lock_manager.cancel_request(&drive_id, &validated_path).await;
+    if cancelled {
+        tracing::info!(drive_id = %drive_id, path = %path, "Lock request cancelled");
+    }
+
+    Ok(cancelled)
+}
+
+/// A lock we hold, with the drive it belongs to
+#[derive(Clone, Debug, Serialize)]
+pub struct MyLockDto {
+    pub drive_id: String,
+    /// Drive name, if the drive is still known locally
+    pub drive_name: Option<String>,
+    #[serde(flatten)]
+    pub lock: FileLockDto,
+}
+
+async fn my_lock_dtos(
+    state: &AppState,
+    lock_manager: &LockManager,
+    locks: &[(String, FileLock)],
+) -> Vec<MyLockDto> {
+    let drives = state.drives.read().await;
+    locks
+        .iter()
+        .map(|(drive_id, lock)| MyLockDto {
+            drive_id: drive_id.clone(),
+            drive_name: crate::core::drive::DriveId::from_hex(drive_id)
+                .ok()
+                .and_then(|id| drives.get(id.as_bytes()))
+                .map(|drive| drive.name.clone()),
+            lock: FileLockDto::from_lock(lock, lock_manager.node_id()),
+        })
+        .collect()
+}
+
+/// List every lock held by this node, across all drives
+#[tauri::command]
+pub async fn list_my_locks(
+    state: State<'_, AppState>,
+    lock_manager: State<'_, Arc<LockManager>>,
+) -> Result<Vec<MyLockDto>, String> {
+    let locks = lock_manager.list_my_locks().await;
+    Ok(my_lock_dtos(&state, &lock_manager, &locks).await)
+}
+
+/// Release every lock held by this node, across all drives
+///
+/// Each release is broadcast to peers. Returns the released locks.
+#[tauri::command]
+pub async fn release_all_my_locks(
+    state: State<'_, AppState>,
+    lock_manager: State<'_, Arc<LockManager>>,
+) -> Result<Vec<MyLockDto>, String> {
+    let released = lock_manager.release_all_my_locks().await;
+    for (drive_id, lock) in &released {
+        broadcast_lock_released(&state, drive_id, lock).await;
+    }
+
+    tracing::info!(count = released.len(), "Released all own locks");
+    Ok(my_lock_dtos(&state, &lock_manager, &released).await)
 }
 
 /// Broadcast lock acquired event via gossip
@@ -303,10 +480,7 @@ async fn broadcast_lock_acquired(state: &AppState, drive_id: &str, lock: &FileLo
             let event = DriveEvent::FileLockAcquired {
                 path: lock.path.clone(),
                 holder: lock.holder,
-                lock_type: match lock.lock_type {
-                    LockType::Advisory => "advisory".to_string(),
-                    LockType::Exclusive => "exclusive".to_string(),
-                },
+                lock_type: lock.lock_type.as_str().to_string(),
                 expires_at: lock.expires_at,
                 timestamp: Utc::now(),
             };
@@ -1,36 +1,90 @@
 mod audit;
+mod backup;
 mod conflict;
+mod dev;
 mod drive;
 mod files;
 mod identity;
 mod locking;
+mod notifications;
+mod operations;
 mod presence;
+mod receipts;
 mod security;
+mod settings;
+mod share_links;
 mod sync;
 
-pub use audit::{get_audit_count, get_audit_log, get_denied_access_log, get_drive_audit_log};
+pub use audit::{
+    get_audit_count, get_audit_log, get_denied_access_log, get_drive_audit_log, subscribe_audit,
+    unsubscribe_audit,
+};
+pub use backup::{backup_all, restore_all};
 pub use conflict::{
-    dismiss_conflict, get_conflict, get_conflict_count, list_conflicts, resolve_conflict,
+    dismiss_conflict, get_conflict, get_conflict_count, get_total_conflict_count,
+    list_all_conflicts, list_conflicts, merge_text_conflict, resolve_all_conflicts,
+    resolve_conflict, set_conflict_policy,
+};
+pub use dev::{list_test_peers, spawn_test_peer, stop_test_peer};
+pub use drive::{
+    archive_drive, create_drive, delete_drive, get_drive, get_drive_metadata, list_deleted_drives,
+    list_drives, merge_drives, rename_drive, restore_drive, set_activity_retention,
+    set_conflict_sensitivity, set_drive_metadata, set_max_file_size, set_name_policy,
+    set_new_file_policy, unarchive_drive,
 };
-pub use drive::{create_drive, delete_drive, get_drive, list_drives, rename_drive};
 pub use files::{
-    delete_path, list_files, read_file, read_file_encrypted, rename_path, write_file,
+    delete_path, export_file_tree, fetch_file_preview, get_encryption_overhead, get_file_details,
+    list_files, list_files_lazy, list_files_recursive, list_files_since, read_file,
+    read_file_encrypted, rename_path, scan_drive_safety, self_test_drive, write_file,
     write_file_encrypted,
 };
-pub use identity::{get_connection_status, get_identity};
+pub use identity::{get_connection_status, get_identity, test_relay_connectivity};
 pub use locking::{
-    acquire_lock, extend_lock, force_release_lock, get_lock_status, list_locks, release_lock,
+    acquire_lock, cancel_lock_request, extend_lock, force_release_lock, get_lock_queue,
+    get_lock_status, list_lock_queue, list_locks, list_my_locks, release_all_my_locks, release_lock,
+};
+pub use notifications::{
+    get_notification_prefs, get_webhooks, notify_drive, set_notification_prefs, set_webhook,
+    test_webhook,
 };
+pub use operations::{cancel_operation, list_operations};
 pub use presence::{
-    get_online_count, get_online_users, get_recent_activity, join_drive_presence,
-    leave_drive_presence, presence_heartbeat,
+    announce_presence, get_drive_timeline, get_online_count, get_online_users, get_recent_activity,
+    join_drive_presence, leave_drive_presence, presence_heartbeat,
 };
+pub use receipts::{get_file_readers, mark_file_read, set_read_receipts};
 pub use security::{
-    accept_invite, check_permission, generate_invite, grant_permission, list_permissions,
-    list_revoked_tokens, revoke_invite, revoke_permission, verify_invite, SecurityStore,
+    accept_invite, apply_acl_state, auto_accept_invite, check_permission, generate_access_report,
+    generate_invite, get_default_invite_permission, get_effective_permission, get_invite_analytics,
+    get_trusted_inviters, grant_permission, list_permissions, list_revoked_tokens,
+    parse_invite_link, preview_join_cost, revalidate_issued_invites, revoke_invite,
+    revoke_permission, rotate_doc_sharing, set_default_invite_permission, set_trusted_inviters,
+    verify_invite, SecurityStore,
+};
+pub use settings::{
+    check_database_integrity, get_bandwidth_limit, get_download_temp_dir, get_endpoint_config,
+    get_instance_settings, get_key_cache_policy, get_key_lock_status, get_maintenance_window,
+    get_power_status, get_presence_timeout, get_transfer_chunk_size, get_transfer_limits,
+    get_watch_mode, lock_keys, set_bandwidth_limit, set_download_temp_dir, set_endpoint_config,
+    set_key_cache_policy, set_key_passphrase, set_maintenance_window, set_max_concurrent_transfers,
+    set_presence_timeout, set_single_instance, set_startup_integrity_check, set_sync_only_on_ac,
+    set_transfer_chunk_size, set_watch_debounce, set_watch_mode, set_watch_throttle, unlock_keys,
+};
+pub use share_links::{
+    check_issued_share_link, create_file_share_link, fetch_shared_file, revoke_file_share_link,
 };
 pub use sync::{
-    cancel_transfer, download_file, get_sync_diagnostics, get_sync_status, get_transfer,
-    import_file, is_watching, list_transfers, start_sync, start_watching, stop_sync,
-    stop_watching, subscribe_drive_events, upload_file,
+    cancel_peer_retry, cancel_transfer, check_drive_consistency, cleanup_orphan_namespaces,
+    compute_peer_gap, discard_pending_upload, download_file, drain_drive, get_blob_store_stats,
+    get_change_summary_config, get_drive_merkle_root, get_drive_peers, get_file_history,
+    get_no_direct_sync, get_peer_metrics, get_peer_retry_config, get_sync_diagnostics,
+    get_sync_filters, get_sync_status, get_transfer, import_file, is_watching,
+    list_active_subscriptions, list_orphan_namespaces, list_pending_uploads, list_permission_errors,
+    list_transfers, list_watch_status, measure_e2e_sync_latency, pause_sync, reimport_drive_blobs,
+    reload_ignore_rules, repair_drive_docs_mapping, restore_version, resume_drive, resume_sync,
+    resume_transfer, retry_pending_uploads, retry_permission_errors, set_change_summary_config,
+    set_max_peers, set_no_direct_sync, set_peer_retry_config, set_sync_filters, start_sync,
+    start_watching, stop_sync, stop_watching, subscribe_drive_events,
+    subscribe_drive_events_filtered, unsubscribe_all_drive_events, unsubscribe_drive_events,
+    upload_file, verify_drive_docs_mapping, verify_local_matches_blobs,
 };
@@ -0,0 +1,167 @@
+//! Per-drive notification preference commands
+//!
+//! Remote drive events are filtered through these preferences before a
+//! system notification is shown. Conflicts and errors are detected by the
+//! frontend, which routes them through `notify_drive` so the same
+//! preferences apply.
+//!
+//! Webhooks send drive events to external systems instead; see
+//! `network::webhook`.
+
+use crate::core::{validate_drive_id, AppError, NotificationCategory, NotificationPrefs};
+use crate::network::{
+    WebhookConfig, WebhookDispatcher, WebhookSettings, WebhookTestResult, WEBHOOK_SETTING,
+};
+use crate::state::AppState;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+use tauri_plugin_notification::NotificationExt;
+
+/// Get the notification preferences for a drive
+#[tauri::command]
+pub async fn get_notification_prefs(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<NotificationPrefs, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let drives = state.drives.read().await;
+    let drive = drives.get(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    Ok(drive.notifications)
+}
+
+/// Set the notification preferences for a drive on this device
+#[tauri::command]
+pub async fn set_notification_prefs(
+    drive_id: String,
+    prefs: NotificationPrefs,
+    state: State<'_, AppState>,
+) -> Result<NotificationPrefs, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.notifications = prefs;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(drive_id = %drive_id, prefs = ?prefs, "Updated notification preferences");
+    Ok(prefs)
+}
+
+/// Show a notification for a drive if its preferences allow the category
+///
+/// Returns whether a notification was shown.
+#[tauri::command]
+pub async fn notify_drive(
+    drive_id: String,
+    category: NotificationCategory,
+    body: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let (name, prefs) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        (drive.name.clone(), drive.notifications)
+    };
+
+    if !prefs.allows(category) {
+        return Ok(false);
+    }
+
+    app.notification()
+        .builder()
+        .title(name)
+        .body(body)
+        .show()
+        .map_err(|e| format!("Failed to show notification: {}", e))?;
+    Ok(true)
+}
+
+// ==============================================
+// Webhook Commands
+// ==============================================
+
+/// Get the global webhook and per-drive webhooks
+#[tauri::command]
+pub async fn get_webhooks(
+    webhooks: State<'_, Arc<WebhookDispatcher>>,
+) -> Result<WebhookSettings, String> {
+    Ok(webhooks.settings().await)
+}
+
+/// Set or clear the webhook for a drive, or the global one
+///
+/// With `drive_id`, the webhook replaces the global one for that drive.
+/// Passing no `config` removes the webhook. Matching events are POSTed as
+/// signed JSON in the background with retries; failures never affect sync.
+#[tauri::command]
+pub async fn set_webhook(
+    drive_id: Option<String>,
+    config: Option<WebhookConfig>,
+    state: State<'_, AppState>,
+    webhooks: State<'_, Arc<WebhookDispatcher>>,
+) -> Result<WebhookSettings, String> {
+    if let Some(ref drive_id) = drive_id {
+        let id_arr = validate_drive_id(drive_id).map_err(|e| e.to_string())?;
+        if !state.drives.read().await.contains_key(&id_arr) {
+            return Err(AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string());
+        }
+    }
+    if let Some(ref config) = config {
+        config.validate().map_err(|e| e.to_string())?;
+    }
+    let enabled = config.is_some();
+
+    let settings = webhooks.set(drive_id.as_deref(), config).await;
+    let bytes = serde_json::to_vec(&settings)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(WEBHOOK_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    tracing::info!(drive_id = ?drive_id, enabled, "Updated webhook");
+    Ok(settings)
+}
+
+/// Send a test event to the webhook for a drive (or the global one)
+///
+/// Sent once without retrying, so the result reflects the endpoint's
+/// answer right now.
+#[tauri::command]
+pub async fn test_webhook(
+    drive_id: Option<String>,
+    webhooks: State<'_, Arc<WebhookDispatcher>>,
+) -> Result<WebhookTestResult, String> {
+    webhooks
+        .test(drive_id.as_deref())
+        .await
+        .map_err(|e| format!("Webhook test failed: {:#}", e))
+}
@@ -0,0 +1,29 @@
+//! Long-running operation commands
+//!
+//! Commands such as `merge_drives` and `check_drive_consistency` accept an
+//! optional `operation_id`; these commands list and cancel them.
+
+use crate::core::{OperationInfo, OperationRegistry};
+use std::sync::Arc;
+use tauri::State;
+
+/// Ask a running operation to stop at its next checkpoint
+///
+/// Returns false if no operation with that ID is running (it may already
+/// have finished). The cancelled command still returns normally, with its
+/// result marked as cancelled.
+#[tauri::command]
+pub async fn cancel_operation(
+    operation_id: String,
+    operations: State<'_, Arc<OperationRegistry>>,
+) -> Result<bool, String> {
+    Ok(operations.cancel(&operation_id))
+}
+
+/// List operations that are currently running
+#[tauri::command]
+pub async fn list_operations(
+    operations: State<'_, Arc<OperationRegistry>>,
+) -> Result<Vec<OperationInfo>, String> {
+    Ok(operations.list())
+}
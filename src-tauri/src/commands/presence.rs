@@ -7,13 +7,31 @@
 //! - Limits activity query results to prevent memory exhaustion
 
 use crate::core::validation::validate_drive_id;
-use crate::core::{ActivityEntryDto, PresenceManager, UserPresenceDto};
+use crate::core::{
+    sort_timeline, ActivityEntryDto, AppError, ConflictManager, DriveId, LockManager,
+    PresenceManager, TimelineEntry, UserPresenceDto,
+};
+use crate::state::AppState;
+use serde::Serialize;
 use std::sync::Arc;
 use tauri::State;
 
 /// Maximum number of activity entries to return
 const MAX_ACTIVITY_LIMIT: usize = 500;
 
+/// Default number of timeline entries per page
+const DEFAULT_TIMELINE_LIMIT: usize = 100;
+
+/// One page of a drive's timeline
+#[derive(Clone, Debug, Serialize)]
+pub struct TimelinePage {
+    pub entries: Vec<TimelineEntry>,
+    /// Total entries across all pages
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
 /// Get online users for a drive
 #[tauri::command]
 pub async fn get_online_users(
@@ -67,6 +85,73 @@ pub async fn get_recent_activity(
         .collect())
 }
 
+/// Get a drive's history merged from activity, transfers, conflicts, and locks
+///
+/// Entries are sorted newest first with a stable tie-break, so paging with
+/// `offset` gives consistent results while nothing new arrives. Locks are
+/// the ones currently held; transfers are those tracked this session.
+#[tauri::command]
+pub async fn get_drive_timeline(
+    drive_id: String,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    state: State<'_, AppState>,
+    presence_manager: State<'_, Arc<PresenceManager>>,
+    conflict_manager: State<'_, Arc<ConflictManager>>,
+    lock_manager: State<'_, Arc<LockManager>>,
+) -> Result<TimelinePage, String> {
+    // Validate drive_id format
+    validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let limit = limit.unwrap_or(DEFAULT_TIMELINE_LIMIT).clamp(1, MAX_ACTIVITY_LIMIT);
+    let offset = offset.unwrap_or(0);
+    let node_id = presence_manager.node_id();
+
+    let mut entries: Vec<TimelineEntry> = presence_manager
+        .get_recent_activity(&drive_id, usize::MAX)
+        .await
+        .iter()
+        .map(|a| TimelineEntry::from_activity(a, node_id))
+        .collect();
+
+    if let Some(file_transfer) = state.file_transfer.as_ref() {
+        entries.extend(
+            file_transfer
+                .list_transfers()
+                .await
+                .iter()
+                .filter(|t| t.drive_id.eq_ignore_ascii_case(&drive_id))
+                .map(|t| TimelineEntry::from_transfer(t, node_id)),
+        );
+    }
+
+    entries.extend(
+        conflict_manager
+            .list_conflicts(&drive_id)
+            .await
+            .iter()
+            .map(|c| TimelineEntry::from_conflict(c, node_id)),
+    );
+    entries.extend(
+        lock_manager
+            .list_locks(&drive_id)
+            .await
+            .iter()
+            .map(|l| TimelineEntry::from_lock(l, node_id)),
+    );
+
+    sort_timeline(&mut entries);
+    let total = entries.len();
+    let entries = entries.into_iter().skip(offset).take(limit).collect();
+
+    Ok(TimelinePage {
+        entries,
+        total,
+        offset,
+        limit,
+    })
+}
+
 /// Join a drive (announce presence)
 #[tauri::command]
 pub async fn join_drive_presence(
@@ -109,3 +194,34 @@ pub async fn presence_heartbeat(
     manager.user_heartbeat(node_id).await;
     Ok(())
 }
+
+/// Re-announce presence to a drive's peers
+///
+/// Broadcasts `UserJoined` with this build's protocol version and
+/// capabilities, so peers can negotiate features and flag version
+/// mismatches. Peers' announcements show up in `get_drive_peers`.
+#[tauri::command]
+pub async fn announce_presence(
+    drive_id: String,
+    state: State<'_, AppState>,
+    presence_manager: State<'_, Arc<PresenceManager>>,
+) -> Result<(), String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    if !state.drives.read().await.contains_key(&id_arr) {
+        return Err(AppError::DriveNotFound { drive_id }.to_string());
+    }
+
+    let broadcaster = state
+        .event_broadcaster
+        .as_ref()
+        .ok_or_else(|| AppError::BroadcasterNotInitialized.to_string())?;
+
+    presence_manager.join_drive(&drive_id).await;
+    broadcaster
+        .announce_presence(&DriveId(id_arr))
+        .await
+        .map_err(|e| AppError::SyncFailed(e.to_string()).to_string())?;
+
+    tracing::debug!(drive_id = %drive_id, "Announced presence");
+    Ok(())
+}
@@ -0,0 +1,160 @@
+//! Read receipt commands
+//!
+//! Receipts are privacy-sensitive, so they are off by default and must be
+//! enabled per drive on each node. Members only send receipts for drives
+//! they have opted in to; owners only record them for drives they own.
+
+use crate::core::{
+    file, validate_drive_id, validate_path, AppError, DriveEvent, DriveId, FileReader,
+    ReadReceiptStore,
+};
+use crate::state::AppState;
+use chrono::Utc;
+use std::sync::Arc;
+use tauri::State;
+
+/// Enable or disable read receipts for a drive on this device
+#[tauri::command]
+pub async fn set_read_receipts(
+    drive_id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.read_receipts = enabled;
+
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(drive_id = %drive_id, enabled = enabled, "Updated read receipts for drive");
+    Ok(())
+}
+
+/// Announce that the user opened a file
+///
+/// Called by the frontend when a file is opened. Returns whether a receipt
+/// was sent; nothing is sent unless read receipts are enabled for the drive,
+/// or when the owner opens their own file.
+#[tauri::command]
+pub async fn mark_file_read(
+    drive_id: String,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let (relative_path, owner) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+
+        if !drive.read_receipts {
+            return Ok(false);
+        }
+
+        let safe_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
+        let relative_path = safe_path
+            .strip_prefix(&drive.local_path)
+            .map(|p| p.to_path_buf())
+            .map_err(|_| {
+                AppError::InvalidPath {
+                    path: path.clone(),
+                    reason: "path is outside the drive".to_string(),
+                }
+                .to_string()
+            })?;
+        (relative_path, drive.owner)
+    };
+
+    let reader = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    if reader == owner {
+        return Ok(false);
+    }
+
+    let broadcaster = state
+        .event_broadcaster
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let event = DriveEvent::FileRead {
+        path: relative_path,
+        reader,
+        timestamp: Utc::now(),
+    };
+    broadcaster
+        .broadcast(&DriveId(id_arr), event)
+        .await
+        .map_err(|e| format!("Failed to send read receipt: {}", e))?;
+
+    tracing::debug!(drive_id = %drive_id, path = %path, "Sent read receipt");
+    Ok(true)
+}
+
+/// List members who have read a file in a drive we own
+#[tauri::command]
+pub async fn get_file_readers(
+    drive_id: String,
+    path: String,
+    state: State<'_, AppState>,
+    receipts: State<'_, Arc<ReadReceiptStore>>,
+) -> Result<Vec<FileReader>, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let (relative_path, owner) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        let safe_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
+        let relative_path = safe_path
+            .strip_prefix(&drive.local_path)
+            .map(|p| p.to_path_buf())
+            .map_err(|_| {
+                AppError::InvalidPath {
+                    path: path.clone(),
+                    reason: "path is outside the drive".to_string(),
+                }
+                .to_string()
+            })?;
+        (relative_path, drive.owner)
+    };
+
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    if caller != owner {
+        return Err(AppError::AccessDenied {
+            reason: "only the drive owner can view read receipts".to_string(),
+        }
+        .to_string());
+    }
+
+    Ok(receipts
+        .readers(&drive_id, &file::path_key(&relative_path))
+        .await)
+}
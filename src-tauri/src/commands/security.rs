@@ -10,9 +10,11 @@
 use crate::core::error::AppError;
 use crate::core::rate_limit::{RateLimitOperation, SharedRateLimiter};
 use crate::core::validation::{validate_drive_id, validate_node_id};
-use crate::core::{DriveId, SharedDrive};
+use crate::core::audit::AuditEvent;
+use crate::core::{AuditLogger, DriveEvent, DriveId, NamePolicy, PresenceManager, SharedDrive};
 use crate::crypto::{
-    AccessControlList, AccessRule, InviteBuilder, InviteToken, NodeId, Permission, TokenTracker,
+    AccessControlList, AccessRule, InviteBuilder, InviteSignatureStatus, InviteStatus, InviteToken,
+    IssuedInvite, NodeId, Permission, ShareLinkRecords, TokenTracker,
 };
 use crate::state::AppState;
 use crate::storage::Database;
@@ -38,6 +40,10 @@ pub struct SecurityStore {
     token_trackers: RwLock<HashMap<String, TokenTracker>>,
     /// Revoked token IDs keyed by drive ID (hex string)
     revoked_tokens: RwLock<HashMap<String, HashSet<String>>>,
+    /// Invites issued by this node keyed by drive ID (hex string)
+    issued_invites: RwLock<HashMap<String, Vec<IssuedInvite>>>,
+    /// Issued and revoked share links keyed by drive ID (hex string)
+    share_links: RwLock<HashMap<String, ShareLinkRecords>>,
 }
 
 impl SecurityStore {
@@ -48,10 +54,12 @@ impl SecurityStore {
             acls: RwLock::new(HashMap::new()),
             token_trackers: RwLock::new(HashMap::new()),
             revoked_tokens: RwLock::new(HashMap::new()),
+            issued_invites: RwLock::new(HashMap::new()),
+            share_links: RwLock::new(HashMap::new()),
         }
     }
 
-    /// Load all ACLs, token trackers, and revoked tokens from database
+    /// Load all ACLs, token trackers, revoked tokens, and issued invites from database
     pub fn load_from_db(&self) -> Result<(), String> {
         // Load ACLs
         let acl_entries = self.db.list_acls().map_err(|e| e.to_string())?;
@@ -111,6 +119,38 @@ impl SecurityStore {
             revoked_guard.len()
         );
 
+        // Load issued invites
+        let issued_entries = self.db.list_issued_invites().map_err(|e| e.to_string())?;
+        let mut issued_guard = self.issued_invites.blocking_write();
+        for (drive_id, data) in issued_entries {
+            match serde_json::from_slice::<Vec<IssuedInvite>>(&data) {
+                Ok(invites) => {
+                    issued_guard.insert(drive_id, invites);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to deserialize issued invites: {}", e);
+                }
+            }
+        }
+        tracing::info!(
+            "Loaded issued invites for {} drives from database",
+            issued_guard.len()
+        );
+
+        // Load share links
+        let link_entries = self.db.list_share_links().map_err(|e| e.to_string())?;
+        let mut links_guard = self.share_links.blocking_write();
+        for (drive_id, data) in link_entries {
+            match serde_json::from_slice::<ShareLinkRecords>(&data) {
+                Ok(records) => {
+                    links_guard.insert(drive_id, records);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to deserialize share links: {}", e);
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -271,6 +311,148 @@ impl SecurityStore {
         let revoked = self.revoked_tokens.read().await;
         revoked.get(drive_id).cloned().unwrap_or_default()
     }
+
+    // ============================================================================
+    // Share Links
+    // ============================================================================
+
+    /// Record a share link issued by this node (persists to database)
+    pub async fn record_issued_share_link(&self, drive_id: &str, token_id: &str) {
+        let mut links = self.share_links.write().await;
+        let records = links.entry(drive_id.to_string()).or_default();
+        records.record_issued(token_id);
+        self.persist_share_links(drive_id, records);
+    }
+
+    /// Check if this node issued a share link
+    pub async fn is_share_link_issued(&self, drive_id: &str, token_id: &str) -> bool {
+        let links = self.share_links.read().await;
+        links
+            .get(drive_id)
+            .is_some_and(|records| records.is_issued(token_id))
+    }
+
+    /// Revoke a share link (persists to database)
+    pub async fn revoke_share_link(&self, drive_id: &str, token_id: &str) {
+        let mut links = self.share_links.write().await;
+        let records = links.entry(drive_id.to_string()).or_default();
+        if records.revoke(token_id) {
+            self.persist_share_links(drive_id, records);
+        }
+    }
+
+    /// Check if a share link is revoked
+    pub async fn is_share_link_revoked(&self, drive_id: &str, token_id: &str) -> bool {
+        let links = self.share_links.read().await;
+        links
+            .get(drive_id)
+            .is_some_and(|records| records.is_revoked(token_id))
+    }
+
+    fn persist_share_links(&self, drive_id: &str, records: &ShareLinkRecords) {
+        match serde_json::to_vec(records) {
+            Ok(data) => {
+                if let Err(e) = self.db.save_share_links(drive_id, &data) {
+                    tracing::error!(
+                        "Failed to persist share links for drive {}: {}",
+                        drive_id,
+                        e
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to serialize share links: {}", e);
+            }
+        }
+    }
+
+    // ============================================================================
+    // Issued Invite Tracking
+    // ============================================================================
+
+    /// Record a newly issued invite (persists to database)
+    pub async fn record_issued_invite(&self, drive_id: &str, invite: IssuedInvite) {
+        let mut issued = self.issued_invites.write().await;
+        let invites = issued.entry(drive_id.to_string()).or_default();
+        invites.push(invite);
+        self.persist_issued_invites(drive_id, invites);
+    }
+
+    /// Record that a node accepted an issued invite
+    ///
+    /// Returns false if the token was not issued by this node for the drive.
+    pub async fn record_invite_acceptance(
+        &self,
+        drive_id: &str,
+        token_id: &str,
+        acceptor: &str,
+    ) -> bool {
+        let mut issued = self.issued_invites.write().await;
+        let Some(invites) = issued.get_mut(drive_id) else {
+            return false;
+        };
+        let Some(invite) = invites.iter_mut().find(|i| i.token_id == token_id) else {
+            return false;
+        };
+        if invite.record_acceptance(acceptor, Utc::now()) {
+            self.persist_issued_invites(drive_id, invites);
+        }
+        true
+    }
+
+    /// Mark an issued invite as revoked (no-op for unknown tokens)
+    pub async fn record_invite_revoked(&self, drive_id: &str, token_id: &str) {
+        let mut issued = self.issued_invites.write().await;
+        let Some(invites) = issued.get_mut(drive_id) else {
+            return;
+        };
+        if let Some(invite) = invites.iter_mut().find(|i| i.token_id == token_id) {
+            if invite.revoked_at.is_none() {
+                invite.revoked_at = Some(Utc::now());
+                self.persist_issued_invites(drive_id, invites);
+            }
+        }
+    }
+
+    /// Check that an acceptance names a live invite issued by this node
+    pub async fn invite_admits(&self, drive_id: &str, token_id: &str, acceptor: &str) -> bool {
+        if self.is_token_revoked(drive_id, token_id).await {
+            return false;
+        }
+        let issued = self.issued_invites.read().await;
+        issued
+            .get(drive_id)
+            .and_then(|invites| invites.iter().find(|i| i.token_id == token_id))
+            .is_some_and(|invite| invite.admits(acceptor, Utc::now()))
+    }
+
+    /// Get all invites issued for a drive
+    pub async fn get_issued_invites(&self, drive_id: &str) -> Vec<IssuedInvite> {
+        let issued = self.issued_invites.read().await;
+        issued.get(drive_id).cloned().unwrap_or_default()
+    }
+
+    /// Get every tracked issued invite, keyed by drive ID
+    pub async fn all_issued_invites(&self) -> HashMap<String, Vec<IssuedInvite>> {
+        self.issued_invites.read().await.clone()
+    }
+
+    fn persist_issued_invites(&self, drive_id: &str, invites: &[IssuedInvite]) {
+        match serde_json::to_vec(invites) {
+            Ok(data) => {
+                if let Err(e) = self.db.save_issued_invites(drive_id, &data) {
+                    tracing::error!(
+                        "Failed to persist issued invites for drive {}: {}",
+                        drive_id,
+                        e
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to serialize issued invites: {}", e);
+            }
+        }
+    }
 }
 
 // ============================================================================
@@ -324,7 +506,8 @@ pub struct UserPermission {
 #[derive(Clone, Debug, Deserialize)]
 pub struct CreateInviteRequest {
     pub drive_id: String,
-    pub permission: PermissionLevel,
+    /// Defaults to the drive's default invite permission, else read
+    pub permission: Option<PermissionLevel>,
     /// Validity in hours (default: 24)
     pub validity_hours: Option<u32>,
     /// Optional note/message
@@ -337,6 +520,10 @@ pub struct CreateInviteRequest {
 #[derive(Clone, Debug, Serialize)]
 pub struct InviteInfo {
     pub token: String,
+    /// `gix://invite/<token>` deep link that opens the app's accept dialog
+    pub link: String,
+    /// Token ID, used to revoke the invite or look it up in analytics
+    pub token_id: String,
     pub drive_id: String,
     pub permission: PermissionLevel,
     pub expires_at: String,
@@ -369,7 +556,7 @@ pub struct InviteVerification {
 pub async fn generate_invite(
     request: CreateInviteRequest,
     state: State<'_, AppState>,
-    _security: State<'_, Arc<SecurityStore>>,
+    security: State<'_, Arc<SecurityStore>>,
     rate_limiter: State<'_, SharedRateLimiter>,
 ) -> Result<InviteInfo, String> {
     // Rate limit check
@@ -408,6 +595,30 @@ pub async fn generate_invite(
         .to_string()
     })?;
 
+    // An explicit permission wins; the drive default must still be within
+    // the issuer's own access, which may have shrunk since it was set
+    let permission = match request.permission.clone() {
+        Some(permission) => permission,
+        None => match drive.default_invite_permission {
+            Some(default) => {
+                let acl = security
+                    .get_or_create_acl(drive_id, &drive.owner.to_hex())
+                    .await;
+                if !acl.check_permission(&node_id.to_hex(), "/", default) {
+                    return Err(AppError::AccessDenied {
+                        reason: format!(
+                            "Default invite permission {:?} exceeds your access",
+                            default
+                        ),
+                    }
+                    .to_string());
+                }
+                default.into()
+            }
+            None => PermissionLevel::Read,
+        },
+    };
+
     // Get the signing key from identity manager
     let signing_key = state
         .identity_manager
@@ -424,9 +635,8 @@ pub async fn generate_invite(
             .create_doc(drive_id_obj)
             .await
             .map_err(|e| format!("Failed to initialize doc for invite: {}", e))?;
-        let permission: Permission = request.permission.clone().into();
         let ticket = docs_manager
-            .get_ticket(&drive_id_obj, permission)
+            .get_ticket(&drive_id_obj, permission.clone().into())
             .await
             .map_err(|e| format!("Failed to generate doc ticket: {}", e))?;
         Some(ticket.to_string())
@@ -435,8 +645,9 @@ pub async fn generate_invite(
     };
 
     let mut builder = InviteBuilder::new(drive_id, &drive.name)
-        .with_permission(request.permission.clone().into())
-        .with_validity(validity);
+        .with_permission(permission.clone().into())
+        .with_validity(validity)
+        .with_drive_stats(drive.file_count, drive.total_size);
 
     if let Some(note) = &request.note {
         // Validate note length
@@ -463,13 +674,21 @@ pub async fn generate_invite(
     let token_string = token
         .to_string()
         .map_err(|e| format!("Failed to serialize token: {}", e))?;
+    let link = token
+        .to_link()
+        .map_err(|e| format!("Failed to serialize token: {}", e))?;
 
     let expires_at = Utc::now() + ChronoDuration::hours(validity_hours as i64);
 
+    // Track the invite so owners can see how it performs
+    security
+        .record_issued_invite(drive_id, IssuedInvite::from_token(&token))
+        .await;
+
     tracing::info!(
         drive_id = %drive_id,
         drive_name = %drive.name,
-        permission = ?request.permission,
+        permission = ?permission,
         validity_hours = validity_hours,
         single_use = request.single_use.unwrap_or(false),
         "Generated invite token"
@@ -477,14 +696,113 @@ pub async fn generate_invite(
 
     Ok(InviteInfo {
         token: token_string,
+        link,
+        token_id: token.token_id().to_string(),
         drive_id: drive_id.clone(),
-        permission: request.permission,
+        permission,
         expires_at: expires_at.to_rfc3339(),
         note: request.note,
         single_use: request.single_use.unwrap_or(false),
     })
 }
 
+/// Get the permission new invites for a drive get when none is specified
+#[tauri::command]
+pub async fn get_default_invite_permission(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<PermissionLevel>, String> {
+    let id_arr = parse_drive_id(&drive_id)?;
+
+    let drives = state.drives.read().await;
+    let drive = drives.get(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    Ok(drive.default_invite_permission.map(PermissionLevel::from))
+}
+
+/// Set the permission new invites for a drive get when none is specified
+///
+/// Requires Manage permission, and the default can't exceed the caller's
+/// own access to the drive. Pass `None` to fall back to read access.
+#[tauri::command]
+pub async fn set_default_invite_permission(
+    drive_id: String,
+    permission: Option<PermissionLevel>,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<Option<PermissionLevel>, String> {
+    let id_arr = parse_drive_id(&drive_id)?;
+
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+
+    let mut drives = state.drives.write().await;
+    let drive = drives.get_mut(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+
+    let acl = security
+        .get_or_create_acl(&drive_id, &drive.owner.to_hex())
+        .await;
+    if !acl.check_permission(&caller_hex, "/", Permission::Manage) {
+        return Err(AppError::AccessDenied {
+            reason: "Manage permission required to change invite defaults".to_string(),
+        }
+        .to_string());
+    }
+    let permission: Option<Permission> = permission.map(Permission::from);
+    if let Some(permission) = permission {
+        if !acl.check_permission(&caller_hex, "/", permission) {
+            return Err(AppError::AccessDenied {
+                reason: format!(
+                    "Default invite permission {:?} exceeds your access",
+                    permission
+                ),
+            }
+            .to_string());
+        }
+    }
+
+    drive.default_invite_permission = permission;
+    let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+        AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+    })?;
+    state.db.save_drive(&id_arr, &drive_bytes).map_err(|e| {
+        AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+    })?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        permission = ?permission,
+        "Updated default invite permission"
+    );
+    Ok(permission.map(PermissionLevel::from))
+}
+
+/// Extract the invite token from a `gix://invite/<token>` deep link
+///
+/// Rejects links with another scheme or a malformed token, so the result
+/// can be passed straight to `verify_invite` and `accept_invite`.
+#[tauri::command]
+pub async fn parse_invite_link(url: String) -> Result<String, String> {
+    let token = InviteToken::from_link(&url).map_err(|e| {
+        tracing::warn!(error = %e, "Rejected invite link");
+        AppError::ValidationError(format!("Invalid invite link: {}", e)).to_string()
+    })?;
+    token.to_string().map_err(|e| AppError::SerializationError(e.to_string()).to_string())
+}
+
 /// Verify an invite token without accepting it
 ///
 /// # Security
@@ -495,7 +813,7 @@ pub async fn generate_invite(
 #[tauri::command]
 pub async fn verify_invite(
     token_string: String,
-    _state: State<'_, AppState>,
+    state: State<'_, AppState>,
     security: State<'_, Arc<SecurityStore>>,
 ) -> Result<InviteVerification, String> {
     // Parse the token
@@ -608,6 +926,19 @@ pub async fn verify_invite(
         });
     }
 
+    // SECURITY: The signer must still be allowed to invite, where we can tell
+    if let Some(reason) = check_inviter_authority(&state, &security, &token).await {
+        return Ok(InviteVerification {
+            valid: false,
+            drive_id: Some(token.payload.drive_id.clone()),
+            drive_name: Some(token.payload.drive_name.clone()),
+            permission: Some(token.payload.permission.into()),
+            inviter: Some(token.payload.inviter.clone()),
+            expires_at: Some(token.payload.expires_at.to_rfc3339()),
+            error: Some(reason),
+        });
+    }
+
     tracing::info!(
         drive_id = %token.payload.drive_id,
         drive_name = %token.payload.drive_name,
@@ -627,6 +958,78 @@ pub async fn verify_invite(
     })
 }
 
+/// Rough per-file cost of a join (metadata entry plus blob request), in seconds
+const JOIN_PER_FILE_SECS: f64 = 0.01;
+
+/// Estimated cost of joining a drive, shown before accepting an invite
+#[derive(Clone, Debug, Serialize)]
+pub struct JoinCostEstimate {
+    pub drive_id: String,
+    pub drive_name: String,
+    /// Always true: these figures are estimates, not guarantees
+    pub is_estimate: bool,
+    /// Files in the drive when the invite was created
+    pub file_count: Option<u64>,
+    /// Bytes in the drive when the invite was created
+    pub total_bytes: Option<u64>,
+    /// Measured download throughput used for the estimate (bytes/sec)
+    pub throughput_bytes_per_sec: Option<f64>,
+    /// Estimated initial sync duration (None = unknown)
+    pub estimated_seconds: Option<u64>,
+    /// Why no duration could be estimated
+    pub unknown_reason: Option<String>,
+}
+
+/// Estimate how long the initial sync of an invited drive will take
+///
+/// Uses the drive size embedded in the invite (as of when it was created)
+/// and this device's measured download throughput. Either may be missing -
+/// older invites carry no size, and throughput is only known after a
+/// download has completed - in which case the duration is reported as
+/// unknown.
+#[tauri::command]
+pub async fn preview_join_cost(
+    token_string: String,
+    state: State<'_, AppState>,
+) -> Result<JoinCostEstimate, String> {
+    let token = InviteToken::from_string(&token_string).map_err(|e| {
+        AppError::ValidationError(format!("Invalid invite token: {}", e)).to_string()
+    })?;
+
+    // Unsigned size claims are not worth showing
+    token.verify_inviter_signature().map_err(|_| {
+        AppError::ValidationError("Invalid signature - token may have been tampered with".into())
+            .to_string()
+    })?;
+
+    let stats = token.payload.drive_stats;
+    let throughput = match state.file_transfer.as_ref() {
+        Some(transfer) => transfer.download_throughput().await,
+        None => None,
+    };
+
+    let (estimated_seconds, unknown_reason) = match (stats, throughput) {
+        (None, _) => (None, Some("invite does not include drive size".to_string())),
+        (Some(_), None) => (None, Some("no download throughput measured yet".to_string())),
+        (Some(stats), Some(rate)) => {
+            let secs =
+                stats.total_bytes as f64 / rate + stats.file_count as f64 * JOIN_PER_FILE_SECS;
+            (Some(secs.ceil() as u64), None)
+        }
+    };
+
+    Ok(JoinCostEstimate {
+        drive_id: token.payload.drive_id.clone(),
+        drive_name: token.payload.drive_name.clone(),
+        is_estimate: true,
+        file_count: stats.map(|s| s.file_count),
+        total_bytes: stats.map(|s| s.total_bytes),
+        throughput_bytes_per_sec: throughput,
+        estimated_seconds,
+        unknown_reason,
+    })
+}
+
 /// Result of accepting an invite
 #[derive(Clone, Debug, Serialize)]
 pub struct AcceptInviteResult {
@@ -747,6 +1150,17 @@ pub async fn accept_invite(
         });
     }
 
+    // SECURITY: The signer must still be allowed to invite, where we can tell
+    if let Some(reason) = check_inviter_authority(&state, &security, &token).await {
+        return Ok(AcceptInviteResult {
+            success: false,
+            drive_id: token.payload.drive_id.clone(),
+            drive_name: String::new(),
+            permission: token.payload.permission.into(),
+            error: Some(reason),
+        });
+    }
+
     // Parse the drive ID from the token
     let drive_id = &token.payload.drive_id;
     let id_arr = match parse_drive_id(drive_id) {
@@ -945,6 +1359,20 @@ pub async fn accept_invite(
             created_at: Utc::now(),
             total_size: 0,
             file_count: 0,
+            max_peers: None,
+            read_receipts: false,
+            notifications: Default::default(),
+            no_direct_sync: Vec::new(),
+            archived_at: None,
+            deleted_at: None,
+            custom_metadata: Default::default(),
+            default_invite_permission: None,
+            max_file_size: 0,
+            name_policy: NamePolicy::default(),
+            conflict_sensitivity: Default::default(),
+            conflict_policy: Default::default(),
+            activity_retention: Default::default(),
+            new_file_policy: Default::default(),
         };
 
         // Save to database
@@ -1007,6 +1435,22 @@ pub async fn accept_invite(
         );
     }
 
+    // Let the issuer know the invite was used (best effort)
+    if let Some(broadcaster) = state.event_broadcaster.as_ref() {
+        let event = DriveEvent::InviteAccepted {
+            token_id: token.token_id().to_string(),
+            user: caller,
+            timestamp: Utc::now(),
+        };
+        if let Err(e) = broadcaster.broadcast(&drive_id_obj, event).await {
+            tracing::debug!(
+                drive_id = %drive_id,
+                error = %e,
+                "Failed to broadcast invite acceptance"
+            );
+        }
+    }
+
     // Auto-start file watching for the joined drive
     if let Some(watcher) = state.file_watcher.as_ref() {
         let drives = state.drives.read().await;
@@ -1044,6 +1488,129 @@ pub async fn accept_invite(
     })
 }
 
+/// Setting holding the trusted inviter list
+const TRUSTED_INVITERS_SETTING: &str = "trusted_inviters";
+
+/// Inviters whose invites may be accepted without confirmation
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrustedInviters {
+    /// Off unless the user turns it on; the list alone does nothing
+    pub auto_accept: bool,
+    /// Node IDs (hex) the user explicitly trusts
+    pub inviters: Vec<String>,
+}
+
+impl TrustedInviters {
+    fn load(db: &Database) -> Self {
+        db.get_setting(TRUSTED_INVITERS_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    fn trusts(&self, inviter: &str) -> bool {
+        self.auto_accept && self.inviters.iter().any(|i| i.eq_ignore_ascii_case(inviter))
+    }
+}
+
+/// Get the trusted inviter list and whether auto-accept is on
+#[tauri::command]
+pub async fn get_trusted_inviters(state: State<'_, AppState>) -> Result<TrustedInviters, String> {
+    Ok(TrustedInviters::load(&state.db))
+}
+
+/// Set which inviters are trusted and whether their invites are auto-accepted
+///
+/// Auto-accept is strictly opt-in: it only applies when `auto_accept` is
+/// true and the invite was signed by a node in `inviters`.
+#[tauri::command]
+pub async fn set_trusted_inviters(
+    auto_accept: bool,
+    inviters: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<TrustedInviters, String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(inviters.len());
+    for inviter in inviters {
+        validate_node_id_hex(&inviter)?;
+        let inviter = inviter.to_lowercase();
+        if !normalized.contains(&inviter) {
+            normalized.push(inviter);
+        }
+    }
+    let trusted = TrustedInviters {
+        auto_accept,
+        inviters: normalized,
+    };
+
+    let bytes = serde_json::to_vec(&trusted)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(TRUSTED_INVITERS_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    tracing::info!(
+        auto_accept = trusted.auto_accept,
+        inviters = trusted.inviters.len(),
+        "Updated trusted inviters"
+    );
+    Ok(trusted)
+}
+
+/// Accept an invite without confirmation if its inviter is trusted
+///
+/// Returns `None` when auto-accept is off or the inviter isn't trusted, so
+/// the caller falls back to the manual accept flow. Otherwise the invite
+/// goes through `accept_invite`, which verifies the signature, expiry,
+/// revocation, and inviter authority. Every auto-acceptance is audit-logged.
+#[tauri::command]
+pub async fn auto_accept_invite(
+    token_string: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    audit_logger: State<'_, Arc<AuditLogger>>,
+) -> Result<Option<AcceptInviteResult>, String> {
+    let Ok(token) = InviteToken::from_string(&token_string) else {
+        return Ok(None);
+    };
+    let inviter = token.payload.inviter.clone();
+    if !TrustedInviters::load(&state.db).trusts(&inviter) {
+        return Ok(None);
+    }
+
+    let result = accept_invite(token_string, state.clone(), security).await?;
+    if !result.success {
+        return Ok(Some(result));
+    }
+
+    let user_id = state
+        .identity_manager
+        .node_id()
+        .await
+        .map(|id| id.to_hex())
+        .unwrap_or_default();
+    tracing::info!(
+        drive_id = %result.drive_id,
+        inviter = %inviter,
+        "Auto-accepted invite from trusted inviter"
+    );
+    if let Err(e) = audit_logger
+        .log(AuditEvent::InviteAutoAccepted {
+            drive_id: result.drive_id.clone(),
+            token_id: token.token_id().to_string(),
+            user_id,
+            inviter,
+        })
+        .await
+    {
+        tracing::warn!("Failed to audit invite auto-acceptance: {}", e);
+    }
+
+    Ok(Some(result))
+}
+
 /// List permissions for a drive
 #[tauri::command]
 pub async fn list_permissions(
@@ -1210,23 +1777,160 @@ pub async fn revoke_permission(
     Ok(())
 }
 
-/// Check if a user has a specific permission for a path
+/// A member in the desired ACL state
+#[derive(Clone, Debug, Deserialize)]
+pub struct DesiredMember {
+    pub node_id: String,
+    pub permission: PermissionLevel,
+}
+
+/// One grant, permission change or revoke made by `apply_acl_state`
+#[derive(Clone, Debug, Serialize)]
+pub struct AclChangeInfo {
+    pub node_id: String,
+    /// Permission before the change (None = no access)
+    pub from: Option<PermissionLevel>,
+    /// Permission after the change (None = revoked)
+    pub to: Option<PermissionLevel>,
+}
+
+/// Result of applying a desired ACL state
+#[derive(Clone, Debug, Serialize)]
+pub struct ApplyAclResult {
+    pub drive_id: String,
+    /// Changes needed to reach the desired state, sorted by node ID
+    pub changes: Vec<AclChangeInfo>,
+    /// False for a dry run or when nothing needed to change
+    pub applied: bool,
+}
+
+/// Make a drive's members match a desired list
+///
+/// Members not in the list are revoked, listed members are granted their
+/// permission, and members already at the listed permission are left
+/// alone, so applying the same list twice changes nothing. The owner is
+/// never changed. Requires Manage; no listed permission may exceed the
+/// caller's own, and members above the caller's level can't be changed.
+/// All changes are saved as one ACL update. With `dry_run` the changes are
+/// only computed.
 #[tauri::command]
-pub async fn check_permission(
+pub async fn apply_acl_state(
     drive_id: String,
-    node_id: Option<String>,
-    path: String,
-    required: PermissionLevel,
+    members: Vec<DesiredMember>,
+    dry_run: Option<bool>,
     state: State<'_, AppState>,
     security: State<'_, Arc<SecurityStore>>,
-) -> Result<bool, String> {
+) -> Result<ApplyAclResult, String> {
     let id_arr = parse_drive_id(&drive_id)?;
 
-    // Get drive to find owner
-    let drives = state.drives.read().await;
-    let drive = drives
-        .get(&id_arr)
-        .ok_or_else(|| "Drive not found".to_string())?;
+    // ACL entries are keyed by lowercase hex, so the list is compared in it too
+    let mut desired: HashMap<String, Permission> = HashMap::new();
+    for member in members {
+        validate_node_id_hex(&member.node_id)?;
+        let node_id = member.node_id.to_lowercase();
+        if desired
+            .insert(node_id.clone(), member.permission.into())
+            .is_some()
+        {
+            return Err(AppError::ValidationFailed {
+                field: "members".to_string(),
+                reason: format!("{} is listed more than once", node_id),
+            }
+            .to_string());
+        }
+    }
+
+    let drives = state.drives.read().await;
+    let drive = drives.get(&id_arr).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    let owner_hex = drive.owner.to_hex();
+
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let caller_hex = caller.to_hex();
+
+    let mut acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, "/", Permission::Manage) {
+        return Err(AppError::AccessDenied {
+            reason: "Manage permission required to change members".to_string(),
+        }
+        .to_string());
+    }
+
+    let changes = acl.diff_members(&desired);
+    for change in &changes {
+        for permission in [change.from, change.to].into_iter().flatten() {
+            if !acl.check_permission(&caller_hex, "/", permission) {
+                return Err(AppError::AccessDenied {
+                    reason: format!(
+                        "Changing {} involves {:?}, which exceeds your access",
+                        change.node_id, permission
+                    ),
+                }
+                .to_string());
+            }
+        }
+    }
+
+    let applied = !dry_run.unwrap_or(false) && !changes.is_empty();
+    if applied {
+        for change in &changes {
+            match change.to {
+                Some(permission) => {
+                    acl.grant(&change.node_id, AccessRule::new(permission, &caller_hex))
+                }
+                None => {
+                    acl.revoke(&change.node_id);
+                }
+            }
+        }
+        security.update_acl(&drive_id, acl).await;
+
+        tracing::info!(
+            drive_id = %drive_id,
+            changes = changes.len(),
+            "Applied desired ACL state"
+        );
+    }
+
+    Ok(ApplyAclResult {
+        drive_id,
+        changes: changes
+            .into_iter()
+            .map(|change| AclChangeInfo {
+                node_id: change.node_id,
+                from: change.from.map(PermissionLevel::from),
+                to: change.to.map(PermissionLevel::from),
+            })
+            .collect(),
+        applied,
+    })
+}
+
+/// Check if a user has a specific permission for a path
+#[tauri::command]
+pub async fn check_permission(
+    drive_id: String,
+    node_id: Option<String>,
+    path: String,
+    required: PermissionLevel,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<bool, String> {
+    let id_arr = parse_drive_id(&drive_id)?;
+
+    // Get drive to find owner
+    let drives = state.drives.read().await;
+    let drive = drives
+        .get(&id_arr)
+        .ok_or_else(|| "Drive not found".to_string())?;
 
     let owner_hex = drive.owner.to_hex();
 
@@ -1255,6 +1959,52 @@ pub async fn check_permission(
     Ok(acl.check_permission(&check_node_id, &path, required_perm))
 }
 
+/// Get the highest permission a user has for a path
+///
+/// Resolves the user's base permission and the drive's path rules, the same
+/// way `check_permission` does. Defaults to the caller. Returns `None` if
+/// the user has no access to the path.
+#[tauri::command]
+pub async fn get_effective_permission(
+    drive_id: String,
+    node_id: Option<String>,
+    path: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<Option<PermissionLevel>, String> {
+    let id_arr = parse_drive_id(&drive_id)?;
+
+    // Get drive to find owner
+    let drives = state.drives.read().await;
+    let drive = drives
+        .get(&id_arr)
+        .ok_or_else(|| "Drive not found".to_string())?;
+
+    let owner_hex = drive.owner.to_hex();
+
+    // Get the node ID to resolve (default to caller)
+    let check_node_id = match node_id {
+        Some(id) => {
+            validate_node_id_hex(&id)?;
+            id
+        }
+        None => {
+            let caller = state
+                .identity_manager
+                .node_id()
+                .await
+                .ok_or_else(|| "Identity not initialized".to_string())?;
+            caller.to_hex()
+        }
+    };
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+
+    Ok(acl
+        .effective_permission(&check_node_id, &path)
+        .map(PermissionLevel::from))
+}
+
 /// Revoke an invite token
 ///
 /// # Security
@@ -1301,6 +2051,7 @@ pub async fn revoke_invite(
 
     // Revoke the token
     security.revoke_token(&drive_id, &token_id).await;
+    security.record_invite_revoked(&drive_id, &token_id).await;
 
     tracing::info!(
         drive_id = %drive_id,
@@ -1325,10 +2076,616 @@ pub async fn list_revoked_tokens(
     Ok(revoked.into_iter().collect())
 }
 
+/// Result of rotating a drive's doc sharing
+#[derive(Clone, Debug, Serialize)]
+pub struct DocSharingRotation {
+    pub drive_id: String,
+    /// Namespace that old tickets point at (no longer served)
+    pub old_namespace: String,
+    pub new_namespace: String,
+    /// Pending invites revoked because their embedded ticket is now dead
+    pub revoked_invites: Vec<String>,
+}
+
+/// Move a drive to a fresh doc namespace so leaked doc tickets stop working
+///
+/// A doc ticket grants replica access regardless of ACLs and cannot be
+/// revoked on its own. Rotation creates a new namespace with the current
+/// metadata and drops the old one on this node. Every peer, including
+/// legitimate members, must rejoin with a new invite; pending invites carry
+/// the old ticket and are revoked.
+///
+/// # Security
+/// - Owner only
+#[tauri::command]
+pub async fn rotate_doc_sharing(
+    drive_id: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<DocSharingRotation, String> {
+    validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let id_arr = parse_drive_id(&drive_id)?;
+
+    let owner = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.owner
+    };
+
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    if caller != owner {
+        tracing::warn!(
+            drive_id = %drive_id,
+            user = %caller.to_hex(),
+            "Access denied: only the owner can rotate doc sharing"
+        );
+        return Err(AppError::AccessDenied {
+            reason: "only the drive owner can rotate sharing".to_string(),
+        }
+        .to_string());
+    }
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let (old_namespace, new_namespace) = docs_manager
+        .rotate_doc(&DriveId(id_arr))
+        .await
+        .map_err(|e| AppError::SyncFailed(format!("Failed to rotate doc: {}", e)).to_string())?;
+
+    let now = Utc::now();
+    let mut revoked_invites = Vec::new();
+    for invite in security.get_issued_invites(&drive_id).await {
+        if invite.status_at(now) == InviteStatus::Issued {
+            security.revoke_token(&drive_id, &invite.token_id).await;
+            security.record_invite_revoked(&drive_id, &invite.token_id).await;
+            revoked_invites.push(invite.token_id);
+        }
+    }
+
+    tracing::info!(
+        drive_id = %drive_id,
+        old_namespace = %old_namespace,
+        new_namespace = %new_namespace,
+        revoked_invites = revoked_invites.len(),
+        "Rotated doc sharing; peers must rejoin"
+    );
+
+    Ok(DocSharingRotation {
+        drive_id,
+        old_namespace: old_namespace.to_string(),
+        new_namespace: new_namespace.to_string(),
+        revoked_invites,
+    })
+}
+
+/// Per-invite entry in the analytics report
+#[derive(Clone, Debug, Serialize)]
+pub struct InviteAnalyticsEntry {
+    pub token_id: String,
+    pub status: InviteStatus,
+    pub permission: PermissionLevel,
+    pub single_use: bool,
+    pub note: Option<String>,
+    pub issued_at: String,
+    pub expires_at: String,
+    /// When the first acceptance was seen
+    pub accepted_at: Option<String>,
+    pub revoked_at: Option<String>,
+    /// Node IDs of acceptors known to this node
+    pub acceptors: Vec<String>,
+}
+
+/// Invite usage analytics for a drive
+#[derive(Clone, Debug, Serialize)]
+pub struct InviteAnalytics {
+    pub drive_id: String,
+    pub issued: usize,
+    pub accepted: usize,
+    pub expired: usize,
+    pub revoked: usize,
+    /// Invites that are still open (not yet accepted, expired, or revoked)
+    pub pending: usize,
+    pub invites: Vec<InviteAnalyticsEntry>,
+}
+
+/// Get usage analytics for the invites issued for a drive
+///
+/// Reads from the issued-invite tracking store. Acceptances are recorded
+/// when the acceptor's announcement reaches this node, so acceptors that
+/// never came online after joining are not counted.
+///
+/// # Security
+/// - Requires Manage permission on the drive
+#[tauri::command]
+pub async fn get_invite_analytics(
+    drive_id: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<InviteAnalytics, String> {
+    validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let id_arr = parse_drive_id(&drive_id)?;
+
+    let owner_hex = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.owner.to_hex()
+    };
+
+    let caller = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller.to_hex(), "/", Permission::Manage) {
+        return Err("Insufficient permission to view invite analytics".to_string());
+    }
+
+    let now = Utc::now();
+    let mut analytics = InviteAnalytics {
+        drive_id: drive_id.clone(),
+        issued: 0,
+        accepted: 0,
+        expired: 0,
+        revoked: 0,
+        pending: 0,
+        invites: Vec::new(),
+    };
+
+    for invite in security.get_issued_invites(&drive_id).await {
+        let status = invite.status_at(now);
+        analytics.issued += 1;
+        match status {
+            InviteStatus::Issued => analytics.pending += 1,
+            InviteStatus::Accepted => analytics.accepted += 1,
+            InviteStatus::Expired => analytics.expired += 1,
+            InviteStatus::Revoked => analytics.revoked += 1,
+        }
+
+        analytics.invites.push(InviteAnalyticsEntry::new(invite, status));
+    }
+
+    // Newest first
+    analytics.invites.sort_by(|a, b| b.issued_at.cmp(&a.issued_at));
+
+    Ok(analytics)
+}
+
+/// An issued invite whose signature no longer checks out
+#[derive(Clone, Debug, Serialize)]
+pub struct FlaggedInvite {
+    pub drive_id: String,
+    pub token_id: String,
+    pub signature: InviteSignatureStatus,
+    /// Node ID the token names as its inviter, if it could be read
+    pub signed_by: Option<String>,
+    /// Whether the invite is still open, accepted, expired or revoked
+    pub status: InviteStatus,
+    pub issued_at: String,
+    pub expires_at: String,
+}
+
+/// Result of re-checking issued invites against the current identity
+#[derive(Clone, Debug, Serialize)]
+pub struct InviteRevalidationReport {
+    /// Node ID (hex) the invites were checked against
+    pub identity: String,
+    pub checked: usize,
+    pub valid: usize,
+    /// Invites recorded before signed tokens were kept
+    pub unverifiable: usize,
+    /// Invites signed by another identity or with a broken signature
+    pub flagged: Vec<FlaggedInvite>,
+    /// ISO 8601 time of the check
+    pub checked_at: String,
+}
+
+/// Re-check the signatures of issued invites against the current identity
+///
+/// After importing a restored identity or switching profiles, invites
+/// issued earlier may have been signed by a different key. Every tracked
+/// invite (or those of one drive) is verified again and the ones that fail
+/// are reported so the owner can revoke and reissue them. Invites recorded
+/// before signed tokens were kept can't be checked and are only counted.
+#[tauri::command]
+pub async fn revalidate_issued_invites(
+    drive_id: Option<String>,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<InviteRevalidationReport, String> {
+    let signing_key = state
+        .identity_manager
+        .signing_key()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let identity = signing_key.verifying_key();
+
+    let mut issued = security.all_issued_invites().await;
+    if let Some(ref drive_id) = drive_id {
+        validate_drive_id(drive_id).map_err(|e| e.to_string())?;
+        issued.retain(|id, _| id == drive_id);
+    }
+
+    let now = Utc::now();
+    let mut report = InviteRevalidationReport {
+        identity: hex::encode(identity.as_bytes()),
+        checked: 0,
+        valid: 0,
+        unverifiable: 0,
+        flagged: Vec::new(),
+        checked_at: now.to_rfc3339(),
+    };
+
+    for (drive_id, invites) in issued {
+        for invite in invites {
+            report.checked += 1;
+            let signature = invite.check_signature(&identity);
+            match signature {
+                InviteSignatureStatus::Valid => report.valid += 1,
+                InviteSignatureStatus::Unverifiable => report.unverifiable += 1,
+                InviteSignatureStatus::OtherIdentity | InviteSignatureStatus::Invalid => {
+                    report.flagged.push(FlaggedInvite {
+                        drive_id: drive_id.clone(),
+                        token_id: invite.token_id.clone(),
+                        signature,
+                        signed_by: invite.signed_by(),
+                        status: invite.status_at(now),
+                        issued_at: invite.issued_at.to_rfc3339(),
+                        expires_at: invite.expires_at.to_rfc3339(),
+                    });
+                }
+            }
+        }
+    }
+
+    // Newest first
+    report.flagged.sort_by(|a, b| b.issued_at.cmp(&a.issued_at));
+
+    tracing::info!(
+        checked = report.checked,
+        valid = report.valid,
+        unverifiable = report.unverifiable,
+        flagged = report.flagged.len(),
+        "Re-checked issued invite signatures"
+    );
+
+    Ok(report)
+}
+
+impl InviteAnalyticsEntry {
+    fn new(invite: IssuedInvite, status: InviteStatus) -> Self {
+        Self {
+            token_id: invite.token_id,
+            status,
+            permission: invite.permission.into(),
+            single_use: invite.single_use,
+            note: invite.note,
+            issued_at: invite.issued_at.to_rfc3339(),
+            expires_at: invite.expires_at.to_rfc3339(),
+            accepted_at: invite
+                .acceptances
+                .iter()
+                .map(|a| a.accepted_at)
+                .min()
+                .map(|t| t.to_rfc3339()),
+            revoked_at: invite.revoked_at.map(|t| t.to_rfc3339()),
+            acceptors: invite.acceptances.into_iter().map(|a| a.node_id).collect(),
+        }
+    }
+}
+
+/// A member's access in an access report
+#[derive(Clone, Debug, Serialize)]
+pub struct AccessReportMember {
+    pub node_id: String,
+    pub permission: PermissionLevel,
+    pub granted_by: String,
+    pub granted_at: String,
+    pub expires_at: Option<String>,
+    /// The grant has expired and no longer gives access
+    pub expired: bool,
+    pub is_owner: bool,
+    pub note: Option<String>,
+    /// Last presence heartbeat seen on this device, if the member is tracked
+    pub last_seen: Option<String>,
+}
+
+/// A path rule in an access report
+#[derive(Clone, Debug, Serialize)]
+pub struct AccessReportPathRule {
+    pub pattern: String,
+    /// Highest permission the rule allows (ignored for deny rules)
+    pub permission: PermissionLevel,
+    pub deny: bool,
+}
+
+/// Who can do what on a drive, for access reviews
+#[derive(Clone, Debug, Serialize)]
+pub struct AccessReport {
+    pub drive_id: String,
+    pub drive_name: String,
+    pub generated_at: String,
+    /// Node ID of whoever generated the report
+    pub generated_by: String,
+    /// Owner first, then by permission (highest first) and node ID
+    pub members: Vec<AccessReportMember>,
+    /// In evaluation order (last match wins)
+    pub path_rules: Vec<AccessReportPathRule>,
+    /// Invites that can still be accepted
+    pub outstanding_invites: Vec<InviteAnalyticsEntry>,
+    /// The same report as Markdown, when requested
+    pub markdown: Option<String>,
+}
+
+/// Generate an access review report for a drive
+///
+/// Lists every member with their permission, who granted it and when, and
+/// any expiry (expired grants are included and flagged), along with path
+/// rules and invites that are still open. With `include_markdown`, a
+/// readable Markdown rendering is included. Each report is recorded in the
+/// audit log.
+///
+/// # Security
+/// - Requires Manage permission on the drive
+#[tauri::command]
+pub async fn generate_access_report(
+    drive_id: String,
+    include_markdown: Option<bool>,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+    presence: State<'_, Arc<PresenceManager>>,
+    audit_logger: State<'_, Arc<AuditLogger>>,
+) -> Result<AccessReport, String> {
+    let id_arr = parse_drive_id(&drive_id)?;
+
+    let (drive_name, owner_hex, created_at) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        (drive.name.clone(), drive.owner.to_hex(), drive.created_at)
+    };
+
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, "/", Permission::Manage) {
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to generate an access report".to_string(),
+        }
+        .to_string());
+    }
+
+    let last_seen: HashMap<String, String> = presence
+        .get_online_users(&drive_id)
+        .await
+        .into_iter()
+        .map(|user| (user.node_id.to_hex(), user.last_seen.to_rfc3339()))
+        .collect();
+
+    let mut members = vec![AccessReportMember {
+        node_id: owner_hex.clone(),
+        permission: PermissionLevel::Admin,
+        granted_by: owner_hex.clone(),
+        granted_at: created_at.to_rfc3339(),
+        expires_at: None,
+        expired: false,
+        is_owner: true,
+        note: None,
+        last_seen: last_seen.get(&owner_hex).cloned(),
+    }];
+    let mut others: Vec<(Permission, AccessReportMember)> = acl
+        .users()
+        .into_iter()
+        .filter(|node_id| *node_id != owner_hex)
+        .filter_map(|node_id| {
+            let rule = acl.get_rule(node_id)?;
+            Some((
+                rule.permission,
+                AccessReportMember {
+                    node_id: node_id.to_string(),
+                    permission: rule.permission.into(),
+                    granted_by: rule.granted_by.clone(),
+                    granted_at: rule.granted_at.to_rfc3339(),
+                    expires_at: rule.expires_at.map(|t| t.to_rfc3339()),
+                    expired: rule.is_expired(),
+                    is_owner: false,
+                    note: rule.note.clone(),
+                    last_seen: last_seen.get(node_id).cloned(),
+                },
+            ))
+        })
+        .collect();
+    others.sort_by(|(pa, a), (pb, b)| pb.cmp(pa).then_with(|| a.node_id.cmp(&b.node_id)));
+    members.extend(others.into_iter().map(|(_, member)| member));
+
+    let path_rules = acl
+        .path_rules()
+        .iter()
+        .map(|rule| AccessReportPathRule {
+            pattern: rule.pattern.clone(),
+            permission: rule.permission.into(),
+            deny: rule.deny,
+        })
+        .collect();
+
+    let now = Utc::now();
+    let mut outstanding_invites: Vec<InviteAnalyticsEntry> = security
+        .get_issued_invites(&drive_id)
+        .await
+        .into_iter()
+        .filter(|invite| matches!(invite.status_at(now), InviteStatus::Issued))
+        .map(|invite| InviteAnalyticsEntry::new(invite, InviteStatus::Issued))
+        .collect();
+    outstanding_invites.sort_by(|a, b| b.issued_at.cmp(&a.issued_at));
+
+    let mut report = AccessReport {
+        drive_id: drive_id.clone(),
+        drive_name,
+        generated_at: now.to_rfc3339(),
+        generated_by: caller_hex.clone(),
+        members,
+        path_rules,
+        outstanding_invites,
+        markdown: None,
+    };
+    if include_markdown.unwrap_or(false) {
+        report.markdown = Some(render_access_report(&report));
+    }
+
+    if let Err(e) = audit_logger
+        .log(AuditEvent::AccessReportGenerated {
+            drive_id: drive_id.clone(),
+            generated_by: caller_hex,
+        })
+        .await
+    {
+        tracing::warn!("Failed to audit access report: {}", e);
+    }
+
+    tracing::info!(
+        drive_id = %drive_id,
+        members = report.members.len(),
+        "Generated access report"
+    );
+    Ok(report)
+}
+
+/// Render an access report as Markdown
+fn render_access_report(report: &AccessReport) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    let _ = writeln!(out, "# Access report: {}\n", report.drive_name);
+    let _ = writeln!(out, "- Drive: `{}`", report.drive_id);
+    let _ = writeln!(out, "- Generated: {}", report.generated_at);
+    let _ = writeln!(out, "- Generated by: `{}`\n", report.generated_by);
+
+    let _ = writeln!(out, "## Members\n");
+    let _ = writeln!(
+        out,
+        "| Member | Permission | Granted by | Granted at | Expires | Last seen |"
+    );
+    let _ = writeln!(out, "|---|---|---|---|---|---|");
+    for member in &report.members {
+        let permission = Permission::from(member.permission.clone()).display_name();
+        let role = if member.is_owner {
+            format!("{} (owner)", permission)
+        } else if member.expired {
+            format!("{} (expired)", permission)
+        } else {
+            permission.to_string()
+        };
+        let _ = writeln!(
+            out,
+            "| `{}` | {} | `{}` | {} | {} | {} |",
+            member.node_id,
+            role,
+            member.granted_by,
+            member.granted_at,
+            member.expires_at.as_deref().unwrap_or("never"),
+            member.last_seen.as_deref().unwrap_or("-"),
+        );
+    }
+
+    if !report.path_rules.is_empty() {
+        let _ = writeln!(out, "\n## Path rules\n");
+        for rule in &report.path_rules {
+            if rule.deny {
+                let _ = writeln!(out, "- `{}`: deny", rule.pattern);
+            } else {
+                let permission = Permission::from(rule.permission.clone()).display_name();
+                let _ = writeln!(out, "- `{}`: at most {}", rule.pattern, permission);
+            }
+        }
+    }
+
+    let _ = writeln!(out, "\n## Outstanding invites\n");
+    if report.outstanding_invites.is_empty() {
+        let _ = writeln!(out, "None");
+    }
+    for invite in &report.outstanding_invites {
+        let permission = Permission::from(invite.permission.clone()).display_name();
+        let _ = writeln!(
+            out,
+            "- `{}`: {}, {}, expires {}{}",
+            invite.token_id,
+            permission,
+            if invite.single_use { "single use" } else { "multi use" },
+            invite.expires_at,
+            invite
+                .note
+                .as_deref()
+                .map(|note| format!(" ({})", note))
+                .unwrap_or_default(),
+        );
+    }
+
+    out
+}
+
 // ============================================================================
 // Helper functions
 // ============================================================================
 
+/// Check that an invite's signer can still grant access to the drive
+///
+/// Only possible when the drive is known locally (its owner and ACL are
+/// here); otherwise the check is skipped. Returns why the invite should be
+/// rejected if the inviter is no longer the owner and lacks Manage.
+async fn check_inviter_authority(
+    state: &AppState,
+    security: &SecurityStore,
+    token: &InviteToken,
+) -> Option<String> {
+    let id_arr = parse_drive_id(&token.payload.drive_id).ok()?;
+    let owner_hex = state.drives.read().await.get(&id_arr)?.owner.to_hex();
+
+    let inviter = &token.payload.inviter;
+    if *inviter == owner_hex {
+        return None;
+    }
+
+    let acl = security
+        .get_or_create_acl(&token.payload.drive_id, &owner_hex)
+        .await;
+    if acl.check_permission(inviter, "/", Permission::Manage) {
+        return None;
+    }
+
+    tracing::warn!(
+        drive_id = %token.payload.drive_id,
+        inviter = %inviter,
+        "Invite signed by a member who can no longer invite"
+    );
+    Some("The inviter no longer has permission to invite members to this drive".to_string())
+}
+
 /// Helper to parse and validate drive ID
 fn parse_drive_id(drive_id: &str) -> Result<[u8; 32], String> {
     let id_bytes = hex::decode(drive_id).map_err(|_| "Invalid drive ID format".to_string())?;
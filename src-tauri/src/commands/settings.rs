@@ -0,0 +1,863 @@
+//! App settings commands
+//!
+//! Local, per-device preferences that are not tied to a single drive.
+
+use crate::core::{
+    AppError, MaintenanceScheduler, MaintenanceStatus, MaintenanceWindow, PowerMonitor,
+    PowerStatus, PresenceManager, WatchConfig, WatchMode, DEFAULT_PRESENCE_TIMEOUT_SECS,
+    MAINTENANCE_WINDOW_SETTING, MAX_PRESENCE_TIMEOUT_SECS, MIN_PRESENCE_TIMEOUT_SECS,
+    PRESENCE_TIMEOUT_SETTING, SYNC_ONLY_ON_AC_SETTING, WATCH_CONFIG_SETTING,
+};
+use crate::crypto::passphrase::validate_passphrase;
+use crate::crypto::{EncryptionManager, KeyCachePolicy, MAX_KEY_IDLE_TIMEOUT_MINS};
+use crate::instance::{InstanceConfig, InstanceInfo};
+use crate::network::{
+    EndpointConfig, TransferLimits, BANDWIDTH_LIMIT_SETTING, DEFAULT_CHUNK_SIZE,
+    DOWNLOAD_TEMP_DIR_SETTING, ENDPOINT_CONFIG_SETTING, MAX_CHUNK_SIZE, MIN_CHUNK_SIZE,
+    TRANSFER_CHUNK_SIZE_SETTING, TRANSFER_LIMITS_SETTING,
+};
+use crate::state::AppState;
+use crate::storage::TableCheck;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::State;
+
+/// Where partial downloads are written before being moved into a drive
+#[derive(Clone, Debug, Serialize)]
+pub struct DownloadTempDir {
+    pub path: String,
+    /// True if no custom directory is configured
+    pub is_default: bool,
+}
+
+/// Bytes read from the blob store per chunk when exporting downloads
+#[derive(Clone, Debug, Serialize)]
+pub struct TransferChunkSize {
+    pub chunk_size: usize,
+    /// True if no custom size is configured
+    pub is_default: bool,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl TransferChunkSize {
+    fn new(chunk_size: usize) -> Self {
+        Self {
+            chunk_size,
+            is_default: chunk_size == DEFAULT_CHUNK_SIZE,
+            min: MIN_CHUNK_SIZE,
+            max: MAX_CHUNK_SIZE,
+        }
+    }
+}
+
+/// How long a user can go without a heartbeat before being marked offline
+#[derive(Clone, Debug, Serialize)]
+pub struct PresenceTimeout {
+    pub timeout_secs: u64,
+    /// True if no custom timeout is configured
+    pub is_default: bool,
+    pub min: u64,
+    pub max: u64,
+}
+
+impl PresenceTimeout {
+    fn new(timeout_secs: u64) -> Self {
+        Self {
+            timeout_secs,
+            is_default: timeout_secs == DEFAULT_PRESENCE_TIMEOUT_SECS,
+            min: MIN_PRESENCE_TIMEOUT_SECS,
+            max: MAX_PRESENCE_TIMEOUT_SECS,
+        }
+    }
+}
+
+/// How this instance was launched and whether single-instance mode is on
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceSettings {
+    /// Data directory in use
+    pub data_dir: String,
+    /// True if started with `--data-dir` (a separate profile)
+    pub data_dir_overridden: bool,
+    /// Whether launching again focuses the running instance
+    pub single_instance: bool,
+}
+
+/// Result of an on-demand database integrity check
+#[derive(Clone, Debug, Serialize)]
+pub struct DatabaseIntegrityReport {
+    /// True if every table could be read in full
+    pub healthy: bool,
+    pub tables: Vec<TableCheck>,
+    /// Total entries read across all tables
+    pub entries_checked: u64,
+    /// Whether the database is also verified at startup
+    pub startup_check_enabled: bool,
+    /// Last verified copy, used for recovery at startup
+    pub backup_path: Option<String>,
+    /// ISO 8601 time of the check
+    pub checked_at: String,
+}
+
+/// Get the policy for clearing cached encryption keys on window blur
+#[tauri::command]
+pub async fn get_key_cache_policy(
+    encryption: State<'_, Arc<EncryptionManager>>,
+) -> Result<KeyCachePolicy, String> {
+    Ok(encryption.key_cache_policy().await)
+}
+
+/// Set the policy for clearing cached encryption keys on window blur
+///
+/// The default clears keys immediately. Users on a trusted machine can
+/// instead clear keys after an idle timeout, or not on blur at all.
+#[tauri::command]
+pub async fn set_key_cache_policy(
+    clear_keys_on_blur: bool,
+    idle_timeout_mins: Option<u64>,
+    encryption: State<'_, Arc<EncryptionManager>>,
+) -> Result<KeyCachePolicy, String> {
+    if let Some(mins) = idle_timeout_mins {
+        if mins == 0 || mins > MAX_KEY_IDLE_TIMEOUT_MINS {
+            return Err(AppError::ValidationFailed {
+                field: "idle_timeout_mins".to_string(),
+                reason: format!("must be between 1 and {}", MAX_KEY_IDLE_TIMEOUT_MINS),
+            }
+            .to_string());
+        }
+    }
+
+    let policy = KeyCachePolicy {
+        clear_keys_on_blur,
+        idle_timeout_mins,
+    };
+
+    encryption
+        .set_key_cache_policy(policy)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    if !clear_keys_on_blur {
+        tracing::warn!(
+            idle_timeout_mins = ?idle_timeout_mins,
+            "Encryption keys will no longer be cleared immediately on window blur"
+        );
+    }
+
+    Ok(policy)
+}
+
+/// Whether drive keys are passphrase protected and currently usable
+#[derive(Clone, Debug, Serialize)]
+pub struct KeyLockStatus {
+    pub protected: bool,
+    pub unlocked: bool,
+}
+
+async fn key_lock_status(encryption: &EncryptionManager) -> KeyLockStatus {
+    KeyLockStatus {
+        protected: encryption.is_protected().await,
+        unlocked: encryption.is_unlocked().await,
+    }
+}
+
+/// Get whether drive keys are passphrase protected and unlocked
+#[tauri::command]
+pub async fn get_key_lock_status(
+    encryption: State<'_, Arc<EncryptionManager>>,
+) -> Result<KeyLockStatus, String> {
+    Ok(key_lock_status(&encryption).await)
+}
+
+/// Protect drive keys with a passphrase
+///
+/// Existing keys are sealed under a master key derived from the
+/// passphrase. Keys stay unlocked until `lock_keys` or the next start.
+#[tauri::command]
+pub async fn set_key_passphrase(
+    passphrase: String,
+    encryption: State<'_, Arc<EncryptionManager>>,
+) -> Result<KeyLockStatus, String> {
+    validate_passphrase(&passphrase).map_err(|e| {
+        AppError::ValidationFailed {
+            field: "passphrase".to_string(),
+            reason: e.to_string(),
+        }
+        .to_string()
+    })?;
+
+    encryption
+        .set_passphrase(&passphrase)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(key_lock_status(&encryption).await)
+}
+
+/// Unlock passphrase-protected drive keys
+#[tauri::command]
+pub async fn unlock_keys(
+    passphrase: String,
+    encryption: State<'_, Arc<EncryptionManager>>,
+) -> Result<KeyLockStatus, String> {
+    encryption
+        .unlock_with_passphrase(&passphrase)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(key_lock_status(&encryption).await)
+}
+
+/// Lock drive keys until the passphrase is entered again
+#[tauri::command]
+pub async fn lock_keys(
+    encryption: State<'_, Arc<EncryptionManager>>,
+) -> Result<KeyLockStatus, String> {
+    encryption.lock().await;
+    Ok(key_lock_status(&encryption).await)
+}
+
+/// Get the directory partial downloads are written to
+#[tauri::command]
+pub async fn get_download_temp_dir(state: State<'_, AppState>) -> Result<DownloadTempDir, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    let path = file_transfer.temp_dir().await;
+    Ok(DownloadTempDir {
+        is_default: path == file_transfer.default_temp_dir(),
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+/// Set the directory partial downloads are written to
+///
+/// Pass no path to restore the default. The directory may be on a different
+/// filesystem than the drives; finished downloads are then copied into place.
+/// The file watcher ignores this directory so partial files never sync.
+#[tauri::command]
+pub async fn set_download_temp_dir(
+    path: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<DownloadTempDir, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    let dir = match path.as_deref() {
+        Some(p) => {
+            let dir = PathBuf::from(p);
+            if !dir.is_absolute() {
+                return Err(AppError::InvalidPath {
+                    path: p.to_string(),
+                    reason: "temp directory must be an absolute path".to_string(),
+                }
+                .to_string());
+            }
+            Some(dir)
+        }
+        None => None,
+    };
+
+    let applied = file_transfer
+        .set_temp_dir(dir)
+        .await
+        .map_err(|e| format!("Failed to set download temp directory: {}", e))?;
+
+    match path.as_deref() {
+        Some(p) => state.db.save_setting(DOWNLOAD_TEMP_DIR_SETTING, p.as_bytes()),
+        None => state.db.delete_setting(DOWNLOAD_TEMP_DIR_SETTING).map(|_| ()),
+    }
+    .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    if let Some(watcher) = state.file_watcher.as_ref() {
+        watcher.set_ignored_dirs(vec![applied.clone()]);
+    }
+
+    Ok(DownloadTempDir {
+        is_default: applied == file_transfer.default_temp_dir(),
+        path: applied.to_string_lossy().to_string(),
+    })
+}
+
+/// Get the transfer chunk size
+#[tauri::command]
+pub async fn get_transfer_chunk_size(
+    state: State<'_, AppState>,
+) -> Result<TransferChunkSize, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    Ok(TransferChunkSize::new(file_transfer.chunk_size()))
+}
+
+/// Set the transfer chunk size
+///
+/// Pass no size to restore the default of 64KB. Larger chunks mean fewer
+/// reads per file at the cost of more memory per transfer.
+#[tauri::command]
+pub async fn set_transfer_chunk_size(
+    chunk_size: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<TransferChunkSize, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    let applied = file_transfer.set_chunk_size(chunk_size).map_err(|e| {
+        AppError::ValidationFailed {
+            field: "chunk_size".to_string(),
+            reason: e.to_string(),
+        }
+        .to_string()
+    })?;
+
+    match chunk_size {
+        Some(size) => serde_json::to_vec(&size)
+            .map_err(|e| AppError::SerializationError(e.to_string()).to_string())
+            .and_then(|bytes| {
+                state
+                    .db
+                    .save_setting(TRANSFER_CHUNK_SIZE_SETTING, &bytes)
+                    .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())
+            }),
+        None => state
+            .db
+            .delete_setting(TRANSFER_CHUNK_SIZE_SETTING)
+            .map(|_| ())
+            .map_err(|e| AppError::DatabaseError(e.to_string()).to_string()),
+    }?;
+
+    Ok(TransferChunkSize::new(applied))
+}
+
+/// Get the bandwidth limit in bytes/sec (none if unlimited)
+#[tauri::command]
+pub async fn get_bandwidth_limit(state: State<'_, AppState>) -> Result<Option<u64>, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    Ok(file_transfer.bandwidth_limit())
+}
+
+/// Limit how fast transfers read from and write to the blob store
+///
+/// The limit is shared by all transfers and applies to ones already
+/// running. Pass no limit or zero for unlimited.
+#[tauri::command]
+pub async fn set_bandwidth_limit(
+    bytes_per_sec: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<Option<u64>, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    file_transfer.set_bandwidth_limit(bytes_per_sec);
+    let applied = file_transfer.bandwidth_limit();
+
+    match applied {
+        Some(limit) => serde_json::to_vec(&limit)
+            .map_err(|e| AppError::SerializationError(e.to_string()).to_string())
+            .and_then(|bytes| {
+                state
+                    .db
+                    .save_setting(BANDWIDTH_LIMIT_SETTING, &bytes)
+                    .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())
+            }),
+        None => state
+            .db
+            .delete_setting(BANDWIDTH_LIMIT_SETTING)
+            .map(|_| ())
+            .map_err(|e| AppError::DatabaseError(e.to_string()).to_string()),
+    }?;
+
+    Ok(applied)
+}
+
+/// Get how many transfers may run at once
+#[tauri::command]
+pub async fn get_transfer_limits(state: State<'_, AppState>) -> Result<TransferLimits, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    Ok(file_transfer.limits())
+}
+
+/// Set how many transfers may run at once
+///
+/// `max_concurrent` (1-64) applies across all drives; `max_per_drive`, if
+/// given, additionally limits each drive and is capped at `max_concurrent`.
+/// Transfers beyond the limits wait with status `Queued`.
+#[tauri::command]
+pub async fn set_max_concurrent_transfers(
+    max_concurrent: usize,
+    max_per_drive: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<TransferLimits, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    let limits = TransferLimits {
+        max_concurrent,
+        max_per_drive,
+    }
+    .normalized();
+    let bytes = serde_json::to_vec(&limits)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(TRANSFER_LIMITS_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    Ok(file_transfer.set_limits(limits))
+}
+
+/// Get the presence idle timeout
+#[tauri::command]
+pub async fn get_presence_timeout(
+    presence_manager: State<'_, Arc<PresenceManager>>,
+) -> Result<PresenceTimeout, String> {
+    Ok(PresenceTimeout::new(presence_manager.timeout_secs()))
+}
+
+/// Set the presence idle timeout
+///
+/// Users with no heartbeat for this long are marked offline, covering peers
+/// that vanish without leaving. Pass no value to restore the default of 90s.
+#[tauri::command]
+pub async fn set_presence_timeout(
+    timeout_secs: Option<u64>,
+    state: State<'_, AppState>,
+    presence_manager: State<'_, Arc<PresenceManager>>,
+) -> Result<PresenceTimeout, String> {
+    let secs = timeout_secs.unwrap_or(DEFAULT_PRESENCE_TIMEOUT_SECS);
+    if !presence_manager.set_timeout_secs(secs) {
+        return Err(AppError::ValidationFailed {
+            field: "timeout_secs".to_string(),
+            reason: format!(
+                "must be between {} and {} seconds",
+                MIN_PRESENCE_TIMEOUT_SECS, MAX_PRESENCE_TIMEOUT_SECS
+            ),
+        }
+        .to_string());
+    }
+
+    match timeout_secs {
+        Some(secs) => serde_json::to_vec(&secs)
+            .map_err(|e| AppError::SerializationError(e.to_string()).to_string())
+            .and_then(|bytes| {
+                state
+                    .db
+                    .save_setting(PRESENCE_TIMEOUT_SETTING, &bytes)
+                    .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())
+            }),
+        None => state
+            .db
+            .delete_setting(PRESENCE_TIMEOUT_SETTING)
+            .map(|_| ())
+            .map_err(|e| AppError::DatabaseError(e.to_string()).to_string()),
+    }?;
+
+    tracing::info!(timeout_secs = secs, "Updated presence timeout");
+    Ok(PresenceTimeout::new(secs))
+}
+
+/// Get the data directory in use and the single-instance setting
+#[tauri::command]
+pub async fn get_instance_settings(
+    instance: State<'_, InstanceInfo>,
+) -> Result<InstanceSettings, String> {
+    let config = instance
+        .default_data_dir
+        .as_deref()
+        .map(InstanceConfig::load)
+        .unwrap_or_default();
+
+    Ok(InstanceSettings {
+        data_dir: instance.data_dir.to_string_lossy().to_string(),
+        data_dir_overridden: instance.data_dir_overridden,
+        single_instance: config.single_instance,
+    })
+}
+
+/// Enable or disable single-instance enforcement
+///
+/// Takes effect on the next launch. When disabled, launching the app again
+/// starts another instance instead of focusing this one; instances sharing a
+/// data directory still cannot open the same database, so each extra
+/// instance should be given its own `--data-dir`.
+#[tauri::command]
+pub async fn set_single_instance(
+    enabled: bool,
+    instance: State<'_, InstanceInfo>,
+) -> Result<InstanceSettings, String> {
+    let default_dir = instance.default_data_dir.as_deref().ok_or_else(|| {
+        AppError::ValidationError("default data directory is unavailable".to_string()).to_string()
+    })?;
+
+    InstanceConfig {
+        single_instance: enabled,
+    }
+    .save(default_dir)
+    .map_err(|e| format!("Failed to save instance settings: {}", e))?;
+
+    tracing::info!(enabled = enabled, "Updated single-instance setting");
+
+    Ok(InstanceSettings {
+        data_dir: instance.data_dir.to_string_lossy().to_string(),
+        data_dir_overridden: instance.data_dir_overridden,
+        single_instance: enabled,
+    })
+}
+
+/// Check that every entry in the database can still be read
+#[tauri::command]
+pub async fn check_database_integrity(
+    state: State<'_, AppState>,
+) -> Result<DatabaseIntegrityReport, String> {
+    let db = state.db.clone();
+    let tables = tokio::task::spawn_blocking(move || db.verify_tables())
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    let healthy = tables.iter().all(|t| t.error.is_none());
+    if !healthy {
+        for table in tables.iter().filter(|t| t.error.is_some()) {
+            tracing::error!(
+                table = %table.table,
+                error = ?table.error,
+                "Database integrity check failed"
+            );
+        }
+    }
+
+    Ok(DatabaseIntegrityReport {
+        healthy,
+        entries_checked: tables.iter().map(|t| t.entries).sum(),
+        tables,
+        startup_check_enabled: state.db.startup_integrity_check_enabled(),
+        backup_path: state
+            .db
+            .backup_path()
+            .map(|p| p.to_string_lossy().to_string()),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Enable or disable verifying the database at startup
+///
+/// The check reads the whole database file, which can slow startup on slow
+/// disks. With it disabled no backup is taken either, so a corrupt database
+/// cannot be restored automatically.
+#[tauri::command]
+pub async fn set_startup_integrity_check(
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    state
+        .db
+        .set_startup_integrity_check(enabled)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    tracing::info!(enabled = enabled, "Updated startup database integrity check");
+    Ok(enabled)
+}
+
+/// Get the power source and whether sync is paused because of it
+#[tauri::command]
+pub async fn get_power_status(
+    power_monitor: State<'_, Arc<PowerMonitor>>,
+) -> Result<PowerStatus, String> {
+    Ok(power_monitor.check().await)
+}
+
+/// Pause network sync and transfers while on battery power
+///
+/// Off by default. Where the power source cannot be determined, sync keeps
+/// running as if on AC.
+#[tauri::command]
+pub async fn set_sync_only_on_ac(
+    enabled: bool,
+    state: State<'_, AppState>,
+    power_monitor: State<'_, Arc<PowerMonitor>>,
+) -> Result<PowerStatus, String> {
+    let bytes = serde_json::to_vec(&enabled)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(SYNC_ONLY_ON_AC_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    tracing::info!(enabled = enabled, "Updated sync_only_on_ac setting");
+    Ok(power_monitor.set_enabled(enabled).await)
+}
+
+/// Get the maintenance window and whether it is open now
+#[tauri::command]
+pub async fn get_maintenance_window(
+    maintenance: State<'_, Arc<MaintenanceScheduler>>,
+) -> Result<MaintenanceStatus, String> {
+    Ok(maintenance.status())
+}
+
+/// Restrict heavy background housekeeping to daily time ranges
+///
+/// Ranges are in local time and may wrap past midnight. Outside them only
+/// essential work (sync, transfers, lock and presence upkeep) runs; pruning
+/// is deferred until the window opens. No ranges removes the restriction,
+/// which is the default.
+#[tauri::command]
+pub async fn set_maintenance_window(
+    window: MaintenanceWindow,
+    state: State<'_, AppState>,
+    maintenance: State<'_, Arc<MaintenanceScheduler>>,
+) -> Result<MaintenanceStatus, String> {
+    window.validate().map_err(|reason| {
+        AppError::ValidationFailed {
+            field: "window".to_string(),
+            reason,
+        }
+        .to_string()
+    })?;
+
+    let bytes = serde_json::to_vec(&window)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(MAINTENANCE_WINDOW_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    tracing::info!(ranges = window.ranges.len(), "Updated maintenance window");
+    maintenance.set_window(window);
+    Ok(maintenance.status())
+}
+
+/// File watching configuration and which drives are being polled
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchModeStatus {
+    #[serde(flatten)]
+    pub config: WatchConfig,
+    /// Drives watched by polling (configured, or chosen by auto mode)
+    pub polling_drives: Vec<String>,
+}
+
+/// Get the file watching mode
+#[tauri::command]
+pub async fn get_watch_mode(state: State<'_, AppState>) -> Result<WatchModeStatus, String> {
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    Ok(WatchModeStatus {
+        config: file_watcher.config(),
+        polling_drives: file_watcher
+            .polling_drives()
+            .await
+            .iter()
+            .map(|id| id.to_hex())
+            .collect(),
+    })
+}
+
+/// Set how drives are watched for local changes
+///
+/// `Polling` re-scans each drive every `poll_interval_secs` (1-3600, default
+/// 10), which works on network filesystems and Docker/WSL mounts where
+/// native notifications get lost, at the cost of latency. `Auto` picks
+/// polling for such filesystems. Active watches restart with the new mode.
+#[tauri::command]
+pub async fn set_watch_mode(
+    mode: WatchMode,
+    poll_interval_secs: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<WatchModeStatus, String> {
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let current = file_watcher.config();
+    let config = WatchConfig {
+        mode,
+        poll_interval_secs: poll_interval_secs.unwrap_or(current.poll_interval_secs),
+        ..current
+    };
+    save_watch_config(config, &state).await
+}
+
+/// Set when native watches switch to batching under high churn
+///
+/// Above `churn_threshold` raw events per second (10-100000, default 500) a
+/// drive's changes are collected and emitted once per path every
+/// `churn_batch_ms` (250-60000, default 2000) until the rate falls below half
+/// the threshold. Each switch is sent as a `WatcherChurn` drive event, and
+/// `list_watch_status` reports the current rate. Omitted values are kept.
+#[tauri::command]
+pub async fn set_watch_throttle(
+    churn_threshold: Option<u32>,
+    churn_batch_ms: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<WatchModeStatus, String> {
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let current = file_watcher.config();
+    let config = WatchConfig {
+        churn_threshold: churn_threshold.unwrap_or(current.churn_threshold),
+        churn_batch_ms: churn_batch_ms.unwrap_or(current.churn_batch_ms),
+        ..current
+    };
+    save_watch_config(config, &state).await
+}
+
+/// Set how long a file must be quiet before its change is synced
+///
+/// Native watches hold each path's events back until `debounce_ms`
+/// (0-10000, default 300) have passed since the last one, so a file written
+/// in many small pieces is synced once. 0 syncs every change as it arrives.
+/// Active watches pick up the new window without restarting.
+#[tauri::command]
+pub async fn set_watch_debounce(
+    debounce_ms: u64,
+    state: State<'_, AppState>,
+) -> Result<WatchModeStatus, String> {
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    file_watcher.set_debounce_ms(debounce_ms);
+    let config = file_watcher.config();
+    let bytes = serde_json::to_vec(&config)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(WATCH_CONFIG_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+    tracing::info!(
+        debounce_ms = config.debounce_ms,
+        "Updated watch debounce window"
+    );
+
+    Ok(WatchModeStatus {
+        config,
+        polling_drives: file_watcher
+            .polling_drives()
+            .await
+            .iter()
+            .map(|id| id.to_hex())
+            .collect(),
+    })
+}
+
+/// Persist a watch configuration and restart active watches with it
+async fn save_watch_config(
+    config: WatchConfig,
+    state: &AppState,
+) -> Result<WatchModeStatus, String> {
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let config = config.normalized();
+
+    let bytes = serde_json::to_vec(&config)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(WATCH_CONFIG_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    let config = file_watcher.apply_config(config).await;
+    tracing::info!(config = ?config, "Updated watch configuration");
+
+    Ok(WatchModeStatus {
+        config,
+        polling_drives: file_watcher
+            .polling_drives()
+            .await
+            .iter()
+            .map(|id| id.to_hex())
+            .collect(),
+    })
+}
+
+/// Saved endpoint bind configuration and the one currently in effect
+#[derive(Clone, Debug, Serialize)]
+pub struct EndpointConfigStatus {
+    /// Configuration applied on next start
+    pub config: EndpointConfig,
+    /// Configuration the running endpoint was started with
+    pub active: EndpointConfig,
+    /// True if `config` differs from `active` until the app restarts
+    pub restart_required: bool,
+    /// Why the active configuration could not be bound, if it couldn't
+    pub bind_error: Option<String>,
+}
+
+async fn endpoint_config_status(config: EndpointConfig, state: &AppState) -> EndpointConfigStatus {
+    let active = state.endpoint.config().clone();
+    EndpointConfigStatus {
+        restart_required: config != active,
+        bind_error: state.endpoint.get_connection_info().await.bind_error,
+        config,
+        active,
+    }
+}
+
+/// Get the P2P endpoint bind address and preferred ports
+#[tauri::command]
+pub async fn get_endpoint_config(
+    state: State<'_, AppState>,
+) -> Result<EndpointConfigStatus, String> {
+    let config = state
+        .db
+        .get_setting(ENDPOINT_CONFIG_SETTING)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?
+        .and_then(|bytes| serde_json::from_slice::<EndpointConfig>(&bytes).ok())
+        .unwrap_or_else(|| state.endpoint.config().clone());
+
+    Ok(endpoint_config_status(config, &state).await)
+}
+
+/// Set the P2P endpoint bind address and preferred ports
+///
+/// Ports are tried in order at startup; an empty list lets the OS pick. If
+/// nothing binds, the endpoint falls back to automatic binding and reports
+/// the error. Takes effect after restart.
+#[tauri::command]
+pub async fn set_endpoint_config(
+    config: EndpointConfig,
+    state: State<'_, AppState>,
+) -> Result<EndpointConfigStatus, String> {
+    config.validate().map_err(|e| e.to_string())?;
+
+    let bytes = serde_json::to_vec(&config)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(ENDPOINT_CONFIG_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    tracing::info!(config = ?config, "Updated endpoint config (applies after restart)");
+    Ok(endpoint_config_status(config, &state).await)
+}
@@ -0,0 +1,370 @@
+//! Single-file share link commands
+//!
+//! A share link grants read access to one file's content until it expires,
+//! without membership in the drive. Links are signed by the issuer and name
+//! the content by hash, so later edits to the file are not shared. Only the
+//! issuer serves a link's content (see `network::share_link`), so it decides
+//! whether the link is still valid.
+
+use crate::commands::security::SecurityStore;
+use crate::core::{file, validate_drive_id, validate_path, AppError, SharedDrive};
+use crate::crypto::{FileShareToken, Permission};
+use crate::network::share_link;
+use crate::state::AppState;
+use chrono::Duration as ChronoDuration;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tauri::State;
+use tokio::sync::RwLock;
+
+/// Longest validity accepted for a share link (30 days)
+const MAX_SHARE_LINK_HOURS: u32 = 30 * 24;
+
+/// A created share link
+#[derive(Clone, Debug, Serialize)]
+pub struct FileShareLinkInfo {
+    pub token: String,
+    /// Token ID, used to revoke the link
+    pub token_id: String,
+    pub drive_id: String,
+    pub path: String,
+    pub hash: String,
+    pub size: u64,
+    pub expires_at: String,
+}
+
+/// Result of fetching a shared file
+#[derive(Clone, Debug, Serialize)]
+pub struct SharedFileFetch {
+    pub destination: String,
+    /// File name the link was created for
+    pub path: String,
+    pub hash: String,
+    pub size: u64,
+    /// Issuer's NodeId (hex)
+    pub issuer: String,
+}
+
+/// Check that a link signed by this node may still be served
+///
+/// The link must have been issued here and not revoked, its drive must
+/// still be here, and this node must still hold Read on the path. Returns
+/// the reason otherwise.
+pub async fn check_issued_share_link(
+    token: &FileShareToken,
+    drives: &RwLock<HashMap<[u8; 32], SharedDrive>>,
+    security: &SecurityStore,
+) -> Result<(), String> {
+    let drive_id = &token.payload.drive_id;
+    if !security
+        .is_share_link_issued(drive_id, token.token_id())
+        .await
+    {
+        return Err("share link was not issued by this device".to_string());
+    }
+    if security
+        .is_share_link_revoked(drive_id, token.token_id())
+        .await
+    {
+        return Err("share link has been revoked".to_string());
+    }
+
+    let id_arr = validate_drive_id(drive_id).map_err(|_| "unknown drive".to_string())?;
+    let owner_hex = drives
+        .read()
+        .await
+        .get(&id_arr)
+        .map(|drive| drive.owner.to_hex())
+        .ok_or_else(|| "unknown drive".to_string())?;
+    let acl = security.get_or_create_acl(drive_id, &owner_hex).await;
+    if !acl.check_permission(&token.payload.issuer, &token.payload.path, Permission::Read) {
+        return Err("share link issuer has no access to the file".to_string());
+    }
+    Ok(())
+}
+
+/// Create a signed, time-limited link to one file
+///
+/// The caller needs Read on the file. The file's current content is added
+/// to the blob store if it isn't stored yet, as a whole blob or as the
+/// chunks of its last chunked upload. `validity_hours` defaults to 24
+/// and is capped at 30 days.
+#[tauri::command]
+pub async fn create_file_share_link(
+    drive_id: String,
+    path: String,
+    validity_hours: Option<u32>,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<FileShareLinkInfo, String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+    let drive_id_obj = crate::core::DriveId(id_arr);
+
+    let (owner_hex, local_path) = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        (drive.owner.to_hex(), drive.local_path.clone())
+    };
+
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !acl.check_permission(&caller_hex, &path, Permission::Read) {
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to share file".to_string(),
+        }
+        .to_string());
+    }
+
+    let file_path = validate_path(&local_path, &path).map_err(|e| e.to_string())?;
+    if !file_path.is_file() {
+        return Err(AppError::NotAFile { path }.to_string());
+    }
+    let relative_path = file_path
+        .strip_prefix(&local_path)
+        .map_err(|_| AppError::PathOutsideDrive { path: path.clone() }.to_string())?
+        .to_path_buf();
+
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+    let content_hash = {
+        let path = file_path.clone();
+        tokio::task::spawn_blocking(move || file::hash_file(&path))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()).to_string())?
+            .map_err(|e| AppError::TransferFailed(e.to_string()).to_string())?
+    };
+
+    // Files uploaded in chunks are served from their manifest; anything
+    // else not stored yet is imported as one blob
+    let mut manifest = None;
+    if !file_transfer.has_blob(&content_hash).await {
+        if let Some(docs_manager) = state.docs_manager.as_ref() {
+            manifest = docs_manager
+                .current_manifest(
+                    &drive_id_obj,
+                    &file::path_key(&relative_path),
+                    &content_hash,
+                )
+                .await;
+        }
+    }
+    let hash = if file_transfer
+        .has_content(&content_hash, manifest.as_deref())
+        .await
+    {
+        content_hash
+            .parse::<iroh_blobs::Hash>()
+            .map_err(|e| AppError::InvalidHash(e.to_string()).to_string())?
+    } else {
+        manifest = None;
+        file_transfer
+            .upload_file(&drive_id_obj, &file_path, &relative_path)
+            .await
+            .map_err(|e| AppError::TransferFailed(e.to_string()).to_string())?
+    };
+    let size = tokio::fs::metadata(&file_path)
+        .await
+        .map_err(|e| AppError::TransferFailed(e.to_string()).to_string())?
+        .len();
+
+    let signing_key = state
+        .identity_manager
+        .signing_key()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let validity_hours = validity_hours.unwrap_or(24).clamp(1, MAX_SHARE_LINK_HOURS);
+    let token = FileShareToken::create(
+        &signing_key,
+        &drive_id,
+        &relative_path.to_string_lossy().replace('\\', "/"),
+        &hash.to_hex(),
+        size,
+        manifest.as_deref(),
+        ChronoDuration::hours(validity_hours as i64),
+    )
+    .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    let token_string = token
+        .to_string()
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+
+    security
+        .record_issued_share_link(&drive_id, token.token_id())
+        .await;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        path = %token.payload.path,
+        token_id = %token.token_id(),
+        validity_hours = validity_hours,
+        "Created file share link"
+    );
+
+    Ok(FileShareLinkInfo {
+        token: token_string,
+        token_id: token.payload.token_id.clone(),
+        drive_id,
+        path: token.payload.path.clone(),
+        hash: token.payload.hash.clone(),
+        size,
+        expires_at: token.payload.expires_at.to_rfc3339(),
+    })
+}
+
+/// Revoke a share link so it can no longer be fetched
+///
+/// Links issued by this node can always be revoked; any other link
+/// requires Manage on the drive. Since only the issuer serves a link, a
+/// revocation made on another device only stops that device from
+/// exporting the content itself. Revocations are kept apart from revoked
+/// invite tokens.
+#[tauri::command]
+pub async fn revoke_file_share_link(
+    drive_id: String,
+    token_id: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<(), String> {
+    let id_arr = validate_drive_id(&drive_id).map_err(|e| e.to_string())?;
+
+    let owner_hex = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(&id_arr).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.owner.to_hex()
+    };
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+
+    let issued_here = security.is_share_link_issued(&drive_id, &token_id).await;
+    let acl = security.get_or_create_acl(&drive_id, &owner_hex).await;
+    if !issued_here && !acl.check_permission(&caller_hex, "/", Permission::Manage) {
+        return Err(AppError::AccessDenied {
+            reason: "insufficient permission to revoke share links".to_string(),
+        }
+        .to_string());
+    }
+
+    security.revoke_share_link(&drive_id, &token_id).await;
+    tracing::info!(drive_id = %drive_id, token_id = %token_id, "Revoked file share link");
+    Ok(())
+}
+
+/// Validate a share link and save the shared file to `destination`
+///
+/// Checks the issuer's signature and expiry, then asks the issuer for the
+/// content, which it only hands out while the link is unrevoked and it can
+/// still read the file. The content is checked against the signed hash.
+/// Links issued by this device are checked and exported locally.
+/// `destination` must be an absolute path that doesn't exist yet.
+#[tauri::command]
+pub async fn fetch_shared_file(
+    token: String,
+    destination: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<SharedFileFetch, String> {
+    let token_string = token;
+    let token = FileShareToken::from_string(&token_string)
+        .map_err(|_| AppError::InvalidTokenFormat.to_string())?;
+    token
+        .verify_issuer_signature()
+        .map_err(|_| AppError::InvalidSignature.to_string())?;
+    if token.is_expired() {
+        return Err(AppError::TokenExpired.to_string());
+    }
+
+    let dest = PathBuf::from(&destination);
+    if !dest.is_absolute() {
+        return Err(AppError::InvalidPath {
+            path: destination,
+            reason: "destination must be absolute".to_string(),
+        }
+        .to_string());
+    }
+    if dest.exists() {
+        return Err(AppError::InvalidPath {
+            path: destination,
+            reason: "destination already exists".to_string(),
+        }
+        .to_string());
+    }
+
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+    if token.payload.issuer == caller_hex {
+        check_issued_share_link(&token, &state.drives, &security)
+            .await
+            .map_err(|reason| AppError::AccessDenied { reason }.to_string())?;
+        let hash = token
+            .payload
+            .hash
+            .parse::<iroh_blobs::Hash>()
+            .map_err(|e| AppError::InvalidHash(e.to_string()).to_string())?;
+        let manifest_hash = token
+            .payload
+            .manifest
+            .as_deref()
+            .map(str::parse::<iroh_blobs::Hash>)
+            .transpose()
+            .map_err(|e| AppError::InvalidHash(e.to_string()).to_string())?;
+        file_transfer
+            .export_content(hash, manifest_hash, &dest)
+            .await
+            .map_err(|e| AppError::TransferFailed(e.to_string()).to_string())?;
+    } else {
+        let endpoint = state
+            .endpoint
+            .get_endpoint()
+            .await
+            .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+        share_link::fetch_share_link(&endpoint, file_transfer, &token, &token_string, &dest)
+            .await
+            .map_err(|e| AppError::TransferFailed(e.to_string()).to_string())?;
+    }
+
+    tracing::info!(
+        token_id = %token.token_id(),
+        path = %token.payload.path,
+        destination = %destination,
+        "Fetched shared file"
+    );
+
+    Ok(SharedFileFetch {
+        destination,
+        path: token.payload.path,
+        hash: token.payload.hash,
+        size: token.payload.size,
+        issuer: token.payload.issuer,
+    })
+}
@@ -3,9 +3,18 @@
 //! These commands expose sync functionality to the frontend.
 //! All commands include proper input validation and error handling.
 
-use crate::core::{validate_drive_id, validate_path, AppError, DriveId};
-use crate::network::{SyncDiagnostics, SyncStatus};
+use crate::core::{
+    validate_drive_id, validate_path, AppError, DriveEventDto, DriveId, DriveSubscriptions,
+    EventFilter, EventSubscriptions, OperationRegistry, SyncFilters, WatchStatus, EVENT_TYPES,
+};
+use crate::network::{
+    latency, ChangeSummaryConfig, DriveMerkleRoot, DrivePeers, E2eLatencyReport, KeyProofTracker,
+    OrphanNamespace, PeerRetryConfig, PendingUploadDto, SyncDiagnostics, SyncStatus,
+    CHANGE_SUMMARY_SETTING, PEER_RETRY_SETTING,
+};
 use crate::state::AppState;
+use std::sync::Arc;
+use tauri::ipc::Channel;
 use tauri::State;
 
 /// Helper to parse drive ID with proper validation
@@ -14,6 +23,20 @@ fn parse_drive_id(drive_id: &str) -> Result<DriveId, String> {
     Ok(DriveId(arr))
 }
 
+/// Refuse to restart a drained drive's subsystems piecemeal
+async fn ensure_not_drained(state: &AppState, id: &DriveId) -> Result<(), String> {
+    if let Some(sync_engine) = state.sync_engine.as_ref() {
+        if sync_engine.is_drained(id).await {
+            return Err(AppError::ValidationError(format!(
+                "Drive {} is drained; resume it first",
+                id
+            ))
+            .to_string());
+        }
+    }
+    Ok(())
+}
+
 /// Start syncing a drive
 ///
 /// This initializes the sync engine for the specified drive:
@@ -22,6 +45,7 @@ fn parse_drive_id(drive_id: &str) -> Result<DriveId, String> {
 #[tauri::command]
 pub async fn start_sync(drive_id: String, state: State<'_, AppState>) -> Result<(), String> {
     let id = parse_drive_id(&drive_id)?;
+    ensure_not_drained(&state, &id).await?;
 
     // Check if sync engine is available
     let sync_engine = state
@@ -37,6 +61,7 @@ pub async fn start_sync(drive_id: String, state: State<'_, AppState>) -> Result<
         }
         .to_string()
     })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
 
     // Initialize sync for this drive
     sync_engine
@@ -69,218 +94,365 @@ pub async fn stop_sync(drive_id: String, state: State<'_, AppState>) -> Result<(
     Ok(())
 }
 
-/// Get sync status for a drive
+/// Pause syncing a drive's local changes
+///
+/// The drive stays subscribed and keeps receiving remote changes; local
+/// changes are held until `resume_sync`. Returns false if the drive was
+/// already paused.
 #[tauri::command]
-pub async fn get_sync_status(
-    drive_id: String,
-    state: State<'_, AppState>,
-) -> Result<SyncStatus, String> {
+pub async fn pause_sync(drive_id: String, state: State<'_, AppState>) -> Result<bool, String> {
     let id = parse_drive_id(&drive_id)?;
 
-    // Check if sync engine is available
     let sync_engine = state
         .sync_engine
         .as_ref()
         .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
 
-    let status = sync_engine.get_status(&id).await;
-    Ok(status)
+    Ok(sync_engine.pause_sync(&id).await)
 }
 
-/// Get sync diagnostics for a drive
+/// Resume syncing a drive paused by `pause_sync`
+///
+/// Held changes are applied and announced, keeping only the latest change
+/// per path. Returns how many were applied.
 #[tauri::command]
-pub async fn get_sync_diagnostics(
-    drive_id: String,
-    state: State<'_, AppState>,
-) -> Result<SyncDiagnostics, String> {
+pub async fn resume_sync(drive_id: String, state: State<'_, AppState>) -> Result<usize, String> {
     let id = parse_drive_id(&drive_id)?;
 
-    // Check if sync engine is available
     let sync_engine = state
         .sync_engine
         .as_ref()
         .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
 
-    let diagnostics = sync_engine.get_diagnostics(&id).await;
-    Ok(diagnostics)
+    sync_engine.resume_sync(&id).await.ok_or_else(|| {
+        AppError::ValidationError(format!("Drive {} is not paused", drive_id)).to_string()
+    })
 }
 
-/// Subscribe to drive events (returns immediately, events come via Tauri events)
+/// Default and longest wait for a drive's in-flight transfers when draining
+const DEFAULT_DRAIN_TIMEOUT_SECS: u64 = 30;
+const MAX_DRAIN_TIMEOUT_SECS: u64 = 600;
+
+/// What `drain_drive` stopped
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct DrainReport {
+    pub drive_id: String,
+    /// Whether the watcher was running (it is restarted by `resume_drive`)
+    pub was_watching: bool,
+    /// Queued transfers that were cancelled
+    pub transfers_cancelled: usize,
+    /// Transfers still running when the timeout ran out
+    pub transfers_in_flight: usize,
+    /// Changes still queued for peers; sent after `resume_drive`
+    pub pending_uploads: usize,
+    /// True once nothing is running for the drive
+    pub quiesced: bool,
+}
+
+/// What `resume_drive` restarted
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ResumeReport {
+    pub drive_id: String,
+    /// Whether the watcher was restarted
+    pub watching: bool,
+    /// Changes made on disk while drained, now picked up
+    pub reconciled_changes: usize,
+}
+
+/// Stop one drive's subsystems ahead of maintenance
 ///
-/// This sets up a listener that forwards gossip events to the frontend
-/// via Tauri's event system.
+/// In order: the watcher stops (remembering the drive's files), pending
+/// change summaries and queued changes are sent, the drive leaves its
+/// gossip topic and docs sync stops, then new transfers are refused, queued
+/// ones cancelled and in-flight ones given up to `timeout_secs` (default
+/// 30) to finish. Until `resume_drive`, local and remote changes for the
+/// drive are refused and `start_sync`/`start_watching` fail. Other drives
+/// are unaffected.
 #[tauri::command]
-pub async fn subscribe_drive_events(
+pub async fn drain_drive(
     drive_id: String,
+    timeout_secs: Option<u64>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let _id = parse_drive_id(&drive_id)?;
+) -> Result<DrainReport, String> {
+    let id = parse_drive_id(&drive_id)?;
+    let timeout_secs = timeout_secs.unwrap_or(DEFAULT_DRAIN_TIMEOUT_SECS);
+    if timeout_secs > MAX_DRAIN_TIMEOUT_SECS {
+        return Err(AppError::ValidationFailed {
+            field: "timeout_secs".to_string(),
+            reason: format!("must be at most {}", MAX_DRAIN_TIMEOUT_SECS),
+        }
+        .to_string());
+    }
 
-    // Check if event broadcaster is available
-    let _broadcaster = state
-        .event_broadcaster
+    let sync_engine = state
+        .sync_engine
         .as_ref()
-        .ok_or_else(|| "Event broadcaster not initialized".to_string())?;
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    if !state.drives.read().await.contains_key(id.as_bytes()) {
+        return Err(AppError::DriveNotFound { drive_id }.to_string());
+    }
+    ensure_not_drained(&state, &id).await?;
+
+    // Stop new local changes before flushing the ones already seen
+    let was_watching = match state.file_watcher.as_ref() {
+        Some(file_watcher) => file_watcher.drain_watch(&id).await,
+        None => false,
+    };
+    sync_engine.drain_drive(&id).await;
+    let (transfers_cancelled, transfers_in_flight) = match state.file_transfer.as_ref() {
+        Some(file_transfer) => {
+            file_transfer
+                .drain_drive(&id, std::time::Duration::from_secs(timeout_secs))
+                .await
+        }
+        None => (0, 0),
+    };
+    let pending_uploads = sync_engine.pending_uploads(Some(&id)).await.len();
 
-    // Note: The actual event forwarding is set up in lib.rs when the app starts.
-    // This command just validates that the drive exists and sync is available.
-    tracing::info!("Frontend subscribed to events for drive: {}", drive_id);
-    Ok(())
+    tracing::info!(
+        drive_id = %drive_id,
+        transfers_cancelled,
+        transfers_in_flight,
+        "Drained drive"
+    );
+    Ok(DrainReport {
+        drive_id,
+        was_watching,
+        transfers_cancelled,
+        transfers_in_flight,
+        pending_uploads,
+        quiesced: transfers_in_flight == 0,
+    })
 }
 
-/// Start watching a drive's folder for local changes
+/// Bring a drive stopped by `drain_drive` back
 ///
-/// This enables the file watcher for the specified drive, which will
-/// detect local file changes and emit events to the sync engine.
+/// Transfers are accepted again, sync restarts where it was running
+/// before, queued changes are sent, and the watcher restarts and reports
+/// files changed on disk in the meantime.
 #[tauri::command]
-pub async fn start_watching(drive_id: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn resume_drive(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<ResumeReport, String> {
     let id = parse_drive_id(&drive_id)?;
 
-    // Check if file watcher is available
-    let file_watcher = state
-        .file_watcher
+    let sync_engine = state
+        .sync_engine
         .as_ref()
-        .ok_or_else(|| "File watcher not initialized".to_string())?;
-
-    // Get the drive's local path from cache
-    let drives = state.drives.read().await;
-    let drive = drives
-        .get(id.as_bytes())
-        .ok_or_else(|| "Drive not found".to_string())?;
-
-    let local_path = drive.local_path.clone();
-    drop(drives); // Release lock before async operation
-
-    // Start watching
-    file_watcher
-        .watch(id, local_path)
-        .await
-        .map_err(|e| format!("Failed to start watching: {}", e))?;
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
 
-    tracing::info!("Started file watching for drive: {}", drive_id);
-    Ok(())
+    if let Some(file_transfer) = state.file_transfer.as_ref() {
+        file_transfer.resume_drive(&id).await;
+    }
+    if !sync_engine.resume_drive(&id).await {
+        return Err(
+            AppError::ValidationError(format!("Drive {} is not drained", drive_id)).to_string(),
+        );
+    }
+    sync_engine.retry_pending_uploads(Some(&id), false).await;
+
+    // Restart the watcher last so reconciled changes reach a live engine
+    let reconciled = match state.file_watcher.as_ref() {
+        Some(file_watcher) => file_watcher
+            .restore_watch(&id)
+            .await
+            .map_err(|e| format!("Failed to restart watching: {}", e))?,
+        None => None,
+    };
+
+    tracing::info!(drive_id = %drive_id, "Resumed drive");
+    Ok(ResumeReport {
+        drive_id,
+        watching: reconciled.is_some(),
+        reconciled_changes: reconciled.unwrap_or(0),
+    })
 }
 
-/// Stop watching a drive's folder
+/// Get sync status for a drive
 #[tauri::command]
-pub async fn stop_watching(drive_id: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn get_sync_status(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<SyncStatus, String> {
     let id = parse_drive_id(&drive_id)?;
 
-    // Check if file watcher is available
-    let file_watcher = state
-        .file_watcher
+    // Check if sync engine is available
+    let sync_engine = state
+        .sync_engine
         .as_ref()
-        .ok_or_else(|| "File watcher not initialized".to_string())?;
-
-    file_watcher.unwatch(&id).await;
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
 
-    tracing::info!("Stopped file watching for drive: {}", drive_id);
-    Ok(())
+    let status = sync_engine.get_status(&id).await;
+    Ok(status)
 }
 
-/// Check if a drive is being watched
+/// Get sync diagnostics for a drive
 #[tauri::command]
-pub async fn is_watching(drive_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn get_sync_diagnostics(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<SyncDiagnostics, String> {
     let id = parse_drive_id(&drive_id)?;
 
-    let file_watcher = state
-        .file_watcher
+    // Check if sync engine is available
+    let sync_engine = state
+        .sync_engine
         .as_ref()
-        .ok_or_else(|| AppError::WatcherNotInitialized.to_string())?;
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
 
-    Ok(file_watcher.is_watching(&id).await)
+    let diagnostics = sync_engine.get_diagnostics(&id).await;
+    Ok(diagnostics)
 }
 
-// ==============================================
-// File Transfer Commands
-// ==============================================
-
-use crate::network::TransferState;
+/// Default and longest wait for peers to answer a latency probe
+const DEFAULT_E2E_TIMEOUT_SECS: u64 = 30;
+const MAX_E2E_TIMEOUT_SECS: u64 = 300;
 
-/// Upload a file to the blob store
+/// Measure how long a saved file takes to be written on connected peers
 ///
-/// This imports a local file into iroh-blobs, making it available to peers.
-///
-/// # Security
-/// - Validates file path is within drive root
-/// - Prevents directory traversal attacks
+/// Writes a small marker file into the drive and times it through the
+/// whole pipeline: watcher, metadata, gossip, transfer and the peer's
+/// write. Peers answer once the marker is on their disk; the marker is
+/// removed afterwards. `timeout_secs` defaults to 30 (max 300). Only peers
+/// running a version that answers probes are counted.
 #[tauri::command]
-pub async fn upload_file(
+pub async fn measure_e2e_sync_latency(
     drive_id: String,
-    file_path: String,
+    timeout_secs: Option<u64>,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+) -> Result<E2eLatencyReport, String> {
     let id = parse_drive_id(&drive_id)?;
 
-    let file_transfer = state
-        .file_transfer
+    let sync_engine = state
+        .sync_engine
         .as_ref()
-        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
-
-    // Get drive to determine relative path
-    let drives = state.drives.read().await;
-    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
-        AppError::DriveNotFound {
-            drive_id: drive_id.clone(),
-        }
-        .to_string()
-    })?;
-
-    // Validate the file path is within drive root (prevents path traversal)
-    let validated_path = validate_path(&drive.local_path, &file_path).map_err(|e| e.to_string())?;
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let broadcaster = state
+        .event_broadcaster
+        .as_ref()
+        .ok_or_else(|| AppError::BroadcasterNotInitialized.to_string())?;
+    let node_id = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
 
-    let relative_path = validated_path
-        .strip_prefix(&drive.local_path)
-        .map_err(|_| {
-            AppError::PathOutsideDrive {
-                path: file_path.clone(),
+    let root = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
             }
             .to_string()
-        })?
-        .to_path_buf();
-
-    drop(drives);
+        })?;
+        drive.ensure_active().map_err(|e| e.to_string())?;
+        drive.local_path.clone()
+    };
+
+    if broadcaster.drive_peers(&id).await.peer_count == 0 {
+        return Err(
+            AppError::SyncFailed("no peers connected to measure against".into()).to_string(),
+        );
+    }
 
-    // Upload the file
-    let hash = file_transfer
-        .upload_file(&id, &validated_path, &relative_path)
-        .await
-        .map_err(|e| AppError::TransferFailed(format!("Upload failed: {}", e)).to_string())?;
+    let timeout = timeout_secs
+        .unwrap_or(DEFAULT_E2E_TIMEOUT_SECS)
+        .clamp(1, MAX_E2E_TIMEOUT_SECS);
+    let report = latency::measure(
+        sync_engine,
+        broadcaster,
+        id,
+        &root,
+        node_id,
+        std::time::Duration::from_secs(timeout),
+    )
+    .await
+    .map_err(|e| AppError::SyncFailed(format!("Latency probe failed: {}", e)).to_string())?;
 
     tracing::info!(
         drive_id = %drive_id,
-        path = %file_path,
-        hash = %hash.to_hex(),
-        "Uploaded file"
+        answered = report.peers.len(),
+        expected = report.expected_peers,
+        local_detect_ms = ?report.local_detect_ms,
+        "Measured end-to-end sync latency"
     );
-    Ok(hash.to_hex().to_string())
+    Ok(report)
 }
 
-/// Download a file from the blob store to local filesystem
+/// Get the current gossip peers for a drive along with the configured limit
+#[tauri::command]
+pub async fn get_drive_peers(
+    drive_id: String,
+    state: State<'_, AppState>,
+    key_proofs: State<'_, Arc<KeyProofTracker>>,
+) -> Result<DrivePeers, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let broadcaster = state
+        .event_broadcaster
+        .as_ref()
+        .ok_or_else(|| AppError::BroadcasterNotInitialized.to_string())?;
+
+    let mut peers = broadcaster.drive_peers(&id).await;
+    peers.key_possession = key_proofs.statuses(&id).await;
+    Ok(peers)
+}
+
+/// Set the maximum number of gossip peers for a drive
 ///
-/// # Security
-/// - Validates destination path is within drive root
-/// - Prevents directory traversal attacks
+/// Once the limit is reached, new neighbors are ignored in favor of
+/// existing ones. Pass `None` to restore the default (unlimited).
 #[tauri::command]
-pub async fn download_file(
+pub async fn set_max_peers(
     drive_id: String,
-    hash: String,
-    destination_path: String,
+    max_peers: Option<usize>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     let id = parse_drive_id(&drive_id)?;
 
-    let file_transfer = state
-        .file_transfer
-        .as_ref()
-        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+    if max_peers == Some(0) {
+        return Err(AppError::ValidationError(
+            "max_peers must be at least 1".to_string(),
+        )
+        .to_string());
+    }
 
-    // Parse the hash
-    let blob_hash = hash
-        .parse::<iroh_blobs::Hash>()
-        .map_err(|e| AppError::InvalidHash(format!("Invalid hash: {}", e)).to_string())?;
+    // Update and persist the drive setting
+    {
+        let mut drives = state.drives.write().await;
+        let drive = drives.get_mut(id.as_bytes()).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.max_peers = max_peers;
+
+        let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+            AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+        })?;
+        state.db.save_drive(id.as_bytes(), &drive_bytes).map_err(|e| {
+            AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+        })?;
+    }
+
+    if let Some(broadcaster) = state.event_broadcaster.as_ref() {
+        broadcaster.set_max_peers(id, max_peers).await;
+    }
+
+    tracing::info!(drive_id = %drive_id, max_peers = ?max_peers, "Updated max peers for drive");
+    Ok(())
+}
+
+/// Get the peers this device never syncs with directly for a drive
+#[tauri::command]
+pub async fn get_no_direct_sync(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let id = parse_drive_id(&drive_id)?;
 
-    // Get drive for path validation
     let drives = state.drives.read().await;
     let drive = drives.get(id.as_bytes()).ok_or_else(|| {
         AppError::DriveNotFound {
@@ -288,196 +460,2634 @@ pub async fn download_file(
         }
         .to_string()
     })?;
+    Ok(drive.no_direct_sync.iter().map(|peer| peer.to_hex()).collect())
+}
 
-    // Validate the destination path is within drive root
-    let validated_path =
-        validate_path(&drive.local_path, &destination_path).map_err(|e| e.to_string())?;
+/// Set the peers this device never syncs with directly for a drive
+///
+/// Unlike revoking access, listed peers keep their ACL permissions and stay
+/// on the gossip topic; docs sync just avoids connecting to them, relying on
+/// other peers to relay their changes. Pass an empty list to clear it.
+#[tauri::command]
+pub async fn set_no_direct_sync(
+    drive_id: String,
+    peers: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let id = parse_drive_id(&drive_id)?;
 
-    let relative_path = validated_path
-        .strip_prefix(&drive.local_path)
-        .map(|p| p.to_path_buf())
-        .unwrap_or_else(|_| validated_path.clone());
+    let mut parsed = Vec::with_capacity(peers.len());
+    for peer in &peers {
+        let node_id = crate::crypto::NodeId::from_hex(peer).map_err(|e| {
+            AppError::ValidationFailed {
+                field: "peers".to_string(),
+                reason: format!("invalid node ID {}: {}", peer, e),
+            }
+            .to_string()
+        })?;
+        if !parsed.contains(&node_id) {
+            parsed.push(node_id);
+        }
+    }
 
-    drop(drives);
+    if let Some(own_id) = state.identity_manager.node_id().await {
+        if parsed.contains(&own_id) {
+            return Err(AppError::ValidationFailed {
+                field: "peers".to_string(),
+                reason: "cannot exclude this device".to_string(),
+            }
+            .to_string());
+        }
+    }
 
-    // Download the file
-    file_transfer
-        .download_file(&id, blob_hash, &validated_path, &relative_path)
-        .await
-        .map_err(|e| AppError::TransferFailed(format!("Download failed: {}", e)).to_string())?;
+    // Update and persist the drive setting
+    {
+        let mut drives = state.drives.write().await;
+        let drive = drives.get_mut(id.as_bytes()).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.no_direct_sync = parsed.clone();
+
+        let drive_bytes = serde_json::to_vec(&*drive).map_err(|e| {
+            AppError::SerializationError(format!("Failed to serialize drive: {}", e)).to_string()
+        })?;
+        state.db.save_drive(id.as_bytes(), &drive_bytes).map_err(|e| {
+            AppError::DatabaseError(format!("Failed to save drive: {}", e)).to_string()
+        })?;
+    }
+
+    if let Some(docs_manager) = state.docs_manager.as_ref() {
+        let excluded = parsed.iter().map(|peer| *peer.as_bytes()).collect();
+        docs_manager
+            .set_no_direct_sync(&id, excluded)
+            .await
+            .map_err(|e| AppError::SyncFailed(format!("Failed to apply: {}", e)).to_string())?;
+    }
 
     tracing::info!(
         drive_id = %drive_id,
-        hash = %hash,
-        path = %destination_path,
-        "Downloaded file"
+        excluded = parsed.len(),
+        "Updated no-direct-sync peers for drive"
     );
-    Ok(())
+    Ok(parsed.iter().map(|peer| peer.to_hex()).collect())
 }
 
-/// List all active transfers
+/// List local changes waiting to be announced to peers
+///
+/// Changes are queued while sync is paused, when no peer is connected, or
+/// after a failed broadcast. Pass a drive ID to list only that drive's.
 #[tauri::command]
-pub async fn list_transfers(state: State<'_, AppState>) -> Result<Vec<TransferState>, String> {
+pub async fn list_pending_uploads(
+    drive_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PendingUploadDto>, String> {
+    let id = drive_id.as_deref().map(parse_drive_id).transpose()?;
+
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    Ok(sync_engine
+        .pending_uploads(id.as_ref())
+        .await
+        .iter()
+        .map(PendingUploadDto::from)
+        .collect())
+}
+
+/// Try to send queued changes now, returning how many were delivered
+///
+/// Unlike the automatic retry when a peer connects, this also attempts
+/// drives that currently have no known peers.
+#[tauri::command]
+pub async fn retry_pending_uploads(
+    drive_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let id = drive_id.as_deref().map(parse_drive_id).transpose()?;
+
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    if sync_engine.is_paused().await {
+        return Err(AppError::SyncFailed("Sync is paused".to_string()).to_string());
+    }
+
+    Ok(sync_engine.retry_pending_uploads(id.as_ref(), true).await)
+}
+
+/// Drop a queued change without announcing it
+///
+/// Peers still see the change through synced metadata; only the real-time
+/// notification is dropped. Returns false if it was no longer queued.
+#[tauri::command]
+pub async fn discard_pending_upload(
+    upload_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let discarded = sync_engine.discard_pending_upload(&upload_id).await;
+    if discarded {
+        tracing::info!(upload_id = %upload_id, "Discarded pending upload");
+    }
+    Ok(discarded)
+}
+
+/// Get how joined drives without responding peers are retried
+#[tauri::command]
+pub async fn get_peer_retry_config(state: State<'_, AppState>) -> Result<PeerRetryConfig, String> {
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    Ok(sync_engine.peer_retry_config().await)
+}
+
+/// Set how joined drives without responding peers are retried
+///
+/// The delay starts at `initial_delay_secs` and doubles per attempt up to
+/// `max_delay_secs` (both clamped to 1 second - 1 day). Disabling stops
+/// retries that are running.
+#[tauri::command]
+pub async fn set_peer_retry_config(
+    config: PeerRetryConfig,
+    state: State<'_, AppState>,
+) -> Result<PeerRetryConfig, String> {
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let config = config.normalized();
+    let bytes = serde_json::to_vec(&config)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(PEER_RETRY_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    let config = sync_engine.set_peer_retry_config(config).await;
+    tracing::info!(config = ?config, "Updated peer retry config");
+    Ok(config)
+}
+
+/// Get how bursts of changes in one directory are summarized
+#[tauri::command]
+pub async fn get_change_summary_config(
+    state: State<'_, AppState>,
+) -> Result<ChangeSummaryConfig, String> {
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    Ok(sync_engine.change_summary_config().await)
+}
+
+/// Set how bursts of changes in one directory are summarized
+///
+/// When enabled, once more than `threshold` files (2 - 10000) under one
+/// directory change within `window_ms` (250 ms - 1 minute), further changes
+/// are announced as a single `FilesChangedSummary` when the window closes.
+/// Downloads and conflicts are always reported per file.
+#[tauri::command]
+pub async fn set_change_summary_config(
+    config: ChangeSummaryConfig,
+    state: State<'_, AppState>,
+) -> Result<ChangeSummaryConfig, String> {
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let config = config.normalized();
+    let bytes = serde_json::to_vec(&config)
+        .map_err(|e| AppError::SerializationError(e.to_string()).to_string())?;
+    state
+        .db
+        .save_setting(CHANGE_SUMMARY_SETTING, &bytes)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+
+    let config = sync_engine.set_change_summary_config(config).await;
+    tracing::info!(config = ?config, "Updated change summary config");
+    Ok(config)
+}
+
+/// Stop waiting for a joined drive's peers
+///
+/// The drive keeps syncing with any peer that connects on its own. Returns
+/// false if the drive was not waiting for peers.
+#[tauri::command]
+pub async fn cancel_peer_retry(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    Ok(sync_engine.cancel_peer_retry(&id).await)
+}
+
+/// Get a drive's selective sync filters
+#[tauri::command]
+pub async fn get_sync_filters(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<SyncFilters, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    Ok(sync_engine.sync_filters(&id).await)
+}
+
+/// Set a drive's selective sync filters
+///
+/// Patterns use the same glob syntax as ACL path rules (`*` within a
+/// segment, `**` across segments). A path syncs when it matches an include
+/// pattern (or `include` is empty) and no exclude pattern. Returns the
+/// previously synced paths whose metadata was tombstoned because they are
+/// now excluded.
+#[tauri::command]
+pub async fn set_sync_filters(
+    drive_id: String,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    let id = parse_drive_id(&drive_id)?;
+    let filters =
+        SyncFilters::new(include, exclude).map_err(|e| AppError::ValidationError(e).to_string())?;
+
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let tombstoned = sync_engine
+        .set_sync_filters(&id, filters)
+        .await
+        .map_err(|e| {
+            AppError::SyncFailed(format!("Failed to set sync filters: {}", e)).to_string()
+        })?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        tombstoned = tombstoned.len(),
+        "Updated sync filters"
+    );
+    Ok(tombstoned)
+}
+
+/// Subscribe to drive events (returns immediately, events come via Tauri events)
+///
+/// This sets up a listener that forwards gossip events to the frontend
+/// via Tauri's event system. Returns a subscription ID; pass it to
+/// `unsubscribe_drive_events` when the view stops listening.
+#[tauri::command]
+pub async fn subscribe_drive_events(
+    drive_id: String,
+    state: State<'_, AppState>,
+    subscriptions: State<'_, Arc<EventSubscriptions>>,
+) -> Result<String, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    // Check if event broadcaster is available
+    let _broadcaster = state
+        .event_broadcaster
+        .as_ref()
+        .ok_or_else(|| "Event broadcaster not initialized".to_string())?;
+
+    // Note: The actual event forwarding is set up in lib.rs when the app starts.
+    // The subscription is only tracked, so leaked listeners can be found.
+    let subscription_id = subscriptions.register_listener(id.to_hex());
+    tracing::info!("Frontend subscribed to events for drive: {}", drive_id);
+    Ok(subscription_id)
+}
+
+/// Subscribe to a subset of a drive's events over a dedicated channel
+///
+/// Unlike `subscribe_drive_events`, only events whose type is in
+/// `event_types` are sent, and only to this subscriber. Returns a
+/// subscription ID for `unsubscribe_drive_events`.
+#[tauri::command]
+pub async fn subscribe_drive_events_filtered(
+    drive_id: String,
+    event_types: Vec<String>,
+    on_event: Channel<DriveEventDto>,
+    state: State<'_, AppState>,
+    subscriptions: State<'_, Arc<EventSubscriptions>>,
+) -> Result<String, String> {
+    let id = parse_drive_id(&drive_id)?;
+    if !state.drives.read().await.contains_key(id.as_bytes()) {
+        return Err(AppError::DriveNotFound { drive_id }.to_string());
+    }
+
+    if event_types.is_empty() {
+        return Err(AppError::ValidationFailed {
+            field: "event_types".to_string(),
+            reason: "at least one event type is required".to_string(),
+        }
+        .to_string());
+    }
+    if let Some(unknown) = event_types.iter().find(|t| !EVENT_TYPES.contains(&t.as_str())) {
+        return Err(AppError::ValidationFailed {
+            field: "event_types".to_string(),
+            reason: format!("unknown event type: {}", unknown),
+        }
+        .to_string());
+    }
+
+    let filter = EventFilter {
+        drive_id: hex::encode(id.as_bytes()),
+        event_types: event_types.into_iter().collect(),
+    };
+    let subscription_id = subscriptions.subscribe(filter, on_event);
+
+    tracing::info!(
+        drive_id = %drive_id,
+        subscription_id = %subscription_id,
+        "Frontend subscribed to filtered drive events"
+    );
+    Ok(subscription_id)
+}
+
+/// Remove an event subscription
+///
+/// Returns false if the subscription was unknown or already closed.
+#[tauri::command]
+pub async fn unsubscribe_drive_events(
+    subscription_id: String,
+    subscriptions: State<'_, Arc<EventSubscriptions>>,
+) -> Result<bool, String> {
+    Ok(subscriptions.unsubscribe(&subscription_id))
+}
+
+/// Remove every event subscription of a drive, returning how many there were
+#[tauri::command]
+pub async fn unsubscribe_all_drive_events(
+    drive_id: String,
+    subscriptions: State<'_, Arc<EventSubscriptions>>,
+) -> Result<usize, String> {
+    let id = parse_drive_id(&drive_id)?;
+    let removed = subscriptions.unsubscribe_drive(&id.to_hex());
+    tracing::info!(drive_id = %drive_id, removed, "Removed drive event subscriptions");
+    Ok(removed)
+}
+
+/// List active frontend event subscriptions by drive (for debugging)
+///
+/// Shows each drive's receiver count and every subscription's ID, kind,
+/// age and delivered event count, to find subscriptions a view leaked.
+#[tauri::command]
+pub async fn list_active_subscriptions(
+    subscriptions: State<'_, Arc<EventSubscriptions>>,
+) -> Result<Vec<DriveSubscriptions>, String> {
+    Ok(subscriptions.list())
+}
+
+/// Start watching a drive's folder for local changes
+///
+/// This enables the file watcher for the specified drive, which will
+/// detect local file changes and emit events to the sync engine.
+#[tauri::command]
+pub async fn start_watching(drive_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let id = parse_drive_id(&drive_id)?;
+    ensure_not_drained(&state, &id).await?;
+
+    // Check if file watcher is available
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| "File watcher not initialized".to_string())?;
+
+    // Get the drive's local path from cache
+    let drives = state.drives.read().await;
+    let drive = drives
+        .get(id.as_bytes())
+        .ok_or_else(|| "Drive not found".to_string())?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+
+    let local_path = drive.local_path.clone();
+    drop(drives); // Release lock before async operation
+
+    // Start watching
+    file_watcher
+        .watch(id, local_path)
+        .await
+        .map_err(|e| format!("Failed to start watching: {}", e))?;
+
+    tracing::info!("Started file watching for drive: {}", drive_id);
+    Ok(())
+}
+
+/// Stop watching a drive's folder
+#[tauri::command]
+pub async fn stop_watching(drive_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    // Check if file watcher is available
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| "File watcher not initialized".to_string())?;
+
+    file_watcher.unwatch(&id).await;
+
+    tracing::info!("Stopped file watching for drive: {}", drive_id);
+    Ok(())
+}
+
+/// Check if a drive is being watched
+#[tauri::command]
+pub async fn is_watching(drive_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| AppError::WatcherNotInitialized.to_string())?;
+
+    Ok(file_watcher.is_watching(&id).await)
+}
+
+/// List every drive's watch state and watcher health
+///
+/// Drives that aren't watched are included with `watching: false`. A drive
+/// whose watcher has errored since its last event reports `healthy: false`;
+/// the errors are also sent as `WatcherError` drive events.
+/// `events_per_sec` is the current raw event load; `throttled` is set while
+/// high churn has the drive's changes batched (see `set_watch_throttle`).
+#[tauri::command]
+pub async fn list_watch_status(state: State<'_, AppState>) -> Result<Vec<WatchStatus>, String> {
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| AppError::WatcherNotInitialized.to_string())?;
+
+    let drive_ids: Vec<DriveId> = state
+        .drives
+        .read()
+        .await
+        .keys()
+        .map(|id| DriveId(*id))
+        .collect();
+
+    let mut statuses = Vec::with_capacity(drive_ids.len());
+    for id in drive_ids {
+        statuses.push(file_watcher.status(&id).await);
+    }
+    statuses.sort_by(|a, b| a.drive_id.cmp(&b.drive_id));
+    Ok(statuses)
+}
+
+/// Re-read a watched drive's `.gixignore` files
+///
+/// The watcher already reloads them when one changes; this forces a
+/// refresh. Returns the number of ignore files found.
+#[tauri::command]
+pub async fn reload_ignore_rules(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let file_watcher = state
+        .file_watcher
+        .as_ref()
+        .ok_or_else(|| AppError::WatcherNotInitialized.to_string())?;
+
+    file_watcher
+        .reload_ignore_rules(&id)
+        .await
+        .map_err(|e| AppError::ValidationError(e.to_string()).to_string())
+}
+
+// ==============================================
+// File Transfer Commands
+// ==============================================
+
+use crate::commands::security::SecurityStore;
+use crate::crypto::Permission;
+use crate::network::chunking::DELTA_MIN_FILE_SIZE;
+use crate::network::docs::FileMetadata;
+use crate::network::history::FileVersion;
+use crate::network::{
+    BlobStoreStats, ContentMismatch, FileTransferManager, PeerMetrics, TransferState,
+};
+
+/// Upload a file to the blob store
+///
+/// This imports a local file into iroh-blobs, making it available to peers.
+/// Files of 8MB or more are uploaded in chunks, so re-uploading an edited
+/// file only imports the chunks that changed (see `upload_file_delta`).
+///
+/// # Security
+/// - Validates file path is within drive root
+/// - Prevents directory traversal attacks
+#[tauri::command]
+pub async fn upload_file(
+    drive_id: String,
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    // Get drive to determine relative path
+    let drives = state.drives.read().await;
+    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+
+    // Validate the file path is within drive root (prevents path traversal)
+    let validated_path = validate_path(&drive.local_path, &file_path).map_err(|e| e.to_string())?;
+
+    let relative_path = validated_path
+        .strip_prefix(&drive.local_path)
+        .map_err(|_| {
+            AppError::PathOutsideDrive {
+                path: file_path.clone(),
+            }
+            .to_string()
+        })?
+        .to_path_buf();
+
+    drop(drives);
+
+    // Upload the file
+    let size = tokio::fs::metadata(&validated_path)
+        .await
+        .map(|meta| meta.len())
+        .unwrap_or(0);
+    let result = if size >= DELTA_MIN_FILE_SIZE {
+        upload_in_chunks(&state, file_transfer, &id, &validated_path, &relative_path).await
+    } else {
+        file_transfer
+            .upload_file(&id, &validated_path, &relative_path)
+            .await
+    };
+    track_access(&state, &id, &relative_path, &validated_path, FsOperation::Read, &result);
+    let hash =
+        result.map_err(|e| AppError::TransferFailed(format!("Upload failed: {}", e)).to_string())?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        path = %file_path,
+        hash = %hash.to_hex(),
+        "Uploaded file"
+    );
+    Ok(hash.to_hex().to_string())
+}
+
+/// Upload a large file in chunks and record its manifest in the metadata
+///
+/// The manifest of the previous upload, if the metadata has one, tells
+/// which chunks changed.
+async fn upload_in_chunks(
+    state: &AppState,
+    file_transfer: &FileTransferManager,
+    id: &DriveId,
+    local_path: &std::path::Path,
+    relative_path: &std::path::Path,
+) -> anyhow::Result<iroh_blobs::Hash> {
+    let path_key = file::path_key(relative_path);
+    let existing = match state.docs_manager.as_ref() {
+        Some(docs_manager) => docs_manager.get_file_metadata(id, &path_key).await,
+        None => None,
+    };
+    let previous = match existing
+        .as_ref()
+        .and_then(|meta| meta.manifest_hash.as_deref())
+        .and_then(|hash| hash.parse().ok())
+    {
+        Some(manifest_hash) => file_transfer.read_manifest(manifest_hash).await.ok(),
+        None => None,
+    };
+
+    let upload = file_transfer
+        .upload_file_delta(id, local_path, relative_path, previous.as_ref())
+        .await?;
+
+    if let Some(docs_manager) = state.docs_manager.as_ref() {
+        let name = relative_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut meta = existing.unwrap_or_else(|| {
+            FileMetadata::new(&path_key, &name, false, 0, &chrono::Utc::now().to_rfc3339())
+        });
+        if meta.content_hash.as_deref() != Some(upload.manifest.hash.as_str()) {
+            meta.modified_at = chrono::Utc::now().to_rfc3339();
+            meta.previous_hash = meta.content_hash.take();
+        }
+        meta.size = upload.manifest.size;
+        meta.content_hash = Some(upload.manifest.hash.clone());
+        meta.manifest_hash = Some(upload.manifest_hash.to_hex().to_string());
+        docs_manager.set_file_metadata(id, &meta).await?;
+    }
+
+    Ok(upload.hash)
+}
+
+/// Check that the caller has `required` permission on `path` of a drive
+async fn check_file_permission(
+    state: &AppState,
+    security: &SecurityStore,
+    drive_id: &str,
+    owner_hex: &str,
+    path: &str,
+    required: Permission,
+) -> Result<(), String> {
+    let caller_hex = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?
+        .to_hex();
+    let acl = security.get_or_create_acl(drive_id, owner_hex).await;
+    if !acl.check_permission(&caller_hex, path, required) {
+        tracing::warn!(
+            drive_id = %drive_id,
+            user = %caller_hex,
+            path = %path,
+            "Access denied: insufficient permission"
+        );
+        return Err(AppError::AccessDenied {
+            reason: format!("insufficient permission: {:?} required", required),
+        }
+        .to_string());
+    }
+    Ok(())
+}
+
+/// Attempts at fetching an old version's blob from peers when restoring it
+const RESTORE_FETCH_ATTEMPTS: u32 = 3;
+
+/// Download a file from the blob store to local filesystem
+///
+/// With `max_retries`, a blob that isn't stored locally is fetched from the
+/// drive's peers, retrying failed fetches that many times with backoff.
+///
+/// # Security
+/// - Validates destination path is within drive root
+/// - Prevents directory traversal attacks
+#[tauri::command]
+pub async fn download_file(
+    drive_id: String,
+    hash: String,
+    destination_path: String,
+    max_retries: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    // Parse the hash
+    let blob_hash = hash
+        .parse::<iroh_blobs::Hash>()
+        .map_err(|e| AppError::InvalidHash(format!("Invalid hash: {}", e)).to_string())?;
+
+    // Get drive for path validation
+    let drives = state.drives.read().await;
+    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+
+    // Validate the destination path is within drive root
+    let validated_path =
+        validate_path(&drive.local_path, &destination_path).map_err(|e| e.to_string())?;
+
+    let relative_path = validated_path
+        .strip_prefix(&drive.local_path)
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|_| validated_path.clone());
+
+    // Enforce the drive's file size limit before writing anything
+    let stored_size = file_transfer
+        .blob_size(&blob_hash)
+        .await
+        .map_err(|e| AppError::TransferFailed(e.to_string()).to_string())?;
+    if let Some(size) = stored_size {
+        drive
+            .check_file_size(&destination_path, size)
+            .map_err(|e| e.to_string())?;
+    }
+
+    drop(drives);
+
+    // Verify against the synced metadata so a stale or tampered blob is not
+    // shown as a correctly synced file
+    let metadata = match state.docs_manager.as_ref() {
+        Some(docs_manager) => {
+            docs_manager
+                .get_file_metadata(&id, &file::path_key(&relative_path))
+                .await
+        }
+        None => None,
+    };
+    let expected_hash = metadata.as_ref().and_then(|meta| meta.content_hash.clone());
+
+    // A file uploaded in chunks is put together from its chunks
+    let manifest_hash = metadata
+        .as_ref()
+        .filter(|meta| {
+            meta.content_hash
+                .as_deref()
+                .is_some_and(|content_hash| content_hash.eq_ignore_ascii_case(&hash))
+        })
+        .and_then(|meta| meta.manifest_hash.as_deref())
+        .and_then(|hash| hash.parse::<iroh_blobs::Hash>().ok());
+
+    // Download the file, fetching it from peers first if asked to
+    let result = match (stored_size, manifest_hash, max_retries) {
+        (None, Some(manifest_hash), _) => {
+            if let Some(meta) = metadata.as_ref() {
+                if let Some(drive) = state.drives.read().await.get(id.as_bytes()) {
+                    drive
+                        .check_file_size(&destination_path, meta.size)
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            let providers = match state.event_broadcaster.as_ref() {
+                Some(broadcaster) => broadcaster.neighbors(&id).await,
+                None => Vec::new(),
+            };
+            file_transfer
+                .download_chunked(
+                    &id,
+                    manifest_hash,
+                    &providers,
+                    &validated_path,
+                    &relative_path,
+                    expected_hash.as_deref(),
+                )
+                .await
+        }
+        (None, None, Some(retries)) => {
+            // Not stored yet, so the size limit is checked against the metadata
+            if let Some(meta) = metadata.as_ref() {
+                if let Some(drive) = state.drives.read().await.get(id.as_bytes()) {
+                    drive
+                        .check_file_size(&destination_path, meta.size)
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            let providers = match state.event_broadcaster.as_ref() {
+                Some(broadcaster) => broadcaster.neighbors(&id).await,
+                None => Vec::new(),
+            };
+            file_transfer
+                .download_with_retry(
+                    &id,
+                    blob_hash,
+                    &providers,
+                    &validated_path,
+                    &relative_path,
+                    expected_hash.as_deref(),
+                    retries.saturating_add(1),
+                )
+                .await
+        }
+        _ => {
+            file_transfer
+                .download_file(
+                    &id,
+                    blob_hash,
+                    &validated_path,
+                    &relative_path,
+                    expected_hash.as_deref(),
+                )
+                .await
+        }
+    };
+    track_access(&state, &id, &relative_path, &validated_path, FsOperation::Write, &result);
+    result.map_err(|e| match e.downcast_ref::<ContentMismatch>() {
+            Some(mismatch) => {
+                tracing::warn!(
+                    drive_id = %drive_id,
+                    path = %destination_path,
+                    expected = %mismatch.expected,
+                    actual = %mismatch.actual,
+                    "Downloaded content does not match metadata; discarded"
+                );
+                AppError::ContentMismatch {
+                    path: destination_path.clone(),
+                    expected: mismatch.expected.clone(),
+                    actual: mismatch.actual.clone(),
+                }
+                .to_string()
+            }
+            None => AppError::TransferFailed(format!("Download failed: {}", e)).to_string(),
+        })?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        hash = %hash,
+        path = %destination_path,
+        "Downloaded file"
+    );
+    Ok(())
+}
+
+/// List the recorded versions of a file, newest first
+///
+/// Each version has its content hash, size, and when it was modified and
+/// recorded. Deleted files keep their history, so they can be restored.
+/// Requires Read permission on the file.
+#[tauri::command]
+pub async fn get_file_history(
+    drive_id: String,
+    path: String,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<Vec<FileVersion>, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    // Validate the path is within drive root
+    let drives = state.drives.read().await;
+    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    let validated_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
+    let relative_path = validated_path
+        .strip_prefix(&drive.local_path)
+        .map_err(|_| AppError::PathOutsideDrive { path: path.clone() }.to_string())?
+        .to_path_buf();
+    let owner_hex = drive.owner.to_hex();
+    drop(drives);
+
+    let path_key = file::path_key(&relative_path);
+    check_file_permission(
+        &state,
+        &security,
+        &drive_id,
+        &owner_hex,
+        &path_key,
+        Permission::Read,
+    )
+    .await?;
+
+    docs_manager
+        .get_file_history(&id, &path_key)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())
+}
+
+/// Restore an earlier version of a file as its current content
+///
+/// The version's blob is fetched from the drive's peers if it is no longer
+/// stored locally, then written over the file. The restored content is
+/// recorded as a new version, so a restore can itself be undone. Returns
+/// that version. Requires Write permission on the file.
+#[tauri::command]
+pub async fn restore_version(
+    drive_id: String,
+    path: String,
+    version: u64,
+    state: State<'_, AppState>,
+    security: State<'_, Arc<SecurityStore>>,
+) -> Result<FileVersion, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    // Validate the path is within drive root
+    let drives = state.drives.read().await;
+    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+    let validated_path = validate_path(&drive.local_path, &path).map_err(|e| e.to_string())?;
+    let relative_path = validated_path
+        .strip_prefix(&drive.local_path)
+        .map_err(|_| AppError::PathOutsideDrive { path: path.clone() }.to_string())?
+        .to_path_buf();
+    let path_key = file::path_key(&relative_path);
+    check_file_permission(
+        &state,
+        &security,
+        &drive_id,
+        &drive.owner.to_hex(),
+        &path_key,
+        Permission::Write,
+    )
+    .await?;
+
+    let target = docs_manager
+        .get_file_version(&id, &path_key, version)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?
+        .ok_or_else(|| {
+            AppError::VersionNotFound {
+                path: path.clone(),
+                version,
+            }
+            .to_string()
+        })?;
+    drive
+        .check_file_size(&path, target.size)
+        .map_err(|e| e.to_string())?;
+    drop(drives);
+
+    let blob_hash = target
+        .content_hash
+        .parse::<iroh_blobs::Hash>()
+        .map_err(|e| AppError::InvalidHash(format!("Invalid hash: {}", e)).to_string())?;
+    let manifest_hash = target
+        .manifest_hash
+        .as_deref()
+        .and_then(|hash| hash.parse::<iroh_blobs::Hash>().ok());
+
+    // Old content is usually still stored; otherwise fetch it from peers
+    let result = if file_transfer.has_blob(&target.content_hash).await {
+        file_transfer
+            .download_file(
+                &id,
+                blob_hash,
+                &validated_path,
+                &relative_path,
+                Some(target.content_hash.as_str()),
+            )
+            .await
+    } else {
+        let providers = match state.event_broadcaster.as_ref() {
+            Some(broadcaster) => broadcaster.neighbors(&id).await,
+            None => Vec::new(),
+        };
+        match manifest_hash {
+            Some(manifest_hash) => {
+                file_transfer
+                    .download_chunked(
+                        &id,
+                        manifest_hash,
+                        &providers,
+                        &validated_path,
+                        &relative_path,
+                        Some(target.content_hash.as_str()),
+                    )
+                    .await
+            }
+            None => {
+                file_transfer
+                    .download_with_retry(
+                        &id,
+                        blob_hash,
+                        &providers,
+                        &validated_path,
+                        &relative_path,
+                        Some(target.content_hash.as_str()),
+                        RESTORE_FETCH_ATTEMPTS,
+                    )
+                    .await
+            }
+        }
+    };
+    track_access(
+        &state,
+        &id,
+        &relative_path,
+        &validated_path,
+        FsOperation::Write,
+        &result,
+    );
+    result.map_err(|e| {
+        AppError::TransferFailed(format!("Restoring version {} failed: {}", version, e)).to_string()
+    })?;
+
+    // Publish the restored content as the file's current version
+    let name = relative_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut meta = docs_manager
+        .get_file_metadata(&id, &path_key)
+        .await
+        .unwrap_or_else(|| FileMetadata::new(&path_key, &name, false, 0, &target.modified_at));
+    if meta.content_hash.as_deref() != Some(target.content_hash.as_str()) {
+        meta.previous_hash = meta.content_hash.take();
+    }
+    meta.size = target.size;
+    meta.modified_at = chrono::Utc::now().to_rfc3339();
+    meta.content_hash = Some(target.content_hash.clone());
+    meta.manifest_hash = target.manifest_hash.clone();
+    docs_manager
+        .set_file_metadata(&id, &meta)
+        .await
+        .map_err(|e| AppError::SyncFailed(e.to_string()).to_string())?;
+
+    let restored = docs_manager
+        .get_file_history(&id, &path_key)
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            AppError::Internal("Restored version was not recorded".into()).to_string()
+        })?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        path = %path,
+        version = version,
+        new_version = restored.version,
+        "Restored file version"
+    );
+    Ok(restored)
+}
+
+/// List all active transfers
+#[tauri::command]
+pub async fn list_transfers(state: State<'_, AppState>) -> Result<Vec<TransferState>, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    Ok(file_transfer.list_transfers().await)
+}
+
+/// Get blob store usage (blob count, total size, incomplete blobs)
+///
+/// Partial blobs are downloads that never completed and are candidates for
+/// cleanup. Stats are cached briefly, so repeated calls are cheap.
+#[tauri::command]
+pub async fn get_blob_store_stats(state: State<'_, AppState>) -> Result<BlobStoreStats, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    file_transfer
+        .blob_store_stats()
+        .await
+        .map_err(|e| format!("Failed to read blob store stats: {}", e))
+}
+
+/// Get download reliability scores for every peer we've fetched from
+///
+/// Scores combine recent success rate and download time, with older
+/// outcomes counting less; the highest scoring provider is tried first.
+#[tauri::command]
+pub async fn get_peer_metrics(state: State<'_, AppState>) -> Result<Vec<PeerMetrics>, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    Ok(file_transfer.reliability().metrics().await)
+}
+
+/// Get a specific transfer by ID
+///
+/// Running transfers include their recent speed and, when the size is
+/// known, an estimated time remaining.
+#[tauri::command]
+pub async fn get_transfer(
+    transfer_id: String,
+    state: State<'_, AppState>,
+) -> Result<Option<TransferState>, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    Ok(file_transfer.get_transfer(&transfer_id).await)
+}
+
+/// Cancel an active transfer
+#[tauri::command]
+pub async fn cancel_transfer(
+    transfer_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    file_transfer
+        .cancel_transfer(&transfer_id)
+        .await
+        .map_err(|e| AppError::TransferFailed(format!("Failed to cancel: {}", e)).to_string())?;
+
+    tracing::info!(transfer_id = %transfer_id, "Cancelled transfer");
+    Ok(())
+}
+
+/// Resume an upload that was interrupted or failed
+///
+/// Uploads left unfinished when the app stopped are listed as `Pending`
+/// after a restart. Returns the hash of the uploaded content.
+///
+/// # Security
+/// - Re-validates the upload's local file is within the drive root
+#[tauri::command]
+pub async fn resume_transfer(
+    transfer_id: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let file_transfer = state
+        .file_transfer
+        .as_ref()
+        .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
+
+    let transfer = file_transfer.get_transfer(&transfer_id).await.ok_or_else(|| {
+        AppError::ValidationError(format!("Transfer {} not found", transfer_id)).to_string()
+    })?;
+    let local_path = transfer.local_path.as_ref().ok_or_else(|| {
+        AppError::ValidationError(format!("Transfer {} cannot be resumed", transfer_id))
+            .to_string()
+    })?;
+
+    // The drive may have been removed or moved since the upload began
+    let id = parse_drive_id(&transfer.drive_id)?;
+    {
+        let drives = state.drives.read().await;
+        let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: transfer.drive_id.clone(),
+            }
+            .to_string()
+        })?;
+        drive.ensure_active().map_err(|e| e.to_string())?;
+        validate_path(&drive.local_path, &local_path.to_string_lossy())
+            .map_err(|e| e.to_string())?;
+    }
+    ensure_not_drained(&state, &id).await?;
+
+    let hash = file_transfer
+        .resume_transfer(&transfer_id)
+        .await
+        .map_err(|e| AppError::TransferFailed(format!("Resume failed: {}", e)).to_string())?;
+
+    tracing::info!(
+        transfer_id = %transfer_id,
+        hash = %hash.to_hex(),
+        "Resumed transfer"
+    );
+    Ok(hash.to_hex().to_string())
+}
+
+/// Import an external file into the drive
+///
+/// This copies a file from outside the drive into the drive's local folder,
+/// then uploads it to the blob store for P2P sharing.
+///
+/// # Arguments
+/// * `drive_id` - The drive to import into
+/// * `source_path` - The external file path to import from
+/// * `dest_name` - Optional destination filename (uses source filename if not provided)
+/// * `dest_folder` - Optional destination folder within drive (uses root if not provided)
+#[tauri::command]
+pub async fn import_file(
+    drive_id: String,
+    source_path: String,
+    dest_name: Option<String>,
+    dest_folder: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    // Check file transfer is available
     let file_transfer = state
         .file_transfer
         .as_ref()
         .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
 
-    Ok(file_transfer.list_transfers().await)
+    // Parse source path and validate it exists
+    let source = std::path::PathBuf::from(&source_path);
+    if !source.exists() {
+        return Err(format!("Source file does not exist: {}", source_path));
+    }
+    if !source.is_file() {
+        return Err(format!("Source is not a file: {}", source_path));
+    }
+    let source_size = std::fs::metadata(&source)
+        .map_err(|e| format!("Failed to read source file: {}", e))?
+        .len();
+
+    // Get drive to determine local path
+    let drives = state.drives.read().await;
+    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+        AppError::DriveNotFound {
+            drive_id: drive_id.clone(),
+        }
+        .to_string()
+    })?;
+    drive.ensure_active().map_err(|e| e.to_string())?;
+    drive
+        .check_file_size(&source_path, source_size)
+        .map_err(|e| e.to_string())?;
+    let drive_local_path = drive.local_path.clone();
+    let name_policy = drive.name_policy;
+    drop(drives);
+
+    // Determine destination filename
+    let file_name = dest_name.unwrap_or_else(|| {
+        source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("imported_file")
+            .to_string()
+    });
+
+    // Sanitize the filename to prevent path traversal
+    let safe_name: String = file_name
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '.' || *c == '-' || *c == '_' || *c == ' ')
+        .collect();
+    if safe_name.is_empty() {
+        return Err("Invalid destination filename".to_string());
+    }
+    let safe_name = name_policy.apply(&safe_name);
+
+    // Build destination path
+    let mut dest_path = drive_local_path.clone();
+    if let Some(folder) = dest_folder {
+        // Sanitize folder path
+        let folder_path = std::path::PathBuf::from(&folder);
+        for component in folder_path.components() {
+            match component {
+                std::path::Component::Normal(name) => {
+                    if let Some(name_str) = name.to_str() {
+                        dest_path.push(name_policy.apply(name_str));
+                    }
+                }
+                _ => {} // Skip .., /, etc.
+            }
+        }
+    }
+    dest_path.push(&safe_name);
+
+    // Create parent directories if needed
+    if let Some(parent) = dest_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            format!("Failed to create destination directory: {}", e)
+        })?;
+    }
+
+    // Copy the file
+    std::fs::copy(&source, &dest_path).map_err(|e| {
+        format!("Failed to copy file: {}", e)
+    })?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        source = %source_path,
+        dest = %dest_path.display(),
+        "Imported file into drive"
+    );
+
+    // Now upload the copied file
+    let relative_path = dest_path
+        .strip_prefix(&drive_local_path)
+        .map(|p| p.to_path_buf())
+        .unwrap_or_else(|_| std::path::PathBuf::from(&safe_name));
+
+    let hash = file_transfer
+        .upload_file(&id, &dest_path, &relative_path)
+        .await
+        .map_err(|e| AppError::TransferFailed(format!("Upload failed: {}", e)).to_string())?;
+
+    tracing::info!(
+        drive_id = %drive_id,
+        path = %dest_path.display(),
+        hash = %hash.to_hex(),
+        "Uploaded imported file"
+    );
+
+    Ok(hash.to_hex().to_string())
+}
+
+// ==============================================
+// Consistency Commands
+// ==============================================
+
+use crate::core::consistency::{
+    classify_blob_mismatch, find_inconsistencies, BlobMatchReport, BlobMismatch, BlobMismatchKind,
+    BlobRepair, ConsistencyReport, InconsistencyKind,
+};
+use crate::core::gap::compute_gap;
+use crate::core::{MetadataSummary, PeerGap};
+use crate::core::{content_type, file, DriveEvent};
+use crate::network::summary::{request_summary, request_summary_entries};
+use crate::network::{ReimportFailure, ReimportReport, SyncEngine};
+use iroh_docs::NamespaceId;
+use std::collections::{HashMap, HashSet};
+use tauri::Manager;
+
+/// Get a Merkle root over a drive's `(path, content_hash)` pairs
+///
+/// Two peers with the same root have identical file sets, so comparing
+/// roots is a cheap check that sync is complete; only differing roots need
+/// a file-by-file diff. See `network::docs::merkle_root` for the exact
+/// construction. The root is cached until the drive's metadata changes.
+#[tauri::command]
+pub async fn get_drive_merkle_root(
+    drive_id: String,
+    state: State<'_, AppState>,
+) -> Result<DriveMerkleRoot, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    if !state.drives.read().await.contains_key(id.as_bytes()) {
+        return Err(AppError::DriveNotFound { drive_id }.to_string());
+    }
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    docs_manager
+        .merkle_root(&id)
+        .await
+        .map_err(|e| {
+            AppError::SyncFailed(format!("Failed to compute Merkle root: {}", e)).to_string()
+        })
+}
+
+/// Find the files a peer is missing compared to us
+///
+/// Compares the files we hold against the files `peer_id` holds so they can
+/// be seeded to a lagging peer (see `core::gap`). If `peer_root` matches
+/// the root over our files, the peer is up to date and isn't contacted. A
+/// `peer_summary` the peer shared saves asking for it; otherwise the peer
+/// is asked directly. Files the peer didn't list in time are reported
+/// as unconfirmed.
+///
+/// # Errors
+/// `PeerUnreachable` if the peer doesn't answer the summary request.
+#[tauri::command]
+pub async fn compute_peer_gap(
+    drive_id: String,
+    peer_id: String,
+    peer_root: Option<String>,
+    peer_summary: Option<MetadataSummary>,
+    state: State<'_, AppState>,
+) -> Result<PeerGap, String> {
+    let id = parse_drive_id(&drive_id)?;
+    let peer = crate::crypto::NodeId::from_hex(&peer_id).map_err(|_| {
+        AppError::ValidationFailed {
+            field: "peer_id".to_string(),
+            reason: "must be a hex-encoded node ID".to_string(),
+        }
+        .to_string()
+    })?;
+
+    let root = state
+        .drives
+        .read()
+        .await
+        .get(id.as_bytes())
+        .map(|d| d.local_path.clone())
+        .ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let ours = sync_engine
+        .held_files(&id, &root)
+        .await
+        .map_err(|e| AppError::SyncFailed(e.to_string()).to_string())?;
+    let local = MetadataSummary::from_files(&ours);
+
+    let mut gap = PeerGap {
+        drive_id: drive_id.clone(),
+        peer_id: peer_id.clone(),
+        local_root: local.root.clone(),
+        peer_root: local.root.clone(),
+        files: Vec::new(),
+        complete: true,
+    };
+    if peer_root.is_some_and(|theirs| theirs.eq_ignore_ascii_case(&local.root)) {
+        return Ok(gap);
+    }
+
+    if !sync_engine.is_syncing(&id).await {
+        return Err(AppError::SyncFailed("drive is not syncing".to_string()).to_string());
+    }
+    let endpoint = state
+        .endpoint
+        .get_endpoint()
+        .await
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let unreachable = || AppError::PeerUnreachable { peer: peer_id.clone() }.to_string();
+    let summary = match peer_summary {
+        Some(summary) => summary,
+        None => request_summary(&endpoint, &id, peer)
+            .await
+            .ok_or_else(unreachable)?,
+    };
+    gap.peer_root = summary.root.clone();
+
+    let differing = local.differing_buckets(&summary);
+    if differing.is_empty() {
+        return Ok(gap);
+    }
+    let (peer_entries, resolved) =
+        request_summary_entries(&endpoint, &id, peer, differing.clone()).await;
+
+    gap.files = compute_gap(&ours, &differing, &peer_entries, &resolved);
+    gap.complete = resolved.len() == differing.len();
+
+    tracing::info!(
+        drive_id = %drive_id,
+        peer = %peer_id,
+        missing = gap.files.len(),
+        complete = gap.complete,
+        "Computed peer gap"
+    );
+    Ok(gap)
+}
+
+/// List docs namespaces that aren't mapped to any drive
+///
+/// These are left behind by drive deletions and interrupted setups and
+/// still take up storage. Use `cleanup_orphan_namespaces` to remove them.
+#[tauri::command]
+pub async fn list_orphan_namespaces(
+    state: State<'_, AppState>,
+) -> Result<Vec<OrphanNamespace>, String> {
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let drives = known_drives(&state).await;
+    docs_manager.orphan_namespaces(&drives).await.map_err(|e| {
+        AppError::SyncFailed(format!("Failed to list orphaned namespaces: {}", e)).to_string()
+    })
+}
+
+/// Remove orphaned docs namespaces with their entries
+///
+/// `namespace_ids` are hex IDs from `list_orphan_namespaces` and `confirm`
+/// must be true. Each namespace is checked again before removal, so one
+/// that has since been mapped to a drive is skipped. Returns the IDs that
+/// were removed.
+#[tauri::command]
+pub async fn cleanup_orphan_namespaces(
+    namespace_ids: Vec<String>,
+    confirm: bool,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, String> {
+    if !confirm {
+        return Err(AppError::ValidationFailed {
+            field: "confirm".to_string(),
+            reason: "Removing namespaces deletes their data; pass confirm = true".to_string(),
+        }
+        .to_string());
+    }
+
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let mut parsed = Vec::with_capacity(namespace_ids.len());
+    for id in &namespace_ids {
+        let bytes = validate_drive_id(id).map_err(|_| {
+            AppError::ValidationFailed {
+                field: "namespace_ids".to_string(),
+                reason: format!("Invalid namespace ID: {}", id),
+            }
+            .to_string()
+        })?;
+        parsed.push((id, NamespaceId::from(&bytes)));
+    }
+
+    let drives = known_drives(&state).await;
+    let mut removed = Vec::new();
+    for (id, namespace_id) in parsed {
+        match docs_manager.remove_orphan_namespace(namespace_id, &drives).await {
+            Ok(()) => removed.push(id.clone()),
+            Err(e) => tracing::warn!(namespace = %id, error = %e, "Skipped namespace cleanup"),
+        }
+    }
+
+    tracing::info!(removed = removed.len(), "Cleaned up orphaned namespaces");
+    Ok(removed)
+}
+
+/// Every drive the app knows about, for orphan checks
+///
+/// Includes deleted drives that can still be restored, so their
+/// namespaces aren't cleaned up from under them.
+async fn known_drives(state: &AppState) -> HashSet<DriveId> {
+    let mut drives: HashSet<DriveId> =
+        state.drives.read().await.keys().map(|id| DriveId(*id)).collect();
+    match crate::core::drive::load_deleted_drives(&state.db) {
+        Ok(deleted) => drives.extend(deleted.into_iter().map(|d| d.id)),
+        Err(e) => tracing::warn!("Failed to load deleted drives: {}", e),
+    }
+    drives
+}
+
+// ==============================================
+// Namespace Mapping Commands
+// ==============================================
+
+use crate::crypto::InviteToken;
+use crate::network::{DocMappingCheck, DocMappingStatus};
+use iroh_docs::DocTicket;
+
+/// Check that drives are mapped to a docs namespace that opens
+///
+/// Checks one drive, or every drive if `drive_id` is omitted. A drive whose
+/// mapping is missing, corrupt or unopenable can't sync; fix it with
+/// `repair_drive_docs_mapping`.
+#[tauri::command]
+pub async fn verify_drive_docs_mapping(
+    drive_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DocMappingCheck>, String> {
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+
+    let ids: Vec<DriveId> = match &drive_id {
+        Some(drive_id) => {
+            let id = parse_drive_id(drive_id)?;
+            if !state.drives.read().await.contains_key(id.as_bytes()) {
+                return Err(AppError::DriveNotFound {
+                    drive_id: drive_id.clone(),
+                }
+                .to_string());
+            }
+            vec![id]
+        }
+        None => state.drives.read().await.keys().map(|id| DriveId(*id)).collect(),
+    };
+
+    let mut checks = Vec::with_capacity(ids.len());
+    for id in ids {
+        let check = docs_manager
+            .verify_mapping(&id)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+        if check.status != DocMappingStatus::Ok {
+            tracing::warn!(drive_id = %id, status = ?check.status, "Broken doc namespace mapping");
+        }
+        checks.push(check);
+    }
+    Ok(checks)
+}
+
+/// Repair a drive whose namespace mapping is broken
+///
+/// Owners get a new doc seeded from the locally stored metadata; members
+/// must pass a fresh `invite_token` for the drive and re-join its doc.
+/// Members that joined through the old namespace need a new invite after
+/// an owner repair. Fails if the mapping is not broken.
+#[tauri::command]
+pub async fn repair_drive_docs_mapping(
+    drive_id: String,
+    invite_token: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<DocMappingCheck, String> {
+    let id = parse_drive_id(&drive_id)?;
+    let drive = state
+        .drives
+        .read()
+        .await
+        .get(id.as_bytes())
+        .cloned()
+        .ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
+            .to_string()
+        })?;
+    let docs_manager = state
+        .docs_manager
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let node_id = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+
+    let check = docs_manager
+        .verify_mapping(&id)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+    if check.status == DocMappingStatus::Ok {
+        return Err(AppError::ValidationFailed {
+            field: "drive_id".to_string(),
+            reason: "Drive's namespace mapping is not broken".to_string(),
+        }
+        .to_string());
+    }
+
+    // Members need a ticket before anything is torn down
+    let ticket = if drive.owner == node_id {
+        None
+    } else {
+        let token = invite_token.ok_or_else(|| {
+            AppError::ValidationFailed {
+                field: "invite_token".to_string(),
+                reason: "A fresh invite is needed to re-join a drive you don't own".to_string(),
+            }
+            .to_string()
+        })?;
+        Some(repair_ticket(&token, &drive_id)?)
+    };
+
+    docs_manager
+        .forget_mapping(&id)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())?;
+    let result = match ticket {
+        None => sync_engine.init_drive(&drive).await,
+        Some(ticket) => sync_engine.join_drive(id, ticket).await,
+    };
+    result.map_err(|e| AppError::SyncFailed(format!("Failed to repair drive: {}", e)).to_string())?;
+
+    tracing::info!(drive_id = %drive_id, was = ?check.status, "Repaired doc namespace mapping");
+    docs_manager
+        .verify_mapping(&id)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()).to_string())
 }
 
-/// Get a specific transfer by ID
+/// Doc ticket from a verified, unexpired invite for `drive_id`
+fn repair_ticket(token: &str, drive_id: &str) -> Result<DocTicket, String> {
+    let invalid = |reason: &str| {
+        AppError::ValidationFailed {
+            field: "invite_token".to_string(),
+            reason: reason.to_string(),
+        }
+        .to_string()
+    };
+
+    let token = InviteToken::from_string(token.trim()).map_err(|e| invalid(&e.to_string()))?;
+    token
+        .verify_inviter_signature()
+        .map_err(|_| invalid("Invalid invite signature"))?;
+    if token.is_expired() {
+        return Err(invalid("Invite has expired"));
+    }
+    if !token.payload.drive_id.eq_ignore_ascii_case(drive_id) {
+        return Err(invalid("Invite is for a different drive"));
+    }
+    token
+        .payload
+        .doc_ticket
+        .as_deref()
+        .ok_or_else(|| invalid("Invite is missing sync ticket"))?
+        .parse::<DocTicket>()
+        .map_err(|e| invalid(&format!("Invalid sync ticket in invite: {}", e)))
+}
+
+/// Find mismatches between a drive's local files and its synced metadata
+///
+/// Reports local files without metadata, and metadata whose file is missing
+/// (split by whether the blob is still stored locally). With `repair`,
+/// metadata is regenerated from local files and missing files are restored
+/// from local blobs. Entries whose content is not available locally get a
+/// background download from the drive's peers, reported as `fetch_queued`.
+/// Each repair needs Write permission on its path.
+///
+/// Can be stopped with `cancel_operation(operation_id)`; each repair either
+/// completes or is not started, and the report covers what was done.
 #[tauri::command]
-pub async fn get_transfer(
-    transfer_id: String,
+pub async fn check_drive_consistency(
+    drive_id: String,
+    repair: Option<bool>,
+    operation_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Option<TransferState>, String> {
+    security: State<'_, Arc<SecurityStore>>,
+    operations: State<'_, Arc<OperationRegistry>>,
+) -> Result<ConsistencyReport, String> {
+    let id = parse_drive_id(&drive_id)?;
+    let repair = repair.unwrap_or(false);
+
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
     let file_transfer = state
         .file_transfer
         .as_ref()
         .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
 
-    Ok(file_transfer.get_transfer(&transfer_id).await)
+    let (root, owner_hex) = {
+        let drives = state.drives.read().await;
+        drives
+            .get(id.as_bytes())
+            .map(|d| (d.local_path.clone(), d.owner.to_hex()))
+            .ok_or_else(|| {
+                AppError::DriveNotFound {
+                    drive_id: drive_id.clone(),
+                }
+                .to_string()
+            })?
+    };
+
+    // Local files (directories carry no content to compare)
+    let local_files: HashSet<String> = {
+        let root = root.clone();
+        tokio::task::spawn_blocking(move || file::index_directory(&root))
+            .await
+            .map_err(|e| format!("Indexing task failed: {}", e))?
+            .map_err(|e| format!("Failed to index drive: {}", e))?
+            .into_iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| file::path_key(&entry.path))
+            .collect()
+    };
+
+    let mut manifests: HashMap<String, iroh_blobs::Hash> = HashMap::new();
+    let metadata: Vec<(String, Option<String>)> = sync_engine
+        .docs_manager()
+        .get_all_metadata(&id)
+        .await
+        .map_err(|e| format!("Failed to load metadata: {}", e))?
+        .into_iter()
+        .filter(|meta| !meta.is_dir)
+        .map(|meta| {
+            let path = meta.path.replace('\\', "/");
+            if let Some(manifest) = meta.manifest_hash.and_then(|m| m.parse().ok()) {
+                manifests.insert(path.clone(), manifest);
+            }
+            (path, meta.content_hash)
+        })
+        .collect();
+
+    let operation = operations
+        .start(operation_id, "check_drive_consistency")
+        .map_err(|e| e.to_string())?;
+
+    // Only entries without a local file need a blob lookup; content
+    // uploaded in chunks counts as stored if all its chunks are
+    let mut local_blobs = HashSet::new();
+    for (path, hash) in &metadata {
+        if operation.is_cancelled() {
+            break;
+        }
+        if let Some(hash) = hash {
+            let manifest = manifests.get(path).map(|m| m.to_hex().to_string());
+            if !local_files.contains(path)
+                && file_transfer.has_content(hash, manifest.as_deref()).await
+            {
+                local_blobs.insert(hash.clone());
+            }
+        }
+    }
+
+    let mut inconsistencies = if operation.is_cancelled() {
+        Vec::new()
+    } else {
+        find_inconsistencies(&local_files, &metadata, &local_blobs)
+    };
+
+    if repair && !inconsistencies.is_empty() {
+        let node_id = state
+            .identity_manager
+            .node_id()
+            .await
+            .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+        let providers = match state.event_broadcaster.as_ref() {
+            Some(broadcaster) => broadcaster.neighbors(&id).await,
+            None => Vec::new(),
+        };
+
+        for item in inconsistencies.iter_mut() {
+            if operation.is_cancelled() {
+                break;
+            }
+
+            // Metadata paths come from peers, so never trust them unvalidated
+            let absolute = match validate_path(&root, &item.path) {
+                Ok(path) => path,
+                Err(e) => {
+                    item.error = Some(e.to_string());
+                    continue;
+                }
+            };
+            if let Err(e) = check_file_permission(
+                &state,
+                &security,
+                &drive_id,
+                &owner_hex,
+                &item.path,
+                Permission::Write,
+            )
+            .await
+            {
+                item.error = Some(e);
+                continue;
+            }
+            let relative = std::path::PathBuf::from(&item.path);
+
+            let result = match item.kind {
+                InconsistencyKind::MissingMetadata => {
+                    regenerate_metadata(sync_engine, &id, &absolute, relative.clone(), node_id)
+                        .await
+                }
+                InconsistencyKind::MissingFile => match item
+                    .content_hash
+                    .as_deref()
+                    .map(|h| h.parse::<iroh_blobs::Hash>())
+                {
+                    Some(Ok(hash)) => {
+                        let result = restore_stored_content(
+                            file_transfer,
+                            &id,
+                            hash,
+                            manifests.get(&item.path).copied(),
+                            &absolute,
+                            &relative,
+                            item.content_hash.as_deref(),
+                        )
+                        .await;
+                        let operation = FsOperation::Write;
+                        track_access(&state, &id, &relative, &absolute, operation, &result);
+                        result.map_err(|e| e.to_string())
+                    }
+                    _ => Err("invalid content hash".to_string()),
+                },
+                InconsistencyKind::MissingContent => match item
+                    .content_hash
+                    .as_deref()
+                    .map(|h| h.parse::<iroh_blobs::Hash>())
+                {
+                    Some(Ok(_)) if providers.is_empty() => {
+                        Err("content is not stored locally and no peer is connected".to_string())
+                    }
+                    Some(Ok(hash)) => {
+                        queue_content_fetch(
+                            &state,
+                            id,
+                            hash,
+                            manifests.get(&item.path).copied(),
+                            providers.clone(),
+                            absolute,
+                            relative,
+                            item.content_hash.clone(),
+                        );
+                        item.fetch_queued = true;
+                        continue;
+                    }
+                    _ => Err("invalid content hash".to_string()),
+                },
+            };
+
+            match result {
+                Ok(()) => item.repaired = true,
+                Err(e) => item.error = Some(e),
+            }
+        }
+    }
+
+    let repaired = inconsistencies.iter().filter(|i| i.repaired).count();
+
+    tracing::info!(
+        drive_id = %drive_id,
+        found = inconsistencies.len(),
+        repaired = repaired,
+        cancelled = operation.is_cancelled(),
+        "Checked drive consistency"
+    );
+
+    Ok(ConsistencyReport {
+        drive_id,
+        files_checked: local_files.len(),
+        metadata_checked: metadata.len(),
+        inconsistencies,
+        repaired,
+        cancelled: operation.is_cancelled(),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    })
 }
 
-/// Cancel an active transfer
+/// Attempts at fetching content a consistency repair found missing
+const CONSISTENCY_FETCH_ATTEMPTS: u32 = 3;
+
+/// Download missing content from `providers` in the background
+///
+/// Content uploaded in chunks is fetched through its manifest. The download
+/// shows up as a transfer; a permission error writing the file is recorded
+/// like any other sync write.
+#[allow(clippy::too_many_arguments)]
+fn queue_content_fetch(
+    state: &AppState,
+    id: DriveId,
+    hash: iroh_blobs::Hash,
+    manifest_hash: Option<iroh_blobs::Hash>,
+    providers: Vec<iroh::NodeId>,
+    absolute: std::path::PathBuf,
+    relative: std::path::PathBuf,
+    expected_hash: Option<String>,
+) {
+    let (Some(file_transfer), Some(sync_engine)) =
+        (state.file_transfer.clone(), state.sync_engine.clone())
+    else {
+        return;
+    };
+    tokio::spawn(async move {
+        let result = match manifest_hash {
+            Some(manifest_hash) => {
+                file_transfer
+                    .download_chunked(
+                        &id,
+                        manifest_hash,
+                        &providers,
+                        &absolute,
+                        &relative,
+                        expected_hash.as_deref(),
+                    )
+                    .await
+            }
+            None => {
+                file_transfer
+                    .download_with_retry(
+                        &id,
+                        hash,
+                        &providers,
+                        &absolute,
+                        &relative,
+                        expected_hash.as_deref(),
+                        CONSISTENCY_FETCH_ATTEMPTS,
+                    )
+                    .await
+            }
+        };
+        let errors = sync_engine.permission_errors();
+        match &result {
+            Ok(()) => errors.clear(&id, &relative),
+            Err(e) => {
+                tracing::warn!(path = %relative.display(), "Fetching missing content failed: {}", e);
+                if let Some(io) = permission_denied_in(e) {
+                    errors.record(&id, &relative, &absolute, FsOperation::Write, io);
+                }
+            }
+        }
+    });
+}
+
+/// Write a file's stored content to `absolute`
+///
+/// Content uploaded in chunks is put together from `manifest_hash` unless
+/// it is also stored as a whole blob.
+async fn restore_stored_content(
+    file_transfer: &FileTransferManager,
+    id: &DriveId,
+    hash: iroh_blobs::Hash,
+    manifest_hash: Option<iroh_blobs::Hash>,
+    absolute: &std::path::Path,
+    relative: &std::path::Path,
+    expected_hash: Option<&str>,
+) -> anyhow::Result<()> {
+    match manifest_hash {
+        Some(manifest_hash) if !file_transfer.has_blob(&hash.to_hex()).await => {
+            file_transfer
+                .download_chunked(id, manifest_hash, &[], absolute, relative, expected_hash)
+                .await
+        }
+        _ => {
+            file_transfer
+                .download_file(id, hash, absolute, relative, expected_hash)
+                .await
+        }
+    }
+}
+
+/// Files re-imported between `ReimportProgress` events
+const REIMPORT_PROGRESS_INTERVAL: usize = 25;
+
+/// Re-import a drive's local files into the blob store
+///
+/// Rebuilds the blob store from the files on disk after it was lost, e.g.
+/// when the data directory moved, so peers can fetch them again. Files whose
+/// blobs are still stored are skipped and metadata is left untouched. Files
+/// whose content doesn't hash to their metadata's content hash are imported
+/// anyway and reported as mismatches. Progress is emitted as
+/// `ReimportProgress` drive events.
+///
+/// Can be stopped with `cancel_operation(operation_id)`; files imported so
+/// far stay in the store.
 #[tauri::command]
-pub async fn cancel_transfer(
-    transfer_id: String,
+pub async fn reimport_drive_blobs(
+    drive_id: String,
+    operation_id: Option<String>,
+    app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+    operations: State<'_, Arc<OperationRegistry>>,
+) -> Result<ReimportReport, String> {
+    let id = parse_drive_id(&drive_id)?;
+
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
     let file_transfer = state
         .file_transfer
         .as_ref()
         .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
 
-    file_transfer
-        .cancel_transfer(&transfer_id)
+    let root = {
+        let drives = state.drives.read().await;
+        drives
+            .get(id.as_bytes())
+            .map(|d| d.local_path.clone())
+            .ok_or_else(|| {
+                AppError::DriveNotFound {
+                    drive_id: drive_id.clone(),
+                }
+                .to_string()
+            })?
+    };
+
+    let local_files: Vec<std::path::PathBuf> = {
+        let root = root.clone();
+        tokio::task::spawn_blocking(move || file::index_directory(&root))
+            .await
+            .map_err(|e| format!("Indexing task failed: {}", e))?
+            .map_err(|e| format!("Failed to index drive: {}", e))?
+            .into_iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| entry.path)
+            .collect()
+    };
+
+    let expected_hashes: HashMap<String, String> = sync_engine
+        .docs_manager()
+        .get_all_metadata(&id)
         .await
-        .map_err(|e| AppError::TransferFailed(format!("Failed to cancel: {}", e)).to_string())?;
+        .map_err(|e| format!("Failed to load metadata: {}", e))?
+        .into_iter()
+        .filter(|meta| !meta.is_dir)
+        .filter_map(|meta| Some((meta.path.replace('\\', "/"), meta.content_hash?)))
+        .collect();
 
-    tracing::info!(transfer_id = %transfer_id, "Cancelled transfer");
-    Ok(())
+    let operation = operations
+        .start(operation_id, "reimport_drive_blobs")
+        .map_err(|e| e.to_string())?;
+
+    let total = local_files.len();
+    let mut report = ReimportReport {
+        drive_id: drive_id.clone(),
+        files_checked: 0,
+        imported: 0,
+        already_stored: 0,
+        mismatches: Vec::new(),
+        failed: Vec::new(),
+        cancelled: false,
+        completed_at: String::new(),
+    };
+
+    for relative in local_files {
+        if operation.is_cancelled() {
+            break;
+        }
+
+        let absolute = root.join(&relative);
+        let key = file::path_key(&relative);
+        let result = file_transfer.reimport_file(&absolute).await;
+        track_access(&state, &id, &relative, &absolute, FsOperation::Read, &result);
+
+        match result {
+            Ok((hash, imported)) => {
+                if imported {
+                    report.imported += 1;
+                } else {
+                    report.already_stored += 1;
+                }
+                let actual = hash.to_hex().to_string();
+                if let Some(expected) = expected_hashes.get(&key) {
+                    if !expected.eq_ignore_ascii_case(&actual) {
+                        report.mismatches.push(ContentMismatch {
+                            path: relative,
+                            expected: expected.clone(),
+                            actual,
+                        });
+                    }
+                }
+            }
+            Err(e) => report.failed.push(ReimportFailure {
+                path: key,
+                error: e.to_string(),
+            }),
+        }
+
+        report.files_checked += 1;
+        let done = report.files_checked;
+        if done % REIMPORT_PROGRESS_INTERVAL == 0 || done == total {
+            let event = DriveEvent::ReimportProgress {
+                files_processed: done,
+                total_files: total,
+                imported: report.imported,
+            };
+            let dto = DriveEventDto::from_event(&drive_id, &event);
+            if let Some(subscriptions) = app.try_state::<Arc<EventSubscriptions>>() {
+                if let Err(e) = subscriptions.publish(&app, &dto) {
+                    tracing::warn!("Failed to emit re-import progress: {}", e);
+                }
+            }
+        }
+    }
+
+    report.cancelled = operation.is_cancelled();
+    report.completed_at = chrono::Utc::now().to_rfc3339();
+
+    tracing::info!(
+        drive_id = %drive_id,
+        checked = report.files_checked,
+        imported = report.imported,
+        mismatches = report.mismatches.len(),
+        failed = report.failed.len(),
+        cancelled = report.cancelled,
+        "Re-imported drive blobs"
+    );
+
+    Ok(report)
 }
 
-/// Import an external file into the drive
+/// Find files whose local content and stored blob disagree
 ///
-/// This copies a file from outside the drive into the drive's local folder,
-/// then uploads it to the blob store for P2P sharing.
+/// For each local file with metadata, compares the file's hash and the
+/// stored blob against the content hash in metadata. Mismatches are
+/// reported as `local_newer` (edited but not re-imported), `blob_newer`
+/// (metadata points to newer content than the file), `blob_corrupt` (the
+/// file is fine but the stored blob is missing or damaged) or
+/// `both_differ`. Content uploaded in chunks is checked chunk by chunk.
+/// Files without metadata are left to `check_drive_consistency`.
 ///
-/// # Arguments
-/// * `drive_id` - The drive to import into
-/// * `source_path` - The external file path to import from
-/// * `dest_name` - Optional destination filename (uses source filename if not provided)
-/// * `dest_folder` - Optional destination folder within drive (uses root if not provided)
+/// With `repair`, `reimport_local` imports the file and points metadata at
+/// it, while `export_blob` overwrites the file with the stored blob (only
+/// possible if the blob is intact). `paths` limits the check to those files.
+///
+/// Can be stopped with `cancel_operation(operation_id)`.
 #[tauri::command]
-pub async fn import_file(
+pub async fn verify_local_matches_blobs(
     drive_id: String,
-    source_path: String,
-    dest_name: Option<String>,
-    dest_folder: Option<String>,
+    repair: Option<BlobRepair>,
+    paths: Option<Vec<String>>,
+    operation_id: Option<String>,
     state: State<'_, AppState>,
-) -> Result<String, String> {
+    operations: State<'_, Arc<OperationRegistry>>,
+) -> Result<BlobMatchReport, String> {
     let id = parse_drive_id(&drive_id)?;
 
-    // Check file transfer is available
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
     let file_transfer = state
         .file_transfer
         .as_ref()
         .ok_or_else(|| AppError::TransferNotInitialized.to_string())?;
 
-    // Get drive to determine local path
-    let drives = state.drives.read().await;
-    let drive = drives.get(id.as_bytes()).ok_or_else(|| {
-        AppError::DriveNotFound {
-            drive_id: drive_id.clone(),
-        }
-        .to_string()
-    })?;
-    let drive_local_path = drive.local_path.clone();
-    drop(drives);
-
-    // Parse source path and validate it exists
-    let source = std::path::PathBuf::from(&source_path);
-    if !source.exists() {
-        return Err(format!("Source file does not exist: {}", source_path));
-    }
-    if !source.is_file() {
-        return Err(format!("Source is not a file: {}", source_path));
-    }
-
-    // Determine destination filename
-    let file_name = dest_name.unwrap_or_else(|| {
-        source
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("imported_file")
+    let root = {
+        let drives = state.drives.read().await;
+        let drive = drives.get(id.as_bytes()).ok_or_else(|| {
+            AppError::DriveNotFound {
+                drive_id: drive_id.clone(),
+            }
             .to_string()
+        })?;
+        if repair.is_some() {
+            drive.ensure_active().map_err(|e| e.to_string())?;
+        }
+        drive.local_path.clone()
+    };
+
+    let only: Option<HashSet<String>> = paths.map(|paths| {
+        paths
+            .iter()
+            .map(|p| p.replace('\\', "/").trim_matches('/').to_string())
+            .collect()
     });
 
-    // Sanitize the filename to prevent path traversal
-    let safe_name: String = file_name
-        .chars()
-        .filter(|c| c.is_alphanumeric() || *c == '.' || *c == '-' || *c == '_' || *c == ' ')
+    // Path key -> (content hash, modified time) for files with content
+    let mut manifests: HashMap<String, iroh_blobs::Hash> = HashMap::new();
+    let metadata: HashMap<String, (String, String)> = sync_engine
+        .docs_manager()
+        .get_all_metadata(&id)
+        .await
+        .map_err(|e| format!("Failed to load metadata: {}", e))?
+        .into_iter()
+        .filter(|meta| !meta.is_dir)
+        .filter_map(|meta| {
+            let hash = meta.content_hash?;
+            let path = meta.path.replace('\\', "/");
+            if let Some(manifest) = meta.manifest_hash.and_then(|m| m.parse().ok()) {
+                manifests.insert(path.clone(), manifest);
+            }
+            Some((path, (hash, meta.modified_at)))
+        })
+        .filter(|(path, _)| match &only {
+            Some(only) => only.contains(path),
+            None => true,
+        })
         .collect();
-    if safe_name.is_empty() {
-        return Err("Invalid destination filename".to_string());
+
+    let local_files: Vec<std::path::PathBuf> = {
+        let root = root.clone();
+        tokio::task::spawn_blocking(move || file::index_directory(&root))
+            .await
+            .map_err(|e| format!("Indexing task failed: {}", e))?
+            .map_err(|e| format!("Failed to index drive: {}", e))?
+            .into_iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| entry.path)
+            .filter(|path| metadata.contains_key(&file::path_key(path)))
+            .collect()
+    };
+
+    let operation = operations
+        .start(operation_id, "verify_local_matches_blobs")
+        .map_err(|e| e.to_string())?;
+
+    let mut files_checked = 0;
+    let mut mismatches = Vec::new();
+    for relative in local_files {
+        if operation.is_cancelled() {
+            break;
+        }
+        let key = file::path_key(&relative);
+        let Some((metadata_hash, modified_at)) = metadata.get(&key) else {
+            continue;
+        };
+        let absolute = root.join(&relative);
+
+        let hashed = {
+            let path = absolute.clone();
+            tokio::task::spawn_blocking(move || file::hash_file(&path))
+                .await
+                .map_err(|e| format!("Hashing task failed: {}", e))?
+                .map_err(anyhow::Error::from)
+        };
+        track_access(&state, &id, &relative, &absolute, FsOperation::Read, &hashed);
+        let Ok(local_hash) = hashed else {
+            continue;
+        };
+        files_checked += 1;
+
+        let blob_intact = match metadata_hash.parse::<iroh_blobs::Hash>() {
+            Ok(hash) => file_transfer
+                .verify_content_blobs(hash, manifests.get(&key).copied())
+                .await
+                .unwrap_or(false),
+            Err(_) => false,
+        };
+        let local_modified = tokio::fs::metadata(&absolute)
+            .await
+            .and_then(|m| m.modified())
+            .map(chrono::DateTime::<chrono::Utc>::from);
+        let local_newer = match (
+            local_modified,
+            chrono::DateTime::parse_from_rfc3339(modified_at),
+        ) {
+            (Ok(local), Ok(recorded)) => local > recorded,
+            // Without a usable time, assume the file on disk is the edit
+            _ => true,
+        };
+
+        if let Some(kind) =
+            classify_blob_mismatch(&local_hash, metadata_hash, blob_intact, local_newer)
+        {
+            mismatches.push(BlobMismatch {
+                path: key,
+                kind,
+                local_hash,
+                metadata_hash: metadata_hash.clone(),
+                repaired: false,
+                error: None,
+            });
+        }
     }
 
-    // Build destination path
-    let mut dest_path = drive_local_path.clone();
-    if let Some(folder) = dest_folder {
-        // Sanitize folder path
-        let folder_path = std::path::PathBuf::from(&folder);
-        for component in folder_path.components() {
-            match component {
-                std::path::Component::Normal(name) => {
-                    if let Some(name_str) = name.to_str() {
-                        dest_path.push(name_str);
+    if let Some(repair) = repair.filter(|_| !mismatches.is_empty()) {
+        let node_id = state
+            .identity_manager
+            .node_id()
+            .await
+            .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+
+        for item in mismatches.iter_mut() {
+            if operation.is_cancelled() {
+                break;
+            }
+            let absolute = match validate_path(&root, &item.path) {
+                Ok(path) => path,
+                Err(e) => {
+                    item.error = Some(e.to_string());
+                    continue;
+                }
+            };
+            let relative = std::path::PathBuf::from(&item.path);
+
+            let result = match (repair, item.kind) {
+                // Same content, so only the stored copy needs replacing
+                (BlobRepair::ReimportLocal, BlobMismatchKind::BlobCorrupt) => file_transfer
+                    .replace_blob(&absolute)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                (BlobRepair::ReimportLocal, _) => {
+                    match file_transfer.reimport_file(&absolute).await {
+                        Ok(_) => {
+                            regenerate_metadata(sync_engine, &id, &absolute, relative, node_id)
+                                .await
+                        }
+                        Err(e) => Err(e.to_string()),
                     }
                 }
-                _ => {} // Skip .., /, etc.
+                (
+                    BlobRepair::ExportBlob,
+                    BlobMismatchKind::LocalNewer | BlobMismatchKind::BlobNewer,
+                ) => match item.metadata_hash.parse::<iroh_blobs::Hash>() {
+                    Ok(hash) => {
+                        let result = restore_stored_content(
+                            file_transfer,
+                            &id,
+                            hash,
+                            manifests.get(&item.path).copied(),
+                            &absolute,
+                            &relative,
+                            Some(&item.metadata_hash),
+                        )
+                        .await;
+                        let operation = FsOperation::Write;
+                        track_access(&state, &id, &relative, &absolute, operation, &result);
+                        result.map_err(|e| e.to_string())
+                    }
+                    Err(_) => Err("invalid content hash".to_string()),
+                },
+                (BlobRepair::ExportBlob, _) => Err(
+                    "stored blob is missing or corrupted; re-import the local file or \
+                     download it from a peer"
+                        .to_string(),
+                ),
+            };
+
+            match result {
+                Ok(()) => item.repaired = true,
+                Err(e) => item.error = Some(e),
             }
         }
     }
-    dest_path.push(&safe_name);
-
-    // Create parent directories if needed
-    if let Some(parent) = dest_path.parent() {
-        std::fs::create_dir_all(parent).map_err(|e| {
-            format!("Failed to create destination directory: {}", e)
-        })?;
-    }
 
-    // Copy the file
-    std::fs::copy(&source, &dest_path).map_err(|e| {
-        format!("Failed to copy file: {}", e)
-    })?;
+    let repaired = mismatches.iter().filter(|m| m.repaired).count();
 
     tracing::info!(
         drive_id = %drive_id,
-        source = %source_path,
-        dest = %dest_path.display(),
-        "Imported file into drive"
+        checked = files_checked,
+        mismatches = mismatches.len(),
+        repaired = repaired,
+        cancelled = operation.is_cancelled(),
+        "Compared local files with stored blobs"
     );
 
-    // Now upload the copied file
-    let relative_path = dest_path
-        .strip_prefix(&drive_local_path)
-        .map(|p| p.to_path_buf())
-        .unwrap_or_else(|_| std::path::PathBuf::from(&safe_name));
+    Ok(BlobMatchReport {
+        drive_id,
+        files_checked,
+        mismatches,
+        repaired,
+        cancelled: operation.is_cancelled(),
+        checked_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
 
-    let hash = file_transfer
-        .upload_file(&id, &dest_path, &relative_path)
+/// Re-announce a local file as if the watcher had just seen it
+///
+/// This writes its metadata and tells peers about it, which is what a crash
+/// between writing the file and updating metadata prevented.
+async fn regenerate_metadata(
+    sync_engine: &SyncEngine,
+    id: &DriveId,
+    absolute: &std::path::Path,
+    relative: std::path::PathBuf,
+    node_id: crate::crypto::NodeId,
+) -> Result<(), String> {
+    let size = tokio::fs::metadata(absolute)
         .await
-        .map_err(|e| AppError::TransferFailed(format!("Upload failed: {}", e)).to_string())?;
+        .map_err(|e| e.to_string())?
+        .len();
+
+    let path = absolute.to_path_buf();
+    let (hash, content_type) = tokio::task::spawn_blocking(move || {
+        file::hash_file(&path).map(|hash| (hash, content_type::detect_file(&path)))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    let event = DriveEvent::FileChanged {
+        path: relative,
+        hash,
+        size,
+        modified_by: node_id,
+        timestamp: chrono::Utc::now(),
+        created: false,
+        content_type,
+    };
+    sync_engine
+        .on_local_change(id, event)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
 
-    tracing::info!(
-        drive_id = %drive_id,
-        path = %dest_path.display(),
-        hash = %hash.to_hex(),
-        "Uploaded imported file"
-    );
+// ==============================================
+// Permission Error Commands
+// ==============================================
 
-    Ok(hash.to_hex().to_string())
+use crate::core::fs_errors::{is_permission_denied, permission_denied_in};
+use crate::core::{FsOperation, PermissionError};
+
+/// Record or clear a permission error for a file sync just accessed
+fn track_access<T>(
+    state: &AppState,
+    id: &DriveId,
+    relative: &std::path::Path,
+    absolute: &std::path::Path,
+    operation: FsOperation,
+    result: &anyhow::Result<T>,
+) {
+    let Some(sync_engine) = state.sync_engine.as_ref() else {
+        return;
+    };
+    let errors = sync_engine.permission_errors();
+    match result {
+        Ok(_) => errors.clear(id, relative),
+        Err(e) => {
+            if let Some(io) = permission_denied_in(e) {
+                errors.record(id, relative, absolute, operation, io);
+            }
+        }
+    }
+}
+
+/// List files sync is skipping because it was denied access to them
+///
+/// # Arguments
+/// * `drive_id` - Only list this drive's files (all drives if omitted)
+#[tauri::command]
+pub async fn list_permission_errors(
+    drive_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PermissionError>, String> {
+    let id = drive_id.as_deref().map(parse_drive_id).transpose()?;
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    Ok(sync_engine.permission_errors().list(id.as_ref()))
+}
+
+/// Retry files sync was denied access to, e.g. after fixing permissions
+///
+/// Files that can now be read are re-announced to peers; files that
+/// couldn't be written are downloaded again. Returns the files that still
+/// fail.
+///
+/// # Arguments
+/// * `drive_id` - Only retry this drive's files (all drives if omitted)
+#[tauri::command]
+pub async fn retry_permission_errors(
+    drive_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PermissionError>, String> {
+    let filter = drive_id.as_deref().map(parse_drive_id).transpose()?;
+    let sync_engine = state
+        .sync_engine
+        .as_ref()
+        .ok_or_else(|| AppError::SyncNotInitialized.to_string())?;
+    let node_id = state
+        .identity_manager
+        .node_id()
+        .await
+        .ok_or_else(|| AppError::IdentityNotInitialized.to_string())?;
+    let errors = sync_engine.permission_errors();
+
+    for entry in errors.list(filter.as_ref()) {
+        let Ok(id) = parse_drive_id(&entry.drive_id) else {
+            continue;
+        };
+        let relative = std::path::PathBuf::from(&entry.path);
+        let root = state
+            .drives
+            .read()
+            .await
+            .get(id.as_bytes())
+            .map(|d| d.local_path.clone());
+        let Some(root) = root else {
+            // The drive was deleted
+            errors.clear(&id, &relative);
+            continue;
+        };
+        let absolute = root.join(&relative);
+
+        match entry.operation {
+            FsOperation::Read => match std::fs::File::open(&absolute) {
+                Ok(_) => {
+                    errors.clear(&id, &relative);
+                    let path = relative.clone();
+                    if let Err(e) =
+                        regenerate_metadata(sync_engine, &id, &absolute, path, node_id).await
+                    {
+                        tracing::warn!(path = %entry.path, "Failed to re-announce file: {}", e);
+                    }
+                }
+                Err(e) if is_permission_denied(&e) => {
+                    errors.record(&id, &relative, &absolute, FsOperation::Read, &e);
+                }
+                // Deleted or otherwise gone; the watcher reports that
+                Err(_) => errors.clear(&id, &relative),
+            },
+            FsOperation::Write => {
+                let content_hash = match state.docs_manager.as_ref() {
+                    Some(docs_manager) => docs_manager
+                        .get_file_metadata(&id, &entry.path)
+                        .await
+                        .and_then(|meta| meta.content_hash),
+                    None => None,
+                };
+                let hash = content_hash
+                    .as_deref()
+                    .and_then(|h| h.parse::<iroh_blobs::Hash>().ok());
+                let (Some(hash), Some(file_transfer)) = (hash, state.file_transfer.as_ref())
+                else {
+                    // Nothing left to download
+                    errors.clear(&id, &relative);
+                    continue;
+                };
+                let result = file_transfer
+                    .download_file(&id, hash, &absolute, &relative, content_hash.as_deref())
+                    .await;
+                track_access(&state, &id, &relative, &absolute, FsOperation::Write, &result);
+                if let Err(e) = result {
+                    tracing::warn!(path = %entry.path, "Retried download failed: {}", e);
+                }
+            }
+        }
+    }
+
+    let remaining = errors.list(filter.as_ref());
+    tracing::info!(remaining = remaining.len(), "Retried permission errors");
+    Ok(remaining)
 }
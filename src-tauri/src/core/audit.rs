@@ -6,11 +6,22 @@
 //! - File operations
 //! - Invite generation and acceptance
 //! - Lock force releases
+//!
+//! New entries are also broadcast as they are written, so the frontend can
+//! tail the log live instead of polling it.
 
 use crate::storage::Database;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::ipc::Channel;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+
+/// Capacity of the live entry channel; slower subscribers skip entries
+const LIVE_CHANNEL_CAPACITY: usize = 256;
 
 /// Audit event types for security logging
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -77,6 +88,14 @@ pub enum AuditEvent {
         user_id: String,
     },
 
+    /// An invite from a trusted inviter was accepted without confirmation
+    InviteAutoAccepted {
+        drive_id: String,
+        token_id: String,
+        user_id: String,
+        inviter: String,
+    },
+
     /// An invite was revoked
     InviteRevoked {
         drive_id: String,
@@ -84,6 +103,12 @@ pub enum AuditEvent {
         revoked_by: String,
     },
 
+    /// An access report listing a drive's members was generated
+    AccessReportGenerated {
+        drive_id: String,
+        generated_by: String,
+    },
+
     // ============================================================================
     // File Events
     // ============================================================================
@@ -141,7 +166,9 @@ impl AuditEvent {
             AuditEvent::PermissionRevoked { .. } => "permission_revoked",
             AuditEvent::InviteCreated { .. } => "invite_created",
             AuditEvent::InviteAccepted { .. } => "invite_accepted",
+            AuditEvent::InviteAutoAccepted { .. } => "invite_auto_accepted",
             AuditEvent::InviteRevoked { .. } => "invite_revoked",
+            AuditEvent::AccessReportGenerated { .. } => "access_report_generated",
             AuditEvent::FileRead { .. } => "file_read",
             AuditEvent::FileWritten { .. } => "file_written",
             AuditEvent::FileDeleted { .. } => "file_deleted",
@@ -161,7 +188,9 @@ impl AuditEvent {
             | AuditEvent::PermissionRevoked { drive_id, .. }
             | AuditEvent::InviteCreated { drive_id, .. }
             | AuditEvent::InviteAccepted { drive_id, .. }
+            | AuditEvent::InviteAutoAccepted { drive_id, .. }
             | AuditEvent::InviteRevoked { drive_id, .. }
+            | AuditEvent::AccessReportGenerated { drive_id, .. }
             | AuditEvent::FileRead { drive_id, .. }
             | AuditEvent::FileWritten { drive_id, .. }
             | AuditEvent::FileDeleted { drive_id, .. }
@@ -180,12 +209,14 @@ impl AuditEvent {
             | AuditEvent::PermissionGranted { user_id, .. }
             | AuditEvent::PermissionRevoked { user_id, .. }
             | AuditEvent::InviteAccepted { user_id, .. }
+            | AuditEvent::InviteAutoAccepted { user_id, .. }
             | AuditEvent::FileRead { user_id, .. }
             | AuditEvent::FileWritten { user_id, .. }
             | AuditEvent::FileDeleted { user_id, .. }
             | AuditEvent::FileRenamed { user_id, .. } => Some(user_id),
             AuditEvent::InviteCreated { created_by, .. } => Some(created_by),
             AuditEvent::InviteRevoked { revoked_by, .. } => Some(revoked_by),
+            AuditEvent::AccessReportGenerated { generated_by, .. } => Some(generated_by),
             AuditEvent::LockForceReleased { by_user, .. } => Some(by_user),
         }
     }
@@ -227,6 +258,37 @@ pub struct AuditFilter {
     pub offset: Option<usize>,
 }
 
+impl AuditFilter {
+    /// Check if an entry passes the drive, event type, user and time filters
+    ///
+    /// `limit` and `offset` apply to queries only and are ignored here.
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        if let Some(ref drive_id) = self.drive_id {
+            if entry.drive_id.as_ref() != Some(drive_id) {
+                return false;
+            }
+        }
+        if let Some(ref event_type) = self.event_type {
+            if &entry.event_type != event_type {
+                return false;
+            }
+        }
+        if let Some(ref user_id) = self.user_id {
+            if entry.user_id.as_ref() != Some(user_id) {
+                return false;
+            }
+        }
+        let millis = entry.timestamp.timestamp_millis();
+        if self.since.is_some_and(|since| millis < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| millis > until) {
+            return false;
+        }
+        true
+    }
+}
+
 /// Error types for audit operations
 #[derive(Debug, thiserror::Error)]
 pub enum AuditError {
@@ -239,12 +301,24 @@ pub enum AuditError {
 /// Audit logger for persisting security events
 pub struct AuditLogger {
     db: Arc<Database>,
+    /// New entries, sent after they are persisted
+    live_tx: broadcast::Sender<AuditEntry>,
+    /// Live subscriptions by ID, cancelled on unsubscribe
+    live_subscriptions: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    /// Source of live subscription IDs
+    next_subscription_id: AtomicU64,
 }
 
 impl AuditLogger {
     /// Create a new audit logger
     pub fn new(db: Arc<Database>) -> Self {
-        Self { db }
+        let (live_tx, _) = broadcast::channel(LIVE_CHANNEL_CAPACITY);
+        Self {
+            db,
+            live_tx,
+            live_subscriptions: Arc::new(Mutex::new(HashMap::new())),
+            next_subscription_id: AtomicU64::new(1),
+        }
     }
 
     /// Log a security event
@@ -255,7 +329,7 @@ impl AuditLogger {
         let drive_id = event.drive_id().map(String::from);
         let user_id = event.user_id().map(String::from);
 
-        let entry = AuditEntry {
+        let mut entry = AuditEntry {
             id: 0, // Will be assigned by database
             timestamp,
             event_type: event_type.clone(),
@@ -267,6 +341,11 @@ impl AuditLogger {
         let entry_bytes = serde_json::to_vec(&entry)?;
         let id = self.db.append_audit_log(&entry_bytes)?;
 
+        // Never blocks: with no subscribers this is a no-op, and subscribers
+        // that fall behind skip entries rather than holding up the writer
+        entry.id = id;
+        let _ = self.live_tx.send(entry);
+
         tracing::debug!(
             id = id,
             event_type = %event_type,
@@ -291,29 +370,82 @@ impl AuditLogger {
         for (id, bytes) in entries {
             if let Ok(mut entry) = serde_json::from_slice::<AuditEntry>(&bytes) {
                 entry.id = id;
-
-                // Apply filters
-                if let Some(ref drive_id) = filter.drive_id {
-                    if entry.drive_id.as_ref() != Some(drive_id) {
-                        continue;
-                    }
+                if filter.matches(&entry) {
+                    results.push(entry);
                 }
-                if let Some(ref event_type) = filter.event_type {
-                    if &entry.event_type != event_type {
-                        continue;
-                    }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Subscribe to entries as they are written
+    pub fn subscribe(&self) -> broadcast::Receiver<AuditEntry> {
+        self.live_tx.subscribe()
+    }
+
+    /// Stream new entries matching `filter` to a frontend channel
+    ///
+    /// Returns a subscription ID for `unsubscribe_live`. The stream also ends
+    /// when the channel is closed. If the subscriber falls behind, the
+    /// skipped entries are dropped and streaming continues.
+    pub fn subscribe_live(&self, filter: AuditFilter, channel: Channel<AuditEntryDto>) -> String {
+        let id = format!("audit_{:x}", self.next_subscription_id.fetch_add(1, Ordering::Relaxed));
+        let token = CancellationToken::new();
+        if let Ok(mut subscriptions) = self.live_subscriptions.lock() {
+            subscriptions.insert(id.clone(), token.clone());
+        }
+
+        let mut rx = self.live_tx.subscribe();
+        let subscriptions = self.live_subscriptions.clone();
+        let subscription_id = id.clone();
+        tokio::spawn(async move {
+            loop {
+                let entry = tokio::select! {
+                    _ = token.cancelled() => break,
+                    received = rx.recv() => match received {
+                        Ok(entry) => entry,
+                        Err(broadcast::error::RecvError::Lagged(count)) => {
+                            tracing::warn!(
+                                "Audit subscription {} lagged, skipped {} entries",
+                                subscription_id,
+                                count
+                            );
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                };
+                if !filter.matches(&entry) {
+                    continue;
                 }
-                if let Some(ref user_id) = filter.user_id {
-                    if entry.user_id.as_ref() != Some(user_id) {
-                        continue;
-                    }
+                if let Err(e) = channel.send(AuditEntryDto::from(entry)) {
+                    tracing::debug!("Dropping audit subscription {}: {}", subscription_id, e);
+                    break;
                 }
+            }
+            if let Ok(mut subscriptions) = subscriptions.lock() {
+                subscriptions.remove(&subscription_id);
+            }
+        });
+
+        id
+    }
 
-                results.push(entry);
+    /// Stop a live subscription. Returns false if the ID was unknown.
+    pub fn unsubscribe_live(&self, subscription_id: &str) -> bool {
+        let token = self
+            .live_subscriptions
+            .lock()
+            .ok()
+            .and_then(|mut subscriptions| subscriptions.remove(subscription_id));
+        match token {
+            Some(token) => {
+                token.cancel();
+                true
             }
+            None => false,
         }
-
-        Ok(results)
     }
 
     /// Get the total count of audit entries
@@ -387,3 +519,64 @@ impl From<AuditEntry> for AuditEntryDto {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn access_denied(drive_id: &str) -> AuditEvent {
+        AuditEvent::AccessDenied {
+            drive_id: drive_id.to_string(),
+            user_id: "user1".to_string(),
+            path: "/a.txt".to_string(),
+            reason: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_audit_filter_matches() {
+        let entry = AuditEntry {
+            id: 1,
+            timestamp: Utc::now(),
+            event_type: "access_denied".to_string(),
+            drive_id: Some("drive1".to_string()),
+            user_id: Some("user1".to_string()),
+            event: access_denied("drive1"),
+        };
+
+        assert!(AuditFilter::default().matches(&entry));
+        let by_drive = AuditFilter {
+            drive_id: Some("drive1".to_string()),
+            event_type: Some("access_denied".to_string()),
+            ..Default::default()
+        };
+        assert!(by_drive.matches(&entry));
+        let other_drive = AuditFilter {
+            drive_id: Some("drive2".to_string()),
+            ..Default::default()
+        };
+        assert!(!other_drive.matches(&entry));
+        let later = AuditFilter {
+            since: Some(entry.timestamp.timestamp_millis() + 1),
+            ..Default::default()
+        };
+        assert!(!later.matches(&entry));
+    }
+
+    #[tokio::test]
+    async fn test_log_broadcasts_new_entries() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(dir.path().join("audit.redb")).unwrap());
+        let logger = AuditLogger::new(db);
+
+        // Logging without subscribers must not fail
+        logger.log(access_denied("drive0")).await.unwrap();
+
+        let mut rx = logger.subscribe();
+        let id = logger.log(access_denied("drive1")).await.unwrap();
+        let entry = rx.recv().await.unwrap();
+        assert_eq!(entry.id, id);
+        assert_eq!(entry.drive_id.as_deref(), Some("drive1"));
+    }
+}
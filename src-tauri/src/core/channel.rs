@@ -2,14 +2,172 @@
 //!
 //! Provides utilities for handling broadcast channels with backpressure monitoring
 //! to prevent message loss and detect slow consumers.
+//!
+//! A full broadcast channel overwrites its oldest message, so what happens
+//! under pressure is chosen per send with a `BackpressureStrategy`:
+//!
+//! | Events                                   | Strategy          |
+//! |------------------------------------------|-------------------|
+//! | Sync/merge/transfer progress, peer waits | `DropOldest`      |
+//! | Local file changes from the watcher      | `Coalesce`        |
+//! | Everything else (conflicts, locks, ...)  | `BlockWithTimeout`|
+//!
+//! `DriveEvent::backpressure_strategy` applies this table to drive events.
 
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::{Handle, RuntimeFlavor};
 use tokio::sync::broadcast;
 
 /// Default warning threshold - warn when queue exceeds this many messages.
 /// This is ~75% of the typical 256-message channel capacity.
 const DEFAULT_WARNING_THRESHOLD: usize = 192;
 
+/// How long important events wait for a backed-up channel to drain
+pub const IMPORTANT_EVENT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// How often a waiting send or a coalescer re-checks the queue
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// How a send behaves when the channel is under pressure
+pub enum BackpressureStrategy<'a, T> {
+    /// Send right away; a full channel overwrites its oldest message and
+    /// slow receivers skip ahead. For progress, where only the latest matters.
+    DropOldest,
+    /// Wait up to the timeout for receivers to drain the queue, so the
+    /// message doesn't push out unread ones; sends anyway on timeout.
+    BlockWithTimeout(Duration),
+    /// Hold the message while the channel is under pressure, replacing any
+    /// held message with the same key, and send it once there is room.
+    Coalesce(&'a Coalescer<T>),
+}
+
+/// Holds back keyed messages while a channel is under pressure
+///
+/// Only the latest message per key is kept. Held messages are sent, in the
+/// order their keys were first held, once the queue drains.
+pub struct Coalescer<T> {
+    /// Coalescing key of a message (None = never held back)
+    key: fn(&T) -> Option<String>,
+    pending: Arc<Mutex<Vec<(String, T)>>>,
+    /// True while a task is waiting to flush `pending`
+    flushing: Arc<AtomicBool>,
+}
+
+impl<T: Clone + Send + 'static> Coalescer<T> {
+    pub fn new(key: fn(&T) -> Option<String>) -> Self {
+        Self {
+            key,
+            pending: Arc::new(Mutex::new(Vec::new())),
+            flushing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Number of messages currently held back
+    pub fn pending_len(&self) -> usize {
+        self.pending.lock().map(|p| p.len()).unwrap_or(0)
+    }
+
+    /// Send every held message
+    pub fn flush(&self, tx: &broadcast::Sender<T>) -> usize {
+        flush_pending(&self.pending, tx)
+    }
+
+    /// Hold `msg` if needed; returns it back if it should be sent now
+    fn offer(&self, tx: &broadcast::Sender<T>, msg: T, channel_name: &str) -> Option<T> {
+        let key = (self.key)(&msg)?;
+        let Ok(mut pending) = self.pending.lock() else {
+            return Some(msg);
+        };
+
+        // A newer message for a held key replaces it, so the stale one is never sent
+        if let Some(slot) = pending.iter_mut().find(|(k, _)| *k == key) {
+            slot.1 = msg;
+            return None;
+        }
+        if tx.len() < DEFAULT_WARNING_THRESHOLD {
+            return Some(msg);
+        }
+
+        pending.push((key, msg));
+        drop(pending);
+        tracing::debug!(channel = channel_name, "Holding message until channel drains");
+        self.schedule_flush(tx);
+        None
+    }
+
+    /// Start a task that flushes held messages once the queue drains
+    fn schedule_flush(&self, tx: &broadcast::Sender<T>) {
+        if self.flushing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let Ok(handle) = Handle::try_current() else {
+            self.flushing.store(false, Ordering::Release);
+            return;
+        };
+
+        let tx = tx.clone();
+        let pending = self.pending.clone();
+        let flushing = self.flushing.clone();
+        handle.spawn(async move {
+            loop {
+                while tx.len() >= DEFAULT_WARNING_THRESHOLD {
+                    tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+                }
+                flush_pending(&pending, &tx);
+                flushing.store(false, Ordering::Release);
+
+                // Something may have been held between the flush and the reset
+                let more = pending.lock().map(|p| !p.is_empty()).unwrap_or(false);
+                if !more || flushing.swap(true, Ordering::AcqRel) {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+fn flush_pending<T>(pending: &Mutex<Vec<(String, T)>>, tx: &broadcast::Sender<T>) -> usize {
+    let held = pending
+        .lock()
+        .map(|mut p| std::mem::take(&mut *p))
+        .unwrap_or_default();
+    let count = held.len();
+    for (_, msg) in held {
+        let _ = tx.send(msg);
+    }
+    count
+}
+
+/// Wait for the queue to drop below the warning threshold
+///
+/// Returns false on timeout, or without waiting on a single-threaded
+/// runtime, which can't be blocked without stalling the receivers.
+fn wait_for_room<T>(tx: &broadcast::Sender<T>, timeout: Duration) -> bool {
+    if tx.len() < DEFAULT_WARNING_THRESHOLD {
+        return true;
+    }
+    let wait = || {
+        let deadline = Instant::now() + timeout;
+        while tx.len() >= DEFAULT_WARNING_THRESHOLD {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(DRAIN_POLL_INTERVAL);
+        }
+        true
+    };
+    match Handle::try_current() {
+        // block_in_place panics on a current_thread runtime, so don't wait there
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(wait)
+        }
+        Ok(_) => false,
+        Err(_) => wait(),
+    }
+}
+
 /// Metrics for tracking channel health
 #[allow(dead_code)]
 #[derive(Debug, Default)]
@@ -61,19 +219,21 @@ pub struct ChannelMetricsSnapshot {
 /// Send a message with backpressure monitoring.
 ///
 /// Logs a warning when the channel queue length exceeds the warning threshold,
-/// indicating that consumers may be falling behind.
+/// indicating that consumers may be falling behind, then applies `strategy`.
 ///
 /// # Arguments
 /// * `tx` - The broadcast sender
 /// * `msg` - The message to send
 /// * `channel_name` - Name for logging purposes
+/// * `strategy` - What to do if the channel is backed up
 ///
 /// # Returns
-/// Returns `true` if the message was sent (even if no receivers).
-pub fn send_with_backpressure<T: Clone>(
+/// Returns `true` if the message was sent or held for sending (even if no receivers).
+pub fn send_with_backpressure<T: Clone + Send + 'static>(
     tx: &broadcast::Sender<T>,
     msg: T,
     channel_name: &str,
+    strategy: BackpressureStrategy<'_, T>,
 ) -> bool {
     let current_len = tx.len();
 
@@ -86,6 +246,25 @@ pub fn send_with_backpressure<T: Clone>(
         );
     }
 
+    let msg = match strategy {
+        BackpressureStrategy::DropOldest => msg,
+        BackpressureStrategy::BlockWithTimeout(timeout) => {
+            if !wait_for_room(tx, timeout) {
+                tracing::warn!(
+                    channel = channel_name,
+                    "Channel still backed up after waiting - sending anyway"
+                );
+            }
+            msg
+        }
+        BackpressureStrategy::Coalesce(coalescer) => {
+            match coalescer.offer(tx, msg, channel_name) {
+                Some(msg) => msg,
+                None => return true,
+            }
+        }
+    };
+
     match tx.send(msg) {
         Ok(receiver_count) => {
             tracing::trace!(
@@ -163,7 +342,7 @@ mod tests {
     fn test_send_with_backpressure_normal() {
         let (tx, mut rx) = broadcast::channel::<i32>(16);
 
-        let result = send_with_backpressure(&tx, 42, "test");
+        let result = send_with_backpressure(&tx, 42, "test", BackpressureStrategy::DropOldest);
         assert!(result);
 
         let received = rx.try_recv().unwrap();
@@ -175,7 +354,7 @@ mod tests {
         let (tx, _) = broadcast::channel::<i32>(16);
 
         // Should not panic even with no receivers
-        let result = send_with_backpressure(&tx, 42, "test");
+        let result = send_with_backpressure(&tx, 42, "test", BackpressureStrategy::DropOldest);
         assert!(result);
     }
 
@@ -208,4 +387,43 @@ mod tests {
         assert_eq!(snapshot.messages_sent, 2);
         assert_eq!(snapshot.messages_dropped, 0);
     }
+
+    #[test]
+    fn test_block_with_timeout_gives_up() {
+        let (tx, _rx) = broadcast::channel::<i32>(256);
+        for i in 0..200 {
+            let _ = tx.send(i);
+        }
+
+        // Nobody drains the queue, so the send waits out the timeout and proceeds
+        let started = Instant::now();
+        let strategy = BackpressureStrategy::BlockWithTimeout(Duration::from_millis(20));
+        assert!(send_with_backpressure(&tx, 200, "test", strategy));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        assert_eq!(tx.len(), 201);
+    }
+
+    #[test]
+    fn test_coalesce_keeps_latest_per_key() {
+        let (tx, mut rx) = broadcast::channel::<(u8, i32)>(256);
+        let coalescer = Coalescer::new(|msg: &(u8, i32)| Some(msg.0.to_string()));
+
+        // No pressure: sent straight through
+        send_with_backpressure(&tx, (1, 0), "test", BackpressureStrategy::Coalesce(&coalescer));
+        assert_eq!(rx.try_recv().unwrap(), (1, 0));
+
+        for i in 0..200 {
+            let _ = tx.send((0, i));
+        }
+        for msg in [(1, 1), (1, 2), (1, 3), (2, 9)] {
+            send_with_backpressure(&tx, msg, "test", BackpressureStrategy::Coalesce(&coalescer));
+        }
+        assert_eq!(coalescer.pending_len(), 2);
+
+        while rx.try_recv().is_ok() {}
+        assert_eq!(coalescer.flush(&tx), 2);
+        assert_eq!(rx.try_recv().unwrap(), (1, 3));
+        assert_eq!(rx.try_recv().unwrap(), (2, 9));
+        assert!(rx.try_recv().is_err());
+    }
 }
@@ -2,35 +2,53 @@
 //!
 //! Provides periodic cleanup of:
 //! - Expired file locks
-//! - Old activity entries
+//! - Lock queue entries for disconnected peers
+//! - Activity entries beyond each drive's retention
 //! - Expired ACL rules
-//! - Stale presence data
+//! - Stale presence data, and users whose heartbeats stopped
+//! - Deleted drives past their recovery window
+//!
+//! Pruning old activity entries and resolved conflicts is heavy
+//! housekeeping and is deferred while the maintenance window is closed.
 
 use crate::commands::SecurityStore;
-use crate::core::{ConflictManager, LockManager, PresenceManager};
+use crate::core::drive::{load_deleted_drives, DELETED_DRIVE_RETENTION_DAYS};
+use crate::core::{
+    ConflictManager, LockManager, MaintenanceScheduler, PresenceManager, SharedDrive,
+};
+use crate::storage::Database;
 use chrono::{Duration, Utc};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use tokio::sync::RwLock;
 use tokio::time::{interval, Duration as TokioDuration};
 
 /// Configuration for cleanup intervals
 pub struct CleanupConfig {
     /// How often to run cleanup (in seconds)
     pub interval_secs: u64,
-    /// Max age for activity entries (in hours)
+    /// How often to check for presence timeouts (in seconds)
+    pub presence_interval_secs: u64,
+    /// Max age for activity entries (in hours), for drives whose retention
+    /// doesn't set one
     pub max_activity_age_hours: i64,
     /// Max age for resolved conflicts (in days)
     pub max_resolved_conflict_age_days: i64,
     /// Idle threshold for presence (in minutes)
     pub presence_idle_threshold_mins: i64,
+    /// How long deleted drives can be restored (in days)
+    pub deleted_drive_retention_days: i64,
 }
 
 impl Default for CleanupConfig {
     fn default() -> Self {
         Self {
             interval_secs: 300,          // 5 minutes
+            presence_interval_secs: 15,
             max_activity_age_hours: 168, // 1 week
             max_resolved_conflict_age_days: 30,
             presence_idle_threshold_mins: 15,
+            deleted_drive_retention_days: DELETED_DRIVE_RETENTION_DAYS,
         }
     }
 }
@@ -52,6 +70,11 @@ impl CleanupManager {
         Self { config }
     }
 
+    /// How long deleted drives can be restored before they are purged
+    pub fn deleted_drive_retention(&self) -> Duration {
+        Duration::days(self.config.deleted_drive_retention_days)
+    }
+
     /// Start the background cleanup task
     ///
     /// This spawns a tokio task that runs cleanup periodically.
@@ -62,19 +85,36 @@ impl CleanupManager {
         conflict_manager: Arc<ConflictManager>,
         presence_manager: Arc<PresenceManager>,
         security_store: Arc<SecurityStore>,
+        maintenance: Arc<MaintenanceScheduler>,
+        db: Arc<Database>,
+        drives: Arc<RwLock<HashMap<[u8; 32], SharedDrive>>>,
     ) -> tauri::async_runtime::JoinHandle<()> {
         let interval_secs = self.config.interval_secs;
+        let presence_interval_secs = self.config.presence_interval_secs;
         let max_activity_age = Duration::hours(self.config.max_activity_age_hours);
         let max_resolved_age = Duration::days(self.config.max_resolved_conflict_age_days);
         let idle_threshold = Duration::minutes(self.config.presence_idle_threshold_mins);
+        let deleted_retention = self.deleted_drive_retention();
 
         tauri::async_runtime::spawn(async move {
             let mut ticker = interval(TokioDuration::from_secs(interval_secs));
+            let mut presence_ticker = interval(TokioDuration::from_secs(presence_interval_secs));
 
             tracing::info!(interval_secs = interval_secs, "Cleanup manager started");
 
             loop {
-                ticker.tick().await;
+                // Presence timeouts are checked more often than the rest so
+                // vanished peers drop off within a few missed heartbeats
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    _ = presence_ticker.tick() => {
+                        let timed_out = presence_manager.expire_timed_out().await;
+                        if timed_out > 0 {
+                            tracing::info!(users = timed_out, "Presence timed out");
+                        }
+                        continue;
+                    }
+                }
 
                 let start = std::time::Instant::now();
                 let mut cleaned = CleanupStats::default();
@@ -82,29 +122,42 @@ impl CleanupManager {
                 // Cleanup expired locks
                 cleaned.locks = cleanup_expired_locks(&lock_manager).await;
 
-                // Cleanup old activities
-                cleaned.activities =
-                    cleanup_old_activities(&presence_manager, max_activity_age).await;
+                // Drop queued lock requests from disconnected peers
+                cleaned.lock_requests =
+                    cleanup_disconnected_lock_requests(&lock_manager, &presence_manager).await;
 
                 // Cleanup stale presence
                 cleaned.presence = cleanup_stale_presence(&presence_manager, idle_threshold).await;
 
-                // Cleanup old resolved conflicts
-                cleaned.conflicts =
-                    cleanup_old_conflicts(&conflict_manager, max_resolved_age).await;
+                // Prune old activities and resolved conflicts only inside the
+                // maintenance window; they are picked up on a later tick
+                if maintenance.is_open() {
+                    cleaned.activities =
+                        cleanup_old_activities(&presence_manager, &drives, max_activity_age)
+                            .await;
+                    cleaned.conflicts =
+                        cleanup_old_conflicts(&conflict_manager, max_resolved_age).await;
+                } else {
+                    tracing::debug!("Outside maintenance window; deferring pruning");
+                }
 
                 // Cleanup expired ACL rules
                 cleaned.acl_rules = cleanup_expired_acls(&security_store).await;
 
+                // Remove deleted drives whose recovery window has passed
+                cleaned.drives = purge_deleted_drives(&db, deleted_retention);
+
                 let elapsed = start.elapsed();
 
                 if cleaned.total() > 0 {
                     tracing::info!(
                         locks = cleaned.locks,
+                        lock_requests = cleaned.lock_requests,
                         activities = cleaned.activities,
                         presence = cleaned.presence,
                         conflicts = cleaned.conflicts,
                         acl_rules = cleaned.acl_rules,
+                        drives = cleaned.drives,
                         elapsed_ms = elapsed.as_millis(),
                         "Cleanup completed"
                     );
@@ -129,15 +182,23 @@ impl Default for CleanupManager {
 #[derive(Default)]
 struct CleanupStats {
     locks: usize,
+    lock_requests: usize,
     activities: usize,
     presence: usize,
     conflicts: usize,
     acl_rules: usize,
+    drives: usize,
 }
 
 impl CleanupStats {
     fn total(&self) -> usize {
-        self.locks + self.activities + self.presence + self.conflicts + self.acl_rules
+        self.locks
+            + self.lock_requests
+            + self.activities
+            + self.presence
+            + self.conflicts
+            + self.acl_rules
+            + self.drives
     }
 }
 
@@ -146,13 +207,41 @@ async fn cleanup_expired_locks(lock_manager: &Arc<LockManager>) -> usize {
     lock_manager.cleanup_expired().await
 }
 
-/// Cleanup old activity entries
+/// Drop lock queue entries for peers no longer present on the drive
+async fn cleanup_disconnected_lock_requests(
+    lock_manager: &Arc<LockManager>,
+    presence_manager: &Arc<PresenceManager>,
+) -> usize {
+    let mut online: HashMap<String, HashSet<_>> = HashMap::new();
+    for drive_id in lock_manager.drive_ids().await {
+        let users = presence_manager.get_online_users(&drive_id).await;
+        online.insert(drive_id, users.into_iter().map(|u| u.node_id).collect());
+    }
+
+    lock_manager
+        .cleanup_disconnected_requests(|drive_id, requester| {
+            online
+                .get(drive_id)
+                .is_some_and(|users| users.contains(requester))
+        })
+        .await
+}
+
+/// Prune activity entries beyond each drive's retention
 async fn cleanup_old_activities(
     presence_manager: &Arc<PresenceManager>,
-    max_age: Duration,
+    drives: &RwLock<HashMap<[u8; 32], SharedDrive>>,
+    default_max_age: Duration,
 ) -> usize {
-    let cutoff = Utc::now() - max_age;
-    presence_manager.cleanup_old_activities(cutoff).await
+    let retention = drives
+        .read()
+        .await
+        .values()
+        .map(|drive| (drive.id.to_hex(), drive.activity_retention))
+        .collect();
+    presence_manager
+        .prune_activities(&retention, default_max_age)
+        .await
 }
 
 /// Mark stale users as away/offline
@@ -177,9 +266,38 @@ async fn cleanup_expired_acls(security_store: &Arc<SecurityStore>) -> usize {
     security_store.cleanup_expired().await
 }
 
+/// Permanently remove drives deleted more than `retention` ago
+fn purge_deleted_drives(db: &Database, retention: Duration) -> usize {
+    let now = Utc::now();
+    let deleted = match load_deleted_drives(db) {
+        Ok(deleted) => deleted,
+        Err(e) => {
+            tracing::warn!("Failed to load deleted drives: {}", e);
+            return 0;
+        }
+    };
+
+    let mut purged = 0;
+    for drive in deleted {
+        let expired = drive.purge_at(retention).is_some_and(|at| at < now);
+        if !expired {
+            continue;
+        }
+        match db.delete_drive(drive.id.as_bytes()) {
+            Ok(_) => {
+                tracing::info!(drive_id = %drive.id, "Purged deleted drive");
+                purged += 1;
+            }
+            Err(e) => tracing::warn!(drive_id = %drive.id, "Failed to purge deleted drive: {}", e),
+        }
+    }
+    purged
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::SharedDrive;
 
     #[test]
     fn test_default_config() {
@@ -192,11 +310,35 @@ mod tests {
     fn test_cleanup_stats() {
         let stats = CleanupStats {
             locks: 5,
+            lock_requests: 4,
             activities: 10,
             presence: 2,
             conflicts: 1,
             acl_rules: 3,
+            drives: 0,
         };
-        assert_eq!(stats.total(), 21);
+        assert_eq!(stats.total(), 25);
+    }
+
+    #[test]
+    fn test_purge_deleted_drives() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path().join("test.redb")).unwrap();
+        let owner = crate::crypto::Identity::generate().node_id();
+
+        let mut old = SharedDrive::new("Old".to_string(), dir.path().join("old"), owner);
+        old.deleted_at = Some(Utc::now() - Duration::days(31));
+        let mut recent = SharedDrive::new("Recent".to_string(), dir.path().join("new"), owner);
+        recent.deleted_at = Some(Utc::now() - Duration::days(1));
+        let active = SharedDrive::new("Active".to_string(), dir.path().join("active"), owner);
+        for drive in [&old, &recent, &active] {
+            db.save_drive(drive.id.as_bytes(), &serde_json::to_vec(drive).unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(purge_deleted_drives(&db, Duration::days(30)), 1);
+        assert!(db.get_drive(old.id.as_bytes()).unwrap().is_none());
+        assert!(db.get_drive(recent.id.as_bytes()).unwrap().is_some());
+        assert!(db.get_drive(active.id.as_bytes()).unwrap().is_some());
     }
 }
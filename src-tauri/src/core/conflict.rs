@@ -3,13 +3,90 @@
 //! Detects when multiple peers modify the same file simultaneously
 //! and provides resolution strategies.
 
+use crate::core::merge::merge_text;
+use crate::core::{send_with_backpressure, AppError, DriveEvent};
 use crate::crypto::NodeId;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+
+/// Largest file whose text is normalized before declaring a conflict
+pub const MAX_NORMALIZED_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Largest version of a file read for a three-way merge
+pub const MAX_MERGE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// How different two versions must be to count as a conflict
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictSensitivity {
+    /// Any difference in content is a conflict
+    #[default]
+    Exact,
+    /// Text files that differ only in line endings or trailing whitespace
+    /// are treated as equal; other files are compared exactly
+    IgnoreWhitespace,
+}
+
+impl ConflictSensitivity {
+    /// Hash to compare `content` by, when it differs from the content hash
+    ///
+    /// Only returns a hash for text files under `IgnoreWhitespace`; callers
+    /// fall back to the content hash otherwise.
+    pub fn normalized_hash(&self, path: &Path, content: &[u8]) -> Option<String> {
+        if *self == ConflictSensitivity::Exact || !is_text_path(path) {
+            return None;
+        }
+        normalize_text(content).map(|text| blake3::hash(text.as_bytes()).to_hex().to_string())
+    }
+
+    /// Whether two versions of the file at `path` count as the same content
+    pub fn equivalent(&self, path: &Path, a: &[u8], b: &[u8]) -> bool {
+        if a == b {
+            return true;
+        }
+        match (self.normalized_hash(path, a), self.normalized_hash(path, b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Whether a path looks like a text file, by extension
+pub fn is_text_path(path: &Path) -> bool {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    matches!(
+        ext.as_str(),
+        "txt" | "md" | "json" | "yaml" | "yml" | "toml" | "xml" | "html" | "css" | "js"
+            | "ts" | "jsx" | "tsx" | "rs" | "py" | "go" | "java" | "c" | "cpp" | "h"
+            | "hpp" | "sh" | "bash" | "zsh" | "ps1" | "bat" | "cmd" | "sql" | "csv"
+    )
+}
+
+/// Text with line endings unified and trailing whitespace removed
+///
+/// Returns `None` for content that isn't UTF-8 text, so binaries that happen
+/// to have a text extension are still compared exactly.
+fn normalize_text(content: &[u8]) -> Option<String> {
+    let text = std::str::from_utf8(content).ok()?;
+    if text.contains('\0') {
+        return None;
+    }
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+    let lines: Vec<&str> = text.split('\n').map(str::trim_end).collect();
+    let end = lines.iter().rposition(|line| !line.is_empty()).map_or(0, |i| i + 1);
+    Some(lines[..end].join("\n"))
+}
 
 /// Conflict resolution strategy
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -24,6 +101,44 @@ pub enum ResolutionStrategy {
     ManualMerge,
 }
 
+impl ResolutionStrategy {
+    /// Which side of a conflict this strategy throws away, if any
+    pub fn discarded<'a>(&self, conflict: &'a FileConflict) -> Option<&'a ConflictVersion> {
+        match self {
+            ResolutionStrategy::KeepLocal => Some(&conflict.remote),
+            ResolutionStrategy::KeepRemote => Some(&conflict.local),
+            ResolutionStrategy::KeepBoth | ResolutionStrategy::ManualMerge => None,
+        }
+    }
+}
+
+/// What to do with a conflict as soon as it is detected
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Leave the conflict for the user to resolve
+    #[default]
+    Manual,
+    /// Keep whichever version was written last
+    LastWriterWins,
+    /// Always keep the local version
+    KeepLocal,
+    /// Always keep the remote version
+    KeepRemote,
+}
+
+impl ConflictPolicy {
+    /// Strategy this policy resolves `conflict` with, or `None` for manual
+    pub fn strategy_for(&self, conflict: &FileConflict) -> Option<ResolutionStrategy> {
+        match self {
+            ConflictPolicy::Manual => None,
+            ConflictPolicy::KeepLocal => Some(ResolutionStrategy::KeepLocal),
+            ConflictPolicy::KeepRemote => Some(ResolutionStrategy::KeepRemote),
+            ConflictPolicy::LastWriterWins => Some(conflict.last_writer()),
+        }
+    }
+}
+
 /// Information about a conflicting version
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ConflictVersion {
@@ -37,6 +152,9 @@ pub struct ConflictVersion {
     pub modified_by: NodeId,
     /// Optional preview/snippet for text files
     pub preview: Option<String>,
+    /// Hash of the normalized text, when the drive ignores cosmetic changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized_hash: Option<String>,
 }
 
 /// Represents a file conflict
@@ -99,19 +217,22 @@ impl FileConflict {
 
     /// Check if file is a text file (can be merged)
     pub fn is_text_file(&self) -> bool {
-        let ext = self
-            .path
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_lowercase();
+        is_text_path(&self.path)
+    }
 
-        matches!(
-            ext.as_str(),
-            "txt" | "md" | "json" | "yaml" | "yml" | "toml" | "xml" | "html" | "css" | "js"
-                | "ts" | "jsx" | "tsx" | "rs" | "py" | "go" | "java" | "c" | "cpp" | "h"
-                | "hpp" | "sh" | "bash" | "zsh" | "ps1" | "bat" | "cmd" | "sql" | "csv"
-        )
+    /// Strategy that keeps the most recently written version
+    ///
+    /// Ties on `modified_at` go to the higher `modified_by` node ID, then
+    /// the higher hash. The order only depends on the two versions, so both
+    /// peers pick the same winner even though each sees the other's version
+    /// as remote.
+    pub fn last_writer(&self) -> ResolutionStrategy {
+        let key = |v: &ConflictVersion| (v.modified_at, *v.modified_by.as_bytes(), v.hash.clone());
+        if key(&self.remote) > key(&self.local) {
+            ResolutionStrategy::KeepRemote
+        } else {
+            ResolutionStrategy::KeepLocal
+        }
     }
 
     /// Get suggested resolution strategy
@@ -172,6 +293,21 @@ impl From<&FileConflict> for FileConflictDto {
     }
 }
 
+/// Outcome of a three-way text merge, for the frontend
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MergeResult {
+    pub path: String,
+    /// Hash of the common ancestor the merge was based on
+    pub base_hash: String,
+    /// BLAKE3 hash of the merged content written to the file
+    pub merged_hash: String,
+    /// Hunks both sides changed differently, left with conflict markers
+    pub conflict_count: usize,
+    /// Whether the file still has markers to sort out by hand; if not,
+    /// the conflict was resolved
+    pub needs_manual_resolution: bool,
+}
+
 /// Manages conflicts for a single drive
 #[derive(Debug)]
 pub struct DriveConflictManager {
@@ -239,6 +375,14 @@ impl DriveConflictManager {
         None
     }
 
+    /// Active conflict for a path followed by its resolved history, newest first
+    pub async fn conflicts_for_path(&self, path: &PathBuf) -> Vec<FileConflict> {
+        let mut found: Vec<FileConflict> = self.get_conflict(path).await.into_iter().collect();
+        let resolved = self.resolved.read().await;
+        found.extend(resolved.iter().rev().filter(|c| &c.path == path).cloned());
+        found
+    }
+
     /// Get count of unresolved conflicts
     pub async fn conflict_count(&self) -> usize {
         let conflicts = self.conflicts.read().await;
@@ -262,12 +406,44 @@ impl Default for DriveConflictManager {
 pub struct ConflictManager {
     /// Conflict managers per drive (keyed by drive ID hex)
     drives: RwLock<HashMap<String, Arc<DriveConflictManager>>>,
+    /// Auto-resolution policy per drive (keyed by drive ID hex); drives
+    /// not listed are `Manual`
+    policies: RwLock<HashMap<String, ConflictPolicy>>,
+    /// Channel for auto-resolution events (drive ID hex, event)
+    event_tx: broadcast::Sender<(String, DriveEvent)>,
 }
 
 impl ConflictManager {
     pub fn new() -> Self {
+        let (event_tx, _) = broadcast::channel(256);
         Self {
             drives: RwLock::new(HashMap::new()),
+            policies: RwLock::new(HashMap::new()),
+            event_tx,
+        }
+    }
+
+    /// Subscribe to auto-resolution events
+    pub fn subscribe(&self) -> broadcast::Receiver<(String, DriveEvent)> {
+        self.event_tx.subscribe()
+    }
+
+    /// Auto-resolution policy for a drive
+    pub async fn policy(&self, drive_id: &str) -> ConflictPolicy {
+        let policies = self.policies.read().await;
+        policies.get(drive_id).copied().unwrap_or_default()
+    }
+
+    /// Set the auto-resolution policy for a drive
+    ///
+    /// Applies to conflicts detected from now on; outstanding ones are left
+    /// for the user.
+    pub async fn set_policy(&self, drive_id: &str, policy: ConflictPolicy) {
+        let mut policies = self.policies.write().await;
+        if policy == ConflictPolicy::Manual {
+            policies.remove(drive_id);
+        } else {
+            policies.insert(drive_id.to_string(), policy);
         }
     }
 
@@ -288,6 +464,11 @@ impl ConflictManager {
     }
 
     /// Detect if incoming change conflicts with local state
+    ///
+    /// Conflicts are resolved straight away when the drive has an
+    /// auto-resolution policy: the returned conflict is then marked
+    /// resolved, goes to the drive's history and a `ConflictAutoResolved`
+    /// event is emitted; callers then apply the kept version.
     pub async fn detect_conflict(
         &self,
         drive_id: &str,
@@ -301,6 +482,11 @@ impl ConflictManager {
             return None;
         }
 
+        // No conflict if the text only differs cosmetically
+        if local.normalized_hash.is_some() && local.normalized_hash == remote.normalized_hash {
+            return None;
+        }
+
         // No conflict if local hasn't been modified since base
         if let Some(ref base) = base_hash {
             if local.hash == *base {
@@ -318,7 +504,52 @@ impl ConflictManager {
         let manager = self.get_drive_conflicts(drive_id).await;
         manager.add_conflict(conflict.clone()).await;
 
-        Some(conflict)
+        let policy = self.policy(drive_id).await;
+        let Some(strategy) = policy.strategy_for(&conflict) else {
+            return Some(conflict);
+        };
+        let resolved = manager.resolve_conflict(&conflict.path, strategy).await?;
+        self.emit_auto_resolved(drive_id, policy, &resolved);
+
+        Some(resolved)
+    }
+
+    /// Tell listeners a conflict was resolved by the drive's policy
+    fn emit_auto_resolved(&self, drive_id: &str, policy: ConflictPolicy, conflict: &FileConflict) {
+        let (strategy, kept, discarded) = match conflict.resolution {
+            Some(ResolutionStrategy::KeepLocal) => {
+                (ResolutionStrategy::KeepLocal, &conflict.local, &conflict.remote)
+            }
+            Some(ResolutionStrategy::KeepRemote) => {
+                (ResolutionStrategy::KeepRemote, &conflict.remote, &conflict.local)
+            }
+            _ => return,
+        };
+
+        tracing::info!(
+            drive_id = %drive_id,
+            path = %conflict.path.display(),
+            policy = ?policy,
+            strategy = ?strategy,
+            "Conflict auto-resolved"
+        );
+
+        let event = DriveEvent::ConflictAutoResolved {
+            path: conflict.path.clone(),
+            policy,
+            strategy,
+            kept_hash: kept.hash.clone(),
+            kept_by: kept.modified_by,
+            discarded_hash: discarded.hash.clone(),
+            timestamp: Utc::now(),
+        };
+        let strategy = event.backpressure_strategy(None);
+        send_with_backpressure(
+            &self.event_tx,
+            (drive_id.to_string(), event),
+            "conflict",
+            strategy,
+        );
     }
 
     /// List conflicts for a drive
@@ -338,6 +569,102 @@ impl ConflictManager {
         manager.resolve_conflict(path, strategy).await
     }
 
+    /// Merge both sides of a text conflict against their common ancestor
+    ///
+    /// The ancestor is the conflict's `base_hash`, or `ancestor` when the
+    /// conflict doesn't know one; `read_version` returns the text of the
+    /// version with a given hash. The merged text replaces the file at the
+    /// conflict's path. Changes that don't overlap merge cleanly and resolve
+    /// the conflict as `ManualMerge`; otherwise the file gets Git-style
+    /// markers around each conflicting hunk and the conflict stays open.
+    pub async fn merge_text_conflict<F, Fut>(
+        &self,
+        drive_id: &str,
+        path: &PathBuf,
+        ancestor: Option<String>,
+        read_version: F,
+    ) -> Result<MergeResult, AppError>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<String, AppError>>,
+    {
+        let manager = self.get_drive_conflicts(drive_id).await;
+        let conflict = manager
+            .get_conflict(path)
+            .await
+            .ok_or_else(|| AppError::PathNotFound {
+                path: path.display().to_string(),
+            })?;
+        if !conflict.is_text_file() {
+            return Err(AppError::ValidationError(format!(
+                "{} is not a text file and can't be merged",
+                path.display()
+            )));
+        }
+        let base_hash = conflict.base_hash.clone().or(ancestor).ok_or_else(|| {
+            AppError::ValidationError(format!(
+                "No common ancestor is known for {}",
+                path.display()
+            ))
+        })?;
+
+        let base = read_version(base_hash.clone()).await?;
+        let local = read_version(conflict.local.hash.clone()).await?;
+        let remote = read_version(conflict.remote.hash.clone()).await?;
+        let merged = merge_text(&base, &local, &remote);
+
+        tokio::fs::write(path, merged.text.as_bytes())
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write merged file: {}", e)))?;
+        if merged.is_clean() {
+            manager
+                .resolve_conflict(path, ResolutionStrategy::ManualMerge)
+                .await;
+        }
+
+        tracing::info!(
+            drive_id = %drive_id,
+            path = %path.display(),
+            conflicts = merged.conflicts,
+            "Merged text conflict"
+        );
+
+        Ok(MergeResult {
+            path: path.to_string_lossy().to_string(),
+            base_hash,
+            merged_hash: blake3::hash(merged.text.as_bytes()).to_hex().to_string(),
+            conflict_count: merged.conflicts,
+            needs_manual_resolution: !merged.is_clean(),
+        })
+    }
+
+    /// List unresolved conflicts across all drives with their drive ID hex
+    ///
+    /// Drives are read from one snapshot of the drive map. Oldest first, so
+    /// conflicts left waiting longest surface at the top.
+    pub async fn list_all_conflicts(&self) -> Vec<(String, FileConflict)> {
+        let drives: Vec<(String, Arc<DriveConflictManager>)> = self
+            .drives
+            .read()
+            .await
+            .iter()
+            .map(|(id, manager)| (id.clone(), manager.clone()))
+            .collect();
+
+        let mut all = Vec::new();
+        for (drive_id, manager) in drives {
+            all.extend(
+                manager
+                    .list_conflicts()
+                    .await
+                    .into_iter()
+                    .map(|conflict| (drive_id.clone(), conflict)),
+            );
+        }
+        all.sort_by(|a, b| a.1.detected_at.cmp(&b.1.detected_at));
+        all
+    }
+
     /// Get total conflict count across all drives
     pub async fn total_conflict_count(&self) -> usize {
         let drives = self.drives.read().await;
@@ -384,6 +711,7 @@ mod tests {
             modified_at: Utc::now(),
             modified_by: identity1.node_id(),
             preview: None,
+            normalized_hash: None,
         };
 
         let remote = ConflictVersion {
@@ -392,6 +720,7 @@ mod tests {
             modified_at: Utc::now(),
             modified_by: identity2.node_id(),
             preview: None,
+            normalized_hash: None,
         };
 
         let conflict = FileConflict::new(
@@ -403,6 +732,13 @@ mod tests {
 
         assert!(!conflict.resolved);
         assert!(conflict.is_text_file());
+
+        let discarded = |strategy: ResolutionStrategy| {
+            strategy.discarded(&conflict).map(|v| v.hash.clone())
+        };
+        assert_eq!(discarded(ResolutionStrategy::KeepLocal).as_deref(), Some("remote456"));
+        assert_eq!(discarded(ResolutionStrategy::KeepRemote).as_deref(), Some("local123"));
+        assert_eq!(discarded(ResolutionStrategy::KeepBoth), None);
     }
 
     #[tokio::test]
@@ -417,6 +753,7 @@ mod tests {
             modified_at: Utc::now(),
             modified_by: identity1.node_id(),
             preview: None,
+            normalized_hash: None,
         };
 
         let remote = ConflictVersion {
@@ -425,6 +762,7 @@ mod tests {
             modified_at: Utc::now(),
             modified_by: identity2.node_id(),
             preview: None,
+            normalized_hash: None,
         };
 
         let conflict = manager
@@ -457,5 +795,194 @@ mod tests {
         // Should be empty now
         let conflicts = manager.list_conflicts("drive123").await;
         assert_eq!(conflicts.len(), 0);
+
+        // Conflicts on other drives show up in the global list, oldest first
+        let version = |hash: &str, node_id| ConflictVersion {
+            hash: hash.to_string(),
+            size: 1,
+            modified_at: Utc::now(),
+            modified_by: node_id,
+            preview: None,
+            normalized_hash: None,
+        };
+        for (drive, path) in [("drive123", "a.txt"), ("drive456", "b.txt")] {
+            manager
+                .detect_conflict(
+                    drive,
+                    PathBuf::from(path),
+                    version("one", identity1.node_id()),
+                    version("two", identity2.node_id()),
+                    None,
+                )
+                .await;
+        }
+        let all = manager.list_all_conflicts().await;
+        assert_eq!(all.len(), 2);
+        assert_eq!(manager.total_conflict_count().await, 2);
+        assert!(all[0].1.detected_at <= all[1].1.detected_at);
+
+        // Resolved conflicts stay in the path's history
+        let history = manager
+            .get_drive_conflicts("drive123")
+            .await
+            .conflicts_for_path(&PathBuf::from("test/file.txt"))
+            .await;
+        assert_eq!(history.len(), 1);
+        assert!(history[0].resolved);
+    }
+
+    #[test]
+    fn test_last_writer_wins_converges() {
+        let (a, b) = (Identity::generate().node_id(), Identity::generate().node_id());
+        let now = Utc::now();
+        let version = |hash: &str, modified_at, modified_by| ConflictVersion {
+            hash: hash.to_string(),
+            size: 1,
+            modified_at,
+            modified_by,
+            preview: None,
+            normalized_hash: None,
+        };
+        let path = PathBuf::from("shared.txt");
+
+        // Each peer sees its own version as local; both must keep the same one
+        let kept = |local: ConflictVersion, remote: ConflictVersion| {
+            let conflict = FileConflict::new(path.clone(), local.clone(), remote.clone(), None);
+            match ConflictPolicy::LastWriterWins.strategy_for(&conflict) {
+                Some(ResolutionStrategy::KeepLocal) => local.hash,
+                Some(ResolutionStrategy::KeepRemote) => remote.hash,
+                other => panic!("unexpected strategy {:?}", other),
+            }
+        };
+
+        let older = version("older", now - chrono::Duration::seconds(5), a);
+        let newer = version("newer", now, b);
+        assert_eq!(kept(older.clone(), newer.clone()), "newer");
+        assert_eq!(kept(newer, older), "newer");
+
+        // Same timestamp: the higher node ID wins on both sides
+        let from_a = version("from_a", now, a);
+        let from_b = version("from_b", now, b);
+        let expected = if b.as_bytes() > a.as_bytes() { "from_b" } else { "from_a" };
+        assert_eq!(kept(from_a.clone(), from_b.clone()), expected);
+        assert_eq!(kept(from_b, from_a), expected);
+
+        let conflict = FileConflict::new(path, version("x", now, a), version("y", now, b), None);
+        assert_eq!(ConflictPolicy::Manual.strategy_for(&conflict), None);
+        assert_eq!(
+            ConflictPolicy::KeepRemote.strategy_for(&conflict),
+            Some(ResolutionStrategy::KeepRemote)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_detect_conflict_applies_policy() {
+        let manager = ConflictManager::new();
+        let mut events = manager.subscribe();
+        let local_node = Identity::generate().node_id();
+        let remote_node = Identity::generate().node_id();
+        let version = |hash: &str, node_id| ConflictVersion {
+            hash: hash.to_string(),
+            size: 1,
+            modified_at: Utc::now(),
+            modified_by: node_id,
+            preview: None,
+            normalized_hash: None,
+        };
+
+        assert_eq!(manager.policy("drive123").await, ConflictPolicy::Manual);
+        manager.set_policy("drive123", ConflictPolicy::KeepRemote).await;
+
+        let conflict = manager
+            .detect_conflict(
+                "drive123",
+                PathBuf::from("a.txt"),
+                version("local", local_node),
+                version("remote", remote_node),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(conflict.resolved);
+        assert_eq!(conflict.resolution, Some(ResolutionStrategy::KeepRemote));
+        assert!(manager.list_conflicts("drive123").await.is_empty());
+
+        let (drive_id, event) = events.try_recv().unwrap();
+        assert_eq!(drive_id, "drive123");
+        match event {
+            DriveEvent::ConflictAutoResolved {
+                policy,
+                kept_hash,
+                discarded_hash,
+                ..
+            } => {
+                assert_eq!(policy, ConflictPolicy::KeepRemote);
+                assert_eq!(kept_hash, "remote");
+                assert_eq!(discarded_hash, "local");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        // Back to manual: conflicts wait for the user again
+        manager.set_policy("drive123", ConflictPolicy::Manual).await;
+        let conflict = manager
+            .detect_conflict(
+                "drive123",
+                PathBuf::from("b.txt"),
+                version("local", local_node),
+                version("remote", remote_node),
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(!conflict.resolved);
+        assert_eq!(manager.list_conflicts("drive123").await.len(), 1);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_conflict_sensitivity() {
+        let text = Path::new("notes.md");
+        let crlf = b"line one  \r\nline two\r\n\r\n";
+        let lf = b"line one\nline two\n";
+
+        assert!(!ConflictSensitivity::Exact.equivalent(text, crlf, lf));
+        assert!(ConflictSensitivity::IgnoreWhitespace.equivalent(text, crlf, lf));
+        assert!(!ConflictSensitivity::IgnoreWhitespace.equivalent(text, b"a b\n", b"ab\n"));
+
+        // Binaries and non-UTF-8 content are still compared exactly
+        let binary = Path::new("image.png");
+        assert!(!ConflictSensitivity::IgnoreWhitespace.equivalent(binary, crlf, lf));
+        assert!(!ConflictSensitivity::IgnoreWhitespace.equivalent(text, b"\xff\r\n", b"\xff\n"));
+        assert!(ConflictSensitivity::IgnoreWhitespace.normalized_hash(binary, lf).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_detect_conflict_ignores_cosmetic_changes() {
+        let manager = ConflictManager::new();
+        let path = PathBuf::from("test/file.txt");
+        let sensitivity = ConflictSensitivity::IgnoreWhitespace;
+        let version = |hash: &str, content: &[u8]| ConflictVersion {
+            hash: hash.to_string(),
+            size: content.len() as u64,
+            modified_at: Utc::now(),
+            modified_by: Identity::generate().node_id(),
+            preview: None,
+            normalized_hash: sensitivity.normalized_hash(&path, content),
+        };
+
+        let local = version("local123", b"fn main() {}\r\n");
+        let remote = version("remote456", b"fn main() {}\n");
+        let conflict = manager
+            .detect_conflict("drive123", path.clone(), local, remote, None)
+            .await;
+        assert!(conflict.is_none());
+
+        let local = version("local123", b"fn main() {}\n");
+        let remote = version("remote456", b"fn main() { run() }\n");
+        let conflict = manager
+            .detect_conflict("drive123", path.clone(), local, remote, None)
+            .await;
+        assert!(conflict.is_some());
     }
 }
@@ -0,0 +1,237 @@
+//! Drive consistency checks
+//!
+//! A crash between writing a file and updating its metadata (or the other
+//! way round) leaves a drive with files nobody else knows about, or metadata
+//! pointing at content that is not on disk. This module compares the two
+//! sides; the `check_drive_consistency` command applies repairs.
+//!
+//! A reimport or rebuilt blob store can also leave a local file and the blob
+//! its metadata points to disagreeing; `verify_local_matches_blobs` reports
+//! those using [`classify_blob_mismatch`].
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Kind of mismatch between local files and synced metadata
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InconsistencyKind {
+    /// A local file has no metadata entry (peers never learned about it)
+    MissingMetadata,
+    /// Metadata exists and the blob is stored locally, but the file is gone
+    MissingFile,
+    /// Metadata exists but neither the file nor its blob is available
+    MissingContent,
+}
+
+/// A single mismatch found by a consistency check
+#[derive(Clone, Debug, Serialize)]
+pub struct Inconsistency {
+    /// Drive-relative path (forward slashes)
+    pub path: String,
+    pub kind: InconsistencyKind,
+    /// Content hash recorded in metadata, if any
+    pub content_hash: Option<String>,
+    /// Whether a repair succeeded
+    pub repaired: bool,
+    /// Whether a download of missing content from peers was started
+    pub fetch_queued: bool,
+    /// Why a repair was not possible or failed
+    pub error: Option<String>,
+}
+
+/// Result of checking (and optionally repairing) a drive
+#[derive(Clone, Debug, Serialize)]
+pub struct ConsistencyReport {
+    pub drive_id: String,
+    /// Local files examined
+    pub files_checked: usize,
+    /// Metadata entries examined
+    pub metadata_checked: usize,
+    pub inconsistencies: Vec<Inconsistency>,
+    /// Number of inconsistencies fixed
+    pub repaired: usize,
+    /// True if the check was cancelled; remaining items were not examined
+    /// or repaired
+    pub cancelled: bool,
+    /// ISO 8601 time of the check
+    pub checked_at: String,
+}
+
+/// How a local file and the blob its metadata points to disagree
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobMismatchKind {
+    /// The file was edited after its metadata was written and not re-imported
+    LocalNewer,
+    /// Metadata points to newer content than the file on disk
+    BlobNewer,
+    /// The file matches its metadata, but the stored blob is missing or
+    /// corrupted
+    BlobCorrupt,
+    /// Neither the file nor the stored blob matches the metadata
+    BothDiffer,
+}
+
+/// Which side wins when repairing a `BlobMismatch`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlobRepair {
+    /// Import the local file and point metadata at it
+    ReimportLocal,
+    /// Overwrite the local file with the stored blob
+    ExportBlob,
+}
+
+/// A file whose local content and stored blob disagree
+#[derive(Clone, Debug, Serialize)]
+pub struct BlobMismatch {
+    /// Drive-relative path (forward slashes)
+    pub path: String,
+    pub kind: BlobMismatchKind,
+    /// Hash of the file on disk
+    pub local_hash: String,
+    /// Content hash recorded in metadata
+    pub metadata_hash: String,
+    /// Whether a repair succeeded
+    pub repaired: bool,
+    /// Why a repair was not possible or failed
+    pub error: Option<String>,
+}
+
+/// Result of comparing a drive's local files with their stored blobs
+#[derive(Clone, Debug, Serialize)]
+pub struct BlobMatchReport {
+    pub drive_id: String,
+    /// Local files with metadata that were compared
+    pub files_checked: usize,
+    pub mismatches: Vec<BlobMismatch>,
+    /// Number of mismatches fixed
+    pub repaired: usize,
+    /// True if the check was cancelled; remaining files were not examined
+    /// or repaired
+    pub cancelled: bool,
+    /// ISO 8601 time of the check
+    pub checked_at: String,
+}
+
+/// Classify a local file against its metadata and stored blob
+///
+/// `blob_intact` is whether the blob for `metadata_hash` is stored and
+/// still hashes to it; `local_newer` whether the file was modified after its
+/// metadata was written. Returns None if everything agrees.
+pub fn classify_blob_mismatch(
+    local_hash: &str,
+    metadata_hash: &str,
+    blob_intact: bool,
+    local_newer: bool,
+) -> Option<BlobMismatchKind> {
+    let local_matches = local_hash.eq_ignore_ascii_case(metadata_hash);
+    match (local_matches, blob_intact) {
+        (true, true) => None,
+        (true, false) => Some(BlobMismatchKind::BlobCorrupt),
+        (false, false) => Some(BlobMismatchKind::BothDiffer),
+        (false, true) if local_newer => Some(BlobMismatchKind::LocalNewer),
+        (false, true) => Some(BlobMismatchKind::BlobNewer),
+    }
+}
+
+/// Compare local files against metadata entries
+///
+/// `local_files` and metadata paths are drive-relative keys with forward
+/// slashes; `metadata` pairs each path with its content hash. `local_blobs`
+/// holds the hashes whose blobs are complete in the local store.
+pub fn find_inconsistencies(
+    local_files: &HashSet<String>,
+    metadata: &[(String, Option<String>)],
+    local_blobs: &HashSet<String>,
+) -> Vec<Inconsistency> {
+    let mut found = Vec::new();
+
+    let known: HashSet<&str> = metadata.iter().map(|(path, _)| path.as_str()).collect();
+    let mut missing_metadata: Vec<&String> = local_files
+        .iter()
+        .filter(|path| !known.contains(path.as_str()))
+        .collect();
+    missing_metadata.sort();
+    for path in missing_metadata {
+        found.push(Inconsistency {
+            path: path.clone(),
+            kind: InconsistencyKind::MissingMetadata,
+            content_hash: None,
+            repaired: false,
+            fetch_queued: false,
+            error: None,
+        });
+    }
+
+    for (path, hash) in metadata {
+        if local_files.contains(path) {
+            continue;
+        }
+        let has_blob = hash.as_ref().is_some_and(|h| local_blobs.contains(h));
+        found.push(Inconsistency {
+            path: path.clone(),
+            kind: if has_blob {
+                InconsistencyKind::MissingFile
+            } else {
+                InconsistencyKind::MissingContent
+            },
+            content_hash: hash.clone(),
+            repaired: false,
+            fetch_queued: false,
+            error: None,
+        });
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_inconsistencies() {
+        let local: HashSet<String> = ["a.txt", "docs/new.txt"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let metadata = vec![
+            ("a.txt".to_string(), Some("h1".to_string())),
+            ("b.txt".to_string(), Some("h2".to_string())),
+            ("c.txt".to_string(), Some("h3".to_string())),
+        ];
+        let blobs: HashSet<String> = ["h2".to_string()].into_iter().collect();
+
+        let found = find_inconsistencies(&local, &metadata, &blobs);
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[0].path, "docs/new.txt");
+        assert_eq!(found[0].kind, InconsistencyKind::MissingMetadata);
+        assert_eq!(found[1].path, "b.txt");
+        assert_eq!(found[1].kind, InconsistencyKind::MissingFile);
+        assert_eq!(found[2].path, "c.txt");
+        assert_eq!(found[2].kind, InconsistencyKind::MissingContent);
+    }
+
+    #[test]
+    fn test_classify_blob_mismatch() {
+        assert_eq!(classify_blob_mismatch("AB", "ab", true, true), None);
+        assert_eq!(
+            classify_blob_mismatch("ab", "ab", false, false),
+            Some(BlobMismatchKind::BlobCorrupt)
+        );
+        assert_eq!(
+            classify_blob_mismatch("cd", "ab", true, true),
+            Some(BlobMismatchKind::LocalNewer)
+        );
+        assert_eq!(
+            classify_blob_mismatch("cd", "ab", true, false),
+            Some(BlobMismatchKind::BlobNewer)
+        );
+        assert_eq!(
+            classify_blob_mismatch("cd", "ab", false, true),
+            Some(BlobMismatchKind::BothDiffer)
+        );
+    }
+}
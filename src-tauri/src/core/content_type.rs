@@ -0,0 +1,284 @@
+//! Content type detection for synced files
+//!
+//! A file's type is sniffed from its first bytes when it changes, falling
+//! back to the extension, and recorded in its metadata so the UI can pick
+//! icons for files that aren't downloaded. The type also says whether the
+//! content is worth compressing: images, audio, video and archives are
+//! already compressed. Unknown content is never treated as compressible.
+
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read from the start of a file to sniff its type
+pub const SNIFF_LEN: usize = 512;
+
+/// Type recorded for content that can't be identified
+pub const UNKNOWN_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Whether compressing content of a type saves anything
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compressibility {
+    /// Text and other formats that usually shrink well
+    Compressible,
+    /// Formats that are already compressed
+    Incompressible,
+    /// Not known; handled like `Incompressible`
+    Unknown,
+}
+
+/// Identify content by its magic number
+pub fn sniff(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+        (b"Rar!\x1a\x07", "application/vnd.rar"),
+        (b"\xfd7zXZ\x00", "application/x-xz"),
+        (b"BZh", "application/x-bzip2"),
+        (b"\x28\xb5\x2f\xfd", "application/zstd"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    ];
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(mime);
+    }
+    if head.len() >= 12 && head.starts_with(b"RIFF") {
+        return match &head[8..12] {
+            b"WEBP" => Some("image/webp"),
+            b"WAVE" => Some("audio/wav"),
+            b"AVI " => Some("video/x-msvideo"),
+            _ => None,
+        };
+    }
+    // ISO base media (MP4, MOV, HEIC): a box size, then "ftyp" and a brand
+    if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        return match &head[8..12] {
+            b"heic" | b"heix" | b"mif1" => Some("image/heic"),
+            b"qt  " => Some("video/quicktime"),
+            b"M4A " => Some("audio/mp4"),
+            _ => Some("video/mp4"),
+        };
+    }
+    None
+}
+
+/// Guess a type from a file's extension
+pub fn from_extension(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    let mime = match ext.as_str() {
+        "txt" | "log" | "rs" | "py" | "ts" | "tsx" | "jsx" | "toml" | "yaml" | "yml" | "ini"
+        | "c" | "h" | "cpp" | "go" | "java" | "sh" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "js" | "mjs" => "text/javascript",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "heic" => "image/heic",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "flac" => "audio/flac",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "mkv" | "webm" => "video/webm",
+        "avi" => "video/x-msvideo",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "7z" => "application/x-7z-compressed",
+        "rar" => "application/vnd.rar",
+        "xz" => "application/x-xz",
+        "bz2" => "application/x-bzip2",
+        "zst" => "application/zstd",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "epub" => "application/epub+zip",
+        "sqlite" | "db" => "application/vnd.sqlite3",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// Whether `head` looks like text: valid UTF-8 (allowing a character cut
+/// off at the end) without NUL bytes
+fn looks_like_text(head: &[u8]) -> bool {
+    if head.is_empty() || head.contains(&0) {
+        return false;
+    }
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none() && head.len() - e.valid_up_to() < 4,
+    }
+}
+
+/// Detect the type of a file from its first bytes and its path
+///
+/// Magic numbers win over the extension, except that a ZIP container keeps
+/// a more specific extension type (docx, epub, ...).
+pub fn detect(path: &Path, head: &[u8]) -> &'static str {
+    let by_extension = from_extension(path);
+    match sniff(head) {
+        Some("application/zip") => by_extension
+            .filter(|mime| mime.ends_with("+zip") || mime.contains("officedocument"))
+            .unwrap_or("application/zip"),
+        Some(mime) => mime,
+        None => by_extension.unwrap_or_else(|| {
+            if looks_like_text(head) {
+                "text/plain"
+            } else {
+                UNKNOWN_CONTENT_TYPE
+            }
+        }),
+    }
+}
+
+/// Read the start of a file and detect its type
+///
+/// Returns None for directories and files that can't be read.
+pub fn detect_file(path: &Path) -> Option<String> {
+    let file = std::fs::File::open(path).ok()?;
+    if file.metadata().ok()?.is_dir() {
+        return None;
+    }
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    file.take(SNIFF_LEN as u64).read_to_end(&mut head).ok()?;
+    Some(detect(path, &head).to_string())
+}
+
+/// Whether content of type `mime` is worth compressing
+pub fn compressibility(mime: &str) -> Compressibility {
+    const COMPRESSIBLE: &[&str] = &[
+        "application/json",
+        "application/xml",
+        "application/javascript",
+        "application/vnd.sqlite3",
+        "image/svg+xml",
+        "audio/wav",
+    ];
+    if mime.starts_with("text/") || COMPRESSIBLE.contains(&mime) {
+        Compressibility::Compressible
+    } else if mime.starts_with("image/")
+        || mime.starts_with("audio/")
+        || mime.starts_with("video/")
+        || mime.ends_with("zip")
+        || mime.contains("officedocument")
+        || [
+            "application/pdf",
+            "application/x-7z-compressed",
+            "application/vnd.rar",
+            "application/x-xz",
+            "application/x-bzip2",
+            "application/zstd",
+        ]
+        .contains(&mime)
+    {
+        Compressibility::Incompressible
+    } else {
+        Compressibility::Unknown
+    }
+}
+
+/// Whether content of an (optionally unknown) type should be compressed
+///
+/// Conservative: only types known to be compressible are.
+pub fn should_compress(content_type: Option<&str>) -> bool {
+    content_type.map(compressibility) == Some(Compressibility::Compressible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let png = b"\x89PNG\r\n\x1a\nrest";
+        // Magic numbers win over a misleading extension
+        assert_eq!(detect(Path::new("image.txt"), png), "image/png");
+        assert_eq!(
+            detect(Path::new("clip.bin"), b"\x00\x00\x00\x18ftypisom"),
+            "video/mp4"
+        );
+        assert_eq!(
+            detect(Path::new("a.webp"), b"RIFF\x00\x00\x00\x00WEBPVP8 "),
+            "image/webp"
+        );
+        // ZIP containers keep a more specific extension type
+        assert_eq!(detect(Path::new("a.zip"), b"PK\x03\x04"), "application/zip");
+        assert_eq!(
+            detect(Path::new("book.epub"), b"PK\x03\x04"),
+            "application/epub+zip"
+        );
+
+        assert_eq!(detect(Path::new("notes.md"), b"# Notes"), "text/markdown");
+        assert_eq!(
+            detect(Path::new("README"), "héllo".as_bytes()),
+            "text/plain"
+        );
+        // A multi-byte character cut off by the sniff length is still text
+        assert_eq!(
+            detect(Path::new("README"), &"é".as_bytes()[..1]),
+            "text/plain"
+        );
+        assert_eq!(
+            detect(Path::new("blob"), b"\x00\x01\x02"),
+            UNKNOWN_CONTENT_TYPE
+        );
+    }
+
+    #[test]
+    fn test_compressibility() {
+        assert_eq!(compressibility("text/plain"), Compressibility::Compressible);
+        assert_eq!(
+            compressibility("application/json"),
+            Compressibility::Compressible
+        );
+        assert_eq!(
+            compressibility("image/svg+xml"),
+            Compressibility::Compressible
+        );
+        assert_eq!(
+            compressibility("image/jpeg"),
+            Compressibility::Incompressible
+        );
+        assert_eq!(
+            compressibility("video/mp4"),
+            Compressibility::Incompressible
+        );
+        assert_eq!(
+            compressibility("application/zip"),
+            Compressibility::Incompressible
+        );
+        assert_eq!(
+            compressibility("application/epub+zip"),
+            Compressibility::Incompressible
+        );
+        assert_eq!(
+            compressibility(UNKNOWN_CONTENT_TYPE),
+            Compressibility::Unknown
+        );
+
+        assert!(should_compress(Some("text/csv")));
+        assert!(!should_compress(Some("image/png")));
+        assert!(!should_compress(Some(UNKNOWN_CONTENT_TYPE)));
+        assert!(!should_compress(None));
+    }
+}
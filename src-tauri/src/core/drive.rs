@@ -1,9 +1,18 @@
-use crate::crypto::NodeId;
+use crate::core::{
+    ActivityRetention, AppError, ConflictPolicy, ConflictSensitivity, NamePolicy, NotificationPrefs,
+};
+use crate::crypto::{NodeId, Permission};
+use crate::storage::Database;
 use blake3::Hasher;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+/// Default days a deleted drive can be restored before it is removed for
+/// good (see `CleanupConfig::deleted_drive_retention_days`)
+pub const DELETED_DRIVE_RETENTION_DAYS: i64 = 30;
+
 /// Unique drive identifier (32-byte BLAKE3 hash)
 #[derive(Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct DriveId(pub [u8; 32]);
@@ -44,6 +53,19 @@ impl std::fmt::Display for DriveId {
     }
 }
 
+/// What happens when a peer adds a file this device doesn't have yet
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NewFilePolicy {
+    /// Fetch the content right away
+    #[default]
+    AutoDownload,
+    /// Keep the file remote-only until it is opened
+    OnDemand,
+    /// Notify and let the user choose whether to download
+    Prompt,
+}
+
 /// A shared drive represents a folder that can be accessed by multiple peers
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct SharedDrive {
@@ -61,6 +83,49 @@ pub struct SharedDrive {
     pub total_size: u64,
     /// Number of files (calculated from file index)
     pub file_count: u64,
+    /// Maximum gossip neighbors for this drive (None = iroh's default)
+    #[serde(default)]
+    pub max_peers: Option<usize>,
+    /// Send (as a member) or record (as the owner) read receipts
+    #[serde(default)]
+    pub read_receipts: bool,
+    /// Which events raise system notifications for this drive
+    #[serde(default)]
+    pub notifications: NotificationPrefs,
+    /// Peers that stay authorized but are never synced with directly
+    #[serde(default)]
+    pub no_direct_sync: Vec<NodeId>,
+    /// When the drive was archived (read-only, not synced); None if active
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// When the drive was deleted on this device; None unless awaiting
+    /// permanent removal
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// User-defined key/value tags (project code, department, ...)
+    #[serde(default)]
+    pub custom_metadata: HashMap<String, String>,
+    /// Permission for new invites that don't specify one
+    #[serde(default)]
+    pub default_invite_permission: Option<Permission>,
+    /// Largest file allowed in the drive, in bytes (0 = unlimited)
+    #[serde(default)]
+    pub max_file_size: u64,
+    /// How file names are canonicalized on write and import
+    #[serde(default)]
+    pub name_policy: NamePolicy,
+    /// Whether cosmetic text differences count as conflicts
+    #[serde(default)]
+    pub conflict_sensitivity: ConflictSensitivity,
+    /// How conflicts are resolved when they're detected
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    /// How much of the activity feed is kept
+    #[serde(default)]
+    pub activity_retention: ActivityRetention,
+    /// Whether files added by peers are downloaded right away
+    #[serde(default)]
+    pub new_file_policy: NewFilePolicy,
 }
 
 impl SharedDrive {
@@ -75,7 +140,66 @@ impl SharedDrive {
             created_at: Utc::now(),
             total_size: 0,
             file_count: 0,
+            max_peers: None,
+            read_receipts: false,
+            notifications: NotificationPrefs::default(),
+            no_direct_sync: Vec::new(),
+            archived_at: None,
+            deleted_at: None,
+            custom_metadata: HashMap::new(),
+            default_invite_permission: None,
+            max_file_size: 0,
+            name_policy: NamePolicy::default(),
+            conflict_sensitivity: ConflictSensitivity::default(),
+            conflict_policy: ConflictPolicy::default(),
+            activity_retention: ActivityRetention::default(),
+            new_file_policy: NewFilePolicy::default(),
+        }
+    }
+
+    /// Check if the drive is archived
+    pub fn is_archived(&self) -> bool {
+        self.archived_at.is_some()
+    }
+
+    /// Check if the drive was deleted and can still be restored
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    /// When a deleted drive is removed for good, given how long deleted
+    /// drives are kept
+    pub fn purge_at(&self, retention: chrono::Duration) -> Option<DateTime<Utc>> {
+        self.deleted_at.map(|t| t + retention)
+    }
+
+    /// Fail if the drive is archived, for operations that write or sync
+    pub fn ensure_active(&self) -> Result<(), AppError> {
+        if self.is_archived() {
+            return Err(AppError::AccessDenied {
+                reason: format!("drive {} is archived", self.name),
+            });
+        }
+        Ok(())
+    }
+
+    /// Fail if a file of `size` bytes at `path` exceeds the drive's limit
+    pub fn check_file_size(&self, path: &str, size: u64) -> Result<(), AppError> {
+        if self.max_file_size > 0 && size > self.max_file_size {
+            return Err(AppError::FileTooLarge {
+                path: path.to_string(),
+                size,
+                max: self.max_file_size,
+            });
         }
+        Ok(())
+    }
+
+    /// Check if every key in `filter` is set to the same value on this drive
+    pub fn matches_metadata(&self, filter: &HashMap<String, String>) -> bool {
+        filter
+            .iter()
+            .all(|(key, value)| self.custom_metadata.get(key) == Some(value))
     }
 
     /// Update statistics after indexing
@@ -95,6 +219,19 @@ pub struct DriveInfo {
     pub created_at: String,
     pub total_size: u64,
     pub file_count: u64,
+    /// ISO 8601 time the drive was archived, if it is
+    pub archived_at: Option<String>,
+    /// ISO 8601 time the drive was deleted, if it is awaiting removal
+    pub deleted_at: Option<String>,
+    pub custom_metadata: HashMap<String, String>,
+    pub default_invite_permission: Option<Permission>,
+    /// Largest file allowed in the drive, in bytes (0 = unlimited)
+    pub max_file_size: u64,
+    pub name_policy: NamePolicy,
+    pub conflict_sensitivity: ConflictSensitivity,
+    pub conflict_policy: ConflictPolicy,
+    pub activity_retention: ActivityRetention,
+    pub new_file_policy: NewFilePolicy,
 }
 
 impl From<&SharedDrive> for DriveInfo {
@@ -107,10 +244,57 @@ impl From<&SharedDrive> for DriveInfo {
             created_at: drive.created_at.to_rfc3339(),
             total_size: drive.total_size,
             file_count: drive.file_count,
+            archived_at: drive.archived_at.map(|t| t.to_rfc3339()),
+            deleted_at: drive.deleted_at.map(|t| t.to_rfc3339()),
+            custom_metadata: drive.custom_metadata.clone(),
+            default_invite_permission: drive.default_invite_permission,
+            max_file_size: drive.max_file_size,
+            name_policy: drive.name_policy,
+            conflict_sensitivity: drive.conflict_sensitivity,
+            conflict_policy: drive.conflict_policy,
+            activity_retention: drive.activity_retention,
+            new_file_policy: drive.new_file_policy,
+        }
+    }
+}
+
+/// A deleted drive that can still be restored
+#[derive(Clone, Serialize, Debug)]
+pub struct DeletedDriveInfo {
+    #[serde(flatten)]
+    pub drive: DriveInfo,
+    /// ISO 8601 time the drive will be removed for good
+    pub purge_at: String,
+}
+
+impl DeletedDriveInfo {
+    /// Describe a deleted drive kept for `retention` after its deletion
+    pub fn new(drive: &SharedDrive, retention: chrono::Duration) -> Self {
+        Self {
+            drive: DriveInfo::from(drive),
+            purge_at: drive
+                .purge_at(retention)
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
         }
     }
 }
 
+/// Deleted drives stored in `db`, most recently deleted first
+///
+/// Deleted drives aren't loaded into memory, so this reads them from the
+/// database.
+pub fn load_deleted_drives(db: &Database) -> anyhow::Result<Vec<SharedDrive>> {
+    let mut deleted: Vec<SharedDrive> = db
+        .list_drives()?
+        .into_iter()
+        .filter_map(|(_, data)| serde_json::from_slice::<SharedDrive>(&data).ok())
+        .filter(SharedDrive::is_deleted)
+        .collect();
+    deleted.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+    Ok(deleted)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,4 +327,117 @@ mod tests {
 
         assert_eq!(id.as_bytes(), restored.as_bytes());
     }
+
+    #[test]
+    fn test_archived_drive_is_read_only() {
+        let identity = Identity::generate();
+        let mut drive = SharedDrive::new(
+            "Test".to_string(),
+            std::path::PathBuf::from("/test/path"),
+            identity.node_id(),
+        );
+        assert!(drive.ensure_active().is_ok());
+        assert!(DriveInfo::from(&drive).archived_at.is_none());
+
+        drive.archived_at = Some(Utc::now());
+        assert!(matches!(drive.ensure_active(), Err(AppError::AccessDenied { .. })));
+        assert!(DriveInfo::from(&drive).archived_at.is_some());
+    }
+
+    #[test]
+    fn test_load_deleted_drives() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path().join("test.redb")).unwrap();
+        let identity = Identity::generate();
+
+        let active =
+            SharedDrive::new("Active".to_string(), dir.path().join("a"), identity.node_id());
+        let mut deleted =
+            SharedDrive::new("Deleted".to_string(), dir.path().join("d"), identity.node_id());
+        deleted.deleted_at = Some(Utc::now() - chrono::Duration::days(2));
+        for drive in [&active, &deleted] {
+            db.save_drive(drive.id.as_bytes(), &serde_json::to_vec(drive).unwrap())
+                .unwrap();
+        }
+
+        let loaded = load_deleted_drives(&db).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "Deleted");
+        let retention = chrono::Duration::days(DELETED_DRIVE_RETENTION_DAYS);
+        let info = DeletedDriveInfo::new(&loaded[0], retention);
+        assert!(info.drive.deleted_at.is_some());
+        let days_left = loaded[0].purge_at(retention).unwrap() - Utc::now();
+        assert_eq!(days_left.num_days(), DELETED_DRIVE_RETENTION_DAYS - 3);
+
+        // A shorter configured retention moves the purge date closer
+        let days_left = loaded[0].purge_at(chrono::Duration::days(7)).unwrap() - Utc::now();
+        assert_eq!(days_left.num_days(), 4);
+    }
+
+    #[test]
+    fn test_no_direct_sync_defaults_empty() {
+        let identity = Identity::generate();
+        let mut drive = SharedDrive::new(
+            "Test".to_string(),
+            std::path::PathBuf::from("/test/path"),
+            identity.node_id(),
+        );
+        drive.no_direct_sync.push(Identity::generate().node_id());
+
+        // Drives stored before the field existed load with an empty list
+        let mut json = serde_json::to_value(&drive).unwrap();
+        json.as_object_mut().unwrap().remove("no_direct_sync");
+        let restored: SharedDrive = serde_json::from_value(json).unwrap();
+        assert!(restored.no_direct_sync.is_empty());
+
+        let bytes = serde_json::to_vec(&drive).unwrap();
+        let restored: SharedDrive = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(restored.no_direct_sync, drive.no_direct_sync);
+    }
+
+    #[test]
+    fn test_matches_metadata() {
+        let identity = Identity::generate();
+        let mut drive = SharedDrive::new(
+            "Test".to_string(),
+            std::path::PathBuf::from("/test/path"),
+            identity.node_id(),
+        );
+        drive
+            .custom_metadata
+            .insert("dept".to_string(), "legal".to_string());
+
+        assert!(drive.matches_metadata(&HashMap::new()));
+        assert!(drive.matches_metadata(&HashMap::from([(
+            "dept".to_string(),
+            "legal".to_string()
+        )])));
+        assert!(!drive.matches_metadata(&HashMap::from([(
+            "dept".to_string(),
+            "sales".to_string()
+        )])));
+        assert!(!drive.matches_metadata(&HashMap::from([(
+            "project".to_string(),
+            "legal".to_string()
+        )])));
+    }
+
+    #[test]
+    fn test_check_file_size() {
+        let identity = Identity::generate();
+        let mut drive = SharedDrive::new(
+            "Test".to_string(),
+            std::path::PathBuf::from("/test/path"),
+            identity.node_id(),
+        );
+        // Zero means unlimited
+        assert!(drive.check_file_size("big.bin", u64::MAX).is_ok());
+
+        drive.max_file_size = 1024;
+        assert!(drive.check_file_size("small.txt", 1024).is_ok());
+        assert!(matches!(
+            drive.check_file_size("big.bin", 1025),
+            Err(AppError::FileTooLarge { size: 1025, max: 1024, .. })
+        ));
+    }
 }
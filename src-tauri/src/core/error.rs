@@ -42,6 +42,9 @@ pub enum AppError {
     #[error("Invalid path: {path} - {reason}")]
     InvalidPath { path: String, reason: String },
 
+    #[error("File too large: {path} is {size} bytes, limit is {max} bytes")]
+    FileTooLarge { path: String, size: u64, max: u64 },
+
     // ========== Identity Errors ==========
     #[error("Identity not initialized")]
     IdentityNotInitialized,
@@ -75,6 +78,9 @@ pub enum AppError {
     #[error("Sync failed: {0}")]
     SyncFailed(String),
 
+    #[error("Peer did not respond: {peer}")]
+    PeerUnreachable { peer: String },
+
     // ========== Lock Errors ==========
     #[error("File locked by another user: {holder}")]
     FileLocked { path: String, holder: String },
@@ -95,6 +101,12 @@ pub enum AppError {
     #[error("Transfer not found: {id}")]
     TransferNotFound { id: String },
 
+    #[error("Content of {path} does not match its metadata: expected {expected}, got {actual}")]
+    ContentMismatch { path: String, expected: String, actual: String },
+
+    #[error("Version {version} of {path} not found")]
+    VersionNotFound { path: String, version: u64 },
+
     // ========== Token Errors ==========
     #[error("Invalid token format")]
     InvalidTokenFormat,
@@ -128,6 +140,9 @@ pub enum AppError {
     #[error("Serialization error: {0}")]
     SerializationError(String),
 
+    #[error("Backup failed: {0}")]
+    BackupFailed(String),
+
     // ========== Internal Errors ==========
     #[error("Internal error: {0}")]
     Internal(String),
@@ -150,6 +165,7 @@ impl AppError {
             AppError::PathTraversal { .. } => "PATH_TRAVERSAL",
             AppError::PathOutsideDrive { .. } => "PATH_OUTSIDE_DRIVE",
             AppError::InvalidPath { .. } => "INVALID_PATH",
+            AppError::FileTooLarge { .. } => "FILE_TOO_LARGE",
             AppError::IdentityNotInitialized => "IDENTITY_NOT_INIT",
             AppError::IdentityLoadFailed(_) => "IDENTITY_LOAD_FAILED",
             AppError::InsufficientPermission { .. } => "PERMISSION_DENIED",
@@ -160,12 +176,15 @@ impl AppError {
             AppError::TransferNotInitialized => "TRANSFER_NOT_INIT",
             AppError::BroadcasterNotInitialized => "BROADCASTER_NOT_INIT",
             AppError::SyncFailed(_) => "SYNC_FAILED",
+            AppError::PeerUnreachable { .. } => "PEER_UNREACHABLE",
             AppError::FileLocked { .. } => "FILE_LOCKED",
             AppError::LockNotFound { .. } => "LOCK_NOT_FOUND",
             AppError::LockExpired { .. } => "LOCK_EXPIRED",
             AppError::TransferFailed(_) => "TRANSFER_FAILED",
             AppError::InvalidHash(_) => "INVALID_HASH",
             AppError::TransferNotFound { .. } => "TRANSFER_NOT_FOUND",
+            AppError::ContentMismatch { .. } => "CONTENT_MISMATCH",
+            AppError::VersionNotFound { .. } => "VERSION_NOT_FOUND",
             AppError::InvalidTokenFormat => "INVALID_TOKEN",
             AppError::TokenExpired => "TOKEN_EXPIRED",
             AppError::InvalidSignature => "INVALID_SIGNATURE",
@@ -176,6 +195,7 @@ impl AppError {
             AppError::NameInvalidChars => "NAME_INVALID_CHARS",
             AppError::DatabaseError(_) => "DATABASE_ERROR",
             AppError::SerializationError(_) => "SERIALIZATION_ERROR",
+            AppError::BackupFailed(_) => "BACKUP_FAILED",
             AppError::Internal(_) => "INTERNAL_ERROR",
             AppError::RateLimited { .. } => "RATE_LIMITED",
         }
@@ -186,6 +206,7 @@ impl AppError {
         matches!(
             self,
             AppError::SyncFailed(_)
+                | AppError::PeerUnreachable { .. }
                 | AppError::TransferFailed(_)
                 | AppError::RateLimited { .. }
                 | AppError::DatabaseError(_)
@@ -7,11 +7,117 @@
 //!
 //! All gossip messages are signed for authentication.
 
+use crate::core::channel::{BackpressureStrategy, Coalescer, IMPORTANT_EVENT_TIMEOUT};
+use crate::core::{ConflictPolicy, ResolutionStrategy};
 use crate::crypto::{Identity, NodeId};
 use chrono::{DateTime, Utc};
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Protocol version stamped on gossip envelopes, doc metadata and presence
+/// announcements
+///
+/// Bumped when a change breaks what older builds can parse.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Oldest peer protocol version this build can sync with
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+/// Reason a peer's protocol version is incompatible with ours, if it is
+///
+/// Versions older than `MIN_PROTOCOL_VERSION` are incompatible. Newer
+/// versions are compatible as long as their messages still parse, so
+/// `parsed` says whether the message carrying the version did. Version 0
+/// (from before versioning) counts as 1.
+pub fn protocol_incompatibility(version: u32, parsed: bool) -> Option<String> {
+    let version = version.max(1);
+    if version < MIN_PROTOCOL_VERSION {
+        Some(format!(
+            "it uses protocol {}, older than the oldest this app supports ({})",
+            version, MIN_PROTOCOL_VERSION
+        ))
+    } else if version > PROTOCOL_VERSION && !parsed {
+        Some(format!(
+            "it uses protocol {}, newer than this app's ({}); update to sync with it",
+            version, PROTOCOL_VERSION
+        ))
+    } else {
+        None
+    }
+}
+
+/// Optional features a peer can advertise when announcing presence
+///
+/// Sent over the wire as strings, so names this build doesn't know are
+/// ignored instead of failing to parse (or breaking the signature).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// End-to-end encrypted file content
+    Encryption,
+    /// Writes split into separately committed chunks
+    ChunkedTransfer,
+}
+
+impl Capability {
+    /// Wire name of this capability
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Encryption => "encryption",
+            Capability::ChunkedTransfer => "chunked_transfer",
+        }
+    }
+
+    /// Parse a wire name, returning None for unknown capabilities
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "encryption" => Some(Capability::Encryption),
+            "chunked_transfer" => Some(Capability::ChunkedTransfer),
+            _ => None,
+        }
+    }
+
+    /// Capabilities this build supports, as wire names
+    pub fn local() -> Vec<String> {
+        vec![Capability::Encryption.as_str().to_string()]
+    }
+}
+
+/// What a peer advertised in its last presence announcement
+#[derive(Clone, Debug, Serialize)]
+pub struct PeerCapabilities {
+    /// Peer's protocol version (0 if it predates versioning)
+    pub protocol_version: u32,
+    /// Advertised capabilities this build knows about
+    pub capabilities: Vec<Capability>,
+    /// False if the peer's protocol is older than this build supports
+    pub compatible: bool,
+    pub advertised_at: DateTime<Utc>,
+}
+
+impl PeerCapabilities {
+    /// Build from an announcement, dropping unknown capability names
+    pub fn from_advertisement(protocol_version: u32, names: &[String]) -> Self {
+        let mut capabilities = Vec::new();
+        for capability in names.iter().filter_map(|name| Capability::parse(name)) {
+            if !capabilities.contains(&capability) {
+                capabilities.push(capability);
+            }
+        }
+        Self {
+            protocol_version,
+            capabilities,
+            compatible: protocol_incompatibility(protocol_version, true).is_none(),
+            advertised_at: Utc::now(),
+        }
+    }
+
+    /// Check if the peer advertised a capability
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
 
 /// Events broadcast over gossip for real-time updates
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -24,6 +130,14 @@ pub enum DriveEvent {
         size: u64,
         modified_by: NodeId,
         timestamp: DateTime<Utc>,
+        /// True if no metadata existed for the path before this change.
+        /// Omitted when false so edits serialize as they did before.
+        #[serde(default, skip_serializing_if = "is_false")]
+        created: bool,
+        /// Detected content type (see `core::content_type`); omitted if
+        /// unknown, and by peers that predate detection
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content_type: Option<String>,
     },
 
     /// A file was deleted
@@ -33,6 +147,30 @@ pub enum DriveEvent {
         timestamp: DateTime<Utc>,
     },
 
+    /// Many files under one directory changed within a short window
+    ///
+    /// Sent in place of per-file announcements once a burst passes the
+    /// configured threshold (see `network::coalesce`).
+    FilesChangedSummary {
+        /// Directory relative to the drive root (empty for the root)
+        dir: PathBuf,
+        /// Distinct files changed during the burst
+        count: u32,
+        /// How many of the changes were deletions
+        deleted: u32,
+        /// Changed files, for drill-down; capped, so may be fewer than `count`
+        paths: Vec<PathBuf>,
+        changed_by: NodeId,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A member opened a file (read receipt, only sent when opted in)
+    FileRead {
+        path: PathBuf,
+        reader: NodeId,
+        timestamp: DateTime<Utc>,
+    },
+
     /// A file is being edited (advisory lock)
     FileEditStarted {
         path: PathBuf,
@@ -61,10 +199,49 @@ pub enum DriveEvent {
         timestamp: DateTime<Utc>,
     },
 
+    /// A released or expired lock was handed to the first queued request
+    ///
+    /// Local only; the grant is announced to peers as a `FileLockAcquired`.
+    LockGranted {
+        path: PathBuf,
+        holder: NodeId,
+        lock_type: String,
+        expires_at: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A requester's position in a file's lock queue changed
+    LockQueueChanged {
+        path: PathBuf,
+        requester: NodeId,
+        /// New 1-based position (None = no longer queued)
+        position: Option<usize>,
+        timestamp: DateTime<Utc>,
+    },
+
     /// User joined the drive
+    ///
+    /// Also re-sent to announce presence. The version and capabilities are
+    /// omitted when empty so the event serializes as it did before.
     UserJoined {
         user: NodeId,
         timestamp: DateTime<Utc>,
+        /// Sender's protocol version (0 if the sender predates versioning)
+        #[serde(default, skip_serializing_if = "is_zero")]
+        protocol_version: u32,
+        /// Capability names the sender supports (see `Capability`)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        capabilities: Vec<String>,
+    },
+
+    /// A user accepted an invite issued for this drive
+    ///
+    /// Sent by the acceptor so the issuer can track invite usage. Only the
+    /// issuer knows the token ID, so it doubles as proof of possession.
+    InviteAccepted {
+        token_id: String,
+        user: NodeId,
+        timestamp: DateTime<Utc>,
     },
 
     /// User left the drive
@@ -85,6 +262,141 @@ pub enum DriveEvent {
         path: PathBuf,
         hash: String,
     },
+
+    /// Progress of merging another drive into this one
+    MergeProgress {
+        /// Drive being merged in (hex)
+        source_drive: String,
+        files_processed: usize,
+        total_files: usize,
+    },
+
+    /// Progress of re-importing a drive's local files into the blob store
+    ReimportProgress {
+        files_processed: usize,
+        total_files: usize,
+        /// Files imported so far (the rest were already stored)
+        imported: usize,
+    },
+
+    /// A joined drive has no responding peers yet and sync will be retried
+    ///
+    /// Local only; never broadcast over gossip.
+    WaitingForPeers {
+        /// Reconnect attempts made so far
+        attempt: u32,
+        /// Seconds until the next attempt
+        next_retry_secs: u64,
+    },
+
+    /// A file conflict was resolved on this device
+    ///
+    /// Local only; never broadcast over gossip.
+    ConflictResolved {
+        path: PathBuf,
+        strategy: ResolutionStrategy,
+        resolved_by: NodeId,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A file conflict was resolved by the drive's conflict policy
+    ///
+    /// Local only; never broadcast over gossip.
+    ConflictAutoResolved {
+        path: PathBuf,
+        policy: ConflictPolicy,
+        strategy: ResolutionStrategy,
+        /// Hash of the version that was kept
+        kept_hash: String,
+        /// Node that wrote the kept version
+        kept_by: NodeId,
+        /// Hash of the version that was given up
+        discarded_hash: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// Messages from a peer were dropped because its protocol version is
+    /// incompatible with ours
+    ///
+    /// Local only; never broadcast over gossip. Sent once per peer and
+    /// version while subscribed to the drive.
+    IncompatiblePeer {
+        peer: NodeId,
+        protocol_version: u32,
+        /// Why the version can't be used, for display
+        reason: String,
+    },
+
+    /// The drive's file watcher hit an error and may have missed changes
+    ///
+    /// Local only; never broadcast over gossip.
+    WatcherError {
+        message: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// The drive's file watcher started or stopped batching under high churn
+    ///
+    /// Local only; never broadcast over gossip.
+    WatcherChurn {
+        /// True while changes are batched
+        active: bool,
+        /// Raw filesystem events per second when the state changed
+        events_per_sec: u32,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A peer added a file that wasn't downloaded because the drive's
+    /// `NewFilePolicy` is `Prompt`
+    ///
+    /// Local only; never broadcast over gossip.
+    NewFileAvailable {
+        path: PathBuf,
+        /// BLAKE3 hash of file content (hex string)
+        hash: String,
+        size: u64,
+        added_by: NodeId,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// A peer downloaded and wrote a latency probe marker (see `network::latency`)
+    SyncProbeAck {
+        /// File stem of the probe marker
+        probe_id: String,
+        /// Peer that wrote the marker
+        node: NodeId,
+        /// When the marker was written, by the peer's clock
+        timestamp: DateTime<Utc>,
+    },
+
+    /// Asks a member to prove it holds the drive key (see `network::key_proof`)
+    KeyChallenge {
+        challenger: NodeId,
+        /// Member that should answer
+        target: NodeId,
+        /// Random hex nonce the proof must cover
+        nonce: String,
+        timestamp: DateTime<Utc>,
+    },
+
+    /// Answer to a `KeyChallenge`
+    KeyProof {
+        node: NodeId,
+        challenger: NodeId,
+        nonce: String,
+        /// MAC keyed from the drive key; None if the member has no key
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        proof: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
 }
 
 impl DriveEvent {
@@ -93,14 +405,77 @@ impl DriveEvent {
         match self {
             DriveEvent::FileChanged { .. } => "FileChanged",
             DriveEvent::FileDeleted { .. } => "FileDeleted",
+            DriveEvent::FilesChangedSummary { .. } => "FilesChangedSummary",
+            DriveEvent::FileRead { .. } => "FileRead",
             DriveEvent::FileEditStarted { .. } => "FileEditStarted",
             DriveEvent::FileEditEnded { .. } => "FileEditEnded",
             DriveEvent::FileLockAcquired { .. } => "FileLockAcquired",
             DriveEvent::FileLockReleased { .. } => "FileLockReleased",
+            DriveEvent::LockGranted { .. } => "LockGranted",
+            DriveEvent::LockQueueChanged { .. } => "LockQueueChanged",
             DriveEvent::UserJoined { .. } => "UserJoined",
+            DriveEvent::InviteAccepted { .. } => "InviteAccepted",
             DriveEvent::UserLeft { .. } => "UserLeft",
             DriveEvent::SyncProgress { .. } => "SyncProgress",
             DriveEvent::SyncComplete { .. } => "SyncComplete",
+            DriveEvent::MergeProgress { .. } => "MergeProgress",
+            DriveEvent::ReimportProgress { .. } => "ReimportProgress",
+            DriveEvent::WaitingForPeers { .. } => "WaitingForPeers",
+            DriveEvent::ConflictResolved { .. } => "ConflictResolved",
+            DriveEvent::ConflictAutoResolved { .. } => "ConflictAutoResolved",
+            DriveEvent::IncompatiblePeer { .. } => "IncompatiblePeer",
+            DriveEvent::WatcherError { .. } => "WatcherError",
+            DriveEvent::WatcherChurn { .. } => "WatcherChurn",
+            DriveEvent::NewFileAvailable { .. } => "NewFileAvailable",
+            DriveEvent::SyncProbeAck { .. } => "SyncProbeAck",
+            DriveEvent::KeyChallenge { .. } => "KeyChallenge",
+            DriveEvent::KeyProof { .. } => "KeyProof",
+        }
+    }
+
+    /// How to send this event when its channel is backed up
+    ///
+    /// Progress updates drop the oldest; file changes are coalesced per path
+    /// when a coalescer is given; everything else waits briefly for room.
+    pub fn backpressure_strategy<'a, T>(
+        &self,
+        coalescer: Option<&'a Coalescer<T>>,
+    ) -> BackpressureStrategy<'a, T> {
+        match (self, coalescer) {
+            (
+                DriveEvent::SyncProgress { .. }
+                | DriveEvent::MergeProgress { .. }
+                | DriveEvent::ReimportProgress { .. }
+                | DriveEvent::WaitingForPeers { .. },
+                _,
+            ) => BackpressureStrategy::DropOldest,
+            (DriveEvent::FileChanged { .. } | DriveEvent::FileDeleted { .. }, Some(coalescer)) => {
+                BackpressureStrategy::Coalesce(coalescer)
+            }
+            _ => BackpressureStrategy::BlockWithTimeout(IMPORTANT_EVENT_TIMEOUT),
+        }
+    }
+
+    /// Path this event is about, for coalescing per-file changes
+    pub fn file_path(&self) -> Option<&Path> {
+        match self {
+            DriveEvent::FileChanged { path, .. } | DriveEvent::FileDeleted { path, .. } => {
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    /// Node the event says it comes from, which must be the node that signed it
+    pub fn claimed_sender(&self) -> Option<&NodeId> {
+        match self {
+            DriveEvent::FileRead { reader, .. } => Some(reader),
+            DriveEvent::FilesChangedSummary { changed_by, .. } => Some(changed_by),
+            DriveEvent::SyncProbeAck { node, .. } => Some(node),
+            DriveEvent::KeyChallenge { challenger, .. } => Some(challenger),
+            DriveEvent::KeyProof { node, .. } => Some(node),
+            DriveEvent::UserLeft { user, .. } => Some(user),
+            _ => None,
         }
     }
 
@@ -109,15 +484,59 @@ impl DriveEvent {
         match self {
             DriveEvent::FileChanged { timestamp, .. } => Some(*timestamp),
             DriveEvent::FileDeleted { timestamp, .. } => Some(*timestamp),
+            DriveEvent::FilesChangedSummary { timestamp, .. } => Some(*timestamp),
+            DriveEvent::FileRead { timestamp, .. } => Some(*timestamp),
             DriveEvent::FileLockAcquired { timestamp, .. } => Some(*timestamp),
             DriveEvent::FileLockReleased { timestamp, .. } => Some(*timestamp),
+            DriveEvent::LockGranted { timestamp, .. } => Some(*timestamp),
+            DriveEvent::LockQueueChanged { timestamp, .. } => Some(*timestamp),
             DriveEvent::UserJoined { timestamp, .. } => Some(*timestamp),
+            DriveEvent::InviteAccepted { timestamp, .. } => Some(*timestamp),
             DriveEvent::UserLeft { timestamp, .. } => Some(*timestamp),
+            DriveEvent::ConflictResolved { timestamp, .. } => Some(*timestamp),
+            DriveEvent::ConflictAutoResolved { timestamp, .. } => Some(*timestamp),
+            DriveEvent::WatcherError { timestamp, .. } => Some(*timestamp),
+            DriveEvent::WatcherChurn { timestamp, .. } => Some(*timestamp),
+            DriveEvent::NewFileAvailable { timestamp, .. } => Some(*timestamp),
+            DriveEvent::SyncProbeAck { timestamp, .. } => Some(*timestamp),
+            DriveEvent::KeyChallenge { timestamp, .. } => Some(*timestamp),
+            DriveEvent::KeyProof { timestamp, .. } => Some(*timestamp),
             _ => None,
         }
     }
 }
 
+/// Every value `DriveEvent::event_type` can return
+pub const EVENT_TYPES: &[&str] = &[
+    "FileChanged",
+    "FileDeleted",
+    "FilesChangedSummary",
+    "FileRead",
+    "FileEditStarted",
+    "FileEditEnded",
+    "FileLockAcquired",
+    "FileLockReleased",
+    "LockGranted",
+    "LockQueueChanged",
+    "UserJoined",
+    "InviteAccepted",
+    "UserLeft",
+    "SyncProgress",
+    "SyncComplete",
+    "MergeProgress",
+    "ReimportProgress",
+    "WaitingForPeers",
+    "ConflictResolved",
+    "ConflictAutoResolved",
+    "IncompatiblePeer",
+    "WatcherError",
+    "WatcherChurn",
+    "NewFileAvailable",
+    "SyncProbeAck",
+    "KeyChallenge",
+    "KeyProof",
+];
+
 /// DTO for sending drive events to frontend via Tauri emit
 #[derive(Clone, Debug, Serialize)]
 pub struct DriveEventDto {
@@ -160,8 +579,20 @@ pub struct SignedGossipMessage {
     pub sender: NodeId,
     /// Unix timestamp (milliseconds) when message was created
     pub timestamp_ms: i64,
-    /// Ed25519 signature over (event || sender || timestamp_ms)
+    /// Ed25519 signature over (event || sender || timestamp_ms), followed
+    /// by the protocol version from version 2 on
     pub signature: Vec<u8>,
+    /// Sender's protocol version (0 if the sender predates versioning)
+    #[serde(default)]
+    pub protocol_version: u32,
+}
+
+/// The envelope fields needed to diagnose a message that didn't parse
+#[derive(Deserialize)]
+struct EnvelopeHeader {
+    sender: NodeId,
+    #[serde(default)]
+    protocol_version: u32,
 }
 
 impl SignedGossipMessage {
@@ -171,7 +602,8 @@ impl SignedGossipMessage {
         let timestamp_ms = Utc::now().timestamp_millis();
         
         // Create the message to sign: serialized event + sender bytes + timestamp
-        let message_bytes = Self::create_signing_payload(&event, &sender, timestamp_ms);
+        let message_bytes =
+            Self::create_signing_payload(&event, &sender, timestamp_ms, PROTOCOL_VERSION);
         let signature = identity.sign(&message_bytes);
         
         Self {
@@ -179,13 +611,19 @@ impl SignedGossipMessage {
             sender,
             timestamp_ms,
             signature: signature.to_bytes().to_vec(),
+            protocol_version: PROTOCOL_VERSION,
         }
     }
     
     /// Verify the signature of this message
     pub fn verify(&self) -> Result<(), GossipAuthError> {
         // Reconstruct the signed payload
-        let message_bytes = Self::create_signing_payload(&self.event, &self.sender, self.timestamp_ms);
+        let message_bytes = Self::create_signing_payload(
+            &self.event,
+            &self.sender,
+            self.timestamp_ms,
+            self.protocol_version,
+        );
         
         // Parse the signature
         let signature_bytes: [u8; 64] = self.signature
@@ -213,13 +651,50 @@ impl SignedGossipMessage {
         now_ms - self.timestamp_ms > max_age_ms
     }
     
+    /// Deterministic ID of the logical change this message carries
+    ///
+    /// Hashes the sender and the event. Events with their own timestamp get
+    /// the same ID however often they are re-signed and re-sent; for events
+    /// without one the envelope timestamp is included, so repeating the same
+    /// action (e.g. starting to edit a file again) is not mistaken for a
+    /// duplicate.
+    pub fn event_id(&self) -> [u8; 32] {
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.sender.as_bytes());
+        hasher.update(&serde_json::to_vec(&self.event).unwrap_or_default());
+        if self.event.timestamp().is_none() {
+            hasher.update(&self.timestamp_ms.to_le_bytes());
+        }
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Read the sender and protocol version of a message that didn't parse
+    ///
+    /// The sender is unverified, since the signature can't be checked
+    /// without the event.
+    pub fn peek_header(bytes: &[u8]) -> Option<(NodeId, u32)> {
+        let header: EnvelopeHeader = serde_json::from_slice(bytes).ok()?;
+        Some((header.sender, header.protocol_version))
+    }
+
     /// Create the payload that is signed
-    fn create_signing_payload(event: &DriveEvent, sender: &NodeId, timestamp_ms: i64) -> Vec<u8> {
+    ///
+    /// Version 1 envelopes sign the same payload as unversioned ones, so
+    /// peers from before versioning can still verify them.
+    fn create_signing_payload(
+        event: &DriveEvent,
+        sender: &NodeId,
+        timestamp_ms: i64,
+        protocol_version: u32,
+    ) -> Vec<u8> {
         let event_json = serde_json::to_vec(event).unwrap_or_default();
-        let mut payload = Vec::with_capacity(event_json.len() + 32 + 8);
+        let mut payload = Vec::with_capacity(event_json.len() + 32 + 8 + 4);
         payload.extend_from_slice(&event_json);
         payload.extend_from_slice(sender.as_bytes());
         payload.extend_from_slice(&timestamp_ms.to_le_bytes());
+        if protocol_version >= 2 {
+            payload.extend_from_slice(&protocol_version.to_le_bytes());
+        }
         payload
     }
 }
@@ -271,6 +746,8 @@ mod tests {
             size: 1024,
             modified_by: node_id,
             timestamp: Utc::now(),
+            created: false,
+            content_type: None,
         };
 
         let json = serde_json::to_string(&event).unwrap();
@@ -279,6 +756,68 @@ mod tests {
         assert_eq!(event.event_type(), parsed.event_type());
     }
 
+    #[test]
+    fn test_file_created_flag_roundtrip() {
+        let identity = Identity::generate();
+        let event = |created| DriveEvent::FileChanged {
+            path: PathBuf::from("report.md"),
+            hash: "abc123".to_string(),
+            size: 10,
+            modified_by: identity.node_id(),
+            timestamp: Utc::now(),
+            created,
+            content_type: None,
+        };
+
+        // Edits serialize exactly as before the flag existed
+        let edit = serde_json::to_value(event(false)).unwrap();
+        assert!(edit["FileChanged"].get("created").is_none());
+
+        // Creations survive signing, transport, and verification
+        let signed = SignedGossipMessage::new(event(true), &identity);
+        let bytes = serde_json::to_vec(&signed).unwrap();
+        let received: SignedGossipMessage = serde_json::from_slice(&bytes).unwrap();
+        assert!(received.verify().is_ok());
+        assert!(matches!(
+            received.event,
+            DriveEvent::FileChanged { created: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_event_id_identifies_logical_change() {
+        let identity = Identity::generate();
+        let changed = DriveEvent::FileChanged {
+            path: PathBuf::from("a.txt"),
+            hash: "abc123".to_string(),
+            size: 10,
+            modified_by: identity.node_id(),
+            timestamp: Utc::now(),
+            created: false,
+            content_type: None,
+        };
+
+        // Re-signing the same change keeps its ID
+        let first = SignedGossipMessage::new(changed.clone(), &identity);
+        let mut resent = SignedGossipMessage::new(changed.clone(), &identity);
+        resent.timestamp_ms += 1000;
+        assert_eq!(first.event_id(), resent.event_id());
+
+        // ...but the same change from another sender is distinct
+        let other = SignedGossipMessage::new(changed, &Identity::generate());
+        assert_ne!(first.event_id(), other.event_id());
+
+        // Untimestamped events are told apart by the envelope time
+        let editing = DriveEvent::FileEditStarted {
+            path: PathBuf::from("a.txt"),
+            editor: identity.node_id(),
+        };
+        let once = SignedGossipMessage::new(editing.clone(), &identity);
+        let mut again = SignedGossipMessage::new(editing, &identity);
+        again.timestamp_ms = once.timestamp_ms + 1;
+        assert_ne!(once.event_id(), again.event_id());
+    }
+
     #[test]
     fn test_event_dto_creation() {
         let identity = Identity::generate();
@@ -287,6 +826,8 @@ mod tests {
         let event = DriveEvent::UserJoined {
             user: node_id,
             timestamp: Utc::now(),
+            protocol_version: 0,
+            capabilities: Vec::new(),
         };
 
         let dto = DriveEventDto::from_event("drive123", &event);
@@ -294,4 +835,86 @@ mod tests {
         assert_eq!(dto.drive_id, "drive123");
         assert_eq!(dto.event_type, "UserJoined");
     }
+
+    #[test]
+    fn test_user_left_claims_its_user() {
+        let identity = Identity::generate();
+        let event = DriveEvent::UserLeft {
+            user: identity.node_id(),
+            timestamp: Utc::now(),
+        };
+        assert_eq!(event.claimed_sender(), Some(&identity.node_id()));
+    }
+
+    #[test]
+    fn test_envelope_protocol_version() {
+        let identity = Identity::generate();
+        let event = DriveEvent::UserLeft {
+            user: identity.node_id(),
+            timestamp: Utc::now(),
+        };
+
+        // Envelopes from before versioning still verify
+        let signed = SignedGossipMessage::new(event, &identity);
+        let mut legacy = serde_json::to_value(&signed).unwrap();
+        legacy.as_object_mut().unwrap().remove("protocol_version");
+        let received: SignedGossipMessage = serde_json::from_value(legacy).unwrap();
+        assert_eq!(received.protocol_version, 0);
+        assert!(received.verify().is_ok());
+        assert!(protocol_incompatibility(received.protocol_version, true).is_none());
+
+        // A newer peer's unknown event still yields who sent it and why it failed
+        let mut newer = serde_json::to_value(&signed).unwrap();
+        newer["event"] = serde_json::json!({ "Teleported": {} });
+        newer["protocol_version"] = serde_json::json!(PROTOCOL_VERSION + 1);
+        let bytes = serde_json::to_vec(&newer).unwrap();
+        assert!(serde_json::from_slice::<SignedGossipMessage>(&bytes).is_err());
+        let (sender, version) = SignedGossipMessage::peek_header(&bytes).unwrap();
+        assert_eq!(sender, identity.node_id());
+        assert!(protocol_incompatibility(version, false).is_some());
+        assert!(protocol_incompatibility(version, true).is_none());
+        assert!(protocol_incompatibility(PROTOCOL_VERSION, false).is_none());
+    }
+
+    #[test]
+    fn test_presence_capabilities_forward_compatible() {
+        let identity = Identity::generate();
+        let event = DriveEvent::UserJoined {
+            user: identity.node_id(),
+            timestamp: Utc::now(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: vec!["encryption".to_string(), "teleport".to_string()],
+        };
+
+        // Unknown capability names survive the round trip, so the signature holds
+        let signed = SignedGossipMessage::new(event, &identity);
+        let bytes = serde_json::to_vec(&signed).unwrap();
+        let received: SignedGossipMessage = serde_json::from_slice(&bytes).unwrap();
+        assert!(received.verify().is_ok());
+
+        let DriveEvent::UserJoined {
+            protocol_version,
+            capabilities,
+            ..
+        } = received.event
+        else {
+            panic!("expected UserJoined");
+        };
+        let peer = PeerCapabilities::from_advertisement(protocol_version, &capabilities);
+        assert!(peer.compatible);
+        assert!(peer.supports(Capability::Encryption));
+        assert!(!peer.supports(Capability::ChunkedTransfer));
+        assert_eq!(peer.capabilities.len(), 1);
+
+        // Announcements from before versioning still parse
+        let legacy = serde_json::json!({
+            "UserJoined": { "user": identity.node_id(), "timestamp": Utc::now() }
+        });
+        let event: DriveEvent = serde_json::from_value(legacy).unwrap();
+        assert!(matches!(
+            event,
+            DriveEvent::UserJoined { protocol_version: 0, ref capabilities, .. }
+                if capabilities.is_empty()
+        ));
+    }
 }
@@ -1,5 +1,7 @@
+use crate::core::conflict::{is_text_path, ConflictSensitivity, MAX_NORMALIZED_SIZE};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::ops::ControlFlow;
 use std::path::PathBuf;
 use walkdir::WalkDir;
 
@@ -33,6 +35,12 @@ pub struct FileEntryDto {
     /// BLAKE3 content hash for file transfer (None for directories)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content_hash: Option<String>,
+    /// Why sync is skipping this file, if it was denied access to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_error: Option<String>,
+    /// Detected content type from the synced metadata, for picking icons
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
 }
 
 fn default_is_local() -> bool {
@@ -57,8 +65,16 @@ impl FileEntryDto {
             modified_at,
             is_local: false,
             content_hash,
+            permission_error: None,
+            content_type: None,
         }
     }
+
+    /// Attach the content type recorded in metadata
+    pub fn with_content_type(mut self, content_type: Option<String>) -> Self {
+        self.content_type = content_type;
+        self
+    }
 }
 
 impl From<&FileEntry> for FileEntryDto {
@@ -71,16 +87,29 @@ impl From<&FileEntry> for FileEntryDto {
             modified_at: entry.modified_at.to_rfc3339(),
             is_local: true, // Local files are always available
             content_hash: None, // Hash computed separately if needed
+            permission_error: None,
+            content_type: None,
         }
     }
 }
 
 /// Index a directory recursively and return all file entries
 pub fn index_directory(root: &std::path::Path) -> anyhow::Result<Vec<FileEntry>> {
+    index_directory_to_depth(root, usize::MAX)
+}
+
+/// Index a directory recursively, descending at most `max_depth` levels
+///
+/// Entries directly inside `root` are at depth 1.
+pub fn index_directory_to_depth(
+    root: &std::path::Path,
+    max_depth: usize,
+) -> anyhow::Result<Vec<FileEntry>> {
     let mut entries = Vec::new();
 
     for entry in WalkDir::new(root)
         .min_depth(1) // Skip the root directory itself
+        .max_depth(max_depth)
         .into_iter()
         .filter_entry(|e| {
             // Skip hidden files and common ignored directories
@@ -188,3 +217,413 @@ pub fn list_directory(root: &std::path::Path, subpath: &str) -> anyhow::Result<V
 
     Ok(entries)
 }
+
+/// A file present in both drives with different content
+#[derive(Clone, Debug)]
+pub struct MergeCollision {
+    /// Relative path that collided
+    pub path: PathBuf,
+    /// Relative path the source copy was written to
+    pub copy_path: PathBuf,
+    pub target_hash: String,
+    pub target_size: u64,
+    pub target_modified_at: DateTime<Utc>,
+    pub source_hash: String,
+    pub source_size: u64,
+    pub source_modified_at: DateTime<Utc>,
+}
+
+/// Result of merging one directory tree into another
+#[derive(Clone, Debug, Default)]
+pub struct MergeOutcome {
+    /// Relative paths copied into the target unchanged
+    pub copied: Vec<PathBuf>,
+    /// Files that already existed in the target with identical content, or
+    /// equivalent content under the merge's conflict sensitivity
+    pub identical: usize,
+    pub collisions: Vec<MergeCollision>,
+    /// Files that could not be copied, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+    /// True if `on_progress` stopped the merge before every file was seen
+    pub cancelled: bool,
+}
+
+/// Copy every file under `source_root` into `target_root`
+///
+/// Existing target files are never overwritten: when content differs, the
+/// source file is written next to it as `name (tag).ext` and reported as a
+/// collision, unless `sensitivity` treats the two versions as equivalent.
+/// `on_progress` is called with (files processed, total files) after each
+/// file; returning `Break` stops before the next file, leaving every file
+/// already handled fully written.
+pub fn merge_directory(
+    source_root: &std::path::Path,
+    target_root: &std::path::Path,
+    collision_tag: &str,
+    sensitivity: ConflictSensitivity,
+    mut on_progress: impl FnMut(usize, usize) -> ControlFlow<()>,
+) -> anyhow::Result<MergeOutcome> {
+    let files: Vec<FileEntry> = index_directory(source_root)?
+        .into_iter()
+        .filter(|e| !e.is_dir)
+        .collect();
+    let total = files.len();
+    let mut outcome = MergeOutcome::default();
+
+    for (i, entry) in files.into_iter().enumerate() {
+        let source = source_root.join(&entry.path);
+        let target = target_root.join(&entry.path);
+
+        let result = if !target.exists() {
+            copy_into(&source, &target).map(|_| outcome.copied.push(entry.path.clone()))
+        } else {
+            match (hash_file(&source), hash_file(&target)) {
+                (Ok(source_hash), Ok(target_hash))
+                    if source_hash == target_hash
+                        || equivalent_files(sensitivity, &entry.path, &source, &target) =>
+                {
+                    outcome.identical += 1;
+                    Ok(())
+                }
+                (Ok(source_hash), Ok(target_hash)) => {
+                    let copy_path = collision_path(target_root, &entry.path, collision_tag);
+                    std::fs::metadata(&target)
+                        .and_then(|meta| {
+                            copy_into(&source, &target_root.join(&copy_path)).map(|_| meta)
+                        })
+                        .map(|target_meta| {
+                            outcome.collisions.push(MergeCollision {
+                                path: entry.path.clone(),
+                                copy_path,
+                                target_hash,
+                                target_size: target_meta.len(),
+                                target_modified_at: target_meta
+                                    .modified()
+                                    .map(DateTime::<Utc>::from)
+                                    .unwrap_or_else(|_| Utc::now()),
+                                source_hash,
+                                source_size: entry.size,
+                                source_modified_at: entry.modified_at,
+                            })
+                        })
+                }
+                (Err(e), _) | (_, Err(e)) => Err(e),
+            }
+        };
+
+        if let Err(e) = result {
+            outcome.failed.push((entry.path, e.to_string()));
+        }
+        if on_progress(i + 1, total).is_break() && i + 1 < total {
+            outcome.cancelled = true;
+            break;
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// An entry whose resolved location is outside the drive root
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathEscape {
+    /// Drive-relative path of the entry
+    pub path: PathBuf,
+    /// Where the entry resolves to (the raw link target if it is dangling)
+    pub target: PathBuf,
+    /// True if the link target does not exist
+    pub dangling: bool,
+}
+
+/// Find entries under `root` that resolve outside it
+///
+/// Symlinks are not followed while walking; each one is resolved on its
+/// own, and dangling links are checked lexically so a link that would
+/// escape once its target appears is still reported. Hidden and ignored
+/// entries are included. Returns the escapes and the number of entries
+/// scanned.
+pub fn find_path_escapes(root: &std::path::Path) -> anyhow::Result<(Vec<PathEscape>, usize)> {
+    let canonical_root = root.canonicalize()?;
+    let mut escapes = Vec::new();
+    let mut scanned = 0;
+
+    for entry in WalkDir::new(root).min_depth(1).follow_links(false) {
+        let entry = match entry {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        scanned += 1;
+        if !entry.path_is_symlink() {
+            continue;
+        }
+
+        let relative = match entry.path().strip_prefix(root) {
+            Ok(p) => p.to_path_buf(),
+            Err(_) => continue,
+        };
+
+        match entry.path().canonicalize() {
+            Ok(resolved) => {
+                if !resolved.starts_with(&canonical_root) {
+                    escapes.push(PathEscape {
+                        path: relative,
+                        target: resolved,
+                        dangling: false,
+                    });
+                }
+            }
+            Err(_) => {
+                let target = std::fs::read_link(entry.path())?;
+                let parent = entry
+                    .path()
+                    .parent()
+                    .and_then(|p| p.canonicalize().ok())
+                    .unwrap_or_else(|| canonical_root.clone());
+                if !normalize_lexically(&parent.join(&target)).starts_with(&canonical_root) {
+                    escapes.push(PathEscape {
+                        path: relative,
+                        target,
+                        dangling: true,
+                    });
+                }
+            }
+        }
+    }
+
+    escapes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok((escapes, scanned))
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+fn normalize_lexically(path: &std::path::Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+/// Drive-relative path as a string key with forward slashes
+pub fn path_key(path: &std::path::Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// BLAKE3 hash of a file's content (hex string)
+pub fn hash_file(path: &std::path::Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Whether two differing files count as the same content under `sensitivity`
+///
+/// Only small text files are read; anything else keeps its hash comparison.
+fn equivalent_files(
+    sensitivity: ConflictSensitivity,
+    relative: &std::path::Path,
+    a: &std::path::Path,
+    b: &std::path::Path,
+) -> bool {
+    if sensitivity == ConflictSensitivity::Exact || !is_text_path(relative) {
+        return false;
+    }
+    let small = |path: &std::path::Path| {
+        std::fs::metadata(path).is_ok_and(|meta| meta.len() <= MAX_NORMALIZED_SIZE)
+    };
+    if !small(a) || !small(b) {
+        return false;
+    }
+    match (std::fs::read(a), std::fs::read(b)) {
+        (Ok(a_content), Ok(b_content)) => sensitivity.equivalent(relative, &a_content, &b_content),
+        _ => false,
+    }
+}
+
+fn copy_into(source: &std::path::Path, target: &std::path::Path) -> std::io::Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(source, target).map(|_| ())
+}
+
+/// Pick an unused `name (tag).ext` path next to `relative`
+fn collision_path(root: &std::path::Path, relative: &std::path::Path, tag: &str) -> PathBuf {
+    let stem = relative
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = relative
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut n = 1;
+    loop {
+        let name = if n == 1 {
+            format!("{} ({}){}", stem, tag, ext)
+        } else {
+            format!("{} ({} {}){}", stem, tag, n, ext)
+        };
+        let candidate = relative.with_file_name(name);
+        if !root.join(&candidate).exists() {
+            return candidate;
+        }
+        n += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_index_directory_to_depth() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        std::fs::write(dir.path().join("top.txt"), b"1").unwrap();
+        std::fs::write(dir.path().join("a/mid.txt"), b"2").unwrap();
+        std::fs::write(dir.path().join("a/b/deep.txt"), b"3").unwrap();
+
+        let mut shallow: Vec<String> = index_directory_to_depth(dir.path(), 2)
+            .unwrap()
+            .iter()
+            .map(|e| path_key(&e.path))
+            .collect();
+        shallow.sort();
+        assert_eq!(shallow, vec!["a", "a/b", "a/mid.txt", "top.txt"]);
+        assert_eq!(index_directory(dir.path()).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn test_merge_directory_keeps_both_on_collision() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+
+        std::fs::create_dir_all(source.path().join("docs")).unwrap();
+        std::fs::write(source.path().join("docs/new.txt"), b"new").unwrap();
+        std::fs::write(source.path().join("same.txt"), b"same").unwrap();
+        std::fs::write(source.path().join("clash.txt"), b"source").unwrap();
+        std::fs::write(target.path().join("same.txt"), b"same").unwrap();
+        std::fs::write(target.path().join("clash.txt"), b"target").unwrap();
+
+        let mut progress = Vec::new();
+        let outcome = merge_directory(
+            source.path(),
+            target.path(),
+            "from Old",
+            ConflictSensitivity::Exact,
+            |done, total| {
+                progress.push((done, total));
+                ControlFlow::Continue(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(outcome.copied, vec![PathBuf::from("docs/new.txt")]);
+        assert_eq!(outcome.identical, 1);
+        assert_eq!(outcome.collisions.len(), 1);
+        assert!(outcome.failed.is_empty());
+        assert_eq!(progress.last(), Some(&(3, 3)));
+
+        // Target content is untouched; source copy sits beside it
+        let collision = &outcome.collisions[0];
+        assert_eq!(collision.copy_path, PathBuf::from("clash (from Old).txt"));
+        assert_eq!(std::fs::read(target.path().join("clash.txt")).unwrap(), b"target");
+        assert_eq!(
+            std::fs::read(target.path().join(&collision.copy_path)).unwrap(),
+            b"source"
+        );
+        assert_eq!(std::fs::read(target.path().join("docs/new.txt")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn test_merge_directory_stops_when_cancelled() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        for name in ["a.txt", "b.txt", "c.txt"] {
+            std::fs::write(source.path().join(name), name).unwrap();
+        }
+
+        let outcome = merge_directory(
+            source.path(),
+            target.path(),
+            "from Old",
+            ConflictSensitivity::Exact,
+            |done, _| {
+                if done == 1 {
+                    ControlFlow::Break(())
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        )
+        .unwrap();
+
+        assert!(outcome.cancelled);
+        assert_eq!(outcome.copied.len(), 1);
+        assert_eq!(std::fs::read_dir(target.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_merge_directory_ignores_whitespace_in_text() {
+        let source = tempdir().unwrap();
+        let target = tempdir().unwrap();
+        std::fs::write(source.path().join("notes.md"), b"one\r\ntwo\r\n").unwrap();
+        std::fs::write(target.path().join("notes.md"), b"one\ntwo").unwrap();
+        std::fs::write(source.path().join("data.bin"), b"one\r\n").unwrap();
+        std::fs::write(target.path().join("data.bin"), b"one\n").unwrap();
+
+        let outcome = merge_directory(
+            source.path(),
+            target.path(),
+            "from Old",
+            ConflictSensitivity::IgnoreWhitespace,
+            |_, _| ControlFlow::Continue(()),
+        )
+        .unwrap();
+
+        assert_eq!(outcome.identical, 1);
+        assert_eq!(outcome.collisions.len(), 1);
+        assert_eq!(outcome.collisions[0].path, PathBuf::from("data.bin"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_path_escapes() {
+        use std::os::unix::fs::symlink;
+
+        let outside = tempdir().unwrap();
+        let drive = tempdir().unwrap();
+        std::fs::create_dir_all(drive.path().join("docs")).unwrap();
+        std::fs::write(drive.path().join("docs/a.txt"), b"a").unwrap();
+        std::fs::write(outside.path().join("secret.txt"), b"s").unwrap();
+
+        // Links that stay inside the drive are fine, even dangling ones
+        symlink("a.txt", drive.path().join("docs/inside")).unwrap();
+        symlink("missing.txt", drive.path().join("docs/dangling_inside")).unwrap();
+        // Links that leave it are reported
+        symlink(outside.path(), drive.path().join("out_dir")).unwrap();
+        symlink("../../nowhere", drive.path().join("docs/.hidden_escape")).unwrap();
+
+        let (escapes, scanned) = find_path_escapes(drive.path()).unwrap();
+        assert_eq!(scanned, 6);
+
+        let paths: Vec<_> = escapes.iter().map(|e| e.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![PathBuf::from("docs/.hidden_escape"), PathBuf::from("out_dir")]
+        );
+        assert!(escapes[0].dangling);
+        assert!(!escapes[1].dangling);
+        assert_eq!(escapes[1].target, outside.path().canonicalize().unwrap());
+    }
+}
@@ -0,0 +1,291 @@
+//! Files sync isn't allowed to access
+//!
+//! On shared machines some files in a drive can't be read or written by
+//! this process. Rather than failing silently (or logging on every pass),
+//! a permission-denied error is recorded against the file: later passes
+//! skip it until its modification time changes, it is flagged in
+//! `list_files` and counted in sync diagnostics, and
+//! `retry_permission_errors` re-attempts it once permissions are fixed.
+//! Records are persisted so they survive a restart.
+
+use crate::core::DriveId;
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// Settings key for the persisted records
+pub const PERMISSION_ERRORS_SETTING: &str = "permission_errors";
+
+/// Oldest records are dropped beyond this many
+const MAX_PERMISSION_ERRORS: usize = 10_000;
+
+/// What sync was doing with the file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FsOperation {
+    /// Reading a local file to hash or upload it
+    Read,
+    /// Writing a downloaded file into the drive
+    Write,
+}
+
+/// A file sync was denied access to
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PermissionError {
+    /// Drive ID (hex)
+    pub drive_id: String,
+    /// Path relative to the drive root, `/`-separated
+    pub path: String,
+    pub operation: FsOperation,
+    /// The OS error
+    pub message: String,
+    pub recorded_at: DateTime<Utc>,
+    /// The file's modification time when the error was recorded
+    pub modified_at: Option<DateTime<Utc>>,
+}
+
+/// Check if an I/O error is the OS refusing access
+pub fn is_permission_denied(error: &std::io::Error) -> bool {
+    error.kind() == ErrorKind::PermissionDenied
+}
+
+/// The permission-denied I/O error in an error's chain, if there is one
+pub fn permission_denied_in(error: &anyhow::Error) -> Option<&std::io::Error> {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .find(|io| is_permission_denied(io))
+}
+
+fn path_key(relative: &Path) -> String {
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+fn modified_time(path: &Path) -> Option<DateTime<Utc>> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .map(DateTime::<Utc>::from)
+}
+
+/// Persistent record of files sync was denied access to
+pub struct PermissionErrors {
+    db: Arc<Database>,
+    /// Keyed by (drive ID hex, path)
+    entries: RwLock<HashMap<(String, String), PermissionError>>,
+}
+
+impl PermissionErrors {
+    /// Load the records saved by a previous run
+    pub fn load(db: Arc<Database>) -> Self {
+        let entries: Vec<PermissionError> = match db.get_setting(PERMISSION_ERRORS_SETTING) {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!("Discarding unreadable permission errors: {}", e);
+                Vec::new()
+            }),
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Failed to load permission errors: {}", e);
+                Vec::new()
+            }
+        };
+        if !entries.is_empty() {
+            tracing::info!("{} file(s) skipped by sync due to permission errors", entries.len());
+        }
+
+        Self {
+            db,
+            entries: RwLock::new(
+                entries
+                    .into_iter()
+                    .map(|e| ((e.drive_id.clone(), e.path.clone()), e))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Record `error` against a file if it is a permission error
+    ///
+    /// `absolute` is used to remember the file's modification time. Returns
+    /// false (recording nothing) for any other kind of error.
+    pub fn record(
+        &self,
+        drive_id: &DriveId,
+        relative: &Path,
+        absolute: &Path,
+        operation: FsOperation,
+        error: &std::io::Error,
+    ) -> bool {
+        if !is_permission_denied(error) {
+            return false;
+        }
+        let Ok(mut entries) = self.entries.write() else {
+            return false;
+        };
+
+        let key = (drive_id.to_hex(), path_key(relative));
+        if !entries.contains_key(&key) {
+            tracing::warn!(
+                drive_id = %drive_id,
+                path = %key.1,
+                ?operation,
+                "Permission denied; skipping file until it changes: {}",
+                error
+            );
+        }
+        entries.insert(
+            key.clone(),
+            PermissionError {
+                drive_id: key.0,
+                path: key.1,
+                operation,
+                message: error.to_string(),
+                recorded_at: Utc::now(),
+                modified_at: modified_time(absolute),
+            },
+        );
+
+        if entries.len() > MAX_PERMISSION_ERRORS {
+            if let Some(oldest) = entries
+                .values()
+                .min_by_key(|e| e.recorded_at)
+                .map(|e| (e.drive_id.clone(), e.path.clone()))
+            {
+                entries.remove(&oldest);
+            }
+        }
+        self.persist(&entries);
+        true
+    }
+
+    /// Whether a pass should leave this file alone
+    ///
+    /// True if access was denied and the file hasn't been modified since.
+    pub fn should_skip(&self, drive_id: &DriveId, relative: &Path, absolute: &Path) -> bool {
+        let Ok(entries) = self.entries.read() else {
+            return false;
+        };
+        match entries.get(&(drive_id.to_hex(), path_key(relative))) {
+            Some(entry) => entry.modified_at == modified_time(absolute),
+            None => false,
+        }
+    }
+
+    /// Forget a file's error, e.g. after it was accessed successfully
+    pub fn clear(&self, drive_id: &DriveId, relative: &Path) {
+        let key = (drive_id.to_hex(), path_key(relative));
+        let present = self
+            .entries
+            .read()
+            .map(|entries| entries.contains_key(&key))
+            .unwrap_or(false);
+        if !present {
+            return;
+        }
+        if let Ok(mut entries) = self.entries.write() {
+            if entries.remove(&key).is_some() {
+                self.persist(&entries);
+            }
+        }
+    }
+
+    /// A file's recorded error, if any
+    pub fn get(&self, drive_id: &DriveId, relative: &Path) -> Option<PermissionError> {
+        self.entries
+            .read()
+            .ok()?
+            .get(&(drive_id.to_hex(), path_key(relative)))
+            .cloned()
+    }
+
+    /// Recorded errors sorted by drive and path, optionally for one drive
+    pub fn list(&self, drive_id: Option<&DriveId>) -> Vec<PermissionError> {
+        let drive_hex = drive_id.map(|id| id.to_hex());
+        let mut list: Vec<PermissionError> = self
+            .entries
+            .read()
+            .map(|entries| {
+                entries
+                    .values()
+                    .filter(|e| match &drive_hex {
+                        Some(hex) => e.drive_id == *hex,
+                        None => true,
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        list.sort_by(|a, b| (&a.drive_id, &a.path).cmp(&(&b.drive_id, &b.path)));
+        list
+    }
+
+    /// Number of files with a recorded error in a drive
+    pub fn count(&self, drive_id: &DriveId) -> usize {
+        let hex = drive_id.to_hex();
+        self.entries
+            .read()
+            .map(|entries| entries.keys().filter(|(drive, _)| *drive == hex).count())
+            .unwrap_or(0)
+    }
+
+    fn persist(&self, entries: &HashMap<(String, String), PermissionError>) {
+        let list: Vec<&PermissionError> = entries.values().collect();
+        let result = serde_json::to_vec(&list)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| self.db.save_setting(PERMISSION_ERRORS_SETTING, &bytes));
+        if let Err(e) = result {
+            tracing::warn!("Failed to persist permission errors: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_permission_denied_in_chain() {
+        let denied = std::io::Error::new(ErrorKind::PermissionDenied, "denied");
+        let error = anyhow::Error::from(denied).context("Failed to move download into place");
+        assert!(permission_denied_in(&error).is_some());
+
+        let missing = anyhow::Error::from(std::io::Error::new(ErrorKind::NotFound, "gone"));
+        assert!(permission_denied_in(&missing).is_none());
+    }
+
+    #[test]
+    fn test_record_skip_and_clear() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(dir.path().join("test.redb")).unwrap());
+        let file = dir.path().join("locked.txt");
+        std::fs::write(&file, b"secret").unwrap();
+
+        let drive = DriveId([3u8; 32]);
+        let relative = Path::new("docs/locked.txt");
+        let errors = PermissionErrors::load(db.clone());
+
+        let other = std::io::Error::new(ErrorKind::NotFound, "gone");
+        assert!(!errors.record(&drive, relative, &file, FsOperation::Read, &other));
+        assert!(!errors.should_skip(&drive, relative, &file));
+
+        let denied = std::io::Error::new(ErrorKind::PermissionDenied, "denied");
+        assert!(errors.record(&drive, relative, &file, FsOperation::Read, &denied));
+        assert!(errors.should_skip(&drive, relative, &file));
+        assert_eq!(errors.count(&drive), 1);
+        assert_eq!(errors.count(&DriveId([4u8; 32])), 0);
+
+        // Records survive a reload
+        let reloaded = PermissionErrors::load(db);
+        assert!(reloaded.should_skip(&drive, relative, &file));
+        assert_eq!(reloaded.list(None)[0].path, "docs/locked.txt");
+
+        reloaded.clear(&drive, relative);
+        assert!(reloaded.get(&drive, relative).is_none());
+        assert!(!reloaded.should_skip(&drive, relative, &file));
+    }
+}
@@ -0,0 +1,323 @@
+//! Finding the files a peer is missing
+//!
+//! To help a lagging peer catch up, we compare the files we hold against
+//! the files it holds. Sending whole file lists doesn't scale, so the
+//! exchange (see `network::summary`) has two rounds:
+//!
+//! 1. The peer sends a [`MetadataSummary`]: a Merkle root over its files
+//!    plus a short digest per bucket, where each path falls into one of
+//!    [`SUMMARY_BUCKETS`] buckets by the hash of its path.
+//! 2. For buckets whose digests differ from ours, the peer sends the
+//!    `(path, hash)` entries in those buckets, split into small messages.
+//!
+//! Files in a differing bucket that the peer lacks or holds different
+//! content for make up the gap. If the second round doesn't complete, the
+//! files in unanswered buckets are reported as unconfirmed.
+
+use crate::network::docs::merkle_root;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Number of buckets paths are spread over
+pub const SUMMARY_BUCKETS: u16 = 64;
+
+/// Hex characters kept of each bucket digest
+const BUCKET_DIGEST_LEN: usize = 16;
+
+/// Hex characters of content hashes sent in summary entries
+const SHORT_HASH_LEN: usize = 16;
+
+/// Bytes of paths and hashes per entries message
+///
+/// Small messages let a peer use the buckets it got in full even if the
+/// rest of the answer doesn't arrive in time.
+pub const MAX_ENTRIES_BYTES: usize = 2048;
+
+/// A peer's summary of the files it holds
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataSummary {
+    /// Hex-encoded Merkle root over all held files
+    pub root: String,
+    pub file_count: usize,
+    /// Truncated digest of each bucket's files, indexed by bucket
+    pub buckets: Vec<String>,
+}
+
+impl MetadataSummary {
+    /// Summarize `(path, content_hash)` pairs of held files
+    pub fn from_files(files: &[(String, String)]) -> Self {
+        let mut buckets: Vec<Vec<(String, String)>> = vec![Vec::new(); SUMMARY_BUCKETS as usize];
+        for (path, hash) in files {
+            buckets[bucket_of(path) as usize].push((path.clone(), hash.clone()));
+        }
+
+        Self {
+            root: hex::encode(merkle_root(files.to_vec())),
+            file_count: files.len(),
+            buckets: buckets
+                .into_iter()
+                .map(|entries| {
+                    let mut digest = hex::encode(merkle_root(entries));
+                    digest.truncate(BUCKET_DIGEST_LEN);
+                    digest
+                })
+                .collect(),
+        }
+    }
+
+    /// Buckets whose digests differ from `other`'s
+    ///
+    /// Every bucket differs if the summaries use different bucket counts.
+    pub fn differing_buckets(&self, other: &MetadataSummary) -> Vec<u16> {
+        if self.root == other.root {
+            return Vec::new();
+        }
+        (0..SUMMARY_BUCKETS)
+            .filter(|&b| self.buckets.get(b as usize) != other.buckets.get(b as usize))
+            .collect()
+    }
+}
+
+/// One held file, as sent in the second round
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SummaryEntry {
+    pub path: String,
+    /// Leading hex characters of the content hash
+    pub hash: String,
+}
+
+/// Why a file is part of a peer's gap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapKind {
+    /// The peer doesn't hold the file
+    Missing,
+    /// The peer holds different content for the file
+    Outdated,
+    /// The file's bucket differs but the peer didn't list its files
+    Unconfirmed,
+}
+
+/// A file we hold that a peer is missing
+#[derive(Clone, Debug, Serialize)]
+pub struct GapFile {
+    pub path: String,
+    /// Our content hash (hex)
+    pub content_hash: String,
+    pub kind: GapKind,
+}
+
+/// Files a peer is missing compared to us
+#[derive(Clone, Debug, Serialize)]
+pub struct PeerGap {
+    pub drive_id: String,
+    pub peer_id: String,
+    /// Merkle root over the files we hold
+    pub local_root: String,
+    /// Merkle root over the files the peer holds
+    pub peer_root: String,
+    /// Sorted by path
+    pub files: Vec<GapFile>,
+    /// False if some files could only be reported as unconfirmed
+    pub complete: bool,
+}
+
+/// Bucket a path falls into
+pub fn bucket_of(path: &str) -> u16 {
+    let digest = blake3::hash(path.as_bytes());
+    let bytes = digest.as_bytes();
+    u16::from_le_bytes([bytes[0], bytes[1]]) % SUMMARY_BUCKETS
+}
+
+/// Files held locally with the content hash their metadata records
+///
+/// A file counts as held if it exists on disk and has synced metadata with
+/// a content hash; directories and files that exist on only one side are
+/// left out.
+pub fn held_files(
+    local_files: &HashSet<String>,
+    metadata: &[(String, Option<String>)],
+) -> Vec<(String, String)> {
+    let mut held: Vec<(String, String)> = metadata
+        .iter()
+        .filter(|(path, _)| local_files.contains(path))
+        .filter_map(|(path, hash)| Some((path.clone(), hash.clone()?)))
+        .collect();
+    held.sort();
+    held
+}
+
+fn short_hash(hash: &str) -> String {
+    hash.chars().take(SHORT_HASH_LEN).collect::<String>().to_ascii_lowercase()
+}
+
+/// Split the entries of `buckets` into messages of at most `MAX_ENTRIES_BYTES`
+///
+/// Each message lists the buckets whose entries it completes, so a
+/// receiver can tell which buckets it has in full even if later messages
+/// are lost.
+pub fn chunk_entries(
+    files: &[(String, String)],
+    buckets: &[u16],
+) -> Vec<(Vec<SummaryEntry>, Vec<u16>)> {
+    let wanted: HashSet<u16> = buckets.iter().copied().collect();
+    let mut by_bucket: HashMap<u16, Vec<SummaryEntry>> = HashMap::new();
+    for (path, hash) in files {
+        let bucket = bucket_of(path);
+        if wanted.contains(&bucket) {
+            by_bucket.entry(bucket).or_default().push(SummaryEntry {
+                path: path.clone(),
+                hash: short_hash(hash),
+            });
+        }
+    }
+
+    let mut ordered: Vec<u16> = wanted.into_iter().collect();
+    ordered.sort();
+
+    let mut chunks = Vec::new();
+    let mut entries = Vec::new();
+    let mut done = Vec::new();
+    let mut bytes = 0;
+    for bucket in ordered {
+        for entry in by_bucket.remove(&bucket).unwrap_or_default() {
+            let size = entry.path.len() + entry.hash.len();
+            if bytes + size > MAX_ENTRIES_BYTES && !entries.is_empty() {
+                chunks.push((std::mem::take(&mut entries), std::mem::take(&mut done)));
+                bytes = 0;
+            }
+            bytes += size;
+            entries.push(entry);
+        }
+        done.push(bucket);
+    }
+    if !entries.is_empty() || !done.is_empty() {
+        chunks.push((entries, done));
+    }
+    chunks
+}
+
+/// Files in `differing` buckets that the peer is missing
+///
+/// `peer_entries` maps the peer's paths to their short hashes for the
+/// buckets in `resolved`; files in unresolved buckets are unconfirmed.
+pub fn compute_gap(
+    ours: &[(String, String)],
+    differing: &[u16],
+    peer_entries: &HashMap<String, String>,
+    resolved: &HashSet<u16>,
+) -> Vec<GapFile> {
+    let differing: HashSet<u16> = differing.iter().copied().collect();
+    let mut gap: Vec<GapFile> = ours
+        .iter()
+        .filter_map(|(path, hash)| {
+            let bucket = bucket_of(path);
+            if !differing.contains(&bucket) {
+                return None;
+            }
+            let kind = if !resolved.contains(&bucket) {
+                GapKind::Unconfirmed
+            } else {
+                match peer_entries.get(path) {
+                    None => GapKind::Missing,
+                    Some(theirs) if *theirs != short_hash(hash) => GapKind::Outdated,
+                    Some(_) => return None,
+                }
+            };
+            Some(GapFile {
+                path: path.clone(),
+                content_hash: hash.clone(),
+                kind,
+            })
+        })
+        .collect();
+    gap.sort_by(|a, b| a.path.cmp(&b.path));
+    gap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(p, h)| (p.to_string(), h.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_held_files() {
+        let local: HashSet<String> = ["a.txt", "b.txt", "local-only.txt"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let metadata = vec![
+            ("a.txt".to_string(), Some("h1".to_string())),
+            ("b.txt".to_string(), None),
+            ("remote-only.txt".to_string(), Some("h3".to_string())),
+        ];
+        assert_eq!(held_files(&local, &metadata), files(&[("a.txt", "h1")]));
+    }
+
+    #[test]
+    fn test_gap_between_summaries() {
+        let ours = files(&[
+            ("a.txt", "aaaa"),
+            ("b.txt", "bbbb"),
+            ("docs/c.txt", "cccc"),
+            ("docs/d.txt", "dddd"),
+        ]);
+        let theirs = files(&[("a.txt", "aaaa"), ("b.txt", "b2b2"), ("docs/d.txt", "dddd")]);
+
+        let local = MetadataSummary::from_files(&ours);
+        let peer = MetadataSummary::from_files(&theirs);
+        assert_eq!(local.buckets.len(), SUMMARY_BUCKETS as usize);
+        assert!(local.differing_buckets(&local.clone()).is_empty());
+
+        let differing = local.differing_buckets(&peer);
+        assert!(differing.contains(&bucket_of("b.txt")));
+        assert!(differing.contains(&bucket_of("docs/c.txt")));
+
+        // Second round: the peer lists its files in the differing buckets
+        let mut peer_entries = HashMap::new();
+        let mut resolved = HashSet::new();
+        for (entries, done) in chunk_entries(&theirs, &differing) {
+            peer_entries.extend(entries.into_iter().map(|e| (e.path, e.hash)));
+            resolved.extend(done);
+        }
+        assert_eq!(resolved.len(), differing.len());
+
+        let gap = compute_gap(&ours, &differing, &peer_entries, &resolved);
+        let found: Vec<(&str, GapKind)> = gap.iter().map(|f| (f.path.as_str(), f.kind)).collect();
+        assert_eq!(
+            found,
+            vec![("b.txt", GapKind::Outdated), ("docs/c.txt", GapKind::Missing)]
+        );
+
+        // Without the second round the differing files are unconfirmed
+        let gap = compute_gap(&ours, &differing, &HashMap::new(), &HashSet::new());
+        assert!(gap.iter().all(|f| f.kind == GapKind::Unconfirmed));
+        assert!(gap.iter().any(|f| f.path == "docs/c.txt"));
+    }
+
+    #[test]
+    fn test_chunk_entries_respects_size() {
+        let many: Vec<(String, String)> = (0..500)
+            .map(|i| (format!("folder/file-{i:04}.txt"), "f".repeat(64)))
+            .collect();
+        let buckets: Vec<u16> = (0..SUMMARY_BUCKETS).collect();
+
+        let chunks = chunk_entries(&many, &buckets);
+        assert!(chunks.len() > 1);
+        for (entries, _) in &chunks {
+            let bytes: usize = entries.iter().map(|e| e.path.len() + e.hash.len()).sum();
+            assert!(bytes <= MAX_ENTRIES_BYTES);
+            assert!(entries.iter().all(|e| e.hash.len() == SHORT_HASH_LEN));
+        }
+        let total: usize = chunks.iter().map(|(entries, _)| entries.len()).sum();
+        let done: usize = chunks.iter().map(|(_, done)| done.len()).sum();
+        assert_eq!(total, 500);
+        assert_eq!(done, SUMMARY_BUCKETS as usize);
+    }
+}
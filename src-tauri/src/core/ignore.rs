@@ -0,0 +1,425 @@
+//! `.gixignore` rules
+//!
+//! A drive can hold `.gixignore` files in gitignore syntax to keep editor
+//! swap files, build output and other local artifacts out of sync. Like
+//! git, a file's patterns are relative to the directory it sits in, files
+//! in deeper directories take precedence over shallower ones, the last
+//! matching pattern wins, and nothing inside an ignored directory can be
+//! re-included.
+
+use crate::core::DriveId;
+use std::collections::HashMap;
+use std::path::{Component, Path};
+use std::sync::{Arc, RwLock};
+
+/// Name of the ignore files read in a drive
+pub const IGNORE_FILE_NAME: &str = ".gixignore";
+
+/// Largest ignore file read; anything past it is skipped
+const MAX_IGNORE_FILE_BYTES: u64 = 256 * 1024;
+
+/// Most ignore files loaded per drive
+const MAX_IGNORE_FILES: usize = 1024;
+
+/// One pattern line
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct IgnorePattern {
+    /// Pattern split on `/`; a `**` segment matches any number of segments
+    segments: Vec<String>,
+    /// `!` pattern: re-includes what an earlier pattern ignored
+    negated: bool,
+    /// Trailing `/`: only matches directories
+    dir_only: bool,
+}
+
+impl IgnorePattern {
+    /// Parse one line, or None for blank lines and comments
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line = trim_trailing_spaces(line);
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        // `\#` and `\!` start a pattern with a literal `#` or `!`
+        let line = match line.strip_prefix('\\') {
+            Some(rest) if rest.starts_with(['#', '!']) => rest,
+            _ => line,
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if line.is_empty() {
+            return None;
+        }
+
+        // A slash anywhere but the end ties the pattern to its directory;
+        // otherwise it matches a name at any depth
+        let anchored = line.contains('/');
+        let mut segments: Vec<String> = line
+            .trim_start_matches('/')
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !anchored {
+            segments.insert(0, "**".to_string());
+        }
+        Some(Self {
+            segments,
+            negated,
+            dir_only,
+        })
+    }
+
+    fn matches(&self, path: &[&str], is_dir: bool) -> bool {
+        (is_dir || !self.dir_only) && segments_match(&self.segments, path)
+    }
+}
+
+/// Strip unescaped trailing spaces, as git does
+fn trim_trailing_spaces(line: &str) -> &str {
+    let trimmed = line.trim_end_matches(' ');
+    if trimmed.len() < line.len() && trimmed.ends_with('\\') {
+        // `\ ` keeps one space
+        &line[..trimmed.len() + 1]
+    } else {
+        trimmed
+    }
+}
+
+/// Patterns of one `.gixignore` file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IgnoreFile {
+    /// Directory holding the file, relative to the drive root
+    base: Vec<String>,
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreFile {
+    /// Parse the contents of an ignore file found in directory `base`
+    pub fn parse(base: &Path, contents: &str) -> Self {
+        Self {
+            base: path_segments(base),
+            patterns: contents.lines().filter_map(IgnorePattern::parse).collect(),
+        }
+    }
+
+    /// Whether the last pattern matching `path` ignores it (Some(true)) or
+    /// re-includes it (Some(false)); None if no pattern matches
+    fn decide(&self, path: &[&str], is_dir: bool) -> Option<bool> {
+        let rest = path.get(self.base.len()..)?;
+        if rest.is_empty() || !self.base.iter().zip(path).all(|(a, b)| a == b) {
+            return None;
+        }
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(rest, is_dir))
+            .map(|pattern| !pattern.negated)
+    }
+}
+
+/// All ignore files of one drive, shallowest first
+#[derive(Clone, Debug, Default)]
+pub struct DriveIgnore {
+    files: Vec<IgnoreFile>,
+}
+
+impl DriveIgnore {
+    /// Read every `.gixignore` under `root`
+    pub fn load(root: &Path) -> Self {
+        let mut files: Vec<IgnoreFile> = walkdir::WalkDir::new(root)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file() && e.file_name() == IGNORE_FILE_NAME)
+            .filter(|e| e.metadata().is_ok_and(|m| m.len() <= MAX_IGNORE_FILE_BYTES))
+            .take(MAX_IGNORE_FILES)
+            .filter_map(|e| {
+                let contents = std::fs::read_to_string(e.path()).ok()?;
+                let base = e.path().parent()?.strip_prefix(root).ok()?;
+                Some(IgnoreFile::parse(base, &contents))
+            })
+            .collect();
+        files.sort_by_key(|file| file.base.len());
+        Self { files }
+    }
+
+    /// Build from already parsed files
+    pub fn from_files(mut files: Vec<IgnoreFile>) -> Self {
+        files.sort_by_key(|file| file.base.len());
+        Self { files }
+    }
+
+    /// Number of ignore files loaded
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// Whether a drive-relative path is ignored
+    ///
+    /// A path inside an ignored directory is ignored whatever later
+    /// patterns say about it.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        if self.files.is_empty() {
+            return false;
+        }
+        let segments = path_segments(relative);
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+        (1..=segments.len()).any(|len| {
+            let is_dir = is_dir || len < segments.len();
+            self.decide(&segments[..len], is_dir)
+        })
+    }
+
+    /// Decision for one path, ignoring its parents
+    fn decide(&self, path: &[&str], is_dir: bool) -> bool {
+        self.files
+            .iter()
+            .rev()
+            .find_map(|file| file.decide(path, is_dir))
+            .unwrap_or(false)
+    }
+}
+
+/// `.gixignore` rules of every watched drive
+///
+/// Shared by the file watcher, which keeps the rules current, and the sync
+/// engine, which drops local changes to ignored paths.
+#[derive(Default)]
+pub struct IgnoreRules {
+    drives: RwLock<HashMap<DriveId, Arc<DriveIgnore>>>,
+}
+
+impl IgnoreRules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Re-read a drive's ignore files, returning how many were found
+    pub fn load(&self, drive_id: DriveId, root: &Path) -> usize {
+        self.set(drive_id, DriveIgnore::load(root))
+    }
+
+    /// Replace a drive's rules, returning the number of ignore files
+    pub fn set(&self, drive_id: DriveId, rules: DriveIgnore) -> usize {
+        let count = rules.file_count();
+        if let Ok(mut drives) = self.drives.write() {
+            drives.insert(drive_id, Arc::new(rules));
+        }
+        count
+    }
+
+    /// Forget a drive's rules
+    pub fn remove(&self, drive_id: &DriveId) {
+        if let Ok(mut drives) = self.drives.write() {
+            drives.remove(drive_id);
+        }
+    }
+
+    /// Whether a drive-relative path is ignored in a drive
+    pub fn is_ignored(&self, drive_id: &DriveId, relative: &Path, is_dir: bool) -> bool {
+        let rules = self
+            .drives
+            .read()
+            .ok()
+            .and_then(|drives| drives.get(drive_id).cloned());
+        rules.is_some_and(|rules| rules.is_ignored(relative, is_dir))
+    }
+}
+
+/// Check if a path names an ignore file
+pub fn is_ignore_file(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name == IGNORE_FILE_NAME)
+}
+
+/// Normal components of a relative path
+fn path_segments(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().replace('\\', "/")),
+            _ => None,
+        })
+        .flat_map(|name| {
+            name.split('/')
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Match pattern segments against path segments, `**` spanning any number
+fn segments_match(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| segments_match(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                wildcard_match(first.as_bytes(), name.as_bytes()) && segments_match(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Match one segment: `*`, `?`, `[...]` classes and `\` escapes
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Where to resume after the last `*`: (pattern index, name index)
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p + 1, n));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(1),
+            Some(b'[') => match_class(&pattern[p..], name[n]),
+            Some(b'\\') if p + 1 < pattern.len() => (pattern[p + 1] == name[n]).then_some(2),
+            Some(&c) => (c == name[n]).then_some(1),
+            None => None,
+        };
+        match (step, star) {
+            (Some(len), _) => {
+                p += len;
+                n += 1;
+            }
+            (None, Some((star_p, star_n))) => {
+                p = star_p;
+                n = star_n + 1;
+                star = Some((star_p, star_n + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Match a `[...]` class at the start of `pattern` against one byte,
+/// returning the class length on a match
+fn match_class(pattern: &[u8], c: u8) -> Option<usize> {
+    let mut i = 1;
+    let negated = matches!(pattern.get(i), Some(b'!') | Some(b'^'));
+    if negated {
+        i += 1;
+    }
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() && (first || pattern[i] != b']') {
+        first = false;
+        let low = pattern[i];
+        if pattern.get(i + 1) == Some(&b'-') && pattern.get(i + 2).is_some_and(|&h| h != b']') {
+            matched |= (low..=pattern[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= low == c;
+            i += 1;
+        }
+    }
+    if i >= pattern.len() {
+        // Unclosed class: a literal `[`
+        return (c == b'[').then_some(1);
+    }
+    (matched != negated).then_some(i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(files: &[(&str, &str)]) -> DriveIgnore {
+        DriveIgnore::from_files(
+            files
+                .iter()
+                .map(|(base, contents)| IgnoreFile::parse(Path::new(base), contents))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_gitignore_patterns() {
+        let rules = rules(&[(
+            "",
+            "# editor files\n*.sw[po]\n*~\n/build/\nlogs/**/*.log\n\\#notes\nscratch \n",
+        )]);
+        let ignored = |path: &str| rules.is_ignored(Path::new(path), false);
+
+        assert!(ignored("a.swp"));
+        assert!(ignored("src/deep/a.swo"));
+        assert!(!ignored("a.swx"));
+        assert!(ignored("draft.txt~"));
+        assert!(ignored("build/out.bin"));
+        // Anchored to the root
+        assert!(!ignored("src/build/out.bin"));
+        // `build/` only matches directories
+        assert!(!ignored("build"));
+        assert!(ignored("logs/a.log"));
+        assert!(ignored("logs/2024/01/a.log"));
+        assert!(!ignored("other/a.log"));
+        assert!(ignored("#notes"));
+        assert!(ignored("scratch"));
+        assert!(!ignored("src/main.rs"));
+    }
+
+    #[test]
+    fn test_negation_and_nested_files() {
+        let rules = rules(&[
+            ("", "*.log\n!keep.log\nvendor/\n"),
+            ("docs", "!debug.log\n*.pdf\n"),
+            ("docs/old", "*\n"),
+        ]);
+        let ignored = |path: &str| rules.is_ignored(Path::new(path), false);
+
+        assert!(ignored("debug.log"));
+        assert!(!ignored("keep.log"));
+        // Deeper files take precedence
+        assert!(!ignored("docs/debug.log"));
+        assert!(ignored("docs/other.log"));
+        assert!(ignored("docs/guide.pdf"));
+        assert!(!ignored("guide.pdf"));
+        assert!(ignored("docs/old/anything.md"));
+        // Nothing inside an ignored directory is re-included
+        assert!(ignored("vendor/keep.log"));
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(b"*.txt", b"a.txt"));
+        assert!(wildcard_match(b"a*b*c", b"aXXbYYc"));
+        assert!(!wildcard_match(b"a*b*c", b"aXXbYY"));
+        assert!(wildcard_match(b"file?.md", b"file1.md"));
+        assert!(wildcard_match(b"[!a-c]x", b"dx"));
+        assert!(!wildcard_match(b"[!a-c]x", b"bx"));
+        assert!(wildcard_match(b"\\*", b"*"));
+        assert!(!wildcard_match(b"\\*", b"a"));
+        assert!(wildcard_match(b"[ab", b"[ab"));
+    }
+
+    #[test]
+    fn test_load_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("sub")).unwrap();
+        std::fs::write(dir.path().join(IGNORE_FILE_NAME), "*.tmp\n").unwrap();
+        std::fs::write(dir.path().join("sub").join(IGNORE_FILE_NAME), "!keep.tmp\n").unwrap();
+
+        let drive_id = DriveId([1u8; 32]);
+        let registry = IgnoreRules::new();
+        assert!(!registry.is_ignored(&drive_id, Path::new("a.tmp"), false));
+        assert_eq!(registry.load(drive_id, dir.path()), 2);
+        assert!(registry.is_ignored(&drive_id, Path::new("a.tmp"), false));
+        assert!(!registry.is_ignored(&drive_id, Path::new("sub/keep.tmp"), false));
+
+        registry.remove(&drive_id);
+        assert!(!registry.is_ignored(&drive_id, Path::new("a.tmp"), false));
+    }
+}
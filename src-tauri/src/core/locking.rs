@@ -1,23 +1,56 @@
 //! File locking for collaborative editing
 //!
-//! Provides advisory and exclusive locking to prevent edit conflicts.
-//! Locks are broadcast via gossip so all peers see lock status.
+//! Provides advisory, shared read and exclusive write locks to prevent edit
+//! conflicts. Any number of nodes can hold read locks on a file at once; a
+//! write lock needs the file to itself. Locks are broadcast via gossip so
+//! all peers see lock status.
 
+use crate::core::{send_with_backpressure, DriveEvent};
 use crate::crypto::NodeId;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 
 /// Type of lock that can be acquired
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub enum LockType {
     /// Advisory lock - warns others but doesn't prevent access
     Advisory,
-    /// Exclusive lock - prevents others from editing
-    Exclusive,
+    /// Shared read lock - held by any number of nodes at once
+    Read,
+    /// Write lock - exclusive, waits until all readers have released
+    #[serde(alias = "Exclusive")]
+    Write,
+}
+
+impl LockType {
+    /// Name used by the frontend and in events
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockType::Advisory => "advisory",
+            LockType::Read => "read",
+            LockType::Write => "write",
+        }
+    }
+
+    /// Parse a lock type name, defaulting to advisory
+    ///
+    /// `exclusive` is accepted as the old name for `write`.
+    pub fn parse(name: &str) -> Self {
+        match name {
+            "read" => LockType::Read,
+            "write" | "exclusive" => LockType::Write,
+            _ => LockType::Advisory,
+        }
+    }
+
+    /// Whether a lock of this type can be held while another node holds `other`
+    pub fn is_compatible_with(&self, other: LockType) -> bool {
+        *self != LockType::Write && other != LockType::Write
+    }
 }
 
 /// Represents an active lock on a file
@@ -96,10 +129,7 @@ impl FileLockDto {
         Self {
             path: lock.path.to_string_lossy().to_string(),
             holder: lock.holder.to_hex(),
-            lock_type: match lock.lock_type {
-                LockType::Advisory => "advisory".to_string(),
-                LockType::Exclusive => "exclusive".to_string(),
-            },
+            lock_type: lock.lock_type.as_str().to_string(),
             acquired_at: lock.acquired_at.to_rfc3339(),
             expires_at: lock.expires_at.to_rfc3339(),
             reason: lock.reason.clone(),
@@ -123,108 +153,220 @@ pub enum LockResult {
         lock: FileLock,
         warning: String,
     },
+    /// File is locked; the request waits in its queue at this 1-based
+    /// position and is granted when the lock is released or expires
+    Queued { position: usize },
+}
+
+/// A request waiting for a locked file to become available
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LockRequest {
+    /// Path relative to drive root
+    pub path: PathBuf,
+    /// Node waiting for the lock
+    pub requester: NodeId,
+    /// Type of lock requested
+    pub lock_type: LockType,
+    /// When the request was queued
+    pub requested_at: DateTime<Utc>,
+    /// Requester holds a read lock and waits to upgrade it to write
+    #[serde(default)]
+    pub upgrade: bool,
+}
+
+/// DTO for sending lock queue entries to frontend
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LockQueueEntryDto {
+    pub path: String,
+    pub requester: String,
+    pub lock_type: String,
+    pub requested_at: String,
+    /// 1-based position in the queue for this path
+    pub position: usize,
+    pub is_mine: bool,
+}
+
+impl LockQueueEntryDto {
+    pub fn from_request(request: &LockRequest, position: usize, my_node_id: &NodeId) -> Self {
+        Self {
+            path: request.path.to_string_lossy().to_string(),
+            requester: request.requester.to_hex(),
+            lock_type: request.lock_type.as_str().to_string(),
+            requested_at: request.requested_at.to_rfc3339(),
+            position,
+            is_mine: request.requester == *my_node_id,
+        }
+    }
+}
+
+/// A queue position change produced by a queue mutation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueuePositionChange {
+    pub path: PathBuf,
+    pub requester: NodeId,
+    /// New 1-based position (None = removed from queue)
+    pub position: Option<usize>,
 }
 
 /// Manages file locks for a single drive
 #[derive(Debug)]
 pub struct DriveLockManager {
-    /// Active locks keyed by file path
-    locks: RwLock<HashMap<PathBuf, FileLock>>,
+    /// Active locks keyed by file path; several readers can share a path
+    locks: RwLock<HashMap<PathBuf, Vec<FileLock>>>,
+    /// Pending lock requests per path, in arrival order
+    queues: RwLock<HashMap<PathBuf, VecDeque<LockRequest>>>,
 }
 
 impl DriveLockManager {
     pub fn new() -> Self {
         Self {
             locks: RwLock::new(HashMap::new()),
+            queues: RwLock::new(HashMap::new()),
         }
     }
 
     /// Attempt to acquire a lock
+    ///
+    /// A holder asking again replaces its own lock, which is how a lock is
+    /// refreshed, downgraded or upgraded. While requests are queued for the
+    /// file, newcomers are denied so they can't overtake the queue; this
+    /// keeps a steady stream of readers from starving a waiting writer.
+    ///
+    /// A reader asking for a write lock while others still read is queued
+    /// ahead of everyone with `upgrade`, and denied without it. Only one
+    /// upgrade can wait per file, since two would wait on each other.
     pub async fn acquire(
         &self,
         path: PathBuf,
         holder: NodeId,
         lock_type: LockType,
+        upgrade: bool,
     ) -> LockResult {
         let mut locks = self.locks.write().await;
+        let mut queues = self.queues.write().await;
+        let holders = locks.entry(path.clone()).or_default();
 
-        // Clean up expired locks first
-        locks.retain(|_, lock| !lock.is_expired());
+        // Expired locks no longer count; waiting requests get them through
+        // `cleanup_expired` and `grant_next`
+        holders.retain(|lock| !lock.is_expired());
 
-        // Check if already locked
-        if let Some(existing) = locks.get(&path) {
-            // Same holder can upgrade/refresh their lock
-            if existing.holder == holder {
-                let new_lock = FileLock::new(path.clone(), holder, lock_type);
-                locks.insert(path, new_lock.clone());
-                return LockResult::Acquired(new_lock);
+        let held = holders.iter().find(|lock| lock.holder == holder).cloned();
+        let others: Vec<&FileLock> = holders
+            .iter()
+            .filter(|lock| lock.holder != holder)
+            .collect();
+
+        if let Some(existing) = others
+            .iter()
+            .find(|lock| !lock_type.is_compatible_with(lock.lock_type))
+        {
+            let existing_lock = (*existing).clone();
+            let upgrading = held
+                .as_ref()
+                .is_some_and(|lock| lock.lock_type == LockType::Read)
+                && lock_type == LockType::Write;
+            if !upgrading {
+                let reason = match existing.lock_type {
+                    LockType::Write => {
+                        format!("File is write-locked by {}", existing.holder.short_string())
+                    }
+                    _ => format!(
+                        "File is locked by {} - cannot acquire write lock",
+                        existing.holder.short_string()
+                    ),
+                };
+                return LockResult::Denied {
+                    existing_lock,
+                    reason,
+                };
+            }
+            if !upgrade {
+                return LockResult::Denied {
+                    existing_lock,
+                    reason: format!("{} other node(s) still hold read locks", others.len()),
+                };
             }
 
-            // Different holder
-            match (existing.lock_type, lock_type) {
-                // Exclusive lock blocks everything
-                (LockType::Exclusive, _) => {
-                    return LockResult::Denied {
-                        existing_lock: existing.clone(),
-                        reason: format!(
-                            "File is exclusively locked by {}",
-                            existing.holder.short_string()
-                        ),
-                    };
-                }
-                // Trying to get exclusive on advisory
-                (LockType::Advisory, LockType::Exclusive) => {
-                    return LockResult::Denied {
-                        existing_lock: existing.clone(),
-                        reason: format!(
-                            "File has advisory lock by {} - cannot acquire exclusive",
-                            existing.holder.short_string()
-                        ),
-                    };
-                }
-                // Advisory on advisory - warn but allow
-                (LockType::Advisory, LockType::Advisory) => {
-                    let new_lock = FileLock::new(path.clone(), holder, lock_type);
-                    // Don't replace existing advisory lock, just warn
-                    return LockResult::AcquiredWithWarning {
-                        lock: new_lock,
-                        warning: format!(
-                            "File also has advisory lock by {}",
-                            existing.holder.short_string()
-                        ),
-                    };
-                }
+            let queue = queues.entry(path.clone()).or_default();
+            if let Some(index) = queue.iter().position(|r| r.requester == holder) {
+                return LockResult::Queued {
+                    position: index + 1,
+                };
+            }
+            if queue.iter().any(|r| r.upgrade) {
+                return LockResult::Denied {
+                    existing_lock,
+                    reason: "Another reader is already waiting to upgrade".to_string(),
+                };
             }
+            queue.push_front(LockRequest {
+                path,
+                requester: holder,
+                lock_type,
+                requested_at: Utc::now(),
+                upgrade: true,
+            });
+            return LockResult::Queued { position: 1 };
         }
 
-        // No existing lock, acquire it
-        let lock = FileLock::new(path.clone(), holder, lock_type);
-        locks.insert(path, lock.clone());
-        LockResult::Acquired(lock)
+        // Queued requests go first
+        if held.is_none() && !others.is_empty() {
+            if let Some(queue) = queues.get(&path).filter(|q| !q.is_empty()) {
+                return LockResult::Denied {
+                    existing_lock: others[0].clone(),
+                    reason: format!("{} request(s) are waiting for this file", queue.len()),
+                };
+            }
+        }
+
+        let warning = others
+            .iter()
+            .find(|lock| lock_type == LockType::Advisory || lock.lock_type == LockType::Advisory)
+            .map(|lock| {
+                format!(
+                    "File also has {} lock by {}",
+                    lock.lock_type.as_str(),
+                    lock.holder.short_string()
+                )
+            });
+
+        let lock = FileLock::new(path, holder, lock_type);
+        holders.retain(|existing| existing.holder != holder);
+        holders.push(lock.clone());
+        match warning {
+            Some(warning) => LockResult::AcquiredWithWarning { lock, warning },
+            None => LockResult::Acquired(lock),
+        }
     }
 
     /// Release a lock
     pub async fn release(&self, path: &PathBuf, holder: &NodeId) -> Option<FileLock> {
         let mut locks = self.locks.write().await;
-
-        if let Some(existing) = locks.get(path) {
-            if existing.holder == *holder {
-                return locks.remove(path);
-            }
+        let holders = locks.get_mut(path)?;
+        let index = holders.iter().position(|lock| lock.holder == *holder)?;
+        let released = holders.remove(index);
+        if holders.is_empty() {
+            locks.remove(path);
         }
-        None
+        Some(released)
     }
 
-    /// Force release a lock (for admins)
-    pub async fn force_release(&self, path: &PathBuf) -> Option<FileLock> {
+    /// Force release every lock on a path (for admins)
+    pub async fn force_release(&self, path: &PathBuf) -> Vec<FileLock> {
         let mut locks = self.locks.write().await;
-        locks.remove(path)
+        locks.remove(path).unwrap_or_default()
     }
 
-    /// Get lock status for a path
-    pub async fn get_lock(&self, path: &PathBuf) -> Option<FileLock> {
+    /// Get the current holders of a path
+    pub async fn get_locks(&self, path: &PathBuf) -> Vec<FileLock> {
         let locks = self.locks.read().await;
-        locks.get(path).filter(|l| !l.is_expired()).cloned()
+        locks
+            .get(path)
+            .into_iter()
+            .flatten()
+            .filter(|l| !l.is_expired())
+            .cloned()
+            .collect()
     }
 
     /// Get all active locks
@@ -232,6 +374,7 @@ impl DriveLockManager {
         let locks = self.locks.read().await;
         locks
             .values()
+            .flatten()
             .filter(|l| !l.is_expired())
             .cloned()
             .collect()
@@ -246,51 +389,235 @@ impl DriveLockManager {
     ) -> Option<FileLock> {
         let mut locks = self.locks.write().await;
 
-        if let Some(lock) = locks.get_mut(path) {
-            if lock.holder == *holder && !lock.is_expired() {
-                lock.extend(Duration::minutes(duration_mins));
-                return Some(lock.clone());
-            }
-        }
-        None
+        let lock = locks
+            .get_mut(path)?
+            .iter_mut()
+            .find(|lock| lock.holder == *holder && !lock.is_expired())?;
+        lock.extend(Duration::minutes(duration_mins));
+        Some(lock.clone())
     }
 
     /// Apply a remote lock (from gossip)
+    ///
+    /// Of two conflicting locks, the older one is kept.
     pub async fn apply_remote_lock(&self, lock: FileLock) {
         if lock.is_expired() {
             return;
         }
 
         let mut locks = self.locks.write().await;
-        
-        // Only apply if no existing lock or existing lock is expired
-        if let Some(existing) = locks.get(&lock.path) {
-            if !existing.is_expired() && existing.acquired_at < lock.acquired_at {
-                // Existing lock is older, keep it
-                return;
-            }
+        let holders = locks.entry(lock.path.clone()).or_default();
+        holders.retain(|existing| !existing.is_expired());
+
+        let conflicts_with_older = holders.iter().any(|existing| {
+            existing.holder != lock.holder
+                && !lock.lock_type.is_compatible_with(existing.lock_type)
+                && existing.acquired_at < lock.acquired_at
+        });
+        if conflicts_with_older {
+            return;
         }
 
-        locks.insert(lock.path.clone(), lock);
+        holders.retain(|existing| {
+            existing.holder != lock.holder && lock.lock_type.is_compatible_with(existing.lock_type)
+        });
+        holders.push(lock);
     }
 
     /// Remove a remote lock (from gossip)
     pub async fn remove_remote_lock(&self, path: &PathBuf, holder: &NodeId) {
-        let mut locks = self.locks.write().await;
-        
-        if let Some(existing) = locks.get(path) {
-            if existing.holder == *holder {
-                locks.remove(path);
-            }
-        }
+        self.release(path, holder).await;
     }
 
     /// Cleanup expired locks
     pub async fn cleanup_expired(&self) -> usize {
+        self.take_expired().await.len()
+    }
+
+    /// Remove expired locks, returning them
+    pub async fn take_expired(&self) -> Vec<FileLock> {
         let mut locks = self.locks.write().await;
-        let before = locks.len();
-        locks.retain(|_, lock| !lock.is_expired());
-        before - locks.len()
+        let mut expired = Vec::new();
+        for holders in locks.values_mut() {
+            let (gone, kept): (Vec<FileLock>, Vec<FileLock>) =
+                holders.drain(..).partition(|lock| lock.is_expired());
+            *holders = kept;
+            expired.extend(gone);
+        }
+        locks.retain(|_, holders| !holders.is_empty());
+        expired
+    }
+
+    /// Give a file to the requests at the head of its queue
+    ///
+    /// Requests are granted in order for as long as they are compatible
+    /// with the current holders, so a run of queued readers is granted
+    /// together while a queued writer waits until the file is free. Returns
+    /// the new locks with the queue position changes they caused, or None
+    /// if nothing could be granted.
+    pub async fn grant_next(
+        &self,
+        path: &Path,
+    ) -> Option<(Vec<FileLock>, Vec<QueuePositionChange>)> {
+        let mut locks = self.locks.write().await;
+        let mut queues = self.queues.write().await;
+        let queue = queues.get_mut(path)?;
+        let holders = locks.entry(path.to_path_buf()).or_default();
+        holders.retain(|lock| !lock.is_expired());
+
+        let mut granted = Vec::new();
+        while let Some(request) = queue.front() {
+            let fits = holders
+                .iter()
+                .filter(|lock| lock.holder != request.requester)
+                .all(|lock| request.lock_type.is_compatible_with(lock.lock_type));
+            if !fits {
+                break;
+            }
+            let Some(request) = queue.pop_front() else {
+                break;
+            };
+            let lock = FileLock::new(path.to_path_buf(), request.requester, request.lock_type);
+            holders.retain(|existing| existing.holder != request.requester);
+            holders.push(lock.clone());
+            granted.push(lock);
+        }
+
+        if holders.is_empty() {
+            locks.remove(path);
+        }
+        if granted.is_empty() {
+            return None;
+        }
+
+        let mut changes: Vec<QueuePositionChange> = granted
+            .iter()
+            .map(|lock| QueuePositionChange {
+                path: path.to_path_buf(),
+                requester: lock.holder,
+                position: None,
+            })
+            .collect();
+        changes.extend(queue.iter().enumerate().map(|(i, r)| QueuePositionChange {
+            path: path.to_path_buf(),
+            requester: r.requester,
+            position: Some(i + 1),
+        }));
+        if queue.is_empty() {
+            queues.remove(path);
+        }
+        Some((granted, changes))
+    }
+
+    /// Queue a request for a locked file
+    ///
+    /// Returns the 1-based queue position. Re-queuing an existing request
+    /// keeps its original position.
+    pub async fn enqueue(&self, path: PathBuf, requester: NodeId, lock_type: LockType) -> usize {
+        let mut queues = self.queues.write().await;
+        let queue = queues.entry(path.clone()).or_default();
+
+        if let Some(index) = queue.iter().position(|r| r.requester == requester) {
+            return index + 1;
+        }
+
+        queue.push_back(LockRequest {
+            path,
+            requester,
+            lock_type,
+            requested_at: Utc::now(),
+            upgrade: false,
+        });
+        queue.len()
+    }
+
+    /// List queued requests with their positions, for one path or the whole drive
+    pub async fn list_queue(&self, path: Option<&Path>) -> Vec<(usize, LockRequest)> {
+        let queues = self.queues.read().await;
+        let mut entries: Vec<(usize, LockRequest)> = queues
+            .iter()
+            .filter(|(p, _)| match path {
+                Some(path) => p.as_path() == path,
+                None => true,
+            })
+            .flat_map(|(_, queue)| {
+                queue
+                    .iter()
+                    .enumerate()
+                    .map(|(i, request)| (i + 1, request.clone()))
+            })
+            .collect();
+
+        entries.sort_by(|a, b| a.1.path.cmp(&b.1.path).then(a.0.cmp(&b.0)));
+        entries
+    }
+
+    /// Withdraw a requester from a path's queue
+    ///
+    /// Returns the position changes caused (including the removal itself),
+    /// or an empty list if the requester was not queued.
+    pub async fn cancel_request(&self, path: &Path, requester: &NodeId) -> Vec<QueuePositionChange> {
+        let mut queues = self.queues.write().await;
+        let Some(queue) = queues.get_mut(path) else {
+            return Vec::new();
+        };
+        let Some(index) = queue.iter().position(|r| r.requester == *requester) else {
+            return Vec::new();
+        };
+
+        queue.remove(index);
+        let mut changes = vec![QueuePositionChange {
+            path: path.to_path_buf(),
+            requester: *requester,
+            position: None,
+        }];
+        changes.extend(queue.iter().enumerate().skip(index).map(|(i, r)| {
+            QueuePositionChange {
+                path: path.to_path_buf(),
+                requester: r.requester,
+                position: Some(i + 1),
+            }
+        }));
+
+        if queue.is_empty() {
+            queues.remove(path);
+        }
+        changes
+    }
+
+    /// Drop queued requests that fail the predicate (e.g. disconnected peers)
+    pub async fn retain_queue<F>(&self, mut keep: F) -> Vec<QueuePositionChange>
+    where
+        F: FnMut(&LockRequest) -> bool,
+    {
+        let mut queues = self.queues.write().await;
+        let mut changes = Vec::new();
+
+        for (path, queue) in queues.iter_mut() {
+            let before: Vec<NodeId> = queue.iter().map(|r| r.requester).collect();
+            queue.retain(&mut keep);
+            if queue.len() == before.len() {
+                continue;
+            }
+
+            for requester in &before {
+                let position = queue
+                    .iter()
+                    .position(|r| r.requester == *requester)
+                    .map(|i| i + 1);
+                let old_position = before.iter().position(|r| r == requester).map(|i| i + 1);
+                if position != old_position {
+                    changes.push(QueuePositionChange {
+                        path: path.clone(),
+                        requester: *requester,
+                        position,
+                    });
+                }
+            }
+        }
+
+        queues.retain(|_, queue| !queue.is_empty());
+        changes
     }
 }
 
@@ -306,16 +633,25 @@ pub struct LockManager {
     drives: RwLock<HashMap<String, Arc<DriveLockManager>>>,
     /// Our node ID for ownership checks
     node_id: NodeId,
+    /// Channel for lock queue events (drive ID hex, event)
+    event_tx: broadcast::Sender<(String, DriveEvent)>,
 }
 
 impl LockManager {
     pub fn new(node_id: NodeId) -> Self {
+        let (event_tx, _) = broadcast::channel(256);
         Self {
             drives: RwLock::new(HashMap::new()),
             node_id,
+            event_tx,
         }
     }
 
+    /// Subscribe to lock queue events
+    pub fn subscribe(&self) -> broadcast::Receiver<(String, DriveEvent)> {
+        self.event_tx.subscribe()
+    }
+
     /// Get or create lock manager for a drive
     pub async fn get_drive_locks(&self, drive_id: &str) -> Arc<DriveLockManager> {
         {
@@ -338,26 +674,123 @@ impl LockManager {
     }
 
     /// Acquire a lock
+    ///
+    /// With `wait`, a request for a locked file joins the file's queue and
+    /// `Queued` is returned instead of `Denied`. Queued requests are served
+    /// first: a file whose lock expired goes to the head of its queue.
+    ///
+    /// Upgrading our read lock to a write lock while others still read is
+    /// governed by `upgrade` instead: the request is queued ahead of the
+    /// others, or denied without it.
     pub async fn acquire_lock(
         &self,
         drive_id: &str,
         path: PathBuf,
         lock_type: LockType,
+        wait: bool,
+        upgrade: bool,
     ) -> LockResult {
+        self.grant_next(drive_id, &path).await;
         let manager = self.get_drive_locks(drive_id).await;
-        manager.acquire(path, self.node_id, lock_type).await
+        let reading = manager
+            .get_locks(&path)
+            .await
+            .iter()
+            .any(|lock| lock.is_held_by(&self.node_id) && lock.lock_type == LockType::Read);
+        let upgrading = reading && lock_type == LockType::Write;
+
+        match manager
+            .acquire(path.clone(), self.node_id, lock_type, upgrade)
+            .await
+        {
+            LockResult::Queued { position } => {
+                // An upgrade goes to the front, moving everyone else back
+                let changes: Vec<QueuePositionChange> = manager
+                    .list_queue(Some(&path))
+                    .await
+                    .into_iter()
+                    .map(|(position, request)| QueuePositionChange {
+                        path: request.path,
+                        requester: request.requester,
+                        position: Some(position),
+                    })
+                    .collect();
+                self.emit_queue_changes(drive_id, &changes);
+                LockResult::Queued { position }
+            }
+            // Queuing behind a writer while still reading would never be granted
+            LockResult::Denied { .. } if wait && !upgrading => {
+                let position = self.enqueue_lock(drive_id, path, lock_type).await;
+                LockResult::Queued { position }
+            }
+            result => result,
+        }
     }
 
     /// Release a lock
+    ///
+    /// The file then goes to the first queued request, if any.
     pub async fn release_lock(&self, drive_id: &str, path: &PathBuf) -> Option<FileLock> {
         let manager = self.get_drive_locks(drive_id).await;
-        manager.release(path, &self.node_id).await
+        let released = manager.release(path, &self.node_id).await?;
+        self.grant_next(drive_id, path).await;
+        Some(released)
+    }
+
+    /// Release every lock on a file whoever holds it, granting the file to
+    /// the next waiters
+    pub async fn force_release_lock(&self, drive_id: &str, path: &PathBuf) -> Vec<FileLock> {
+        let manager = self.get_drive_locks(drive_id).await;
+        let released = manager.force_release(path).await;
+        if !released.is_empty() {
+            self.grant_next(drive_id, path).await;
+        }
+        released
+    }
+
+    /// Grant a file to the requests at the head of its queue
+    ///
+    /// Emits `LockGranted` for each new lock and the queue position
+    /// changes. Returns the new locks, empty if the file is still locked
+    /// or nobody is waiting.
+    pub async fn grant_next(&self, drive_id: &str, path: &Path) -> Vec<FileLock> {
+        let manager = self.get_drive_locks(drive_id).await;
+        let Some((granted, changes)) = manager.grant_next(path).await else {
+            return Vec::new();
+        };
+
+        for lock in &granted {
+            tracing::info!(
+                drive_id = %drive_id,
+                path = %path.display(),
+                holder = %lock.holder,
+                lock_type = lock.lock_type.as_str(),
+                "Lock granted to next in queue"
+            );
+
+            let event = DriveEvent::LockGranted {
+                path: lock.path.clone(),
+                holder: lock.holder,
+                lock_type: lock.lock_type.as_str().to_string(),
+                expires_at: lock.expires_at,
+                timestamp: Utc::now(),
+            };
+            let strategy = event.backpressure_strategy(None);
+            send_with_backpressure(
+                &self.event_tx,
+                (drive_id.to_string(), event),
+                "lock_queue",
+                strategy,
+            );
+        }
+        self.emit_queue_changes(drive_id, &changes);
+        granted
     }
 
-    /// Get lock info
-    pub async fn get_lock(&self, drive_id: &str, path: &PathBuf) -> Option<FileLock> {
+    /// Get every current holder of a file
+    pub async fn get_locks(&self, drive_id: &str, path: &PathBuf) -> Vec<FileLock> {
         let manager = self.get_drive_locks(drive_id).await;
-        manager.get_lock(path).await
+        manager.get_locks(path).await
     }
 
     /// List all locks for a drive
@@ -377,6 +810,43 @@ impl LockManager {
         manager.extend_lock(path, &self.node_id, duration_mins).await
     }
 
+    /// List locks we hold across all drives as (drive ID hex, lock)
+    ///
+    /// Sorted by drive, then oldest lock first. Expired locks are skipped.
+    pub async fn list_my_locks(&self) -> Vec<(String, FileLock)> {
+        let drives: Vec<(String, Arc<DriveLockManager>)> = {
+            let drives = self.drives.read().await;
+            drives.iter().map(|(id, m)| (id.clone(), m.clone())).collect()
+        };
+
+        let mut mine = Vec::new();
+        for (drive_id, manager) in drives {
+            for lock in manager.list_locks().await {
+                if lock.is_held_by(&self.node_id) {
+                    mine.push((drive_id.clone(), lock));
+                }
+            }
+        }
+        mine.sort_by(|(a_drive, a), (b_drive, b)| {
+            a_drive
+                .cmp(b_drive)
+                .then(a.acquired_at.cmp(&b.acquired_at))
+                .then(a.path.cmp(&b.path))
+        });
+        mine
+    }
+
+    /// Release every lock we hold, returning the released locks
+    pub async fn release_all_my_locks(&self) -> Vec<(String, FileLock)> {
+        let mut released = Vec::new();
+        for (drive_id, lock) in self.list_my_locks().await {
+            if let Some(lock) = self.release_lock(&drive_id, &lock.path).await {
+                released.push((drive_id, lock));
+            }
+        }
+        released
+    }
+
     /// Apply a lock received from gossip
     pub async fn apply_remote_lock(&self, drive_id: &str, lock: FileLock) {
         let manager = self.get_drive_locks(drive_id).await;
@@ -387,17 +857,106 @@ impl LockManager {
     pub async fn remove_remote_lock(&self, drive_id: &str, path: &PathBuf, holder: &NodeId) {
         let manager = self.get_drive_locks(drive_id).await;
         manager.remove_remote_lock(path, holder).await;
+        self.grant_next(drive_id, path).await;
     }
 
     /// Cleanup expired locks across all drives
+    ///
+    /// Each expired file goes to the first request in its queue.
     pub async fn cleanup_expired(&self) -> usize {
-        let drives = self.drives.read().await;
+        let drives: Vec<(String, Arc<DriveLockManager>)> = {
+            let drives = self.drives.read().await;
+            drives.iter().map(|(id, m)| (id.clone(), m.clone())).collect()
+        };
+
+        let mut total = 0;
+        for (drive_id, manager) in drives {
+            let expired = manager.take_expired().await;
+            total += expired.len();
+            for lock in expired {
+                self.grant_next(&drive_id, &lock.path).await;
+            }
+        }
+        total
+    }
+
+    /// Queue our request for a locked file, returns the 1-based position
+    pub async fn enqueue_lock(&self, drive_id: &str, path: PathBuf, lock_type: LockType) -> usize {
+        let manager = self.get_drive_locks(drive_id).await;
+        let position = manager.enqueue(path.clone(), self.node_id, lock_type).await;
+        self.emit_queue_changes(
+            drive_id,
+            &[QueuePositionChange {
+                path,
+                requester: self.node_id,
+                position: Some(position),
+            }],
+        );
+        position
+    }
+
+    /// List the lock queue for a drive, optionally for a single path
+    pub async fn list_queue(&self, drive_id: &str, path: Option<&Path>) -> Vec<(usize, LockRequest)> {
+        let manager = self.get_drive_locks(drive_id).await;
+        manager.list_queue(path).await
+    }
+
+    /// Withdraw our queued request for a file
+    pub async fn cancel_request(&self, drive_id: &str, path: &Path) -> bool {
+        let manager = self.get_drive_locks(drive_id).await;
+        let changes = manager.cancel_request(path, &self.node_id).await;
+        self.emit_queue_changes(drive_id, &changes);
+        !changes.is_empty()
+    }
+
+    /// Drop queued requests from peers that are no longer connected
+    ///
+    /// `is_connected` is called with (drive_id, requester). Our own
+    /// requests are always kept.
+    pub async fn cleanup_disconnected_requests<F>(&self, is_connected: F) -> usize
+    where
+        F: Fn(&str, &NodeId) -> bool,
+    {
+        let drives: Vec<(String, Arc<DriveLockManager>)> = {
+            let drives = self.drives.read().await;
+            drives.iter().map(|(id, m)| (id.clone(), m.clone())).collect()
+        };
+
         let mut total = 0;
-        for manager in drives.values() {
-            total += manager.cleanup_expired().await;
+        for (drive_id, manager) in drives {
+            let changes = manager
+                .retain_queue(|r| r.requester == self.node_id || is_connected(&drive_id, &r.requester))
+                .await;
+            total += changes.iter().filter(|c| c.position.is_none()).count();
+            self.emit_queue_changes(&drive_id, &changes);
         }
         total
     }
+
+    /// Get IDs of all drives with lock state
+    pub async fn drive_ids(&self) -> Vec<String> {
+        let drives = self.drives.read().await;
+        drives.keys().cloned().collect()
+    }
+
+    /// Notify listeners of queue position changes
+    fn emit_queue_changes(&self, drive_id: &str, changes: &[QueuePositionChange]) {
+        for change in changes {
+            let event = DriveEvent::LockQueueChanged {
+                path: change.path.clone(),
+                requester: change.requester,
+                position: change.position,
+                timestamp: Utc::now(),
+            };
+            let strategy = event.backpressure_strategy(None);
+            send_with_backpressure(
+                &self.event_tx,
+                (drive_id.to_string(), event),
+                "lock_queue",
+                strategy,
+            );
+        }
+    }
 }
 
 #[cfg(test)]
@@ -413,7 +972,9 @@ mod tests {
         let path = PathBuf::from("test/file.txt");
 
         // Acquire lock
-        let result = manager.acquire(path.clone(), node_id, LockType::Advisory).await;
+        let result = manager
+            .acquire(path.clone(), node_id, LockType::Advisory, false)
+            .await;
         assert!(matches!(result, LockResult::Acquired(_)));
 
         // Release lock
@@ -421,11 +982,11 @@ mod tests {
         assert!(released.is_some());
 
         // Verify released
-        assert!(manager.get_lock(&path).await.is_none());
+        assert!(manager.get_locks(&path).await.is_empty());
     }
 
     #[tokio::test]
-    async fn test_exclusive_lock_blocks() {
+    async fn test_write_lock_blocks() {
         let identity1 = Identity::generate();
         let identity2 = Identity::generate();
         let node1 = identity1.node_id();
@@ -433,12 +994,16 @@ mod tests {
         let manager = DriveLockManager::new();
         let path = PathBuf::from("test/file.txt");
 
-        // User 1 acquires exclusive lock
-        let result = manager.acquire(path.clone(), node1, LockType::Exclusive).await;
+        // User 1 acquires write lock
+        let result = manager
+            .acquire(path.clone(), node1, LockType::Write, false)
+            .await;
         assert!(matches!(result, LockResult::Acquired(_)));
 
         // User 2 cannot acquire any lock
-        let result = manager.acquire(path.clone(), node2, LockType::Advisory).await;
+        let result = manager
+            .acquire(path.clone(), node2, LockType::Advisory, false)
+            .await;
         assert!(matches!(result, LockResult::Denied { .. }));
     }
 
@@ -455,10 +1020,316 @@ mod tests {
 
         {
             let mut locks = manager.locks.write().await;
-            locks.insert(path.clone(), lock);
+            locks.insert(path.clone(), vec![lock]);
         }
 
         // Should not return expired lock
-        assert!(manager.get_lock(&path).await.is_none());
+        assert!(manager.get_locks(&path).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lock_queue_positions() {
+        let node1 = Identity::generate().node_id();
+        let node2 = Identity::generate().node_id();
+        let node3 = Identity::generate().node_id();
+        let manager = DriveLockManager::new();
+        let path = PathBuf::from("test/file.txt");
+
+        assert_eq!(manager.enqueue(path.clone(), node1, LockType::Write).await, 1);
+        assert_eq!(manager.enqueue(path.clone(), node2, LockType::Advisory).await, 2);
+        assert_eq!(manager.enqueue(path.clone(), node3, LockType::Write).await, 3);
+
+        // Re-queuing keeps the original position
+        assert_eq!(manager.enqueue(path.clone(), node2, LockType::Advisory).await, 2);
+
+        let queue = manager.list_queue(Some(&path)).await;
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue[0].1.requester, node1);
+
+        // Cancelling moves later requesters up
+        let changes = manager.cancel_request(&path, &node1).await;
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].position, None);
+        assert_eq!(changes[1].requester, node2);
+        assert_eq!(changes[1].position, Some(1));
+
+        // Cancelling something not queued is a no-op
+        assert!(manager.cancel_request(&path, &node1).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lock_queue_retain() {
+        let node1 = Identity::generate().node_id();
+        let node2 = Identity::generate().node_id();
+        let manager = DriveLockManager::new();
+        let path = PathBuf::from("test/file.txt");
+
+        manager.enqueue(path.clone(), node1, LockType::Write).await;
+        manager.enqueue(path.clone(), node2, LockType::Write).await;
+
+        let changes = manager.retain_queue(|r| r.requester != node1).await;
+        assert_eq!(
+            changes,
+            vec![
+                QueuePositionChange {
+                    path: path.clone(),
+                    requester: node1,
+                    position: None,
+                },
+                QueuePositionChange {
+                    path: path.clone(),
+                    requester: node2,
+                    position: Some(1),
+                },
+            ]
+        );
+
+        manager.retain_queue(|_| false).await;
+        assert!(manager.list_queue(None).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_my_locks_across_drives() {
+        let me = Identity::generate().node_id();
+        let other = Identity::generate().node_id();
+        let manager = LockManager::new(me);
+
+        manager
+            .acquire_lock(
+                "drive_b",
+                PathBuf::from("b.txt"),
+                LockType::Write,
+                false,
+                false,
+            )
+            .await;
+        manager
+            .acquire_lock(
+                "drive_a",
+                PathBuf::from("a.txt"),
+                LockType::Advisory,
+                false,
+                false,
+            )
+            .await;
+        manager
+            .apply_remote_lock(
+                "drive_a",
+                FileLock::new(PathBuf::from("theirs.txt"), other, LockType::Write),
+            )
+            .await;
+
+        let mine = manager.list_my_locks().await;
+        let paths: Vec<_> = mine
+            .iter()
+            .map(|(drive, lock)| (drive.as_str(), lock.path.clone()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("drive_a", PathBuf::from("a.txt")),
+                ("drive_b", PathBuf::from("b.txt")),
+            ]
+        );
+
+        assert_eq!(manager.release_all_my_locks().await.len(), 2);
+        assert!(manager.list_my_locks().await.is_empty());
+        // Other holders' locks are untouched
+        assert_eq!(manager.list_locks("drive_a").await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_queued_request_granted_on_release() {
+        let holder = Identity::generate().node_id();
+        let me = Identity::generate().node_id();
+        let manager = LockManager::new(me);
+        let mut events = manager.subscribe();
+        let path = PathBuf::from("shared.txt");
+
+        let drive = manager.get_drive_locks("drive1").await;
+        drive
+            .acquire(path.clone(), holder, LockType::Write, false)
+            .await;
+
+        // Waiting twice keeps one place in line
+        for _ in 0..2 {
+            let result = manager
+                .acquire_lock("drive1", path.clone(), LockType::Write, true, false)
+                .await;
+            assert!(matches!(result, LockResult::Queued { position: 1 }));
+        }
+        assert_eq!(manager.list_queue("drive1", Some(&path)).await.len(), 1);
+        while events.try_recv().is_ok() {}
+
+        manager.remove_remote_lock("drive1", &path, &holder).await;
+        let locks = manager.get_locks("drive1", &path).await;
+        assert_eq!(locks.len(), 1);
+        assert!(locks[0].is_held_by(&me));
+        assert!(manager.list_queue("drive1", None).await.is_empty());
+
+        let (_, event) = events.try_recv().unwrap();
+        assert!(matches!(event, DriveEvent::LockGranted { holder: granted, .. } if granted == me));
+        let (_, event) = events.try_recv().unwrap();
+        assert!(matches!(event, DriveEvent::LockQueueChanged { position: None, .. }));
+    }
+
+    #[tokio::test]
+    async fn test_expired_lock_goes_to_next_waiter() {
+        let holder = Identity::generate().node_id();
+        let waiter = Identity::generate().node_id();
+        let manager = LockManager::new(holder);
+        let path = PathBuf::from("shared.txt");
+
+        let drive = manager.get_drive_locks("drive1").await;
+        let mut lock = FileLock::new(path.clone(), holder, LockType::Write);
+        lock.expires_at = Utc::now() - Duration::minutes(1);
+        drive.locks.write().await.insert(path.clone(), vec![lock]);
+        drive.enqueue(path.clone(), waiter, LockType::Advisory).await;
+
+        assert_eq!(manager.cleanup_expired().await, 1);
+        let locks = manager.get_locks("drive1", &path).await;
+        assert!(locks[0].is_held_by(&waiter));
+        assert_eq!(locks[0].lock_type, LockType::Advisory);
+
+        // Nothing to grant while the new lock is held
+        assert!(manager.grant_next("drive1", &path).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_readers_share_and_block_writer() {
+        let reader1 = Identity::generate().node_id();
+        let reader2 = Identity::generate().node_id();
+        let writer = Identity::generate().node_id();
+        let manager = DriveLockManager::new();
+        let path = PathBuf::from("shared.txt");
+
+        for reader in [reader1, reader2] {
+            let result = manager
+                .acquire(path.clone(), reader, LockType::Read, false)
+                .await;
+            assert!(matches!(result, LockResult::Acquired(_)));
+        }
+        assert_eq!(manager.get_locks(&path).await.len(), 2);
+
+        let result = manager
+            .acquire(path.clone(), writer, LockType::Write, false)
+            .await;
+        assert!(matches!(result, LockResult::Denied { .. }));
+
+        // The writer gets the file once the last reader leaves
+        manager.enqueue(path.clone(), writer, LockType::Write).await;
+        manager.release(&path, &reader1).await;
+        assert!(manager.grant_next(&path).await.is_none());
+        manager.release(&path, &reader2).await;
+        let (granted, _) = manager.grant_next(&path).await.unwrap();
+        assert_eq!(granted.len(), 1);
+        assert!(granted[0].is_held_by(&writer));
+        assert_eq!(manager.get_locks(&path).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_writer_is_not_starved_by_readers() {
+        let reader = Identity::generate().node_id();
+        let writer = Identity::generate().node_id();
+        let late1 = Identity::generate().node_id();
+        let late2 = Identity::generate().node_id();
+        let manager = LockManager::new(writer);
+        let path = PathBuf::from("shared.txt");
+        let drive = manager.get_drive_locks("drive1").await;
+
+        drive
+            .acquire(path.clone(), reader, LockType::Read, false)
+            .await;
+        let result = manager
+            .acquire_lock("drive1", path.clone(), LockType::Write, true, false)
+            .await;
+        assert!(matches!(result, LockResult::Queued { position: 1 }));
+
+        // Readers arriving after the writer wait behind it
+        for late in [late1, late2] {
+            let result = drive
+                .acquire(path.clone(), late, LockType::Read, false)
+                .await;
+            assert!(matches!(result, LockResult::Denied { .. }));
+            drive.enqueue(path.clone(), late, LockType::Read).await;
+        }
+
+        manager.remove_remote_lock("drive1", &path, &reader).await;
+        let locks = manager.get_locks("drive1", &path).await;
+        assert_eq!(locks.len(), 1);
+        assert!(locks[0].is_held_by(&writer));
+
+        // Then the queued readers are granted together
+        let granted = manager.grant_next("drive1", &path).await;
+        assert!(granted.is_empty());
+        manager.release_lock("drive1", &path).await;
+        let holders: Vec<NodeId> = manager
+            .get_locks("drive1", &path)
+            .await
+            .iter()
+            .map(|lock| lock.holder)
+            .collect();
+        assert_eq!(holders, vec![late1, late2]);
+        assert!(manager.list_queue("drive1", None).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_lock_upgrade() {
+        let me = Identity::generate().node_id();
+        let other = Identity::generate().node_id();
+        let writer = Identity::generate().node_id();
+        let manager = LockManager::new(me);
+        let path = PathBuf::from("shared.txt");
+        let drive = manager.get_drive_locks("drive1").await;
+
+        // A sole reader upgrades straight away
+        manager
+            .acquire_lock("drive1", path.clone(), LockType::Read, false, false)
+            .await;
+        let result = manager
+            .acquire_lock("drive1", path.clone(), LockType::Write, false, false)
+            .await;
+        assert!(
+            matches!(result, LockResult::Acquired(ref lock) if lock.lock_type == LockType::Write)
+        );
+
+        // With another reader, upgrading fails unless asked to wait, even
+        // with `wait`, since queuing behind others would deadlock
+        manager
+            .acquire_lock("drive1", path.clone(), LockType::Read, false, false)
+            .await;
+        drive
+            .acquire(path.clone(), other, LockType::Read, false)
+            .await;
+        drive.enqueue(path.clone(), writer, LockType::Write).await;
+        let result = manager
+            .acquire_lock("drive1", path.clone(), LockType::Write, true, false)
+            .await;
+        assert!(matches!(result, LockResult::Denied { .. }));
+        assert_eq!(manager.list_queue("drive1", None).await.len(), 1);
+
+        // With `upgrade`, it waits ahead of the queued writer
+        let result = manager
+            .acquire_lock("drive1", path.clone(), LockType::Write, false, true)
+            .await;
+        assert!(matches!(result, LockResult::Queued { position: 1 }));
+        let queue = manager.list_queue("drive1", Some(&path)).await;
+        assert_eq!(queue[1].1.requester, writer);
+
+        // A second upgrade would wait on ours forever
+        let result = drive
+            .acquire(path.clone(), other, LockType::Write, true)
+            .await;
+        assert!(matches!(result, LockResult::Denied { .. }));
+
+        manager.remove_remote_lock("drive1", &path, &other).await;
+        let locks = manager.get_locks("drive1", &path).await;
+        assert_eq!(locks.len(), 1);
+        assert!(locks[0].is_held_by(&me));
+        assert_eq!(locks[0].lock_type, LockType::Write);
+        assert_eq!(
+            manager.list_queue("drive1", None).await[0].1.requester,
+            writer
+        );
     }
 }
@@ -0,0 +1,169 @@
+//! Maintenance windows for background housekeeping
+//!
+//! Operators can restrict expensive housekeeping (pruning old activity and
+//! resolved conflicts, and similar jobs) to off-hours. Outside the window
+//! only essential work runs: sync, transfers, and cleanup that keeps locks,
+//! presence and ACLs correct. With no window configured, nothing is
+//! deferred.
+
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// Setting holding the configured maintenance window
+pub const MAINTENANCE_WINDOW_SETTING: &str = "maintenance_window";
+
+/// Upper bound on the number of ranges in a window
+pub const MAX_MAINTENANCE_RANGES: usize = 8;
+
+/// A daily time range in local time, as `HH:MM:SS`
+///
+/// A range whose end is before its start wraps past midnight, so
+/// `22:00:00`-`06:00:00` covers the night.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeRange {
+    /// Check if a time of day falls inside the range (start inclusive)
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// Time ranges during which heavy background work may run
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    /// Allowed ranges; empty means maintenance may run at any time
+    #[serde(default)]
+    pub ranges: Vec<TimeRange>,
+}
+
+impl MaintenanceWindow {
+    /// True if no restriction is configured
+    pub fn is_unrestricted(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Check if maintenance may run at a time of day
+    pub fn allows(&self, time: NaiveTime) -> bool {
+        self.is_unrestricted() || self.ranges.iter().any(|range| range.contains(time))
+    }
+
+    /// Reject windows that could never open or are unreasonably large
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ranges.len() > MAX_MAINTENANCE_RANGES {
+            return Err(format!("At most {} ranges are allowed", MAX_MAINTENANCE_RANGES));
+        }
+        if let Some(range) = self.ranges.iter().find(|r| r.start == r.end) {
+            return Err(format!("Range starting at {} is empty", range.start));
+        }
+        Ok(())
+    }
+}
+
+/// Current maintenance window and whether it is open
+#[derive(Clone, Debug, Serialize)]
+pub struct MaintenanceStatus {
+    #[serde(flatten)]
+    pub window: MaintenanceWindow,
+    /// True if heavy maintenance may run right now
+    pub open_now: bool,
+}
+
+/// Shared gate that background jobs check before heavy work
+pub struct MaintenanceScheduler {
+    window: RwLock<MaintenanceWindow>,
+}
+
+impl MaintenanceScheduler {
+    pub fn new(window: MaintenanceWindow) -> Self {
+        Self {
+            window: RwLock::new(window),
+        }
+    }
+
+    /// Get the configured window
+    pub fn window(&self) -> MaintenanceWindow {
+        self.window.read().map(|w| w.clone()).unwrap_or_default()
+    }
+
+    /// Replace the configured window
+    pub fn set_window(&self, window: MaintenanceWindow) {
+        if let Ok(mut current) = self.window.write() {
+            *current = window;
+        }
+    }
+
+    /// Check if heavy maintenance may run now
+    pub fn is_open(&self) -> bool {
+        self.window().allows(Local::now().time())
+    }
+
+    /// Get the window together with whether it is open now
+    pub fn status(&self) -> MaintenanceStatus {
+        let window = self.window();
+        let open_now = window.allows(Local::now().time());
+        MaintenanceStatus { window, open_now }
+    }
+}
+
+impl Default for MaintenanceScheduler {
+    fn default() -> Self {
+        Self::new(MaintenanceWindow::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn range(start: NaiveTime, end: NaiveTime) -> TimeRange {
+        TimeRange { start, end }
+    }
+
+    #[test]
+    fn test_unrestricted_by_default() {
+        let window = MaintenanceWindow::default();
+        assert!(window.is_unrestricted());
+        assert!(window.allows(at(12, 0)));
+        assert!(MaintenanceScheduler::default().is_open());
+    }
+
+    #[test]
+    fn test_window_ranges() {
+        let window = MaintenanceWindow {
+            ranges: vec![range(at(22, 0), at(6, 0)), range(at(12, 0), at(13, 0))],
+        };
+        assert!(window.allows(at(23, 30)));
+        assert!(window.allows(at(2, 0)));
+        assert!(window.allows(at(12, 0)));
+        assert!(!window.allows(at(13, 0)));
+        assert!(!window.allows(at(6, 0)));
+        assert!(!window.allows(at(9, 0)));
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut window = MaintenanceWindow {
+            ranges: vec![range(at(1, 0), at(1, 0))],
+        };
+        assert!(window.validate().is_err());
+
+        window.ranges = vec![range(at(1, 0), at(2, 0)); MAX_MAINTENANCE_RANGES + 1];
+        assert!(window.validate().is_err());
+
+        window.ranges.truncate(1);
+        assert!(window.validate().is_ok());
+    }
+}
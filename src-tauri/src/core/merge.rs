@@ -0,0 +1,210 @@
+//! Three-way merge of text files
+//!
+//! Merges two edited versions of a text against their common ancestor line
+//! by line, the way `diff3` does. Regions changed on one side only, or
+//! changed the same way on both, are taken as they are; regions both sides
+//! changed differently are kept with Git-style conflict markers.
+
+/// Line opening a conflicting hunk, followed by the local lines
+pub const MARKER_LOCAL: &str = "<<<<<<< local";
+/// Line between the local and remote lines of a conflicting hunk
+pub const MARKER_SEPARATOR: &str = "=======";
+/// Line closing a conflicting hunk, after the remote lines
+pub const MARKER_REMOTE: &str = ">>>>>>> remote";
+
+/// Largest table computed when diffing a changed region, in cells; bigger
+/// regions are treated as replaced wholesale
+const MAX_DIFF_CELLS: usize = 4 * 1024 * 1024;
+
+/// Merged text and how many hunks still need a person
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextMerge {
+    pub text: String,
+    /// Hunks left with conflict markers
+    pub conflicts: usize,
+}
+
+impl TextMerge {
+    /// Whether both sides merged without conflict markers
+    pub fn is_clean(&self) -> bool {
+        self.conflicts == 0
+    }
+}
+
+/// Merge `local` and `remote` against their common ancestor `base`
+pub fn merge_text(base: &str, local: &str, remote: &str) -> TextMerge {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let local: Vec<&str> = local.split_inclusive('\n').collect();
+    let remote: Vec<&str> = remote.split_inclusive('\n').collect();
+    let to_local = matching_lines(&base, &local);
+    let to_remote = matching_lines(&base, &remote);
+
+    let mut merged = TextMerge {
+        text: String::new(),
+        conflicts: 0,
+    };
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    loop {
+        // Lines neither side touched
+        while i < base.len() && to_local[i] == Some(j) && to_remote[i] == Some(k) {
+            merged.text.push_str(base[i]);
+            i += 1;
+            j += 1;
+            k += 1;
+        }
+        if i == base.len() && j == local.len() && k == remote.len() {
+            break;
+        }
+
+        // The changed region runs up to the next line both sides kept
+        let (next_i, next_j, next_k) = (i..base.len())
+            .find_map(|n| Some((n, to_local[n]?, to_remote[n]?)))
+            .unwrap_or((base.len(), local.len(), remote.len()));
+        let ours = &local[j..next_j];
+        let theirs = &remote[k..next_k];
+        let original = &base[i..next_i];
+
+        if ours == original {
+            merged.text.extend(theirs.iter().copied());
+        } else if theirs == original || ours == theirs {
+            merged.text.extend(ours.iter().copied());
+        } else {
+            merged.conflicts += 1;
+            push_marker(&mut merged.text, MARKER_LOCAL);
+            push_side(&mut merged.text, ours);
+            push_marker(&mut merged.text, MARKER_SEPARATOR);
+            push_side(&mut merged.text, theirs);
+            push_marker(&mut merged.text, MARKER_REMOTE);
+        }
+
+        (i, j, k) = (next_i, next_j, next_k);
+    }
+    merged
+}
+
+fn push_marker(text: &mut String, marker: &str) {
+    text.push_str(marker);
+    text.push('\n');
+}
+
+/// Append one side of a conflict, ending it with a newline so the next
+/// marker starts on its own line
+fn push_side(text: &mut String, lines: &[&str]) {
+    text.extend(lines.iter().copied());
+    if lines.last().is_some_and(|line| !line.ends_with('\n')) {
+        text.push('\n');
+    }
+}
+
+/// For each line of `a`, the line of `b` it is kept as, if any
+///
+/// Matches are increasing on both sides: common prefix and suffix first,
+/// then a longest common subsequence of what is left.
+fn matching_lines(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let mut matched = vec![None; a.len()];
+
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    for (n, slot) in matched.iter_mut().enumerate().take(prefix) {
+        *slot = Some(n);
+    }
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    for n in 1..=suffix {
+        matched[a.len() - n] = Some(b.len() - n);
+    }
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    if a_mid.len().saturating_mul(b_mid.len()) <= MAX_DIFF_CELLS {
+        for (x, y) in longest_common_subsequence(a_mid, b_mid) {
+            matched[prefix + x] = Some(prefix + y);
+        }
+    }
+    matched
+}
+
+/// Index pairs of a longest common subsequence of `a` and `b`
+fn longest_common_subsequence(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    // lengths[x * width + y] is the LCS length of a[x..] and b[y..]
+    let width = b.len() + 1;
+    let mut lengths = vec![0u32; (a.len() + 1) * width];
+    for x in (0..a.len()).rev() {
+        for y in (0..b.len()).rev() {
+            lengths[x * width + y] = if a[x] == b[y] {
+                lengths[(x + 1) * width + y + 1] + 1
+            } else {
+                lengths[(x + 1) * width + y].max(lengths[x * width + y + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    while x < a.len() && y < b.len() {
+        if a[x] == b[y] {
+            pairs.push((x, y));
+            x += 1;
+            y += 1;
+        } else if lengths[(x + 1) * width + y] >= lengths[x * width + y + 1] {
+            x += 1;
+        } else {
+            y += 1;
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "one\ntwo\nthree\nfour\nfive\nsix\n";
+
+    #[test]
+    fn test_separate_changes_merge_cleanly() {
+        let local = "one\nTWO\nthree\nfour\nfive\nsix\n";
+        let remote = "one\ntwo\nthree\nfour\nFIVE\nsix\nseven\n";
+        let merged = merge_text(BASE, local, remote);
+        assert!(merged.is_clean());
+        assert_eq!(merged.text, "one\nTWO\nthree\nfour\nFIVE\nsix\nseven\n");
+
+        // Same edit on both sides, and one-sided deletion
+        let local = "one\ntwo\nthree\n4\nfive\nsix\n";
+        let remote = "two\nthree\n4\nfive\nsix\n";
+        let merged = merge_text(BASE, local, remote);
+        assert!(merged.is_clean());
+        assert_eq!(merged.text, "two\nthree\n4\nfive\nsix\n");
+    }
+
+    #[test]
+    fn test_overlapping_changes_get_markers() {
+        let local = "one\ntwo\nthree (local)\nfour\nfive\nsix\n";
+        let remote = "one\ntwo\nthree (remote)\nfour\nfive\nSIX\n";
+        let merged = merge_text(BASE, local, remote);
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(
+            merged.text,
+            "one\ntwo\n\
+             <<<<<<< local\nthree (local)\n=======\nthree (remote)\n>>>>>>> remote\n\
+             four\nfive\nSIX\n"
+        );
+    }
+
+    #[test]
+    fn test_missing_final_newline() {
+        let merged = merge_text("a\nb", "a\nb\nlocal", "a\nb\nremote");
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< local\nb\nlocal\n=======\nb\nremote\n>>>>>>> remote\n"
+        );
+
+        let merged = merge_text("a\nb", "A\nb", "a\nb");
+        assert!(merged.is_clean());
+        assert_eq!(merged.text, "A\nb");
+    }
+}
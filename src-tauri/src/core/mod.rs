@@ -4,30 +4,72 @@ pub mod channel;
 pub mod cleanup;
 #[allow(dead_code)]
 pub mod conflict;
+pub mod consistency;
+pub mod content_type;
 pub mod drive;
 pub mod error;
 pub mod events;
 pub mod file;
+pub mod fs_errors;
+pub mod gap;
 pub mod identity;
+pub mod ignore;
 #[allow(dead_code)]
 pub mod locking;
+pub mod maintenance;
+pub mod merge;
+pub mod notifications;
+pub mod operations;
+pub mod power;
 #[allow(dead_code)]
 pub mod presence;
 pub mod rate_limit;
+pub mod receipts;
+pub mod subscriptions;
+pub mod sync_filter;
+pub mod timeline;
 pub mod validation;
 pub mod watcher;
 
 pub use audit::{AuditEntryDto, AuditFilter, AuditLogger};
-pub use channel::send_with_backpressure;
+pub use channel::{send_with_backpressure, BackpressureStrategy, Coalescer};
 pub use cleanup::CleanupManager;
-pub use conflict::{ConflictManager, FileConflictDto, ResolutionStrategy};
-pub use drive::{DriveId, DriveInfo, SharedDrive};
+pub use conflict::{
+    ConflictManager, ConflictPolicy, ConflictSensitivity, FileConflictDto, MergeResult,
+    ResolutionStrategy,
+};
+pub use consistency::{ConsistencyReport, InconsistencyKind};
+pub use drive::{DeletedDriveInfo, DriveId, DriveInfo, NewFilePolicy, SharedDrive};
 pub use error::AppError;
-pub use events::{DriveEvent, DriveEventDto, SignedGossipMessage};
+pub use events::{
+    protocol_incompatibility, Capability, DriveEvent, DriveEventDto, PeerCapabilities,
+    SignedGossipMessage, EVENT_TYPES, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
 pub use file::FileEntryDto;
+pub use fs_errors::{FsOperation, PermissionError, PermissionErrors};
+pub use gap::{MetadataSummary, PeerGap};
 pub use identity::IdentityManager;
-pub use locking::{FileLock, FileLockDto, LockManager, LockResult, LockType};
-pub use presence::{ActivityEntryDto, PresenceManager, UserPresenceDto};
+pub use ignore::IgnoreRules;
+pub use locking::{FileLock, FileLockDto, LockManager, LockQueueEntryDto, LockResult, LockType};
+pub use maintenance::{
+    MaintenanceScheduler, MaintenanceStatus, MaintenanceWindow, MAINTENANCE_WINDOW_SETTING,
+};
+pub use notifications::{notification_body, NotificationCategory, NotificationPrefs};
+pub use operations::{OperationInfo, OperationRegistry};
+pub use power::{PowerMonitor, PowerStatus, SYNC_ONLY_ON_AC_SETTING};
+pub use presence::{
+    ActivityEntryDto, ActivityRetention, PresenceManager, UserPresenceDto,
+    DEFAULT_PRESENCE_TIMEOUT_SECS,
+    MAX_PRESENCE_TIMEOUT_SECS, MIN_PRESENCE_TIMEOUT_SECS, PRESENCE_TIMEOUT_SETTING,
+};
 pub use rate_limit::{RateLimiter, SharedRateLimiter};
-pub use validation::{validate_drive_id, validate_name, validate_path};
-pub use watcher::FileWatcherManager;
+pub use receipts::{FileReader, ReadReceiptStore};
+pub use subscriptions::{DriveSubscriptions, EventFilter, EventSubscriptions};
+pub use sync_filter::SyncFilters;
+pub use timeline::{sort_timeline, TimelineEntry};
+pub use validation::{
+    validate_custom_metadata, validate_drive_id, validate_name, validate_path, NamePolicy,
+};
+pub use watcher::{
+    FileWatcherManager, WatchConfig, WatchMode, WatchStatus, WATCH_CONFIG_SETTING,
+};
@@ -0,0 +1,140 @@
+//! Per-drive notification preferences
+//!
+//! Each drive decides which kinds of events raise a system notification.
+//! The default is quiet: conflicts and errors notify, routine activity
+//! (file changes, joins, locks, completed syncs) does not.
+
+use crate::core::DriveEventDto;
+use serde::{Deserialize, Serialize};
+
+/// Kind of event a notification is raised for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    /// Members joining or leaving, accepted invites
+    Joins,
+    Conflicts,
+    Errors,
+    /// A file finished syncing
+    SyncCompleted,
+    /// Files changed or deleted by peers
+    FileChanges,
+    /// Locks acquired, released, or queue changes
+    Locks,
+}
+
+impl NotificationCategory {
+    /// Category for a drive event type, if it can notify at all
+    pub fn for_event_type(event_type: &str) -> Option<Self> {
+        match event_type {
+            "UserJoined" | "UserLeft" | "InviteAccepted" => Some(Self::Joins),
+            "SyncComplete" => Some(Self::SyncCompleted),
+            "FileChanged" | "FileDeleted" => Some(Self::FileChanges),
+            "FileLockAcquired" | "FileLockReleased" | "LockGranted" | "LockQueueChanged" => {
+                Some(Self::Locks)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Which categories raise notifications for a drive
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationPrefs {
+    pub joins: bool,
+    pub conflicts: bool,
+    pub errors: bool,
+    pub sync_completed: bool,
+    pub file_changes: bool,
+    pub locks: bool,
+}
+
+impl Default for NotificationPrefs {
+    fn default() -> Self {
+        Self {
+            joins: false,
+            conflicts: true,
+            errors: true,
+            sync_completed: false,
+            file_changes: false,
+            locks: false,
+        }
+    }
+}
+
+impl NotificationPrefs {
+    /// Check if a category is enabled
+    pub fn allows(&self, category: NotificationCategory) -> bool {
+        match category {
+            NotificationCategory::Joins => self.joins,
+            NotificationCategory::Conflicts => self.conflicts,
+            NotificationCategory::Errors => self.errors,
+            NotificationCategory::SyncCompleted => self.sync_completed,
+            NotificationCategory::FileChanges => self.file_changes,
+            NotificationCategory::Locks => self.locks,
+        }
+    }
+}
+
+/// Notification body for a drive event, or None if it never notifies
+pub fn notification_body(event: &DriveEventDto) -> Option<String> {
+    NotificationCategory::for_event_type(&event.event_type)?;
+
+    let path = event
+        .payload
+        .get(&event.event_type)
+        .and_then(|fields| fields.get("path"))
+        .and_then(|path| path.as_str());
+
+    let body = match (event.event_type.as_str(), path) {
+        ("UserJoined", _) => "A member came online".to_string(),
+        ("UserLeft", _) => "A member went offline".to_string(),
+        ("InviteAccepted", _) => "An invite was accepted".to_string(),
+        ("SyncComplete", Some(path)) => format!("Synced {}", path),
+        ("FileChanged", Some(path)) => format!("{} was updated", path),
+        ("FileDeleted", Some(path)) => format!("{} was deleted", path),
+        ("FileLockAcquired", Some(path)) => format!("{} was locked", path),
+        ("FileLockReleased", Some(path)) => format!("{} was unlocked", path),
+        ("LockGranted", Some(path)) => format!("{} was locked for the next in line", path),
+        ("LockQueueChanged", Some(path)) => format!("Lock queue for {} changed", path),
+        (event_type, _) => event_type.to_string(),
+    };
+    Some(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_prefs_are_quiet() {
+        let prefs = NotificationPrefs::default();
+        assert!(prefs.allows(NotificationCategory::Conflicts));
+        assert!(prefs.allows(NotificationCategory::Errors));
+        assert!(!prefs.allows(NotificationCategory::FileChanges));
+        assert!(!prefs.allows(NotificationCategory::Joins));
+
+        // Fields missing from stored prefs fall back to the defaults
+        let partial: NotificationPrefs = serde_json::from_str(r#"{"joins":true}"#).unwrap();
+        assert!(partial.joins);
+        assert!(partial.conflicts);
+    }
+
+    #[test]
+    fn test_notification_body() {
+        let event = DriveEventDto {
+            drive_id: "drive1".to_string(),
+            event_type: "FileDeleted".to_string(),
+            payload: serde_json::json!({ "FileDeleted": { "path": "docs/a.txt" } }),
+            timestamp: String::new(),
+        };
+        assert_eq!(notification_body(&event).as_deref(), Some("docs/a.txt was deleted"));
+
+        let progress = DriveEventDto {
+            event_type: "SyncProgress".to_string(),
+            ..event
+        };
+        assert_eq!(notification_body(&progress), None);
+    }
+}
@@ -0,0 +1,182 @@
+//! Cancellation for long-running commands
+//!
+//! A long command registers an operation under an ID (supplied by the
+//! frontend, or generated and visible through `list_operations`) and checks
+//! its token at safe points. `cancel_operation` trips the token; the command
+//! stops at its next checkpoint and returns what it finished so far.
+
+use crate::core::AppError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+/// Longest operation ID accepted from the frontend
+const MAX_OPERATION_ID_LEN: usize = 64;
+
+/// A running operation as reported to the frontend
+#[derive(Clone, Debug, Serialize)]
+pub struct OperationInfo {
+    pub id: String,
+    /// Command that started it (e.g. "merge_drives")
+    pub kind: String,
+    pub started_at: DateTime<Utc>,
+    pub cancelled: bool,
+}
+
+struct RunningOperation {
+    info: OperationInfo,
+    token: CancellationToken,
+}
+
+/// Registry of cancellable operations
+#[derive(Default)]
+pub struct OperationRegistry {
+    operations: Arc<Mutex<HashMap<String, RunningOperation>>>,
+}
+
+impl OperationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register an operation, generating an ID if none is given
+    ///
+    /// The operation is unregistered when the returned guard is dropped.
+    pub fn start(&self, id: Option<String>, kind: &str) -> Result<OperationGuard, AppError> {
+        let id = match id {
+            Some(id) => {
+                if id.is_empty()
+                    || id.len() > MAX_OPERATION_ID_LEN
+                    || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                {
+                    return Err(AppError::ValidationFailed {
+                        field: "operation_id".to_string(),
+                        reason: format!(
+                            "must be 1-{} letters, digits, '-' or '_'",
+                            MAX_OPERATION_ID_LEN
+                        ),
+                    });
+                }
+                id
+            }
+            None => format!("op_{}", hex::encode(rand::random::<[u8; 8]>())),
+        };
+
+        let token = CancellationToken::new();
+        let mut operations = self.lock();
+        if operations.contains_key(&id) {
+            return Err(AppError::ValidationFailed {
+                field: "operation_id".to_string(),
+                reason: format!("operation {} is already running", id),
+            });
+        }
+        operations.insert(
+            id.clone(),
+            RunningOperation {
+                info: OperationInfo {
+                    id: id.clone(),
+                    kind: kind.to_string(),
+                    started_at: Utc::now(),
+                    cancelled: false,
+                },
+                token: token.clone(),
+            },
+        );
+
+        tracing::debug!(operation_id = %id, kind = kind, "Operation started");
+        Ok(OperationGuard {
+            id,
+            token,
+            operations: self.operations.clone(),
+        })
+    }
+
+    /// Signal an operation to stop; returns false if it is not running
+    pub fn cancel(&self, id: &str) -> bool {
+        let mut operations = self.lock();
+        let Some(operation) = operations.get_mut(id) else {
+            return false;
+        };
+        operation.info.cancelled = true;
+        operation.token.cancel();
+        tracing::info!(operation_id = %id, kind = %operation.info.kind, "Operation cancelled");
+        true
+    }
+
+    /// List running operations, oldest first
+    pub fn list(&self) -> Vec<OperationInfo> {
+        let mut infos: Vec<OperationInfo> =
+            self.lock().values().map(|op| op.info.clone()).collect();
+        infos.sort_by(|a, b| a.started_at.cmp(&b.started_at).then(a.id.cmp(&b.id)));
+        infos
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, RunningOperation>> {
+        self.operations.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Registration of one running operation; unregisters it when dropped
+pub struct OperationGuard {
+    id: String,
+    token: CancellationToken,
+    operations: Arc<Mutex<HashMap<String, RunningOperation>>>,
+}
+
+impl OperationGuard {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Token for checks on other threads (e.g. inside `spawn_blocking`)
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        let mut operations = self.operations.lock().unwrap_or_else(|e| e.into_inner());
+        operations.remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_running_operation() {
+        let registry = OperationRegistry::new();
+        let guard = registry.start(Some("merge-1".to_string()), "merge_drives").unwrap();
+        assert!(!guard.is_cancelled());
+
+        // IDs are unique while running
+        assert!(registry.start(Some("merge-1".to_string()), "merge_drives").is_err());
+
+        assert!(registry.cancel("merge-1"));
+        assert!(guard.is_cancelled());
+        assert!(registry.list()[0].cancelled);
+
+        drop(guard);
+        assert!(registry.list().is_empty());
+        assert!(!registry.cancel("merge-1"));
+    }
+
+    #[test]
+    fn test_operation_ids() {
+        let registry = OperationRegistry::new();
+        let generated = registry.start(None, "check").unwrap();
+        assert!(generated.id().starts_with("op_"));
+
+        assert!(registry.start(Some(String::new()), "check").is_err());
+        assert!(registry.start(Some("bad id!".to_string()), "check").is_err());
+        assert!(registry.start(Some("a".repeat(65)), "check").is_err());
+    }
+}
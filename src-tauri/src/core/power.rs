@@ -0,0 +1,235 @@
+//! Power-aware sync
+//!
+//! With `sync_only_on_ac` enabled, network sync and peer transfers are
+//! paused while the machine runs on battery and resumed once it is plugged
+//! in again. The power source is polled, since there is no portable change
+//! notification; where it cannot be determined, sync is left running.
+
+use crate::network::{FileTransferManager, SyncEngine};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::time::{interval, Duration};
+
+/// Setting that pauses sync while on battery
+pub const SYNC_ONLY_ON_AC_SETTING: &str = "sync_only_on_ac";
+
+/// How often the power source is re-checked
+const POWER_POLL_INTERVAL_SECS: u64 = 30;
+
+/// Where the machine is drawing power from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    /// Power status is not available on this platform
+    Unknown,
+}
+
+/// Current power state as seen by the monitor
+#[derive(Clone, Debug, Serialize)]
+pub struct PowerStatus {
+    pub sync_only_on_ac: bool,
+    pub power_source: PowerSource,
+    /// True if sync is currently paused because of the power source
+    pub paused_on_battery: bool,
+}
+
+/// Pauses and resumes sync as the power source changes
+pub struct PowerMonitor {
+    enabled: AtomicBool,
+    paused_on_battery: AtomicBool,
+    /// Set once the "power status unavailable" warning has been logged
+    warned_unknown: AtomicBool,
+    sync_engine: Option<Arc<SyncEngine>>,
+    file_transfer: Option<Arc<FileTransferManager>>,
+}
+
+impl PowerMonitor {
+    pub fn new(
+        enabled: bool,
+        sync_engine: Option<Arc<SyncEngine>>,
+        file_transfer: Option<Arc<FileTransferManager>>,
+    ) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+            paused_on_battery: AtomicBool::new(false),
+            warned_unknown: AtomicBool::new(false),
+            sync_engine,
+            file_transfer,
+        }
+    }
+
+    /// Start polling the power source
+    pub fn start(self: &Arc<Self>) -> tauri::async_runtime::JoinHandle<()> {
+        let monitor = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut ticker = interval(Duration::from_secs(POWER_POLL_INTERVAL_SECS));
+            loop {
+                ticker.tick().await;
+                monitor.check().await;
+            }
+        })
+    }
+
+    /// Enable or disable pausing on battery, applying the change immediately
+    pub async fn set_enabled(&self, enabled: bool) -> PowerStatus {
+        self.enabled.store(enabled, Ordering::SeqCst);
+        self.check().await
+    }
+
+    /// Re-check the power source and pause or resume sync to match
+    pub async fn check(&self) -> PowerStatus {
+        let enabled = self.enabled.load(Ordering::SeqCst);
+        let source = tokio::task::spawn_blocking(current_power_source)
+            .await
+            .unwrap_or(PowerSource::Unknown);
+
+        if enabled
+            && source == PowerSource::Unknown
+            && !self.warned_unknown.swap(true, Ordering::SeqCst)
+        {
+            tracing::warn!("Power status unavailable; sync_only_on_ac has no effect");
+        }
+
+        let should_pause = enabled && source == PowerSource::Battery;
+        if should_pause != self.paused_on_battery.load(Ordering::SeqCst) {
+            self.apply_pause(should_pause).await;
+        }
+
+        PowerStatus {
+            sync_only_on_ac: enabled,
+            power_source: source,
+            paused_on_battery: should_pause,
+        }
+    }
+
+    async fn apply_pause(&self, pause: bool) {
+        if let Some(ref sync_engine) = self.sync_engine {
+            if pause {
+                sync_engine.pause_all().await;
+            } else {
+                sync_engine.resume_all().await;
+            }
+        }
+        if let Some(ref file_transfer) = self.file_transfer {
+            file_transfer.set_paused(pause);
+        }
+        self.paused_on_battery.store(pause, Ordering::SeqCst);
+
+        if pause {
+            tracing::info!("On battery power, pausing sync");
+        } else {
+            tracing::info!("Sync resumed (on AC power or sync_only_on_ac disabled)");
+        }
+    }
+}
+
+/// Determine the current power source from the OS
+#[cfg(target_os = "linux")]
+pub fn current_power_source() -> PowerSource {
+    let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+        return PowerSource::Unknown;
+    };
+
+    let supplies: Vec<(String, Option<bool>)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let kind = std::fs::read_to_string(dir.join("type")).ok()?;
+            let online = std::fs::read_to_string(dir.join("online"))
+                .ok()
+                .map(|v| v.trim() == "1");
+            Some((kind.trim().to_string(), online))
+        })
+        .collect();
+
+    classify_linux_supplies(&supplies)
+}
+
+/// Determine the current power source from the OS
+#[cfg(target_os = "macos")]
+pub fn current_power_source() -> PowerSource {
+    std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| parse_pmset(&String::from_utf8_lossy(&output.stdout)))
+        .unwrap_or(PowerSource::Unknown)
+}
+
+/// Determine the current power source from the OS
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn current_power_source() -> PowerSource {
+    PowerSource::Unknown
+}
+
+/// Classify `/sys/class/power_supply` entries as (type, online)
+///
+/// A machine without a battery is always on AC. With a battery, any online
+/// mains or USB supply means AC.
+#[cfg(any(target_os = "linux", test))]
+fn classify_linux_supplies(supplies: &[(String, Option<bool>)]) -> PowerSource {
+    if !supplies.iter().any(|(kind, _)| kind == "Battery") {
+        return PowerSource::Ac;
+    }
+
+    let on_ac = supplies
+        .iter()
+        .any(|(kind, online)| (kind == "Mains" || kind == "USB") && *online == Some(true));
+    if on_ac {
+        PowerSource::Ac
+    } else {
+        PowerSource::Battery
+    }
+}
+
+/// Parse `pmset -g batt` output ("Now drawing from 'AC Power'")
+#[cfg(any(target_os = "macos", test))]
+fn parse_pmset(output: &str) -> PowerSource {
+    let first = output.lines().next().unwrap_or_default();
+    if first.contains("'AC Power'") {
+        PowerSource::Ac
+    } else if first.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supply(kind: &str, online: Option<bool>) -> (String, Option<bool>) {
+        (kind.to_string(), online)
+    }
+
+    #[test]
+    fn test_classify_linux_supplies() {
+        assert_eq!(classify_linux_supplies(&[]), PowerSource::Ac);
+        assert_eq!(
+            classify_linux_supplies(&[supply("Mains", Some(true)), supply("Battery", None)]),
+            PowerSource::Ac
+        );
+        assert_eq!(
+            classify_linux_supplies(&[supply("Mains", Some(false)), supply("Battery", None)]),
+            PowerSource::Battery
+        );
+        assert_eq!(
+            classify_linux_supplies(&[supply("Battery", None)]),
+            PowerSource::Battery
+        );
+    }
+
+    #[test]
+    fn test_parse_pmset() {
+        let ac = "Now drawing from 'AC Power'\n -InternalBattery-0\t100%; charged;";
+        let battery = "Now drawing from 'Battery Power'\n -InternalBattery-0\t80%; discharging;";
+        assert_eq!(parse_pmset(ac), PowerSource::Ac);
+        assert_eq!(parse_pmset(battery), PowerSource::Battery);
+        assert_eq!(parse_pmset(""), PowerSource::Unknown);
+    }
+}
@@ -3,13 +3,78 @@
 //! Tracks which users are currently connected to a drive and
 //! maintains an activity log of recent changes.
 
+use crate::core::{send_with_backpressure, AppError, DriveEvent};
 use crate::crypto::NodeId;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+
+/// Settings key for the presence idle timeout (seconds)
+pub const PRESENCE_TIMEOUT_SETTING: &str = "presence_timeout_secs";
+
+/// Default idle timeout: three missed 30s heartbeats
+pub const DEFAULT_PRESENCE_TIMEOUT_SECS: u64 = 90;
+
+/// Shortest configurable idle timeout
+pub const MIN_PRESENCE_TIMEOUT_SECS: u64 = 30;
+
+/// Longest configurable idle timeout (1 hour)
+pub const MAX_PRESENCE_TIMEOUT_SECS: u64 = 3600;
+
+/// Most activity entries a drive can keep
+///
+/// Also a hard cap enforced as entries are added, so a drive can't grow
+/// past it between cleanup runs.
+pub const MAX_ACTIVITY_ENTRIES: usize = 10_000;
+
+/// Longest a drive can keep activity entries (in days)
+pub const MAX_ACTIVITY_AGE_DAYS: u32 = 365;
+
+/// How much of a drive's activity feed is kept
+///
+/// Enforced by the cleanup job; the oldest entries are pruned first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityRetention {
+    /// Most entries kept
+    pub max_entries: usize,
+    /// Entries older than this many days are pruned (None = the cleanup
+    /// job's default)
+    pub max_age_days: Option<u32>,
+}
+
+impl Default for ActivityRetention {
+    fn default() -> Self {
+        Self {
+            max_entries: 1000,
+            max_age_days: None,
+        }
+    }
+}
+
+impl ActivityRetention {
+    /// Check the limits are within bounds
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.max_entries == 0 || self.max_entries > MAX_ACTIVITY_ENTRIES {
+            return Err(AppError::ValidationFailed {
+                field: "max_entries".to_string(),
+                reason: format!("must be between 1 and {}", MAX_ACTIVITY_ENTRIES),
+            });
+        }
+        if let Some(days) = self.max_age_days {
+            if days == 0 || days > MAX_ACTIVITY_AGE_DAYS {
+                return Err(AppError::ValidationFailed {
+                    field: "max_age_days".to_string(),
+                    reason: format!("must be between 1 and {}", MAX_ACTIVITY_AGE_DAYS),
+                });
+            }
+        }
+        Ok(())
+    }
+}
 
 /// User presence status
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -194,17 +259,14 @@ pub struct DrivePresenceManager {
     /// Connected users
     users: RwLock<HashMap<NodeId, UserPresence>>,
     /// Activity feed (newest first)
-    activities: RwLock<Vec<ActivityEntry>>,
-    /// Max activities to keep
-    max_activities: usize,
+    activities: RwLock<VecDeque<ActivityEntry>>,
 }
 
 impl DrivePresenceManager {
     pub fn new() -> Self {
         Self {
             users: RwLock::new(HashMap::new()),
-            activities: RwLock::new(Vec::new()),
-            max_activities: 200,
+            activities: RwLock::new(VecDeque::new()),
         }
     }
 
@@ -257,14 +319,29 @@ impl DrivePresenceManager {
     }
 
     /// Add an activity entry
+    ///
+    /// Only the hard cap is enforced here; retention is applied by the
+    /// cleanup job so recording stays cheap.
     pub async fn add_activity(&self, entry: ActivityEntry) {
         let mut activities = self.activities.write().await;
-        activities.insert(0, entry);
+        activities.push_front(entry);
+        if activities.len() > MAX_ACTIVITY_ENTRIES {
+            activities.pop_back();
+        }
+    }
 
-        // Trim to max
-        if activities.len() > self.max_activities {
-            activities.truncate(self.max_activities);
+    /// Prune activities older than `cutoff` and beyond `max_entries`
+    ///
+    /// Returns the number of entries removed.
+    pub async fn prune_activities(&self, cutoff: DateTime<Utc>, max_entries: usize) -> usize {
+        let mut activities = self.activities.write().await;
+        let before = activities.len();
+        // Newest first, so old entries are all at the back
+        while activities.back().is_some_and(|a| a.timestamp <= cutoff) {
+            activities.pop_back();
         }
+        activities.truncate(max_entries);
+        before - activities.len()
     }
 
     /// Get recent activities
@@ -295,6 +372,32 @@ impl DrivePresenceManager {
             .collect()
     }
 
+    /// Remove users not seen since `cutoff`
+    ///
+    /// Covers peers that vanished without sending a leave. Returns the
+    /// removed users.
+    pub async fn remove_timed_out(&self, cutoff: DateTime<Utc>) -> Vec<NodeId> {
+        let mut users = self.users.write().await;
+        let timed_out: Vec<NodeId> = users
+            .values()
+            .filter(|user| user.last_seen < cutoff)
+            .map(|user| user.node_id)
+            .collect();
+        for node_id in &timed_out {
+            users.remove(node_id);
+        }
+        drop(users);
+
+        for node_id in &timed_out {
+            self.add_activity(
+                ActivityEntry::new(ActivityType::UserLeft, *node_id)
+                    .with_details("Timed out".to_string()),
+            )
+            .await;
+        }
+        timed_out
+    }
+
     /// Check and update idle users
     pub async fn check_idle_users(&self) {
         let mut users = self.users.write().await;
@@ -316,16 +419,45 @@ pub struct PresenceManager {
     drives: RwLock<HashMap<String, Arc<DrivePresenceManager>>>,
     /// Our node ID
     node_id: NodeId,
+    /// Seconds without a heartbeat before a user is marked offline
+    timeout_secs: AtomicU64,
+    /// Channel for presence changes (drive ID hex, event)
+    event_tx: broadcast::Sender<(String, DriveEvent)>,
 }
 
 impl PresenceManager {
     pub fn new(node_id: NodeId) -> Self {
+        let (event_tx, _) = broadcast::channel(256);
         Self {
             drives: RwLock::new(HashMap::new()),
             node_id,
+            timeout_secs: AtomicU64::new(DEFAULT_PRESENCE_TIMEOUT_SECS),
+            event_tx,
         }
     }
 
+    /// Subscribe to presence changes
+    pub fn subscribe(&self) -> broadcast::Receiver<(String, DriveEvent)> {
+        self.event_tx.subscribe()
+    }
+
+    /// Get the idle timeout in seconds
+    pub fn timeout_secs(&self) -> u64 {
+        self.timeout_secs.load(Ordering::Relaxed)
+    }
+
+    /// Set the idle timeout in seconds
+    ///
+    /// Returns false (leaving the timeout unchanged) if `secs` is outside
+    /// [`MIN_PRESENCE_TIMEOUT_SECS`]..=[`MAX_PRESENCE_TIMEOUT_SECS`].
+    pub fn set_timeout_secs(&self, secs: u64) -> bool {
+        if !(MIN_PRESENCE_TIMEOUT_SECS..=MAX_PRESENCE_TIMEOUT_SECS).contains(&secs) {
+            return false;
+        }
+        self.timeout_secs.store(secs, Ordering::Relaxed);
+        true
+    }
+
     /// Get or create presence manager for a drive
     pub async fn get_drive_presence(&self, drive_id: &str) -> Arc<DrivePresenceManager> {
         {
@@ -377,15 +509,68 @@ impl PresenceManager {
         manager.add_activity(entry).await;
     }
 
-    /// Cleanup old activities across all drives
-    pub async fn cleanup_old_activities(&self, cutoff: DateTime<Utc>) -> usize {
-        let drives = self.drives.read().await;
+    /// Apply each drive's activity retention
+    ///
+    /// `retention` is keyed by drive ID hex; drives without an entry use the
+    /// default. `default_max_age` applies where no age is set. Drives are
+    /// pruned one at a time so recording on other drives isn't held up.
+    pub async fn prune_activities(
+        &self,
+        retention: &HashMap<String, ActivityRetention>,
+        default_max_age: Duration,
+    ) -> usize {
+        let drives: Vec<(String, Arc<DrivePresenceManager>)> = self
+            .drives
+            .read()
+            .await
+            .iter()
+            .map(|(id, manager)| (id.clone(), manager.clone()))
+            .collect();
+
+        let now = Utc::now();
         let mut total = 0;
-        for manager in drives.values() {
-            let mut activities = manager.activities.write().await;
-            let before = activities.len();
-            activities.retain(|a| a.timestamp > cutoff);
-            total += before - activities.len();
+        for (drive_id, manager) in drives {
+            let policy = retention.get(&drive_id).copied().unwrap_or_default();
+            let max_age = policy
+                .max_age_days
+                .map(|days| Duration::days(days as i64))
+                .unwrap_or(default_max_age);
+            total += manager.prune_activities(now - max_age, policy.max_entries).await;
+        }
+        total
+    }
+
+    /// Mark users with no heartbeat within the idle timeout as offline
+    ///
+    /// Timed-out users are removed from their drives and a `UserLeft` event
+    /// is emitted for each. Returns the number of users removed.
+    pub async fn expire_timed_out(&self) -> usize {
+        let cutoff = Utc::now() - Duration::seconds(self.timeout_secs() as i64);
+        let drives: Vec<(String, Arc<DrivePresenceManager>)> = self
+            .drives
+            .read()
+            .await
+            .iter()
+            .map(|(id, manager)| (id.clone(), manager.clone()))
+            .collect();
+
+        let mut total = 0;
+        for (drive_id, manager) in drives {
+            for user in manager.remove_timed_out(cutoff).await {
+                tracing::debug!(drive_id = %drive_id, user = %user, "Presence timed out");
+                let event = DriveEvent::UserLeft {
+                    user,
+                    timestamp: Utc::now(),
+                };
+                let strategy = event.backpressure_strategy(None);
+                send_with_backpressure(
+                    &self.event_tx,
+                    (drive_id.clone(), event),
+                    "presence",
+                    strategy,
+                );
+                total += 1;
+            }
         }
         total
     }
@@ -445,4 +630,67 @@ mod tests {
         assert_eq!(activities.len(), 1);
         assert!(matches!(activities[0].activity_type, ActivityType::FileCreated));
     }
+
+    #[tokio::test]
+    async fn test_activity_retention() {
+        let node_id = Identity::generate().node_id();
+        let presence = PresenceManager::new(node_id);
+        let manager = presence.get_drive_presence("busy").await;
+
+        for i in 0..10 {
+            let mut entry = ActivityEntry::new(ActivityType::FileModified, node_id);
+            entry.timestamp = Utc::now() - Duration::days(10 - i);
+            manager.add_activity(entry).await;
+        }
+        let newest = manager.recent_activities(1).await[0].id.clone();
+
+        // Keep at most 3 entries from the last 5 days
+        let retention = HashMap::from([(
+            "busy".to_string(),
+            ActivityRetention {
+                max_entries: 3,
+                max_age_days: Some(5),
+            },
+        )]);
+        assert_eq!(presence.prune_activities(&retention, Duration::weeks(1)).await, 7);
+
+        let kept = manager.recent_activities(10).await;
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept[0].id, newest);
+
+        assert!(ActivityRetention::default().validate().is_ok());
+        let too_many = ActivityRetention {
+            max_entries: MAX_ACTIVITY_ENTRIES + 1,
+            max_age_days: None,
+        };
+        assert!(too_many.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_presence_timeout() {
+        let active = Identity::generate().node_id();
+        let vanished = Identity::generate().node_id();
+
+        let presence = PresenceManager::new(active);
+        assert!(!presence.set_timeout_secs(MIN_PRESENCE_TIMEOUT_SECS - 1));
+        assert_eq!(presence.timeout_secs(), DEFAULT_PRESENCE_TIMEOUT_SECS);
+        let mut events = presence.subscribe();
+
+        let manager = presence.get_drive_presence("drive").await;
+        manager.user_joined(active).await;
+        manager.user_joined(vanished).await;
+        {
+            let mut users = manager.users.write().await;
+            users.get_mut(&vanished).unwrap().last_seen = Utc::now() - Duration::minutes(5);
+        }
+
+        assert_eq!(presence.expire_timed_out().await, 1);
+        let online = presence.get_online_users("drive").await;
+        assert_eq!(online.len(), 1);
+        assert_eq!(online[0].node_id, active);
+
+        let (drive_id, event) = events.try_recv().unwrap();
+        assert_eq!(drive_id, "drive");
+        assert!(matches!(event, DriveEvent::UserLeft { user, .. } if user == vanished));
+    }
 }
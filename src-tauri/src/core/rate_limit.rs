@@ -53,6 +53,11 @@ impl RateLimitConfig {
     pub fn drive_creation() -> Self {
         Self::new(5, 5.0 / 60.0)
     }
+
+    /// Preset for peers' file summary requests (30 per minute)
+    pub fn summary_request() -> Self {
+        Self::new(30, 30.0 / 60.0)
+    }
 }
 
 impl Default for RateLimitConfig {
@@ -142,12 +147,12 @@ pub enum RateLimitOperation {
     InviteGeneration,
     #[allow(dead_code)]
     FileUpload,
-    #[allow(dead_code)]
     FileDownload,
     #[allow(dead_code)]
     DriveCreation,
     #[allow(dead_code)]
     GeneralApi,
+    SummaryRequest,
     #[allow(dead_code)]
     Custom(String),
 }
@@ -159,6 +164,7 @@ impl RateLimitOperation {
             Self::FileUpload => RateLimitConfig::file_upload(),
             Self::FileDownload => RateLimitConfig::file_download(),
             Self::DriveCreation => RateLimitConfig::drive_creation(),
+            Self::SummaryRequest => RateLimitConfig::summary_request(),
             Self::GeneralApi | Self::Custom(_) => RateLimitConfig::general_api(),
         }
     }
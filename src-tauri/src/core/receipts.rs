@@ -0,0 +1,149 @@
+//! Read receipts for shared files
+//!
+//! When a drive has read receipts enabled, members announce a signed
+//! `FileRead` event when they open a file. The drive owner records these so
+//! they can see who has read sensitive documents. Receipts are opt-in per
+//! drive on every node: a member that turns them off never sends any.
+
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// A member who has read a file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileReader {
+    /// Reader's node ID (hex)
+    pub node_id: String,
+    pub first_read_at: DateTime<Utc>,
+    pub last_read_at: DateTime<Utc>,
+    pub read_count: u32,
+}
+
+/// Receipts for one drive, keyed by relative file path
+type DriveReceipts = HashMap<String, Vec<FileReader>>;
+
+/// Persistent store of read receipts received for owned drives
+pub struct ReadReceiptStore {
+    db: Arc<Database>,
+    /// Receipts keyed by drive ID (hex string)
+    receipts: RwLock<HashMap<String, DriveReceipts>>,
+}
+
+impl ReadReceiptStore {
+    /// Create a new store with database persistence
+    pub fn new(db: Arc<Database>) -> Self {
+        Self {
+            db,
+            receipts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Load all persisted receipts from database
+    pub fn load_from_db(&self) -> Result<(), String> {
+        let entries = self.db.list_read_receipts().map_err(|e| e.to_string())?;
+        let mut guard = self.receipts.blocking_write();
+        for (drive_id, data) in entries {
+            match serde_json::from_slice::<DriveReceipts>(&data) {
+                Ok(receipts) => {
+                    guard.insert(drive_id, receipts);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to deserialize read receipts: {}", e);
+                }
+            }
+        }
+        tracing::info!("Loaded read receipts for {} drives from database", guard.len());
+        Ok(())
+    }
+
+    /// Record that a member read a file (persists to database)
+    pub async fn record(&self, drive_id: &str, path: &str, reader: &str, read_at: DateTime<Utc>) {
+        let mut receipts = self.receipts.write().await;
+        let drive_receipts = receipts.entry(drive_id.to_string()).or_default();
+        let readers = drive_receipts.entry(path.to_string()).or_default();
+
+        match readers.iter_mut().find(|r| r.node_id == reader) {
+            Some(existing) => {
+                existing.first_read_at = existing.first_read_at.min(read_at);
+                existing.last_read_at = existing.last_read_at.max(read_at);
+                existing.read_count = existing.read_count.saturating_add(1);
+            }
+            None => readers.push(FileReader {
+                node_id: reader.to_string(),
+                first_read_at: read_at,
+                last_read_at: read_at,
+                read_count: 1,
+            }),
+        }
+
+        match serde_json::to_vec(&*drive_receipts) {
+            Ok(data) => {
+                if let Err(e) = self.db.save_read_receipts(drive_id, &data) {
+                    tracing::error!(
+                        "Failed to persist read receipts for drive {}: {}",
+                        drive_id,
+                        e
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to serialize read receipts: {}", e);
+            }
+        }
+    }
+
+    /// Get everyone who has read a file, most recent first
+    pub async fn readers(&self, drive_id: &str, path: &str) -> Vec<FileReader> {
+        let receipts = self.receipts.read().await;
+        let mut readers = receipts
+            .get(drive_id)
+            .and_then(|r| r.get(path))
+            .cloned()
+            .unwrap_or_default();
+        readers.sort_by(|a, b| b.last_read_at.cmp(&a.last_read_at));
+        readers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_record_and_list_readers() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(dir.path().join("test.redb")).unwrap());
+        let store = ReadReceiptStore::new(db.clone());
+
+        let now = Utc::now();
+        store.record("drive1", "docs/a.txt", "alice", now).await;
+        store
+            .record("drive1", "docs/a.txt", "bob", now + Duration::seconds(5))
+            .await;
+        store
+            .record("drive1", "docs/a.txt", "alice", now + Duration::seconds(10))
+            .await;
+
+        let readers = store.readers("drive1", "docs/a.txt").await;
+        assert_eq!(readers.len(), 2);
+        assert_eq!(readers[0].node_id, "alice");
+        assert_eq!(readers[0].read_count, 2);
+        assert_eq!(readers[0].first_read_at, now);
+        assert!(store.readers("drive1", "docs/b.txt").await.is_empty());
+
+        // Receipts survive a reload
+        let reloaded = ReadReceiptStore::new(db);
+        let reloaded = tokio::task::spawn_blocking(move || {
+            reloaded.load_from_db().unwrap();
+            reloaded
+        })
+        .await
+        .unwrap();
+        assert_eq!(reloaded.readers("drive1", "docs/a.txt").await.len(), 2);
+    }
+}
@@ -0,0 +1,328 @@
+//! Filtered drive event subscriptions
+//!
+//! Drive events are emitted to the frontend as a global `drive-event`.
+//! Focused views (conflicts only, lock activity only, ...) can instead open a
+//! filtered subscription: they pass a channel and a set of event types, and
+//! only matching events for their drive are sent over that channel.
+//!
+//! Views listening to the global event register too, so leaked
+//! subscriptions of either kind can be listed and torn down. Once a drive
+//! has filtered subscriptions, its events only go out globally while a
+//! listener is registered.
+
+use crate::core::DriveEventDto;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use tauri::ipc::Channel;
+use tauri::{AppHandle, Emitter};
+
+/// Which events a subscriber wants
+#[derive(Clone, Debug)]
+pub struct EventFilter {
+    /// Drive to receive events for (hex string)
+    pub drive_id: String,
+    /// Event types to receive (values of `DriveEvent::event_type`)
+    pub event_types: HashSet<String>,
+}
+
+impl EventFilter {
+    /// Check if an event passes this filter
+    pub fn matches(&self, event: &DriveEventDto) -> bool {
+        event.drive_id == self.drive_id && self.event_types.contains(&event.event_type)
+    }
+}
+
+/// A registered subscriber
+///
+/// Filtered subscribers have a channel; listeners of the global
+/// `drive-event` don't and are only tracked.
+struct Subscriber {
+    filter: EventFilter,
+    channel: Option<Channel<DriveEventDto>>,
+    created_at: DateTime<Utc>,
+    /// Events sent over the channel
+    delivered: AtomicU64,
+}
+
+/// An active subscription, for debugging leaks
+#[derive(Clone, Debug, Serialize)]
+pub struct SubscriptionInfo {
+    pub id: String,
+    pub drive_id: String,
+    /// False for listeners of the global `drive-event`
+    pub filtered: bool,
+    /// Event types a filtered subscription receives, sorted
+    pub event_types: Vec<String>,
+    pub created_at: String,
+    /// Events sent to a filtered subscription so far
+    pub events_delivered: u64,
+}
+
+/// Active subscriptions of one drive
+#[derive(Clone, Debug, Serialize)]
+pub struct DriveSubscriptions {
+    pub drive_id: String,
+    /// Number of subscribers receiving this drive's events
+    pub receivers: usize,
+    pub subscriptions: Vec<SubscriptionInfo>,
+}
+
+/// Registry of frontend subscribers, fed by the event forwarders
+pub struct EventSubscriptions {
+    subscribers: RwLock<HashMap<String, Subscriber>>,
+    /// Source of subscription IDs
+    next_id: AtomicU64,
+}
+
+impl EventSubscriptions {
+    pub fn new() -> Self {
+        Self {
+            subscribers: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Register a subscriber, returning its subscription ID
+    pub fn subscribe(&self, filter: EventFilter, channel: Channel<DriveEventDto>) -> String {
+        self.insert(filter, Some(channel))
+    }
+
+    /// Register a listener of a drive's global `drive-event`s
+    ///
+    /// Nothing is sent to it; it is tracked so the frontend can see and
+    /// end its subscriptions.
+    pub fn register_listener(&self, drive_id: String) -> String {
+        let filter = EventFilter {
+            drive_id,
+            event_types: HashSet::new(),
+        };
+        self.insert(filter, None)
+    }
+
+    fn insert(&self, filter: EventFilter, channel: Option<Channel<DriveEventDto>>) -> String {
+        let id = format!("sub_{:x}", self.next_id.fetch_add(1, Ordering::Relaxed));
+        if let Ok(mut subscribers) = self.subscribers.write() {
+            let subscriber = Subscriber {
+                filter,
+                channel,
+                created_at: Utc::now(),
+                delivered: AtomicU64::new(0),
+            };
+            subscribers.insert(id.clone(), subscriber);
+        }
+        id
+    }
+
+    /// Remove a subscriber. Returns false if the ID was unknown.
+    pub fn unsubscribe(&self, subscription_id: &str) -> bool {
+        self.subscribers
+            .write()
+            .map(|mut subscribers| subscribers.remove(subscription_id).is_some())
+            .unwrap_or(false)
+    }
+
+    /// Remove every subscriber of a drive, returning how many there were
+    pub fn unsubscribe_drive(&self, drive_id: &str) -> usize {
+        self.subscribers
+            .write()
+            .map(|mut subscribers| {
+                let before = subscribers.len();
+                subscribers.retain(|_, s| s.filter.drive_id != drive_id);
+                before - subscribers.len()
+            })
+            .unwrap_or(0)
+    }
+
+    /// Active subscriptions grouped by drive, oldest first within a drive
+    pub fn list(&self) -> Vec<DriveSubscriptions> {
+        let mut drives: BTreeMap<String, Vec<SubscriptionInfo>> = BTreeMap::new();
+        if let Ok(subscribers) = self.subscribers.read() {
+            for (id, subscriber) in subscribers.iter() {
+                let mut event_types: Vec<String> =
+                    subscriber.filter.event_types.iter().cloned().collect();
+                event_types.sort();
+                drives
+                    .entry(subscriber.filter.drive_id.clone())
+                    .or_default()
+                    .push(SubscriptionInfo {
+                        id: id.clone(),
+                        drive_id: subscriber.filter.drive_id.clone(),
+                        filtered: subscriber.channel.is_some(),
+                        event_types,
+                        created_at: subscriber.created_at.to_rfc3339(),
+                        events_delivered: subscriber.delivered.load(Ordering::Relaxed),
+                    });
+            }
+        }
+
+        drives
+            .into_iter()
+            .map(|(drive_id, mut subscriptions)| {
+                subscriptions.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+                DriveSubscriptions {
+                    drive_id,
+                    receivers: subscriptions.len(),
+                    subscriptions,
+                }
+            })
+            .collect()
+    }
+
+    /// Send an event to its filtered subscribers, and as a global
+    /// `drive-event` unless `dispatch` says nobody listens to that
+    pub fn publish(&self, app_handle: &AppHandle, event: &DriveEventDto) -> tauri::Result<()> {
+        if self.dispatch(event) {
+            app_handle.emit("drive-event", event)?;
+        }
+        Ok(())
+    }
+
+    /// Send an event to every subscriber whose filter matches
+    ///
+    /// Subscribers whose channel can no longer be reached (e.g. the view was
+    /// closed without unsubscribing) are dropped. Returns whether the event
+    /// should also be emitted globally: false only if its drive has filtered
+    /// subscriptions and no registered listener.
+    pub fn dispatch(&self, event: &DriveEventDto) -> bool {
+        let mut closed = Vec::new();
+        let mut filtered = false;
+        let mut listened = false;
+
+        if let Ok(subscribers) = self.subscribers.read() {
+            for (id, subscriber) in subscribers.iter() {
+                if subscriber.filter.drive_id != event.drive_id {
+                    continue;
+                }
+                let Some(channel) = subscriber.channel.as_ref() else {
+                    listened = true;
+                    continue;
+                };
+                if !subscriber.filter.matches(event) {
+                    filtered = true;
+                    continue;
+                }
+                match channel.send(event.clone()) {
+                    Ok(()) => {
+                        filtered = true;
+                        subscriber.delivered.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(e) => {
+                        tracing::debug!("Dropping event subscription {}: {}", id, e);
+                        closed.push(id.clone());
+                    }
+                }
+            }
+        }
+
+        if !closed.is_empty() {
+            if let Ok(mut subscribers) = self.subscribers.write() {
+                for id in closed {
+                    subscribers.remove(&id);
+                }
+            }
+        }
+        listened || !filtered
+    }
+}
+
+impl Default for EventSubscriptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dto(drive_id: &str, event_type: &str) -> DriveEventDto {
+        DriveEventDto {
+            drive_id: drive_id.to_string(),
+            event_type: event_type.to_string(),
+            payload: serde_json::Value::Null,
+            timestamp: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_event_filter_matches() {
+        let filter = EventFilter {
+            drive_id: "drive1".to_string(),
+            event_types: ["FileLockAcquired", "FileLockReleased"]
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+        };
+
+        assert!(filter.matches(&dto("drive1", "FileLockAcquired")));
+        assert!(!filter.matches(&dto("drive1", "FileChanged")));
+        assert!(!filter.matches(&dto("drive2", "FileLockAcquired")));
+    }
+
+    #[test]
+    fn test_listeners_listed_and_removed() {
+        let subscriptions = EventSubscriptions::new();
+        let a = subscriptions.register_listener("drive1".to_string());
+        subscriptions.register_listener("drive1".to_string());
+        subscriptions.register_listener("drive2".to_string());
+
+        // Listeners have no channel, so dispatch sends them nothing
+        assert!(subscriptions.dispatch(&dto("drive1", "FileChanged")));
+
+        let listed = subscriptions.list();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].drive_id, "drive1");
+        assert_eq!(listed[0].receivers, 2);
+        assert!(!listed[0].subscriptions[0].filtered);
+        assert_eq!(listed[0].subscriptions[0].events_delivered, 0);
+
+        assert!(subscriptions.unsubscribe(&a));
+        assert!(!subscriptions.unsubscribe(&a));
+        assert_eq!(subscriptions.unsubscribe_drive("drive1"), 1);
+        assert_eq!(subscriptions.unsubscribe_drive("drive1"), 0);
+        assert_eq!(subscriptions.list().len(), 1);
+    }
+
+    #[test]
+    fn test_global_emit_without_filtered_subscriptions() {
+        let subscriptions = EventSubscriptions::new();
+
+        // Nobody registered: unregistered global listeners still get events
+        assert!(subscriptions.dispatch(&dto("drive1", "FileChanged")));
+
+        // A closed filtered channel is dropped and doesn't count
+        let filter = EventFilter {
+            drive_id: "drive1".to_string(),
+            event_types: ["FileChanged".to_string()].into_iter().collect(),
+        };
+        subscriptions.subscribe(
+            filter,
+            Channel::new(|_| Err(std::io::Error::other("closed").into())),
+        );
+        assert!(subscriptions.dispatch(&dto("drive1", "FileChanged")));
+        assert!(subscriptions.list().is_empty());
+    }
+
+    #[test]
+    fn test_global_emit_skipped_for_filtered_drives() {
+        let subscriptions = EventSubscriptions::new();
+        let filter = EventFilter {
+            drive_id: "drive1".to_string(),
+            event_types: ["FileLockAcquired".to_string()].into_iter().collect(),
+        };
+        subscriptions.subscribe(filter, Channel::new(|_| Ok(())));
+
+        // Matching or not, drive1's events only reach its filtered subscriber
+        assert!(!subscriptions.dispatch(&dto("drive1", "FileLockAcquired")));
+        assert!(!subscriptions.dispatch(&dto("drive1", "FileChanged")));
+        assert!(subscriptions.dispatch(&dto("drive2", "FileChanged")));
+        assert_eq!(subscriptions.list()[0].subscriptions[0].events_delivered, 1);
+
+        // A registered listener brings the global emit back
+        subscriptions.register_listener("drive1".to_string());
+        assert!(subscriptions.dispatch(&dto("drive1", "FileChanged")));
+    }
+}
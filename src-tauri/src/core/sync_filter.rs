@@ -0,0 +1,119 @@
+//! Selective sync filters
+//!
+//! Each drive can limit which paths take part in sync with include and
+//! exclude globs. Patterns use the same matching as ACL path rules (see
+//! `glob_matches`). A path is synced when it matches an include pattern
+//! (or no include patterns are set) and matches no exclude pattern.
+
+use crate::crypto::glob_matches;
+use serde::{Deserialize, Serialize};
+
+/// Longest accepted glob pattern
+const MAX_PATTERN_LEN: usize = 512;
+
+/// Most patterns accepted per list
+const MAX_PATTERNS: usize = 256;
+
+/// Include/exclude globs for one drive
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncFilters {
+    /// Paths to sync; empty means everything
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Paths never synced, checked after `include`
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl SyncFilters {
+    /// Build filters, trimming patterns and dropping empty ones
+    pub fn new(include: Vec<String>, exclude: Vec<String>) -> Result<Self, String> {
+        Ok(Self {
+            include: normalize_patterns(include)?,
+            exclude: normalize_patterns(exclude)?,
+        })
+    }
+
+    /// Whether no filtering applies
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a drive-relative path takes part in sync
+    pub fn allows(&self, path: &str) -> bool {
+        let path = path.replace('\\', "/");
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| glob_matches(pattern, &path));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| glob_matches(pattern, &path))
+    }
+}
+
+fn normalize_patterns(patterns: Vec<String>) -> Result<Vec<String>, String> {
+    if patterns.len() > MAX_PATTERNS {
+        return Err(format!("At most {} patterns are allowed", MAX_PATTERNS));
+    }
+
+    let mut normalized = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        let pattern = pattern.trim().replace('\\', "/");
+        if pattern.is_empty() {
+            continue;
+        }
+        if pattern.len() > MAX_PATTERN_LEN {
+            return Err(format!(
+                "Pattern is longer than {} characters",
+                MAX_PATTERN_LEN
+            ));
+        }
+        if !normalized.contains(&pattern) {
+            normalized.push(pattern);
+        }
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allows() {
+        let filters = SyncFilters::default();
+        assert!(filters.is_empty());
+        assert!(filters.allows("anything/at/all.txt"));
+
+        let filters =
+            SyncFilters::new(vec![], vec!["node_modules/**".into(), "*.log".into()]).unwrap();
+        assert!(!filters.allows("node_modules/pkg/index.js"));
+        assert!(!filters.allows("debug.log"));
+        assert!(filters.allows("src/main.rs"));
+        assert!(filters.allows("logs/debug.log"));
+
+        let filters =
+            SyncFilters::new(vec!["docs/**".into()], vec!["docs/drafts/**".into()]).unwrap();
+        assert!(filters.allows("docs/guide.md"));
+        assert!(filters.allows("docs\\guide.md"));
+        assert!(!filters.allows("docs/drafts/new.md"));
+        assert!(!filters.allows("src/main.rs"));
+    }
+
+    #[test]
+    fn test_new_normalizes_patterns() {
+        let filters = SyncFilters::new(
+            vec![" ".into()],
+            vec![" build\\** ".into(), "build/**".into()],
+        )
+        .unwrap();
+        assert!(filters.include.is_empty());
+        assert_eq!(filters.exclude, vec!["build/**".to_string()]);
+
+        assert!(SyncFilters::new(vec!["a".repeat(MAX_PATTERN_LEN + 1)], vec![]).is_err());
+    }
+}
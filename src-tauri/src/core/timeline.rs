@@ -0,0 +1,186 @@
+//! Unified drive timeline
+//!
+//! Merges presence activity, transfers, conflicts, and locks into a single
+//! list of entries sorted newest first, so the UI can show one history view
+//! instead of stitching the sources together itself.
+
+use crate::core::conflict::FileConflict;
+use crate::core::locking::FileLock;
+use crate::core::presence::ActivityEntry;
+use crate::crypto::NodeId;
+use crate::network::transfer::{TransferDirection, TransferState, TransferStatus};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Source of a timeline entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineKind {
+    /// Presence activity (joins, leaves, file changes)
+    Activity,
+    Transfer,
+    Conflict,
+    Lock,
+}
+
+/// A single entry in a drive's timeline
+#[derive(Clone, Debug, Serialize)]
+pub struct TimelineEntry {
+    /// ID unique within its kind
+    pub id: String,
+    pub kind: TimelineKind,
+    /// What happened (e.g. "FileModified", "download_completed")
+    pub action: String,
+    /// Node ID (hex) of whoever caused the entry
+    pub actor: String,
+    pub path: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub details: Option<String>,
+    /// True if the actor is this device
+    pub is_self: bool,
+}
+
+impl TimelineEntry {
+    pub fn from_activity(entry: &ActivityEntry, my_node_id: &NodeId) -> Self {
+        Self {
+            id: entry.id.clone(),
+            kind: TimelineKind::Activity,
+            action: format!("{:?}", entry.activity_type),
+            actor: entry.user.to_hex(),
+            path: entry.path.as_ref().map(|p| p.to_string_lossy().to_string()),
+            timestamp: entry.timestamp,
+            details: entry.details.clone(),
+            is_self: entry.user == *my_node_id,
+        }
+    }
+
+    /// Transfers are always started by this device
+    pub fn from_transfer(transfer: &TransferState, my_node_id: &NodeId) -> Self {
+        let direction = match transfer.direction {
+            TransferDirection::Upload => "upload",
+            TransferDirection::Download => "download",
+        };
+        let status = match transfer.status {
+            TransferStatus::Pending => "pending",
+            TransferStatus::Queued => "queued",
+            TransferStatus::InProgress => "in_progress",
+            TransferStatus::Retrying { .. } => "retrying",
+            TransferStatus::Completed => "completed",
+            TransferStatus::Failed => "failed",
+            TransferStatus::Cancelled => "cancelled",
+        };
+        Self {
+            id: transfer.id.clone(),
+            kind: TimelineKind::Transfer,
+            action: format!("{}_{}", direction, status),
+            actor: my_node_id.to_hex(),
+            path: Some(transfer.path.clone()),
+            timestamp: transfer.started_at,
+            details: transfer.error.clone(),
+            is_self: true,
+        }
+    }
+
+    /// Attributed to the peer whose change caused the conflict
+    pub fn from_conflict(conflict: &FileConflict, my_node_id: &NodeId) -> Self {
+        Self {
+            id: conflict.id.clone(),
+            kind: TimelineKind::Conflict,
+            action: if conflict.resolved {
+                "conflict_resolved".to_string()
+            } else {
+                "conflict_detected".to_string()
+            },
+            actor: conflict.remote.modified_by.to_hex(),
+            path: Some(conflict.path.to_string_lossy().to_string()),
+            timestamp: conflict.detected_at,
+            details: conflict.resolution.map(|r| format!("{:?}", r)),
+            is_self: conflict.remote.modified_by == *my_node_id,
+        }
+    }
+
+    pub fn from_lock(lock: &FileLock, my_node_id: &NodeId) -> Self {
+        let path = lock.path.to_string_lossy().to_string();
+        let lock_type = lock.lock_type.as_str();
+        Self {
+            id: format!("{}:{}", path, lock.acquired_at.timestamp_millis()),
+            kind: TimelineKind::Lock,
+            action: format!("{}_lock_acquired", lock_type),
+            actor: lock.holder.to_hex(),
+            path: Some(path),
+            timestamp: lock.acquired_at,
+            details: lock.reason.clone(),
+            is_self: lock.holder == *my_node_id,
+        }
+    }
+}
+
+/// Sort entries newest first
+///
+/// Ties are broken by kind and then ID, so the order is stable across calls
+/// and pages don't shift when timestamps collide.
+pub fn sort_timeline(entries: &mut [TimelineEntry]) {
+    entries.sort_by(|a, b| {
+        b.timestamp
+            .cmp(&a.timestamp)
+            .then(a.kind.cmp(&b.kind))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::locking::LockType;
+    use crate::core::presence::ActivityType;
+    use crate::crypto::Identity;
+    use chrono::Duration;
+
+    fn activity(id: &str, user: NodeId, timestamp: DateTime<Utc>) -> ActivityEntry {
+        ActivityEntry {
+            id: id.to_string(),
+            activity_type: ActivityType::FileModified,
+            user,
+            path: Some("docs/a.txt".into()),
+            timestamp,
+            details: None,
+        }
+    }
+
+    #[test]
+    fn test_sort_timeline_newest_first_and_stable() {
+        let me = Identity::generate().node_id();
+        let now = Utc::now();
+
+        let mut lock = FileLock::new("docs/a.txt".into(), me, LockType::Write);
+        lock.acquired_at = now;
+
+        let mut entries = vec![
+            TimelineEntry::from_activity(&activity("b", me, now), &me),
+            TimelineEntry::from_activity(&activity("old", me, now - Duration::hours(1)), &me),
+            TimelineEntry::from_lock(&lock, &me),
+            TimelineEntry::from_activity(&activity("a", me, now), &me),
+        ];
+        sort_timeline(&mut entries);
+
+        let order: Vec<_> = entries.iter().map(|e| (e.kind, e.id.as_str())).collect();
+        assert_eq!(order[0], (TimelineKind::Activity, "a"));
+        assert_eq!(order[1], (TimelineKind::Activity, "b"));
+        assert_eq!(order[2].0, TimelineKind::Lock);
+        assert_eq!(order[3], (TimelineKind::Activity, "old"));
+    }
+
+    #[test]
+    fn test_timeline_entry_actor() {
+        let me = Identity::generate().node_id();
+        let peer = Identity::generate().node_id();
+
+        let entry = TimelineEntry::from_activity(&activity("x", peer, Utc::now()), &me);
+        assert_eq!(entry.actor, peer.to_hex());
+        assert!(!entry.is_self);
+
+        let json = serde_json::to_value(&entry).unwrap();
+        assert_eq!(json["kind"], "activity");
+        assert_eq!(json["action"], "FileModified");
+    }
+}
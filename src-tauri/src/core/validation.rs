@@ -4,7 +4,10 @@
 //! to prevent common vulnerabilities.
 
 use crate::core::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use unicode_normalization::UnicodeNormalization;
 
 /// Maximum allowed name length for drives and other entities
 pub const MAX_NAME_LENGTH: usize = 255;
@@ -15,9 +18,27 @@ pub const MIN_NAME_LENGTH: usize = 1;
 /// Maximum path depth to prevent resource exhaustion
 pub const MAX_PATH_DEPTH: usize = 64;
 
+/// Maximum length of a custom metadata key (bytes)
+pub const MAX_METADATA_KEY_LENGTH: usize = 64;
+
+/// Maximum length of a custom metadata value (bytes)
+pub const MAX_METADATA_VALUE_LENGTH: usize = 512;
+
+/// Maximum number of custom metadata entries per drive
+pub const MAX_METADATA_ENTRIES: usize = 32;
+
+/// Maximum combined size of all custom metadata keys and values (bytes)
+pub const MAX_METADATA_TOTAL_SIZE: usize = 4096;
+
 /// Characters forbidden in names (for cross-platform compatibility)
 const FORBIDDEN_NAME_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*', '\0'];
 
+/// Device names Windows reserves regardless of extension
+const RESERVED_WINDOWS_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
 /// Patterns that indicate path traversal attempts
 const TRAVERSAL_PATTERNS: &[&str] = &[
     "..",
@@ -229,6 +250,75 @@ pub fn validate_node_id(node_id: &str) -> Result<[u8; 32], AppError> {
     Ok(arr)
 }
 
+/// Validate custom key/value metadata attached to a drive
+///
+/// Keys are trimmed and must be non-empty, at most
+/// `MAX_METADATA_KEY_LENGTH` bytes, and use only letters, digits, `-`, `_`
+/// and `.`. Values may be empty but not contain control characters. Returns
+/// the metadata with trimmed keys.
+pub fn validate_custom_metadata(
+    metadata: HashMap<String, String>,
+) -> Result<HashMap<String, String>, AppError> {
+    if metadata.len() > MAX_METADATA_ENTRIES {
+        return Err(AppError::ValidationFailed {
+            field: "custom_metadata".to_string(),
+            reason: format!("At most {} entries allowed", MAX_METADATA_ENTRIES),
+        });
+    }
+
+    let mut validated = HashMap::with_capacity(metadata.len());
+    let mut total_size = 0;
+    for (key, value) in metadata {
+        let key = key.trim().to_string();
+        if key.is_empty()
+            || key.len() > MAX_METADATA_KEY_LENGTH
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(AppError::ValidationFailed {
+                field: "custom_metadata".to_string(),
+                reason: format!(
+                    "Invalid key '{}': use 1-{} letters, digits, '-', '_' or '.'",
+                    key, MAX_METADATA_KEY_LENGTH
+                ),
+            });
+        }
+        if value.len() > MAX_METADATA_VALUE_LENGTH {
+            return Err(AppError::ValidationFailed {
+                field: "custom_metadata".to_string(),
+                reason: format!(
+                    "Value for '{}' exceeds {} bytes",
+                    key, MAX_METADATA_VALUE_LENGTH
+                ),
+            });
+        }
+        if value.chars().any(|c| c.is_control()) {
+            return Err(AppError::ValidationFailed {
+                field: "custom_metadata".to_string(),
+                reason: format!("Value for '{}' contains control characters", key),
+            });
+        }
+
+        total_size += key.len() + value.len();
+        if validated.insert(key.clone(), value).is_some() {
+            return Err(AppError::ValidationFailed {
+                field: "custom_metadata".to_string(),
+                reason: format!("Duplicate key '{}'", key),
+            });
+        }
+    }
+
+    if total_size > MAX_METADATA_TOTAL_SIZE {
+        return Err(AppError::ValidationFailed {
+            field: "custom_metadata".to_string(),
+            reason: format!("Total size exceeds {} bytes", MAX_METADATA_TOTAL_SIZE),
+        });
+    }
+
+    Ok(validated)
+}
+
 /// Check if a file path is safe for filesystem operations
 pub fn is_safe_filename(name: &str) -> bool {
     // Empty names are not safe
@@ -244,20 +334,96 @@ pub fn is_safe_filename(name: &str) -> bool {
     }
 
     // Check for reserved Windows names
-    let upper = name.to_uppercase();
-    let reserved = [
-        "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
-        "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
-    ];
-    
-    let name_without_ext = upper.split('.').next().unwrap_or(&upper);
-    if reserved.contains(&name_without_ext) {
+    if is_reserved_windows_name(name) {
         return false;
     }
 
     true
 }
 
+/// Check if a name's part before the first `.` is a reserved Windows device
+fn is_reserved_windows_name(name: &str) -> bool {
+    let upper = name.to_uppercase();
+    let name_without_ext = upper.split('.').next().unwrap_or(&upper);
+    RESERVED_WINDOWS_NAMES.contains(&name_without_ext)
+}
+
+/// Unicode normalization form applied to file names
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnicodeForm {
+    /// Keep names exactly as given
+    #[default]
+    Preserve,
+    /// Composed form, used by Windows and Linux tools
+    Nfc,
+    /// Decomposed form, as older macOS filesystems store names
+    Nfd,
+}
+
+/// How a drive canonicalizes file names on write and import
+///
+/// Peers on different OSes can spell the same name differently (e.g. `é`
+/// composed vs decomposed), which makes one file show up twice. A policy
+/// makes every write store the same canonical name. The default keeps
+/// names as-is.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamePolicy {
+    #[serde(default)]
+    pub unicode: UnicodeForm,
+    /// Replace characters Windows can't store and avoid reserved device names
+    #[serde(default)]
+    pub windows_safe: bool,
+}
+
+impl NamePolicy {
+    /// True if names are kept as-is
+    pub fn is_preserve(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Canonicalize a drive-relative path
+    ///
+    /// Separators become `/` and each component is normalized. With
+    /// `windows_safe`, forbidden and control characters become `_`, trailing
+    /// dots and spaces are dropped, and reserved names like `CON.txt` become
+    /// `CON_.txt`.
+    pub fn apply(&self, path: &str) -> String {
+        if self.is_preserve() {
+            return path.to_string();
+        }
+        path.split(['/', '\\'])
+            .map(|component| self.apply_component(component))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    fn apply_component(&self, name: &str) -> String {
+        let mut name: String = match self.unicode {
+            UnicodeForm::Preserve => name.to_string(),
+            UnicodeForm::Nfc => name.nfc().collect(),
+            UnicodeForm::Nfd => name.nfd().collect(),
+        };
+        if !self.windows_safe {
+            return name;
+        }
+
+        name = name
+            .chars()
+            .map(|c| if FORBIDDEN_NAME_CHARS.contains(&c) || c.is_control() { '_' } else { c })
+            .collect();
+        let trimmed_len = name.trim_end_matches(['.', ' ']).len();
+        if trimmed_len > 0 {
+            name.truncate(trimmed_len);
+        }
+        if is_reserved_windows_name(&name) {
+            let base_len = name.find('.').unwrap_or(name.len());
+            name.insert(base_len, '_');
+        }
+        name
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -363,4 +529,65 @@ mod tests {
         assert!(!is_safe_filename("COM1.txt"));
         assert!(!is_safe_filename(""));
     }
+
+    #[test]
+    fn test_validate_custom_metadata() {
+        let ok = HashMap::from([
+            (" project ".to_string(), "GX-42".to_string()),
+            ("retention.class".to_string(), String::new()),
+        ]);
+        let validated = validate_custom_metadata(ok).unwrap();
+        assert_eq!(validated.get("project").map(String::as_str), Some("GX-42"));
+
+        let bad_key = HashMap::from([("has space".to_string(), "x".to_string())]);
+        assert!(validate_custom_metadata(bad_key).is_err());
+
+        let duplicate = HashMap::from([
+            ("dept".to_string(), "a".to_string()),
+            ("dept ".to_string(), "b".to_string()),
+        ]);
+        assert!(validate_custom_metadata(duplicate).is_err());
+
+        let long_value = HashMap::from([("k".to_string(), "v".repeat(513))]);
+        assert!(validate_custom_metadata(long_value).is_err());
+
+        let too_big: HashMap<String, String> = (0..10)
+            .map(|i| (format!("key{}", i), "v".repeat(500)))
+            .collect();
+        assert!(validate_custom_metadata(too_big).is_err());
+    }
+
+    #[test]
+    fn test_name_policy() {
+        let composed = "caf\u{e9}/r\u{e9}sum\u{e9}.txt";
+        let decomposed = "cafe\u{301}/re\u{301}sume\u{301}.txt";
+
+        // Default keeps names untouched
+        let preserve = NamePolicy::default();
+        assert!(preserve.is_preserve());
+        assert_eq!(preserve.apply(decomposed), decomposed);
+        assert_eq!(preserve.apply("a\\b?.txt"), "a\\b?.txt");
+
+        // Both spellings map to the same canonical name
+        let nfc = NamePolicy {
+            unicode: UnicodeForm::Nfc,
+            windows_safe: false,
+        };
+        assert_eq!(nfc.apply(decomposed), composed);
+        assert_eq!(nfc.apply(composed), composed);
+        let nfd = NamePolicy {
+            unicode: UnicodeForm::Nfd,
+            windows_safe: false,
+        };
+        assert_eq!(nfd.apply(composed), decomposed);
+
+        let windows = NamePolicy {
+            unicode: UnicodeForm::Preserve,
+            windows_safe: true,
+        };
+        assert_eq!(windows.apply("notes\\a:b?.txt"), "notes/a_b_.txt");
+        assert_eq!(windows.apply("draft. "), "draft");
+        assert_eq!(windows.apply("docs/CON.txt"), "docs/CON_.txt");
+        assert_eq!(windows.apply("docs/console.txt"), "docs/console.txt");
+    }
 }
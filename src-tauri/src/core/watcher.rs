@@ -2,19 +2,38 @@
 //!
 //! Uses the notify crate with debouncing to monitor shared drive folders
 //! and convert file system events into DriveEvents for sync.
+//!
+//! Native notifications miss changes on some network filesystems and in
+//! Docker/WSL mounts. There the watcher falls back to polling: the drive is
+//! re-scanned on an interval and snapshots are reconciled into events.
+//!
+//! When a native watch sees more raw events per second than the churn
+//! threshold (a build loop, log spam), it stops handling them one by one
+//! and emits each changed path once per batch interval until the rate drops.
+//!
+//! A native watch holds each path's events back until the path has been
+//! quiet for the debounce window, so a file written in many small pieces
+//! produces one change instead of a storm.
+//!
+//! Paths matched by the drive's `.gixignore` files are dropped before any
+//! event is emitted; the rules are re-read when one of those files changes.
 
-use crate::core::{send_with_backpressure, DriveEvent, DriveId};
+use crate::core::{send_with_backpressure, Coalescer, DriveEvent, DriveId};
+use crate::core::ignore::is_ignore_file;
+use crate::core::{content_type, FsOperation, IgnoreRules, PermissionErrors};
 use crate::crypto::NodeId;
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use notify::{
     event::{CreateKind, ModifyKind, RemoveKind, RenameMode},
     Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
 };
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::{broadcast, mpsc, RwLock};
 
 /// Patterns to ignore when watching
@@ -35,14 +54,382 @@ const IGNORE_PATTERNS: &[&str] = &[
     "~$*", // Office temp files
 ];
 
+/// Setting holding the watch configuration
+pub const WATCH_CONFIG_SETTING: &str = "watch_config";
+
+/// Bounds for the polling interval
+const MIN_POLL_INTERVAL_SECS: u64 = 1;
+const MAX_POLL_INTERVAL_SECS: u64 = 3600;
+
+/// Window over which a drive's recent event count is reported
+const RECENT_EVENTS_WINDOW: Duration = Duration::from_secs(5 * 60);
+
+/// Upper bound on event times remembered per drive for the recent count
+const MAX_RECENT_EVENTS: usize = 10_000;
+
+/// Watcher errors kept per drive
+const MAX_WATCH_ERRORS: usize = 10;
+
+/// Window over which a native watch's raw event rate is measured
+const CHURN_WINDOW: Duration = Duration::from_secs(1);
+
+/// Bounds for the churn threshold (raw events per second)
+const MIN_CHURN_THRESHOLD: u32 = 10;
+const MAX_CHURN_THRESHOLD: u32 = 100_000;
+
+/// Bounds for the batch interval while churn is high
+const MIN_CHURN_BATCH_MS: u64 = 250;
+const MAX_CHURN_BATCH_MS: u64 = 60_000;
+
+/// Default quiet period before a path's changes are emitted
+pub const DEFAULT_DEBOUNCE_MS: u64 = 300;
+
+/// Longest accepted debounce window
+const MAX_DEBOUNCE_MS: u64 = 10_000;
+
+/// Filesystems where native change notifications are unreliable
+#[cfg(target_os = "linux")]
+const UNRELIABLE_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "9p",    // WSL2 Windows drives, some VMs
+    "drvfs", // WSL1 Windows drives
+    "fuse.sshfs",
+    "vboxsf",
+    "fuse.vmhgfs-fuse",
+    "afs",
+];
+
+/// How drives are watched for changes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchMode {
+    /// OS change notifications only
+    Native,
+    /// Periodic re-scan of the drive
+    Polling,
+    /// Native, unless the drive is on a filesystem known to drop
+    /// notifications or the native watcher fails to start
+    #[default]
+    Auto,
+}
+
+/// Watch backend configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    pub mode: WatchMode,
+    /// Seconds between scans in polling mode
+    pub poll_interval_secs: u64,
+    /// Raw events per second above which a native watch batches changes
+    pub churn_threshold: u32,
+    /// Milliseconds between batches while churn is high
+    pub churn_batch_ms: u64,
+    /// Milliseconds a path must be quiet before its change is emitted
+    /// (native only; 0 emits every change as it arrives)
+    pub debounce_ms: u64,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            mode: WatchMode::Auto,
+            poll_interval_secs: 10,
+            churn_threshold: 500,
+            churn_batch_ms: 2_000,
+            debounce_ms: DEFAULT_DEBOUNCE_MS,
+        }
+    }
+}
+
+impl WatchConfig {
+    /// Clamp the polling interval, churn and debounce settings to supported
+    /// bounds
+    pub fn normalized(mut self) -> Self {
+        self.poll_interval_secs = self
+            .poll_interval_secs
+            .clamp(MIN_POLL_INTERVAL_SECS, MAX_POLL_INTERVAL_SECS);
+        self.churn_threshold = self
+            .churn_threshold
+            .clamp(MIN_CHURN_THRESHOLD, MAX_CHURN_THRESHOLD);
+        self.churn_batch_ms = self
+            .churn_batch_ms
+            .clamp(MIN_CHURN_BATCH_MS, MAX_CHURN_BATCH_MS);
+        self.debounce_ms = self.debounce_ms.min(MAX_DEBOUNCE_MS);
+        self
+    }
+}
+
+/// How a drive is actually being watched
+enum WatchBackend {
+    Native(RecommendedWatcher),
+    Polling(tokio::task::JoinHandle<()>),
+}
+
+/// A watcher error, as reported to the frontend
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchError {
+    pub at: DateTime<Utc>,
+    pub message: String,
+}
+
+/// Activity of one drive's watcher, shared with its watcher task
+#[derive(Default)]
+struct WatchHealth {
+    events_total: u64,
+    /// When recent events were emitted, oldest first
+    recent: VecDeque<Instant>,
+    last_event_at: Option<DateTime<Utc>>,
+    /// Last completed scan (polling only)
+    last_scan_at: Option<DateTime<Utc>>,
+    /// Why a native watch fell back to polling
+    fallback_reason: Option<String>,
+    errors: VecDeque<WatchError>,
+    /// Raw events per second over the last measured window (native only)
+    events_per_sec: u32,
+    /// Changes are being batched because of high churn
+    throttled: bool,
+}
+
+type SharedHealth = Arc<Mutex<WatchHealth>>;
+
+impl WatchHealth {
+    fn record_events(&mut self, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let now = Instant::now();
+        self.events_total += count as u64;
+        self.last_event_at = Some(Utc::now());
+        for _ in 0..count.min(MAX_RECENT_EVENTS) {
+            self.recent.push_back(now);
+        }
+        self.prune(now);
+        while self.recent.len() > MAX_RECENT_EVENTS {
+            self.recent.pop_front();
+        }
+    }
+
+    fn record_error(&mut self, message: String) -> WatchError {
+        let error = WatchError {
+            at: Utc::now(),
+            message,
+        };
+        if self.errors.len() == MAX_WATCH_ERRORS {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(error.clone());
+        error
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .recent
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > RECENT_EVENTS_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+    }
+}
+
+/// Raw event rate of a native watch, and whether its changes are batched
+struct ChurnMonitor {
+    threshold: u32,
+    window_start: Instant,
+    window_events: u32,
+    /// Events per second over the last full window
+    rate: u32,
+    throttled: bool,
+}
+
+impl ChurnMonitor {
+    fn new(threshold: u32, now: Instant) -> Self {
+        Self {
+            threshold,
+            window_start: now,
+            window_events: 0,
+            rate: 0,
+            throttled: false,
+        }
+    }
+
+    /// Count `events` raw events seen at `now`
+    ///
+    /// Returns the new throttled state when it changes. Batching starts when
+    /// a window goes over the threshold and stops once one falls below half
+    /// of it, so a rate hovering at the threshold doesn't flap.
+    fn record(&mut self, events: u32, now: Instant) -> Option<bool> {
+        self.window_events = self.window_events.saturating_add(events);
+        let elapsed = now.duration_since(self.window_start);
+        if elapsed < CHURN_WINDOW {
+            return None;
+        }
+        self.rate = (self.window_events as f64 / elapsed.as_secs_f64()) as u32;
+        self.window_start = now;
+        self.window_events = 0;
+
+        let throttled = if self.throttled {
+            self.rate >= self.threshold / 2
+        } else {
+            self.rate > self.threshold
+        };
+        if throttled == self.throttled {
+            return None;
+        }
+        self.throttled = throttled;
+        Some(throttled)
+    }
+}
+
+/// Holds back a native watch's raw events until their path goes quiet
+///
+/// Each path keeps only its latest event, and its window restarts with
+/// every new one. A delete is never replaced by a later event for the same
+/// path: a delete followed by a create is emitted as both, in order.
+struct Debouncer {
+    window: Duration,
+    pending: HashMap<PathBuf, PendingPath>,
+}
+
+/// Events held back for one path
+struct PendingPath {
+    /// When the path will have been quiet for the window
+    due: Instant,
+    /// Oldest first: a held delete, then the latest event after it
+    events: Vec<notify::Event>,
+}
+
+impl Debouncer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Hold `event` back until its path is quiet, or hand it straight back
+    /// when debouncing is off
+    fn push(&mut self, event: notify::Event, now: Instant) -> Option<notify::Event> {
+        let path = match event.paths.first() {
+            Some(path) if !self.window.is_zero() => path.clone(),
+            _ => return Some(event),
+        };
+        let due = now + self.window;
+        let held = self.pending.entry(path).or_insert_with(|| PendingPath {
+            due,
+            events: Vec::new(),
+        });
+        held.due = due;
+        if held.events.last().is_some_and(|last| !is_remove(last)) {
+            held.events.pop();
+        }
+        if !(is_remove(&event) && held.events.last().is_some_and(is_remove)) {
+            held.events.push(event);
+        }
+        None
+    }
+
+    /// When the next held path goes quiet
+    fn next_due(&self) -> Option<Instant> {
+        self.pending.values().map(|held| held.due).min()
+    }
+
+    /// Events of every path quiet by `now`, in the order the paths went quiet
+    fn take_due(&mut self, now: Instant) -> Vec<notify::Event> {
+        let mut due = Vec::new();
+        self.pending.retain(|_, held| {
+            if held.due > now {
+                return true;
+            }
+            due.push((held.due, std::mem::take(&mut held.events)));
+            false
+        });
+        due.sort_by_key(|(at, _)| *at);
+        due.into_iter().flat_map(|(_, events)| events).collect()
+    }
+}
+
+fn is_remove(event: &notify::Event) -> bool {
+    matches!(event.kind, EventKind::Remove(_))
+}
+
+/// Sleep until `due`, or forever if nothing is due
+async fn sleep_until_due(due: Option<Instant>) {
+    match due {
+        Some(due) => tokio::time::sleep_until(due.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Watch state and recent activity of one drive
+#[derive(Clone, Debug, Serialize)]
+pub struct WatchStatus {
+    pub drive_id: String,
+    pub watching: bool,
+    /// `native` or `polling` while watching
+    pub backend: Option<WatchMode>,
+    pub root_path: Option<String>,
+    /// True while a bulk operation has events suspended
+    pub suspended: bool,
+    /// Events emitted since the watch started
+    pub events_total: u64,
+    /// Events emitted in the last five minutes
+    pub events_recent: usize,
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Last completed scan (polling only)
+    pub last_scan_at: Option<DateTime<Utc>>,
+    /// Why the drive is polled although native watching was wanted
+    pub fallback_reason: Option<String>,
+    /// Most recent watcher errors, oldest first
+    pub errors: Vec<WatchError>,
+    /// Watching, and no error since the last event
+    pub healthy: bool,
+    /// Raw filesystem events per second over the last second (native only)
+    pub events_per_sec: u32,
+    /// True while changes are batched because of high churn
+    pub throttled: bool,
+}
+
 /// A watched drive's state
 struct WatchedDrive {
     /// The drive ID (stored for future reference)
     _drive_id: DriveId,
-    /// Root path being watched (stored for future reference)
-    _root_path: PathBuf,
-    /// The file watcher handle
-    _watcher: RecommendedWatcher,
+    /// Root path being watched
+    root_path: PathBuf,
+    /// The native watcher or polling task
+    backend: WatchBackend,
+    /// Activity counters updated by the watcher task
+    health: SharedHealth,
+}
+
+impl Drop for WatchedDrive {
+    fn drop(&mut self) {
+        if let WatchBackend::Polling(handle) = &self.backend {
+            handle.abort();
+        }
+    }
+}
+
+/// Size and modification time of each file, keyed by absolute path
+type Snapshot = HashMap<PathBuf, (u64, Option<SystemTime>)>;
+
+/// Content hash and author of files whose metadata a bulk operation
+/// already wrote, keyed by absolute path
+type Recorded = HashMap<PathBuf, (String, NodeId)>;
+
+/// A drive whose events are being dropped during a bulk operation
+struct Suspension {
+    /// Nested suspensions; events resume when this reaches zero
+    depth: usize,
+    root_path: PathBuf,
+    /// Files as they were when the suspension started
+    snapshot: Snapshot,
+    /// Files the operation already recorded; not reported again
+    recorded: Recorded,
 }
 
 /// Manages file system watchers for all active drives
@@ -53,17 +440,163 @@ pub struct FileWatcherManager {
     node_id: NodeId,
     /// Channel for emitting drive events
     event_tx: broadcast::Sender<(DriveId, DriveEvent)>,
+    /// Directories whose contents never produce events (e.g. download temp dir)
+    ignored_dirs: Arc<std::sync::RwLock<Vec<PathBuf>>>,
+    /// Drives whose events are suspended
+    suspensions: Arc<RwLock<HashMap<DriveId, Suspension>>>,
+    /// Drives unwatched by `drain_watch`, with their files at the time
+    drained: RwLock<HashMap<DriveId, Suspension>>,
+    /// Backend used for drives watched from now on
+    config: std::sync::RwLock<WatchConfig>,
+    /// Holds back repeated changes to the same file while the channel is full
+    coalescer: Arc<Coalescer<(DriveId, DriveEvent)>>,
+    /// Channel for watcher errors (local only, never synced)
+    error_tx: broadcast::Sender<(DriveId, DriveEvent)>,
+    /// Files that can't be read are recorded here and skipped until changed
+    permission_errors: std::sync::RwLock<Option<Arc<PermissionErrors>>>,
+    /// `.gixignore` rules of watched drives
+    ignore_rules: std::sync::RwLock<Arc<IgnoreRules>>,
+    /// Debounce window in milliseconds, read by running watches
+    debounce_ms: Arc<AtomicU64>,
 }
 
 impl FileWatcherManager {
     /// Create a new file watcher manager
     pub fn new(node_id: NodeId) -> Self {
         let (event_tx, _) = broadcast::channel(1024);
+        let (error_tx, _) = broadcast::channel(64);
 
         Self {
             watched: Arc::new(RwLock::new(HashMap::new())),
             node_id,
             event_tx,
+            ignored_dirs: Arc::new(std::sync::RwLock::new(Vec::new())),
+            suspensions: Arc::new(RwLock::new(HashMap::new())),
+            drained: RwLock::new(HashMap::new()),
+            config: std::sync::RwLock::new(WatchConfig::default()),
+            coalescer: Arc::new(Coalescer::new(coalesce_key)),
+            error_tx,
+            permission_errors: std::sync::RwLock::new(None),
+            ignore_rules: std::sync::RwLock::new(Arc::new(IgnoreRules::new())),
+            debounce_ms: Arc::new(AtomicU64::new(DEFAULT_DEBOUNCE_MS)),
+        }
+    }
+
+    /// Record unreadable files in `errors` for drives watched from now on
+    pub fn set_permission_errors(&self, errors: Arc<PermissionErrors>) {
+        if let Ok(mut current) = self.permission_errors.write() {
+            *current = Some(errors);
+        }
+    }
+
+    /// Keep `rules` current for drives watched from now on
+    ///
+    /// Lets the sync engine check local changes against the same rules.
+    pub fn set_ignore_rules(&self, rules: Arc<IgnoreRules>) {
+        if let Ok(mut current) = self.ignore_rules.write() {
+            *current = rules;
+        }
+    }
+
+    fn ignore_rules(&self) -> Arc<IgnoreRules> {
+        match self.ignore_rules.read() {
+            Ok(rules) => rules.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Re-read a watched drive's `.gixignore` files
+    ///
+    /// Changes to those files are picked up by the watcher; this is for
+    /// a manual refresh. Returns the number of ignore files found.
+    pub async fn reload_ignore_rules(&self, drive_id: &DriveId) -> Result<usize> {
+        let root_path = self
+            .watched
+            .read()
+            .await
+            .get(drive_id)
+            .map(|w| w.root_path.clone());
+        let Some(root_path) = root_path else {
+            anyhow::bail!("Drive {} is not watched", drive_id);
+        };
+        Ok(load_ignore_rules(self.ignore_rules(), *drive_id, root_path).await)
+    }
+
+    /// Hasher for a drive's change events
+    fn hasher(&self, drive_id: DriveId) -> FileHasher {
+        FileHasher {
+            drive_id,
+            errors: self.permission_errors.read().ok().and_then(|e| e.clone()),
+        }
+    }
+
+    /// Get the watch configuration
+    pub fn config(&self) -> WatchConfig {
+        self.config.read().map(|c| *c).unwrap_or_default()
+    }
+
+    /// Set the watch configuration for drives watched from now on
+    ///
+    /// The debounce window applies to running watches as well.
+    pub fn set_config(&self, config: WatchConfig) {
+        let config = config.normalized();
+        self.debounce_ms
+            .store(config.debounce_ms, Ordering::Relaxed);
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+    }
+
+    /// Set how long a path must be quiet before its change is emitted
+    ///
+    /// Takes effect on running watches without restarting them. Returns the
+    /// window in effect, capped at 10 seconds; 0 turns debouncing off.
+    pub fn set_debounce_ms(&self, ms: u64) -> u64 {
+        self.set_config(WatchConfig {
+            debounce_ms: ms,
+            ..self.config()
+        });
+        self.debounce_ms.load(Ordering::Relaxed)
+    }
+
+    /// Set the watch configuration and restart every watch with it
+    pub async fn apply_config(&self, config: WatchConfig) -> WatchConfig {
+        self.set_config(config);
+
+        let drives: Vec<(DriveId, PathBuf)> = self
+            .watched
+            .write()
+            .await
+            .drain()
+            .map(|(id, w)| (id, w.root_path.clone()))
+            .collect();
+        for (drive_id, root_path) in drives {
+            if let Err(e) = self.watch(drive_id, root_path).await {
+                tracing::warn!("Failed to restart watch for drive {}: {}", drive_id, e);
+            }
+        }
+
+        self.config()
+    }
+
+    /// Drives currently watched by polling
+    pub async fn polling_drives(&self) -> Vec<DriveId> {
+        self.watched
+            .read()
+            .await
+            .iter()
+            .filter(|(_, w)| matches!(w.backend, WatchBackend::Polling(_)))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Set directories to ignore in all watched drives
+    ///
+    /// Used for the download temp directory, so partial downloads never show
+    /// up as file changes even if it lives inside a drive.
+    pub fn set_ignored_dirs(&self, dirs: Vec<PathBuf>) {
+        if let Ok(mut ignored) = self.ignored_dirs.write() {
+            *ignored = dirs;
         }
     }
 
@@ -72,6 +605,14 @@ impl FileWatcherManager {
         self.event_tx.subscribe()
     }
 
+    /// Subscribe to `WatcherError` and `WatcherChurn` events
+    ///
+    /// Kept apart from file events so they are never treated as local
+    /// changes to sync.
+    pub fn subscribe_errors(&self) -> broadcast::Receiver<(DriveId, DriveEvent)> {
+        self.error_tx.subscribe()
+    }
+
     /// Start watching a drive's folder
     pub async fn watch(&self, drive_id: DriveId, path: PathBuf) -> Result<()> {
         // Check if already watching
@@ -91,6 +632,76 @@ impl FileWatcherManager {
             anyhow::bail!("Path is not a directory: {:?}", path);
         }
 
+        let ignore_files = load_ignore_rules(self.ignore_rules(), drive_id, path.clone()).await;
+        if ignore_files > 0 {
+            tracing::debug!(
+                "Loaded {} ignore file(s) for drive {}",
+                ignore_files,
+                drive_id
+            );
+        }
+
+        let config = self.config();
+        let use_native = match config.mode {
+            WatchMode::Native => true,
+            WatchMode::Polling => false,
+            WatchMode::Auto => match unreliable_fs_type(&path) {
+                Some(fs_type) => {
+                    tracing::info!(
+                        "Drive {} is on {} filesystem, using polling watcher",
+                        drive_id,
+                        fs_type
+                    );
+                    false
+                }
+                None => true,
+            },
+        };
+
+        let health = SharedHealth::default();
+        let backend = if use_native {
+            match self.start_native(drive_id, &path, health.clone()) {
+                Ok(backend) => backend,
+                Err(e) if config.mode == WatchMode::Auto => {
+                    tracing::warn!(
+                        "Native watcher failed for drive {}, falling back to polling: {}",
+                        drive_id,
+                        e
+                    );
+                    if let Ok(mut health) = health.lock() {
+                        health.fallback_reason = Some(e.to_string());
+                    }
+                    let interval = config.poll_interval_secs;
+                    self.start_polling(drive_id, path.clone(), interval, health.clone())
+                }
+                Err(e) => return Err(e),
+            }
+        } else {
+            let interval = config.poll_interval_secs;
+            self.start_polling(drive_id, path.clone(), interval, health.clone())
+        };
+
+        // Store watcher
+        let watched_drive = WatchedDrive {
+            _drive_id: drive_id,
+            root_path: path.clone(),
+            backend,
+            health,
+        };
+
+        self.watched.write().await.insert(drive_id, watched_drive);
+        tracing::info!("Started watching drive {} at {:?}", drive_id, path);
+
+        Ok(())
+    }
+
+    /// Watch a drive with OS change notifications
+    fn start_native(
+        &self,
+        drive_id: DriveId,
+        path: &Path,
+        health: SharedHealth,
+    ) -> Result<WatchBackend> {
         // Create channel for this watcher
         let (tx, mut rx) = mpsc::channel::<notify::Result<notify::Event>>(256);
 
@@ -104,33 +715,114 @@ impl FileWatcherManager {
 
         // Start watching
         let mut watcher = watcher;
-        watcher.watch(&path, RecursiveMode::Recursive)?;
+        watcher.watch(path, RecursiveMode::Recursive)?;
 
         // Spawn event processor task
         let drive_id_clone = drive_id;
-        let root_path = path.clone();
+        let root_path = path.to_path_buf();
         let node_id = self.node_id;
         let event_tx = self.event_tx.clone();
+        let ignored_dirs = self.ignored_dirs.clone();
+        let suspensions = self.suspensions.clone();
+        let coalescer = self.coalescer.clone();
+        let error_tx = self.error_tx.clone();
+        let hasher = self.hasher(drive_id);
+        let ignore_rules = self.ignore_rules();
+        let debounce_ms = self.debounce_ms.clone();
+        let config = self.config();
 
         tokio::spawn(async move {
             let mut pending_renames: HashMap<PathBuf, std::time::Instant> = HashMap::new();
+            let mut churn = ChurnMonitor::new(config.churn_threshold, Instant::now());
+            // Latest raw event per path while churn is high
+            let mut batch: HashMap<PathBuf, notify::Event> = HashMap::new();
+            let mut flush = tokio::time::interval(Duration::from_millis(config.churn_batch_ms));
+            flush.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut debouncer = Debouncer::new(Duration::from_millis(config.debounce_ms));
 
-            while let Some(res) = rx.recv().await {
-                match res {
-                    Ok(event) => {
-                        // Process the event
-                        if let Some(drive_event) =
-                            process_fs_event(&event, &root_path, &node_id, &mut pending_renames)
-                        {
-                            send_with_backpressure(
-                                &event_tx,
-                                (drive_id_clone, drive_event),
-                                "file_watcher",
+            let emit = |event: &notify::Event, pending: &mut HashMap<PathBuf, Instant>| {
+                if let Some(drive_event) =
+                    process_fs_event(event, &root_path, &node_id, &hasher, pending)
+                {
+                    if is_ignored_event(&ignore_rules, &drive_id_clone, &root_path, &drive_event) {
+                        return;
+                    }
+                    let strategy = drive_event.backpressure_strategy(Some(&*coalescer));
+                    send_with_backpressure(
+                        &event_tx,
+                        (drive_id_clone, drive_event),
+                        "file_watcher",
+                        strategy,
+                    );
+                    if let Ok(mut health) = health.lock() {
+                        health.record_events(1);
+                    }
+                }
+            };
+
+            loop {
+                let quiet = debouncer.next_due();
+                let event = tokio::select! {
+                    res = rx.recv() => match res {
+                        Some(Ok(event)) => event,
+                        Some(Err(e)) => {
+                            tracing::warn!(
+                                "File watcher error for drive {}: {}",
+                                drive_id_clone,
+                                e
                             );
+                            report_error(&health, &error_tx, drive_id_clone, e.to_string());
+                            continue;
+                        }
+                        None => break,
+                    },
+                    _ = flush.tick() => {
+                        let change = churn.record(0, Instant::now());
+                        update_churn(&health, &error_tx, drive_id_clone, &churn, change);
+                        for event in std::mem::take(&mut batch).into_values() {
+                            emit(&event, &mut pending_renames);
                         }
+                        continue;
                     }
-                    Err(e) => {
-                        tracing::warn!("File watcher error for drive {}: {}", drive_id_clone, e);
+                    _ = sleep_until_due(quiet) => {
+                        let due = debouncer.take_due(Instant::now());
+                        if !suspensions.read().await.contains_key(&drive_id_clone) {
+                            for event in due {
+                                emit(&event, &mut pending_renames);
+                            }
+                        }
+                        continue;
+                    }
+                };
+
+                if is_in_ignored_dir(&event, &ignored_dirs) {
+                    continue;
+                }
+                // A bulk operation owns this drive; resume will reconcile
+                if suspensions.read().await.contains_key(&drive_id_clone) {
+                    continue;
+                }
+                if event.paths.iter().any(|path| is_ignore_file(path)) {
+                    let rules = ignore_rules.clone();
+                    load_ignore_rules(rules, drive_id_clone, root_path.clone()).await;
+                }
+
+                let change = churn.record(1, Instant::now());
+                if change.is_some() {
+                    update_churn(&health, &error_tx, drive_id_clone, &churn, change);
+                }
+                if churn.throttled {
+                    if let Some(path) = event.paths.first() {
+                        batch.insert(path.clone(), event);
+                    }
+                    continue;
+                }
+                debouncer.window = Duration::from_millis(debounce_ms.load(Ordering::Relaxed));
+                let now = Instant::now();
+                // Churn just dropped: pass on what was held back first to keep order
+                for event in std::mem::take(&mut batch).into_values().chain([event]) {
+                    if let Some(event) = debouncer.push(event, now) {
+                        emit(&event, &mut pending_renames);
                     }
                 }
             }
@@ -138,17 +830,88 @@ impl FileWatcherManager {
             tracing::debug!("File watcher stopped for drive: {}", drive_id_clone);
         });
 
-        // Store watcher
-        let watched_drive = WatchedDrive {
-            _drive_id: drive_id,
-            _root_path: path.clone(),
-            _watcher: watcher,
-        };
+        Ok(WatchBackend::Native(watcher))
+    }
 
-        self.watched.write().await.insert(drive_id, watched_drive);
-        tracing::info!("Started watching drive {} at {:?}", drive_id, path);
+    /// Watch a drive by re-scanning it every `interval_secs`
+    ///
+    /// Each scan is reconciled against the previous one, the same way a
+    /// suspended watch is caught up on resume.
+    fn start_polling(
+        &self,
+        drive_id: DriveId,
+        root_path: PathBuf,
+        interval_secs: u64,
+        health: SharedHealth,
+    ) -> WatchBackend {
+        let node_id = self.node_id;
+        let event_tx = self.event_tx.clone();
+        let ignored_dirs = self.ignored_dirs.clone();
+        let suspensions = self.suspensions.clone();
+        let coalescer = self.coalescer.clone();
+        let error_tx = self.error_tx.clone();
+        let hasher = self.hasher(drive_id);
+        let ignore_rules = self.ignore_rules();
 
-        Ok(())
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(interval_secs));
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            let mut previous: Option<Snapshot> = None;
+
+            loop {
+                ticker.tick().await;
+
+                // A bulk operation owns this drive; resume reconciles it, so
+                // start from a fresh baseline afterwards
+                if suspensions.read().await.contains_key(&drive_id) {
+                    previous = None;
+                    continue;
+                }
+
+                let root = root_path.clone();
+                let dirs = ignored_dirs.clone();
+                let before = previous.take();
+                let hasher = hasher.clone();
+                let rules = ignore_rules.clone();
+                let scan = tokio::task::spawn_blocking(move || {
+                    let mut current = snapshot_files(&root);
+                    if let Ok(dirs) = dirs.read() {
+                        current.retain(|path, _| !dirs.iter().any(|dir| path.starts_with(dir)));
+                    }
+                    let mut events = before
+                        .map(|before| {
+                            let recorded = Recorded::new();
+                            reconcile_snapshots(
+                                &root, &before, &current, &node_id, &hasher, &recorded,
+                            )
+                        })
+                        .unwrap_or_default();
+                    filter_ignored(&rules, drive_id, &root, &mut events);
+                    (current, events)
+                })
+                .await;
+
+                let (current, events) = match scan {
+                    Ok(scan) => scan,
+                    Err(e) => {
+                        tracing::warn!("Polling scan failed for drive {}: {}", drive_id, e);
+                        report_error(&health, &error_tx, drive_id, format!("scan failed: {}", e));
+                        continue;
+                    }
+                };
+                if let Ok(mut health) = health.lock() {
+                    health.last_scan_at = Some(Utc::now());
+                    health.record_events(events.len());
+                }
+                for event in events {
+                    let strategy = event.backpressure_strategy(Some(&*coalescer));
+                    send_with_backpressure(&event_tx, (drive_id, event), "file_watcher", strategy);
+                }
+                previous = Some(current);
+            }
+        });
+
+        WatchBackend::Polling(handle)
     }
 
     /// Stop watching a drive
@@ -157,6 +920,167 @@ impl FileWatcherManager {
         if watched.remove(drive_id).is_some() {
             tracing::info!("Stopped watching drive: {}", drive_id);
         }
+        self.ignore_rules().remove(drive_id);
+        self.suspensions.write().await.remove(drive_id);
+    }
+
+    /// Stop emitting events for a drive while a bulk operation runs
+    ///
+    /// Returns a guard that resumes the watch when dropped, so an early
+    /// return or error cannot leave the drive suspended. Prefer calling
+    /// `WatchSuspension::resume` to wait for the reconciliation pass.
+    /// Suspensions nest; events resume after the last one ends.
+    pub async fn suspend_watch(self: &Arc<Self>, drive_id: DriveId) -> WatchSuspension {
+        let root_path = self
+            .watched
+            .read()
+            .await
+            .get(&drive_id)
+            .map(|w| w.root_path.clone());
+
+        if let Some(root_path) = root_path {
+            let nested = match self.suspensions.write().await.get_mut(&drive_id) {
+                Some(existing) => {
+                    existing.depth += 1;
+                    true
+                }
+                None => false,
+            };
+
+            if !nested {
+                // Snapshot before suspending so nothing slips between the two
+                let root = root_path.clone();
+                let snapshot = tokio::task::spawn_blocking(move || snapshot_files(&root))
+                    .await
+                    .unwrap_or_default();
+                self.suspensions
+                    .write()
+                    .await
+                    .entry(drive_id)
+                    .and_modify(|s| s.depth += 1)
+                    .or_insert(Suspension {
+                        depth: 1,
+                        root_path,
+                        snapshot,
+                        recorded: Recorded::new(),
+                    });
+                tracing::debug!("Suspended watch for drive {}", drive_id);
+            }
+        }
+
+        WatchSuspension {
+            manager: self.clone(),
+            drive_id,
+            active: true,
+        }
+    }
+
+    /// End one suspension and, if it was the last, reconcile the drive
+    ///
+    /// Emits one event per file created, modified, or deleted while the
+    /// watch was suspended. Returns the number of events emitted.
+    async fn resume_watch(&self, drive_id: &DriveId) -> usize {
+        let finished = {
+            let mut suspensions = self.suspensions.write().await;
+            match suspensions.get_mut(drive_id) {
+                Some(s) if s.depth > 1 => {
+                    s.depth -= 1;
+                    None
+                }
+                Some(_) => suspensions.remove(drive_id),
+                None => None,
+            }
+        };
+        let Some(suspension) = finished else {
+            return 0;
+        };
+        self.reconcile(drive_id, suspension).await
+    }
+
+    /// Emit one event per file that changed since `suspension` began
+    async fn reconcile(&self, drive_id: &DriveId, suspension: Suspension) -> usize {
+        let node_id = self.node_id;
+        let hasher = self.hasher(*drive_id);
+        let rules = self.ignore_rules();
+        let id = *drive_id;
+        let events = tokio::task::spawn_blocking(move || {
+            let root = &suspension.root_path;
+            let current = snapshot_files(root);
+            let mut events = reconcile_snapshots(
+                root,
+                &suspension.snapshot,
+                &current,
+                &node_id,
+                &hasher,
+                &suspension.recorded,
+            );
+            filter_ignored(&rules, id, root, &mut events);
+            events
+        })
+        .await
+        .unwrap_or_default();
+
+        let count = events.len();
+        for event in events {
+            let strategy = event.backpressure_strategy(Some(&*self.coalescer));
+            send_with_backpressure(&self.event_tx, (*drive_id, event), "file_watcher", strategy);
+        }
+        tracing::debug!(
+            "Resumed watch for drive {} ({} reconciled changes)",
+            drive_id,
+            count
+        );
+        count
+    }
+
+    /// Stop watching a drive until `restore_watch`
+    ///
+    /// Unlike `unwatch`, the drive's files are recorded first so changes
+    /// made while it is unwatched are reported when the watch is restored.
+    /// Returns false if the drive wasn't watched.
+    pub async fn drain_watch(&self, drive_id: &DriveId) -> bool {
+        let root_path = self
+            .watched
+            .read()
+            .await
+            .get(drive_id)
+            .map(|w| w.root_path.clone());
+        let Some(root_path) = root_path else {
+            return false;
+        };
+
+        // Snapshot before unwatching so nothing slips between the two
+        let root = root_path.clone();
+        let snapshot = tokio::task::spawn_blocking(move || snapshot_files(&root))
+            .await
+            .unwrap_or_default();
+        self.unwatch(drive_id).await;
+        self.drained.write().await.insert(
+            *drive_id,
+            Suspension {
+                depth: 1,
+                root_path,
+                snapshot,
+                recorded: Recorded::new(),
+            },
+        );
+        true
+    }
+
+    /// Watch a drive stopped by `drain_watch` again
+    ///
+    /// Emits one event per file created, modified, or deleted in between.
+    /// Returns the number of events, or None if the drive wasn't drained.
+    pub async fn restore_watch(&self, drive_id: &DriveId) -> Result<Option<usize>> {
+        let Some(drained) = self.drained.write().await.remove(drive_id) else {
+            return Ok(None);
+        };
+        if let Err(e) = self.watch(*drive_id, drained.root_path.clone()).await {
+            // Keep the snapshot so a later attempt still reconciles
+            self.drained.write().await.insert(*drive_id, drained);
+            return Err(e);
+        }
+        Ok(Some(self.reconcile(drive_id, drained).await))
     }
 
     /// Check if a drive is being watched
@@ -164,6 +1088,63 @@ impl FileWatcherManager {
         self.watched.read().await.contains_key(drive_id)
     }
 
+    /// Get a drive's watch state and recent activity
+    pub async fn status(&self, drive_id: &DriveId) -> WatchStatus {
+        let suspended = self.suspensions.read().await.contains_key(drive_id);
+        let watched = self.watched.read().await;
+        let Some(watched) = watched.get(drive_id) else {
+            return WatchStatus {
+                drive_id: drive_id.to_hex(),
+                watching: false,
+                backend: None,
+                root_path: None,
+                suspended,
+                events_total: 0,
+                events_recent: 0,
+                last_event_at: None,
+                last_scan_at: None,
+                fallback_reason: None,
+                errors: Vec::new(),
+                healthy: false,
+                events_per_sec: 0,
+                throttled: false,
+            };
+        };
+
+        let backend = match watched.backend {
+            WatchBackend::Native(_) => WatchMode::Native,
+            WatchBackend::Polling(_) => WatchMode::Polling,
+        };
+        let mut health = watched
+            .health
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        health.prune(Instant::now());
+        let last_error_at = health.errors.back().map(|e| e.at);
+        let healthy = match (last_error_at, health.last_event_at) {
+            (None, _) => true,
+            (Some(error_at), Some(event_at)) => event_at > error_at,
+            (Some(_), None) => false,
+        };
+
+        WatchStatus {
+            drive_id: drive_id.to_hex(),
+            watching: true,
+            backend: Some(backend),
+            root_path: Some(watched.root_path.to_string_lossy().to_string()),
+            suspended,
+            events_total: health.events_total,
+            events_recent: health.recent.len(),
+            last_event_at: health.last_event_at,
+            last_scan_at: health.last_scan_at,
+            fallback_reason: health.fallback_reason.clone(),
+            errors: health.errors.iter().cloned().collect(),
+            healthy,
+            events_per_sec: health.events_per_sec,
+            throttled: health.throttled,
+        }
+    }
+
     /// Get count of watched drives
     #[allow(dead_code)]
     pub async fn watched_count(&self) -> usize {
@@ -171,11 +1152,184 @@ impl FileWatcherManager {
     }
 }
 
+/// Scope of a watch suspension; resumes the watch when dropped
+pub struct WatchSuspension {
+    manager: Arc<FileWatcherManager>,
+    drive_id: DriveId,
+    active: bool,
+}
+
+impl WatchSuspension {
+    /// Resume the watch and wait for the reconciliation pass
+    ///
+    /// Returns the number of changes reconciled.
+    pub async fn resume(mut self) -> usize {
+        self.active = false;
+        self.manager.resume_watch(&self.drive_id).await
+    }
+
+    /// Note that the file at `path` was written with content `hash` and
+    /// its metadata already records it
+    ///
+    /// Reconciliation skips the file while its content still matches, and
+    /// otherwise reports it as changed by `author` rather than this node.
+    pub async fn record(&self, path: PathBuf, hash: String, author: NodeId) {
+        let mut suspensions = self.manager.suspensions.write().await;
+        if let Some(suspension) = suspensions.get_mut(&self.drive_id) {
+            suspension.recorded.insert(path, (hash, author));
+        }
+    }
+}
+
+impl Drop for WatchSuspension {
+    fn drop(&mut self) {
+        if !self.active {
+            return;
+        }
+        // Dropped without resume() (early return or error): resume in the background
+        let manager = self.manager.clone();
+        let drive_id = self.drive_id;
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async move {
+                manager.resume_watch(&drive_id).await;
+            });
+        }
+    }
+}
+
+/// Record a watcher error and send it to the frontend
+fn report_error(
+    health: &SharedHealth,
+    error_tx: &broadcast::Sender<(DriveId, DriveEvent)>,
+    drive_id: DriveId,
+    message: String,
+) {
+    let error = match health.lock() {
+        Ok(mut health) => health.record_error(message),
+        Err(_) => WatchError {
+            at: Utc::now(),
+            message,
+        },
+    };
+    let _ = error_tx.send((
+        drive_id,
+        DriveEvent::WatcherError {
+            message: error.message,
+            timestamp: error.at,
+        },
+    ));
+}
+
+/// Record a native watch's event rate and announce throttling changes
+fn update_churn(
+    health: &SharedHealth,
+    error_tx: &broadcast::Sender<(DriveId, DriveEvent)>,
+    drive_id: DriveId,
+    churn: &ChurnMonitor,
+    change: Option<bool>,
+) {
+    if let Ok(mut health) = health.lock() {
+        health.events_per_sec = churn.rate;
+        health.throttled = churn.throttled;
+    }
+    let Some(active) = change else {
+        return;
+    };
+    if active {
+        tracing::warn!(
+            "High churn on drive {} ({} events/s), batching changes",
+            drive_id,
+            churn.rate
+        );
+    } else {
+        tracing::info!("Churn on drive {} dropped, resuming per-event processing", drive_id);
+    }
+    let _ = error_tx.send((
+        drive_id,
+        DriveEvent::WatcherChurn {
+            active,
+            events_per_sec: churn.rate,
+            timestamp: Utc::now(),
+        },
+    ));
+}
+
+/// Record size and modification time of every non-ignored file under root
+fn snapshot_files(root: &Path) -> Snapshot {
+    walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| !should_ignore(e.path()))
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            if !metadata.is_file() {
+                return None;
+            }
+            Some((e.into_path(), (metadata.len(), metadata.modified().ok())))
+        })
+        .collect()
+}
+
+/// Turn the difference between two snapshots into drive events
+///
+/// Files in `recorded` are skipped while their content still matches the
+/// recorded hash, and otherwise keep the recorded author.
+fn reconcile_snapshots(
+    root_path: &Path,
+    before: &Snapshot,
+    after: &Snapshot,
+    node_id: &NodeId,
+    hasher: &FileHasher,
+    recorded: &Recorded,
+) -> Vec<DriveEvent> {
+    let mut events = Vec::new();
+
+    for (path, state) in after {
+        if before.get(path) == Some(state) {
+            continue;
+        }
+        let Ok(relative) = path.strip_prefix(root_path) else {
+            continue;
+        };
+        let modified_by = match recorded.get(path) {
+            // Large files are only sampled by the hasher, so compare in full
+            Some((hash, _)) if content_hash(path).as_ref() == Some(hash) => continue,
+            Some((_, author)) => *author,
+            None => *node_id,
+        };
+        if let Some((hash, size)) = hasher.hash(path, relative) {
+            events.push(DriveEvent::FileChanged {
+                path: relative.to_path_buf(),
+                hash,
+                size,
+                modified_by,
+                timestamp: Utc::now(),
+                created: false,
+                content_type: content_type::detect_file(path),
+            });
+        }
+    }
+
+    for path in before.keys().filter(|p| !after.contains_key(*p)) {
+        if let Ok(relative) = path.strip_prefix(root_path) {
+            events.push(DriveEvent::FileDeleted {
+                path: relative.to_path_buf(),
+                deleted_by: *node_id,
+                timestamp: Utc::now(),
+            });
+        }
+    }
+
+    events
+}
+
 /// Process a file system event and convert to DriveEvent if applicable
 fn process_fs_event(
     event: &notify::Event,
     root_path: &Path,
     node_id: &NodeId,
+    hasher: &FileHasher,
     _pending_renames: &mut HashMap<PathBuf, std::time::Instant>,
 ) -> Option<DriveEvent> {
     // Get the first path from the event
@@ -192,7 +1346,7 @@ fn process_fs_event(
     match &event.kind {
         EventKind::Create(CreateKind::File) | EventKind::Modify(ModifyKind::Data(_)) => {
             // File created or modified
-            let (hash, size) = compute_file_info(path)?;
+            let (hash, size) = hasher.hash(path, &relative_path)?;
 
             Some(DriveEvent::FileChanged {
                 path: relative_path,
@@ -200,6 +1354,8 @@ fn process_fs_event(
                 size,
                 modified_by: *node_id,
                 timestamp: Utc::now(),
+                created: false,
+                content_type: content_type::detect_file(path),
             })
         }
 
@@ -211,6 +1367,8 @@ fn process_fs_event(
                 size: 0,
                 modified_by: *node_id,
                 timestamp: Utc::now(),
+                created: false,
+                content_type: None,
             })
         }
 
@@ -231,13 +1389,15 @@ fn process_fs_event(
                 // Emit delete for old path, create for new path
                 // For now, just emit the new file as changed
                 if new_path.exists() {
-                    let (hash, size) = compute_file_info(new_path)?;
+                    let (hash, size) = hasher.hash(new_path, &new_relative)?;
                     Some(DriveEvent::FileChanged {
                         path: new_relative,
                         hash,
                         size,
                         modified_by: *node_id,
                         timestamp: Utc::now(),
+                        created: false,
+                        content_type: content_type::detect_file(new_path),
                     })
                 } else {
                     None
@@ -254,6 +1414,99 @@ fn process_fs_event(
     }
 }
 
+/// Filesystem type of `path` if native notifications are unreliable on it
+#[cfg(target_os = "linux")]
+fn unreliable_fs_type(path: &Path) -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+    let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let fs_type = mount_fs_type(&mounts, &path)?;
+    UNRELIABLE_FS_TYPES
+        .contains(&fs_type)
+        .then(|| fs_type.to_string())
+}
+
+/// Filesystem type of `path` if native notifications are unreliable on it
+#[cfg(not(target_os = "linux"))]
+fn unreliable_fs_type(_path: &Path) -> Option<String> {
+    None
+}
+
+/// Filesystem type of the longest mount point containing `path`
+///
+/// `mounts` is in `/proc/mounts` format: device, mount point, type, ...
+#[cfg(any(target_os = "linux", test))]
+fn mount_fs_type<'a>(mounts: &'a str, path: &Path) -> Option<&'a str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            path.starts_with(&mount_point).then_some((mount_point.len(), fs_type))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, fs_type)| fs_type)
+}
+
+/// Coalescing key for watcher events: one pending change per drive and file
+fn coalesce_key(msg: &(DriveId, DriveEvent)) -> Option<String> {
+    let (drive_id, event) = msg;
+    event.file_path().map(|path| format!("{}:{}", drive_id, path.display()))
+}
+
+/// Re-read a drive's `.gixignore` files off the async runtime
+async fn load_ignore_rules(rules: Arc<IgnoreRules>, drive_id: DriveId, root: PathBuf) -> usize {
+    tokio::task::spawn_blocking(move || rules.load(drive_id, &root))
+        .await
+        .unwrap_or(0)
+}
+
+/// Check if an event is for a path the drive's `.gixignore` files ignore
+fn is_ignored_event(
+    rules: &IgnoreRules,
+    drive_id: &DriveId,
+    root_path: &Path,
+    event: &DriveEvent,
+) -> bool {
+    event
+        .file_path()
+        .is_some_and(|path| rules.is_ignored(drive_id, path, root_path.join(path).is_dir()))
+}
+
+/// Drop events for ignored paths, re-reading the rules first if a
+/// `.gixignore` file is among the changes
+fn filter_ignored(
+    rules: &IgnoreRules,
+    drive_id: DriveId,
+    root_path: &Path,
+    events: &mut Vec<DriveEvent>,
+) {
+    if events
+        .iter()
+        .any(|event| event.file_path().is_some_and(is_ignore_file))
+    {
+        rules.load(drive_id, root_path);
+    }
+    events.retain(|event| !is_ignored_event(rules, &drive_id, root_path, event));
+}
+
+/// Check if any of an event's paths is inside one of the ignored directories
+///
+/// A rename has both its source and target checked, so moving a file into
+/// or out of an ignored directory is skipped as well.
+fn is_in_ignored_dir(
+    event: &notify::Event,
+    ignored_dirs: &std::sync::RwLock<Vec<PathBuf>>,
+) -> bool {
+    match ignored_dirs.read() {
+        Ok(dirs) => event
+            .paths
+            .iter()
+            .any(|path| dirs.iter().any(|dir| path.starts_with(dir))),
+        Err(_) => false,
+    }
+}
+
 /// Check if a path should be ignored
 fn should_ignore(path: &Path) -> bool {
     let path_str = path.to_string_lossy();
@@ -286,12 +1539,52 @@ fn should_ignore(path: &Path) -> bool {
     false
 }
 
+/// Hashes changed files for a drive's events
+///
+/// Files the OS won't let us read are recorded as permission errors and
+/// skipped until they change, rather than retried on every event.
+#[derive(Clone)]
+struct FileHasher {
+    drive_id: DriveId,
+    errors: Option<Arc<PermissionErrors>>,
+}
+
+impl FileHasher {
+    /// Hash and size of `path`, or None if it can't be read
+    fn hash(&self, path: &Path, relative: &Path) -> Option<(String, u64)> {
+        let Some(errors) = self.errors.as_deref() else {
+            return compute_file_info(path).ok();
+        };
+        if errors.should_skip(&self.drive_id, relative, path) {
+            return None;
+        }
+        match compute_file_info(path) {
+            Ok(info) => {
+                errors.clear(&self.drive_id, relative);
+                Some(info)
+            }
+            Err(e) => {
+                errors.record(&self.drive_id, relative, path, FsOperation::Read, &e);
+                None
+            }
+        }
+    }
+}
+
+/// BLAKE3 hash (hex) of a file's full content
+fn content_hash(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = blake3::Hasher::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finalize().to_hex().to_string())
+}
+
 /// Compute BLAKE3 hash and size for a file
-fn compute_file_info(path: &Path) -> Option<(String, u64)> {
-    let metadata = std::fs::metadata(path).ok()?;
+fn compute_file_info(path: &Path) -> std::io::Result<(String, u64)> {
+    let metadata = std::fs::metadata(path)?;
 
     if metadata.is_dir() {
-        return Some((String::new(), 0));
+        return Ok((String::new(), 0));
     }
 
     let size = metadata.len();
@@ -300,25 +1593,25 @@ fn compute_file_info(path: &Path) -> Option<(String, u64)> {
     // For large files, we could sample or defer
     if size <= 10 * 1024 * 1024 {
         // 10MB limit for inline hashing
-        let data = std::fs::read(path).ok()?;
+        let data = std::fs::read(path)?;
         let hash = blake3::hash(&data);
-        Some((hash.to_hex().to_string(), size))
+        Ok((hash.to_hex().to_string(), size))
     } else {
         // For large files, hash first/last chunks + size
         // This is faster but less accurate - good enough for change detection
-        let mut file = std::fs::File::open(path).ok()?;
+        let mut file = std::fs::File::open(path)?;
         let mut hasher = blake3::Hasher::new();
 
         // Hash first 1MB
         let mut buffer = vec![0u8; 1024 * 1024];
         use std::io::Read;
-        let n = file.read(&mut buffer).ok()?;
+        let n = file.read(&mut buffer)?;
         hasher.update(&buffer[..n]);
 
         // Hash the size as well for uniqueness
         hasher.update(&size.to_le_bytes());
 
-        Some((hasher.finalize().to_hex().to_string(), size))
+        Ok((hasher.finalize().to_hex().to_string(), size))
     }
 }
 
@@ -336,6 +1629,30 @@ mod tests {
         assert!(!should_ignore(Path::new("/project/README.md")));
     }
 
+    #[test]
+    fn test_watch_health() {
+        let mut health = WatchHealth::default();
+        health.record_events(0);
+        assert_eq!(health.events_total, 0);
+        assert!(health.last_event_at.is_none());
+
+        health.record_events(3);
+        assert_eq!(health.events_total, 3);
+        assert_eq!(health.recent.len(), 3);
+        assert!(health.last_event_at.is_some());
+
+        // Events older than the window no longer count as recent
+        health.prune(Instant::now() + RECENT_EVENTS_WINDOW + Duration::from_secs(1));
+        assert!(health.recent.is_empty());
+        assert_eq!(health.events_total, 3);
+
+        for i in 0..MAX_WATCH_ERRORS + 2 {
+            health.record_error(format!("error {}", i));
+        }
+        assert_eq!(health.errors.len(), MAX_WATCH_ERRORS);
+        assert_eq!(health.errors.front().unwrap().message, "error 2");
+    }
+
     #[test]
     fn test_ignore_patterns() {
         // Test various patterns
@@ -344,4 +1661,323 @@ mod tests {
         assert!(should_ignore(Path::new("/test.swp")));
         assert!(should_ignore(Path::new("/doc.tmp")));
     }
+
+    #[test]
+    fn test_ignored_dirs() {
+        let ignored = std::sync::RwLock::new(vec![PathBuf::from("/project/.downloads")]);
+        let event = |path: &str| {
+            notify::Event::new(EventKind::Create(CreateKind::File)).add_path(PathBuf::from(path))
+        };
+
+        assert!(is_in_ignored_dir(&event("/project/.downloads/xfer_1.download"), &ignored));
+        assert!(!is_in_ignored_dir(&event("/project/src/main.rs"), &ignored));
+        assert!(!is_in_ignored_dir(&event("/project/.downloads-old/a.txt"), &ignored));
+
+        // Either side of a rename counts
+        let rename = |from: &str, to: &str| {
+            notify::Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+                .add_path(PathBuf::from(from))
+                .add_path(PathBuf::from(to))
+        };
+        let temp = "/project/.downloads/xfer_1.download";
+        let (a, b) = ("/project/a.txt", "/project/b.txt");
+        assert!(is_in_ignored_dir(&rename(temp, a), &ignored));
+        assert!(is_in_ignored_dir(&rename(a, temp), &ignored));
+        assert!(!is_in_ignored_dir(&rename(a, b), &ignored));
+    }
+
+    #[test]
+    fn test_mount_fs_type() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+                      server:/export /mnt/share nfs4 rw 0 0\n\
+                      drvfs /mnt/c 9p rw 0 0\n\
+                      /dev/sdb1 /mnt/my\\040disk ext4 rw 0 0\n";
+
+        assert_eq!(mount_fs_type(mounts, Path::new("/home/me/drive")), Some("ext4"));
+        assert_eq!(mount_fs_type(mounts, Path::new("/mnt/share/team")), Some("nfs4"));
+        assert_eq!(mount_fs_type(mounts, Path::new("/mnt/c/Users")), Some("9p"));
+        assert_eq!(mount_fs_type(mounts, Path::new("/mnt/my disk/a")), Some("ext4"));
+        // Component-wise match: /mnt/shared is not under /mnt/share
+        assert_eq!(mount_fs_type(mounts, Path::new("/mnt/shared")), Some("ext4"));
+    }
+
+    #[test]
+    fn test_watch_config_defaults() {
+        let config: WatchConfig = serde_json::from_str(r#"{"mode":"polling"}"#).unwrap();
+        assert_eq!(config.mode, WatchMode::Polling);
+        assert_eq!(config.poll_interval_secs, 10);
+
+        assert_eq!(config.churn_threshold, 500);
+
+        let config = WatchConfig {
+            mode: WatchMode::Auto,
+            poll_interval_secs: 0,
+            churn_threshold: 0,
+            churn_batch_ms: u64::MAX,
+            debounce_ms: u64::MAX,
+        };
+        assert_eq!(config.normalized().poll_interval_secs, MIN_POLL_INTERVAL_SECS);
+        assert_eq!(config.normalized().churn_threshold, MIN_CHURN_THRESHOLD);
+        assert_eq!(config.normalized().churn_batch_ms, MAX_CHURN_BATCH_MS);
+        assert_eq!(config.normalized().debounce_ms, MAX_DEBOUNCE_MS);
+    }
+
+    #[test]
+    fn test_churn_monitor() {
+        let start = Instant::now();
+        let mut churn = ChurnMonitor::new(100, start);
+
+        // Rate is only judged once a full window has passed
+        assert_eq!(churn.record(500, start), None);
+        assert_eq!(churn.record(0, start + CHURN_WINDOW), Some(true));
+        assert_eq!(churn.rate, 500);
+
+        // Below the threshold but above half of it: keep batching
+        assert_eq!(churn.record(60, start + CHURN_WINDOW * 2), None);
+        assert!(churn.throttled);
+
+        // Quiet window ends batching
+        assert_eq!(churn.record(10, start + CHURN_WINDOW * 3), Some(false));
+        assert!(!churn.throttled);
+    }
+
+    #[test]
+    fn test_debouncer() {
+        let start = Instant::now();
+        let window = Duration::from_millis(300);
+        let mut debouncer = Debouncer::new(window);
+        let event =
+            |kind: EventKind, path: &str| notify::Event::new(kind).add_path(PathBuf::from(path));
+        let write = EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Any));
+        let remove = EventKind::Remove(RemoveKind::File);
+        let create = EventKind::Create(CreateKind::File);
+
+        // Writes to one path collapse into the last, due once it is quiet
+        for i in 0..5 {
+            let at = start + Duration::from_millis(100 * i);
+            assert!(debouncer.push(event(write, "/d/a.txt"), at).is_none());
+        }
+        debouncer.push(event(write, "/d/b.txt"), start);
+        assert_eq!(debouncer.next_due(), Some(start + window));
+        let due = debouncer.take_due(start + window);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].paths[0], PathBuf::from("/d/b.txt"));
+        assert_eq!(debouncer.take_due(start + window * 2).len(), 0);
+        let quiet = start + Duration::from_millis(700);
+        assert_eq!(debouncer.take_due(quiet).len(), 1);
+        assert!(debouncer.next_due().is_none());
+
+        // A delete survives a create of the same name
+        debouncer.push(event(write, "/d/c.txt"), start);
+        debouncer.push(event(remove, "/d/c.txt"), start);
+        debouncer.push(event(create, "/d/c.txt"), start);
+        debouncer.push(event(write, "/d/c.txt"), start);
+        let kinds: Vec<EventKind> = debouncer
+            .take_due(start + window)
+            .into_iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(kinds, vec![remove, write]);
+
+        // No window: events pass straight through
+        let mut debouncer = Debouncer::new(Duration::ZERO);
+        assert!(debouncer.push(event(write, "/d/a.txt"), start).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_polling_watch_detects_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = FileWatcherManager::new(NodeId([7u8; 32]));
+        manager.set_config(WatchConfig {
+            mode: WatchMode::Polling,
+            poll_interval_secs: 1,
+            ..Default::default()
+        });
+        let mut rx = manager.subscribe();
+
+        let drive_id = DriveId([1u8; 32]);
+        manager.watch(drive_id, dir.path().to_path_buf()).await.unwrap();
+        assert_eq!(manager.polling_drives().await, vec![drive_id]);
+
+        // Let the first scan take its baseline
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        std::fs::write(dir.path().join("new.txt"), b"hello").unwrap();
+
+        let (id, event) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(id, drive_id);
+        assert!(matches!(
+            event,
+            DriveEvent::FileChanged { path, .. } if path == Path::new("new.txt")
+        ));
+
+        manager.unwatch(&drive_id).await;
+    }
+
+    #[tokio::test]
+    async fn test_polling_watch_skips_ignored_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(".gixignore"), "*.bak\n").unwrap();
+        let manager = FileWatcherManager::new(NodeId([7u8; 32]));
+        manager.set_config(WatchConfig {
+            mode: WatchMode::Polling,
+            poll_interval_secs: 1,
+            ..Default::default()
+        });
+        let mut rx = manager.subscribe();
+
+        let drive_id = DriveId([1u8; 32]);
+        assert!(manager.reload_ignore_rules(&drive_id).await.is_err());
+        manager.watch(drive_id, dir.path().to_path_buf()).await.unwrap();
+        assert_eq!(manager.reload_ignore_rules(&drive_id).await.unwrap(), 1);
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        std::fs::write(dir.path().join("old.bak"), b"skip").unwrap();
+        std::fs::write(dir.path().join("kept.txt"), b"sync").unwrap();
+
+        let (_, event) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            event,
+            DriveEvent::FileChanged { path, .. } if path == Path::new("kept.txt")
+        ));
+
+        // New rules apply to changes seen in the same scan
+        std::fs::write(dir.path().join(".gixignore"), "*.txt\n*.log\n").unwrap();
+        std::fs::write(dir.path().join("new.txt"), b"skip").unwrap();
+        std::fs::write(dir.path().join("extra.bak"), b"sync").unwrap();
+
+        let mut changed = Vec::new();
+        for _ in 0..2 {
+            let (_, event) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            changed.push(event.file_path().unwrap().to_string_lossy().to_string());
+        }
+        changed.sort();
+        assert_eq!(changed, vec![".gixignore", "extra.bak"]);
+
+        manager.unwatch(&drive_id).await;
+    }
+
+    #[tokio::test]
+    async fn test_drain_and_restore_watch() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = FileWatcherManager::new(NodeId([7u8; 32]));
+        manager.set_config(WatchConfig {
+            mode: WatchMode::Polling,
+            poll_interval_secs: 1,
+            ..Default::default()
+        });
+        let mut rx = manager.subscribe();
+
+        let drive_id = DriveId([1u8; 32]);
+        assert!(!manager.drain_watch(&drive_id).await);
+        manager.watch(drive_id, dir.path().to_path_buf()).await.unwrap();
+        assert!(manager.drain_watch(&drive_id).await);
+        assert!(!manager.is_watching(&drive_id).await);
+
+        // Changed while drained: reported once the watch is restored
+        std::fs::write(dir.path().join("during.txt"), b"hello").unwrap();
+        assert_eq!(manager.restore_watch(&drive_id).await.unwrap(), Some(1));
+        assert!(manager.is_watching(&drive_id).await);
+        assert_eq!(manager.restore_watch(&drive_id).await.unwrap(), None);
+
+        let (_, event) = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            event,
+            DriveEvent::FileChanged { path, .. } if path == Path::new("during.txt")
+        ));
+
+        manager.unwatch(&drive_id).await;
+    }
+
+    #[test]
+    fn test_reconcile_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::write(root.join("kept.txt"), b"same").unwrap();
+        std::fs::write(root.join("edited.txt"), b"before").unwrap();
+        std::fs::write(root.join("removed.txt"), b"gone soon").unwrap();
+        let before = snapshot_files(root);
+
+        std::fs::write(root.join("edited.txt"), b"after the edit").unwrap();
+        std::fs::remove_file(root.join("removed.txt")).unwrap();
+        std::fs::write(root.join("added.txt"), b"new").unwrap();
+        let after = snapshot_files(root);
+
+        let node_id = NodeId([7u8; 32]);
+        let hasher = FileHasher {
+            drive_id: DriveId([1u8; 32]),
+            errors: None,
+        };
+        let events =
+            reconcile_snapshots(root, &before, &after, &node_id, &hasher, &Recorded::new());
+
+        let mut changed: Vec<String> = events
+            .iter()
+            .filter_map(|e| match e {
+                DriveEvent::FileChanged { path, .. } => Some(path.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        changed.sort();
+        assert_eq!(changed, vec!["added.txt", "edited.txt"]);
+        assert!(events.iter().any(|e| matches!(
+            e,
+            DriveEvent::FileDeleted { path, .. } if path == Path::new("removed.txt")
+        )));
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn test_reconcile_keeps_recorded_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let before = snapshot_files(root);
+
+        std::fs::write(root.join("merged.txt"), b"from the source").unwrap();
+        std::fs::write(root.join("stale.txt"), b"edited after merging").unwrap();
+        std::fs::write(root.join("local.txt"), b"written here").unwrap();
+        let after = snapshot_files(root);
+
+        let node_id = NodeId([7u8; 32]);
+        let author = NodeId([9u8; 32]);
+        let hasher = FileHasher {
+            drive_id: DriveId([1u8; 32]),
+            errors: None,
+        };
+        let hash = |content: &[u8]| blake3::hash(content).to_hex().to_string();
+        let recorded = Recorded::from([
+            (root.join("merged.txt"), (hash(b"from the source"), author)),
+            (root.join("stale.txt"), (hash(b"as merged"), author)),
+        ]);
+        let events = reconcile_snapshots(root, &before, &after, &node_id, &hasher, &recorded);
+
+        let mut changed: Vec<(String, NodeId)> = events
+            .iter()
+            .filter_map(|e| match e {
+                DriveEvent::FileChanged {
+                    path, modified_by, ..
+                } => Some((path.to_string_lossy().to_string(), *modified_by)),
+                _ => None,
+            })
+            .collect();
+        changed.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            changed,
+            vec![
+                ("local.txt".to_string(), node_id),
+                ("stale.txt".to_string(), author)
+            ]
+        );
+    }
 }
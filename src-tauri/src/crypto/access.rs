@@ -121,72 +121,81 @@ impl PathRule {
 
     /// Check if the path matches this rule
     pub fn matches(&self, path: &str) -> bool {
-        // Simple glob matching: * matches any characters within a segment, ** matches any path
-        let pattern = self.pattern.trim_start_matches('/');
-        let path = path.trim_start_matches('/');
-
-        if pattern == "**" {
-            return true;
-        }
+        glob_matches(&self.pattern, path)
+    }
+}
 
-        if pattern.contains("**") {
-            // Double star: match any path
-            let parts: Vec<&str> = pattern.split("**").collect();
-            if parts.len() == 2 {
-                let prefix = parts[0].trim_end_matches('/');
-                let suffix = parts[1].trim_start_matches('/');
+/// Check if a drive-relative path matches a glob pattern
+///
+/// `*` matches characters within one path segment and `**` matches any
+/// number of segments, so `src/*.rs`, `*.log` and `node_modules/**` behave
+/// the same wherever path patterns are used.
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    // Simple glob matching: * matches any characters within a segment, ** matches any path
+    let pattern = pattern.trim_start_matches('/');
+    let path = path.trim_start_matches('/');
 
-                if !prefix.is_empty() && !path.starts_with(prefix) {
-                    return false;
-                }
-                if !suffix.is_empty() && !path.ends_with(suffix) {
-                    return false;
-                }
-                return true;
-            }
-        }
+    if pattern == "**" {
+        return true;
+    }
 
-        // Single star: match characters within a path segment
-        if pattern.contains('*') && !pattern.contains("**") {
-            let pattern_parts: Vec<&str> = pattern.split('/').collect();
-            let path_parts: Vec<&str> = path.split('/').collect();
+    if pattern.contains("**") {
+        // Double star: match any path
+        let parts: Vec<&str> = pattern.split("**").collect();
+        if parts.len() == 2 {
+            let prefix = parts[0].trim_end_matches('/');
+            let suffix = parts[1].trim_start_matches('/');
 
-            if pattern_parts.len() != path_parts.len() {
+            if !prefix.is_empty() && !path.starts_with(prefix) {
                 return false;
             }
-
-            for (pp, pathp) in pattern_parts.iter().zip(path_parts.iter()) {
-                if !Self::segment_matches(pp, pathp) {
-                    return false;
-                }
+            if !suffix.is_empty() && !path.ends_with(suffix) {
+                return false;
             }
             return true;
         }
-
-        // Exact match
-        pattern == path
     }
 
-    /// Match a single path segment with potential wildcards
-    fn segment_matches(pattern: &str, segment: &str) -> bool {
-        if pattern == "*" {
-            return true;
-        }
-        if !pattern.contains('*') {
-            return pattern == segment;
+    // Single star: match characters within a path segment
+    if pattern.contains('*') && !pattern.contains("**") {
+        let pattern_parts: Vec<&str> = pattern.split('/').collect();
+        let path_parts: Vec<&str> = path.split('/').collect();
+
+        if pattern_parts.len() != path_parts.len() {
+            return false;
         }
-        // Handle patterns like "*.txt" or "prefix*"
-        let parts: Vec<&str> = pattern.split('*').collect();
-        if parts.len() == 2 {
-            let prefix = parts[0];
-            let suffix = parts[1];
-            return segment.starts_with(prefix)
-                && segment.ends_with(suffix)
-                && segment.len() >= prefix.len() + suffix.len();
+
+        for (pp, pathp) in pattern_parts.iter().zip(path_parts.iter()) {
+            if !segment_matches(pp, pathp) {
+                return false;
+            }
         }
-        // Fall back to exact match for complex patterns
-        pattern == segment
+        return true;
     }
+
+    // Exact match
+    pattern == path
+}
+
+/// Match a single path segment with potential wildcards
+fn segment_matches(pattern: &str, segment: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    if !pattern.contains('*') {
+        return pattern == segment;
+    }
+    // Handle patterns like "*.txt" or "prefix*"
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 2 {
+        let prefix = parts[0];
+        let suffix = parts[1];
+        return segment.starts_with(prefix)
+            && segment.ends_with(suffix)
+            && segment.len() >= prefix.len() + suffix.len();
+    }
+    // Fall back to exact match for complex patterns
+    pattern == segment
 }
 
 /// Access Control List for a shared drive
@@ -259,16 +268,22 @@ impl AccessControlList {
 
     /// Check if a user has at least the required permission for a path
     pub fn check_permission(&self, node_id: &str, path: &str, required: Permission) -> bool {
+        self.effective_permission(node_id, path)
+            .is_some_and(|permission| permission.satisfies(required))
+    }
+
+    /// Get the highest permission a user has for a path
+    ///
+    /// Applies the user's base permission and then the path rules. Returns
+    /// `None` if the user has no access or a deny rule matches the path.
+    pub fn effective_permission(&self, node_id: &str, path: &str) -> Option<Permission> {
         // Owner always bypasses path rules
         if self.is_owner(node_id) {
-            return true;
+            return Some(Permission::Admin);
         }
 
-        // Get base permission
-        let base_permission = match self.get_user_permission(node_id) {
-            Some(p) => p,
-            None => return false, // No access
-        };
+        // Get base permission (None means no access)
+        let base_permission = self.get_user_permission(node_id)?;
 
         // Check path rules (evaluated in order, last match wins)
         let mut effective_permission = base_permission;
@@ -289,10 +304,10 @@ impl AccessControlList {
         }
 
         if denied {
-            return false;
+            return None;
         }
 
-        effective_permission.satisfies(required)
+        Some(effective_permission)
     }
 
     /// Get all users with access
@@ -314,12 +329,62 @@ impl AccessControlList {
         &self.path_rules
     }
 
+    /// Changes needed for users' base permissions to match `desired`
+    ///
+    /// Users missing from `desired` are revoked. The owner is never part of
+    /// the diff, whether listed or not. Sorted by node ID.
+    pub fn diff_members(&self, desired: &HashMap<String, Permission>) -> Vec<AclChange> {
+        let mut changes: Vec<AclChange> = Vec::new();
+
+        for (node_id, permission) in desired {
+            if self.is_owner(node_id) {
+                continue;
+            }
+            let current = self.get_user_permission(node_id);
+            if current != Some(*permission) {
+                changes.push(AclChange {
+                    node_id: node_id.clone(),
+                    from: current,
+                    to: Some(*permission),
+                });
+            }
+        }
+
+        for node_id in self.user_rules.keys() {
+            if self.is_owner(node_id) || desired.contains_key(node_id) {
+                continue;
+            }
+            // Expired rules already grant nothing
+            if let Some(current) = self.get_user_permission(node_id) {
+                changes.push(AclChange {
+                    node_id: node_id.clone(),
+                    from: Some(current),
+                    to: None,
+                });
+            }
+        }
+
+        changes.sort_by(|a, b| a.node_id.cmp(&b.node_id));
+        changes
+    }
+
     /// Remove expired rules
     pub fn cleanup_expired(&mut self) {
         self.user_rules.retain(|_, rule| rule.is_valid());
     }
 }
 
+/// A change to one user's base permission
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AclChange {
+    /// NodeId (hex) of the user
+    pub node_id: String,
+    /// Current permission (None = no access)
+    pub from: Option<Permission>,
+    /// New permission (None = revoke)
+    pub to: Option<Permission>,
+}
+
 /// Result of a permission check
 #[derive(Debug, Clone, Serialize)]
 pub struct PermissionCheckResult {
@@ -420,6 +485,62 @@ mod tests {
         assert_eq!(acl.get_user_permission("user456"), None);
     }
 
+    #[test]
+    fn test_acl_diff_members() {
+        let mut acl = AccessControlList::new("owner123");
+        acl.grant("keep", AccessRule::new(Permission::Read, "owner123"));
+        acl.grant("upgrade", AccessRule::new(Permission::Read, "owner123"));
+        acl.grant("remove", AccessRule::new(Permission::Write, "owner123"));
+        acl.grant(
+            "expired",
+            AccessRule::new(Permission::Write, "owner123")
+                .with_expiry(Utc::now() - chrono::Duration::hours(1)),
+        );
+
+        let desired: HashMap<String, Permission> = [
+            ("owner123", Permission::Read),
+            ("keep", Permission::Read),
+            ("upgrade", Permission::Manage),
+            ("new", Permission::Write),
+        ]
+        .into_iter()
+        .map(|(id, p)| (id.to_string(), p))
+        .collect();
+
+        let changes = acl.diff_members(&desired);
+        assert_eq!(
+            changes,
+            vec![
+                AclChange {
+                    node_id: "new".to_string(),
+                    from: None,
+                    to: Some(Permission::Write),
+                },
+                AclChange {
+                    node_id: "remove".to_string(),
+                    from: Some(Permission::Write),
+                    to: None,
+                },
+                AclChange {
+                    node_id: "upgrade".to_string(),
+                    from: Some(Permission::Read),
+                    to: Some(Permission::Manage),
+                },
+            ]
+        );
+
+        // Applying the changes leaves nothing to do
+        for change in changes {
+            match change.to {
+                Some(p) => acl.grant(&change.node_id, AccessRule::new(p, "owner123")),
+                None => {
+                    acl.revoke(&change.node_id);
+                }
+            }
+        }
+        assert!(acl.diff_members(&desired).is_empty());
+    }
+
     #[test]
     fn test_acl_path_restriction() {
         let mut acl = AccessControlList::new("owner123");
@@ -449,6 +570,23 @@ mod tests {
         assert!(acl.check_permission("owner123", ".git/config", Permission::Read));
     }
 
+    #[test]
+    fn test_acl_effective_permission() {
+        let mut acl = AccessControlList::new("owner123");
+        acl.grant("user456", AccessRule::new(Permission::Manage, "owner123"));
+        acl.add_path_rule(PathRule::allow("private/**", Permission::Read));
+        acl.add_path_rule(PathRule::deny(".git/**"));
+
+        assert_eq!(acl.effective_permission("user456", "notes.txt"), Some(Permission::Manage));
+        assert_eq!(
+            acl.effective_permission("user456", "private/secret.txt"),
+            Some(Permission::Read)
+        );
+        assert_eq!(acl.effective_permission("user456", ".git/config"), None);
+        assert_eq!(acl.effective_permission("owner123", ".git/config"), Some(Permission::Admin));
+        assert_eq!(acl.effective_permission("stranger", "notes.txt"), None);
+    }
+
     #[test]
     fn test_expired_rule_no_access() {
         let mut acl = AccessControlList::new("owner123");
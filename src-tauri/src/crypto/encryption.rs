@@ -25,6 +25,21 @@ const CHUNK_SIZE: usize = 64 * 1024;
 /// Current encryption version for forward compatibility
 const ENCRYPTION_VERSION: u8 = 1;
 
+/// Bytes `DriveEncryption::encrypt` adds to a payload (version + nonce + tag)
+pub const PAYLOAD_OVERHEAD: u64 = (1 + NONCE_SIZE + TAG_SIZE) as u64;
+
+/// Plaintext size of a payload written by `DriveEncryption::encrypt`
+///
+/// Judged from the version byte and length alone, so a match is not proof
+/// the data is encrypted; only decryption authenticates it. Returns None if
+/// the data cannot be such a payload.
+pub fn payload_plaintext_len(first_byte: u8, stored_len: u64) -> Option<u64> {
+    if first_byte != ENCRYPTION_VERSION {
+        return None;
+    }
+    stored_len.checked_sub(PAYLOAD_OVERHEAD)
+}
+
 #[derive(Error, Debug)]
 pub enum EncryptionError {
     #[error("Encryption failed: {0}")]
@@ -82,6 +97,11 @@ impl DriveKey {
     pub fn derive_metadata_key(&self) -> [u8; 32] {
         blake3::derive_key("gix-drive:metadata-key", &self.bytes)
     }
+
+    /// Derive the key peers use to prove they hold this drive key
+    pub fn derive_proof_key(&self) -> [u8; 32] {
+        blake3::derive_key("gix-drive:key-proof", &self.bytes)
+    }
 }
 
 impl std::fmt::Debug for DriveKey {
@@ -392,6 +412,23 @@ mod tests {
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
 
+    #[test]
+    fn test_payload_plaintext_len() {
+        let encryption = DriveEncryption::generate();
+        let plaintext = b"Hello, encrypted world!";
+        let ciphertext = encryption.encrypt(plaintext, "test.txt").unwrap();
+
+        assert_eq!(
+            payload_plaintext_len(ciphertext[0], ciphertext.len() as u64),
+            Some(plaintext.len() as u64)
+        );
+        assert_eq!(ciphertext.len() as u64 - plaintext.len() as u64, PAYLOAD_OVERHEAD);
+
+        // Plain text starts with a printable byte, and short data can't hold a tag
+        assert_eq!(payload_plaintext_len(b'H', 100), None);
+        assert_eq!(payload_plaintext_len(ENCRYPTION_VERSION, PAYLOAD_OVERHEAD - 1), None);
+    }
+
     #[test]
     fn test_decrypt_wrong_context_fails() {
         let encryption = DriveEncryption::generate();
@@ -2,16 +2,65 @@
 //!
 //! Provides a centralized manager for drive encryption keys and operations.
 //! Keys are stored encrypted (wrapped) per user using their X25519 public key.
+//! Once a passphrase is set, keys are instead stored sealed under a master
+//! key derived from it, and stay unusable until the manager is unlocked.
 
+use crate::crypto::passphrase::MasterKey;
 use crate::crypto::{
-    DriveEncryption, DriveKey, EncryptionError, KeyExchangeError, KeyExchangePair, WrappedKey,
+    DriveEncryption, DriveKey, EncryptionError, KeyExchangeError, KeyExchangePair, KeyProtection,
+    PassphraseError, ProtectedDriveKey, WrappedKey,
 };
 use crate::storage::Database;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use x25519_dalek::PublicKey;
 
+/// Settings key for the persisted key cache policy
+const KEY_CACHE_POLICY_SETTING: &str = "key_cache_policy";
+
+/// Settings key for the passphrase KDF parameters and verifier
+const KEY_PROTECTION_SETTING: &str = "key_protection";
+
+/// Maximum idle timeout before keys are cleared (24 hours)
+pub const MAX_KEY_IDLE_TIMEOUT_MINS: u64 = 24 * 60;
+
+/// Policy for clearing cached drive keys when the window loses focus
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyCachePolicy {
+    /// Clear keys immediately when the window loses focus
+    pub clear_keys_on_blur: bool,
+    /// If not clearing immediately, clear after this many minutes without focus
+    /// (None = keep keys cached until the app exits)
+    pub idle_timeout_mins: Option<u64>,
+}
+
+impl Default for KeyCachePolicy {
+    /// Secure default: clear keys as soon as focus is lost
+    fn default() -> Self {
+        Self {
+            clear_keys_on_blur: true,
+            idle_timeout_mins: None,
+        }
+    }
+}
+
+impl KeyCachePolicy {
+    /// How long to wait after losing focus before clearing keys
+    ///
+    /// Returns None if keys should not be cleared on blur at all.
+    pub fn blur_delay(&self) -> Option<Duration> {
+        if self.clear_keys_on_blur {
+            return Some(Duration::ZERO);
+        }
+        self.idle_timeout_mins
+            .map(|mins| Duration::from_secs(mins * 60))
+    }
+}
+
 /// Manages encryption keys for all drives
 ///
 /// Handles:
@@ -26,6 +75,14 @@ pub struct EncryptionManager {
     cached_keys: RwLock<HashMap<String, DriveKey>>,
     /// Database for persistent storage
     db: Arc<Database>,
+    /// When to clear cached keys after the window loses focus
+    cache_policy: RwLock<KeyCachePolicy>,
+    /// Incremented on every focus gain to cancel pending idle clears
+    focus_epoch: AtomicU64,
+    /// Passphrase protection, if a passphrase has been set
+    protection: RwLock<Option<KeyProtection>>,
+    /// Master key while unlocked in passphrase mode
+    master_key: RwLock<Option<MasterKey>>,
 }
 
 impl EncryptionManager {
@@ -53,10 +110,33 @@ impl EncryptionManager {
             }
         };
 
+        // Load the key cache policy, falling back to the secure default
+        let cache_policy = match db.get_setting(KEY_CACHE_POLICY_SETTING) {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            _ => KeyCachePolicy::default(),
+        };
+
+        // A stored protection header means keys start out locked
+        let protection = match db.get_setting(KEY_PROTECTION_SETTING) {
+            Ok(Some(bytes)) => Some(
+                serde_json::from_slice::<KeyProtection>(&bytes)
+                    .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?,
+            ),
+            Ok(None) => None,
+            Err(e) => return Err(EncryptionManagerError::StorageError(e.to_string())),
+        };
+        if protection.is_some() {
+            tracing::info!("Drive keys are passphrase protected; waiting for unlock");
+        }
+
         Ok(Self {
             exchange_keypair,
             cached_keys: RwLock::new(HashMap::new()),
             db,
+            cache_policy: RwLock::new(cache_policy),
+            focus_epoch: AtomicU64::new(0),
+            protection: RwLock::new(protection),
+            master_key: RwLock::new(None),
         })
     }
 
@@ -80,18 +160,15 @@ impl EncryptionManager {
         let wrapped = KeyExchangePair::wrap_key_for(&owner_pk, drive_key.as_bytes())
             .map_err(EncryptionManagerError::KeyExchangeError)?;
 
+        // Store the key before caching it, so a locked manager fails cleanly
+        self.store_drive_key(drive_id, &drive_key, &wrapped).await?;
+
         // Cache the unwrapped key for immediate use
         {
             let mut cache = self.cached_keys.write().await;
             cache.insert(drive_id.to_string(), drive_key);
         }
 
-        // Store wrapped key in database
-        let wrapped_bytes = wrapped.to_bytes();
-        self.db
-            .save_drive_key(drive_id, &wrapped_bytes)
-            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?;
-
         Ok(wrapped)
     }
 
@@ -111,51 +188,121 @@ impl EncryptionManager {
 
         let drive_key = DriveKey::from_bytes(drive_key_bytes);
 
+        // Store the key in database for persistence
+        self.store_drive_key(drive_id, &drive_key, wrapped).await?;
+
         // Cache the key
         {
             let mut cache = self.cached_keys.write().await;
             cache.insert(drive_id.to_string(), drive_key);
         }
 
-        // Store wrapped key in database for persistence
-        let wrapped_bytes = wrapped.to_bytes();
+        Ok(())
+    }
+
+    /// Persist a drive key: sealed under the master key in passphrase
+    /// mode, otherwise as the key wrapped for us
+    async fn store_drive_key(
+        &self,
+        drive_id: &str,
+        drive_key: &DriveKey,
+        wrapped: &WrappedKey,
+    ) -> Result<(), EncryptionManagerError> {
+        let protection = self.protection.read().await;
+        let Some(protection) = protection.as_ref() else {
+            return self
+                .db
+                .save_drive_key(drive_id, &wrapped.to_bytes())
+                .map_err(|e| EncryptionManagerError::StorageError(e.to_string()));
+        };
+
+        let master = self.master_key.read().await;
+        let master = master.as_ref().ok_or(EncryptionManagerError::Locked)?;
+        let sealed =
+            ProtectedDriveKey::seal(master, &protection.kdf, drive_id, drive_key.as_bytes())
+                .map_err(EncryptionManagerError::PassphraseError)?;
+        self.save_protected_key(drive_id, &sealed)
+    }
+
+    fn save_protected_key(
+        &self,
+        drive_id: &str,
+        sealed: &ProtectedDriveKey,
+    ) -> Result<(), EncryptionManagerError> {
+        let bytes = sealed
+            .to_bytes()
+            .map_err(EncryptionManagerError::PassphraseError)?;
         self.db
-            .save_drive_key(drive_id, &wrapped_bytes)
-            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?;
+            .save_protected_drive_key(drive_id, &bytes)
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))
+    }
 
-        Ok(())
+    /// Load a drive key from the database
+    async fn load_drive_key(&self, drive_id: &str) -> Result<DriveKey, EncryptionManagerError> {
+        if self.protection.read().await.is_some() {
+            let master = self.master_key.read().await;
+            let master = master.as_ref().ok_or(EncryptionManagerError::Locked)?;
+            let bytes = self
+                .db
+                .get_protected_drive_key(drive_id)
+                .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?
+                .ok_or_else(|| EncryptionManagerError::KeyNotFound(drive_id.to_string()))?;
+            let key_bytes = ProtectedDriveKey::from_bytes(&bytes)
+                .and_then(|sealed| sealed.open(master, drive_id))
+                .map_err(EncryptionManagerError::PassphraseError)?;
+            return Ok(DriveKey::from_bytes(key_bytes));
+        }
+
+        let wrapped_bytes = self
+            .db
+            .get_drive_key(drive_id)
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?
+            .ok_or_else(|| EncryptionManagerError::KeyNotFound(drive_id.to_string()))?;
+
+        let wrapped = WrappedKey::from_bytes(&wrapped_bytes)
+            .map_err(EncryptionManagerError::KeyExchangeError)?;
+
+        let key_bytes = self
+            .exchange_keypair
+            .unwrap_key(&wrapped)
+            .map_err(EncryptionManagerError::KeyExchangeError)?;
+
+        Ok(DriveKey::from_bytes(key_bytes))
     }
 
     /// Get the encryption handler for a drive
     ///
     /// Returns None if we don't have access to the drive's key.
-    pub async fn get_encryption(&self, drive_id: &str) -> Option<DriveEncryption> {
+    ///
+    /// # Errors
+    /// `Locked` if the key is protected by a passphrase that hasn't been
+    /// entered, or an error if the stored key can't be read.
+    pub async fn get_encryption(
+        &self,
+        drive_id: &str,
+    ) -> Result<Option<DriveEncryption>, EncryptionManagerError> {
         // First check cache
         {
             let cache = self.cached_keys.read().await;
             if let Some(key) = cache.get(drive_id) {
-                return Some(DriveEncryption::new(key.clone()));
+                return Ok(Some(DriveEncryption::new(key.clone())));
             }
         }
 
         // Try to load from database
-        if let Ok(Some(wrapped_bytes)) = self.db.get_drive_key(drive_id) {
-            if let Ok(wrapped) = WrappedKey::from_bytes(&wrapped_bytes) {
-                if let Ok(key_bytes) = self.exchange_keypair.unwrap_key(&wrapped) {
-                    let drive_key = DriveKey::from_bytes(key_bytes);
-
-                    // Cache for future use
-                    {
-                        let mut cache = self.cached_keys.write().await;
-                        cache.insert(drive_id.to_string(), drive_key.clone());
-                    }
-
-                    return Some(DriveEncryption::new(drive_key));
-                }
-            }
+        let drive_key = match self.load_drive_key(drive_id).await {
+            Ok(key) => key,
+            Err(EncryptionManagerError::KeyNotFound(_)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        // Cache for future use
+        {
+            let mut cache = self.cached_keys.write().await;
+            cache.insert(drive_id.to_string(), drive_key.clone());
         }
 
-        None
+        Ok(Some(DriveEncryption::new(drive_key)))
     }
 
     /// Wrap a drive key for a new user
@@ -174,24 +321,7 @@ impl EncryptionManager {
 
         let drive_key = match drive_key {
             Some(key) => key,
-            None => {
-                // Try to load from database
-                let wrapped_bytes = self
-                    .db
-                    .get_drive_key(drive_id)
-                    .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?
-                    .ok_or_else(|| EncryptionManagerError::KeyNotFound(drive_id.to_string()))?;
-
-                let wrapped = WrappedKey::from_bytes(&wrapped_bytes)
-                    .map_err(EncryptionManagerError::KeyExchangeError)?;
-
-                let key_bytes = self
-                    .exchange_keypair
-                    .unwrap_key(&wrapped)
-                    .map_err(EncryptionManagerError::KeyExchangeError)?;
-
-                DriveKey::from_bytes(key_bytes)
-            }
+            None => self.load_drive_key(drive_id).await?,
         };
 
         // Wrap for new user
@@ -207,9 +337,10 @@ impl EncryptionManager {
         path: &str,
         plaintext: &[u8],
     ) -> Result<Vec<u8>, EncryptionManagerError> {
+        self.ensure_unlocked().await?;
         let encryption = self
             .get_encryption(drive_id)
-            .await
+            .await?
             .ok_or_else(|| EncryptionManagerError::KeyNotFound(drive_id.to_string()))?;
 
         encryption
@@ -224,9 +355,10 @@ impl EncryptionManager {
         path: &str,
         ciphertext: &[u8],
     ) -> Result<Vec<u8>, EncryptionManagerError> {
+        self.ensure_unlocked().await?;
         let encryption = self
             .get_encryption(drive_id)
-            .await
+            .await?
             .ok_or_else(|| EncryptionManagerError::KeyNotFound(drive_id.to_string()))?;
 
         encryption
@@ -235,8 +367,20 @@ impl EncryptionManager {
     }
 
     /// Check if we have the key for a drive
-    pub async fn has_key(&self, drive_id: &str) -> bool {
-        self.get_encryption(drive_id).await.is_some()
+    ///
+    /// A key sealed under the passphrase counts even while locked.
+    pub async fn has_key(&self, drive_id: &str) -> Result<bool, EncryptionManagerError> {
+        if self.cached_keys.read().await.contains_key(drive_id) {
+            return Ok(true);
+        }
+        let stored = if self.is_protected().await {
+            self.db.get_protected_drive_key(drive_id)
+        } else {
+            self.db.get_drive_key(drive_id)
+        };
+        stored
+            .map(|key| key.is_some())
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))
     }
 
     /// Clear cached keys (for security, e.g., on app lock)
@@ -253,6 +397,181 @@ impl EncryptionManager {
         tracing::info!("Encryption key cache cleared ({} keys)", count);
         true
     }
+
+    /// Whether drive keys are protected by a passphrase
+    pub async fn is_protected(&self) -> bool {
+        self.protection.read().await.is_some()
+    }
+
+    /// Whether drive keys can be used: always true without a passphrase
+    pub async fn is_unlocked(&self) -> bool {
+        !self.is_protected().await || self.master_key.read().await.is_some()
+    }
+
+    async fn ensure_unlocked(&self) -> Result<(), EncryptionManagerError> {
+        if self.is_unlocked().await {
+            Ok(())
+        } else {
+            Err(EncryptionManagerError::Locked)
+        }
+    }
+
+    /// Protect drive keys with a passphrase
+    ///
+    /// Derives a master key with Argon2id, seals every stored drive key
+    /// under it and removes the unprotected copies. The manager is left
+    /// unlocked.
+    pub async fn set_passphrase(&self, passphrase: &str) -> Result<(), EncryptionManagerError> {
+        let mut protection = self.protection.write().await;
+        if protection.is_some() {
+            return Err(EncryptionManagerError::PassphraseAlreadySet);
+        }
+
+        let passphrase = passphrase.to_string();
+        let (new_protection, master) =
+            tokio::task::spawn_blocking(move || KeyProtection::create(&passphrase))
+                .await
+                .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?
+                .map_err(EncryptionManagerError::PassphraseError)?;
+
+        // Seal existing keys before saving the header, so an interrupted
+        // migration leaves the unprotected keys usable
+        let sealed = self.seal_unprotected_keys(&new_protection, &master)?;
+
+        let bytes = serde_json::to_vec(&new_protection)
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?;
+        self.db
+            .save_setting(KEY_PROTECTION_SETTING, &bytes)
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?;
+        for drive_id in &sealed {
+            self.db
+                .delete_drive_key(drive_id)
+                .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?;
+        }
+
+        *self.master_key.write().await = Some(master);
+        *protection = Some(new_protection);
+        tracing::info!("Drive keys protected by passphrase ({} keys)", sealed.len());
+        Ok(())
+    }
+
+    /// Seal every unprotected drive key, returning the drive IDs sealed
+    fn seal_unprotected_keys(
+        &self,
+        protection: &KeyProtection,
+        master: &MasterKey,
+    ) -> Result<Vec<String>, EncryptionManagerError> {
+        let keys = self
+            .db
+            .list_drive_keys()
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?;
+
+        let mut sealed_ids = Vec::with_capacity(keys.len());
+        for (drive_id, wrapped_bytes) in keys {
+            let wrapped = WrappedKey::from_bytes(&wrapped_bytes)
+                .map_err(EncryptionManagerError::KeyExchangeError)?;
+            let key_bytes = self
+                .exchange_keypair
+                .unwrap_key(&wrapped)
+                .map_err(EncryptionManagerError::KeyExchangeError)?;
+            let sealed = ProtectedDriveKey::seal(master, &protection.kdf, &drive_id, &key_bytes)
+                .map_err(EncryptionManagerError::PassphraseError)?;
+            self.save_protected_key(&drive_id, &sealed)?;
+            sealed_ids.push(drive_id);
+        }
+        Ok(sealed_ids)
+    }
+
+    /// Unlock drive keys with the passphrase
+    ///
+    /// The passphrase is checked against the stored verifier, so a wrong
+    /// one fails with `WrongPassphrase` before any drive key is opened.
+    pub async fn unlock_with_passphrase(
+        &self,
+        passphrase: &str,
+    ) -> Result<(), EncryptionManagerError> {
+        let protection = self
+            .protection
+            .read()
+            .await
+            .clone()
+            .ok_or(EncryptionManagerError::NoPassphrase)?;
+
+        let passphrase = passphrase.to_string();
+        let master = tokio::task::spawn_blocking(move || protection.unlock(&passphrase))
+            .await
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?
+            .map_err(|e| match e {
+                PassphraseError::WrongPassphrase => EncryptionManagerError::WrongPassphrase,
+                other => EncryptionManagerError::PassphraseError(other),
+            })?;
+
+        *self.master_key.write().await = Some(master);
+        tracing::info!("Drive keys unlocked");
+        Ok(())
+    }
+
+    /// Lock drive keys, dropping the master key and all cached keys
+    ///
+    /// Without a passphrase this only clears the cache.
+    pub async fn lock(&self) {
+        let had_master = self.master_key.write().await.take().is_some();
+        self.clear_cache().await;
+        if had_master {
+            tracing::info!("Drive keys locked");
+        }
+    }
+
+    /// Get the current key cache policy
+    pub async fn key_cache_policy(&self) -> KeyCachePolicy {
+        *self.cache_policy.read().await
+    }
+
+    /// Update and persist the key cache policy
+    pub async fn set_key_cache_policy(
+        &self,
+        policy: KeyCachePolicy,
+    ) -> Result<(), EncryptionManagerError> {
+        let bytes = serde_json::to_vec(&policy)
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?;
+        self.db
+            .save_setting(KEY_CACHE_POLICY_SETTING, &bytes)
+            .map_err(|e| EncryptionManagerError::StorageError(e.to_string()))?;
+
+        *self.cache_policy.write().await = policy;
+        tracing::info!(
+            clear_keys_on_blur = policy.clear_keys_on_blur,
+            idle_timeout_mins = ?policy.idle_timeout_mins,
+            "Key cache policy updated"
+        );
+        Ok(())
+    }
+
+    /// Handle the window losing focus according to the key cache policy
+    ///
+    /// With an idle timeout this waits for the timeout and only clears if
+    /// focus was not regained in the meantime. Returns true if keys were cleared.
+    pub async fn on_focus_lost(&self) -> bool {
+        let epoch = self.focus_epoch.load(Ordering::SeqCst);
+        let delay = match self.key_cache_policy().await.blur_delay() {
+            Some(delay) => delay,
+            None => return false,
+        };
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+            if self.focus_epoch.load(Ordering::SeqCst) != epoch {
+                return false;
+            }
+        }
+
+        self.clear_cache().await
+    }
+
+    /// Handle the window regaining focus, cancelling any pending idle clear
+    pub fn on_focus_gained(&self) {
+        self.focus_epoch.fetch_add(1, Ordering::SeqCst);
+    }
 }
 
 /// Errors from the encryption manager
@@ -266,6 +585,16 @@ pub enum EncryptionManagerError {
     EncryptionError(EncryptionError),
     /// Storage error
     StorageError(String),
+    /// Keys are passphrase protected and not unlocked
+    Locked,
+    /// Passphrase didn't match the stored verifier
+    WrongPassphrase,
+    /// No passphrase has been set
+    NoPassphrase,
+    /// A passphrase is already set
+    PassphraseAlreadySet,
+    /// Passphrase protection error
+    PassphraseError(PassphraseError),
 }
 
 impl std::fmt::Display for EncryptionManagerError {
@@ -277,6 +606,13 @@ impl std::fmt::Display for EncryptionManagerError {
             EncryptionManagerError::KeyExchangeError(e) => write!(f, "Key exchange error: {}", e),
             EncryptionManagerError::EncryptionError(e) => write!(f, "Encryption error: {}", e),
             EncryptionManagerError::StorageError(e) => write!(f, "Storage error: {}", e),
+            EncryptionManagerError::Locked => write!(f, "Drive keys are locked"),
+            EncryptionManagerError::WrongPassphrase => write!(f, "Wrong passphrase"),
+            EncryptionManagerError::NoPassphrase => write!(f, "No passphrase has been set"),
+            EncryptionManagerError::PassphraseAlreadySet => {
+                write!(f, "A passphrase is already set")
+            }
+            EncryptionManagerError::PassphraseError(e) => write!(f, "Passphrase error: {}", e),
         }
     }
 }
@@ -317,4 +653,130 @@ mod tests {
 
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
+
+    #[test]
+    fn test_key_cache_policy_blur_delay() {
+        assert_eq!(KeyCachePolicy::default().blur_delay(), Some(Duration::ZERO));
+
+        let idle = KeyCachePolicy {
+            clear_keys_on_blur: false,
+            idle_timeout_mins: Some(5),
+        };
+        assert_eq!(idle.blur_delay(), Some(Duration::from_secs(300)));
+
+        let never = KeyCachePolicy {
+            clear_keys_on_blur: false,
+            idle_timeout_mins: None,
+        };
+        assert_eq!(never.blur_delay(), None);
+    }
+
+    #[tokio::test]
+    async fn test_key_cache_policy_persists() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(&dir.path().join("test.redb")).unwrap());
+
+        let manager = EncryptionManager::new(db.clone()).unwrap();
+        assert_eq!(manager.key_cache_policy().await, KeyCachePolicy::default());
+
+        let policy = KeyCachePolicy {
+            clear_keys_on_blur: false,
+            idle_timeout_mins: Some(10),
+        };
+        manager.set_key_cache_policy(policy).await.unwrap();
+        drop(manager);
+
+        let reloaded = EncryptionManager::new(db).unwrap();
+        assert_eq!(reloaded.key_cache_policy().await, policy);
+    }
+
+    #[tokio::test]
+    async fn test_focus_lost_respects_policy() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(&dir.path().join("test.redb")).unwrap());
+        let manager = Arc::new(EncryptionManager::new(db).unwrap());
+
+        let owner_pk = manager.public_key();
+        manager
+            .generate_drive_key("test-drive", &owner_pk)
+            .await
+            .unwrap();
+
+        // Keep keys while unfocused
+        manager
+            .set_key_cache_policy(KeyCachePolicy {
+                clear_keys_on_blur: false,
+                idle_timeout_mins: None,
+            })
+            .await
+            .unwrap();
+        assert!(!manager.on_focus_lost().await);
+
+        // Immediate clearing
+        manager
+            .set_key_cache_policy(KeyCachePolicy::default())
+            .await
+            .unwrap();
+        assert!(manager.on_focus_lost().await);
+    }
+
+    #[tokio::test]
+    async fn test_passphrase_locks_keys() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(&dir.path().join("test.redb")).unwrap());
+        let manager = EncryptionManager::new(db.clone()).unwrap();
+
+        let owner_pk = manager.public_key();
+        manager
+            .generate_drive_key("test-drive", &owner_pk)
+            .await
+            .unwrap();
+        let ciphertext = manager
+            .encrypt_file("test-drive", "test.txt", b"secret")
+            .await
+            .unwrap();
+
+        // Existing keys are moved under the passphrase
+        manager.set_passphrase("correct horse").await.unwrap();
+        assert!(manager.is_unlocked().await);
+        assert!(db.get_drive_key("test-drive").unwrap().is_none());
+        drop(manager);
+
+        let reloaded = EncryptionManager::new(db).unwrap();
+        assert!(reloaded.is_protected().await);
+        assert!(!reloaded.is_unlocked().await);
+        assert!(reloaded.has_key("test-drive").await.unwrap());
+        assert!(matches!(
+            reloaded.get_encryption("test-drive").await,
+            Err(EncryptionManagerError::Locked)
+        ));
+        assert!(matches!(
+            reloaded
+                .decrypt_file("test-drive", "test.txt", &ciphertext)
+                .await,
+            Err(EncryptionManagerError::Locked)
+        ));
+        assert!(matches!(
+            reloaded.unlock_with_passphrase("wrong horse").await,
+            Err(EncryptionManagerError::WrongPassphrase)
+        ));
+
+        reloaded
+            .unlock_with_passphrase("correct horse")
+            .await
+            .unwrap();
+        let decrypted = reloaded
+            .decrypt_file("test-drive", "test.txt", &ciphertext)
+            .await
+            .unwrap();
+        assert_eq!(decrypted, b"secret");
+
+        reloaded.lock().await;
+        assert!(matches!(
+            reloaded
+                .encrypt_file("test-drive", "test.txt", b"more")
+                .await,
+            Err(EncryptionManagerError::Locked)
+        ));
+    }
 }
@@ -12,6 +12,12 @@ use thiserror::Error;
 /// Current invite token version
 const INVITE_VERSION: u8 = 1;
 
+/// Scheme and path of invite deep links (`gix://invite/<token>`)
+pub const INVITE_LINK_PREFIX: &str = "gix://invite/";
+
+/// Longest invite link accepted, to bound decoding work on untrusted input
+const MAX_INVITE_LINK_LEN: usize = 16 * 1024;
+
 #[derive(Error, Debug)]
 pub enum InviteError {
     #[error("Token expired")]
@@ -56,6 +62,18 @@ pub struct InvitePayload {
     /// Optional iroh-docs share ticket for metadata sync
     #[serde(default)]
     pub doc_ticket: Option<String>,
+    /// Size of the drive when the invite was created (for join estimates)
+    ///
+    /// Omitted when absent so tokens issued before this field still verify.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drive_stats: Option<InviteDriveStats>,
+}
+
+/// Drive size snapshot embedded in an invite
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InviteDriveStats {
+    pub file_count: u64,
+    pub total_bytes: u64,
 }
 
 impl InvitePayload {
@@ -106,8 +124,14 @@ impl InviteToken {
             single_use,
             token_id,
             doc_ticket,
+            drive_stats: None,
         };
 
+        Self::sign(signing_key, payload)
+    }
+
+    /// Sign a payload into a token
+    fn sign(signing_key: &SigningKey, payload: InvitePayload) -> Result<Self, InviteError> {
         let payload_bytes = payload.to_bytes()?;
         let signature: Signature = signing_key.sign(&payload_bytes);
 
@@ -135,6 +159,16 @@ impl InviteToken {
             .map_err(|_| InviteError::InvalidSignature)
     }
 
+    /// Verify the signature against the inviter key embedded in the payload
+    pub fn verify_inviter_signature(&self) -> Result<(), InviteError> {
+        let key_bytes: [u8; 32] = hex::decode(&self.payload.inviter)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(InviteError::InvalidFormat)?;
+        let key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| InviteError::InvalidFormat)?;
+        self.verify(&key)
+    }
+
     /// Check if the token is expired
     pub fn is_expired(&self) -> bool {
         self.payload.expires_at < Utc::now()
@@ -160,6 +194,38 @@ impl InviteToken {
         json_deserialize(&bytes)
     }
 
+    /// Deep link for sharing this token
+    pub fn to_link(&self) -> Result<String, InviteError> {
+        Ok(format!("{}{}", INVITE_LINK_PREFIX, self.to_string()?))
+    }
+
+    /// Parse a `gix://invite/<token>` deep link
+    ///
+    /// The link comes from outside the app, so anything other than the
+    /// expected scheme and a URL-safe base64 token is rejected. A query
+    /// string or fragment (such as `?drive=...`) is ignored.
+    pub fn from_link(url: &str) -> Result<Self, InviteError> {
+        let url = url.trim();
+        if url.len() > MAX_INVITE_LINK_LEN {
+            return Err(InviteError::InvalidFormat);
+        }
+        let prefix = url
+            .get(..INVITE_LINK_PREFIX.len())
+            .filter(|p| p.eq_ignore_ascii_case(INVITE_LINK_PREFIX))
+            .ok_or(InviteError::InvalidFormat)?;
+        let rest = &url[prefix.len()..];
+        let token = rest
+            .split(['?', '#'])
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        let valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '=');
+        if token.is_empty() || !token.chars().all(valid_char) {
+            return Err(InviteError::InvalidFormat);
+        }
+        Self::from_string(token)
+    }
+
     /// Get the token ID
     pub fn token_id(&self) -> &str {
         &self.payload.token_id
@@ -175,6 +241,7 @@ pub struct InviteBuilder {
     note: Option<String>,
     single_use: bool,
     doc_ticket: Option<String>,
+    drive_stats: Option<InviteDriveStats>,
 }
 
 impl InviteBuilder {
@@ -188,6 +255,7 @@ impl InviteBuilder {
             note: None,
             single_use: false,
             doc_ticket: None,
+            drive_stats: None,
         }
     }
 
@@ -221,9 +289,18 @@ impl InviteBuilder {
         self
     }
 
+    /// Include the drive's current size so invitees can estimate the join
+    pub fn with_drive_stats(mut self, file_count: u64, total_bytes: u64) -> Self {
+        self.drive_stats = Some(InviteDriveStats {
+            file_count,
+            total_bytes,
+        });
+        self
+    }
+
     /// Build and sign the token
     pub fn build(self, signing_key: &SigningKey) -> Result<InviteToken, InviteError> {
-        InviteToken::create(
+        let token = InviteToken::create(
             signing_key,
             &self.drive_id,
             &self.drive_name,
@@ -232,7 +309,16 @@ impl InviteBuilder {
             self.note,
             self.single_use,
             self.doc_ticket,
-        )
+        )?;
+
+        match self.drive_stats {
+            Some(stats) => {
+                let mut payload = token.payload;
+                payload.drive_stats = Some(stats);
+                InviteToken::sign(signing_key, payload)
+            }
+            None => Ok(token),
+        }
     }
 }
 
@@ -272,21 +358,155 @@ impl TokenTracker {
     }
 }
 
+/// Lifecycle status of an issued invite
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InviteStatus {
+    Issued,
+    Accepted,
+    Expired,
+    Revoked,
+}
+
+/// A single acceptance of an issued invite
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InviteAcceptance {
+    /// Acceptor's node ID (hex)
+    pub node_id: String,
+    pub accepted_at: DateTime<Utc>,
+}
+
+/// Record of an invite issued by this node, kept for analytics
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IssuedInvite {
+    pub token_id: String,
+    pub permission: Permission,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub single_use: bool,
+    pub note: Option<String>,
+    /// Acceptances seen so far (multi-use invites may have several)
+    #[serde(default)]
+    pub acceptances: Vec<InviteAcceptance>,
+    #[serde(default)]
+    pub revoked_at: Option<DateTime<Utc>>,
+    /// The token as issued, so its signature can be re-checked later
+    /// (None for invites recorded before tokens were kept)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signed_token: Option<String>,
+}
+
+/// Whether an issued invite's signature holds up against an identity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InviteSignatureStatus {
+    /// Signed by the identity and intact
+    Valid,
+    /// Intact, but signed by a different identity (e.g. another profile)
+    OtherIdentity,
+    /// The signature doesn't verify or the stored token is unreadable
+    Invalid,
+    /// Recorded before tokens were kept, so there is nothing to check
+    Unverifiable,
+}
+
+impl IssuedInvite {
+    /// Create a record for a freshly built token
+    pub fn from_token(token: &InviteToken) -> Self {
+        Self {
+            token_id: token.payload.token_id.clone(),
+            permission: token.payload.permission,
+            issued_at: token.payload.created_at,
+            expires_at: token.payload.expires_at,
+            single_use: token.payload.single_use,
+            note: token.payload.note.clone(),
+            acceptances: Vec::new(),
+            revoked_at: None,
+            signed_token: token.to_string().ok(),
+        }
+    }
+
+    /// Re-check the stored token's signature against `identity`
+    pub fn check_signature(&self, identity: &VerifyingKey) -> InviteSignatureStatus {
+        let Some(encoded) = &self.signed_token else {
+            return InviteSignatureStatus::Unverifiable;
+        };
+        let Ok(token) = InviteToken::from_string(encoded) else {
+            return InviteSignatureStatus::Invalid;
+        };
+        if token.payload.token_id != self.token_id {
+            return InviteSignatureStatus::Invalid;
+        }
+        if token.verify(identity).is_ok() {
+            InviteSignatureStatus::Valid
+        } else if token.verify_inviter_signature().is_ok() {
+            InviteSignatureStatus::OtherIdentity
+        } else {
+            InviteSignatureStatus::Invalid
+        }
+    }
+
+    /// Node ID (hex) the stored token names as its inviter
+    pub fn signed_by(&self) -> Option<String> {
+        let token = InviteToken::from_string(self.signed_token.as_deref()?).ok()?;
+        Some(token.payload.inviter)
+    }
+
+    /// Record an acceptance (ignores repeats from the same node)
+    pub fn record_acceptance(&mut self, node_id: &str, accepted_at: DateTime<Utc>) -> bool {
+        if self.acceptances.iter().any(|a| a.node_id == node_id) {
+            return false;
+        }
+        self.acceptances.push(InviteAcceptance {
+            node_id: node_id.to_string(),
+            accepted_at,
+        });
+        true
+    }
+
+    /// Whether this invite lets `node_id` join at `now`
+    ///
+    /// Revoked and expired invites admit nobody. A single-use invite only
+    /// admits the node that already accepted it, if there is one.
+    pub fn admits(&self, node_id: &str, now: DateTime<Utc>) -> bool {
+        if self.revoked_at.is_some() || now > self.expires_at {
+            return false;
+        }
+        !self.single_use || self.acceptances.iter().all(|a| a.node_id == node_id)
+    }
+
+    /// Status as of `now`
+    ///
+    /// Revocation wins over acceptance; an invite only counts as expired if
+    /// nobody accepted it before it ran out.
+    pub fn status_at(&self, now: DateTime<Utc>) -> InviteStatus {
+        if self.revoked_at.is_some() {
+            InviteStatus::Revoked
+        } else if !self.acceptances.is_empty() {
+            InviteStatus::Accepted
+        } else if now > self.expires_at {
+            InviteStatus::Expired
+        } else {
+            InviteStatus::Issued
+        }
+    }
+}
+
 /// Generate a unique token ID
-fn generate_token_id() -> String {
+pub(crate) fn generate_token_id() -> String {
     let mut bytes = [0u8; 16];
     rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut bytes);
     hex::encode(bytes)
 }
 
 /// URL-safe base64 encoding
-fn base64_url_encode(data: &[u8]) -> String {
+pub(crate) fn base64_url_encode(data: &[u8]) -> String {
     use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
     URL_SAFE_NO_PAD.encode(data)
 }
 
 /// URL-safe base64 decoding
-fn base64_url_decode(s: &str) -> Result<Vec<u8>, InviteError> {
+pub(crate) fn base64_url_decode(s: &str) -> Result<Vec<u8>, InviteError> {
     use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
     URL_SAFE_NO_PAD
         .decode(s)
@@ -294,12 +514,14 @@ fn base64_url_decode(s: &str) -> Result<Vec<u8>, InviteError> {
 }
 
 /// JSON serialization helper
-fn json_serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, InviteError> {
+pub(crate) fn json_serialize<T: Serialize>(value: &T) -> Result<Vec<u8>, InviteError> {
     serde_json::to_vec(value).map_err(|e| InviteError::SerializationError(e.to_string()))
 }
 
 /// JSON deserialization helper
-fn json_deserialize<T: for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, InviteError> {
+pub(crate) fn json_deserialize<T: for<'de> Deserialize<'de>>(
+    bytes: &[u8],
+) -> Result<T, InviteError> {
     serde_json::from_slice(bytes).map_err(|e| InviteError::SerializationError(e.to_string()))
 }
 
@@ -329,6 +551,31 @@ mod tests {
         assert!(!token.is_expired());
     }
 
+    #[test]
+    fn test_invite_drive_stats() {
+        let key = generate_signing_key();
+        let token = InviteBuilder::new("drive123", "Big Drive")
+            .with_drive_stats(1200, 5_000_000_000)
+            .build(&key)
+            .unwrap();
+
+        let parsed = InviteToken::from_string(&token.to_string().unwrap()).unwrap();
+        assert_eq!(
+            parsed.payload.drive_stats,
+            Some(InviteDriveStats {
+                file_count: 1200,
+                total_bytes: 5_000_000_000,
+            })
+        );
+        assert!(parsed.verify_inviter_signature().is_ok());
+
+        // Tokens without stats don't carry the field at all
+        let plain = InviteBuilder::new("drive123", "Drive").build(&key).unwrap();
+        let json = String::from_utf8(plain.payload.to_bytes().unwrap()).unwrap();
+        assert!(!json.contains("drive_stats"));
+        assert!(plain.verify_inviter_signature().is_ok());
+    }
+
     #[test]
     fn test_invite_verification() {
         let key = generate_signing_key();
@@ -363,6 +610,30 @@ mod tests {
         assert!(restored.verify(&key.verifying_key()).is_ok());
     }
 
+    #[test]
+    fn test_invite_link() {
+        let key = generate_signing_key();
+        let token = InviteBuilder::new("drive123", "Link Test").build(&key).unwrap();
+        let link = token.to_link().unwrap();
+        assert!(link.starts_with(INVITE_LINK_PREFIX));
+
+        let parsed = InviteToken::from_link(&link).unwrap();
+        assert_eq!(parsed.token_id(), token.token_id());
+        assert!(parsed.verify_inviter_signature().is_ok());
+
+        // Query strings, trailing slashes, and scheme case are tolerated
+        let decorated = format!("  {}/?drive=drive123#x", link.replacen("gix", "GIX", 1));
+        assert!(InviteToken::from_link(&decorated).is_ok());
+
+        // Anything else is rejected
+        let token_string = token.to_string().unwrap();
+        assert!(InviteToken::from_link(&token_string).is_err());
+        assert!(InviteToken::from_link("https://invite/abc").is_err());
+        assert!(InviteToken::from_link("gix://invite/").is_err());
+        assert!(InviteToken::from_link("gix://invite/abc%20def").is_err());
+        assert!(InviteToken::from_link("gix://invite/not-a-token").is_err());
+    }
+
     #[test]
     fn test_invite_doc_ticket_roundtrip() {
         let key = generate_signing_key();
@@ -417,4 +688,85 @@ mod tests {
         // Default single_use is false
         assert!(!token.payload.single_use);
     }
+
+    #[test]
+    fn test_issued_invite_status() {
+        let key = generate_signing_key();
+        let token = InviteBuilder::new("drive123", "Analytics Test").build(&key).unwrap();
+        let mut record = IssuedInvite::from_token(&token);
+        let now = Utc::now();
+
+        assert_eq!(record.status_at(now), InviteStatus::Issued);
+        assert_eq!(
+            record.status_at(record.expires_at + Duration::seconds(1)),
+            InviteStatus::Expired
+        );
+
+        assert!(record.record_acceptance("node1", now));
+        assert!(!record.record_acceptance("node1", now));
+        assert_eq!(record.acceptances.len(), 1);
+        // Accepted invites stay accepted after expiry
+        assert_eq!(
+            record.status_at(record.expires_at + Duration::seconds(1)),
+            InviteStatus::Accepted
+        );
+
+        record.revoked_at = Some(now);
+        assert_eq!(record.status_at(now), InviteStatus::Revoked);
+    }
+
+    #[test]
+    fn test_issued_invite_admits() {
+        let key = generate_signing_key();
+        let token = InviteBuilder::new("drive123", "Admit Test").build(&key).unwrap();
+        let mut record = IssuedInvite::from_token(&token);
+        record.single_use = true;
+        let now = Utc::now();
+
+        assert!(record.admits("node1", now));
+        assert!(!record.admits("node1", record.expires_at + Duration::seconds(1)));
+
+        // A used single-use invite only admits its acceptor again
+        record.record_acceptance("node1", now);
+        assert!(record.admits("node1", now));
+        assert!(!record.admits("node2", now));
+        record.single_use = false;
+        assert!(record.admits("node2", now));
+
+        record.revoked_at = Some(now);
+        assert!(!record.admits("node1", now));
+    }
+
+    #[test]
+    fn test_issued_invite_check_signature() {
+        let key = generate_signing_key();
+        let other = generate_signing_key();
+        let token = InviteBuilder::new("drive123", "Signature Test").build(&key).unwrap();
+        let mut record = IssuedInvite::from_token(&token);
+
+        assert_eq!(
+            record.check_signature(&key.verifying_key()),
+            InviteSignatureStatus::Valid
+        );
+        // Imported a different identity since issuing
+        assert_eq!(
+            record.check_signature(&other.verifying_key()),
+            InviteSignatureStatus::OtherIdentity
+        );
+        assert_eq!(record.signed_by(), Some(token.payload.inviter.clone()));
+
+        let mut tampered = token.clone();
+        tampered.payload.permission = Permission::Admin;
+        record.signed_token = Some(tampered.to_string().unwrap());
+        assert_eq!(
+            record.check_signature(&key.verifying_key()),
+            InviteSignatureStatus::Invalid
+        );
+
+        record.signed_token = None;
+        assert_eq!(
+            record.check_signature(&key.verifying_key()),
+            InviteSignatureStatus::Unverifiable
+        );
+    }
 }
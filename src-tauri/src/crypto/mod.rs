@@ -10,11 +10,17 @@ pub mod invite;
 #[allow(dead_code)]
 pub mod key_exchange;
 pub mod keys;
+pub mod passphrase;
+pub mod share_link;
 
 // Re-export commonly used types
-pub use access::{AccessControlList, AccessRule, Permission};
-pub use encryption::{DriveEncryption, DriveKey, EncryptionError};
-pub use encryption_manager::EncryptionManager;
-pub use invite::{InviteBuilder, InviteToken, TokenTracker};
+pub use access::{glob_matches, AccessControlList, AccessRule, AclChange, Permission};
+pub use encryption::{payload_plaintext_len, DriveEncryption, DriveKey, EncryptionError};
+pub use encryption_manager::{EncryptionManager, KeyCachePolicy, MAX_KEY_IDLE_TIMEOUT_MINS};
+pub use invite::{
+    InviteBuilder, InviteSignatureStatus, InviteStatus, InviteToken, IssuedInvite, TokenTracker,
+};
 pub use key_exchange::{KeyExchangeError, KeyExchangePair, WrappedKey};
 pub use keys::{Identity, NodeId};
+pub use passphrase::{KeyProtection, PassphraseError, ProtectedDriveKey};
+pub use share_link::{FileShareToken, ShareLinkRecords};
@@ -0,0 +1,307 @@
+//! Passphrase protection of drive keys at rest
+//!
+//! In passphrase mode a master key is derived from the user's passphrase
+//! with Argon2id and drive keys are stored sealed under it with
+//! XChaCha20-Poly1305, bound to their drive ID. A sealed verifier is kept
+//! with the KDF parameters so a wrong passphrase is rejected before any
+//! drive key is touched. Each sealed key records the parameters it was
+//! sealed under, so keys survive a change of the default cost.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Shortest passphrase accepted, for drive keys and backups alike
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Argon2id cost for new master keys and backups (64 MiB, 3 passes)
+pub const KDF_MEMORY_KIB: u32 = 64 * 1024;
+pub const KDF_ITERATIONS: u32 = 3;
+pub const KDF_PARALLELISM: u32 = 1;
+/// Stored parameters above these are rejected rather than run (1 GiB)
+const MAX_KDF_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 16;
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+
+/// Plaintext sealed as the passphrase verifier
+const VERIFIER_PLAINTEXT: &[u8] = b"gix-drive:passphrase-verifier";
+/// Associated data of the verifier, so it can't pass for a drive key
+const VERIFIER_AAD: &[u8] = b"gix-drive:verifier";
+
+/// Errors from passphrase protection
+#[derive(Error, Debug)]
+pub enum PassphraseError {
+    #[error("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN)]
+    TooShort,
+
+    #[error("Wrong passphrase")]
+    WrongPassphrase,
+
+    #[error("Invalid key derivation parameters: {0}")]
+    InvalidParams(String),
+
+    #[error("Sealing failed: {0}")]
+    SealFailed(String),
+
+    #[error("Sealed key is corrupted")]
+    Corrupted,
+}
+
+/// Check that a passphrase is acceptable for protecting keys or backups
+pub fn validate_passphrase(passphrase: &str) -> Result<(), PassphraseError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(PassphraseError::TooShort);
+    }
+    Ok(())
+}
+
+/// Derive a 32-byte key from `passphrase` with Argon2id
+///
+/// Deliberately slow; run it off the async runtime. Costs above the
+/// supported maximum are rejected rather than run.
+pub fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<[u8; 32], PassphraseError> {
+    if memory_kib > MAX_KDF_MEMORY_KIB
+        || iterations > MAX_KDF_ITERATIONS
+        || parallelism > MAX_KDF_PARALLELISM
+    {
+        return Err(PassphraseError::InvalidParams(
+            "cost exceeds the supported maximum".to_string(),
+        ));
+    }
+    let params = Params::new(memory_kib, iterations, parallelism, Some(32))
+        .map_err(|e| PassphraseError::InvalidParams(e.to_string()))?;
+
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| PassphraseError::InvalidParams(e.to_string()))?;
+    Ok(key)
+}
+
+/// Argon2id parameters and salt a master key is derived with
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    /// Random salt (hex)
+    pub salt: String,
+}
+
+impl KdfParams {
+    /// Default cost with a fresh salt
+    pub fn generate() -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self {
+            memory_kib: KDF_MEMORY_KIB,
+            iterations: KDF_ITERATIONS,
+            parallelism: KDF_PARALLELISM,
+            salt: hex::encode(salt),
+        }
+    }
+
+    /// Derive the master key for `passphrase`
+    ///
+    /// Deliberately slow; run it off the async runtime.
+    pub fn derive(&self, passphrase: &str) -> Result<MasterKey, PassphraseError> {
+        let salt =
+            hex::decode(&self.salt).map_err(|e| PassphraseError::InvalidParams(e.to_string()))?;
+        derive_key(
+            passphrase,
+            &salt,
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+        )
+        .map(MasterKey)
+    }
+}
+
+/// Key derived from the passphrase; zeroed when dropped
+pub struct MasterKey([u8; 32]);
+
+impl MasterKey {
+    fn cipher(&self) -> XChaCha20Poly1305 {
+        XChaCha20Poly1305::new((&self.0).into())
+    }
+
+    fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<SealedBox, PassphraseError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .map_err(|e| PassphraseError::SealFailed(e.to_string()))?;
+        Ok(SealedBox {
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    fn open(&self, sealed: &SealedBox, aad: &[u8]) -> Option<Vec<u8>> {
+        let nonce = hex::decode(&sealed.nonce).ok()?;
+        let ciphertext = hex::decode(&sealed.ciphertext).ok()?;
+        if nonce.len() != NONCE_LEN {
+            return None;
+        }
+        self.cipher()
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad,
+                },
+            )
+            .ok()
+    }
+}
+
+impl Drop for MasterKey {
+    fn drop(&mut self) {
+        self.0.fill(0);
+    }
+}
+
+/// Nonce and ciphertext of a sealed value
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedBox {
+    /// Hex
+    pub nonce: String,
+    /// Hex, including the authentication tag
+    pub ciphertext: String,
+}
+
+/// How drive keys are protected: KDF parameters and passphrase verifier
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyProtection {
+    pub kdf: KdfParams,
+    pub verifier: SealedBox,
+}
+
+impl KeyProtection {
+    /// Protection for a new passphrase, with its master key
+    pub fn create(passphrase: &str) -> Result<(Self, MasterKey), PassphraseError> {
+        validate_passphrase(passphrase)?;
+        let kdf = KdfParams::generate();
+        let master = kdf.derive(passphrase)?;
+        let verifier = master.seal(VERIFIER_PLAINTEXT, VERIFIER_AAD)?;
+        Ok((Self { kdf, verifier }, master))
+    }
+
+    /// Derive the master key, failing with `WrongPassphrase` if the
+    /// verifier doesn't open
+    pub fn unlock(&self, passphrase: &str) -> Result<MasterKey, PassphraseError> {
+        let master = self.kdf.derive(passphrase)?;
+        match master.open(&self.verifier, VERIFIER_AAD) {
+            Some(plaintext) if plaintext == VERIFIER_PLAINTEXT => Ok(master),
+            _ => Err(PassphraseError::WrongPassphrase),
+        }
+    }
+}
+
+/// A drive key sealed under the master key
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProtectedDriveKey {
+    /// Parameters of the master key it was sealed under
+    pub kdf: KdfParams,
+    pub sealed: SealedBox,
+}
+
+impl ProtectedDriveKey {
+    /// Seal a drive key, bound to its drive ID
+    pub fn seal(
+        master: &MasterKey,
+        kdf: &KdfParams,
+        drive_id: &str,
+        key: &[u8; 32],
+    ) -> Result<Self, PassphraseError> {
+        Ok(Self {
+            kdf: kdf.clone(),
+            sealed: master.seal(key, drive_id.as_bytes())?,
+        })
+    }
+
+    /// Open with the master key derived from `self.kdf`
+    pub fn open(&self, master: &MasterKey, drive_id: &str) -> Result<[u8; 32], PassphraseError> {
+        let plaintext = master
+            .open(&self.sealed, drive_id.as_bytes())
+            .ok_or(PassphraseError::Corrupted)?;
+        plaintext.try_into().map_err(|_| PassphraseError::Corrupted)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, PassphraseError> {
+        serde_json::to_vec(self).map_err(|e| PassphraseError::SealFailed(e.to_string()))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PassphraseError> {
+        serde_json::from_slice(bytes).map_err(|_| PassphraseError::Corrupted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters so tests don't spend seconds in Argon2
+    fn fast_protection(passphrase: &str) -> (KeyProtection, MasterKey) {
+        let kdf = KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            ..KdfParams::generate()
+        };
+        let master = kdf.derive(passphrase).unwrap();
+        let verifier = master.seal(VERIFIER_PLAINTEXT, VERIFIER_AAD).unwrap();
+        (KeyProtection { kdf, verifier }, master)
+    }
+
+    #[test]
+    fn test_unlock_checks_passphrase() {
+        let (protection, master) = fast_protection("correct horse");
+        let key = [9u8; 32];
+        let sealed = ProtectedDriveKey::seal(&master, &protection.kdf, "drive-a", &key).unwrap();
+
+        let unlocked = protection.unlock("correct horse").unwrap();
+        assert_eq!(sealed.open(&unlocked, "drive-a").unwrap(), key);
+        assert!(matches!(
+            protection.unlock("wrong horse"),
+            Err(PassphraseError::WrongPassphrase)
+        ));
+    }
+
+    #[test]
+    fn test_sealed_key_is_bound_to_drive() {
+        let (protection, master) = fast_protection("correct horse");
+        let sealed =
+            ProtectedDriveKey::seal(&master, &protection.kdf, "drive-a", &[1u8; 32]).unwrap();
+        let restored = ProtectedDriveKey::from_bytes(&sealed.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(restored.kdf, protection.kdf);
+        assert!(matches!(
+            restored.open(&master, "drive-b"),
+            Err(PassphraseError::Corrupted)
+        ));
+        assert!(matches!(
+            validate_passphrase("short"),
+            Err(PassphraseError::TooShort)
+        ));
+    }
+}
@@ -0,0 +1,266 @@
+//! Signed share links for a single file
+//!
+//! A share link is narrower than a drive invite: it names one file by path
+//! and content hash, grants read access to exactly that content, and
+//! expires. It is signed by the issuer so it can't be widened or extended.
+
+use crate::crypto::invite::{
+    base64_url_decode, base64_url_encode, generate_token_id, json_deserialize, json_serialize,
+    InviteError,
+};
+use chrono::{DateTime, Duration, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Current share link version
+const SHARE_LINK_VERSION: u8 = 1;
+
+/// The share link payload (signed)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileSharePayload {
+    /// Version for future compatibility
+    pub version: u8,
+    /// Unique token ID, used to revoke the link
+    pub token_id: String,
+    /// Drive the file belongs to (DriveId hex)
+    pub drive_id: String,
+    /// Drive-relative path of the file when the link was created
+    pub path: String,
+    /// BLAKE3 hash of the shared content (hex)
+    pub hash: String,
+    /// Size of the shared content in bytes
+    pub size: u64,
+    /// Chunk manifest the issuer builds the content from, for files it
+    /// stored in chunks (blob hash, hex); omitted for whole blobs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<String>,
+    /// The issuer's NodeId (hex)
+    pub issuer: String,
+    /// When the link was created
+    pub created_at: DateTime<Utc>,
+    /// When the link expires
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A share link token including signature
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FileShareToken {
+    /// The signed payload
+    pub payload: FileSharePayload,
+    /// Ed25519 signature over the payload (hex-encoded)
+    pub signature: String,
+}
+
+impl FileShareToken {
+    /// Create a signed link to one file's content
+    pub fn create(
+        signing_key: &SigningKey,
+        drive_id: &str,
+        path: &str,
+        hash: &str,
+        size: u64,
+        manifest: Option<&str>,
+        validity: Duration,
+    ) -> Result<Self, InviteError> {
+        let now = Utc::now();
+        let payload = FileSharePayload {
+            version: SHARE_LINK_VERSION,
+            token_id: generate_token_id(),
+            drive_id: drive_id.to_string(),
+            path: path.to_string(),
+            hash: hash.to_string(),
+            size,
+            manifest: manifest.map(str::to_string),
+            issuer: hex::encode(signing_key.verifying_key().to_bytes()),
+            created_at: now,
+            expires_at: now + validity,
+        };
+
+        let signature: Signature = signing_key.sign(&json_serialize(&payload)?);
+        Ok(Self {
+            payload,
+            signature: hex::encode(signature.to_bytes()),
+        })
+    }
+
+    /// Verify the signature against the issuer key embedded in the payload
+    pub fn verify_issuer_signature(&self) -> Result<(), InviteError> {
+        let key_bytes: [u8; 32] = hex::decode(&self.payload.issuer)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(InviteError::InvalidFormat)?;
+        let key = VerifyingKey::from_bytes(&key_bytes).map_err(|_| InviteError::InvalidFormat)?;
+
+        let sig_bytes: [u8; 64] = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(InviteError::InvalidFormat)?;
+        key.verify(
+            &json_serialize(&self.payload)?,
+            &Signature::from_bytes(&sig_bytes),
+        )
+        .map_err(|_| InviteError::InvalidSignature)
+    }
+
+    /// Check if the link is expired
+    pub fn is_expired(&self) -> bool {
+        self.payload.expires_at < Utc::now()
+    }
+
+    /// Serialize to URL-safe base64
+    pub fn to_string(&self) -> Result<String, InviteError> {
+        Ok(base64_url_encode(&json_serialize(self)?))
+    }
+
+    /// Parse from URL-safe base64
+    pub fn from_string(s: &str) -> Result<Self, InviteError> {
+        json_deserialize(&base64_url_decode(s)?)
+    }
+
+    /// Get the token ID
+    pub fn token_id(&self) -> &str {
+        &self.payload.token_id
+    }
+}
+
+/// Share links this node issued for one drive, and the ones it revoked
+///
+/// Kept apart from revoked invite tokens, so revoking a link can never
+/// revoke an invite.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ShareLinkRecords {
+    /// Token IDs of links issued by this node
+    #[serde(default)]
+    pub issued: HashSet<String>,
+    /// Token IDs of revoked links
+    #[serde(default)]
+    pub revoked: HashSet<String>,
+}
+
+impl ShareLinkRecords {
+    /// Record a link issued by this node
+    pub fn record_issued(&mut self, token_id: &str) {
+        self.issued.insert(token_id.to_string());
+    }
+
+    /// Check if this node issued a link
+    pub fn is_issued(&self, token_id: &str) -> bool {
+        self.issued.contains(token_id)
+    }
+
+    /// Revoke a link, returning false if it was already revoked
+    pub fn revoke(&mut self, token_id: &str) -> bool {
+        self.revoked.insert(token_id.to_string())
+    }
+
+    /// Check if a link is revoked
+    pub fn is_revoked(&self, token_id: &str) -> bool {
+        self.revoked.contains(token_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn test_share_link_roundtrip_and_tamper() {
+        let key = SigningKey::generate(&mut OsRng);
+        let token = FileShareToken::create(
+            &key,
+            "drive123",
+            "docs/a.txt",
+            "abcd",
+            42,
+            None,
+            Duration::hours(1),
+        )
+        .unwrap();
+        assert!(!token.is_expired());
+
+        let parsed = FileShareToken::from_string(&token.to_string().unwrap()).unwrap();
+        assert_eq!(parsed.token_id(), token.token_id());
+        assert!(parsed.verify_issuer_signature().is_ok());
+
+        // Pointing the link at another file breaks the signature
+        let mut widened = parsed.clone();
+        widened.payload.path = "docs/secret.txt".to_string();
+        assert!(matches!(
+            widened.verify_issuer_signature(),
+            Err(InviteError::InvalidSignature)
+        ));
+
+        let mut extended = parsed;
+        extended.payload.expires_at += Duration::days(30);
+        assert!(extended.verify_issuer_signature().is_err());
+    }
+
+    #[test]
+    fn test_share_link_expiry() {
+        let key = SigningKey::generate(&mut OsRng);
+        let token = FileShareToken::create(
+            &key,
+            "drive123",
+            "a.txt",
+            "abcd",
+            1,
+            None,
+            Duration::seconds(-1),
+        )
+        .unwrap();
+        assert!(token.is_expired());
+        assert!(token.verify_issuer_signature().is_ok());
+    }
+
+    #[test]
+    fn test_share_link_manifest_is_signed() {
+        let key = SigningKey::generate(&mut OsRng);
+        let whole = FileShareToken::create(
+            &key,
+            "drive123",
+            "a.bin",
+            "abcd",
+            1,
+            None,
+            Duration::hours(1),
+        )
+        .unwrap();
+        // Links to whole blobs serialize as before
+        assert!(!serde_json::to_string(&whole.payload)
+            .unwrap()
+            .contains("manifest"));
+
+        let chunked = FileShareToken::create(
+            &key,
+            "drive123",
+            "a.bin",
+            "abcd",
+            1,
+            Some("ef01"),
+            Duration::hours(1),
+        )
+        .unwrap();
+        let mut swapped = chunked.clone();
+        swapped.payload.manifest = Some("2345".to_string());
+        assert!(chunked.verify_issuer_signature().is_ok());
+        assert!(swapped.verify_issuer_signature().is_err());
+    }
+
+    #[test]
+    fn test_share_link_records() {
+        let mut records = ShareLinkRecords::default();
+        records.record_issued("link-1");
+        assert!(records.is_issued("link-1"));
+        assert!(!records.is_issued("link-2"));
+
+        assert!(records.revoke("link-1"));
+        assert!(!records.revoke("link-1"));
+        assert!(records.is_revoked("link-1"));
+
+        let restored: ShareLinkRecords =
+            serde_json::from_slice(&serde_json::to_vec(&records).unwrap()).unwrap();
+        assert!(restored.is_issued("link-1") && restored.is_revoked("link-1"));
+    }
+}
@@ -0,0 +1,240 @@
+//! Launch options and single-instance configuration
+//!
+//! By default only one Gix instance runs; launching again focuses the
+//! existing window. Passing `--data-dir <path>` starts an independent
+//! instance on a separate profile, and single-instance enforcement can be
+//! turned off entirely via `instance.json` in the default data directory.
+//! Both are read before Tauri starts, so they cannot live in the database.
+//!
+//! A separate profile is still only opened by one instance at a time: the
+//! instance using it holds a lock file in its data directory.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+/// File in the default data directory holding instance settings
+const INSTANCE_CONFIG_FILE: &str = "instance.json";
+
+/// File in a `--data-dir` profile locked by the instance using it
+const DATA_DIR_LOCK_FILE: &str = "instance.lock";
+
+/// Options parsed from the command line
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LaunchOptions {
+    /// Data directory override (`--data-dir <path>` or `--data-dir=<path>`),
+    /// resolved against the working directory
+    pub data_dir: Option<PathBuf>,
+}
+
+impl LaunchOptions {
+    /// Parse launch options, ignoring arguments we don't know
+    pub fn from_args<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            if let Some(value) = arg.strip_prefix("--data-dir=") {
+                options.data_dir = Some(PathBuf::from(value));
+            } else if arg == "--data-dir" {
+                options.data_dir = args.next().map(|v| PathBuf::from(v.as_ref()));
+            }
+        }
+
+        let cwd = std::env::current_dir().unwrap_or_default();
+        options.data_dir = options
+            .data_dir
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(|dir| resolve_dir(&dir, &cwd));
+        options
+    }
+}
+
+/// Make `dir` absolute against `cwd`, resolving symlinks and `..` if it
+/// exists, so every spelling of a profile's path names the same directory
+pub fn resolve_dir(dir: &Path, cwd: &Path) -> PathBuf {
+    let absolute = cwd.join(dir);
+    std::fs::canonicalize(&absolute).unwrap_or(absolute)
+}
+
+/// Persisted instance settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceConfig {
+    /// Focus the running instance instead of starting another
+    pub single_instance: bool,
+}
+
+impl Default for InstanceConfig {
+    fn default() -> Self {
+        Self {
+            single_instance: true,
+        }
+    }
+}
+
+impl InstanceConfig {
+    /// Load settings from a data directory, falling back to defaults
+    pub fn load(data_dir: &Path) -> Self {
+        std::fs::read(data_dir.join(INSTANCE_CONFIG_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save settings to a data directory
+    pub fn save(&self, data_dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(data_dir)?;
+        let bytes = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(data_dir.join(INSTANCE_CONFIG_FILE), bytes)
+    }
+}
+
+/// Where Tauri puts app data for this identifier when no override is given
+pub fn default_data_dir(identifier: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(identifier))
+}
+
+/// Whether this launch uses its own profile rather than the default one
+///
+/// A `--data-dir` naming the default directory is not a separate profile.
+pub fn is_separate_profile(options: &LaunchOptions, default_data_dir: Option<&Path>) -> bool {
+    match (&options.data_dir, default_data_dir) {
+        (None, _) => false,
+        (Some(dir), Some(default)) => {
+            *dir != std::fs::canonicalize(default).unwrap_or_else(|_| default.to_path_buf())
+        }
+        (Some(_), None) => true,
+    }
+}
+
+/// Whether to register the single-instance plugin for this launch
+///
+/// A separate profile always runs independently of the default one (see
+/// `lock_data_dir`); otherwise the persisted setting decides.
+pub fn enforce_single_instance(
+    options: &LaunchOptions,
+    config: &InstanceConfig,
+    default_data_dir: Option<&Path>,
+) -> bool {
+    !is_separate_profile(options, default_data_dir) && config.single_instance
+}
+
+/// Held by the instance using a data directory until it exits
+#[derive(Debug)]
+pub struct DataDirLock {
+    _file: File,
+}
+
+/// Lock a data directory for this instance
+///
+/// Returns None if another instance already holds the lock.
+pub fn lock_data_dir(data_dir: &Path) -> std::io::Result<Option<DataDirLock>> {
+    std::fs::create_dir_all(data_dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(data_dir.join(DATA_DIR_LOCK_FILE))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(DataDirLock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Information about the running instance, managed as Tauri state
+#[derive(Clone, Debug)]
+pub struct InstanceInfo {
+    /// Data directory in use
+    pub data_dir: PathBuf,
+    /// True if started with `--data-dir`
+    pub data_dir_overridden: bool,
+    /// Default data directory, where instance settings are stored
+    pub default_data_dir: Option<PathBuf>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_data_dir() {
+        let options = LaunchOptions::from_args(["gix", "--minimized", "--data-dir", "/tmp/p2"]);
+        assert_eq!(options.data_dir, Some(PathBuf::from("/tmp/p2")));
+
+        let options = LaunchOptions::from_args(["gix", "--data-dir=/tmp/p3"]);
+        assert_eq!(options.data_dir, Some(PathBuf::from("/tmp/p3")));
+
+        assert_eq!(LaunchOptions::from_args(["gix", "--data-dir"]).data_dir, None);
+        assert_eq!(LaunchOptions::from_args(["gix"]).data_dir, None);
+    }
+
+    #[test]
+    fn test_resolve_relative_data_dir() {
+        let cwd = tempfile::tempdir().unwrap();
+        let cwd = std::fs::canonicalize(cwd.path()).unwrap();
+        let profile = cwd.join("profile");
+        std::fs::create_dir(&profile).unwrap();
+
+        assert_eq!(resolve_dir(Path::new("profile"), &cwd), profile);
+        assert_eq!(resolve_dir(Path::new("./profile/"), &cwd), profile);
+        assert_eq!(resolve_dir(&profile, Path::new("/elsewhere")), profile);
+        // Not created yet: made absolute as given
+        assert_eq!(resolve_dir(Path::new("new"), &cwd), cwd.join("new"));
+    }
+
+    #[test]
+    fn test_enforce_single_instance() {
+        let default = LaunchOptions::default();
+        let profile = LaunchOptions {
+            data_dir: Some(PathBuf::from("/tmp/p2")),
+        };
+        let disabled = InstanceConfig {
+            single_instance: false,
+        };
+
+        let default_dir = Some(Path::new("/tmp/p1"));
+        let enabled = InstanceConfig::default();
+
+        assert!(enforce_single_instance(&default, &enabled, default_dir));
+        assert!(!enforce_single_instance(&default, &disabled, default_dir));
+        assert!(!enforce_single_instance(&profile, &enabled, default_dir));
+        assert!(!enforce_single_instance(&profile, &enabled, None));
+
+        // Naming the default directory explicitly is still the default profile
+        let explicit = LaunchOptions {
+            data_dir: Some(PathBuf::from("/tmp/p1")),
+        };
+        assert!(is_separate_profile(&profile, default_dir));
+        assert!(!is_separate_profile(&explicit, default_dir));
+        assert!(enforce_single_instance(&explicit, &enabled, default_dir));
+    }
+
+    #[test]
+    fn test_data_dir_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = lock_data_dir(dir.path()).unwrap();
+        assert!(lock.is_some());
+        assert!(lock_data_dir(dir.path()).unwrap().is_none());
+
+        drop(lock);
+        assert!(lock_data_dir(dir.path()).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_instance_config_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(InstanceConfig::load(dir.path()), InstanceConfig::default());
+
+        let config = InstanceConfig {
+            single_instance: false,
+        };
+        config.save(dir.path()).unwrap();
+        assert_eq!(InstanceConfig::load(dir.path()), config);
+    }
+}
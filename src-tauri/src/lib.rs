@@ -1,35 +1,80 @@
 mod commands;
 mod core;
 mod crypto;
+mod instance;
 mod network;
 mod state;
 mod storage;
+mod test_peer;
 mod tray;
 
 use commands::{
-    accept_invite, acquire_lock, cancel_transfer, check_permission, create_drive, delete_drive,
-    delete_path, dismiss_conflict, download_file, extend_lock, force_release_lock, generate_invite,
-    get_audit_count, get_audit_log, get_conflict, get_conflict_count, get_connection_status,
-    get_denied_access_log, get_drive, get_drive_audit_log, get_identity, get_lock_status,
-    get_online_count, get_online_users, get_recent_activity, get_sync_diagnostics, get_sync_status,
-    get_transfer,
-    grant_permission, import_file, is_watching, join_drive_presence, leave_drive_presence,
-    list_conflicts, list_drives, list_files, list_locks, list_permissions, list_revoked_tokens,
-    list_transfers, presence_heartbeat, read_file, read_file_encrypted, release_lock, rename_drive,
-    rename_path, resolve_conflict, revoke_invite, revoke_permission, start_sync, start_watching,
-    stop_sync, stop_watching, subscribe_drive_events, upload_file, verify_invite, write_file,
+    accept_invite, acquire_lock, announce_presence, apply_acl_state, archive_drive,
+    auto_accept_invite, backup_all, cancel_lock_request, cancel_operation, cancel_peer_retry,
+    cancel_transfer, check_database_integrity, check_drive_consistency, check_permission,
+    cleanup_orphan_namespaces, compute_peer_gap, create_drive, create_file_share_link, delete_drive,
+    delete_path, discard_pending_upload, dismiss_conflict, download_file, drain_drive,
+    export_file_tree, extend_lock, fetch_file_preview, fetch_shared_file, force_release_lock,
+    generate_access_report, generate_invite, get_audit_count, get_audit_log, get_bandwidth_limit,
+    get_blob_store_stats, get_change_summary_config, get_conflict, get_conflict_count,
+    get_connection_status, get_default_invite_permission, get_denied_access_log,
+    get_download_temp_dir, get_drive, get_drive_audit_log, get_drive_merkle_root,
+    get_drive_metadata, get_drive_peers, get_drive_timeline, get_effective_permission,
+    get_encryption_overhead, get_endpoint_config, get_file_details, get_file_history,
+    get_file_readers, get_identity, get_instance_settings, get_invite_analytics,
+    get_key_cache_policy, get_key_lock_status, get_lock_queue, get_lock_status,
+    get_maintenance_window, get_no_direct_sync, get_notification_prefs, get_online_count,
+    get_online_users, get_peer_metrics, get_peer_retry_config, get_power_status,
+    get_presence_timeout, get_recent_activity, get_sync_diagnostics, get_sync_filters,
+    get_sync_status, get_total_conflict_count, get_transfer, get_transfer_chunk_size,
+    get_transfer_limits, get_trusted_inviters, get_watch_mode, get_webhooks, grant_permission,
+    import_file, is_watching, join_drive_presence, leave_drive_presence, list_active_subscriptions,
+    list_all_conflicts, list_conflicts, list_deleted_drives, list_drives, list_files,
+    list_files_lazy, list_files_recursive, list_files_since, list_lock_queue, list_locks,
+    list_my_locks, list_operations, list_orphan_namespaces, list_pending_uploads,
+    list_permission_errors, list_permissions, list_revoked_tokens, list_test_peers, list_transfers,
+    list_watch_status, lock_keys, mark_file_read, measure_e2e_sync_latency, merge_drives,
+    merge_text_conflict, notify_drive, parse_invite_link, pause_sync, presence_heartbeat,
+    preview_join_cost, read_file, read_file_encrypted, reimport_drive_blobs, release_all_my_locks,
+    release_lock, reload_ignore_rules, rename_drive, rename_path, repair_drive_docs_mapping,
+    resolve_all_conflicts, resolve_conflict, restore_all, restore_drive, restore_version,
+    resume_drive, resume_sync, resume_transfer, retry_pending_uploads, retry_permission_errors,
+    revalidate_issued_invites, revoke_file_share_link, revoke_invite, revoke_permission,
+    rotate_doc_sharing, scan_drive_safety, self_test_drive, set_activity_retention,
+    set_bandwidth_limit, set_change_summary_config, set_conflict_policy, set_conflict_sensitivity,
+    set_default_invite_permission, set_download_temp_dir, set_drive_metadata, set_endpoint_config,
+    set_key_cache_policy, set_key_passphrase, set_maintenance_window, set_max_concurrent_transfers,
+    set_max_file_size, set_max_peers, set_name_policy, set_new_file_policy, set_no_direct_sync,
+    set_notification_prefs, set_peer_retry_config, set_presence_timeout, set_read_receipts,
+    set_single_instance, set_startup_integrity_check, set_sync_filters, set_sync_only_on_ac,
+    set_transfer_chunk_size, set_trusted_inviters, set_watch_debounce, set_watch_mode,
+    set_watch_throttle, set_webhook, spawn_test_peer, start_sync, start_watching, stop_sync,
+    stop_test_peer, stop_watching, subscribe_audit, subscribe_drive_events,
+    subscribe_drive_events_filtered, test_relay_connectivity, test_webhook, unarchive_drive,
+    unlock_keys, unsubscribe_all_drive_events, unsubscribe_audit, unsubscribe_drive_events,
+    upload_file, verify_drive_docs_mapping, verify_invite, verify_local_matches_blobs, write_file,
     write_file_encrypted, SecurityStore,
 };
 use core::{
-    AuditLogger, ConflictManager, DriveEvent, DriveEventDto, DriveId, LockManager, PresenceManager,
-    RateLimiter, SharedRateLimiter,
+    file, AuditLogger, ConflictManager, DriveEvent, DriveEventDto, DriveId, EventSubscriptions,
+    notification_body, LockManager, MaintenanceScheduler, MaintenanceWindow, NotificationCategory,
+    OperationRegistry, PowerMonitor, PresenceManager, MAINTENANCE_WINDOW_SETTING,
+    PRESENCE_TIMEOUT_SETTING, RateLimiter, ReadReceiptStore, SharedDrive, SharedRateLimiter,
+    SYNC_ONLY_ON_AC_SETTING,
 };
+use crypto::NodeId;
+use instance::{InstanceConfig, InstanceInfo, LaunchOptions};
 use state::AppState;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::{AppHandle, Emitter, Manager, RunEvent};
-use tokio::sync::broadcast;
+use tauri::{AppHandle, Manager, RunEvent};
+use tauri_plugin_notification::NotificationExt;
+use test_peer::TestPeerManager;
+use tokio::sync::{broadcast, RwLock};
 
-use crate::network::SyncEngine;
+use crate::network::{
+    EventBroadcaster, KeyProofTracker, SyncEngine, WebhookDispatcher, WEBHOOK_SETTING,
+};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -44,7 +89,57 @@ pub fn run() {
 
     tracing::info!("Starting Gix P2P Drive Share");
 
-    tauri::Builder::default()
+    // Launch options and instance settings must be known before the builder runs
+    let context = tauri::generate_context!();
+    let launch_options = LaunchOptions::from_args(std::env::args().skip(1));
+    let default_data_dir = instance::default_data_dir(&context.config().identifier);
+    let instance_config = default_data_dir
+        .as_deref()
+        .map(InstanceConfig::load)
+        .unwrap_or_default();
+
+    // A separate profile can't be handed to its running instance, so a second
+    // launch on the same directory just exits
+    let separate_profile = launch_options
+        .data_dir
+        .as_deref()
+        .filter(|_| instance::is_separate_profile(&launch_options, default_data_dir.as_deref()));
+    let _data_dir_lock = match separate_profile {
+        Some(dir) => match instance::lock_data_dir(dir) {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                tracing::error!(data_dir = ?dir, "Data directory is in use by another instance");
+                return;
+            }
+            Err(e) => {
+                tracing::warn!(data_dir = ?dir, "Failed to lock data directory: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    let mut builder = tauri::Builder::default();
+    if instance::enforce_single_instance(
+        &launch_options,
+        &instance_config,
+        default_data_dir.as_deref(),
+    ) {
+        builder = builder.plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            // Show window when another instance is launched
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }));
+    } else {
+        tracing::info!(
+            data_dir = ?launch_options.data_dir,
+            "Single-instance enforcement disabled for this launch"
+        );
+    }
+
+    builder
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
@@ -53,17 +148,10 @@ pub fn run() {
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             Some(vec!["--minimized"]),
         ))
-        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
-            // Show window when another instance is launched
-            if let Some(window) = app.get_webview_window("main") {
-                let _ = window.show();
-                let _ = window.set_focus();
-            }
-        }))
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_process::init())
-        .setup(|app| {
+        .setup(move |app| {
             // Initialize system tray
             if let Err(e) = tray::init(app) {
                 tracing::error!("Failed to initialize system tray: {}", e);
@@ -71,17 +159,25 @@ pub fn run() {
 
             let app_handle = app.handle().clone();
 
-            // Get data directory - use match instead of expect for production safety
-            let data_dir = match app.path().app_data_dir() {
-                Ok(dir) => dir,
-                Err(e) => {
-                    tracing::error!("Failed to get app data directory: {}", e);
-                    // Use a fallback directory in temp
-                    std::env::temp_dir().join("gix-portal")
-                }
+            // Get data directory - a --data-dir override selects a separate profile
+            let data_dir = match launch_options.data_dir.clone() {
+                Some(dir) => dir,
+                None => match app.path().app_data_dir() {
+                    Ok(dir) => dir,
+                    Err(e) => {
+                        tracing::error!("Failed to get app data directory: {}", e);
+                        // Use a fallback directory in temp
+                        std::env::temp_dir().join("gix-portal")
+                    }
+                },
             };
 
             tracing::info!("Data directory: {:?}", data_dir);
+            app_handle.manage(InstanceInfo {
+                data_dir: data_dir.clone(),
+                data_dir_overridden: launch_options.data_dir.is_some(),
+                default_data_dir: default_data_dir.clone(),
+            });
 
             // Initialize state synchronously to ensure it's available before any commands run
             // Using block_on since we're not in an async context but need to await the initialization
@@ -90,13 +186,32 @@ pub fn run() {
 
             match state {
                 Ok(state) => {
+                    // Registry for filtered event subscriptions, fed by every forwarder
+                    let event_subscriptions = Arc::new(EventSubscriptions::new());
+                    app_handle.manage(event_subscriptions.clone());
+
+                    // Cancellation tokens for long-running commands
+                    app_handle.manage(Arc::new(OperationRegistry::new()));
+
+                    // Which members of encrypted drives proved they hold the key
+                    let key_proofs = Arc::new(KeyProofTracker::new());
+                    app_handle.manage(key_proofs.clone());
+
                     // Spawn event forwarding task if event_broadcaster is available
                     if let Some(ref broadcaster) = state.event_broadcaster {
                         let event_rx = broadcaster.subscribe_frontend();
                         let app_handle_for_events = app_handle.clone();
+                        let subscriptions = event_subscriptions.clone();
+                        let drives = state.drives.clone();
 
                         tauri::async_runtime::spawn(async move {
-                            spawn_event_forwarder(app_handle_for_events, event_rx).await;
+                            spawn_event_forwarder(
+                                app_handle_for_events,
+                                event_rx,
+                                subscriptions,
+                                drives,
+                            )
+                            .await;
                         });
                     }
 
@@ -105,19 +220,38 @@ pub fn run() {
                         (&state.file_watcher, &state.sync_engine)
                     {
                         let watcher_rx = watcher.subscribe();
+                        let error_rx = watcher.subscribe_errors();
                         let sync_engine_clone = sync_engine.clone();
                         let app_handle_for_watcher = app_handle.clone();
+                        let app_handle_for_errors = app_handle.clone();
+                        let subscriptions = event_subscriptions.clone();
+                        let error_subscriptions = event_subscriptions.clone();
 
                         tauri::async_runtime::spawn(async move {
                             spawn_watcher_forwarder(
                                 app_handle_for_watcher,
                                 watcher_rx,
                                 sync_engine_clone,
+                                subscriptions,
+                            )
+                            .await;
+                        });
+
+                        tauri::async_runtime::spawn(async move {
+                            spawn_watcher_error_forwarder(
+                                app_handle_for_errors,
+                                error_rx,
+                                error_subscriptions,
                             )
                             .await;
                         });
                     }
 
+                    // Re-send deferred changes as peers come online
+                    if let Some(ref sync_engine) = state.sync_engine {
+                        let _outbound_handle = sync_engine.start_outbound_retry();
+                    }
+
                     // Get node ID for managers - handle gracefully if not available
                     let node_id = tauri::async_runtime::block_on(async {
                         state.identity_manager.node_id().await
@@ -133,6 +267,70 @@ pub fn run() {
                         }
                     };
 
+                    // Check that members of encrypted drives hold the drive key
+                    if let (Some(broadcaster), Some(encryption)) =
+                        (&state.event_broadcaster, &state.encryption_manager)
+                    {
+                        let _key_proof_handle = network::key_proof::start_key_proof_responder(
+                            node_id,
+                            broadcaster.clone(),
+                            encryption.clone(),
+                            key_proofs.clone(),
+                        );
+                    }
+
+                    // Download and acknowledge peers' sync latency probes
+                    if let (Some(broadcaster), Some(transfer)) =
+                        (&state.event_broadcaster, &state.file_transfer)
+                    {
+                        let _probe_handle = network::latency::start_probe_responder(
+                            node_id,
+                            state.drives.clone(),
+                            broadcaster.clone(),
+                            transfer.clone(),
+                        );
+
+                        // Fetch, skip or offer files peers add, per drive policy
+                        let (new_file_tx, new_file_rx) = broadcast::channel(256);
+                        let _new_file_handle = network::new_files::start_new_file_responder(
+                            node_id,
+                            state.drives.clone(),
+                            broadcaster.clone(),
+                            transfer.clone(),
+                            new_file_tx,
+                        );
+                        let app_handle_for_new_files = app_handle.clone();
+                        let subscriptions = event_subscriptions.clone();
+                        tauri::async_runtime::spawn(async move {
+                            spawn_local_event_forwarder(
+                                app_handle_for_new_files,
+                                new_file_rx,
+                                subscriptions,
+                                "new_file",
+                            )
+                            .await;
+                        });
+                    }
+
+                    // POST selected drive events to configured webhooks
+                    let webhook_settings = state
+                        .db
+                        .get_setting(WEBHOOK_SETTING)
+                        .ok()
+                        .flatten()
+                        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+                        .unwrap_or_default();
+                    match WebhookDispatcher::new(webhook_settings) {
+                        Ok(dispatcher) => {
+                            let dispatcher = Arc::new(dispatcher);
+                            if let Some(ref broadcaster) = state.event_broadcaster {
+                                let _webhook_handle = dispatcher.start(broadcaster);
+                            }
+                            app_handle.manage(dispatcher);
+                        }
+                        Err(e) => tracing::error!("Failed to start webhook dispatcher: {}", e),
+                    }
+
                     // Initialize SecurityStore for Phase 3 with database persistence
                     let security_store = Arc::new(SecurityStore::new(state.db.clone()));
                     // Load persisted ACLs from database
@@ -141,6 +339,76 @@ pub fn run() {
                     }
                     app_handle.manage(security_store.clone());
 
+                    // Serve blobs and share links, and tell peers which files we hold
+                    // when they ask, except to peers that failed the key check
+                    {
+                        let security_for_summaries = security_store.clone();
+                        let acl_lookup: network::AclLookup = Arc::new(move |drive_id, owner| {
+                            tokio::task::block_in_place(|| {
+                                tokio::runtime::Handle::current().block_on(
+                                    security_for_summaries.get_or_create_acl(drive_id, owner),
+                                )
+                            })
+                        });
+                        let blobs = state
+                            .file_transfer
+                            .as_ref()
+                            .map(|transfer| transfer.blobs_protocol(key_proofs.clone()));
+                        let summaries = state.sync_engine.clone().map(|engine| {
+                            network::SummaryProtocol::new(
+                                engine,
+                                state.drives.clone(),
+                                key_proofs.clone(),
+                                acl_lookup,
+                            )
+                        });
+                        // Share links are only served after the issuer checks them
+                        let security_for_links = security_store.clone();
+                        let drives_for_links = state.drives.clone();
+                        let link_check: network::ShareLinkCheck = Arc::new(move |token| {
+                            tokio::task::block_in_place(|| {
+                                tokio::runtime::Handle::current().block_on(
+                                    commands::check_issued_share_link(
+                                        token,
+                                        &drives_for_links,
+                                        &security_for_links,
+                                    ),
+                                )
+                            })
+                        });
+                        let share_links = state.file_transfer.clone().map(|transfer| {
+                            network::ShareLinkProtocol::new(node_id, transfer, link_check)
+                        });
+                        let endpoint = state.endpoint.clone();
+                        tauri::async_runtime::spawn(async move {
+                            endpoint
+                                .serve(|mut router| {
+                                    if let Some(blobs) = blobs {
+                                        router = router.accept(iroh_blobs::ALPN, blobs);
+                                    }
+                                    if let Some(summaries) = summaries {
+                                        router = router.accept(network::SUMMARY_ALPN, summaries);
+                                    }
+                                    if let Some(share_links) = share_links {
+                                        router =
+                                            router.accept(network::SHARE_LINK_ALPN, share_links);
+                                    }
+                                    router
+                                })
+                                .await;
+                        });
+                    }
+
+                    // In-process test peers for reproducing sync issues (debug builds only)
+                    app_handle.manage(Arc::new(TestPeerManager::new()));
+
+                    // Initialize read receipt store for owned drives
+                    let receipt_store = Arc::new(ReadReceiptStore::new(state.db.clone()));
+                    if let Err(e) = receipt_store.load_from_db() {
+                        tracing::error!("Failed to load read receipts from database: {}", e);
+                    }
+                    app_handle.manage(receipt_store.clone());
+
                     // Initialize AuditLogger for security event tracking
                     let audit_logger = Arc::new(AuditLogger::new(state.db.clone()));
                     app_handle.manage(audit_logger);
@@ -163,10 +431,35 @@ pub fn run() {
                                 acl.check_permission(sender_id, "/", Permission::Read)
                             });
 
+                        // Only invites issued here let a non-member's acceptance through
+                        let security_for_invites = security_store.clone();
+                        let invite_checker: network::InviteChecker =
+                            Arc::new(move |drive_id, token_id, acceptor| {
+                                tokio::task::block_in_place(|| {
+                                    tokio::runtime::Handle::current().block_on(
+                                        security_for_invites
+                                            .invite_admits(drive_id, token_id, acceptor),
+                                    )
+                                })
+                            });
+
                         // Set the ACL checker asynchronously
                         let broadcaster_clone = broadcaster.clone();
                         tauri::async_runtime::spawn(async move {
                             broadcaster_clone.set_acl_checker(acl_checker).await;
+                            broadcaster_clone.set_invite_checker(invite_checker).await;
+                        });
+
+                        // Record invite acceptances and read receipts from peers
+                        let remote_rx = broadcaster.subscribe_frontend();
+                        let recorder = RemoteEventRecorder {
+                            node_id,
+                            drives: state.drives.clone(),
+                            security_store: security_store.clone(),
+                            receipt_store: receipt_store.clone(),
+                        };
+                        tauri::async_runtime::spawn(async move {
+                            spawn_remote_event_recorder(remote_rx, recorder).await;
                         });
                     }
 
@@ -179,24 +472,119 @@ pub fn run() {
                     let lock_manager = Arc::new(LockManager::new(node_id));
                     app_handle.manage(lock_manager.clone());
 
+                    // Forward lock queue events to the frontend
+                    let lock_rx = lock_manager.subscribe();
+                    let app_handle_for_locks = app_handle.clone();
+                    let subscriptions = event_subscriptions.clone();
+                    tauri::async_runtime::spawn(async move {
+                        spawn_local_event_forwarder(
+                            app_handle_for_locks,
+                            lock_rx,
+                            subscriptions,
+                            "lock",
+                        )
+                        .await;
+                    });
+
+                    // Announce locks handed to queued requests, whether by a
+                    // release, an expiry or a peer's release
+                    if let Some(ref broadcaster) = state.event_broadcaster {
+                        let grant_rx = lock_manager.subscribe();
+                        let broadcaster = broadcaster.clone();
+                        tauri::async_runtime::spawn(async move {
+                            announce_lock_grants(grant_rx, broadcaster).await;
+                        });
+                    }
+
                     // Initialize ConflictManager for Phase 4
                     let conflict_manager = Arc::new(ConflictManager::new());
                     app_handle.manage(conflict_manager.clone());
 
+                    // Restore each drive's conflict policy
+                    let conflict_manager_for_policies = conflict_manager.clone();
+                    let drives_for_policies = state.drives.clone();
+                    tauri::async_runtime::spawn(async move {
+                        for drive in drives_for_policies.read().await.values() {
+                            conflict_manager_for_policies
+                                .set_policy(&drive.id.to_hex(), drive.conflict_policy)
+                                .await;
+                        }
+                    });
+
+                    // Forward auto-resolved conflicts to the frontend
+                    let conflict_rx = conflict_manager.subscribe();
+                    let app_handle_for_conflicts = app_handle.clone();
+                    let subscriptions = event_subscriptions.clone();
+                    tauri::async_runtime::spawn(async move {
+                        spawn_local_event_forwarder(
+                            app_handle_for_conflicts,
+                            conflict_rx,
+                            subscriptions,
+                            "conflict",
+                        )
+                        .await;
+                    });
+
                     // Initialize PresenceManager for Phase 4
                     let presence_manager = Arc::new(PresenceManager::new(node_id));
+                    if let Ok(Some(bytes)) = state.db.get_setting(PRESENCE_TIMEOUT_SETTING) {
+                        let secs = serde_json::from_slice(&bytes).unwrap_or_default();
+                        if !presence_manager.set_timeout_secs(secs) {
+                            tracing::warn!("Ignoring invalid presence timeout: {}s", secs);
+                        }
+                    }
                     app_handle.manage(presence_manager.clone());
 
+                    // Forward presence timeouts to the frontend
+                    let presence_rx = presence_manager.subscribe();
+                    let app_handle_for_presence = app_handle.clone();
+                    let subscriptions = event_subscriptions.clone();
+                    tauri::async_runtime::spawn(async move {
+                        spawn_local_event_forwarder(
+                            app_handle_for_presence,
+                            presence_rx,
+                            subscriptions,
+                            "presence",
+                        )
+                        .await;
+                    });
+
+                    // Restrict heavy housekeeping to the configured window
+                    let window_setting = state.db.get_setting(MAINTENANCE_WINDOW_SETTING);
+                    let maintenance_window = match window_setting {
+                        Ok(Some(bytes)) => serde_json::from_slice(&bytes).unwrap_or_default(),
+                        _ => MaintenanceWindow::default(),
+                    };
+                    let maintenance = Arc::new(MaintenanceScheduler::new(maintenance_window));
+                    app_handle.manage(maintenance.clone());
+
                     // Start cleanup manager for resource maintenance
-                    let cleanup_manager = core::CleanupManager::new();
+                    let cleanup_manager = Arc::new(core::CleanupManager::new());
+                    app_handle.manage(cleanup_manager.clone());
                     let _cleanup_handle = cleanup_manager.start(
                         lock_manager,
                         conflict_manager,
                         presence_manager,
                         security_store,
+                        maintenance,
+                        state.db.clone(),
+                        state.drives.clone(),
                     );
                     tracing::info!("Cleanup manager started");
 
+                    // Pause sync on battery if the user opted in
+                    let sync_only_on_ac = match state.db.get_setting(SYNC_ONLY_ON_AC_SETTING) {
+                        Ok(Some(bytes)) => serde_json::from_slice(&bytes).unwrap_or(false),
+                        _ => false,
+                    };
+                    let power_monitor = Arc::new(PowerMonitor::new(
+                        sync_only_on_ac,
+                        state.sync_engine.clone(),
+                        state.file_transfer.clone(),
+                    ));
+                    let _power_handle = power_monitor.start();
+                    app_handle.manage(power_monitor);
+
                     // Register EncryptionManager for E2E encryption commands
                     if let Some(ref em) = state.encryption_manager {
                         app_handle.manage(em.clone());
@@ -204,20 +592,26 @@ pub fn run() {
 
                         // SECURITY: Set up window blur listener to clear encryption key cache
                         // This protects against cold boot attacks if device is stolen while app is running
+                        // Whether keys clear immediately or after an idle timeout is set by KeyCachePolicy
                         let em_for_blur = em.clone();
                         if let Some(window) = app_handle.get_webview_window("main") {
-                            window.on_window_event(move |event| {
-                                if let tauri::WindowEvent::Focused(false) = event {
-                                    // Window lost focus - clear encryption key cache for security
+                            window.on_window_event(move |event| match event {
+                                tauri::WindowEvent::Focused(false) => {
+                                    // Window lost focus - clear encryption key cache per policy
                                     let em_clone = em_for_blur.clone();
                                     tauri::async_runtime::spawn(async move {
-                                        if em_clone.clear_cache().await {
+                                        if em_clone.on_focus_lost().await {
                                             tracing::debug!(
                                                 "Encryption key cache cleared due to window blur"
                                             );
                                         }
                                     });
                                 }
+                                tauri::WindowEvent::Focused(true) => {
+                                    // Cancel any pending idle-timeout clear
+                                    em_for_blur.on_focus_gained();
+                                }
+                                _ => {}
                             });
                             tracing::info!(
                                 "Window blur listener configured for encryption cache clearing"
@@ -241,45 +635,126 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             get_identity,
             get_connection_status,
+            test_relay_connectivity,
             create_drive,
             delete_drive,
+            list_deleted_drives,
+            restore_drive,
             rename_drive,
+            archive_drive,
+            unarchive_drive,
+            get_drive_metadata,
+            set_drive_metadata,
+            set_max_file_size,
+            set_name_policy,
+            set_conflict_sensitivity,
+            set_activity_retention,
+            set_new_file_policy,
+            merge_drives,
+            cancel_operation,
+            list_operations,
             list_drives,
             get_drive,
             list_files,
+            list_files_recursive,
+            list_files_since,
+            list_files_lazy,
             read_file,
+            get_file_details,
+            export_file_tree,
+            fetch_file_preview,
             write_file,
             read_file_encrypted,
+            get_encryption_overhead,
+            scan_drive_safety,
             write_file_encrypted,
+            self_test_drive,
             delete_path,
             rename_path,
             // Phase 2: Sync commands
             start_sync,
             stop_sync,
+            pause_sync,
+            resume_sync,
+            drain_drive,
+            resume_drive,
             get_sync_status,
             get_sync_diagnostics,
+            measure_e2e_sync_latency,
+            check_drive_consistency,
+            reimport_drive_blobs,
+            verify_local_matches_blobs,
+            get_drive_merkle_root,
+            compute_peer_gap,
+            list_orphan_namespaces,
+            cleanup_orphan_namespaces,
+            verify_drive_docs_mapping,
+            repair_drive_docs_mapping,
+            get_drive_peers,
+            set_max_peers,
+            get_no_direct_sync,
+            set_no_direct_sync,
+            list_pending_uploads,
+            retry_pending_uploads,
+            discard_pending_upload,
+            get_peer_retry_config,
+            set_peer_retry_config,
+            get_change_summary_config,
+            set_change_summary_config,
+            cancel_peer_retry,
+            get_sync_filters,
+            set_sync_filters,
             subscribe_drive_events,
+            subscribe_drive_events_filtered,
+            unsubscribe_drive_events,
+            unsubscribe_all_drive_events,
+            list_active_subscriptions,
             // Phase 2: File watcher commands
             start_watching,
             stop_watching,
             is_watching,
+            list_watch_status,
+            reload_ignore_rules,
+            list_permission_errors,
+            retry_permission_errors,
             // Phase 2: File transfer commands
             upload_file,
             download_file,
+            get_file_history,
+            restore_version,
             list_transfers,
             get_transfer,
+            get_blob_store_stats,
+            get_peer_metrics,
             cancel_transfer,
+            resume_transfer,
             import_file,
             // Phase 3: Security commands
             generate_invite,
+            get_default_invite_permission,
+            set_default_invite_permission,
             verify_invite,
+            parse_invite_link,
+            preview_join_cost,
             accept_invite,
+            get_trusted_inviters,
+            set_trusted_inviters,
+            auto_accept_invite,
             revoke_invite,
+            create_file_share_link,
+            revoke_file_share_link,
+            fetch_shared_file,
+            rotate_doc_sharing,
             list_revoked_tokens,
+            get_invite_analytics,
+            revalidate_issued_invites,
+            generate_access_report,
             list_permissions,
             grant_permission,
             revoke_permission,
+            apply_acl_state,
             check_permission,
+            get_effective_permission,
             // Phase 4: Locking commands
             acquire_lock,
             release_lock,
@@ -287,26 +762,87 @@ pub fn run() {
             list_locks,
             extend_lock,
             force_release_lock,
+            list_lock_queue,
+            get_lock_queue,
+            cancel_lock_request,
+            list_my_locks,
+            release_all_my_locks,
             // Phase 4: Conflict commands
             list_conflicts,
+            list_all_conflicts,
+            get_total_conflict_count,
             get_conflict,
             resolve_conflict,
             get_conflict_count,
             dismiss_conflict,
+            resolve_all_conflicts,
+            set_conflict_policy,
+            merge_text_conflict,
             // Phase 4: Presence commands
             get_online_users,
             get_online_count,
             get_recent_activity,
+            get_drive_timeline,
             join_drive_presence,
             leave_drive_presence,
             presence_heartbeat,
+            announce_presence,
             // Security: Audit logging commands
             get_audit_log,
             get_audit_count,
             get_drive_audit_log,
             get_denied_access_log,
+            subscribe_audit,
+            unsubscribe_audit,
+            // Settings commands
+            get_key_cache_policy,
+            set_key_cache_policy,
+            get_key_lock_status,
+            set_key_passphrase,
+            unlock_keys,
+            lock_keys,
+            get_download_temp_dir,
+            set_download_temp_dir,
+            get_transfer_chunk_size,
+            set_transfer_chunk_size,
+            get_bandwidth_limit,
+            set_bandwidth_limit,
+            get_transfer_limits,
+            set_max_concurrent_transfers,
+            get_presence_timeout,
+            set_presence_timeout,
+            get_instance_settings,
+            set_single_instance,
+            backup_all,
+            restore_all,
+            check_database_integrity,
+            set_startup_integrity_check,
+            get_power_status,
+            set_sync_only_on_ac,
+            get_maintenance_window,
+            set_maintenance_window,
+            get_watch_mode,
+            set_watch_mode,
+            set_watch_throttle,
+            set_watch_debounce,
+            get_endpoint_config,
+            set_endpoint_config,
+            // Read receipt commands
+            set_read_receipts,
+            get_notification_prefs,
+            set_notification_prefs,
+            notify_drive,
+            get_webhooks,
+            set_webhook,
+            test_webhook,
+            mark_file_read,
+            get_file_readers,
+            // Development commands (debug builds only)
+            spawn_test_peer,
+            list_test_peers,
+            stop_test_peer,
         ])
-        .build(tauri::generate_context!())
+        .build(context)
         .expect("error while building tauri application")
         .run(|app_handle, event| {
             // Handle app lifecycle events for graceful shutdown
@@ -317,6 +853,14 @@ pub fn run() {
                     tracing::info!("Application exit requested, initiating graceful shutdown...");
 
                     // Get app state and perform graceful shutdown
+                    // Tear down test peers before the runtime goes away
+                    if let (Some(test_peers), Some(security)) = (
+                        app_handle.try_state::<Arc<TestPeerManager>>(),
+                        app_handle.try_state::<Arc<SecurityStore>>(),
+                    ) {
+                        tauri::async_runtime::block_on(test_peers.stop_all(&security));
+                    }
+
                     if let Some(state) = app_handle.try_state::<AppState>() {
                         // Use block_on to run the async shutdown within the event handler
                         // This ensures all async resources are cleaned up before the runtime is destroyed
@@ -339,9 +883,14 @@ pub fn run() {
 }
 
 /// Spawns a background task that forwards drive events to the frontend
+///
+/// Remote events also raise a system notification when the drive's
+/// notification preferences allow it.
 async fn spawn_event_forwarder(
     app_handle: AppHandle,
     mut event_rx: broadcast::Receiver<DriveEventDto>,
+    subscriptions: Arc<EventSubscriptions>,
+    drives: Arc<RwLock<HashMap<[u8; 32], SharedDrive>>>,
 ) {
     tracing::info!("Event forwarder started");
 
@@ -349,9 +898,10 @@ async fn spawn_event_forwarder(
         match event_rx.recv().await {
             Ok(event) => {
                 // Emit event to frontend
-                if let Err(e) = app_handle.emit("drive-event", &event) {
+                if let Err(e) = subscriptions.publish(&app_handle, &event) {
                     tracing::warn!("Failed to emit drive event: {}", e);
                 }
+                notify_drive_event(&app_handle, &drives, &event).await;
             }
             Err(broadcast::error::RecvError::Lagged(count)) => {
                 tracing::warn!("Event receiver lagged, missed {} events", count);
@@ -364,27 +914,76 @@ async fn spawn_event_forwarder(
     }
 }
 
+/// Show a system notification for a drive event if the drive's preferences allow it
+async fn notify_drive_event(
+    app_handle: &AppHandle,
+    drives: &RwLock<HashMap<[u8; 32], SharedDrive>>,
+    event: &DriveEventDto,
+) {
+    let Some(category) = NotificationCategory::for_event_type(&event.event_type) else {
+        return;
+    };
+    let Ok(drive_id) = DriveId::from_hex(&event.drive_id) else {
+        return;
+    };
+
+    let name = match drives.read().await.get(drive_id.as_bytes()) {
+        Some(drive) if drive.notifications.allows(category) => drive.name.clone(),
+        _ => return,
+    };
+    let Some(body) = notification_body(event) else {
+        return;
+    };
+
+    if let Err(e) = app_handle.notification().builder().title(name).body(body).show() {
+        tracing::warn!("Failed to show notification: {}", e);
+    }
+}
+
 /// Spawns a background task that forwards file watcher events to SyncEngine and frontend
 async fn spawn_watcher_forwarder(
     app_handle: AppHandle,
     mut watcher_rx: broadcast::Receiver<(DriveId, DriveEvent)>,
     sync_engine: Arc<SyncEngine>,
+    subscriptions: Arc<EventSubscriptions>,
 ) {
     tracing::info!("File watcher forwarder started");
 
+    let emit = |drive_id: &DriveId, event: &DriveEvent| {
+        let dto = DriveEventDto::from_event(&hex::encode(drive_id.as_bytes()), event);
+        if let Err(e) = subscriptions.publish(&app_handle, &dto) {
+            tracing::warn!("Failed to emit watcher event: {}", e);
+        }
+    };
+    // Directory summaries are sent once their burst's window closes
+    let mut summary_tick = tokio::time::interval(std::time::Duration::from_millis(250));
+    summary_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
     loop {
-        match watcher_rx.recv().await {
+        let received = tokio::select! {
+            received = watcher_rx.recv() => received,
+            _ = summary_tick.tick() => {
+                for (drive_id, summary) in sync_engine.flush_change_summaries().await {
+                    emit(&drive_id, &summary);
+                }
+                continue;
+            }
+        };
+        match received {
             Ok((drive_id, event)) => {
                 // Forward to sync engine for processing (metadata updates, gossip broadcast)
-                if let Err(e) = sync_engine.on_local_change(&drive_id, event.clone()).await {
-                    tracing::warn!("Failed to process local change: {}", e);
-                }
+                let event = match sync_engine.on_local_change(&drive_id, event.clone()).await {
+                    Ok(Some(processed)) => processed,
+                    // Folded into a directory summary
+                    Ok(None) => continue,
+                    Err(e) => {
+                        tracing::warn!("Failed to process local change: {}", e);
+                        event
+                    }
+                };
 
                 // Also emit directly to frontend for immediate UI update
-                let dto = DriveEventDto::from_event(&hex::encode(drive_id.as_bytes()), &event);
-                if let Err(e) = app_handle.emit("drive-event", &dto) {
-                    tracing::warn!("Failed to emit watcher event: {}", e);
-                }
+                emit(&drive_id, &event);
             }
             Err(broadcast::error::RecvError::Lagged(count)) => {
                 tracing::warn!("Watcher receiver lagged, missed {} events", count);
@@ -396,3 +995,177 @@ async fn spawn_watcher_forwarder(
         }
     }
 }
+
+/// Spawns a background task that forwards file watcher errors to the frontend
+///
+/// Errors are local only, so unlike file events they skip the sync engine.
+async fn spawn_watcher_error_forwarder(
+    app_handle: AppHandle,
+    mut error_rx: broadcast::Receiver<(DriveId, DriveEvent)>,
+    subscriptions: Arc<EventSubscriptions>,
+) {
+    loop {
+        match error_rx.recv().await {
+            Ok((drive_id, event)) => {
+                let dto = DriveEventDto::from_event(&drive_id.to_hex(), &event);
+                if let Err(e) = subscriptions.publish(&app_handle, &dto) {
+                    tracing::warn!("Failed to emit watcher error: {}", e);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                tracing::warn!("Watcher error receiver lagged, missed {} events", count);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Spawns a background task that forwards locally generated events to the frontend
+///
+/// Used for lock queue changes and presence timeouts, which are observed
+/// locally rather than received over gossip.
+async fn spawn_local_event_forwarder(
+    app_handle: AppHandle,
+    mut event_rx: broadcast::Receiver<(String, DriveEvent)>,
+    subscriptions: Arc<EventSubscriptions>,
+    source: &'static str,
+) {
+    tracing::info!("{} event forwarder started", source);
+
+    loop {
+        match event_rx.recv().await {
+            Ok((drive_id, event)) => {
+                let dto = DriveEventDto::from_event(&drive_id, &event);
+                if let Err(e) = subscriptions.publish(&app_handle, &dto) {
+                    tracing::warn!("Failed to emit {} event: {}", source, e);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                tracing::warn!("{} event receiver lagged, missed {} events", source, count);
+            }
+            Err(broadcast::error::RecvError::Closed) => {
+                tracing::info!("{} event channel closed, stopping forwarder", source);
+                break;
+            }
+        }
+    }
+}
+
+/// Broadcast a `FileLockAcquired` for each lock the LockManager grants
+/// from its queue, so peers learn the new holder
+async fn announce_lock_grants(
+    mut event_rx: broadcast::Receiver<(String, DriveEvent)>,
+    broadcaster: Arc<EventBroadcaster>,
+) {
+    loop {
+        match event_rx.recv().await {
+            Ok((drive_id, event)) => {
+                let DriveEvent::LockGranted {
+                    path,
+                    holder,
+                    lock_type,
+                    expires_at,
+                    timestamp,
+                } = event
+                else {
+                    continue;
+                };
+                let Ok(id) = DriveId::from_hex(&drive_id) else {
+                    continue;
+                };
+                let event = DriveEvent::FileLockAcquired {
+                    path,
+                    holder,
+                    lock_type,
+                    expires_at,
+                    timestamp,
+                };
+                if let Err(e) = broadcaster.broadcast(&id, event).await {
+                    tracing::warn!("Failed to broadcast granted lock: {}", e);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                tracing::warn!("Lock grant receiver lagged, missed {} grants", count);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Local stores updated from authenticated remote events
+struct RemoteEventRecorder {
+    node_id: NodeId,
+    drives: Arc<RwLock<HashMap<[u8; 32], SharedDrive>>>,
+    security_store: Arc<SecurityStore>,
+    receipt_store: Arc<ReadReceiptStore>,
+}
+
+/// Spawns a background task that records invite acceptances and read receipts
+///
+/// Acceptances are only recorded for invites this node issued; receipts are
+/// only recorded for drives we own that have read receipts enabled.
+async fn spawn_remote_event_recorder(
+    mut event_rx: broadcast::Receiver<DriveEventDto>,
+    recorder: RemoteEventRecorder,
+) {
+    loop {
+        let dto = match event_rx.recv().await {
+            Ok(dto) => dto,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                tracing::warn!("Remote event recorder lagged, missed {} events", count);
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        if dto.event_type != "InviteAccepted" && dto.event_type != "FileRead" {
+            continue;
+        }
+
+        match serde_json::from_value::<DriveEvent>(dto.payload) {
+            Ok(DriveEvent::InviteAccepted { token_id, user, .. }) => {
+                let acceptor = user.to_hex();
+                if recorder
+                    .security_store
+                    .record_invite_acceptance(&dto.drive_id, &token_id, &acceptor)
+                    .await
+                {
+                    tracing::info!(
+                        drive_id = %dto.drive_id,
+                        token_id = %token_id,
+                        acceptor = %acceptor,
+                        "Recorded invite acceptance"
+                    );
+                }
+            }
+            Ok(DriveEvent::FileRead {
+                path,
+                reader,
+                timestamp,
+            }) => {
+                let Ok(drive_id) = DriveId::from_hex(&dto.drive_id) else {
+                    continue;
+                };
+                let should_record = recorder
+                    .drives
+                    .read()
+                    .await
+                    .get(drive_id.as_bytes())
+                    .map(|d| d.read_receipts && d.owner == recorder.node_id)
+                    .unwrap_or(false);
+                if should_record {
+                    recorder
+                        .receipt_store
+                        .record(
+                            &dto.drive_id,
+                            &file::path_key(&path),
+                            &reader.to_hex(),
+                            timestamp,
+                        )
+                        .await;
+                }
+            }
+            _ => {}
+        }
+    }
+}
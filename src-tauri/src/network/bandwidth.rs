@@ -0,0 +1,128 @@
+//! Bandwidth limiting for blob store reads and writes
+//!
+//! A token bucket shared by every transfer caps their combined throughput.
+//! The limit can be changed while transfers run.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Settings key for the configured bandwidth limit (bytes/sec)
+pub const BANDWIDTH_LIMIT_SETTING: &str = "bandwidth_limit";
+
+/// Longest burst allowed after an idle period, as time at the limit
+const MAX_BURST: Duration = Duration::from_millis(100);
+
+/// Token bucket capping transfer throughput
+pub struct BandwidthLimiter {
+    /// Limit in bytes/sec; 0 means unlimited
+    bytes_per_sec: AtomicU64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    /// Bytes that may pass now; negative while transfers are ahead of the limit
+    tokens: f64,
+    /// When tokens were last added
+    refilled_at: Instant,
+}
+
+impl BandwidthLimiter {
+    /// Create a limiter; `None` or zero means unlimited
+    pub fn new(bytes_per_sec: Option<u64>) -> Self {
+        Self {
+            bytes_per_sec: AtomicU64::new(bytes_per_sec.unwrap_or(0)),
+            bucket: Mutex::new(Bucket {
+                tokens: 0.0,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Current limit in bytes/sec, or `None` if unlimited
+    pub fn limit(&self) -> Option<u64> {
+        match self.bytes_per_sec.load(Ordering::Relaxed) {
+            0 => None,
+            limit => Some(limit),
+        }
+    }
+
+    /// Change the limit; `None` or zero means unlimited
+    pub fn set_limit(&self, bytes_per_sec: Option<u64>) {
+        self.bytes_per_sec
+            .store(bytes_per_sec.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    /// Wait until `bytes` may pass without exceeding the limit
+    pub async fn consume(&self, bytes: usize) {
+        let delay = self.reserve(bytes as u64, Instant::now());
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Take `bytes` from the bucket, returning how long to wait before using them
+    fn reserve(&self, bytes: u64, now: Instant) -> Duration {
+        let Some(limit) = self.limit() else {
+            return Duration::ZERO;
+        };
+        let rate = limit as f64;
+
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        let elapsed = now.saturating_duration_since(bucket.refilled_at);
+        bucket.tokens =
+            (bucket.tokens + elapsed.as_secs_f64() * rate).min(rate * MAX_BURST.as_secs_f64());
+        bucket.refilled_at = now;
+        bucket.tokens -= bytes as f64;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_never_waits() {
+        let limiter = BandwidthLimiter::new(None);
+        assert_eq!(limiter.limit(), None);
+        assert_eq!(limiter.reserve(u64::MAX, Instant::now()), Duration::ZERO);
+
+        limiter.set_limit(Some(0));
+        assert_eq!(limiter.limit(), None);
+    }
+
+    #[test]
+    fn test_reserve_paces_to_limit() {
+        let limiter = BandwidthLimiter::new(Some(1000));
+        let start = Instant::now();
+        {
+            let mut bucket = limiter.bucket.lock().unwrap();
+            bucket.refilled_at = start;
+        }
+
+        assert_eq!(limiter.reserve(500, start), Duration::from_millis(500));
+        assert_eq!(limiter.reserve(500, start), Duration::from_millis(1000));
+
+        // Debt is paid off over time and the burst is capped at 100ms worth
+        let later = start + Duration::from_secs(3);
+        assert_eq!(limiter.reserve(100, later), Duration::ZERO);
+        assert_eq!(limiter.reserve(100, later), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_set_limit_applies_immediately() {
+        let limiter = BandwidthLimiter::new(Some(1000));
+        let now = Instant::now();
+        limiter.set_limit(None);
+        assert_eq!(limiter.reserve(1_000_000, now), Duration::ZERO);
+
+        limiter.set_limit(Some(2000));
+        assert_eq!(limiter.limit(), Some(2000));
+    }
+}
@@ -0,0 +1,270 @@
+//! Content-defined chunking for delta sync of large files
+//!
+//! Large files are split into chunks whose boundaries come from a rolling
+//! (gear) hash over the content rather than fixed offsets, so editing a few
+//! bytes changes only the chunk around the edit; the chunks before and
+//! after keep their hashes. Each chunk is stored as its own blob and the
+//! chunk list as a `ChunkManifest` blob, referenced from
+//! `FileMetadata::manifest_hash`. Entries without a manifest are whole
+//! blobs, as before.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Files at least this large are uploaded as chunks (8MB)
+pub const DELTA_MIN_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Largest manifest read back from the blob store (a 1GB file has ~1000 chunks)
+pub const MAX_MANIFEST_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Per-byte values mixed into the rolling hash
+const GEAR: [u64; 256] = gear_table();
+
+/// Fixed pseudo-random table (splitmix64), so every peer cuts the same way
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+}
+
+/// Chunk size bounds
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkParams {
+    /// No cut before this many bytes
+    pub min_size: usize,
+    /// A cut is made where the low `avg_bits` bits of the hash are zero,
+    /// giving chunks of about `2^avg_bits` bytes
+    pub avg_bits: u32,
+    /// Always cut at this many bytes
+    pub max_size: usize,
+}
+
+impl Default for ChunkParams {
+    /// 256KB to 4MB chunks, about 1MB on average
+    fn default() -> Self {
+        Self {
+            min_size: 256 * 1024,
+            avg_bits: 20,
+            max_size: 4 * 1024 * 1024,
+        }
+    }
+}
+
+/// Finds chunk boundaries in a stream of bytes
+///
+/// Feed the content in order with `next_cut`; state carries over between
+/// calls, so the buffer size doesn't affect where cuts fall.
+#[derive(Clone, Debug)]
+pub struct Chunker {
+    params: ChunkParams,
+    mask: u64,
+    hash: u64,
+    len: usize,
+}
+
+impl Chunker {
+    pub fn new(params: ChunkParams) -> Self {
+        Self {
+            params,
+            mask: (1u64 << params.avg_bits) - 1,
+            hash: 0,
+            len: 0,
+        }
+    }
+
+    /// Offset just past the next chunk boundary in `data`, if there is one
+    ///
+    /// The bytes up to the returned offset complete the current chunk; call
+    /// again with the rest of `data` to find the next boundary.
+    pub fn next_cut(&mut self, data: &[u8]) -> Option<usize> {
+        for (i, &byte) in data.iter().enumerate() {
+            self.hash = (self.hash << 1).wrapping_add(GEAR[byte as usize]);
+            self.len += 1;
+            let at_boundary = self.len >= self.params.min_size && self.hash & self.mask == 0;
+            if at_boundary || self.len >= self.params.max_size {
+                self.hash = 0;
+                self.len = 0;
+                return Some(i + 1);
+            }
+        }
+        None
+    }
+}
+
+/// Split in-memory content into chunk ranges
+pub fn split(data: &[u8], params: ChunkParams) -> Vec<Range<usize>> {
+    let mut chunker = Chunker::new(params);
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while let Some(cut) = chunker.next_cut(&data[start..]) {
+        ranges.push(start..start + cut);
+        start += cut;
+    }
+    if start < data.len() {
+        ranges.push(start..data.len());
+    }
+    ranges
+}
+
+/// One chunk of a file, in file order
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkRef {
+    /// BLAKE3 hash of the chunk (hex), also its blob hash
+    pub hash: String,
+    pub size: u64,
+}
+
+/// The chunks a file is made of
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    /// BLAKE3 hash of the whole file (hex), as in `FileMetadata::content_hash`
+    pub hash: String,
+    /// Total file size in bytes
+    pub size: u64,
+    /// Chunks in file order; empty means the file is stored as one blob
+    #[serde(default)]
+    pub chunks: Vec<ChunkRef>,
+}
+
+impl ChunkManifest {
+    /// Build the manifest of in-memory content
+    pub fn from_bytes(data: &[u8], params: ChunkParams) -> Self {
+        Self {
+            hash: blake3::hash(data).to_hex().to_string(),
+            size: data.len() as u64,
+            chunks: split(data, params)
+                .into_iter()
+                .map(|range| ChunkRef {
+                    hash: blake3::hash(&data[range.clone()]).to_hex().to_string(),
+                    size: range.len() as u64,
+                })
+                .collect(),
+        }
+    }
+
+    /// True for a whole-blob entry (no chunks listed)
+    pub fn is_single_blob(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Indexes of chunks whose content isn't in `previous`
+    pub fn changed_chunks(&self, previous: &ChunkManifest) -> Vec<usize> {
+        let known: HashSet<&str> = previous.chunks.iter().map(|c| c.hash.as_str()).collect();
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| !known.contains(chunk.hash.as_str()))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Chunk hashes without duplicates, in first-seen order
+    pub fn unique_chunks(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.chunks
+            .iter()
+            .map(|chunk| chunk.hash.as_str())
+            .filter(|hash| seen.insert(*hash))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_PARAMS: ChunkParams = ChunkParams {
+        min_size: 2 * 1024,
+        avg_bits: 13,
+        max_size: 64 * 1024,
+    };
+
+    fn sample(len: usize) -> Vec<u8> {
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_covers_content_within_bounds() {
+        let data = sample(1024 * 1024);
+        let ranges = split(&data, TEST_PARAMS);
+        assert!(ranges.len() > 16);
+        assert_eq!(ranges[0].start, 0);
+        assert_eq!(ranges.last().unwrap().end, data.len());
+        for pair in ranges.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        for range in &ranges[..ranges.len() - 1] {
+            assert!(range.len() >= TEST_PARAMS.min_size);
+            assert!(range.len() <= TEST_PARAMS.max_size);
+        }
+    }
+
+    #[test]
+    fn test_chunker_ignores_buffer_size() {
+        let data = sample(256 * 1024);
+        let expected = split(&data, TEST_PARAMS);
+
+        let mut chunker = Chunker::new(TEST_PARAMS);
+        let mut cuts = Vec::new();
+        let mut offset = 0;
+        for piece in data.chunks(1000) {
+            let mut rest = piece;
+            while let Some(cut) = chunker.next_cut(rest) {
+                cuts.push(offset + cut);
+                offset += cut;
+                rest = &rest[cut..];
+            }
+            offset += rest.len();
+        }
+        let expected_cuts: Vec<usize> = expected.iter().map(|r| r.end).collect();
+        assert_eq!(cuts, expected_cuts[..cuts.len()]);
+    }
+
+    #[test]
+    fn test_one_byte_edit_changes_one_chunk() {
+        let mut data = sample(1024 * 1024);
+        let before = ChunkManifest::from_bytes(&data, TEST_PARAMS);
+
+        // Edit a byte in the middle of a chunk well inside the file
+        let target = split(&data, TEST_PARAMS)[10].clone();
+        data[target.start + target.len() / 2] ^= 0xFF;
+        let after = ChunkManifest::from_bytes(&data, TEST_PARAMS);
+
+        assert_ne!(before.hash, after.hash);
+        assert_eq!(before.chunks.len(), after.chunks.len());
+        assert_eq!(after.changed_chunks(&before), vec![10]);
+    }
+
+    #[test]
+    fn test_manifest_roundtrip_and_single_blob() {
+        let manifest = ChunkManifest::from_bytes(&sample(100 * 1024), TEST_PARAMS);
+        let json = serde_json::to_vec(&manifest).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<ChunkManifest>(&json).unwrap(),
+            manifest
+        );
+        assert!(!manifest.is_single_blob());
+
+        let whole: ChunkManifest = serde_json::from_str(r#"{"hash":"ab","size":3}"#).unwrap();
+        assert!(whole.is_single_blob());
+        assert!(whole.unique_chunks().is_empty());
+    }
+}
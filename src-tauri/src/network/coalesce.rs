@@ -0,0 +1,351 @@
+//! Directory summaries for bursts of local file changes
+//!
+//! A bulk rename or an unpacked archive produces one watcher event per file,
+//! and each of them would otherwise be announced over gossip and shown in
+//! the activity feed. Once more than `threshold` distinct files under the
+//! same directory change within `window_ms`, further changes there are
+//! folded into a single `FilesChangedSummary` ("42 files changed in
+//! /photos") sent when the window closes. The first `threshold` changes
+//! still go out on their own, so a small edit is never delayed.
+//!
+//! Only announcements are folded: metadata is still written per file, and
+//! peers pick folded files up from synced metadata like any missed event.
+
+use crate::core::{DriveEvent, DriveId};
+use crate::crypto::NodeId;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Setting holding the summary configuration
+pub const CHANGE_SUMMARY_SETTING: &str = "change_summary_config";
+
+/// Most paths listed in one summary
+pub const MAX_SUMMARY_PATHS: usize = 200;
+
+/// Bounds for the number of files announced on their own
+const MIN_SUMMARY_THRESHOLD: u32 = 2;
+const MAX_SUMMARY_THRESHOLD: u32 = 10_000;
+
+/// Bounds for the window a burst is collected over
+const MIN_SUMMARY_WINDOW_MS: u64 = 250;
+const MAX_SUMMARY_WINDOW_MS: u64 = 60_000;
+
+/// When bursts of changes in one directory are summarized
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChangeSummaryConfig {
+    /// Fold bursts into summaries; off announces every file
+    pub enabled: bool,
+    /// Files per directory and window announced on their own
+    pub threshold: u32,
+    /// Milliseconds a burst is collected before its summary is sent
+    pub window_ms: u64,
+}
+
+impl Default for ChangeSummaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 20,
+            window_ms: 2_000,
+        }
+    }
+}
+
+impl ChangeSummaryConfig {
+    /// Clamp the threshold and window to supported bounds
+    pub fn normalized(mut self) -> Self {
+        self.threshold = self
+            .threshold
+            .clamp(MIN_SUMMARY_THRESHOLD, MAX_SUMMARY_THRESHOLD);
+        self.window_ms = self
+            .window_ms
+            .clamp(MIN_SUMMARY_WINDOW_MS, MAX_SUMMARY_WINDOW_MS);
+        self
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_millis(self.window_ms)
+    }
+}
+
+/// Changes seen in one directory during the current window
+struct Burst {
+    started: Instant,
+    /// Distinct files changed, in the order first seen
+    files: HashSet<PathBuf>,
+    /// First `MAX_SUMMARY_PATHS` of `files`
+    paths: Vec<PathBuf>,
+    deleted: u32,
+    /// True once a change was held back for the summary
+    folded: bool,
+    changed_by: NodeId,
+}
+
+/// Decides which local changes are announced and which are summarized
+pub struct DirectoryCoalescer {
+    config: ChangeSummaryConfig,
+    bursts: HashMap<(DriveId, PathBuf), Burst>,
+}
+
+impl DirectoryCoalescer {
+    pub fn new(config: ChangeSummaryConfig) -> Self {
+        Self {
+            config: config.normalized(),
+            bursts: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> ChangeSummaryConfig {
+        self.config
+    }
+
+    /// Replace the configuration; open bursts close at the next `take_due`
+    /// if summaries were turned off
+    pub fn set_config(&mut self, config: ChangeSummaryConfig) -> ChangeSummaryConfig {
+        self.config = config.normalized();
+        self.config
+    }
+
+    /// Record a local change, returning true if it should be announced now
+    ///
+    /// Only file changes and deletions are summarized; every other event is
+    /// passed through.
+    pub fn offer(&mut self, drive_id: &DriveId, event: &DriveEvent, now: Instant) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let (path, changed_by, deleted) = match event {
+            DriveEvent::FileChanged {
+                path, modified_by, ..
+            } => (path, *modified_by, false),
+            DriveEvent::FileDeleted {
+                path, deleted_by, ..
+            } => (path, *deleted_by, true),
+            _ => return true,
+        };
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let window = self.config.window();
+
+        let burst = self
+            .bursts
+            .entry((*drive_id, dir))
+            .or_insert_with(|| Burst {
+                started: now,
+                files: HashSet::new(),
+                paths: Vec::new(),
+                deleted: 0,
+                folded: false,
+                changed_by,
+            });
+        // A quiet burst that ran out without a summary starts over
+        if !burst.folded && now.duration_since(burst.started) >= window {
+            burst.started = now;
+            burst.files.clear();
+            burst.paths.clear();
+            burst.deleted = 0;
+        }
+
+        if burst.files.insert(path.clone()) && burst.paths.len() < MAX_SUMMARY_PATHS {
+            burst.paths.push(path.clone());
+        }
+        if deleted {
+            burst.deleted += 1;
+        }
+        burst.changed_by = changed_by;
+        if burst.files.len() as u32 <= self.config.threshold && !burst.folded {
+            return true;
+        }
+        burst.folded = true;
+        false
+    }
+
+    /// Close bursts whose window ran out, returning summaries to announce
+    pub fn take_due(&mut self, now: Instant) -> Vec<(DriveId, DriveEvent)> {
+        let window = self.config.window();
+        let enabled = self.config.enabled;
+        self.close_where(|_, burst| !enabled || now.duration_since(burst.started) >= window)
+    }
+
+    /// Close a drive's bursts now, whatever their window, returning summaries
+    pub fn take_drive(&mut self, drive_id: &DriveId) -> Vec<(DriveId, DriveEvent)> {
+        self.close_where(|id, _| id == drive_id)
+    }
+
+    fn close_where(
+        &mut self,
+        mut close: impl FnMut(&DriveId, &Burst) -> bool,
+    ) -> Vec<(DriveId, DriveEvent)> {
+        let mut due = Vec::new();
+        self.bursts.retain(|(drive_id, dir), burst| {
+            if !close(drive_id, burst) {
+                return true;
+            }
+            if burst.folded {
+                due.push((
+                    *drive_id,
+                    DriveEvent::FilesChangedSummary {
+                        dir: dir.clone(),
+                        count: burst.files.len() as u32,
+                        deleted: burst.deleted,
+                        paths: std::mem::take(&mut burst.paths),
+                        changed_by: burst.changed_by,
+                        timestamp: Utc::now(),
+                    },
+                ));
+            }
+            false
+        });
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Identity;
+
+    fn changed(path: &str, by: NodeId) -> DriveEvent {
+        DriveEvent::FileChanged {
+            path: path.into(),
+            hash: "h".to_string(),
+            size: 1,
+            modified_by: by,
+            timestamp: Utc::now(),
+            created: false,
+            content_type: None,
+        }
+    }
+
+    #[test]
+    fn test_config_normalized() {
+        let config = ChangeSummaryConfig {
+            enabled: true,
+            threshold: 0,
+            window_ms: u64::MAX,
+        }
+        .normalized();
+        assert_eq!(config.threshold, MIN_SUMMARY_THRESHOLD);
+        assert_eq!(config.window_ms, MAX_SUMMARY_WINDOW_MS);
+    }
+
+    #[test]
+    fn test_burst_folds_past_threshold() {
+        let me = Identity::generate().node_id();
+        let drive = DriveId([1u8; 32]);
+        let mut coalescer = DirectoryCoalescer::new(ChangeSummaryConfig {
+            enabled: true,
+            threshold: 2,
+            window_ms: 1_000,
+        });
+        let start = Instant::now();
+
+        assert!(coalescer.offer(&drive, &changed("photos/a.jpg", me), start));
+        assert!(coalescer.offer(&drive, &changed("photos/b.jpg", me), start));
+        // The same file again doesn't count twice
+        assert!(coalescer.offer(&drive, &changed("photos/b.jpg", me), start));
+        assert!(!coalescer.offer(&drive, &changed("photos/c.jpg", me), start));
+        let deleted = DriveEvent::FileDeleted {
+            path: "photos/d.jpg".into(),
+            deleted_by: me,
+            timestamp: Utc::now(),
+        };
+        assert!(!coalescer.offer(&drive, &deleted, start));
+
+        // Other directories and other events are unaffected
+        assert!(coalescer.offer(&drive, &changed("docs/a.txt", me), start));
+        let editing = DriveEvent::FileEditStarted {
+            path: "photos/a.jpg".into(),
+            editor: me,
+        };
+        assert!(coalescer.offer(&drive, &editing, start));
+
+        assert!(coalescer.take_due(start).is_empty());
+        let due = coalescer.take_due(start + Duration::from_millis(1_000));
+        assert_eq!(due.len(), 1);
+        let (id, summary) = &due[0];
+        assert_eq!(*id, drive);
+        let DriveEvent::FilesChangedSummary {
+            dir,
+            count,
+            deleted,
+            paths,
+            changed_by,
+            ..
+        } = summary
+        else {
+            panic!("expected a summary, got {:?}", summary);
+        };
+        assert_eq!(dir, &PathBuf::from("photos"));
+        assert_eq!(*count, 4);
+        assert_eq!(*deleted, 1);
+        assert_eq!(paths.len(), 4);
+        assert_eq!(*changed_by, me);
+
+        // A new window announces files on their own again
+        let later = start + Duration::from_millis(1_500);
+        assert!(coalescer.offer(&drive, &changed("photos/e.jpg", me), later));
+    }
+
+    #[test]
+    fn test_quiet_window_restarts_and_disable_flushes() {
+        let me = Identity::generate().node_id();
+        let drive = DriveId([1u8; 32]);
+        let mut coalescer = DirectoryCoalescer::new(ChangeSummaryConfig {
+            enabled: true,
+            threshold: 2,
+            window_ms: 1_000,
+        });
+        let start = Instant::now();
+
+        assert!(coalescer.offer(&drive, &changed("a.txt", me), start));
+        assert!(coalescer.offer(&drive, &changed("b.txt", me), start));
+        // Without take_due, an expired window still starts over
+        let later = start + Duration::from_millis(1_200);
+        assert!(coalescer.offer(&drive, &changed("c.txt", me), later));
+        assert!(coalescer.offer(&drive, &changed("d.txt", me), later));
+        assert!(!coalescer.offer(&drive, &changed("e.txt", me), later));
+
+        // Turning summaries off closes open bursts right away
+        coalescer.set_config(ChangeSummaryConfig::default());
+        let due = coalescer.take_due(later);
+        assert_eq!(due.len(), 1);
+        assert!(matches!(
+            &due[0].1,
+            DriveEvent::FilesChangedSummary { dir, count: 3, .. } if dir.as_os_str().is_empty()
+        ));
+        assert!(coalescer.offer(&drive, &changed("f.txt", me), later));
+    }
+
+    #[test]
+    fn test_take_drive_closes_only_that_drive() {
+        let me = Identity::generate().node_id();
+        let drive = DriveId([1u8; 32]);
+        let other = DriveId([2u8; 32]);
+        let mut coalescer = DirectoryCoalescer::new(ChangeSummaryConfig {
+            enabled: true,
+            threshold: 2,
+            window_ms: 1_000,
+        });
+        let start = Instant::now();
+
+        for id in [&drive, &other] {
+            assert!(coalescer.offer(id, &changed("a.txt", me), start));
+            assert!(coalescer.offer(id, &changed("b.txt", me), start));
+            assert!(!coalescer.offer(id, &changed("c.txt", me), start));
+        }
+
+        // The window hasn't closed, but the drive's summary comes out anyway
+        let due = coalescer.take_drive(&drive);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, drive);
+        assert!(coalescer.take_drive(&drive).is_empty());
+
+        let due = coalescer.take_due(start + Duration::from_millis(1_000));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, other);
+    }
+}
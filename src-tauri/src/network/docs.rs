@@ -8,8 +8,10 @@
 
 #![allow(dead_code)]
 
-use crate::core::DriveId;
+use crate::core::content_type::{self, Compressibility};
+use crate::core::{protocol_incompatibility, DriveId, SyncFilters, PROTOCOL_VERSION};
 use crate::crypto::Permission;
+use crate::network::history::{FileHistory, FileVersion, HistoryRetention};
 use crate::storage::Database;
 use anyhow::{anyhow, Result};
 use futures_lite::StreamExt;
@@ -25,17 +27,24 @@ use iroh_gossip::net::Gossip;
 use iroh_io::AsyncSliceReader;
 use quic_rpc::transport::flume::FlumeConnector;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 const DOC_KEY_PREFIX: &str = "file:";
+
+/// How long a lazily fetched directory listing is served from cache
+const DIRECTORY_LISTING_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Domain separation for Merkle leaves and interior nodes
+const MERKLE_LEAF_PREFIX: u8 = 0x00;
+const MERKLE_NODE_PREFIX: u8 = 0x01;
 type MemDoc = Doc<FlumeConnector<DocsResponse, DocsRequest>>;
 
 /// Metadata schema stored in iroh-docs
 /// Key format: "file:{relative_path}"
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct FileMetadata {
     /// File name
     pub name: String,
@@ -49,8 +58,22 @@ pub struct FileMetadata {
     pub modified_at: String,
     /// BLAKE3 content hash (hex string, for file transfer)
     pub content_hash: Option<String>,
-    /// Monotonic version number for conflict resolution
+    /// Monotonic version number, matching the path's version history
     pub version: u64,
+    /// Protocol version of the writer (0 if it predates versioning)
+    #[serde(default)]
+    pub protocol_version: u32,
+    /// Detected content type, e.g. `image/png` (None if unknown)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Blob hash of the file's `ChunkManifest` (hex) if it was uploaded in
+    /// chunks; None means the content is one blob under `content_hash`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_hash: Option<String>,
+    /// Content hash of the version this one replaced, kept so the common
+    /// ancestor of a conflict can be read back from the blob store
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_hash: Option<String>,
 }
 
 impl FileMetadata {
@@ -64,6 +87,10 @@ impl FileMetadata {
             modified_at: modified_at.to_string(),
             content_hash: None,
             version: 1,
+            protocol_version: PROTOCOL_VERSION,
+            content_type: None,
+            manifest_hash: None,
+            previous_hash: None,
         }
     }
 
@@ -84,15 +111,340 @@ impl FileMetadata {
             modified_at: modified_at.to_string(),
             content_hash: Some(hash),
             version: 1,
+            protocol_version: PROTOCOL_VERSION,
+            content_type: None,
+            manifest_hash: None,
+            previous_hash: None,
         }
     }
 
+    /// Whether the content is worth compressing (unknown types are not)
+    pub fn compressibility(&self) -> Compressibility {
+        self.content_type
+            .as_deref()
+            .map_or(Compressibility::Unknown, content_type::compressibility)
+    }
+
     /// Generate the iroh-docs key for this entry
     pub fn doc_key(&self) -> Vec<u8> {
         format!("{}{}", DOC_KEY_PREFIX, self.path).into_bytes()
     }
 }
 
+/// Merkle root over a drive's file set
+#[derive(Clone, Debug, Serialize)]
+pub struct DriveMerkleRoot {
+    /// Hex-encoded BLAKE3 root
+    pub root: String,
+    /// Number of files covered (directories are excluded)
+    pub file_count: usize,
+    /// ISO 8601 time the root was computed
+    pub computed_at: String,
+}
+
+/// A docs namespace that no existing drive uses
+///
+/// Left behind when a drive is deleted without its doc, or when a doc was
+/// created or joined but the drive never finished setting up.
+#[derive(Clone, Debug, Serialize)]
+pub struct OrphanNamespace {
+    /// Hex-encoded namespace ID
+    pub namespace_id: String,
+    /// Hex-encoded ID of the deleted drive still mapped to this namespace
+    pub drive_id: Option<String>,
+    /// Number of entries in the doc (latest per key)
+    pub entry_count: usize,
+}
+
+/// What is wrong with a drive's namespace mapping, if anything
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocMappingStatus {
+    /// Mapped to a namespace that opens
+    Ok,
+    /// No namespace is stored for the drive
+    Missing,
+    /// The stored value is not a namespace ID
+    Corrupt,
+    /// The mapped namespace isn't in the docs store or fails to open
+    Unopenable,
+}
+
+/// Result of checking one drive's namespace mapping
+#[derive(Clone, Debug, Serialize)]
+pub struct DocMappingCheck {
+    /// Hex-encoded drive ID
+    pub drive_id: String,
+    /// Hex-encoded namespace ID, if one is stored and well-formed
+    pub namespace_id: Option<String>,
+    pub status: DocMappingStatus,
+    /// Why the namespace failed to open
+    pub error: Option<String>,
+}
+
+/// Parse a stored namespace mapping, or say why it is unusable
+fn parse_mapping(stored: Option<&[u8]>) -> std::result::Result<NamespaceId, DocMappingStatus> {
+    let bytes = stored.ok_or(DocMappingStatus::Missing)?;
+    let bytes: [u8; 32] = bytes.try_into().map_err(|_| DocMappingStatus::Corrupt)?;
+    Ok(NamespaceId::from(&bytes))
+}
+
+/// A directory's metadata, fetched on demand
+#[derive(Clone, Debug, Serialize)]
+pub struct DirectoryListing {
+    /// Direct children of the directory
+    pub entries: Vec<FileMetadata>,
+    /// True if the listing may be incomplete: no peer was connected to
+    /// sync with, or some entries' metadata hasn't arrived from peers yet
+    pub partial: bool,
+    /// ISO 8601 time the listing was fetched
+    pub fetched_at: String,
+    #[serde(skip)]
+    fetched: Option<std::time::Instant>,
+}
+
+/// Normalize a directory path to the form metadata paths use ("" for root)
+fn normalize_dir(dir_path: &str) -> String {
+    dir_path.trim_matches('/').to_string()
+}
+
+/// Directory containing a metadata path ("" for the root)
+fn parent_dir(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    path.rsplit_once('/')
+        .map(|(parent, _)| parent.to_string())
+        .unwrap_or_default()
+}
+
+/// Check if `path` is a direct child of the normalized directory `dir`
+fn is_direct_child(dir: &str, path: &str) -> bool {
+    let path = path.trim_start_matches('/');
+    if dir.is_empty() {
+        return !path.is_empty() && !path.contains('/');
+    }
+    match path.strip_prefix(dir).and_then(|rest| rest.strip_prefix('/')) {
+        Some(name) => !name.is_empty() && !name.contains('/'),
+        None => false,
+    }
+}
+
+/// Pick out namespaces that aren't mapped to any of `drives`
+///
+/// Returns each orphan with the (deleted) drive it is still mapped to, if any.
+pub fn find_orphans(
+    all: &[NamespaceId],
+    mapping: &HashMap<DriveId, NamespaceId>,
+    drives: &HashSet<DriveId>,
+) -> Vec<(NamespaceId, Option<DriveId>)> {
+    let owners: HashMap<NamespaceId, DriveId> =
+        mapping.iter().map(|(drive, ns)| (*ns, *drive)).collect();
+    all.iter()
+        .filter_map(|ns| match owners.get(ns) {
+            Some(drive) if drives.contains(drive) => None,
+            owner => Some((*ns, owner.copied())),
+        })
+        .collect()
+}
+
+/// A file or directory in a drive's file tree
+#[derive(Clone, Debug, Serialize)]
+pub struct FileTreeNode {
+    pub name: String,
+    /// Path relative to the drive root, with `/` separators
+    pub path: String,
+    pub is_dir: bool,
+    /// File size, or the total of the files listed under a directory
+    pub size: u64,
+    /// BLAKE3 content hash (files only)
+    pub content_hash: Option<String>,
+    /// ISO 8601 modified time; None for directories without metadata
+    pub modified_at: Option<String>,
+    /// Entries of a directory, sorted by name; None for files
+    pub children: Option<Vec<FileTreeNode>>,
+    /// True if entries below this directory were cut off by the depth limit
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// A drive's metadata as a nested tree
+#[derive(Clone, Debug, Serialize)]
+pub struct FileTree {
+    pub root: FileTreeNode,
+    pub file_count: usize,
+    pub dir_count: usize,
+    /// Depth limit that was applied (levels below the root)
+    pub max_depth: usize,
+    /// True if entries were dropped by the depth or entry limit
+    pub truncated: bool,
+}
+
+/// Tree node under construction, children keyed by name
+#[derive(Default)]
+struct TreeBuilder {
+    meta: Option<FileMetadata>,
+    is_dir: bool,
+    children: std::collections::BTreeMap<String, TreeBuilder>,
+    truncated: bool,
+}
+
+impl TreeBuilder {
+    fn finish(self, name: String, path: String) -> FileTreeNode {
+        let (size, children) = if self.is_dir {
+            let children: Vec<FileTreeNode> = self
+                .children
+                .into_iter()
+                .map(|(child, node)| {
+                    let child_path = if path.is_empty() {
+                        child.clone()
+                    } else {
+                        format!("{}/{}", path, child)
+                    };
+                    node.finish(child, child_path)
+                })
+                .collect();
+            (children.iter().map(|c| c.size).sum(), Some(children))
+        } else {
+            (self.meta.as_ref().map_or(0, |m| m.size), None)
+        };
+        FileTreeNode {
+            name,
+            path,
+            is_dir: self.is_dir,
+            size,
+            content_hash: self.meta.as_ref().and_then(|m| m.content_hash.clone()),
+            modified_at: self.meta.map(|m| m.modified_at),
+            children,
+            truncated: self.truncated,
+        }
+    }
+}
+
+/// Arrange metadata entries under `root` into a tree
+///
+/// Directories without their own entry are filled in from file paths.
+/// Entries deeper than `max_depth` levels below `root` are left out (their
+/// deepest listed ancestor is marked truncated), and at most `max_entries`
+/// nodes are added.
+pub fn build_file_tree(
+    entries: &[FileMetadata],
+    root: &str,
+    max_depth: usize,
+    max_entries: usize,
+) -> FileTree {
+    let root = normalize_dir(root);
+    let mut relative: Vec<(&str, &FileMetadata)> = entries
+        .iter()
+        .filter_map(|meta| {
+            let path = meta.path.trim_start_matches('/');
+            let rest = if root.is_empty() {
+                path
+            } else {
+                path.strip_prefix(root.as_str())?.strip_prefix('/')?
+            };
+            (!rest.is_empty()).then_some((rest, meta))
+        })
+        .collect();
+    relative.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut tree = TreeBuilder {
+        is_dir: true,
+        ..Default::default()
+    };
+    let (mut file_count, mut dir_count, mut truncated) = (0, 0, false);
+    for (path, meta) in relative {
+        let parts: Vec<&str> = path.split('/').collect();
+        let mut node = &mut tree;
+        for (depth, part) in parts.iter().enumerate() {
+            if depth == max_depth {
+                node.truncated = true;
+                truncated = true;
+                break;
+            }
+            let last = depth + 1 == parts.len();
+            if !node.children.contains_key(*part) {
+                if file_count + dir_count >= max_entries {
+                    truncated = true;
+                    break;
+                }
+                if last && !meta.is_dir {
+                    file_count += 1;
+                } else {
+                    dir_count += 1;
+                }
+            }
+            node = node.children.entry(part.to_string()).or_default();
+            if last {
+                node.is_dir = meta.is_dir;
+                node.meta = Some(meta.clone());
+            } else {
+                node.is_dir = true;
+            }
+        }
+    }
+
+    let name = root.rsplit('/').next().unwrap_or_default().to_string();
+    FileTree {
+        root: tree.finish(name, root),
+        file_count,
+        dir_count,
+        max_depth,
+        truncated,
+    }
+}
+
+/// Compute a deterministic Merkle root over `(path, content_hash)` pairs
+///
+/// So that independent implementations agree:
+/// 1. Paths use `/` separators with no leading `/`; content hashes are
+///    lowercase hex, or empty if unknown.
+/// 2. Pairs are sorted by path, comparing UTF-8 bytes.
+/// 3. Each leaf is `BLAKE3(0x00 || path || 0x00 || content_hash)`.
+/// 4. Each level pairs adjacent nodes as `BLAKE3(0x01 || left || right)`;
+///    an odd node at the end of a level moves up unchanged.
+/// 5. The root is the last remaining node. An empty set has the root
+///    `BLAKE3(0x01)`.
+pub fn merkle_root(mut entries: Vec<(String, String)>) -> [u8; 32] {
+    for (path, hash) in entries.iter_mut() {
+        *path = path.replace('\\', "/").trim_start_matches('/').to_string();
+        *hash = hash.to_ascii_lowercase();
+    }
+    entries.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+
+    let mut level: Vec<[u8; 32]> = entries
+        .iter()
+        .map(|(path, hash)| {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(&[MERKLE_LEAF_PREFIX]);
+            hasher.update(path.as_bytes());
+            hasher.update(&[0x00]);
+            hasher.update(hash.as_bytes());
+            *hasher.finalize().as_bytes()
+        })
+        .collect();
+
+    if level.is_empty() {
+        return *blake3::hash(&[MERKLE_NODE_PREFIX]).as_bytes();
+    }
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = blake3::Hasher::new();
+                    hasher.update(&[MERKLE_NODE_PREFIX]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    *hasher.finalize().as_bytes()
+                }
+                [single] => *single,
+                _ => unreachable!("chunks(2) yields one or two nodes"),
+            })
+            .collect();
+    }
+    level[0]
+}
+
 /// Manages document metadata for drives
 ///
 /// Stores metadata in database for persistence and in memory for fast access.
@@ -114,6 +466,19 @@ pub struct DocsManager {
     docs_by_drive: RwLock<HashMap<DriveId, MemDoc>>,
     /// In-memory metadata cache per drive (for fast lookups)
     metadata_cache: RwLock<HashMap<DriveId, HashMap<String, FileMetadata>>>,
+    /// Merkle root per drive, dropped whenever its metadata changes
+    merkle_roots: RwLock<HashMap<DriveId, DriveMerkleRoot>>,
+    /// Lazily fetched listings per drive and directory, dropped when a
+    /// child changes
+    dir_listings: RwLock<HashMap<DriveId, HashMap<String, DirectoryListing>>>,
+    /// Peers per drive that docs sync must not connect to directly
+    no_direct_sync: RwLock<HashMap<DriveId, HashSet<PeerIdBytes>>>,
+    /// Peers from the ticket each joined drive was imported with
+    bootstrap_peers: RwLock<HashMap<DriveId, Vec<iroh::NodeAddr>>>,
+    /// Selective sync filters per drive (drives without filters sync everything)
+    sync_filters: RwLock<HashMap<DriveId, SyncFilters>>,
+    /// How many past versions of each file are kept
+    history_retention: HistoryRetention,
     /// Data directory for persistent storage
     #[allow(dead_code)]
     data_dir: PathBuf,
@@ -147,6 +512,22 @@ impl DocsManager {
             namespaces.insert(DriveId(drive_id), NamespaceId::from(&namespace));
         }
 
+        let mut sync_filters = HashMap::new();
+        for (drive_id, data) in db.list_sync_filters()? {
+            let Ok(drive_id) = DriveId::from_hex(&drive_id) else {
+                tracing::warn!("Skipping sync filters for invalid drive ID {}", drive_id);
+                continue;
+            };
+            match serde_json::from_slice::<SyncFilters>(&data) {
+                Ok(filters) => {
+                    sync_filters.insert(drive_id, filters);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to deserialize sync filters: {}", e);
+                }
+            }
+        }
+
         tracing::info!("DocsManager initialized with author: {}", author_id);
 
         Ok(Self {
@@ -158,6 +539,12 @@ impl DocsManager {
             namespaces: RwLock::new(namespaces),
             docs_by_drive: RwLock::new(HashMap::new()),
             metadata_cache: RwLock::new(HashMap::new()),
+            merkle_roots: RwLock::new(HashMap::new()),
+            dir_listings: RwLock::new(HashMap::new()),
+            no_direct_sync: RwLock::new(HashMap::new()),
+            bootstrap_peers: RwLock::new(HashMap::new()),
+            sync_filters: RwLock::new(sync_filters),
+            history_retention: HistoryRetention::default(),
             data_dir: data_dir.to_path_buf(),
         })
     }
@@ -169,6 +556,7 @@ impl DocsManager {
         let metadata_list = self.db.list_file_metadata(&drive_id_hex)?;
 
         let mut cache = self.metadata_cache.write().await;
+        self.merkle_roots.write().await.remove(drive_id);
         let drive_cache = cache.entry(*drive_id).or_insert_with(HashMap::new);
 
         for (path, data) in metadata_list {
@@ -187,6 +575,15 @@ impl DocsManager {
             drive_cache.len(),
             drive_id
         );
+        drop(cache);
+
+        match self.prune_history(drive_id) {
+            Ok(0) => {}
+            Ok(pruned) => {
+                tracing::info!("Pruned {} old file versions for drive {}", pruned, drive_id)
+            }
+            Err(e) => tracing::warn!("Failed to prune file history for drive {}: {}", drive_id, e),
+        }
 
         Ok(())
     }
@@ -213,9 +610,27 @@ impl DocsManager {
     }
 
     /// Join an existing document via ticket (peer joining)
-    pub async fn join_doc(&self, drive_id: DriveId, ticket: DocTicket) -> Result<NamespaceId> {
+    pub async fn join_doc(&self, drive_id: DriveId, mut ticket: DocTicket) -> Result<NamespaceId> {
+        // Bootstrap only from peers we are allowed to sync with directly
+        {
+            let excluded = self.no_direct_sync.read().await;
+            if let Some(excluded) = excluded.get(&drive_id) {
+                ticket
+                    .nodes
+                    .retain(|node| !excluded.contains(node.node_id.as_bytes()));
+                if ticket.nodes.is_empty() {
+                    tracing::warn!(
+                        "All ticket peers for drive {} are excluded from direct sync",
+                        drive_id
+                    );
+                }
+            }
+        }
+
+        let nodes = ticket.nodes.clone();
         let doc = self.docs_client.import(ticket).await?;
         let namespace_id = doc.id();
+        self.bootstrap_peers.write().await.insert(drive_id, nodes);
 
         self.store_namespace_mapping(drive_id, namespace_id).await?;
         self.docs_by_drive.write().await.insert(drive_id, doc);
@@ -229,14 +644,17 @@ impl DocsManager {
     }
 
     /// Update file metadata in a drive's document (persists to DB)
+    ///
+    /// New content is added to the file's version history and `version`
+    /// is set to its number.
     pub async fn set_file_metadata(&self, drive_id: &DriveId, meta: &FileMetadata) -> Result<()> {
-        self.set_file_metadata_cached(drive_id, meta).await?;
+        let meta = self.store_file_metadata(drive_id, meta).await?;
 
         let Some(doc) = self.get_or_open_doc(drive_id).await? else {
             return Ok(());
         };
 
-        let data = serde_json::to_vec(meta)?;
+        let data = serde_json::to_vec(&meta)?;
         doc.set_bytes(self.author_id, meta.doc_key(), data).await?;
 
         tracing::debug!("Saved metadata for {} in drive {}", meta.path, drive_id);
@@ -266,18 +684,38 @@ impl DocsManager {
         drive_id: &DriveId,
         meta: &FileMetadata,
     ) -> Result<()> {
+        self.store_file_metadata(drive_id, meta).await?;
+        Ok(())
+    }
+
+    /// Record new content in the file's history, then persist and cache
+    /// the metadata; returns it as stored
+    async fn store_file_metadata(
+        &self,
+        drive_id: &DriveId,
+        meta: &FileMetadata,
+    ) -> Result<FileMetadata> {
         let drive_id_hex = hex::encode(drive_id.as_bytes());
 
+        // Held throughout so history updates for a path don't interleave
+        let mut cache = self.metadata_cache.write().await;
+
+        let mut meta = meta.clone();
+        if let Some(version) = self.record_history(&drive_id_hex, &meta)? {
+            meta.version = version;
+        }
+
         // Serialize and persist to database
-        let data = serde_json::to_vec(meta)?;
+        let data = serde_json::to_vec(&meta)?;
         self.db.save_file_metadata(&drive_id_hex, &meta.path, &data)?;
 
         // Update in-memory cache
-        let mut cache = self.metadata_cache.write().await;
+        self.merkle_roots.write().await.remove(drive_id);
+        self.invalidate_listing(drive_id, &meta.path).await;
         let drive_cache = cache.entry(*drive_id).or_insert_with(HashMap::new);
         drive_cache.insert(meta.path.clone(), meta.clone());
 
-        Ok(())
+        Ok(meta)
     }
 
     /// Delete metadata cache and DB without touching the docs replica
@@ -293,6 +731,8 @@ impl DocsManager {
 
         // Delete from in-memory cache
         let mut cache = self.metadata_cache.write().await;
+        self.merkle_roots.write().await.remove(drive_id);
+        self.invalidate_listing(drive_id, path).await;
         if let Some(drive_cache) = cache.get_mut(drive_id) {
             drive_cache.remove(path);
         }
@@ -300,6 +740,140 @@ impl DocsManager {
         Ok(())
     }
 
+    /// Check whether metadata exists for a path (cache first, then DB)
+    pub async fn has_file_metadata(&self, drive_id: &DriveId, path: &str) -> bool {
+        if let Some(drive_cache) = self.metadata_cache.read().await.get(drive_id) {
+            if drive_cache.contains_key(path) {
+                return true;
+            }
+        }
+
+        let drive_id_hex = hex::encode(drive_id.as_bytes());
+        matches!(self.db.get_file_metadata(&drive_id_hex, path), Ok(Some(_)))
+    }
+
+    /// Get the metadata for a single path, from cache or database
+    pub async fn get_file_metadata(&self, drive_id: &DriveId, path: &str) -> Option<FileMetadata> {
+        if let Some(meta) = self
+            .metadata_cache
+            .read()
+            .await
+            .get(drive_id)
+            .and_then(|drive_cache| drive_cache.get(path))
+        {
+            return Some(meta.clone());
+        }
+
+        let drive_id_hex = hex::encode(drive_id.as_bytes());
+        let data = self.db.get_file_metadata(&drive_id_hex, path).ok()??;
+        serde_json::from_slice(&data).ok()
+    }
+
+    /// Chunk manifest recorded for a path, if its content is still `content_hash`
+    ///
+    /// Lets a metadata update for unchanged content keep the manifest an
+    /// earlier chunked upload recorded.
+    pub async fn current_manifest(
+        &self,
+        drive_id: &DriveId,
+        path: &str,
+        content_hash: &str,
+    ) -> Option<String> {
+        self.get_file_metadata(drive_id, path)
+            .await
+            .filter(|meta| {
+                meta.content_hash
+                    .as_deref()
+                    .is_some_and(|hash| hash.eq_ignore_ascii_case(content_hash))
+            })
+            .and_then(|meta| meta.manifest_hash)
+    }
+
+    /// Content hash a write of `content_hash` to `path` replaces
+    ///
+    /// The recorded content hash if it differs, otherwise the version that
+    /// one replaced, so rewriting unchanged content keeps the ancestor.
+    pub async fn previous_hash(
+        &self,
+        drive_id: &DriveId,
+        path: &str,
+        content_hash: &str,
+    ) -> Option<String> {
+        let meta = self.get_file_metadata(drive_id, path).await?;
+        match meta.content_hash {
+            Some(hash) if !hash.eq_ignore_ascii_case(content_hash) => Some(hash),
+            _ => meta.previous_hash,
+        }
+    }
+
+    /// Past versions of a file, newest first
+    ///
+    /// Deleted files keep their history, so they can be restored.
+    pub fn get_file_history(&self, drive_id: &DriveId, path: &str) -> Result<Vec<FileVersion>> {
+        let drive_id_hex = hex::encode(drive_id.as_bytes());
+        Ok(self.load_history(&drive_id_hex, path)?.newest_first())
+    }
+
+    /// Look up one version of a file by number
+    pub fn get_file_version(
+        &self,
+        drive_id: &DriveId,
+        path: &str,
+        version: u64,
+    ) -> Result<Option<FileVersion>> {
+        let drive_id_hex = hex::encode(drive_id.as_bytes());
+        Ok(self
+            .load_history(&drive_id_hex, path)?
+            .get(version)
+            .cloned())
+    }
+
+    /// Apply the retention policy to every file history of a drive
+    ///
+    /// Returns how many versions were dropped. Their blobs stay in the
+    /// store until nothing else references them.
+    pub fn prune_history(&self, drive_id: &DriveId) -> Result<usize> {
+        let drive_id_hex = hex::encode(drive_id.as_bytes());
+        let now = chrono::Utc::now();
+        let mut pruned = 0;
+        for (path, data) in self.db.list_file_history(&drive_id_hex)? {
+            let Ok(mut history) = serde_json::from_slice::<FileHistory>(&data) else {
+                continue;
+            };
+            let dropped = history.prune(&self.history_retention, now).len();
+            if dropped > 0 {
+                self.db
+                    .save_file_history(&drive_id_hex, &path, &serde_json::to_vec(&history)?)?;
+                pruned += dropped;
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Add `meta`'s content to its path's history, returning its version number
+    fn record_history(&self, drive_id_hex: &str, meta: &FileMetadata) -> Result<Option<u64>> {
+        let mut history = self.load_history(drive_id_hex, &meta.path)?;
+        let before = history.clone();
+        let now = chrono::Utc::now();
+        let version = history.record(meta, now);
+        history.prune(&self.history_retention, now);
+        if history != before {
+            self.db
+                .save_file_history(drive_id_hex, &meta.path, &serde_json::to_vec(&history)?)?;
+        }
+        Ok(version)
+    }
+
+    fn load_history(&self, drive_id_hex: &str, path: &str) -> Result<FileHistory> {
+        let Some(data) = self.db.get_file_history(drive_id_hex, path)? else {
+            return Ok(FileHistory::default());
+        };
+        Ok(serde_json::from_slice(&data).unwrap_or_else(|e| {
+            tracing::warn!("Failed to deserialize file history for {}: {}", path, e);
+            FileHistory::default()
+        }))
+    }
+
     /// Get all file metadata for a drive (from cache)
     pub async fn get_all_metadata(&self, drive_id: &DriveId) -> Result<Vec<FileMetadata>> {
         if let Err(err) = self.refresh_from_doc(drive_id).await {
@@ -324,6 +898,41 @@ impl DocsManager {
         }
     }
 
+    /// Get the Merkle root over a drive's files (see [`merkle_root`])
+    ///
+    /// Cached until the drive's metadata next changes.
+    pub async fn merkle_root(&self, drive_id: &DriveId) -> Result<DriveMerkleRoot> {
+        // Pulls in remote changes (invalidating the cache) and loads the drive
+        self.get_all_metadata(drive_id).await?;
+
+        // Metadata writers hold the cache lock while invalidating, so the
+        // root stored here always matches the entries it was computed from
+        let cache = self.metadata_cache.read().await;
+        let mut roots = self.merkle_roots.write().await;
+        if let Some(root) = roots.get(drive_id) {
+            return Ok(root.clone());
+        }
+
+        let entries: Vec<(String, String)> = cache
+            .get(drive_id)
+            .map(|files| {
+                files
+                    .values()
+                    .filter(|meta| !meta.is_dir)
+                    .map(|meta| (meta.path.clone(), meta.content_hash.clone().unwrap_or_default()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let root = DriveMerkleRoot {
+            file_count: entries.len(),
+            root: hex::encode(merkle_root(entries)),
+            computed_at: chrono::Utc::now().to_rfc3339(),
+        };
+        roots.insert(*drive_id, root.clone());
+        Ok(root)
+    }
+
     /// Get metadata for files in a specific directory
     pub async fn get_directory_metadata(
         &self,
@@ -331,33 +940,169 @@ impl DocsManager {
         dir_path: &str,
     ) -> Result<Vec<FileMetadata>> {
         let all_metadata = self.get_all_metadata(drive_id).await?;
+        let normalized_dir = normalize_dir(dir_path);
 
-        let normalized_dir = if dir_path.is_empty() || dir_path == "/" {
-            String::new()
-        } else {
-            dir_path.trim_start_matches('/').to_string()
-        };
+        let filters = self.sync_filters(drive_id).await;
 
         let result: Vec<FileMetadata> = all_metadata
             .into_iter()
-            .filter(|meta| {
-                let meta_path = meta.path.trim_start_matches('/');
-
-                if normalized_dir.is_empty() {
-                    // Root directory: only include files without path separator
-                    !meta_path.contains('/')
-                } else if let Some(remainder) = meta_path.strip_prefix(&normalized_dir) {
-                    let remainder = remainder.trim_start_matches('/');
-                    !remainder.is_empty() && !remainder.contains('/')
-                } else {
-                    false
-                }
-            })
+            .filter(|meta| is_direct_child(&normalized_dir, &meta.path))
+            .filter(|meta| filters.allows(&meta.path))
             .collect();
 
         Ok(result)
     }
 
+    /// Selective sync filters for a drive
+    pub async fn sync_filters(&self, drive_id: &DriveId) -> SyncFilters {
+        self.sync_filters
+            .read()
+            .await
+            .get(drive_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Check whether a path takes part in sync for a drive
+    pub async fn is_path_synced(&self, drive_id: &DriveId, path: &str) -> bool {
+        self.sync_filters
+            .read()
+            .await
+            .get(drive_id)
+            .is_none_or(|filters| filters.allows(path))
+    }
+
+    /// Replace a drive's selective sync filters (persists to DB)
+    ///
+    /// Files that were synced but are excluded by the new filters have
+    /// their metadata deleted from the drive's doc, so peers see a
+    /// tombstone rather than an entry that never updates again. Returns
+    /// the paths that were tombstoned.
+    pub async fn set_sync_filters(
+        &self,
+        drive_id: &DriveId,
+        filters: SyncFilters,
+    ) -> Result<Vec<String>> {
+        let drive_id_hex = hex::encode(drive_id.as_bytes());
+        if filters.is_empty() {
+            self.db.delete_sync_filters(&drive_id_hex)?;
+        } else {
+            self.db
+                .save_sync_filters(&drive_id_hex, &serde_json::to_vec(&filters)?)?;
+        }
+
+        let previous = self
+            .sync_filters
+            .write()
+            .await
+            .insert(*drive_id, filters.clone())
+            .unwrap_or_default();
+
+        let newly_excluded: Vec<String> = self
+            .get_all_metadata(drive_id)
+            .await?
+            .into_iter()
+            .filter(|meta| previous.allows(&meta.path) && !filters.allows(&meta.path))
+            .map(|meta| meta.path)
+            .collect();
+
+        for path in &newly_excluded {
+            self.delete_file_metadata(drive_id, path).await?;
+        }
+        if !newly_excluded.is_empty() {
+            tracing::info!(
+                "Tombstoned {} newly excluded entries in drive {}",
+                newly_excluded.len(),
+                drive_id
+            );
+        }
+
+        Ok(newly_excluded)
+    }
+
+    /// Fetch a directory's metadata on demand, caching the listing
+    ///
+    /// Reads only the directory's keys from the drive's doc instead of
+    /// refreshing the whole drive, and serves the result from cache for a
+    /// short while. Cached listings are dropped when one of their entries
+    /// changes. Docs sync still replicates the drive as a whole in the
+    /// background; the listing is marked partial while no peer is
+    /// connected or some entries' metadata hasn't arrived yet, in which
+    /// case whatever we already have is returned. Partial listings aren't
+    /// served from cache, so the next call checks again.
+    pub async fn fetch_directory(
+        &self,
+        drive_id: &DriveId,
+        dir_path: &str,
+        refresh: bool,
+    ) -> Result<DirectoryListing> {
+        let dir = normalize_dir(dir_path);
+
+        if !refresh {
+            let listings = self.dir_listings.read().await;
+            if let Some(listing) = listings.get(drive_id).and_then(|dirs| dirs.get(&dir)) {
+                let fresh = listing
+                    .fetched
+                    .is_some_and(|at| at.elapsed() < DIRECTORY_LISTING_TTL);
+                if fresh && !listing.partial {
+                    return Ok(listing.clone());
+                }
+            }
+        }
+
+        if !self.metadata_cache.read().await.contains_key(drive_id) {
+            self.load_drive_metadata(drive_id).await?;
+        }
+
+        let partial = match self.get_or_open_doc(drive_id).await? {
+            Some(doc) => {
+                let missing = self.refresh_directory_from_doc(drive_id, &doc, &dir).await?;
+                let connected = doc
+                    .get_sync_peers()
+                    .await?
+                    .is_some_and(|peers| !peers.is_empty());
+                missing > 0 || !connected
+            }
+            // Only what was persisted before the doc went away
+            None => true,
+        };
+
+        let entries: Vec<FileMetadata> = self
+            .metadata_cache
+            .read()
+            .await
+            .get(drive_id)
+            .map(|files| {
+                files
+                    .values()
+                    .filter(|meta| is_direct_child(&dir, &meta.path))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let listing = DirectoryListing {
+            entries,
+            partial,
+            fetched_at: chrono::Utc::now().to_rfc3339(),
+            fetched: Some(std::time::Instant::now()),
+        };
+        self.dir_listings
+            .write()
+            .await
+            .entry(*drive_id)
+            .or_default()
+            .insert(dir, listing.clone());
+        Ok(listing)
+    }
+
+    /// Drop the cached listing of the directory containing `path`
+    async fn invalidate_listing(&self, drive_id: &DriveId, path: &str) {
+        if let Some(dirs) = self.dir_listings.write().await.get_mut(drive_id) {
+            dirs.remove(&parent_dir(path));
+        }
+    }
+
     /// Generate a sharing ticket for a drive's document
     pub async fn get_ticket(&self, drive_id: &DriveId, permission: Permission) -> Result<DocTicket> {
         let doc = self
@@ -377,6 +1122,69 @@ impl DocsManager {
         Ok(ticket)
     }
 
+    /// Move a drive's metadata to a fresh document (owner only)
+    ///
+    /// Doc tickets carry the namespace capability itself and cannot be
+    /// revoked, so the only way to shut out a leaked ticket is to stop using
+    /// its namespace. This creates a new document, seeds it with the current
+    /// metadata, switches the drive over, and leaves and drops the old
+    /// replica. Peers keep syncing the old namespace among themselves until
+    /// they rejoin with a ticket for the new one.
+    ///
+    /// Returns the old and new namespace IDs.
+    pub async fn rotate_doc(&self, drive_id: &DriveId) -> Result<(NamespaceId, NamespaceId)> {
+        let old_doc = self
+            .get_or_open_doc(drive_id)
+            .await?
+            .ok_or_else(|| anyhow!("Doc not found for drive {}", drive_id))?;
+        let old_id = old_doc.id();
+
+        // Pick up anything peers wrote since the cache was last refreshed
+        if let Err(err) = self.refresh_from_doc(drive_id).await {
+            tracing::warn!(
+                error = %err,
+                drive_id = %drive_id,
+                "Failed to refresh metadata before rotating doc"
+            );
+        }
+
+        let doc = self.docs_client.create().await?;
+        let new_id = doc.id();
+        self.sync_cache_to_doc(drive_id, &doc).await?;
+
+        self.store_namespace_mapping(*drive_id, new_id).await?;
+        self.docs_by_drive.write().await.insert(*drive_id, doc);
+
+        if let Err(err) = old_doc.leave().await {
+            tracing::warn!(error = %err, namespace = %old_id, "Failed to leave old doc");
+        }
+        if let Err(err) = self.docs_client.drop_doc(old_id).await {
+            tracing::warn!(error = %err, namespace = %old_id, "Failed to drop old doc");
+        }
+
+        tracing::info!("Rotated doc for drive {}: {} -> {}", drive_id, old_id, new_id);
+
+        Ok((old_id, new_id))
+    }
+
+    /// Stop or restart background doc sync for a drive
+    ///
+    /// The doc and its metadata are kept either way. Does nothing if the
+    /// drive has no doc.
+    pub async fn set_doc_syncing(&self, drive_id: &DriveId, syncing: bool) -> Result<()> {
+        let Some(doc) = self.get_or_open_doc(drive_id).await? else {
+            return Ok(());
+        };
+
+        if syncing {
+            doc.start_sync(Vec::new()).await?;
+        } else {
+            doc.leave().await?;
+        }
+        tracing::info!("Doc sync for drive {} set to {}", drive_id, syncing);
+        Ok(())
+    }
+
     /// Check if we have a document for a drive
     pub async fn has_doc(&self, drive_id: &DriveId) -> bool {
         let ns = self.namespaces.read().await;
@@ -389,6 +1197,123 @@ impl DocsManager {
         ns.get(drive_id).copied()
     }
 
+    /// List docs namespaces that no drive in `drives` uses
+    ///
+    /// `drives` must hold every drive the app knows about; a namespace mapped
+    /// to any of them is never reported.
+    pub async fn orphan_namespaces(
+        &self,
+        drives: &HashSet<DriveId>,
+    ) -> Result<Vec<OrphanNamespace>> {
+        let all = self.list_namespaces().await?;
+        let orphans = find_orphans(&all, &*self.namespaces.read().await, drives);
+
+        let mut result = Vec::with_capacity(orphans.len());
+        for (namespace_id, drive_id) in orphans {
+            let entry_count = match self.docs_client.open(namespace_id).await? {
+                Some(doc) => {
+                    let query = Query::single_latest_per_key().build();
+                    doc.get_many(query).await?.count().await
+                }
+                None => 0,
+            };
+            result.push(OrphanNamespace {
+                namespace_id: hex::encode(namespace_id.as_bytes()),
+                drive_id: drive_id.map(|id| hex::encode(id.as_bytes())),
+                entry_count,
+            });
+        }
+        Ok(result)
+    }
+
+    /// Drop an orphaned docs namespace and everything stored for it
+    ///
+    /// Re-checks that the namespace is still orphaned against `drives` and
+    /// refuses otherwise. Removes the doc with its entries, the stale drive
+    /// mapping and that drive's persisted metadata. Content blobs lose the
+    /// doc's protection and are reclaimed by blob garbage collection.
+    pub async fn remove_orphan_namespace(
+        &self,
+        namespace_id: NamespaceId,
+        drives: &HashSet<DriveId>,
+    ) -> Result<()> {
+        let all = self.list_namespaces().await?;
+        let mut namespaces = self.namespaces.write().await;
+        let Some((_, stale_drive)) = find_orphans(&all, &namespaces, drives)
+            .into_iter()
+            .find(|(ns, _)| *ns == namespace_id)
+        else {
+            return Err(anyhow!("Namespace {} is not orphaned", namespace_id));
+        };
+
+        if let Some(drive_id) = stale_drive {
+            self.db.delete_doc_namespace(drive_id.as_bytes())?;
+            self.db.delete_drive_metadata(&hex::encode(drive_id.as_bytes()))?;
+            self.db.delete_drive_history(&hex::encode(drive_id.as_bytes()))?;
+            namespaces.remove(&drive_id);
+            self.docs_by_drive.write().await.remove(&drive_id);
+            self.metadata_cache.write().await.remove(&drive_id);
+            self.merkle_roots.write().await.remove(&drive_id);
+            self.dir_listings.write().await.remove(&drive_id);
+            self.no_direct_sync.write().await.remove(&drive_id);
+            self.bootstrap_peers.write().await.remove(&drive_id);
+        }
+        drop(namespaces);
+
+        self.docs_client.drop_doc(namespace_id).await?;
+        tracing::info!(
+            namespace = %namespace_id,
+            drive_id = ?stale_drive.map(|id| id.to_string()),
+            "Removed orphaned doc namespace"
+        );
+        Ok(())
+    }
+
+    /// Check that a drive's stored namespace mapping opens a doc
+    pub async fn verify_mapping(&self, drive_id: &DriveId) -> Result<DocMappingCheck> {
+        let stored = self.db.get_doc_namespace_bytes(drive_id.as_bytes())?;
+        let mut check = DocMappingCheck {
+            drive_id: hex::encode(drive_id.as_bytes()),
+            namespace_id: None,
+            status: DocMappingStatus::Ok,
+            error: None,
+        };
+        let namespace_id = match parse_mapping(stored.as_deref()) {
+            Ok(namespace_id) => namespace_id,
+            Err(status) => {
+                check.status = status;
+                return Ok(check);
+            }
+        };
+        check.namespace_id = Some(hex::encode(namespace_id.as_bytes()));
+
+        match self.docs_client.open(namespace_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => check.status = DocMappingStatus::Unopenable,
+            Err(err) => {
+                check.status = DocMappingStatus::Unopenable;
+                check.error = Some(err.to_string());
+            }
+        }
+        Ok(check)
+    }
+
+    /// Drop a drive's namespace mapping so a doc can be created or joined again
+    ///
+    /// The doc itself is left in the store; if it still exists it shows up
+    /// in `orphan_namespaces`. Cached metadata is kept so a new doc can be
+    /// seeded from it.
+    pub async fn forget_mapping(&self, drive_id: &DriveId) -> Result<()> {
+        self.db.delete_doc_namespace(drive_id.as_bytes())?;
+        self.namespaces.write().await.remove(drive_id);
+        self.docs_by_drive.write().await.remove(drive_id);
+        self.merkle_roots.write().await.remove(drive_id);
+        self.dir_listings.write().await.remove(drive_id);
+        self.bootstrap_peers.write().await.remove(drive_id);
+        tracing::info!("Forgot doc namespace mapping for drive {}", drive_id);
+        Ok(())
+    }
+
     /// Get sync peers for a drive document
     pub async fn get_sync_peers(
         &self,
@@ -401,11 +1326,100 @@ impl DocsManager {
         doc.get_sync_peers().await
     }
 
+    /// Peers from the ticket a drive was joined with (empty for owned drives)
+    pub async fn bootstrap_peers(&self, drive_id: &DriveId) -> Vec<iroh::NodeAddr> {
+        self.bootstrap_peers
+            .read()
+            .await
+            .get(drive_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Ask docs sync to dial a joined drive's ticket peers again
+    ///
+    /// Peers excluded from direct sync since the join are skipped. Returns
+    /// the peers that were dialed.
+    pub async fn retry_bootstrap_peers(&self, drive_id: &DriveId) -> Result<Vec<iroh::NodeAddr>> {
+        let Some(doc) = self.get_or_open_doc(drive_id).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut peers = self.bootstrap_peers(drive_id).await;
+        if let Some(excluded) = self.no_direct_sync.read().await.get(drive_id) {
+            peers.retain(|node| !excluded.contains(node.node_id.as_bytes()));
+        }
+        if !peers.is_empty() {
+            doc.start_sync(peers.clone()).await?;
+        }
+        Ok(peers)
+    }
+
+    /// Set the peers docs sync must not connect to directly for a drive
+    ///
+    /// If the doc is open and currently syncing with an excluded peer, sync
+    /// is restarted with the remaining peers. Content from excluded peers
+    /// still arrives when other peers relay it, and iroh-docs may rediscover
+    /// them through gossip neighbors; this only affects the peers we pick.
+    pub async fn set_no_direct_sync(
+        &self,
+        drive_id: &DriveId,
+        peers: HashSet<PeerIdBytes>,
+    ) -> Result<()> {
+        {
+            let mut excluded = self.no_direct_sync.write().await;
+            if peers.is_empty() {
+                excluded.remove(drive_id);
+            } else {
+                excluded.insert(*drive_id, peers.clone());
+            }
+        }
+
+        if peers.is_empty() {
+            return Ok(());
+        }
+        let Some(doc) = self.docs_by_drive.read().await.get(drive_id).cloned() else {
+            return Ok(());
+        };
+
+        let current = doc.get_sync_peers().await?.unwrap_or_default();
+        if !current.iter().any(|peer| peers.contains(peer)) {
+            return Ok(());
+        }
+
+        let remaining: Vec<iroh::NodeAddr> = current
+            .iter()
+            .filter(|peer| !peers.contains(*peer))
+            .filter_map(|peer| iroh::NodeId::from_bytes(peer).ok())
+            .map(iroh::NodeAddr::from)
+            .collect();
+
+        doc.leave().await?;
+        doc.start_sync(remaining).await?;
+        tracing::info!(
+            "Restarted doc sync for drive {} without {} excluded peer(s)",
+            drive_id,
+            peers.len()
+        );
+
+        Ok(())
+    }
+
     /// Get our author ID
     pub fn author_id(&self) -> AuthorId {
         self.author_id
     }
 
+    async fn list_namespaces(&self) -> Result<Vec<NamespaceId>> {
+        let mut stream = self.docs_client.list().await?;
+        let mut namespaces = Vec::new();
+        while let Some(item) = stream.next().await {
+            let (namespace_id, _capability) = item?;
+            namespaces.push(namespace_id);
+        }
+        Ok(namespaces)
+    }
+
     async fn store_namespace_mapping(
         &self,
         drive_id: DriveId,
@@ -467,13 +1481,59 @@ impl DocsManager {
             return Ok(());
         };
 
+        let (updates, _missing) = self
+            .read_doc_entries(drive_id, &doc, DOC_KEY_PREFIX.as_bytes())
+            .await?;
+        self.apply_doc_updates(drive_id, updates).await
+    }
+
+    /// Pull one directory's entries from the doc into the cache
+    ///
+    /// Returns the number of children whose metadata blob isn't available
+    /// locally yet.
+    async fn refresh_directory_from_doc(
+        &self,
+        drive_id: &DriveId,
+        doc: &MemDoc,
+        dir: &str,
+    ) -> Result<usize> {
+        let prefix = if dir.is_empty() {
+            DOC_KEY_PREFIX.to_string()
+        } else {
+            format!("{}{}/", DOC_KEY_PREFIX, dir)
+        };
+
+        let (mut updates, missing) = self
+            .read_doc_entries(drive_id, doc, prefix.as_bytes())
+            .await?;
+        updates.retain(|(path, _)| is_direct_child(dir, path));
+        let missing = missing
+            .iter()
+            .filter(|path| is_direct_child(dir, path))
+            .count();
+
+        self.apply_doc_updates(drive_id, updates).await?;
+        Ok(missing)
+    }
+
+    /// Read the latest doc entries under a key prefix
+    ///
+    /// Returns the updates (None for deletions) and the paths whose
+    /// metadata blob hasn't been downloaded yet.
+    async fn read_doc_entries(
+        &self,
+        drive_id: &DriveId,
+        doc: &MemDoc,
+        prefix: &[u8],
+    ) -> Result<(Vec<(String, Option<FileMetadata>)>, Vec<String>)> {
         let query = Query::single_latest_per_key()
-            .key_prefix(DOC_KEY_PREFIX.as_bytes())
+            .key_prefix(prefix)
             .include_empty()
             .build();
 
         let mut stream = doc.get_many(query).await?;
         let mut updates: Vec<(String, Option<FileMetadata>)> = Vec::new();
+        let mut missing = Vec::new();
 
         while let Some(entry) = stream.next().await {
             let entry = entry?;
@@ -487,6 +1547,7 @@ impl DocsManager {
             }
 
             let Some(bytes) = self.read_entry_bytes(&entry).await? else {
+                missing.push(path);
                 continue;
             };
 
@@ -503,34 +1564,64 @@ impl DocsManager {
                     }
                     updates.push((path, Some(meta)));
                 }
-                Err(err) => {
-                    tracing::warn!(error = %err, drive_id = %drive_id, "Failed to decode doc metadata");
-                }
+                Err(err) => match Self::incompatible_writer(&bytes) {
+                    Some(reason) => tracing::warn!(
+                        drive_id = %drive_id,
+                        path = %path,
+                        "Skipping metadata from a peer running an incompatible version: {}",
+                        reason
+                    ),
+                    None => tracing::warn!(
+                        error = %err,
+                        drive_id = %drive_id,
+                        "Failed to decode doc metadata"
+                    ),
+                },
             }
         }
 
+        Ok((updates, missing))
+    }
+
+    async fn apply_doc_updates(
+        &self,
+        drive_id: &DriveId,
+        updates: Vec<(String, Option<FileMetadata>)>,
+    ) -> Result<()> {
         if updates.is_empty() {
             return Ok(());
         }
 
         let drive_id_hex = hex::encode(drive_id.as_bytes());
         let mut cache = self.metadata_cache.write().await;
+        self.merkle_roots.write().await.remove(drive_id);
         let drive_cache = cache.entry(*drive_id).or_insert_with(HashMap::new);
+        let mut changed = Vec::new();
 
         for (path, meta) in updates {
             match meta {
                 Some(meta) => {
+                    self.record_history(&drive_id_hex, &meta)?;
                     let data = serde_json::to_vec(&meta)?;
                     self.db.save_file_metadata(&drive_id_hex, &path, &data)?;
+                    if drive_cache.get(&path) != Some(&meta) {
+                        changed.push(path.clone());
+                    }
                     drive_cache.insert(path, meta);
                 }
                 None => {
                     self.db.delete_file_metadata(&drive_id_hex, &path)?;
-                    drive_cache.remove(&path);
+                    if drive_cache.remove(&path).is_some() {
+                        changed.push(path);
+                    }
                 }
             }
         }
 
+        for path in changed {
+            self.invalidate_listing(drive_id, &path).await;
+        }
+
         Ok(())
     }
 
@@ -555,6 +1646,14 @@ impl DocsManager {
         Ok(Some(bytes.to_vec()))
     }
 
+    /// Why metadata that didn't decode was written by an incompatible
+    /// version, if its protocol version says so
+    fn incompatible_writer(bytes: &[u8]) -> Option<String> {
+        let value: serde_json::Value = serde_json::from_slice(bytes).ok()?;
+        let version = value.get("protocol_version")?.as_u64()?;
+        protocol_incompatibility(u32::try_from(version).unwrap_or(u32::MAX), false)
+    }
+
     fn path_from_key(key: &[u8]) -> Option<String> {
         let key_str = std::str::from_utf8(key).ok()?;
         key_str
@@ -587,4 +1686,133 @@ mod tests {
         assert_eq!(meta.path, parsed.path);
         assert_eq!(meta.size, parsed.size);
     }
+
+    #[test]
+    fn test_find_orphans() {
+        let live = DriveId([1u8; 32]);
+        let deleted = DriveId([2u8; 32]);
+        let live_ns = NamespaceId::from(&[10u8; 32]);
+        let stale_ns = NamespaceId::from(&[20u8; 32]);
+        let unmapped_ns = NamespaceId::from(&[30u8; 32]);
+
+        let mapping = HashMap::from([(live, live_ns), (deleted, stale_ns)]);
+        let drives = HashSet::from([live]);
+
+        let orphans = find_orphans(&[live_ns, stale_ns, unmapped_ns], &mapping, &drives);
+        assert_eq!(orphans.len(), 2);
+        assert!(orphans.contains(&(stale_ns, Some(deleted))));
+        assert!(orphans.contains(&(unmapped_ns, None)));
+
+        // A namespace mapped to a known drive is never an orphan
+        let all_known = HashSet::from([live, deleted]);
+        let orphans = find_orphans(&[live_ns, stale_ns], &mapping, &all_known);
+        assert!(orphans.is_empty());
+    }
+
+    #[test]
+    fn test_build_file_tree() {
+        let file = |path: &str, size| {
+            let name = path.rsplit('/').next().unwrap();
+            FileMetadata::with_hash(path, name, false, size, "2024-01-01T00:00:00Z", path.into())
+        };
+        let entries = vec![
+            file("photos/2024/a.jpg", 10),
+            file("photos/b.jpg", 5),
+            FileMetadata::new("photos", "photos", true, 0, "2024-01-02T00:00:00Z"),
+            file("readme.md", 1),
+            file("photos/2024/deep/c.jpg", 100),
+        ];
+
+        let tree = build_file_tree(&entries, "", 8, 100);
+        assert_eq!((tree.file_count, tree.dir_count, tree.truncated), (4, 3, false));
+        let root = &tree.root;
+        assert!(root.is_dir);
+        assert_eq!(root.size, 116);
+        let children = root.children.as_ref().unwrap();
+        assert_eq!(children[0].path, "photos");
+        assert_eq!(children[0].modified_at.as_deref(), Some("2024-01-02T00:00:00Z"));
+        assert_eq!(children[1].path, "readme.md");
+        assert_eq!(children[1].content_hash.as_deref(), Some("readme.md"));
+        assert!(children[1].children.is_none());
+
+        // Rooted at a subdirectory, with the depth capped
+        let tree = build_file_tree(&entries, "/photos/", 1, 100);
+        assert_eq!(tree.root.path, "photos");
+        assert_eq!(tree.root.name, "photos");
+        assert!(tree.truncated);
+        let children = tree.root.children.as_ref().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0].path, "photos/2024");
+        assert!(children[0].truncated);
+        assert!(children[0].children.as_ref().unwrap().is_empty());
+
+        // Entry cap
+        let tree = build_file_tree(&entries, "", 8, 2);
+        assert_eq!(tree.file_count + tree.dir_count, 2);
+        assert!(tree.truncated);
+    }
+
+    #[test]
+    fn test_parse_mapping() {
+        let namespace = NamespaceId::from(&[7u8; 32]);
+        assert_eq!(parse_mapping(Some(&[7u8; 32][..])), Ok(namespace));
+        assert_eq!(parse_mapping(None), Err(DocMappingStatus::Missing));
+        assert_eq!(parse_mapping(Some(&[7u8; 31][..])), Err(DocMappingStatus::Corrupt));
+        assert_eq!(parse_mapping(Some(&[][..])), Err(DocMappingStatus::Corrupt));
+    }
+
+    fn pairs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(path, hash)| (path.to_string(), hash.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_merkle_root_is_order_independent() {
+        let a = merkle_root(pairs(&[("a.txt", "aa"), ("b/c.txt", "bb"), ("d.txt", "cc")]));
+        let b = merkle_root(pairs(&[("d.txt", "cc"), ("/a.txt", "AA"), ("b\\c.txt", "bb")]));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_merkle_root_detects_changes() {
+        let base = merkle_root(pairs(&[("a.txt", "aa"), ("b.txt", "bb"), ("c.txt", "cc")]));
+
+        let edited = merkle_root(pairs(&[("a.txt", "aa"), ("b.txt", "b2"), ("c.txt", "cc")]));
+        let renamed = merkle_root(pairs(&[("a.txt", "aa"), ("x.txt", "bb"), ("c.txt", "cc")]));
+        let removed = merkle_root(pairs(&[("a.txt", "aa"), ("b.txt", "bb")]));
+        assert_ne!(base, edited);
+        assert_ne!(base, renamed);
+        assert_ne!(base, removed);
+
+        // The path/hash separator keeps boundaries from shifting
+        assert_ne!(
+            merkle_root(pairs(&[("ab", "c")])),
+            merkle_root(pairs(&[("a", "bc")]))
+        );
+    }
+
+    #[test]
+    fn test_directory_children() {
+        assert!(is_direct_child("", "a.txt"));
+        assert!(!is_direct_child("", "docs/a.txt"));
+        assert!(is_direct_child("docs", "docs/a.txt"));
+        assert!(is_direct_child("docs", "/docs/a.txt"));
+        assert!(!is_direct_child("docs", "docs/sub/a.txt"));
+        assert!(!is_direct_child("docs", "docs2"));
+        assert!(!is_direct_child("docs", "docs"));
+
+        assert_eq!(normalize_dir("/docs/"), "docs");
+        assert_eq!(parent_dir("docs/sub/a.txt"), "docs/sub");
+        assert_eq!(parent_dir("a.txt"), "");
+    }
+
+    #[test]
+    fn test_merkle_root_known_values() {
+        assert_eq!(merkle_root(Vec::new()), *blake3::hash(&[0x01]).as_bytes());
+
+        let leaf = *blake3::hash(b"\x00a.txt\x00aa").as_bytes();
+        assert_eq!(merkle_root(pairs(&[("a.txt", "aa")])), leaf);
+    }
 }
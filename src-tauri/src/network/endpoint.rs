@@ -4,17 +4,85 @@
 
 #![allow(dead_code)]
 
+use crate::core::AppError;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use iroh::{endpoint::Connection, Endpoint, NodeId as IrohNodeId, SecretKey};
-use serde::Serialize;
-use std::collections::HashMap;
+use iroh::protocol::{Router, RouterBuilder};
+use iroh::watcher::Watcher as _;
+use iroh::{endpoint::Connection, Endpoint, NodeId as IrohNodeId, RelayMode, RelayUrl, SecretKey};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
 /// Application-level protocol name for P2P drive sharing
 const ALPN: &[u8] = b"gix/1";
 
+/// Setting holding the endpoint bind configuration
+pub const ENDPOINT_CONFIG_SETTING: &str = "endpoint_config";
+
+/// Most preferred ports that can be configured
+const MAX_PREFERRED_PORTS: usize = 16;
+
+/// How long a relay gets to accept a connection before it counts as unreachable
+const RELAY_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the endpoint binds its UDP sockets
+///
+/// The default (no address, no ports) keeps iroh's automatic binding.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EndpointConfig {
+    /// Local address to bind (None = all interfaces)
+    ///
+    /// An IPv4 address only pins the IPv4 socket and vice versa; the other
+    /// family keeps automatic binding.
+    pub bind_address: Option<IpAddr>,
+    /// Ports to try in order; the first that binds is used (empty = any)
+    pub ports: Vec<u16>,
+}
+
+impl EndpointConfig {
+    /// True if this is the automatic binding behavior
+    pub fn is_auto(&self) -> bool {
+        self.bind_address.is_none() && self.ports.is_empty()
+    }
+
+    /// Check the configuration is usable
+    pub fn validate(&self) -> Result<(), AppError> {
+        if let Some(addr) = self.bind_address {
+            if addr.is_multicast() {
+                return Err(AppError::ValidationFailed {
+                    field: "bind_address".to_string(),
+                    reason: "must not be a multicast address".to_string(),
+                });
+            }
+        }
+        if self.ports.len() > MAX_PREFERRED_PORTS {
+            return Err(AppError::ValidationFailed {
+                field: "ports".to_string(),
+                reason: format!("at most {} ports can be listed", MAX_PREFERRED_PORTS),
+            });
+        }
+        if self.ports.contains(&0) {
+            return Err(AppError::ValidationFailed {
+                field: "ports".to_string(),
+                reason: "port 0 is not allowed; leave the list empty for any port".to_string(),
+            });
+        }
+        let unique: HashSet<u16> = self.ports.iter().copied().collect();
+        if unique.len() != self.ports.len() {
+            return Err(AppError::ValidationFailed {
+                field: "ports".to_string(),
+                reason: "ports must not repeat".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
 /// Information about a connected peer
 #[derive(Clone, Debug, Serialize)]
 pub struct PeerInfo {
@@ -30,6 +98,57 @@ pub struct ConnectionInfo {
     pub node_id: Option<String>,
     pub relay_url: Option<String>,
     pub peer_count: usize,
+    /// Why the configured bind address/ports could not be used, if they
+    /// couldn't (the endpoint then fell back to automatic binding)
+    pub bind_error: Option<String>,
+}
+
+/// Result of probing one relay server
+#[derive(Clone, Debug, Serialize)]
+pub struct RelayProbe {
+    pub relay_url: String,
+    /// True if this is the relay the endpoint is connected through
+    pub in_use: bool,
+    pub reachable: bool,
+    /// Time to open a connection to the relay
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Reachability of the relays the endpoint uses or can fall back to
+#[derive(Clone, Debug, Serialize)]
+pub struct RelayConnectivity {
+    /// Relay the endpoint is connected through (None if it has none yet)
+    pub home_relay: Option<String>,
+    /// The home relay first, then the remaining default relays
+    pub relays: Vec<RelayProbe>,
+    /// ISO 8601 time of the test
+    pub tested_at: String,
+}
+
+/// Open a TCP connection to a relay and time it
+async fn probe_relay(url: RelayUrl, in_use: bool) -> RelayProbe {
+    let host = url.host_str().unwrap_or_default().to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let start = Instant::now();
+    let connect = tokio::net::TcpStream::connect((host.as_str(), port));
+    let (latency_ms, error) = match tokio::time::timeout(RELAY_PROBE_TIMEOUT, connect).await {
+        Ok(Ok(_)) => (Some(start.elapsed().as_millis() as u64), None),
+        Ok(Err(e)) => (None, Some(e.to_string())),
+        Err(_) => (
+            None,
+            Some(format!("timed out after {}s", RELAY_PROBE_TIMEOUT.as_secs())),
+        ),
+    };
+
+    RelayProbe {
+        relay_url: url.to_string(),
+        in_use,
+        reachable: error.is_none(),
+        latency_ms,
+        error,
+    }
 }
 
 /// Manages the Iroh endpoint for P2P connections
@@ -37,6 +156,12 @@ pub struct P2PEndpoint {
     endpoint: Arc<RwLock<Option<Endpoint>>>,
     secret_key: SecretKey,
     peers: Arc<RwLock<HashMap<String, PeerInfo>>>,
+    /// Bind configuration used by `start`
+    config: EndpointConfig,
+    /// Error from binding with `config`, if it failed
+    bind_error: Arc<RwLock<Option<String>>>,
+    /// Accepts connections for the protocols registered by `serve`
+    router: Arc<RwLock<Option<Router>>>,
 }
 
 impl P2PEndpoint {
@@ -47,18 +172,41 @@ impl P2PEndpoint {
             endpoint: Arc::new(RwLock::new(None)),
             secret_key,
             peers: Arc::new(RwLock::new(HashMap::new())),
+            config: EndpointConfig::default(),
+            bind_error: Arc::new(RwLock::new(None)),
+            router: Arc::new(RwLock::new(None)),
         }
     }
 
+    /// Bind with the given address and ports instead of automatically
+    pub fn with_config(mut self, config: EndpointConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Bind configuration this endpoint was created with
+    pub fn config(&self) -> &EndpointConfig {
+        &self.config
+    }
+
     /// Initialize and bind the endpoint
+    ///
+    /// With a configured address or ports, each port is tried in order. If
+    /// none binds, the error is kept for `get_connection_info` and the
+    /// endpoint falls back to automatic binding so the app stays usable.
     pub async fn start(&self) -> Result<()> {
-        let endpoint = Endpoint::builder()
-            .secret_key(self.secret_key.clone())
-            .alpns(vec![ALPN.to_vec()])
-            // Use n0's discovery network for NAT traversal
-            .discovery_n0()
-            .bind()
-            .await?;
+        let endpoint = if self.config.is_auto() {
+            self.bind(None).await?
+        } else {
+            match self.bind_configured().await {
+                Ok(endpoint) => endpoint,
+                Err(err) => {
+                    tracing::error!("{}; falling back to automatic binding", err);
+                    *self.bind_error.write().await = Some(err.to_string());
+                    self.bind(None).await?
+                }
+            }
+        };
 
         let node_id = endpoint.node_id();
         tracing::info!("Iroh endpoint started with NodeId: {}", node_id);
@@ -73,6 +221,66 @@ impl P2PEndpoint {
         Ok(())
     }
 
+    /// Try each configured port until one binds
+    async fn bind_configured(&self) -> Result<Endpoint> {
+        let ports: Vec<u16> = if self.config.ports.is_empty() {
+            vec![0]
+        } else {
+            self.config.ports.clone()
+        };
+
+        let mut last_error = None;
+        for port in &ports {
+            match self.bind(Some(*port)).await {
+                Ok(endpoint) => {
+                    tracing::info!(
+                        "Endpoint bound to {:?} port {}",
+                        self.config.bind_address,
+                        port
+                    );
+                    return Ok(endpoint);
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to bind endpoint on port {}: {}", port, err);
+                    last_error = Some(err);
+                }
+            }
+        }
+
+        let address = self
+            .config
+            .bind_address
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "all interfaces".to_string());
+        Err(anyhow::anyhow!(
+            "Could not bind endpoint to {} on port(s) {:?}: {}",
+            address,
+            self.config.ports,
+            last_error.map(|e| e.to_string()).unwrap_or_default()
+        ))
+    }
+
+    /// Build and bind an endpoint, on `port` of the configured address if given
+    async fn bind(&self, port: Option<u16>) -> Result<Endpoint> {
+        let mut builder = Endpoint::builder()
+            .secret_key(self.secret_key.clone())
+            .alpns(vec![ALPN.to_vec()])
+            // Use n0's discovery network for NAT traversal
+            .discovery_n0();
+
+        if let Some(port) = port {
+            builder = match self.config.bind_address {
+                Some(IpAddr::V6(addr)) => builder.bind_addr_v6(SocketAddrV6::new(addr, port, 0, 0)),
+                Some(IpAddr::V4(addr)) => builder.bind_addr_v4(SocketAddrV4::new(addr, port)),
+                None => builder
+                    .bind_addr_v4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, port))
+                    .bind_addr_v6(SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, port, 0, 0)),
+            };
+        }
+
+        Ok(builder.bind().await?)
+    }
+
     /// Get the Iroh NodeId
     pub async fn node_id(&self) -> Option<IrohNodeId> {
         let guard = self.endpoint.read().await;
@@ -89,18 +297,23 @@ impl P2PEndpoint {
     pub async fn get_connection_info(&self) -> ConnectionInfo {
         let guard = self.endpoint.read().await;
         let peers = self.peers.read().await;
+        let bind_error = self.bind_error.read().await.clone();
 
         match guard.as_ref() {
             Some(endpoint) => {
-                // TODO: Properly extract relay URL from Watcher
-                // For now, we skip this as the Watcher API is complex
-                let relay_url: Option<String> = None;
+                let relay_url = endpoint
+                    .home_relay()
+                    .get()
+                    .ok()
+                    .flatten()
+                    .map(|url| url.to_string());
 
                 ConnectionInfo {
                     is_online: true,
                     node_id: Some(endpoint.node_id().to_string()),
                     relay_url,
                     peer_count: peers.len(),
+                    bind_error,
                 }
             }
             None => ConnectionInfo {
@@ -108,10 +321,53 @@ impl P2PEndpoint {
                 node_id: None,
                 relay_url: None,
                 peer_count: 0,
+                bind_error,
             },
         }
     }
 
+    /// Relay the endpoint is currently connected through
+    pub async fn home_relay(&self) -> Option<RelayUrl> {
+        let guard = self.endpoint.read().await;
+        guard.as_ref()?.home_relay().get().ok().flatten()
+    }
+
+    /// Probe the home relay and the default relays
+    ///
+    /// The home relay is the one actually in use, which can differ from the
+    /// defaults (e.g. the nearest relay was picked). Relays are probed in
+    /// parallel, each with a short timeout.
+    pub async fn test_relays(&self) -> RelayConnectivity {
+        let home = self.home_relay().await;
+
+        let mut urls: Vec<RelayUrl> = home.iter().cloned().collect();
+        for url in RelayMode::Default.relay_map().urls() {
+            if !urls.contains(url) {
+                urls.push(url.clone());
+            }
+        }
+
+        let mut probes = tokio::task::JoinSet::new();
+        for (index, url) in urls.into_iter().enumerate() {
+            let in_use = home.as_ref() == Some(&url);
+            probes.spawn(async move { (index, probe_relay(url, in_use).await) });
+        }
+        let mut relays: Vec<(usize, RelayProbe)> = Vec::new();
+        while let Some(result) = probes.join_next().await {
+            match result {
+                Ok(probe) => relays.push(probe),
+                Err(e) => tracing::warn!("Relay probe task failed: {}", e),
+            }
+        }
+        relays.sort_by_key(|(index, _)| *index);
+
+        RelayConnectivity {
+            home_relay: home.map(|url| url.to_string()),
+            relays: relays.into_iter().map(|(_, probe)| probe).collect(),
+            tested_at: Utc::now().to_rfc3339(),
+        }
+    }
+
     /// Get list of connected peers
     pub async fn get_peers(&self) -> Vec<PeerInfo> {
         let peers = self.peers.read().await;
@@ -170,8 +426,30 @@ impl P2PEndpoint {
         }
     }
 
+    /// Accept connections from peers for the protocols `register` adds
+    ///
+    /// Only the first call takes effect; does nothing if the endpoint hasn't
+    /// been started.
+    pub async fn serve(&self, register: impl FnOnce(RouterBuilder) -> RouterBuilder) {
+        let Some(endpoint) = self.get_endpoint().await else {
+            tracing::warn!("Endpoint not started, not accepting connections");
+            return;
+        };
+        let mut router = self.router.write().await;
+        if router.is_none() {
+            *router = Some(register(Router::builder(endpoint)).spawn());
+            tracing::info!("Accepting connections from peers");
+        }
+    }
+
     /// Shutdown the endpoint gracefully
     pub async fn shutdown(&self) {
+        if let Some(router) = self.router.write().await.take() {
+            if let Err(e) = router.shutdown().await {
+                tracing::warn!("Failed to stop accepting connections: {}", e);
+            }
+        }
+
         let mut guard = self.endpoint.write().await;
         if let Some(endpoint) = guard.take() {
             endpoint.close().await;
@@ -232,6 +510,7 @@ mod tests {
             node_id: None,
             relay_url: None,
             peer_count: 0,
+            bind_error: None,
         };
 
         assert!(!info.is_online);
@@ -248,6 +527,7 @@ mod tests {
             node_id: Some("node123".to_string()),
             relay_url: Some("https://relay.example.com".to_string()),
             peer_count: 5,
+            bind_error: None,
         };
 
         assert!(info.is_online);
@@ -264,6 +544,7 @@ mod tests {
             node_id: Some("node_abc".to_string()),
             relay_url: None,
             peer_count: 3,
+            bind_error: None,
         };
 
         let json = serde_json::to_string(&info).unwrap();
@@ -375,6 +656,38 @@ mod tests {
         assert_eq!(peers.len(), 1);
     }
 
+    /// Test endpoint config validation
+    #[test]
+    fn test_endpoint_config_validate() {
+        assert!(EndpointConfig::default().is_auto());
+        assert!(EndpointConfig::default().validate().is_ok());
+
+        let config = EndpointConfig {
+            bind_address: Some("192.168.1.10".parse().unwrap()),
+            ports: vec![4433, 4434],
+        };
+        assert!(!config.is_auto());
+        assert!(config.validate().is_ok());
+
+        let with_zero = EndpointConfig {
+            ports: vec![0],
+            ..Default::default()
+        };
+        assert!(with_zero.validate().is_err());
+
+        let repeated = EndpointConfig {
+            ports: vec![4433, 4433],
+            ..Default::default()
+        };
+        assert!(repeated.validate().is_err());
+
+        let multicast = EndpointConfig {
+            bind_address: Some("224.0.0.1".parse().unwrap()),
+            ports: Vec::new(),
+        };
+        assert!(multicast.validate().is_err());
+    }
+
     /// Test ALPN constant
     #[test]
     fn test_alpn_protocol() {
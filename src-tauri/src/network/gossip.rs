@@ -10,18 +10,21 @@
 #![allow(dead_code)]
 
 use crate::core::{
-    send_with_backpressure, DriveEvent, DriveEventDto, DriveId, SignedGossipMessage,
+    protocol_incompatibility, send_with_backpressure, Capability, DriveEvent, DriveEventDto,
+    DriveId, PeerCapabilities, SignedGossipMessage, MIN_PROTOCOL_VERSION, PROTOCOL_VERSION,
 };
-use crate::crypto::Identity;
+use crate::crypto::{Identity, NodeId};
+use crate::network::key_proof::KeyPossession;
 use anyhow::Result;
 use iroh::protocol::ProtocolHandler;
-use iroh::Endpoint;
+use iroh::{Endpoint, NodeId as IrohNodeId};
 use iroh_gossip::net::Gossip;
-use iroh_gossip::proto::TopicId;
-use std::collections::HashMap;
+use iroh_gossip::proto::{HyparviewConfig, TopicId};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, Mutex, RwLock};
 use tokio::task::JoinHandle;
 
@@ -34,6 +37,12 @@ const PEER_RATE_LIMIT_PER_SEC: usize = 100;
 /// Rate limit window duration in seconds
 const RATE_LIMIT_WINDOW_SECS: u64 = 1;
 
+/// Most recently seen event IDs remembered for duplicate detection
+const SEEN_EVENTS_CAPACITY: usize = 8192;
+
+/// Peers per drive subscription reported as running an incompatible version
+const MAX_INCOMPATIBLE_REPORTS: usize = 64;
+
 /// Per-peer rate limiter to prevent DoS attacks
 #[derive(Clone)]
 struct PeerRateLimiter {
@@ -87,10 +96,158 @@ impl PeerRateLimiter {
     }
 }
 
+/// Bounded record of recently processed gossip events
+///
+/// Gossip re-broadcasts and reconnect catch-up can deliver the same change
+/// more than once. IDs are remembered for `MAX_MESSAGE_AGE_MS`, after which
+/// a replayed envelope is rejected as stale anyway; the capacity bounds
+/// memory if events arrive faster than they expire.
+struct SeenEvents {
+    /// (seen at, key) in arrival order, for expiry
+    order: VecDeque<(Instant, (DriveId, [u8; 32]))>,
+    ids: HashSet<(DriveId, [u8; 32])>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl SeenEvents {
+    fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            order: VecDeque::new(),
+            ids: HashSet::new(),
+            ttl,
+            capacity,
+        }
+    }
+
+    /// Record an event, returning false if it was already seen
+    fn insert(&mut self, drive_id: DriveId, event_id: [u8; 32]) -> bool {
+        let now = Instant::now();
+        while let Some((seen_at, key)) = self.order.front() {
+            if now.duration_since(*seen_at) < self.ttl && self.order.len() < self.capacity {
+                break;
+            }
+            self.ids.remove(key);
+            self.order.pop_front();
+        }
+
+        let key = (drive_id, event_id);
+        if !self.ids.insert(key) {
+            return false;
+        }
+        self.order.push_back((now, key));
+        true
+    }
+}
+
+/// Neighbor bookkeeping for a single drive's gossip topic
+///
+/// iroh-gossip sizes its active view globally, so per-drive limits are
+/// enforced here: neighbors beyond `max_peers` are tracked as rejected and
+/// their messages are dropped. Existing neighbors are always preferred, and
+/// rejected ones are admitted as soon as there is room again.
+#[derive(Debug, Default)]
+struct NeighborSet {
+    /// Neighbors whose messages we process
+    admitted: HashSet<IrohNodeId>,
+    /// Neighbors that came up while the drive was at capacity
+    rejected: HashSet<IrohNodeId>,
+    /// Maximum admitted neighbors (None = unlimited)
+    max_peers: Option<usize>,
+}
+
+impl NeighborSet {
+    /// Try to admit a new neighbor, returns false if the drive is at capacity
+    fn admit(&mut self, peer: IrohNodeId) -> bool {
+        if self.admitted.contains(&peer) {
+            return true;
+        }
+        if let Some(max) = self.max_peers {
+            if self.admitted.len() >= max {
+                self.rejected.insert(peer);
+                return false;
+            }
+        }
+        self.admitted.insert(peer);
+        true
+    }
+
+    /// Remove a neighbor, promoting a rejected one if a slot frees up
+    fn remove(&mut self, peer: &IrohNodeId) -> Option<IrohNodeId> {
+        self.rejected.remove(peer);
+        if !self.admitted.remove(peer) {
+            return None;
+        }
+        let promoted = *self.rejected.iter().next()?;
+        self.rejected.remove(&promoted);
+        self.admitted.insert(promoted);
+        Some(promoted)
+    }
+
+    /// Whether messages from this neighbor should be processed
+    fn is_admitted(&self, peer: &IrohNodeId) -> bool {
+        !self.rejected.contains(peer)
+    }
+
+    /// Apply a new limit, demoting excess neighbors if it was lowered
+    ///
+    /// Returns the rejected neighbors admitted because it was raised.
+    fn set_max_peers(&mut self, max_peers: Option<usize>) -> Vec<IrohNodeId> {
+        self.max_peers = max_peers;
+        if let Some(max) = max_peers {
+            while self.admitted.len() > max {
+                let Some(peer) = self.admitted.iter().next().copied() else {
+                    break;
+                };
+                self.admitted.remove(&peer);
+                self.rejected.insert(peer);
+            }
+        }
+
+        let room = match max_peers {
+            Some(max) => max.saturating_sub(self.admitted.len()),
+            None => self.rejected.len(),
+        };
+        let promoted: Vec<_> = self.rejected.iter().take(room).copied().collect();
+        for peer in &promoted {
+            self.rejected.remove(peer);
+            self.admitted.insert(*peer);
+        }
+        promoted
+    }
+}
+
+/// Current gossip peers for a drive, for the frontend
+#[derive(Clone, Debug, Serialize)]
+pub struct DrivePeers {
+    pub drive_id: String,
+    /// Admitted neighbor node IDs
+    pub peers: Vec<String>,
+    pub peer_count: usize,
+    /// Neighbors refused because the drive is at capacity
+    pub rejected_count: usize,
+    /// Configured limit (None = unlimited)
+    pub max_peers: Option<usize>,
+    /// Protocol version and capabilities announced by members, by NodeId hex
+    pub capabilities: HashMap<String, PeerCapabilities>,
+    /// Whether members proved they hold the drive key, by NodeId hex
+    ///
+    /// None for drives that aren't encrypted, whose members aren't checked.
+    pub key_possession: Option<HashMap<String, KeyPossession>>,
+}
+
+/// Capabilities announced per member, per drive
+type CapabilityMap = HashMap<DriveId, HashMap<NodeId, PeerCapabilities>>;
+
 /// Type alias for the ACL checking callback
 /// Takes (drive_id, sender_node_id) and returns true if sender is authorized
 pub type AclChecker = Arc<dyn Fn(&str, &str) -> bool + Send + Sync>;
 
+/// Type alias for the invite checking callback
+/// Takes (drive_id, token_id, acceptor_node_id) and returns true if this node
+/// issued the invite and it still admits the acceptor
+pub type InviteChecker = Arc<dyn Fn(&str, &str, &str) -> bool + Send + Sync>;
+
 /// Manages gossip subscriptions per drive for real-time event broadcasting
 pub struct EventBroadcaster {
     /// The gossip protocol instance (wrapped in RwLock<Option<>> for safe shutdown)
@@ -105,6 +262,16 @@ pub struct EventBroadcaster {
     identity: Arc<Identity>,
     /// Optional ACL checker for sender authorization
     acl_checker: RwLock<Option<AclChecker>>,
+    /// Optional checker for invite acceptances from non-members
+    invite_checker: RwLock<Option<InviteChecker>>,
+    /// Neighbor tracking and peer limits per drive
+    neighbors: Arc<RwLock<HashMap<DriveId, NeighborSet>>>,
+    /// Signals each drive that gained an admitted neighbor
+    neighbor_up_tx: broadcast::Sender<DriveId>,
+    /// Recently processed events, shared by every drive's receiver
+    seen_events: Arc<Mutex<SeenEvents>>,
+    /// What each member announced in its last `UserJoined`
+    peer_capabilities: Arc<RwLock<CapabilityMap>>,
 }
 
 /// Holds state for a single drive's gossip subscription
@@ -117,11 +284,26 @@ struct TopicSubscription {
 
 impl EventBroadcaster {
     /// Create a new EventBroadcaster from an Iroh endpoint
-    pub async fn new(endpoint: &Endpoint, identity: Arc<Identity>) -> Result<Self> {
-        let gossip = Gossip::builder().spawn(endpoint.clone()).await?;
+    ///
+    /// `max_peers` is the largest per-drive neighbor limit; iroh-gossip's
+    /// active view is sized to fit it, since it caps every topic.
+    pub async fn new(
+        endpoint: &Endpoint,
+        identity: Arc<Identity>,
+        max_peers: Option<usize>,
+    ) -> Result<Self> {
+        let mut membership = HyparviewConfig::default();
+        if let Some(max) = max_peers {
+            membership.active_view_capacity = membership.active_view_capacity.max(max);
+        }
+        let gossip = Gossip::builder()
+            .membership_config(membership)
+            .spawn(endpoint.clone())
+            .await?;
 
         // Create broadcast channel for frontend events (buffer 256 events)
         let (frontend_tx, _) = broadcast::channel(256);
+        let (neighbor_up_tx, _) = broadcast::channel(64);
 
         tracing::info!("EventBroadcaster initialized with message signing enabled");
 
@@ -132,6 +314,14 @@ impl EventBroadcaster {
             shutdown_flag: AtomicBool::new(false),
             identity,
             acl_checker: RwLock::new(None),
+            invite_checker: RwLock::new(None),
+            neighbors: Arc::new(RwLock::new(HashMap::new())),
+            neighbor_up_tx,
+            seen_events: Arc::new(Mutex::new(SeenEvents::new(
+                Duration::from_millis(MAX_MESSAGE_AGE_MS as u64),
+                SEEN_EVENTS_CAPACITY,
+            ))),
+            peer_capabilities: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 
@@ -146,6 +336,14 @@ impl EventBroadcaster {
         tracing::info!("ACL checker configured for gossip sender authorization");
     }
 
+    /// Set the checker for invite acceptances
+    ///
+    /// Acceptances come from nodes the ACL doesn't know yet. Without a
+    /// checker they are held to the ACL like any other message.
+    pub async fn set_invite_checker(&self, checker: InviteChecker) {
+        *self.invite_checker.write().await = Some(checker);
+    }
+
     /// Get the underlying gossip instance (if initialized)
     pub async fn gossip(&self) -> Option<Arc<Gossip>> {
         self.get_gossip().await
@@ -187,6 +385,7 @@ impl EventBroadcaster {
 
         // Clone ACL checker for the spawned task
         let acl_checker = self.acl_checker.read().await.clone();
+        let invite_checker = self.invite_checker.read().await.clone();
 
         // Create per-peer rate limiter for this topic
         let rate_limiter = PeerRateLimiter::new(PEER_RATE_LIMIT_PER_SEC, RATE_LIMIT_WINDOW_SECS);
@@ -195,11 +394,17 @@ impl EventBroadcaster {
         let frontend_tx = self.frontend_tx.clone();
         let drive_id_hex = drive_id.to_hex();
         let drive_id_for_task = drive_id;
+        let neighbors = self.neighbors.clone();
+        neighbors.write().await.entry(drive_id).or_default();
+        let neighbor_up_tx = self.neighbor_up_tx.clone();
+        let seen_events = self.seen_events.clone();
+        let peer_capabilities = self.peer_capabilities.clone();
 
         let receiver_task = tokio::spawn(async move {
             use futures_lite::StreamExt;
 
             tracing::debug!("Started gossip receiver for drive {}", drive_id_hex);
+            let mut incompatible_reported: HashSet<(NodeId, u32)> = HashSet::new();
 
             // Periodically cleanup rate limiter entries
             let rate_limiter_for_cleanup = rate_limiter.clone();
@@ -218,6 +423,16 @@ impl EventBroadcaster {
 
                         match event {
                             Event::Gossip(GossipEvent::Received(msg)) => {
+                                // Drop messages relayed by neighbors over the peer limit
+                                {
+                                    let guard = neighbors.read().await;
+                                    if let Some(set) = guard.get(&drive_id_for_task) {
+                                        if !set.is_admitted(&msg.delivered_from) {
+                                            continue;
+                                        }
+                                    }
+                                }
+
                                 // Deserialize the signed message envelope
                                 match serde_json::from_slice::<SignedGossipMessage>(&msg.content) {
                                     Ok(signed_msg) => {
@@ -243,6 +458,20 @@ impl EventBroadcaster {
                                             continue;
                                         }
 
+                                        let version = signed_msg.protocol_version;
+                                        let incompatible = protocol_incompatibility(version, true);
+                                        if let Some(reason) = incompatible {
+                                            report_incompatible_peer(
+                                                &mut incompatible_reported,
+                                                &frontend_tx,
+                                                drive_id_for_task,
+                                                signed_msg.sender,
+                                                version,
+                                                reason,
+                                            );
+                                            continue;
+                                        }
+
                                         // Check for replay attack (stale messages)
                                         if signed_msg.is_stale(MAX_MESSAGE_AGE_MS) {
                                             tracing::warn!(
@@ -254,10 +483,41 @@ impl EventBroadcaster {
                                             continue;
                                         }
 
-                                        // SECURITY: Check if sender is authorized for this drive
+                                        // SECURITY: Read receipts, probe acks, key checks,
+                                        // departures and summaries must come from the node
+                                        // they name
+                                        if let Some(claimed) = signed_msg.event.claimed_sender() {
+                                            if *claimed != signed_msg.sender {
+                                                tracing::warn!(
+                                                    "Rejected forged {} event from {} for drive {}",
+                                                    signed_msg.event.event_type(),
+                                                    signed_msg.sender.short_string(),
+                                                    drive_id_hex
+                                                );
+                                                continue;
+                                            }
+                                        }
+
+                                        // SECURITY: Check if sender is authorized for this drive.
+                                        // Invite acceptances come from new members that the
+                                        // issuer's ACL does not know yet, so they are let through
+                                        // only on the issuer, when the acceptor signed for
+                                        // themselves and the token is a live invite issued here.
+                                        let sender_hex = signed_msg.sender.to_hex();
+                                        let is_own_acceptance = match &signed_msg.event {
+                                            DriveEvent::InviteAccepted {
+                                                token_id, user, ..
+                                            } if *user == signed_msg.sender => {
+                                                invite_checker.as_ref().is_some_and(|check| {
+                                                    check(&drive_id_hex, token_id, &sender_hex)
+                                                })
+                                            }
+                                            _ => false,
+                                        };
                                         if let Some(ref checker) = acl_checker {
-                                            let sender_hex = signed_msg.sender.to_hex();
-                                            if !checker(&drive_id_hex, &sender_hex) {
+                                            if !is_own_acceptance
+                                                && !checker(&drive_id_hex, &sender_hex)
+                                            {
                                                 tracing::warn!(
                                                     "Rejected gossip message from unauthorized sender {} for drive {}",
                                                     signed_msg.sender.short_string(),
@@ -267,8 +527,30 @@ impl EventBroadcaster {
                                             }
                                         }
 
+                                        // Drop re-deliveries of a change we already processed
+                                        let event_id = signed_msg.event_id();
+                                        if !seen_events
+                                            .lock()
+                                            .await
+                                            .insert(drive_id_for_task, event_id)
+                                        {
+                                            tracing::debug!(
+                                                "Dropped duplicate {} event for drive {}",
+                                                signed_msg.event.event_type(),
+                                                drive_id_hex
+                                            );
+                                            continue;
+                                        }
+
                                         // Message is authenticated and authorized - extract the event
                                         let drive_event = signed_msg.event;
+                                        record_capabilities(
+                                            &peer_capabilities,
+                                            drive_id_for_task,
+                                            signed_msg.sender,
+                                            &drive_event,
+                                        )
+                                        .await;
                                         let dto = DriveEventDto::from_event(
                                             &drive_id_for_task.to_hex(),
                                             &drive_event,
@@ -286,13 +568,35 @@ impl EventBroadcaster {
                                             &frontend_tx,
                                             dto,
                                             "gossip_frontend",
+                                            drive_event.backpressure_strategy(None),
                                         );
                                     }
                                     Err(e) => {
-                                        tracing::warn!(
-                                            "Failed to deserialize gossip message: {}",
-                                            e
-                                        );
+                                        // Tell a newer or older peer apart from a malformed message
+                                        let incompatible =
+                                            SignedGossipMessage::peek_header(&msg.content)
+                                                .and_then(|(sender, version)| {
+                                                    protocol_incompatibility(version, false)
+                                                        .map(|reason| (sender, version, reason))
+                                                });
+                                        match incompatible {
+                                            Some((sender, version, reason)) => {
+                                                report_incompatible_peer(
+                                                    &mut incompatible_reported,
+                                                    &frontend_tx,
+                                                    drive_id_for_task,
+                                                    sender,
+                                                    version,
+                                                    reason,
+                                                );
+                                            }
+                                            None => {
+                                                tracing::warn!(
+                                                    "Failed to deserialize gossip message: {}",
+                                                    e
+                                                );
+                                            }
+                                        }
                                     }
                                 }
                             }
@@ -304,9 +608,32 @@ impl EventBroadcaster {
                                 );
                             }
                             Event::Gossip(GossipEvent::NeighborUp(peer)) => {
-                                tracing::debug!("Peer {} joined drive {}", peer, drive_id_hex);
+                                let mut guard = neighbors.write().await;
+                                let set = guard.entry(drive_id_for_task).or_default();
+                                if set.admit(peer) {
+                                    tracing::debug!("Peer {} joined drive {}", peer, drive_id_hex);
+                                    let _ = neighbor_up_tx.send(drive_id_for_task);
+                                } else {
+                                    tracing::info!(
+                                        "Ignoring peer {} for drive {}: max peers ({:?}) reached",
+                                        peer,
+                                        drive_id_hex,
+                                        set.max_peers
+                                    );
+                                }
                             }
                             Event::Gossip(GossipEvent::NeighborDown(peer)) => {
+                                let mut guard = neighbors.write().await;
+                                if let Some(set) = guard.get_mut(&drive_id_for_task) {
+                                    if let Some(promoted) = set.remove(&peer) {
+                                        tracing::debug!(
+                                            "Admitted waiting peer {} for drive {}",
+                                            promoted,
+                                            drive_id_hex
+                                        );
+                                        let _ = neighbor_up_tx.send(drive_id_for_task);
+                                    }
+                                }
                                 tracing::debug!("Peer {} left drive {}", peer, drive_id_hex);
                             }
                             Event::Lagged => {
@@ -346,6 +673,124 @@ impl EventBroadcaster {
             sub.receiver_task.abort();
             tracing::info!("Unsubscribed from gossip topic for drive {}", drive_id);
         }
+
+        // Keep the configured limit, forget the neighbors
+        if let Some(set) = self.neighbors.write().await.get_mut(drive_id) {
+            set.admitted.clear();
+            set.rejected.clear();
+        }
+        self.peer_capabilities.write().await.remove(drive_id);
+    }
+
+    /// Set the maximum number of gossip neighbors for a drive
+    ///
+    /// Takes effect immediately for subscribed drives: lowering it demotes
+    /// neighbors and raising it admits waiting ones. `None` restores iroh's
+    /// default (unlimited at the drive level). A limit above the active view
+    /// iroh-gossip was started with can only be filled after a restart.
+    pub async fn set_max_peers(&self, drive_id: DriveId, max_peers: Option<usize>) {
+        let promoted = self
+            .neighbors
+            .write()
+            .await
+            .entry(drive_id)
+            .or_default()
+            .set_max_peers(max_peers);
+        tracing::info!("Max peers for drive {} set to {:?}", drive_id, max_peers);
+        if !promoted.is_empty() {
+            tracing::debug!(
+                "Admitted {} waiting peer(s) for drive {}",
+                promoted.len(),
+                drive_id
+            );
+            let _ = self.neighbor_up_tx.send(drive_id);
+        }
+    }
+
+    /// Get the current gossip neighbors for a drive
+    pub async fn drive_peers(&self, drive_id: &DriveId) -> DrivePeers {
+        let guard = self.neighbors.read().await;
+        let (peers, rejected_count, max_peers) = match guard.get(drive_id) {
+            Some(set) => (
+                set.admitted.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+                set.rejected.len(),
+                set.max_peers,
+            ),
+            None => (Vec::new(), 0, None),
+        };
+        drop(guard);
+
+        let capabilities = self
+            .peer_capabilities
+            .read()
+            .await
+            .get(drive_id)
+            .map(|peers| {
+                peers
+                    .iter()
+                    .map(|(node_id, caps)| (node_id.to_hex(), caps.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        DrivePeers {
+            drive_id: drive_id.to_hex(),
+            peer_count: peers.len(),
+            peers,
+            rejected_count,
+            max_peers,
+            capabilities,
+            key_possession: None,
+        }
+    }
+
+    /// Get the admitted gossip neighbors for a drive
+    pub async fn neighbors(&self, drive_id: &DriveId) -> Vec<IrohNodeId> {
+        self.neighbors
+            .read()
+            .await
+            .get(drive_id)
+            .map(|set| set.admitted.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get what a member announced for a drive, if it has announced
+    pub async fn peer_capabilities(
+        &self,
+        drive_id: &DriveId,
+        node_id: &NodeId,
+    ) -> Option<PeerCapabilities> {
+        self.peer_capabilities
+            .read()
+            .await
+            .get(drive_id)
+            .and_then(|peers| peers.get(node_id))
+            .cloned()
+    }
+
+    /// Check if a member announced support for a capability
+    ///
+    /// Members that haven't announced are assumed not to support it.
+    pub async fn peer_supports(
+        &self,
+        drive_id: &DriveId,
+        node_id: &NodeId,
+        capability: Capability,
+    ) -> bool {
+        self.peer_capabilities(drive_id, node_id)
+            .await
+            .is_some_and(|caps| caps.compatible && caps.supports(capability))
+    }
+
+    /// Announce our presence, protocol version and capabilities on a drive
+    pub async fn announce_presence(&self, drive_id: &DriveId) -> Result<()> {
+        let event = DriveEvent::UserJoined {
+            user: self.identity.node_id(),
+            timestamp: chrono::Utc::now(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: Capability::local(),
+        };
+        self.broadcast(drive_id, event).await
     }
 
     /// Broadcast an event to all peers subscribed to a drive
@@ -382,6 +827,39 @@ impl EventBroadcaster {
         Ok(())
     }
 
+    /// Ask gossip to join a drive's topic through the given peers
+    ///
+    /// Used when no neighbor has shown up on its own. The peers' addresses
+    /// must already be known to the endpoint (docs sync adds them).
+    pub async fn join_peers(&self, drive_id: &DriveId, peers: Vec<IrohNodeId>) -> Result<()> {
+        if peers.is_empty() {
+            return Ok(());
+        }
+        let topic_id = self.drive_to_topic(drive_id);
+
+        let gossip = self
+            .get_gossip()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("EventBroadcaster has been shut down"))?;
+
+        let topic = gossip.subscribe(topic_id, vec![])?;
+        let (sender, _receiver) = topic.split();
+        sender.join_peers(peers).await?;
+
+        Ok(())
+    }
+
+    /// Send a locally generated event to the frontend without broadcasting it
+    pub fn emit_local(&self, drive_id: &DriveId, event: &DriveEvent) {
+        let dto = DriveEventDto::from_event(&drive_id.to_hex(), event);
+        send_with_backpressure(
+            &self.frontend_tx,
+            dto,
+            "local_frontend",
+            event.backpressure_strategy(None),
+        );
+    }
+
     /// Get a receiver for frontend events
     ///
     /// Returns a broadcast receiver that gets all events from all subscribed drives.
@@ -390,6 +868,11 @@ impl EventBroadcaster {
         self.frontend_tx.subscribe()
     }
 
+    /// Get a receiver notified when a drive gains a neighbor
+    pub fn subscribe_neighbor_up(&self) -> broadcast::Receiver<DriveId> {
+        self.neighbor_up_tx.subscribe()
+    }
+
     /// Check if subscribed to a drive
     pub async fn is_subscribed(&self, drive_id: &DriveId) -> bool {
         let subs = self.subscriptions.read().await;
@@ -457,6 +940,97 @@ impl Drop for EventBroadcaster {
     }
 }
 
+/// Log and surface a peer whose protocol version we can't use
+///
+/// Reported once per peer and version for the subscription; later
+/// messages are dropped quietly. Stops reporting new peers after
+/// `MAX_INCOMPATIBLE_REPORTS` so forged headers can't grow the set.
+fn report_incompatible_peer(
+    reported: &mut HashSet<(NodeId, u32)>,
+    frontend_tx: &broadcast::Sender<DriveEventDto>,
+    drive_id: DriveId,
+    peer: NodeId,
+    protocol_version: u32,
+    reason: String,
+) {
+    if reported.len() >= MAX_INCOMPATIBLE_REPORTS || !reported.insert((peer, protocol_version)) {
+        tracing::debug!(
+            "Dropped message from incompatible peer {} on drive {}",
+            peer.short_string(),
+            drive_id
+        );
+        return;
+    }
+
+    tracing::warn!(
+        "Peer {} on drive {} is running an incompatible version: {}",
+        peer.short_string(),
+        drive_id,
+        reason
+    );
+    let event = DriveEvent::IncompatiblePeer {
+        peer,
+        protocol_version,
+        reason,
+    };
+    send_with_backpressure(
+        frontend_tx,
+        DriveEventDto::from_event(&drive_id.to_hex(), &event),
+        "gossip_frontend",
+        event.backpressure_strategy(None),
+    );
+}
+
+/// Track a member's announced capabilities from a signed presence event
+///
+/// Only announcements a member makes about itself are recorded.
+async fn record_capabilities(
+    peer_capabilities: &RwLock<CapabilityMap>,
+    drive_id: DriveId,
+    sender: NodeId,
+    event: &DriveEvent,
+) {
+    match event {
+        DriveEvent::UserJoined {
+            user,
+            protocol_version,
+            capabilities,
+            ..
+        } if *user == sender => {
+            let caps = PeerCapabilities::from_advertisement(*protocol_version, capabilities);
+            if !caps.compatible {
+                tracing::warn!(
+                    "Peer {} on drive {} uses protocol {}, older than the minimum {}",
+                    sender.short_string(),
+                    drive_id,
+                    protocol_version,
+                    MIN_PROTOCOL_VERSION
+                );
+            } else if *protocol_version > PROTOCOL_VERSION {
+                tracing::info!(
+                    "Peer {} on drive {} uses newer protocol {} (ours is {})",
+                    sender.short_string(),
+                    drive_id,
+                    protocol_version,
+                    PROTOCOL_VERSION
+                );
+            }
+            peer_capabilities
+                .write()
+                .await
+                .entry(drive_id)
+                .or_default()
+                .insert(sender, caps);
+        }
+        DriveEvent::UserLeft { user, .. } if *user == sender => {
+            if let Some(peers) = peer_capabilities.write().await.get_mut(&drive_id) {
+                peers.remove(&sender);
+            }
+        }
+        _ => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -590,6 +1164,8 @@ mod tests {
         let event = DriveEvent::UserJoined {
             user: node_id,
             timestamp: Utc::now(),
+            protocol_version: 0,
+            capabilities: Vec::new(),
         };
 
         let signed_msg = SignedGossipMessage::new(event.clone(), &identity);
@@ -609,6 +1185,8 @@ mod tests {
             size: 1024,
             modified_by: identity.node_id(),
             timestamp: Utc::now(),
+            created: false,
+            content_type: None,
         };
 
         let signed_msg = SignedGossipMessage::new(event, &identity);
@@ -659,6 +1237,88 @@ mod tests {
         assert!(signed_msg.is_stale(MAX_MESSAGE_AGE_MS));
     }
 
+    fn test_peer() -> IrohNodeId {
+        let identity = Identity::generate();
+        IrohNodeId::from_bytes(identity.node_id().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_seen_events_drops_duplicates() {
+        let mut seen = SeenEvents::new(Duration::from_secs(60), 2);
+        let drive = DriveId([1u8; 32]);
+
+        assert!(seen.insert(drive, [1u8; 32]));
+        assert!(!seen.insert(drive, [1u8; 32]));
+        // Same event ID on another drive is a different event
+        assert!(seen.insert(DriveId([2u8; 32]), [1u8; 32]));
+
+        // Capacity evicts the oldest entry
+        assert!(seen.insert(drive, [2u8; 32]));
+        assert!(seen.insert(drive, [1u8; 32]));
+        assert_eq!(seen.ids.len(), 2);
+    }
+
+    #[test]
+    fn test_seen_events_expire() {
+        let mut seen = SeenEvents::new(Duration::ZERO, 16);
+        let drive = DriveId([1u8; 32]);
+
+        assert!(seen.insert(drive, [1u8; 32]));
+        assert!(seen.insert(drive, [1u8; 32]));
+        assert_eq!(seen.order.len(), 1);
+    }
+
+    #[test]
+    fn test_neighbor_set_unlimited_by_default() {
+        let mut set = NeighborSet::default();
+        for _ in 0..20 {
+            assert!(set.admit(test_peer()));
+        }
+        assert_eq!(set.admitted.len(), 20);
+        assert!(set.rejected.is_empty());
+    }
+
+    #[test]
+    fn test_neighbor_set_enforces_max_peers() {
+        let mut set = NeighborSet::default();
+        assert!(set.set_max_peers(Some(2)).is_empty());
+
+        let (a, b, c) = (test_peer(), test_peer(), test_peer());
+        assert!(set.admit(a));
+        assert!(set.admit(b));
+        assert!(!set.admit(c));
+        assert!(!set.is_admitted(&c));
+
+        // Existing neighbors are re-admitted without consuming a slot
+        assert!(set.admit(a));
+
+        // Freeing a slot promotes the waiting neighbor
+        assert_eq!(set.remove(&a), Some(c));
+        assert!(set.is_admitted(&c));
+        assert_eq!(set.admitted.len(), 2);
+    }
+
+    #[test]
+    fn test_neighbor_set_lowering_limit_demotes() {
+        let mut set = NeighborSet::default();
+        for _ in 0..4 {
+            set.admit(test_peer());
+        }
+
+        assert!(set.set_max_peers(Some(1)).is_empty());
+        assert_eq!(set.admitted.len(), 1);
+        assert_eq!(set.rejected.len(), 3);
+
+        // Raising the limit admits waiting neighbors up to the new limit
+        assert_eq!(set.set_max_peers(Some(3)).len(), 2);
+        assert_eq!(set.admitted.len(), 3);
+        assert_eq!(set.rejected.len(), 1);
+
+        assert_eq!(set.set_max_peers(None).len(), 1);
+        assert_eq!(set.admitted.len(), 4);
+        assert!(set.rejected.is_empty());
+    }
+
     #[tokio::test]
     async fn test_rate_limiter_many_peers() {
         let limiter = PeerRateLimiter::new(10, 1);
@@ -699,4 +1359,34 @@ mod tests {
         let limits = limiter.limits.lock().await;
         assert_eq!(limits.len(), 10);
     }
+
+    #[tokio::test]
+    async fn test_record_capabilities_from_self_announcements() {
+        let map = RwLock::new(CapabilityMap::new());
+        let drive_id = DriveId([7u8; 32]);
+        let member = Identity::generate().node_id();
+        let other = Identity::generate().node_id();
+        let joined = |user| DriveEvent::UserJoined {
+            user,
+            timestamp: Utc::now(),
+            protocol_version: PROTOCOL_VERSION,
+            capabilities: Capability::local(),
+        };
+
+        // Announcing on behalf of someone else is ignored
+        record_capabilities(&map, drive_id, other, &joined(member)).await;
+        assert!(map.read().await.get(&drive_id).is_none());
+
+        record_capabilities(&map, drive_id, member, &joined(member)).await;
+        let caps = map.read().await[&drive_id][&member].clone();
+        assert_eq!(caps.protocol_version, PROTOCOL_VERSION);
+        assert!(caps.supports(Capability::Encryption));
+
+        let left = DriveEvent::UserLeft {
+            user: member,
+            timestamp: Utc::now(),
+        };
+        record_capabilities(&map, drive_id, member, &left).await;
+        assert!(map.read().await[&drive_id].is_empty());
+    }
 }
@@ -0,0 +1,182 @@
+//! Version history of synced files
+//!
+//! Whenever a path's metadata records new content, that content is added
+//! to the path's history. Blobs are content-addressed, so an earlier
+//! version can be read back from the blob store as long as its hash is
+//! known; the history is the index of those hashes. It is bounded by a
+//! `HistoryRetention` policy so hashes aren't kept forever.
+
+use crate::network::docs::FileMetadata;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Versions kept per path by default
+pub const DEFAULT_MAX_VERSIONS: usize = 20;
+
+/// How long past versions are kept by default (90 days)
+pub const DEFAULT_MAX_AGE_DAYS: i64 = 90;
+
+/// One recorded version of a file
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileVersion {
+    /// Version number, increasing along the path's history
+    pub version: u64,
+    /// BLAKE3 content hash (hex)
+    pub content_hash: String,
+    /// Chunk manifest blob hash (hex) if the version was uploaded in chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manifest_hash: Option<String>,
+    /// File size in bytes
+    pub size: u64,
+    /// ISO 8601 modified timestamp from the metadata
+    pub modified_at: String,
+    /// When this node recorded the version
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// How much history to keep per path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HistoryRetention {
+    /// Most versions kept, including the current one
+    pub max_versions: usize,
+    /// Versions recorded longer ago are dropped; the current version is
+    /// always kept
+    pub max_age: Option<Duration>,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self {
+            max_versions: DEFAULT_MAX_VERSIONS,
+            max_age: Some(Duration::days(DEFAULT_MAX_AGE_DAYS)),
+        }
+    }
+}
+
+/// Past versions of one path, oldest first
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHistory {
+    #[serde(default)]
+    pub versions: Vec<FileVersion>,
+}
+
+impl FileHistory {
+    /// Record the content `meta` describes as the newest version
+    ///
+    /// Content the newest version already has isn't recorded again.
+    /// Returns the number of the version holding `meta`'s content, or None
+    /// for directories and entries without content.
+    pub fn record(&mut self, meta: &FileMetadata, now: DateTime<Utc>) -> Option<u64> {
+        let hash = meta.content_hash.as_deref().filter(|_| !meta.is_dir)?;
+        let latest = self.versions.last();
+        if let Some(latest) = latest.filter(|v| v.content_hash.eq_ignore_ascii_case(hash)) {
+            return Some(latest.version);
+        }
+
+        let version = latest.map_or(1, |v| v.version + 1).max(meta.version);
+        self.versions.push(FileVersion {
+            version,
+            content_hash: hash.to_string(),
+            manifest_hash: meta.manifest_hash.clone(),
+            size: meta.size,
+            modified_at: meta.modified_at.clone(),
+            recorded_at: now,
+        });
+        Some(version)
+    }
+
+    /// Drop versions outside the retention policy, returning them
+    pub fn prune(&mut self, retention: &HistoryRetention, now: DateTime<Utc>) -> Vec<FileVersion> {
+        let excess = self
+            .versions
+            .len()
+            .saturating_sub(retention.max_versions.max(1));
+        let mut pruned: Vec<FileVersion> = self.versions.drain(..excess).collect();
+
+        if let Some(max_age) = retention.max_age {
+            let cutoff = now - max_age;
+            let expired = self
+                .versions
+                .iter()
+                .take_while(|v| v.recorded_at < cutoff)
+                .count()
+                .min(self.versions.len().saturating_sub(1));
+            pruned.extend(self.versions.drain(..expired));
+        }
+        pruned
+    }
+
+    /// Look up a version by number
+    pub fn get(&self, version: u64) -> Option<&FileVersion> {
+        self.versions.iter().find(|v| v.version == version)
+    }
+
+    /// Versions newest first
+    pub fn newest_first(&self) -> Vec<FileVersion> {
+        self.versions.iter().rev().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta(hash: &str) -> FileMetadata {
+        FileMetadata::with_hash(
+            "docs/a.txt",
+            "a.txt",
+            false,
+            hash.len() as u64,
+            "2024-01-01T00:00:00Z",
+            hash.to_string(),
+        )
+    }
+
+    #[test]
+    fn test_record_numbers_new_content() {
+        let now = Utc::now();
+        let mut history = FileHistory::default();
+
+        assert_eq!(history.record(&meta("aa"), now), Some(1));
+        assert_eq!(history.record(&meta("AA"), now), Some(1));
+        assert_eq!(history.record(&meta("bb"), now), Some(2));
+
+        // Going back to old content is a new version
+        assert_eq!(history.record(&meta("aa"), now), Some(3));
+        assert_eq!(history.versions.len(), 3);
+        assert_eq!(history.get(2).unwrap().content_hash, "bb");
+        assert_eq!(history.newest_first()[0].version, 3);
+
+        // A peer further along keeps its numbering
+        let mut ahead = meta("cc");
+        ahead.version = 10;
+        assert_eq!(history.record(&ahead, now), Some(10));
+
+        let dir = FileMetadata::new("docs", "docs", true, 0, "2024-01-01T00:00:00Z");
+        assert_eq!(history.record(&dir, now), None);
+    }
+
+    #[test]
+    fn test_prune_keeps_newest_versions() {
+        let now = Utc::now();
+        let mut history = FileHistory::default();
+        for i in 0..25 {
+            history.record(&meta(&format!("{:02x}", i)), now - Duration::days(200 - i));
+        }
+
+        let retention = HistoryRetention::default();
+        let pruned = history.prune(&retention, now);
+        assert_eq!(pruned.len(), 25 - 1);
+        assert_eq!(history.versions.len(), 1);
+        assert_eq!(history.versions[0].version, 25);
+
+        let mut history = FileHistory::default();
+        for i in 0..25 {
+            history.record(&meta(&format!("{:02x}", i)), now);
+        }
+        let pruned = history.prune(&retention, now);
+        assert_eq!(pruned.len(), 5);
+        assert_eq!(history.versions.len(), DEFAULT_MAX_VERSIONS);
+        assert_eq!(history.versions[0].version, 6);
+    }
+}
@@ -0,0 +1,576 @@
+//! Checks that members of encrypted drives actually hold the drive key
+//!
+//! Being in a drive's ACL doesn't mean a peer can read it: if its key
+//! exchange failed it has no drive key, and encrypted content is useless to
+//! it. When a member announces itself (`UserJoined`) on a drive we hold the
+//! key for, or is one of its neighbors when we start or gain a neighbor, we
+//! send a `KeyChallenge` with a fresh nonce. The member answers with a
+//! `KeyProof`: a MAC over the drive, the nonce and its own node ID, keyed
+//! from the drive key, so a proof can't be replayed or passed on to another
+//! node. Blobs and file summaries of encrypted drives are only served to
+//! members that passed. Drives we hold no key for are not checked.
+
+use crate::core::{DriveEvent, DriveId};
+use crate::crypto::{DriveKey, EncryptionManager, NodeId};
+use crate::network::EventBroadcaster;
+use chrono::Utc;
+use iroh::endpoint::Connection;
+use iroh::protocol::ProtocolHandler;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+
+/// How long a challenged member has to answer before it counts as failed
+const CHALLENGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Whether a member proved it holds a drive's key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyPossession {
+    /// Challenged; waiting for the proof
+    Pending,
+    /// Answered with a valid proof
+    Verified,
+    /// Answered that it has no key
+    Missing,
+    /// Answered with a wrong proof, or not in time
+    Failed,
+}
+
+/// Proof that `node` holds `key`, answering `nonce` for `drive_id`
+pub fn key_proof(key: &DriveKey, drive_id: &DriveId, nonce: &str, node: &NodeId) -> String {
+    let mut hasher = blake3::Hasher::new_keyed(&key.derive_proof_key());
+    hasher.update(drive_id.as_bytes());
+    hasher.update(nonce.as_bytes());
+    hasher.update(node.as_bytes());
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Check `proof` against the expected one without leaking timing
+fn proof_matches(expected: &str, proof: &str) -> bool {
+    match (
+        blake3::Hash::from_hex(expected),
+        blake3::Hash::from_hex(proof),
+    ) {
+        // blake3::Hash equality is constant-time
+        (Ok(expected), Ok(proof)) => expected == proof,
+        _ => false,
+    }
+}
+
+struct MemberKeyState {
+    status: KeyPossession,
+    /// Nonce of the outstanding challenge
+    nonce: Option<String>,
+    challenged_at: Instant,
+}
+
+impl MemberKeyState {
+    fn status(&self, now: Instant) -> KeyPossession {
+        match self.status {
+            KeyPossession::Pending
+                if now.duration_since(self.challenged_at) >= CHALLENGE_TIMEOUT =>
+            {
+                KeyPossession::Failed
+            }
+            status => status,
+        }
+    }
+}
+
+/// Key possession status per encrypted drive and member
+#[derive(Default)]
+pub struct KeyProofTracker {
+    /// Drives we hold the key for
+    encrypted: RwLock<HashSet<DriveId>>,
+    members: RwLock<HashMap<DriveId, HashMap<NodeId, MemberKeyState>>>,
+}
+
+impl KeyProofTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record whether we hold `drive_id`'s key; members of drives we don't
+    /// are forgotten
+    pub async fn set_encrypted(&self, drive_id: &DriveId, encrypted: bool) {
+        if encrypted {
+            self.encrypted.write().await.insert(*drive_id);
+        } else if self.encrypted.write().await.remove(drive_id) {
+            self.members.write().await.remove(drive_id);
+        }
+    }
+
+    /// Start a challenge for `member`, returning its nonce
+    ///
+    /// Returns None if the drive isn't encrypted, the member is verified,
+    /// or an earlier challenge is still waiting for an answer.
+    pub async fn begin_challenge(&self, drive_id: &DriveId, member: &NodeId) -> Option<String> {
+        if !self.encrypted.read().await.contains(drive_id) {
+            return None;
+        }
+        let now = Instant::now();
+        let mut members = self.members.write().await;
+        let drive = members.entry(*drive_id).or_default();
+        if let Some(state) = drive.get(member) {
+            if matches!(
+                state.status(now),
+                KeyPossession::Verified | KeyPossession::Pending
+            ) {
+                return None;
+            }
+        }
+
+        let nonce = hex::encode(rand::random::<[u8; 16]>());
+        drive.insert(
+            *member,
+            MemberKeyState {
+                status: KeyPossession::Pending,
+                nonce: Some(nonce.clone()),
+                challenged_at: now,
+            },
+        );
+        Some(nonce)
+    }
+
+    /// Check a member's answer to our challenge, returning its new status
+    ///
+    /// Answers to challenges that aren't outstanding (stale, expired or
+    /// never sent) are ignored and return None.
+    pub async fn record_proof(
+        &self,
+        drive_id: &DriveId,
+        member: &NodeId,
+        nonce: &str,
+        proof: Option<&str>,
+        key: &DriveKey,
+    ) -> Option<KeyPossession> {
+        let now = Instant::now();
+        let mut members = self.members.write().await;
+        let state = members.get_mut(drive_id)?.get_mut(member)?;
+        if state.status(now) != KeyPossession::Pending || state.nonce.as_deref() != Some(nonce) {
+            return None;
+        }
+
+        state.status = match proof {
+            None => KeyPossession::Missing,
+            Some(proof) if proof_matches(&key_proof(key, drive_id, nonce, member), proof) => {
+                KeyPossession::Verified
+            }
+            Some(_) => KeyPossession::Failed,
+        };
+        state.nonce = None;
+        Some(state.status)
+    }
+
+    /// Forget a member that left the drive
+    ///
+    /// Only verified members are forgotten, so they are checked again when
+    /// they return. A member that failed, has no key or hasn't answered yet
+    /// keeps its status until a new challenge settles it; otherwise leaving
+    /// would make it a never-checked peer that blobs are served to.
+    pub async fn forget(&self, drive_id: &DriveId, member: &NodeId) {
+        let now = Instant::now();
+        if let Some(drive) = self.members.write().await.get_mut(drive_id) {
+            if drive
+                .get(member)
+                .is_some_and(|state| state.status(now) == KeyPossession::Verified)
+            {
+                drive.remove(member);
+            }
+        }
+    }
+
+    /// Status of every member seen on a drive, by NodeId hex
+    ///
+    /// None if the drive isn't encrypted, since its members aren't checked.
+    pub async fn statuses(&self, drive_id: &DriveId) -> Option<HashMap<String, KeyPossession>> {
+        if !self.encrypted.read().await.contains(drive_id) {
+            return None;
+        }
+        let now = Instant::now();
+        let members = self.members.read().await;
+        Some(
+            members
+                .get(drive_id)
+                .map(|drive| {
+                    drive
+                        .iter()
+                        .map(|(member, state)| (member.to_hex(), state.status(now)))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Whether encrypted content of a drive may be offered to `member`
+    ///
+    /// Always true for drives that aren't encrypted.
+    pub async fn may_serve(&self, drive_id: &DriveId, member: &NodeId) -> bool {
+        if !self.encrypted.read().await.contains(drive_id) {
+            return true;
+        }
+        let now = Instant::now();
+        self.members
+            .read()
+            .await
+            .get(drive_id)
+            .and_then(|drive| drive.get(member))
+            .is_some_and(|state| state.status(now) == KeyPossession::Verified)
+    }
+
+    /// Whether blobs may be served to `member` at all
+    ///
+    /// Blob requests don't name a drive, so a member is refused unless it
+    /// proved the key of every encrypted drive it was checked on. Peers
+    /// never checked on an encrypted drive are not refused.
+    pub async fn may_serve_peer(&self, member: &NodeId) -> bool {
+        let encrypted = self.encrypted.read().await;
+        let now = Instant::now();
+        self.members
+            .read()
+            .await
+            .iter()
+            .filter(|(drive_id, _)| encrypted.contains(*drive_id))
+            .filter_map(|(_, drive)| drive.get(member))
+            .all(|state| state.status(now) == KeyPossession::Verified)
+    }
+}
+
+/// Blobs protocol handler that refuses peers failing the key check
+#[derive(Clone)]
+pub struct KeyCheckedBlobs<H> {
+    inner: H,
+    tracker: Arc<KeyProofTracker>,
+}
+
+impl<H> KeyCheckedBlobs<H> {
+    pub fn new(inner: H, tracker: Arc<KeyProofTracker>) -> Self {
+        Self { inner, tracker }
+    }
+}
+
+impl<H: std::fmt::Debug> std::fmt::Debug for KeyCheckedBlobs<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KeyCheckedBlobs")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<H: ProtocolHandler + Clone> ProtocolHandler for KeyCheckedBlobs<H> {
+    fn accept(
+        &self,
+        connection: Connection,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'static>> {
+        let this = self.clone();
+        Box::pin(async move {
+            let peer = connection.remote_node_id()?;
+            if !this.tracker.may_serve_peer(&NodeId(*peer.as_bytes())).await {
+                connection.close(0u32.into(), b"key check failed");
+                anyhow::bail!("Refused blobs to {}: drive key not proven", peer);
+            }
+            this.inner.accept(connection).await
+        })
+    }
+}
+
+/// A challenge for `member` if its key possession needs checking
+async fn challenge_for(
+    tracker: &KeyProofTracker,
+    node_id: NodeId,
+    drive_id: &DriveId,
+    member: NodeId,
+) -> Option<DriveEvent> {
+    if member == node_id {
+        return None;
+    }
+    tracker
+        .begin_challenge(drive_id, &member)
+        .await
+        .map(|nonce| DriveEvent::KeyChallenge {
+            challenger: node_id,
+            target: member,
+            nonce,
+            timestamp: Utc::now(),
+        })
+}
+
+/// Challenge every current neighbor of a drive we hold the key for
+async fn challenge_neighbors(
+    node_id: NodeId,
+    drive_id: DriveId,
+    broadcaster: &EventBroadcaster,
+    encryption: &EncryptionManager,
+    tracker: &KeyProofTracker,
+) {
+    let has_key = match encryption.has_key(&drive_id.to_hex()).await {
+        Ok(has_key) => has_key,
+        Err(e) => {
+            tracing::warn!(drive_id = %drive_id, "Failed to check for the drive key: {}", e);
+            return;
+        }
+    };
+    tracker.set_encrypted(&drive_id, has_key).await;
+    if !has_key {
+        return;
+    }
+    for neighbor in broadcaster.neighbors(&drive_id).await {
+        let member = NodeId(*neighbor.as_bytes());
+        if let Some(challenge) = challenge_for(tracker, node_id, &drive_id, member).await {
+            if let Err(e) = broadcaster.broadcast(&drive_id, challenge).await {
+                tracing::warn!(drive_id = %drive_id, "Failed to send key check: {}", e);
+            }
+        }
+    }
+}
+
+/// Challenge members of encrypted drives and answer their challenges
+pub fn start_key_proof_responder(
+    node_id: NodeId,
+    broadcaster: Arc<EventBroadcaster>,
+    encryption: Arc<EncryptionManager>,
+    tracker: Arc<KeyProofTracker>,
+) -> JoinHandle<()> {
+    let mut rx = broadcaster.subscribe_frontend();
+    let mut neighbor_rx = broadcaster.subscribe_neighbor_up();
+    tokio::spawn(async move {
+        // Members already connected never announce themselves again
+        for drive_id in broadcaster.subscribed_drives().await {
+            challenge_neighbors(node_id, drive_id, &broadcaster, &encryption, &tracker).await;
+        }
+
+        loop {
+            let received = tokio::select! {
+                received = rx.recv() => received,
+                neighbor = neighbor_rx.recv() => {
+                    match neighbor {
+                        Ok(drive_id) => {
+                            challenge_neighbors(
+                                node_id,
+                                drive_id,
+                                &broadcaster,
+                                &encryption,
+                                &tracker,
+                            )
+                            .await;
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    }
+                    continue;
+                }
+            };
+            let dto = match received {
+                Ok(dto) => dto,
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    tracing::warn!("Key proof responder lagged, missed {} events", count);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if !matches!(
+                dto.event_type.as_str(),
+                "UserJoined" | "UserLeft" | "KeyChallenge" | "KeyProof"
+            ) {
+                continue;
+            }
+            let Ok(event) = serde_json::from_value::<DriveEvent>(dto.payload) else {
+                continue;
+            };
+            let Ok(drive_id) = DriveId::from_hex(&dto.drive_id) else {
+                continue;
+            };
+
+            let reply = match event {
+                DriveEvent::UserJoined { user, .. } if user != node_id => {
+                    match encryption.has_key(&dto.drive_id).await {
+                        Ok(has_key) => {
+                            tracker.set_encrypted(&drive_id, has_key).await;
+                            challenge_for(&tracker, node_id, &drive_id, user).await
+                        }
+                        Err(e) => {
+                            tracing::warn!(
+                                drive_id = %drive_id,
+                                "Failed to check for the drive key: {}",
+                                e
+                            );
+                            None
+                        }
+                    }
+                }
+                DriveEvent::UserLeft { user, .. } => {
+                    tracker.forget(&drive_id, &user).await;
+                    None
+                }
+                DriveEvent::KeyChallenge {
+                    challenger,
+                    target,
+                    nonce,
+                    ..
+                } if target == node_id => {
+                    let drive_encryption = match encryption.get_encryption(&dto.drive_id).await {
+                        Ok(drive_encryption) => drive_encryption,
+                        Err(e) => {
+                            // Locked: answering "no key" would fail us for good
+                            tracing::warn!(
+                                drive_id = %drive_id,
+                                "Can't answer the key check for drive {}: {}",
+                                drive_id,
+                                e
+                            );
+                            continue;
+                        }
+                    };
+                    let proof = drive_encryption
+                        .map(|enc| key_proof(enc.key(), &drive_id, &nonce, &node_id));
+                    if proof.is_none() {
+                        tracing::warn!(
+                            drive_id = %drive_id,
+                            "Challenged for the key of drive {}, which we don't hold",
+                            drive_id
+                        );
+                    }
+                    Some(DriveEvent::KeyProof {
+                        node: node_id,
+                        challenger,
+                        nonce,
+                        proof,
+                        timestamp: Utc::now(),
+                    })
+                }
+                DriveEvent::KeyProof {
+                    node,
+                    challenger,
+                    nonce,
+                    proof,
+                    ..
+                } if challenger == node_id => {
+                    let Ok(Some(enc)) = encryption.get_encryption(&dto.drive_id).await else {
+                        continue;
+                    };
+                    let status = tracker
+                        .record_proof(&drive_id, &node, &nonce, proof.as_deref(), enc.key())
+                        .await;
+                    match status {
+                        Some(KeyPossession::Verified) => {
+                            tracing::debug!("Peer {} holds the key of drive {}", node, drive_id);
+                        }
+                        Some(status) => {
+                            tracing::warn!(
+                                drive_id = %drive_id,
+                                "Peer {} failed the key check for drive {}: {:?}",
+                                node,
+                                drive_id,
+                                status
+                            );
+                        }
+                        None => {}
+                    }
+                    None
+                }
+                _ => None,
+            };
+
+            if let Some(reply) = reply {
+                if let Err(e) = broadcaster.broadcast(&drive_id, reply).await {
+                    tracing::warn!(drive_id = %drive_id, "Failed to send key check: {}", e);
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Identity;
+
+    #[tokio::test]
+    async fn test_key_possession_flow() {
+        let drive_id = DriveId([1u8; 32]);
+        let member = Identity::generate().node_id();
+        let other = Identity::generate().node_id();
+        let key = DriveKey::generate();
+        let tracker = KeyProofTracker::new();
+
+        // Unencrypted drives aren't checked
+        assert!(tracker.begin_challenge(&drive_id, &member).await.is_none());
+        assert!(tracker.statuses(&drive_id).await.is_none());
+        assert!(tracker.may_serve(&drive_id, &member).await);
+
+        tracker.set_encrypted(&drive_id, true).await;
+        assert!(!tracker.may_serve(&drive_id, &member).await);
+        let nonce = tracker.begin_challenge(&drive_id, &member).await.unwrap();
+        // No second challenge while one is outstanding
+        assert!(tracker.begin_challenge(&drive_id, &member).await.is_none());
+        let statuses = tracker.statuses(&drive_id).await.unwrap();
+        assert_eq!(statuses[&member.to_hex()], KeyPossession::Pending);
+
+        // A proof made for another node, or for another nonce, is rejected
+        let relayed = key_proof(&key, &drive_id, &nonce, &other);
+        let stale = key_proof(&key, &drive_id, "old", &member);
+        assert_eq!(
+            tracker
+                .record_proof(&drive_id, &member, "old", Some(&stale), &key)
+                .await,
+            None
+        );
+        assert_eq!(
+            tracker
+                .record_proof(&drive_id, &member, &nonce, Some(&relayed), &key)
+                .await,
+            Some(KeyPossession::Failed)
+        );
+
+        let nonce = tracker.begin_challenge(&drive_id, &member).await.unwrap();
+        let proof = key_proof(&key, &drive_id, &nonce, &member);
+        assert_eq!(
+            tracker
+                .record_proof(&drive_id, &member, &nonce, Some(&proof), &key)
+                .await,
+            Some(KeyPossession::Verified)
+        );
+        assert!(tracker.may_serve(&drive_id, &member).await);
+        assert!(tracker.begin_challenge(&drive_id, &member).await.is_none());
+        // The same answer can't be recorded twice
+        assert_eq!(
+            tracker
+                .record_proof(&drive_id, &member, &nonce, Some(&proof), &key)
+                .await,
+            None
+        );
+
+        let nonce = tracker.begin_challenge(&drive_id, &other).await.unwrap();
+        assert_eq!(
+            tracker
+                .record_proof(&drive_id, &other, &nonce, None, &key)
+                .await,
+            Some(KeyPossession::Missing)
+        );
+        assert!(!tracker.may_serve(&drive_id, &other).await);
+
+        // Blobs are refused only to members that failed somewhere
+        let stranger = Identity::generate().node_id();
+        assert!(tracker.may_serve_peer(&member).await);
+        assert!(!tracker.may_serve_peer(&other).await);
+        assert!(tracker.may_serve_peer(&stranger).await);
+
+        tracker.forget(&drive_id, &member).await;
+        assert!(!tracker.may_serve(&drive_id, &member).await);
+
+        // Leaving doesn't clear a failed check
+        tracker.forget(&drive_id, &other).await;
+        assert!(!tracker.may_serve_peer(&other).await);
+        assert_eq!(
+            tracker.statuses(&drive_id).await.unwrap()[&other.to_hex()],
+            KeyPossession::Missing
+        );
+        assert!(tracker.begin_challenge(&drive_id, &other).await.is_some());
+    }
+}
@@ -0,0 +1,312 @@
+//! End-to-end sync latency probes
+//!
+//! Gossip round-trip time says little about how long a saved file takes to
+//! show up on a peer. A probe measures that instead: a small marker file is
+//! written under [`LATENCY_PROBE_DIR`] and travels the normal path (watcher,
+//! metadata, gossip). Peers running the responder download it, write it to
+//! disk and answer with a `SyncProbeAck`; the marker is then removed again
+//! on both sides.
+//!
+//! Probe files are named after the node that started them, so a peer's own
+//! write of a downloaded marker is never mistaken for a new probe.
+
+use crate::core::{validate_path, DriveEvent, DriveId, SharedDrive};
+use crate::crypto::NodeId;
+use crate::network::{EventBroadcaster, FileTransferManager, SyncEngine};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+
+/// Directory, relative to the drive root, that probe markers are written to
+pub const LATENCY_PROBE_DIR: &str = ".gix-latency";
+
+/// Extension of probe marker files
+const PROBE_EXTENSION: &str = "probe";
+
+/// One peer's answer to a probe
+#[derive(Clone, Debug, Serialize)]
+pub struct PeerSyncLatency {
+    /// Peer node ID (hex)
+    pub node_id: String,
+    /// From writing the marker to receiving this peer's acknowledgement
+    pub total_ms: u64,
+    /// From writing the marker to the peer's write, by the peer's clock
+    ///
+    /// Excludes the acknowledgement's trip back but is off by any clock
+    /// skew between the two devices; it can even be negative.
+    pub peer_written_ms: i64,
+}
+
+/// Result of an end-to-end sync latency measurement
+#[derive(Clone, Debug, Serialize)]
+pub struct E2eLatencyReport {
+    pub drive_id: String,
+    /// Marker file path relative to the drive root
+    pub probe_path: String,
+    /// From writing the marker to the local watcher announcing it
+    pub local_detect_ms: Option<u64>,
+    /// Peers connected when the probe started
+    pub expected_peers: usize,
+    /// Peers that downloaded the marker, fastest first
+    pub peers: Vec<PeerSyncLatency>,
+    /// True if some peers hadn't answered when the timeout ran out
+    pub timed_out: bool,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Node ID (hex) of the node that started the probe at `path`
+///
+/// Returns None unless `path` is a probe marker directly inside
+/// [`LATENCY_PROBE_DIR`].
+pub fn probe_origin(path: &Path) -> Option<&str> {
+    let mut components = path.components();
+    if components.next()?.as_os_str() != LATENCY_PROBE_DIR {
+        return None;
+    }
+    let name = components.next()?.as_os_str().to_str()?;
+    if components.next().is_some() {
+        return None;
+    }
+    let stem = name.strip_suffix(PROBE_EXTENSION)?.strip_suffix('.')?;
+    let (origin, _nonce) = stem.split_once('-')?;
+    (origin.len() == 64 && origin.chars().all(|c| c.is_ascii_hexdigit())).then_some(origin)
+}
+
+/// Probe ID (file stem) of the marker at `path`
+fn probe_id(path: &Path) -> Option<String> {
+    path.file_stem().map(|s| s.to_string_lossy().to_string())
+}
+
+/// Measure how long a file written here takes to be written on peers
+///
+/// Waits until every peer connected at the start has answered or
+/// `timeout` runs out, then removes the marker.
+pub async fn measure(
+    sync_engine: &SyncEngine,
+    broadcaster: &EventBroadcaster,
+    drive_id: DriveId,
+    root: &Path,
+    node_id: NodeId,
+    timeout: Duration,
+) -> Result<E2eLatencyReport> {
+    let expected_peers = broadcaster.drive_peers(&drive_id).await.peer_count;
+    let nonce: u64 = rand::random();
+    let relative = PathBuf::from(LATENCY_PROBE_DIR)
+        .join(format!("{}-{:016x}.{}", node_id.to_hex(), nonce, PROBE_EXTENSION));
+    let absolute = root.join(&relative);
+    let probe = probe_id(&relative).unwrap_or_default();
+
+    // Subscribe before writing so a quick answer isn't missed
+    let mut local_rx = sync_engine.subscribe_events();
+    let mut remote_rx = broadcaster.subscribe_frontend();
+
+    tokio::fs::create_dir_all(root.join(LATENCY_PROBE_DIR))
+        .await
+        .context("Failed to create probe directory")?;
+    let started_at = Utc::now();
+    let started = tokio::time::Instant::now();
+    tokio::fs::write(&absolute, format!("{} {}", probe, started_at.to_rfc3339()))
+        .await
+        .context("Failed to write probe file")?;
+
+    let drive_hex = drive_id.to_hex();
+    let deadline = started + timeout;
+    let mut local_detect_ms = None;
+    let mut answers: HashMap<String, PeerSyncLatency> = HashMap::new();
+    let mut timed_out = false;
+
+    while answers.len() < expected_peers || local_detect_ms.is_none() {
+        tokio::select! {
+            local = local_rx.recv() => match local {
+                Ok((id, DriveEvent::FileChanged { path, .. }))
+                    if id == drive_id && path == relative && local_detect_ms.is_none() =>
+                {
+                    local_detect_ms = Some(started.elapsed().as_millis() as u64);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+                _ => {}
+            },
+            remote = remote_rx.recv() => {
+                let dto = match remote {
+                    Ok(dto) => dto,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                if dto.drive_id != drive_hex || dto.event_type != "SyncProbeAck" {
+                    continue;
+                }
+                let Ok(DriveEvent::SyncProbeAck {
+                    probe_id,
+                    node,
+                    timestamp,
+                }) = serde_json::from_value::<DriveEvent>(dto.payload)
+                else {
+                    continue;
+                };
+                if probe_id != probe {
+                    continue;
+                }
+                answers.entry(node.to_hex()).or_insert_with(|| PeerSyncLatency {
+                    node_id: node.to_hex(),
+                    total_ms: started.elapsed().as_millis() as u64,
+                    peer_written_ms: (timestamp - started_at).num_milliseconds(),
+                });
+            }
+            _ = tokio::time::sleep_until(deadline) => {
+                timed_out = answers.len() < expected_peers;
+                break;
+            }
+        }
+    }
+
+    if let Err(e) = tokio::fs::remove_file(&absolute).await {
+        tracing::debug!("Failed to remove probe file {}: {}", absolute.display(), e);
+    }
+
+    let mut peers: Vec<PeerSyncLatency> = answers.into_values().collect();
+    peers.sort_by_key(|p| p.total_ms);
+    Ok(E2eLatencyReport {
+        drive_id: drive_hex,
+        probe_path: relative.to_string_lossy().replace('\\', "/"),
+        local_detect_ms,
+        expected_peers,
+        peers,
+        timed_out,
+        started_at,
+    })
+}
+
+/// Answer other nodes' latency probes
+///
+/// On a probe marker announced by the node that started it, the marker is
+/// downloaded from that node, written into the drive, acknowledged, and
+/// removed again.
+pub fn start_probe_responder(
+    node_id: NodeId,
+    drives: Arc<RwLock<HashMap<[u8; 32], SharedDrive>>>,
+    broadcaster: Arc<EventBroadcaster>,
+    transfer: Arc<FileTransferManager>,
+) -> JoinHandle<()> {
+    let mut rx = broadcaster.subscribe_frontend();
+    tokio::spawn(async move {
+        loop {
+            let dto = match rx.recv().await {
+                Ok(dto) => dto,
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    tracing::warn!("Probe responder lagged, missed {} events", count);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if dto.event_type != "FileChanged" {
+                continue;
+            }
+            let Ok(DriveEvent::FileChanged {
+                path,
+                hash,
+                modified_by,
+                ..
+            }) = serde_json::from_value::<DriveEvent>(dto.payload)
+            else {
+                continue;
+            };
+            // Only the node a probe is named after can start it
+            if modified_by == node_id || probe_origin(&path) != Some(modified_by.to_hex().as_str()) {
+                continue;
+            }
+            let Ok(drive_id) = DriveId::from_hex(&dto.drive_id) else {
+                continue;
+            };
+            let root = drives
+                .read()
+                .await
+                .get(drive_id.as_bytes())
+                .map(|d| d.local_path.clone());
+            let Some(root) = root else {
+                continue;
+            };
+
+            let broadcaster = broadcaster.clone();
+            let transfer = transfer.clone();
+            tokio::spawn(async move {
+                let answer = answer_probe(
+                    &broadcaster,
+                    &transfer,
+                    drive_id,
+                    &root,
+                    &path,
+                    &hash,
+                    modified_by,
+                    node_id,
+                );
+                if let Err(e) = answer.await {
+                    tracing::warn!(drive_id = %drive_id, "Failed to answer latency probe: {}", e);
+                }
+            });
+        }
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn answer_probe(
+    broadcaster: &EventBroadcaster,
+    transfer: &FileTransferManager,
+    drive_id: DriveId,
+    root: &Path,
+    relative: &Path,
+    hash: &str,
+    origin: NodeId,
+    node_id: NodeId,
+) -> Result<()> {
+    let absolute = validate_path(root, &relative.to_string_lossy())?;
+    let hash = hash.parse::<iroh_blobs::Hash>()?;
+    let provider = iroh::NodeId::from_bytes(origin.as_bytes())?;
+
+    transfer
+        .download_from_peer(&drive_id, hash, &[provider], &absolute, relative)
+        .await?;
+    let ack = DriveEvent::SyncProbeAck {
+        probe_id: probe_id(relative).unwrap_or_default(),
+        node: node_id,
+        timestamp: Utc::now(),
+    };
+    broadcaster.broadcast(&drive_id, ack).await?;
+
+    if let Err(e) = tokio::fs::remove_file(&absolute).await {
+        tracing::debug!("Failed to remove probe file {}: {}", absolute.display(), e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_origin() {
+        let origin = "ab".repeat(32);
+        let path = PathBuf::from(LATENCY_PROBE_DIR).join(format!("{}-00ff.probe", origin));
+        assert_eq!(probe_origin(&path), Some(origin.as_str()));
+        assert_eq!(probe_id(&path), Some(format!("{}-00ff", origin)));
+
+        // Only markers directly inside the probe directory count
+        let nested = PathBuf::from(LATENCY_PROBE_DIR)
+            .join("sub")
+            .join(format!("{}-00ff.probe", origin));
+        assert_eq!(probe_origin(&nested), None);
+        let elsewhere = PathBuf::from("docs").join(format!("{}-00ff.probe", origin));
+        assert_eq!(probe_origin(&elsewhere), None);
+
+        // Malformed names are ignored
+        let short = PathBuf::from(LATENCY_PROBE_DIR).join("abcd-00ff.probe");
+        assert_eq!(probe_origin(&short), None);
+        let no_ext = PathBuf::from(LATENCY_PROBE_DIR).join(format!("{}-00ff", origin));
+        assert_eq!(probe_origin(&no_ext), None);
+    }
+}
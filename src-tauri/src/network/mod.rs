@@ -1,11 +1,45 @@
+pub mod bandwidth;
+pub mod chunking;
+pub mod coalesce;
 pub mod docs;
 pub mod endpoint;
 pub mod gossip;
+pub mod history;
+pub mod key_proof;
+pub mod latency;
+pub mod new_files;
+pub mod outbound;
+pub mod peer_retry;
+pub mod range_fetch;
+pub mod reliability;
+pub mod share_link;
+pub mod summary;
 pub mod sync;
 pub mod transfer;
+pub mod transfer_queue;
+pub mod webhook;
 
-pub use docs::DocsManager;
-pub use endpoint::{ConnectionInfo, P2PEndpoint};
-pub use gossip::{AclChecker, EventBroadcaster};
-pub use sync::{SyncDiagnostics, SyncEngine, SyncStatus};
-pub use transfer::{FileTransferManager, TransferState};
+pub use bandwidth::BANDWIDTH_LIMIT_SETTING;
+pub use coalesce::{ChangeSummaryConfig, CHANGE_SUMMARY_SETTING};
+pub use docs::{DocMappingCheck, DocMappingStatus, DocsManager, DriveMerkleRoot, OrphanNamespace};
+pub use endpoint::{
+    ConnectionInfo, EndpointConfig, P2PEndpoint, RelayConnectivity, ENDPOINT_CONFIG_SETTING,
+};
+pub use gossip::{AclChecker, DrivePeers, EventBroadcaster, InviteChecker};
+pub use key_proof::{KeyPossession, KeyProofTracker};
+pub use latency::E2eLatencyReport;
+pub use outbound::{PendingUpload, PendingUploadDto};
+pub use peer_retry::{PeerRetryConfig, PEER_RETRY_SETTING};
+pub use reliability::PeerMetrics;
+pub use share_link::{ShareLinkCheck, ShareLinkProtocol, SHARE_LINK_ALPN};
+pub use summary::{AclLookup, SummaryProtocol, SUMMARY_ALPN};
+pub use sync::{SyncDiagnostics, SyncEngine, SyncState, SyncStatus};
+pub use transfer::{
+    BlobStoreStats, ContentMismatch, DeltaUpload, FileTransferManager, ReimportFailure,
+    ReimportReport, TransferState, DEFAULT_CHUNK_SIZE, DOWNLOAD_TEMP_DIR_SETTING, MAX_CHUNK_SIZE,
+    MIN_CHUNK_SIZE, TRANSFER_CHUNK_SIZE_SETTING,
+};
+pub use transfer_queue::{TransferLimits, TRANSFER_LIMITS_SETTING};
+pub use webhook::{
+    WebhookConfig, WebhookDispatcher, WebhookSettings, WebhookTestResult, WEBHOOK_SETTING,
+};
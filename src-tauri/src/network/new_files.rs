@@ -0,0 +1,183 @@
+//! Applies each drive's `NewFilePolicy` to files added by peers
+//!
+//! Peers announce a new file with a `FileChanged` event marked `created`.
+//! Depending on the drive's policy the content is fetched right away, left
+//! remote-only until it is opened, or offered to the user with a local
+//! `NewFileAvailable` event; `download_file` fetches it if they accept.
+
+use crate::core::{validate_path, DriveEvent, DriveId, NewFilePolicy, SharedDrive};
+use crate::crypto::NodeId;
+use crate::network::latency::probe_origin;
+use crate::network::{EventBroadcaster, FileTransferManager};
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+
+/// What to do about a file a peer just added
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NewFileAction {
+    /// Fetch the content now
+    Download,
+    /// Tell the user it is available
+    Prompt,
+    /// Leave it remote-only
+    Skip,
+}
+
+/// Decide how to handle a new `size`-byte file in `drive`
+///
+/// Archived drives aren't synced and files over the drive's size limit
+/// would be rejected on write, so neither is downloaded or offered.
+pub fn new_file_action(drive: &SharedDrive, size: u64, exists_locally: bool) -> NewFileAction {
+    let too_large = drive.max_file_size > 0 && size > drive.max_file_size;
+    if drive.is_archived() || exists_locally || too_large {
+        return NewFileAction::Skip;
+    }
+    match drive.new_file_policy {
+        NewFilePolicy::AutoDownload => NewFileAction::Download,
+        NewFilePolicy::OnDemand => NewFileAction::Skip,
+        NewFilePolicy::Prompt => NewFileAction::Prompt,
+    }
+}
+
+/// Handle files added by peers according to each drive's policy
+///
+/// `NewFileAvailable` events for `Prompt` drives are sent on `events`.
+pub fn start_new_file_responder(
+    node_id: NodeId,
+    drives: Arc<RwLock<HashMap<[u8; 32], SharedDrive>>>,
+    broadcaster: Arc<EventBroadcaster>,
+    transfer: Arc<FileTransferManager>,
+    events: broadcast::Sender<(String, DriveEvent)>,
+) -> JoinHandle<()> {
+    let mut rx = broadcaster.subscribe_frontend();
+    tokio::spawn(async move {
+        loop {
+            let dto = match rx.recv().await {
+                Ok(dto) => dto,
+                Err(broadcast::error::RecvError::Lagged(count)) => {
+                    tracing::warn!("New file responder lagged, missed {} events", count);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if dto.event_type != "FileChanged" {
+                continue;
+            }
+            let Ok(DriveEvent::FileChanged {
+                path,
+                hash,
+                size,
+                modified_by,
+                created: true,
+                ..
+            }) = serde_json::from_value::<DriveEvent>(dto.payload)
+            else {
+                continue;
+            };
+            // Latency probes are fetched by their own responder
+            if modified_by == node_id || probe_origin(&path).is_some() {
+                continue;
+            }
+            let Ok(drive_id) = DriveId::from_hex(&dto.drive_id) else {
+                continue;
+            };
+            let (root, action) = {
+                let drives = drives.read().await;
+                let Some(drive) = drives.get(drive_id.as_bytes()) else {
+                    continue;
+                };
+                let Ok(absolute) = validate_path(&drive.local_path, &path.to_string_lossy())
+                else {
+                    continue;
+                };
+                let action = new_file_action(drive, size, absolute.exists());
+                (drive.local_path.clone(), action)
+            };
+
+            match action {
+                NewFileAction::Skip => {}
+                NewFileAction::Prompt => {
+                    let event = DriveEvent::NewFileAvailable {
+                        path,
+                        hash,
+                        size,
+                        added_by: modified_by,
+                        timestamp: Utc::now(),
+                    };
+                    let _ = events.send((dto.drive_id, event));
+                }
+                NewFileAction::Download => {
+                    let transfer = transfer.clone();
+                    tokio::spawn(async move {
+                        let download = download_new_file(
+                            &transfer,
+                            drive_id,
+                            &root,
+                            &path,
+                            &hash,
+                            modified_by,
+                        );
+                        if let Err(e) = download.await {
+                            tracing::warn!(
+                                drive_id = %drive_id,
+                                path = %path.display(),
+                                "Failed to download new file: {}",
+                                e
+                            );
+                        }
+                    });
+                }
+            }
+        }
+    })
+}
+
+async fn download_new_file(
+    transfer: &FileTransferManager,
+    drive_id: DriveId,
+    root: &Path,
+    relative: &Path,
+    hash: &str,
+    added_by: NodeId,
+) -> Result<()> {
+    let absolute = validate_path(root, &relative.to_string_lossy())?;
+    let hash = hash.parse::<iroh_blobs::Hash>()?;
+    let provider = iroh::NodeId::from_bytes(added_by.as_bytes())?;
+    transfer
+        .download_from_peer(&drive_id, hash, &[provider], &absolute, relative)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Identity;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_new_file_action() {
+        let owner = Identity::generate().node_id();
+        let mut drive = SharedDrive::new("Test".to_string(), PathBuf::from("/tmp/x"), owner);
+
+        // Default matches the old behavior of fetching new files
+        assert_eq!(new_file_action(&drive, 10, false), NewFileAction::Download);
+        assert_eq!(new_file_action(&drive, 10, true), NewFileAction::Skip);
+
+        drive.new_file_policy = NewFilePolicy::OnDemand;
+        assert_eq!(new_file_action(&drive, 10, false), NewFileAction::Skip);
+
+        drive.new_file_policy = NewFilePolicy::Prompt;
+        assert_eq!(new_file_action(&drive, 10, false), NewFileAction::Prompt);
+        drive.max_file_size = 5;
+        assert_eq!(new_file_action(&drive, 10, false), NewFileAction::Skip);
+
+        drive.max_file_size = 0;
+        drive.archived_at = Some(Utc::now());
+        assert_eq!(new_file_action(&drive, 10, false), NewFileAction::Skip);
+    }
+}
@@ -0,0 +1,329 @@
+//! Deferred outbound changes
+//!
+//! A local change is announced to peers over gossip. When that can't happen
+//! (sync paused, no peers connected, or the broadcast failed) the event is
+//! queued here instead of being dropped, persisted so it survives a restart,
+//! and re-sent once peers are reachable again. Each entry is its own row in
+//! the database, so queueing or sending one change doesn't rewrite the rest.
+
+use crate::core::{DriveEvent, DriveId};
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Oldest entries are dropped beyond this many
+const MAX_PENDING_UPLOADS: usize = 10_000;
+
+/// Why a change was queued rather than sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeferReason {
+    /// Network sync was paused (e.g. on battery)
+    SyncPaused,
+    /// No peers were connected for the drive
+    NoPeers,
+    /// Sending to peers failed
+    BroadcastFailed,
+}
+
+/// A queued outbound change
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PendingUpload {
+    pub id: String,
+    pub drive_id: DriveId,
+    /// The change to announce (`FileChanged` or `FileDeleted`)
+    pub event: DriveEvent,
+    /// When the path was first queued; later changes to it keep this time
+    pub queued_at: DateTime<Utc>,
+    pub reason: DeferReason,
+    /// Failed send attempts since it was queued
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+impl PendingUpload {
+    /// Drive-relative path the change applies to
+    pub fn path(&self) -> Option<&std::path::Path> {
+        match &self.event {
+            DriveEvent::FileChanged { path, .. } | DriveEvent::FileDeleted { path, .. } => {
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    /// True if both entries carry the same change (not just the same path)
+    fn event_matches(&self, other: &PendingUpload) -> bool {
+        match (&self.event, &other.event) {
+            (
+                DriveEvent::FileChanged { hash: a, timestamp: ta, .. },
+                DriveEvent::FileChanged { hash: b, timestamp: tb, .. },
+            ) => a == b && ta == tb,
+            (
+                DriveEvent::FileDeleted { timestamp: a, .. },
+                DriveEvent::FileDeleted { timestamp: b, .. },
+            ) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Queued change for the frontend
+#[derive(Clone, Debug, Serialize)]
+pub struct PendingUploadDto {
+    pub id: String,
+    pub drive_id: String,
+    pub path: String,
+    /// Size of the new content (0 for deletions)
+    pub size: u64,
+    pub deleted: bool,
+    pub queued_at: String,
+    pub reason: DeferReason,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+impl From<&PendingUpload> for PendingUploadDto {
+    fn from(pending: &PendingUpload) -> Self {
+        let (size, deleted) = match &pending.event {
+            DriveEvent::FileChanged { size, .. } => (*size, false),
+            _ => (0, true),
+        };
+        Self {
+            id: pending.id.clone(),
+            drive_id: pending.drive_id.to_hex(),
+            path: pending
+                .path()
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default(),
+            size,
+            deleted,
+            queued_at: pending.queued_at.to_rfc3339(),
+            reason: pending.reason,
+            attempts: pending.attempts,
+            last_error: pending.last_error.clone(),
+        }
+    }
+}
+
+/// Persistent queue of changes waiting to be announced to peers
+pub struct OutboundQueue {
+    db: Arc<Database>,
+    entries: RwLock<Vec<PendingUpload>>,
+}
+
+impl OutboundQueue {
+    /// Load the queue saved by a previous run
+    pub fn load(db: Arc<Database>) -> Self {
+        let rows = db.list_pending_uploads().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load outbound queue: {}", e);
+            Vec::new()
+        });
+        let mut entries: Vec<PendingUpload> = rows
+            .into_iter()
+            .filter_map(|(id, bytes)| match serde_json::from_slice(&bytes) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    tracing::warn!("Discarding unreadable outbound change {}: {}", id, e);
+                    None
+                }
+            })
+            .collect();
+        entries.sort_by(|a, b| a.queued_at.cmp(&b.queued_at));
+        if !entries.is_empty() {
+            tracing::info!("Loaded {} pending outbound change(s)", entries.len());
+        }
+
+        Self {
+            db,
+            entries: RwLock::new(entries),
+        }
+    }
+
+    /// Queue a change, replacing any queued change to the same path
+    ///
+    /// Only file changes and deletions are queued; other events describe
+    /// momentary state that is stale by the time peers return.
+    pub async fn push(&self, drive_id: DriveId, event: DriveEvent, reason: DeferReason) -> bool {
+        if !matches!(
+            event,
+            DriveEvent::FileChanged { .. } | DriveEvent::FileDeleted { .. }
+        ) {
+            return false;
+        }
+
+        let mut entries = self.entries.write().await;
+        let mut pending = PendingUpload {
+            id: format!("out_{}", hex::encode(rand::random::<[u8; 8]>())),
+            drive_id,
+            event,
+            queued_at: Utc::now(),
+            reason,
+            attempts: 0,
+            last_error: None,
+        };
+        if let Some(i) = entries
+            .iter()
+            .position(|e| e.drive_id == drive_id && e.path() == pending.path())
+        {
+            let previous = entries.remove(i);
+            pending.id = previous.id;
+            pending.queued_at = previous.queued_at;
+        }
+        self.save(&pending);
+        entries.push(pending);
+
+        if entries.len() > MAX_PENDING_UPLOADS {
+            let excess = entries.len() - MAX_PENDING_UPLOADS;
+            let dropped: Vec<String> = entries.drain(..excess).map(|e| e.id).collect();
+            self.delete(&dropped);
+            tracing::warn!("Outbound queue full, dropped {} oldest change(s)", excess);
+        }
+        true
+    }
+
+    /// Queued changes, oldest first, optionally for one drive
+    pub async fn list(&self, drive_id: Option<&DriveId>) -> Vec<PendingUpload> {
+        self.entries
+            .read()
+            .await
+            .iter()
+            .filter(|e| match drive_id {
+                Some(id) => e.drive_id == *id,
+                None => true,
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Drop a queued change; returns false if it was not queued
+    pub async fn remove(&self, id: &str) -> bool {
+        let mut entries = self.entries.write().await;
+        let Some(i) = entries.iter().position(|e| e.id == id) else {
+            return false;
+        };
+        let removed = entries.remove(i);
+        self.delete(&[removed.id]);
+        true
+    }
+
+    /// Record the outcome of a send attempt
+    ///
+    /// A successful send removes the entry unless the path was re-queued
+    /// with a newer change in the meantime.
+    pub async fn record_attempt(&self, sent: &PendingUpload, error: Option<String>) {
+        let mut entries = self.entries.write().await;
+        let Some(entry) = entries.iter_mut().find(|e| e.id == sent.id) else {
+            return;
+        };
+        match error {
+            None if entry.event_matches(sent) => {
+                entries.retain(|e| e.id != sent.id);
+                self.delete(std::slice::from_ref(&sent.id));
+            }
+            None => {}
+            Some(error) => {
+                entry.attempts += 1;
+                entry.last_error = Some(error);
+                self.save(entry);
+            }
+        }
+    }
+
+    /// Number of queued changes
+    pub async fn count(&self) -> usize {
+        self.entries.read().await.len()
+    }
+
+    fn save(&self, entry: &PendingUpload) {
+        let result = serde_json::to_vec(entry)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| self.db.save_pending_upload(&entry.id, &bytes));
+        if let Err(e) = result {
+            tracing::warn!("Failed to persist outbound change {}: {}", entry.id, e);
+        }
+    }
+
+    fn delete(&self, ids: &[String]) {
+        if let Err(e) = self.db.delete_pending_uploads(ids) {
+            tracing::warn!("Failed to remove outbound change(s): {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Identity;
+    use tempfile::tempdir;
+
+    fn changed(path: &str, hash: &str) -> DriveEvent {
+        DriveEvent::FileChanged {
+            path: path.into(),
+            hash: hash.to_string(),
+            size: 3,
+            modified_by: Identity::generate().node_id(),
+            timestamp: Utc::now(),
+            created: false,
+            content_type: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_push_coalesces_and_persists() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(dir.path().join("test.redb")).unwrap());
+        let drive = DriveId([1u8; 32]);
+
+        let queue = OutboundQueue::load(db.clone());
+        assert!(queue.push(drive, changed("a.txt", "h1"), DeferReason::NoPeers).await);
+        let first = queue.list(None).await[0].clone();
+        assert!(queue.push(drive, changed("a.txt", "h2"), DeferReason::SyncPaused).await);
+        assert!(queue.push(drive, changed("b.txt", "h3"), DeferReason::NoPeers).await);
+
+        // Presence-style events are never queued
+        let editing = DriveEvent::FileEditStarted {
+            path: "a.txt".into(),
+            editor: Identity::generate().node_id(),
+        };
+        assert!(!queue.push(drive, editing, DeferReason::NoPeers).await);
+
+        let entries = queue.list(Some(&drive)).await;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, first.id);
+        assert_eq!(entries[0].queued_at, first.queued_at);
+        assert_eq!(entries[0].reason, DeferReason::SyncPaused);
+
+        // Survives a reload
+        let reloaded = OutboundQueue::load(db);
+        assert_eq!(reloaded.count().await, 2);
+        assert!(reloaded.remove(&first.id).await);
+        assert!(!reloaded.remove(&first.id).await);
+        assert!(reloaded.list(Some(&DriveId([2u8; 32]))).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_record_attempt_keeps_newer_change() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(dir.path().join("test.redb")).unwrap());
+        let drive = DriveId([1u8; 32]);
+        let queue = OutboundQueue::load(db);
+
+        queue.push(drive, changed("a.txt", "h1"), DeferReason::NoPeers).await;
+        let sending = queue.list(None).await[0].clone();
+
+        queue.record_attempt(&sending, Some("timeout".to_string())).await;
+        assert_eq!(queue.list(None).await[0].attempts, 1);
+
+        // A newer change arrives while the old one is being sent
+        queue.push(drive, changed("a.txt", "h2"), DeferReason::NoPeers).await;
+        queue.record_attempt(&sending, None).await;
+        assert_eq!(queue.count().await, 1);
+
+        let latest = queue.list(None).await[0].clone();
+        queue.record_attempt(&latest, None).await;
+        assert_eq!(queue.count().await, 0);
+    }
+}
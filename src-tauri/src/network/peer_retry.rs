@@ -0,0 +1,96 @@
+//! Reconnect attempts for joined drives that have no peers yet
+//!
+//! Joining a drive imports its doc from the invite ticket, which succeeds
+//! even when every peer in the ticket is offline. Until one of them comes
+//! back nothing arrives and nothing re-dials them, so the sync engine keeps
+//! re-attempting docs sync and the gossip join with the ticket's peers,
+//! backing off between attempts, until metadata arrives or the user cancels.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Setting holding the retry configuration
+pub const PEER_RETRY_SETTING: &str = "peer_retry_config";
+
+/// Bounds for the delays between attempts
+const MIN_RETRY_DELAY_SECS: u64 = 1;
+const MAX_RETRY_DELAY_SECS: u64 = 24 * 60 * 60;
+
+/// How a drive without peers is retried
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerRetryConfig {
+    /// Retry joined drives until a peer responds
+    pub enabled: bool,
+    /// Delay before the first attempt; doubled after each one
+    pub initial_delay_secs: u64,
+    /// Upper bound for the delay between attempts
+    pub max_delay_secs: u64,
+}
+
+impl Default for PeerRetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            initial_delay_secs: 5,
+            max_delay_secs: 300,
+        }
+    }
+}
+
+impl PeerRetryConfig {
+    /// Clamp the delays to supported bounds, keeping max >= initial
+    pub fn normalized(mut self) -> Self {
+        self.initial_delay_secs = self
+            .initial_delay_secs
+            .clamp(MIN_RETRY_DELAY_SECS, MAX_RETRY_DELAY_SECS);
+        self.max_delay_secs = self
+            .max_delay_secs
+            .clamp(self.initial_delay_secs, MAX_RETRY_DELAY_SECS);
+        self
+    }
+
+    /// Delay to wait after `attempts` attempts have been made
+    pub fn delay_for(&self, attempts: u32) -> Duration {
+        let secs = self
+            .initial_delay_secs
+            .saturating_mul(1u64.checked_shl(attempts).unwrap_or(u64::MAX))
+            .min(self.max_delay_secs);
+        Duration::from_secs(secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_backs_off_to_max() {
+        let config = PeerRetryConfig::default();
+        assert_eq!(config.delay_for(0), Duration::from_secs(5));
+        assert_eq!(config.delay_for(1), Duration::from_secs(10));
+        assert_eq!(config.delay_for(3), Duration::from_secs(40));
+        assert_eq!(config.delay_for(7), Duration::from_secs(300));
+        assert_eq!(config.delay_for(200), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_normalized_keeps_max_above_initial() {
+        let config = PeerRetryConfig {
+            enabled: true,
+            initial_delay_secs: 0,
+            max_delay_secs: 0,
+        }
+        .normalized();
+        assert_eq!(config.initial_delay_secs, 1);
+        assert_eq!(config.max_delay_secs, 1);
+
+        let config = PeerRetryConfig {
+            enabled: true,
+            initial_delay_secs: 60,
+            max_delay_secs: 10,
+        }
+        .normalized();
+        assert_eq!(config.max_delay_secs, 60);
+    }
+}
@@ -0,0 +1,150 @@
+//! Byte-range blob fetches for downloading from several peers at once
+//!
+//! A blob is split into ranges aligned to BAO chunks so each range can be
+//! requested and verified on its own. Ranges are written straight into the
+//! download's temp file at their offset.
+
+use anyhow::{Context, Result};
+use bao_tree::io::BaoContentItem;
+use bao_tree::{ChunkNum, ChunkRanges};
+use iroh::endpoint::Connection;
+use iroh::{Endpoint, NodeAddr, NodeId};
+use iroh_blobs::get::fsm::{self, BlobContentNext, ConnectedNext, EndBlobNext};
+use iroh_blobs::protocol::{GetRequest, RangeSpecSeq};
+use iroh_blobs::Hash;
+use std::future::Future;
+use std::io::SeekFrom;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+/// Size of a BAO chunk, the unit ranges are requested in
+const BAO_CHUNK_SIZE: u64 = 1024;
+
+/// Chunks per range (4MB)
+pub const RANGE_CHUNKS: u64 = 4 * 1024;
+
+/// How long a peer may send nothing before its range is given to another
+pub const RANGE_STALL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Split a blob of `size` bytes into chunk ranges of `chunks_per_range`
+///
+/// An empty blob still gets one range, so its size is verified.
+pub fn plan_ranges(size: u64, chunks_per_range: u64) -> Vec<Range<u64>> {
+    let chunks = size.div_ceil(BAO_CHUNK_SIZE).max(1);
+    let step = chunks_per_range.max(1);
+    (0..chunks)
+        .step_by(step as usize)
+        .map(|start| start..(start + step).min(chunks))
+        .collect()
+}
+
+/// Open a blobs protocol connection to `peer`
+pub async fn connect(endpoint: &Endpoint, peer: NodeId) -> Result<Connection> {
+    endpoint
+        .connect(NodeAddr::new(peer), iroh_blobs::protocol::ALPN)
+        .await
+        .with_context(|| format!("Failed to connect to {}", peer.fmt_short()))
+}
+
+/// Ask a peer for the verified size of a blob
+pub async fn fetch_size(connection: &Connection, hash: &Hash) -> Result<u64> {
+    let request = iroh_blobs::get::request::get_verified_size(connection, hash);
+    let (size, _stats) = stalled(request).await??;
+    Ok(size)
+}
+
+/// Fetch one range of a blob into the file at `path`
+///
+/// `received` grows as data arrives. If the fetch fails, what this range
+/// added is taken off again so the range can be fetched from another peer.
+pub async fn fetch_range(
+    connection: Connection,
+    hash: Hash,
+    chunks: Range<u64>,
+    path: PathBuf,
+    received: Arc<AtomicU64>,
+) -> Result<()> {
+    let mut written = 0u64;
+    let result = write_range(connection, hash, chunks, &path, &received, &mut written).await;
+    if result.is_err() {
+        received.fetch_sub(written, Ordering::Relaxed);
+    }
+    result
+}
+
+async fn write_range(
+    connection: Connection,
+    hash: Hash,
+    chunks: Range<u64>,
+    path: &Path,
+    received: &AtomicU64,
+    written: &mut u64,
+) -> Result<()> {
+    let ranges = ChunkRanges::from(ChunkNum(chunks.start)..ChunkNum(chunks.end));
+    let request = GetRequest::new(hash, RangeSpecSeq::from_ranges([ranges]));
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .context("Failed to open download file")?;
+
+    let connected = stalled(fsm::start(connection, request).next()).await??;
+    let ConnectedNext::StartRoot(start) = stalled(connected.next()).await?? else {
+        anyhow::bail!("Peer did not send blob {}", hash.to_hex());
+    };
+    let (mut content, _size) = stalled(start.next().next()).await??;
+
+    let end = loop {
+        match stalled(content.next()).await? {
+            BlobContentNext::More((next, item)) => {
+                if let BaoContentItem::Leaf(leaf) = item? {
+                    file.seek(SeekFrom::Start(leaf.offset)).await?;
+                    file.write_all(&leaf.data).await?;
+                    *written += leaf.data.len() as u64;
+                    received.fetch_add(leaf.data.len() as u64, Ordering::Relaxed);
+                }
+                content = next;
+            }
+            BlobContentNext::Done(end) => break end,
+        }
+    };
+    file.flush().await?;
+
+    if let EndBlobNext::Closing(closing) = end.next() {
+        stalled(closing.next()).await??;
+    }
+    Ok(())
+}
+
+/// Fail if `future` doesn't finish within `RANGE_STALL_TIMEOUT`
+async fn stalled<F: Future>(future: F) -> Result<F::Output> {
+    tokio::time::timeout(RANGE_STALL_TIMEOUT, future)
+        .await
+        .context("Peer stalled")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_ranges_covers_blob() {
+        let size = 10 * 1024 * 1024 + 5;
+        let ranges = plan_ranges(size, RANGE_CHUNKS);
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[0], 0..4096);
+        assert_eq!(ranges[1], 4096..8192);
+        assert_eq!(ranges[2], 8192..10241);
+    }
+
+    #[test]
+    fn test_plan_ranges_small_and_empty() {
+        assert_eq!(plan_ranges(0, RANGE_CHUNKS), vec![0..1]);
+        assert_eq!(plan_ranges(1, RANGE_CHUNKS), vec![0..1]);
+        assert_eq!(plan_ranges(4096, 2), vec![0..2, 2..4]);
+    }
+}
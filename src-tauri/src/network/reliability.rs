@@ -0,0 +1,300 @@
+//! Per-peer download reliability
+//!
+//! Every blob fetch from a peer is recorded as a success (with how long it
+//! took) or a failure. The counts decay over time, so a peer that was flaky
+//! an afternoon ago but has recovered isn't penalized forever, and the
+//! resulting score is used to try the most reliable provider first. Records
+//! are persisted so scores survive a restart.
+
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Settings key for the persisted records
+pub const PEER_RELIABILITY_SETTING: &str = "peer_reliability";
+
+/// Time for a recorded success or failure to lose half its weight
+const SCORE_HALF_LIFE: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Download time that costs a peer a quarter of its score
+///
+/// Latency can at most halve a score, so a slow peer that delivers still
+/// ranks above one that fails.
+const LATENCY_REFERENCE_MS: f64 = 2000.0;
+
+/// Weight of the newest sample in a peer's latency average
+const LATENCY_SMOOTHING: f64 = 0.3;
+
+/// Least recently updated peers are forgotten beyond this many
+const MAX_TRACKED_PEERS: usize = 1024;
+
+/// Download history of one peer
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PeerRecord {
+    /// Hex-encoded iroh node ID
+    peer: String,
+    /// Decayed count of successful downloads, as of `updated_at`
+    successes: f64,
+    /// Decayed count of failed downloads, as of `updated_at`
+    failures: f64,
+    /// Smoothed time successful downloads took
+    latency_ms: Option<f64>,
+    last_success_at: Option<DateTime<Utc>>,
+    last_failure_at: Option<DateTime<Utc>>,
+    updated_at: DateTime<Utc>,
+}
+
+impl PeerRecord {
+    fn new(peer: String, now: DateTime<Utc>) -> Self {
+        Self {
+            peer,
+            successes: 0.0,
+            failures: 0.0,
+            latency_ms: None,
+            last_success_at: None,
+            last_failure_at: None,
+            updated_at: now,
+        }
+    }
+
+    /// Decay the counts to `now`
+    fn decay(&mut self, now: DateTime<Utc>) {
+        let factor = decay_factor(self.updated_at, now);
+        self.successes *= factor;
+        self.failures *= factor;
+        self.updated_at = now.max(self.updated_at);
+    }
+
+    /// Score in (0, 1]: smoothed success rate scaled down by latency
+    ///
+    /// A peer with no history scores 0.5, so one proven reliable is tried
+    /// before an unknown one, and an unknown one before a flaky one.
+    fn score(&self, now: DateTime<Utc>) -> f64 {
+        let factor = decay_factor(self.updated_at, now);
+        let successes = self.successes * factor;
+        let failures = self.failures * factor;
+        let success_rate = (successes + 1.0) / (successes + failures + 2.0);
+        let latency_factor = match self.latency_ms {
+            Some(ms) => 0.5 + 0.5 * LATENCY_REFERENCE_MS / (LATENCY_REFERENCE_MS + ms.max(0.0)),
+            None => 1.0,
+        };
+        success_rate * latency_factor
+    }
+}
+
+/// How much weight remains of samples recorded at `since`
+fn decay_factor(since: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let elapsed = (now - since).num_milliseconds().max(0) as f64 / 1000.0;
+    0.5f64.powf(elapsed / SCORE_HALF_LIFE.as_secs_f64())
+}
+
+/// A peer's reliability for the frontend
+#[derive(Clone, Debug, Serialize)]
+pub struct PeerMetrics {
+    /// Hex-encoded iroh node ID
+    pub peer_id: String,
+    /// 0-1; higher is tried first
+    pub score: f64,
+    /// Recent successful downloads, decayed (older ones count less)
+    pub successes: f64,
+    /// Recent failed downloads, decayed
+    pub failures: f64,
+    /// Smoothed download time in milliseconds
+    pub latency_ms: Option<f64>,
+    pub last_success_at: Option<String>,
+    pub last_failure_at: Option<String>,
+}
+
+/// Persistent reliability scores of the peers we download from
+pub struct PeerReliability {
+    db: Arc<Database>,
+    peers: RwLock<HashMap<String, PeerRecord>>,
+}
+
+impl PeerReliability {
+    /// Load the records saved by a previous run
+    pub fn load(db: Arc<Database>) -> Self {
+        let records: Vec<PeerRecord> = match db.get_setting(PEER_RELIABILITY_SETTING) {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                tracing::warn!("Discarding unreadable peer reliability records: {}", e);
+                Vec::new()
+            }),
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                tracing::warn!("Failed to load peer reliability records: {}", e);
+                Vec::new()
+            }
+        };
+
+        Self {
+            db,
+            peers: RwLock::new(records.into_iter().map(|r| (r.peer.clone(), r)).collect()),
+        }
+    }
+
+    /// Record a successful download from `peer` that took `elapsed`
+    pub async fn record_success(&self, peer: iroh::NodeId, elapsed: Duration) {
+        let now = Utc::now();
+        let sample = elapsed.as_secs_f64() * 1000.0;
+        self.update(peer, now, |record| {
+            record.successes += 1.0;
+            record.last_success_at = Some(now);
+            record.latency_ms = Some(match record.latency_ms {
+                Some(avg) => avg + LATENCY_SMOOTHING * (sample - avg),
+                None => sample,
+            });
+        })
+        .await;
+    }
+
+    /// Record a failed download from `peer`
+    pub async fn record_failure(&self, peer: iroh::NodeId) {
+        let now = Utc::now();
+        self.update(peer, now, |record| {
+            record.failures += 1.0;
+            record.last_failure_at = Some(now);
+        })
+        .await;
+    }
+
+    /// Current score of `peer`
+    pub async fn score(&self, peer: &iroh::NodeId) -> f64 {
+        let now = Utc::now();
+        match self.peers.read().await.get(&peer_key(peer)) {
+            Some(record) => record.score(now),
+            None => PeerRecord::new(String::new(), now).score(now),
+        }
+    }
+
+    /// `peers` ordered best first; peers with equal scores keep their order
+    pub async fn rank(&self, peers: &[iroh::NodeId]) -> Vec<iroh::NodeId> {
+        let mut scored = Vec::with_capacity(peers.len());
+        for peer in peers {
+            scored.push((*peer, self.score(peer).await));
+        }
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().map(|(peer, _)| peer).collect()
+    }
+
+    /// Metrics for every tracked peer, best first
+    pub async fn metrics(&self) -> Vec<PeerMetrics> {
+        let now = Utc::now();
+        let mut metrics: Vec<PeerMetrics> = self
+            .peers
+            .read()
+            .await
+            .values()
+            .map(|record| {
+                let factor = decay_factor(record.updated_at, now);
+                PeerMetrics {
+                    peer_id: record.peer.clone(),
+                    score: record.score(now),
+                    successes: record.successes * factor,
+                    failures: record.failures * factor,
+                    latency_ms: record.latency_ms,
+                    last_success_at: record.last_success_at.map(|t| t.to_rfc3339()),
+                    last_failure_at: record.last_failure_at.map(|t| t.to_rfc3339()),
+                }
+            })
+            .collect();
+        metrics.sort_by(|a, b| b.score.total_cmp(&a.score));
+        metrics
+    }
+
+    async fn update(
+        &self,
+        peer: iroh::NodeId,
+        now: DateTime<Utc>,
+        apply: impl FnOnce(&mut PeerRecord),
+    ) {
+        let key = peer_key(&peer);
+        let mut peers = self.peers.write().await;
+        let record = peers
+            .entry(key.clone())
+            .or_insert_with(|| PeerRecord::new(key, now));
+        record.decay(now);
+        apply(record);
+
+        if peers.len() > MAX_TRACKED_PEERS {
+            if let Some(oldest) = peers
+                .values()
+                .min_by_key(|r| r.updated_at)
+                .map(|r| r.peer.clone())
+            {
+                peers.remove(&oldest);
+            }
+        }
+        self.persist(&peers);
+    }
+
+    fn persist(&self, peers: &HashMap<String, PeerRecord>) {
+        let records: Vec<&PeerRecord> = peers.values().collect();
+        let result = serde_json::to_vec(&records)
+            .map_err(anyhow::Error::from)
+            .and_then(|bytes| self.db.save_setting(PEER_RELIABILITY_SETTING, &bytes));
+        if let Err(e) = result {
+            tracing::warn!("Failed to persist peer reliability records: {}", e);
+        }
+    }
+}
+
+fn peer_key(peer: &iroh::NodeId) -> String {
+    hex::encode(peer.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn peer(seed: u8) -> iroh::NodeId {
+        iroh::SecretKey::from_bytes(&[seed; 32]).public()
+    }
+
+    #[test]
+    fn test_score_decays() {
+        let now = Utc::now();
+        let mut record = PeerRecord::new("peer".to_string(), now);
+        let unknown = record.score(now);
+        assert!((unknown - 0.5).abs() < f64::EPSILON);
+
+        record.failures = 10.0;
+        assert!(record.score(now) < 0.1);
+
+        // After many half-lives the failures have all but faded
+        let later = now + chrono::Duration::days(7);
+        assert!((record.score(later) - unknown).abs() < 0.01);
+        record.decay(later);
+        assert!(record.failures < 0.01);
+        assert_eq!(record.updated_at, later);
+    }
+
+    #[tokio::test]
+    async fn test_rank_and_persist() {
+        let dir = tempdir().unwrap();
+        let db = Arc::new(Database::open(dir.path().join("test.redb")).unwrap());
+        let reliability = PeerReliability::load(db.clone());
+        let (flaky, unknown, fast, slow) = (peer(1), peer(2), peer(3), peer(4));
+
+        for _ in 0..3 {
+            reliability.record_failure(flaky).await;
+            reliability.record_success(fast, Duration::from_millis(100)).await;
+            reliability.record_success(slow, Duration::from_secs(20)).await;
+        }
+
+        let ranked = reliability.rank(&[flaky, slow, unknown, fast]).await;
+        assert_eq!(ranked, vec![fast, unknown, slow, flaky]);
+
+        // Scores survive a reload
+        let reloaded = PeerReliability::load(db);
+        assert_eq!(reloaded.rank(&[flaky, fast]).await, vec![fast, flaky]);
+        let metrics = reloaded.metrics().await;
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(metrics[0].peer_id, peer_key(&fast));
+        assert!(metrics[0].latency_ms.is_some());
+    }
+}
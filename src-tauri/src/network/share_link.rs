@@ -0,0 +1,246 @@
+//! Serving single-file share links (see `crypto::share_link`)
+//!
+//! Shared content is only handed out by the device that issued the link,
+//! over a connection of its own: the recipient sends the token and the
+//! issuer checks it before streaming the content. Expiry, revocation and
+//! the issuer's own access to the file are therefore decided where the
+//! link was created, not by whoever holds the token. Each peer's requests
+//! are rate limited.
+
+use crate::core::rate_limit::{RateLimitOperation, RateLimiter};
+use crate::crypto::{FileShareToken, NodeId};
+use crate::network::summary::{read_frame, write_frame};
+use crate::network::FileTransferManager;
+use anyhow::{Context, Result};
+use iroh::endpoint::Connection;
+use iroh::protocol::ProtocolHandler;
+use iroh::Endpoint;
+use iroh_blobs::Hash;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// ALPN of the share link protocol
+pub const SHARE_LINK_ALPN: &[u8] = b"gix/share/1";
+
+/// How long to wait for the issuer to accept or refuse a link
+const SHARE_LINK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Type alias for the share link check callback
+/// Takes a token signed by this node and returns why it may not be served
+pub type ShareLinkCheck = Arc<dyn Fn(&FileShareToken) -> Result<(), String> + Send + Sync>;
+
+/// Asks the issuer for the content of a share link
+#[derive(Debug, Serialize, Deserialize)]
+struct ShareLinkRequest {
+    /// The share link as handed to the recipient
+    token: String,
+}
+
+/// The issuer's answer to a `ShareLinkRequest`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ShareLinkReply {
+    /// The content follows as `size` raw bytes
+    Accepted { size: u64 },
+    /// The link wasn't served
+    Refused { reason: String },
+}
+
+/// Serves the content of share links this node issued
+#[derive(Clone)]
+pub struct ShareLinkProtocol {
+    node_id: NodeId,
+    transfer: Arc<FileTransferManager>,
+    check: ShareLinkCheck,
+    limiter: Arc<RateLimiter>,
+}
+
+impl ShareLinkProtocol {
+    pub fn new(node_id: NodeId, transfer: Arc<FileTransferManager>, check: ShareLinkCheck) -> Self {
+        Self {
+            node_id,
+            transfer,
+            check,
+            limiter: Arc::new(RateLimiter::new()),
+        }
+    }
+
+    async fn answer(&self, connection: Connection) -> Result<()> {
+        let peer = NodeId(*connection.remote_node_id()?.as_bytes());
+        let (mut send, mut recv) = connection.accept_bi().await?;
+        let Some(request) = read_frame::<ShareLinkRequest>(&mut recv).await? else {
+            return Ok(());
+        };
+
+        match self.accept_link(peer, &request.token).await {
+            Ok((token, hash, manifest_hash)) => {
+                write_frame(
+                    &mut send,
+                    &ShareLinkReply::Accepted {
+                        size: token.payload.size,
+                    },
+                )
+                .await?;
+                self.transfer
+                    .stream_content(hash, manifest_hash, &mut send)
+                    .await?;
+                tracing::info!(
+                    token_id = %token.token_id(),
+                    peer = %peer,
+                    "Served file share link"
+                );
+            }
+            Err(reason) => {
+                tracing::debug!("Not serving share link to {}: {}", peer, reason);
+                write_frame(&mut send, &ShareLinkReply::Refused { reason }).await?;
+            }
+        }
+        send.finish()?;
+
+        // The requester closes the connection once it has read the content
+        connection.closed().await;
+        Ok(())
+    }
+
+    /// Check a link sent by `peer`, returning it with its content and
+    /// manifest hashes if the content may be served
+    async fn accept_link(
+        &self,
+        peer: NodeId,
+        token: &str,
+    ) -> std::result::Result<(FileShareToken, Hash, Option<Hash>), String> {
+        if !self
+            .limiter
+            .check(&peer.0, RateLimitOperation::FileDownload)
+            .await
+            .is_allowed()
+        {
+            return Err("too many share link requests".to_string());
+        }
+
+        let token =
+            FileShareToken::from_string(token).map_err(|_| "invalid share link".to_string())?;
+        token
+            .verify_issuer_signature()
+            .map_err(|_| "invalid share link signature".to_string())?;
+        if token.payload.issuer != self.node_id.to_hex() {
+            return Err("share link was issued by another device".to_string());
+        }
+        if token.is_expired() {
+            return Err("share link has expired".to_string());
+        }
+        (self.check)(&token)?;
+
+        let hash = token
+            .payload
+            .hash
+            .parse::<Hash>()
+            .map_err(|_| "invalid content hash".to_string())?;
+        let manifest_hash = token
+            .payload
+            .manifest
+            .as_deref()
+            .map(str::parse::<Hash>)
+            .transpose()
+            .map_err(|_| "invalid manifest hash".to_string())?;
+        if self.transfer.content_size(hash, manifest_hash).await != Some(token.payload.size) {
+            return Err("shared content is no longer available".to_string());
+        }
+        Ok((token, hash, manifest_hash))
+    }
+}
+
+impl std::fmt::Debug for ShareLinkProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShareLinkProtocol").finish_non_exhaustive()
+    }
+}
+
+impl ProtocolHandler for ShareLinkProtocol {
+    fn accept(
+        &self,
+        connection: Connection,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'static>> {
+        let this = self.clone();
+        Box::pin(async move { this.answer(connection).await })
+    }
+}
+
+/// Fetch a share link's content from its issuer and save it to `dest`
+///
+/// The issuer refuses links that expired, were revoked or whose file it can
+/// no longer read. The content is checked against the signed hash before
+/// it is moved to `dest`.
+pub async fn fetch_share_link(
+    endpoint: &Endpoint,
+    transfer: &FileTransferManager,
+    token: &FileShareToken,
+    token_string: &str,
+    dest: &Path,
+) -> Result<()> {
+    let hash = token.payload.hash.parse::<Hash>()?;
+    let issuer_bytes: [u8; 32] = hex::decode(&token.payload.issuer)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .context("invalid issuer in share link")?;
+    let issuer = iroh::NodeId::from_bytes(&issuer_bytes)?;
+    let request = ShareLinkRequest {
+        token: token_string.to_string(),
+    };
+
+    let handshake = async {
+        let connection = endpoint.connect(issuer, SHARE_LINK_ALPN).await?;
+        let (mut send, mut recv) = connection.open_bi().await?;
+        write_frame(&mut send, &request).await?;
+        send.finish()?;
+        let reply = read_frame::<ShareLinkReply>(&mut recv)
+            .await?
+            .context("issuer closed the connection without answering")?;
+        anyhow::Ok((connection, recv, reply))
+    };
+    let (connection, mut recv, reply) = tokio::time::timeout(SHARE_LINK_TIMEOUT, handshake)
+        .await
+        .context("issuer did not answer in time")??;
+
+    let result = match reply {
+        ShareLinkReply::Accepted { size } if size == token.payload.size => {
+            transfer.save_verified(&mut recv, size, hash, dest).await
+        }
+        ShareLinkReply::Accepted { size } => Err(anyhow::anyhow!(
+            "issuer offered {} bytes instead of {}",
+            size,
+            token.payload.size
+        )),
+        ShareLinkReply::Refused { reason } => Err(anyhow::anyhow!("issuer refused: {}", reason)),
+    };
+    connection.close(0u32.into(), b"done");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_link_reply_round_trip() {
+        let json = serde_json::to_string(&ShareLinkReply::Accepted { size: 42 }).unwrap();
+        assert!(json.contains("\"type\":\"accepted\""));
+        match serde_json::from_str::<ShareLinkReply>(&json).unwrap() {
+            ShareLinkReply::Accepted { size } => assert_eq!(size, 42),
+            other => panic!("unexpected reply: {:?}", other),
+        }
+
+        let json = serde_json::to_string(&ShareLinkReply::Refused {
+            reason: "share link has been revoked".to_string(),
+        })
+        .unwrap();
+        assert!(matches!(
+            serde_json::from_str::<ShareLinkReply>(&json).unwrap(),
+            ShareLinkReply::Refused { .. }
+        ));
+    }
+}
@@ -0,0 +1,351 @@
+//! Direct exchange of file summaries (see `core::gap`)
+//!
+//! A peer asks for our summary, or for the entries of some of its buckets,
+//! over a connection of its own rather than the drive topic, so our file
+//! list only goes to the requester. Requests are refused unless the
+//! requester may read the drive and, on encrypted drives, proved it holds
+//! the key (see `network::key_proof`). Files under paths the requester may
+//! not read are left out, and each peer's requests are rate limited since
+//! answering one walks the whole drive index.
+
+use crate::core::gap::{chunk_entries, MetadataSummary, SummaryEntry};
+use crate::core::rate_limit::{RateLimitOperation, RateLimiter};
+use crate::core::{DriveId, SharedDrive};
+use crate::crypto::{AccessControlList, NodeId, Permission};
+use crate::network::{KeyProofTracker, SyncEngine};
+use anyhow::Result;
+use iroh::endpoint::{Connection, ReadExactError, RecvStream, SendStream};
+use iroh::protocol::ProtocolHandler;
+use iroh::Endpoint;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// ALPN of the summary protocol
+pub const SUMMARY_ALPN: &[u8] = b"gix/summary/1";
+
+/// How long to wait for a peer to answer a summary request
+const SUMMARY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest message accepted from the other side
+const MAX_FRAME_BYTES: usize = 64 * 1024;
+
+/// Type alias for the ACL lookup callback
+/// Takes (drive_id, owner_node_id) and returns the drive's ACL
+pub type AclLookup = Arc<dyn Fn(&str, &str) -> AccessControlList + Send + Sync>;
+
+/// Asks a peer for its summary of a drive, or for some of its buckets
+#[derive(Debug, Serialize, Deserialize)]
+struct SummaryRequest {
+    drive_id: String,
+    /// Buckets whose entries are wanted; empty for the summary itself
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    buckets: Vec<u16>,
+}
+
+/// One message answering a `SummaryRequest`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SummaryReply {
+    /// The summary of the files we hold
+    Summary { summary: MetadataSummary },
+    /// Part of our file list
+    Entries {
+        entries: Vec<SummaryEntry>,
+        /// Buckets whose entries are complete with this message
+        buckets_done: Vec<u16>,
+    },
+    /// The request wasn't answered
+    Refused { reason: String },
+}
+
+/// Answers summary requests from peers
+#[derive(Clone)]
+pub struct SummaryProtocol {
+    engine: Arc<SyncEngine>,
+    drives: Arc<RwLock<HashMap<[u8; 32], SharedDrive>>>,
+    key_proofs: Arc<KeyProofTracker>,
+    acl_lookup: AclLookup,
+    limiter: Arc<RateLimiter>,
+}
+
+impl SummaryProtocol {
+    pub fn new(
+        engine: Arc<SyncEngine>,
+        drives: Arc<RwLock<HashMap<[u8; 32], SharedDrive>>>,
+        key_proofs: Arc<KeyProofTracker>,
+        acl_lookup: AclLookup,
+    ) -> Self {
+        Self {
+            engine,
+            drives,
+            key_proofs,
+            acl_lookup,
+            limiter: Arc::new(RateLimiter::new()),
+        }
+    }
+
+    async fn answer(&self, connection: Connection) -> Result<()> {
+        let peer = NodeId(*connection.remote_node_id()?.as_bytes());
+        let (mut send, mut recv) = connection.accept_bi().await?;
+        let Some(request) = read_frame::<SummaryRequest>(&mut recv).await? else {
+            return Ok(());
+        };
+
+        let replies = match self.replies(peer, &request).await {
+            Ok(replies) => replies,
+            Err(reason) => {
+                tracing::debug!(
+                    "Not answering summary request from {} for drive {}: {}",
+                    peer,
+                    request.drive_id,
+                    reason
+                );
+                vec![SummaryReply::Refused { reason }]
+            }
+        };
+        for reply in &replies {
+            write_frame(&mut send, reply).await?;
+        }
+        send.finish()?;
+
+        // The requester closes the connection once it has read the replies
+        connection.closed().await;
+        Ok(())
+    }
+
+    async fn replies(
+        &self,
+        peer: NodeId,
+        request: &SummaryRequest,
+    ) -> std::result::Result<Vec<SummaryReply>, String> {
+        if !self
+            .limiter
+            .check(&peer.0, RateLimitOperation::SummaryRequest)
+            .await
+            .is_allowed()
+        {
+            return Err("too many summary requests".to_string());
+        }
+
+        let drive_id =
+            DriveId::from_hex(&request.drive_id).map_err(|_| "unknown drive".to_string())?;
+        let (root, owner) = self
+            .drives
+            .read()
+            .await
+            .get(drive_id.as_bytes())
+            .map(|d| (d.local_path.clone(), d.owner.to_hex()))
+            .ok_or_else(|| "unknown drive".to_string())?;
+        if self.engine.is_drained(&drive_id).await {
+            return Err("drive is being removed".to_string());
+        }
+        if !self.key_proofs.may_serve(&drive_id, &peer).await {
+            return Err("drive key not proven".to_string());
+        }
+
+        let acl = (self.acl_lookup)(&request.drive_id, &owner);
+        let peer_hex = peer.to_hex();
+        if !acl.check_permission(&peer_hex, "/", Permission::Read) {
+            return Err("no read access".to_string());
+        }
+        let files: Vec<(String, String)> = self
+            .engine
+            .held_files(&drive_id, &root)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|(path, _)| acl.check_permission(&peer_hex, path, Permission::Read))
+            .collect();
+
+        if request.buckets.is_empty() {
+            let summary = MetadataSummary::from_files(&files);
+            return Ok(vec![SummaryReply::Summary { summary }]);
+        }
+        Ok(chunk_entries(&files, &request.buckets)
+            .into_iter()
+            .map(|(entries, buckets_done)| SummaryReply::Entries {
+                entries,
+                buckets_done,
+            })
+            .collect())
+    }
+}
+
+impl std::fmt::Debug for SummaryProtocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SummaryProtocol").finish_non_exhaustive()
+    }
+}
+
+impl ProtocolHandler for SummaryProtocol {
+    fn accept(
+        &self,
+        connection: Connection,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'static>> {
+        let this = self.clone();
+        Box::pin(async move { this.answer(connection).await })
+    }
+}
+
+/// Ask `peer` for its summary of a drive's files
+///
+/// Returns None if the peer can't be reached, refuses or doesn't answer
+/// within `SUMMARY_TIMEOUT`.
+pub async fn request_summary(
+    endpoint: &Endpoint,
+    drive_id: &DriveId,
+    peer: NodeId,
+) -> Option<MetadataSummary> {
+    let mut summary = None;
+    exchange_summary(endpoint, drive_id, peer, Vec::new(), |reply| {
+        if let SummaryReply::Summary { summary: received } = reply {
+            summary = Some(received);
+            return true;
+        }
+        false
+    })
+    .await;
+    summary
+}
+
+/// Ask `peer` for the entries of some buckets of its summary
+///
+/// Returns the peer's paths with their short hashes and the buckets it
+/// listed in full before `SUMMARY_TIMEOUT` ran out.
+pub async fn request_summary_entries(
+    endpoint: &Endpoint,
+    drive_id: &DriveId,
+    peer: NodeId,
+    buckets: Vec<u16>,
+) -> (HashMap<String, String>, HashSet<u16>) {
+    let wanted: HashSet<u16> = buckets.iter().copied().collect();
+    let mut paths = HashMap::new();
+    let mut resolved = HashSet::new();
+    exchange_summary(endpoint, drive_id, peer, buckets, |reply| {
+        if let SummaryReply::Entries {
+            entries,
+            buckets_done,
+        } = reply
+        {
+            paths.extend(entries.into_iter().map(|e| (e.path, e.hash)));
+            resolved.extend(buckets_done.into_iter().filter(|b| wanted.contains(b)));
+        }
+        resolved.len() == wanted.len()
+    })
+    .await;
+    (paths, resolved)
+}
+
+/// Send a `SummaryRequest` to `peer` and feed its replies to `on_reply`
+/// until it returns true, the peer is done or `SUMMARY_TIMEOUT` runs out
+async fn exchange_summary(
+    endpoint: &Endpoint,
+    drive_id: &DriveId,
+    peer: NodeId,
+    buckets: Vec<u16>,
+    mut on_reply: impl FnMut(SummaryReply) -> bool,
+) {
+    let request = SummaryRequest {
+        drive_id: drive_id.to_hex(),
+        buckets,
+    };
+    let exchange = async {
+        let peer_id = iroh::NodeId::from_bytes(&peer.0)?;
+        let connection = endpoint.connect(peer_id, SUMMARY_ALPN).await?;
+        let (mut send, mut recv) = connection.open_bi().await?;
+        write_frame(&mut send, &request).await?;
+        send.finish()?;
+
+        while let Some(reply) = read_frame::<SummaryReply>(&mut recv).await? {
+            if let SummaryReply::Refused { reason } = &reply {
+                tracing::debug!("{} refused summary request: {}", peer, reason);
+                break;
+            }
+            if on_reply(reply) {
+                break;
+            }
+        }
+        connection.close(0u32.into(), b"done");
+        anyhow::Ok(())
+    };
+
+    match tokio::time::timeout(SUMMARY_TIMEOUT, exchange).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::debug!("Summary request to {} failed: {}", peer, e),
+        Err(_) => tracing::debug!("Summary request to {} timed out", peer),
+    }
+}
+
+/// Write `value` as a length-prefixed JSON message
+pub(crate) async fn write_frame<T: Serialize>(send: &mut SendStream, value: &T) -> Result<()> {
+    let bytes = serde_json::to_vec(value)?;
+    send.write_all(&(bytes.len() as u32).to_be_bytes()).await?;
+    send.write_all(&bytes).await?;
+    Ok(())
+}
+
+/// Read the next length-prefixed JSON message, or None once the other
+/// side finished the stream
+pub(crate) async fn read_frame<T: DeserializeOwned>(recv: &mut RecvStream) -> Result<Option<T>> {
+    let mut len = [0u8; 4];
+    match recv.read_exact(&mut len).await {
+        Ok(()) => {}
+        Err(ReadExactError::FinishedEarly(0)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    anyhow::ensure!(
+        len <= MAX_FRAME_BYTES,
+        "message of {} bytes is too large",
+        len
+    );
+    let mut bytes = vec![0u8; len];
+    recv.read_exact(&mut bytes).await?;
+    Ok(Some(serde_json::from_slice(&bytes)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_reply_round_trip() {
+        let reply = SummaryReply::Entries {
+            entries: vec![SummaryEntry {
+                path: "docs/a.txt".to_string(),
+                hash: "0123456789abcdef".to_string(),
+            }],
+            buckets_done: vec![3],
+        };
+        let json = serde_json::to_string(&reply).unwrap();
+        assert!(json.contains("\"type\":\"entries\""));
+        match serde_json::from_str::<SummaryReply>(&json).unwrap() {
+            SummaryReply::Entries {
+                entries,
+                buckets_done,
+            } => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(buckets_done, vec![3]);
+            }
+            other => panic!("unexpected reply: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_summary_request_omits_empty_buckets() {
+        let request = SummaryRequest {
+            drive_id: "ab".repeat(32),
+            buckets: Vec::new(),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(!json.contains("buckets"));
+        let parsed: SummaryRequest = serde_json::from_str(&json).unwrap();
+        assert!(parsed.buckets.is_empty());
+    }
+}
@@ -8,14 +8,76 @@
 
 #![allow(dead_code)]
 
-use crate::core::{DriveEvent, DriveId, SharedDrive};
+use crate::core::gap::held_files;
+use crate::core::{
+    content_type, file, DriveEvent, DriveId, IgnoreRules, PermissionErrors, SharedDrive,
+    SyncFilters, PROTOCOL_VERSION,
+};
+use crate::crypto::NodeId;
+use crate::network::coalesce::{ChangeSummaryConfig, DirectoryCoalescer, CHANGE_SUMMARY_SETTING};
+use crate::network::outbound::{DeferReason, OutboundQueue, PendingUpload};
+use crate::network::peer_retry::{PeerRetryConfig, PEER_RETRY_SETTING};
 use crate::network::{DocsManager, EventBroadcaster};
+use crate::storage::Database;
 use anyhow::Result;
 use iroh_docs::DocTicket;
 use chrono::Utc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Most local changes buffered for a drive paused by `pause_sync`
+const MAX_PAUSED_CHANGES: usize = 1_000;
+
+/// A running reconnect loop for a joined drive
+struct PeerRetry {
+    token: CancellationToken,
+    /// Attempts made so far
+    attempt: Arc<AtomicU32>,
+}
+
+/// Local changes held back for a drive paused by `pause_sync`
+#[derive(Debug, Default)]
+struct HeldChanges {
+    /// Oldest first
+    events: VecDeque<DriveEvent>,
+}
+
+impl HeldChanges {
+    /// Hold `event`, returning the oldest change if it was dropped to stay
+    /// within `MAX_PAUSED_CHANGES`
+    fn push(&mut self, event: DriveEvent) -> Option<DriveEvent> {
+        self.events.push_back(event);
+        if self.events.len() > MAX_PAUSED_CHANGES {
+            return self.events.pop_front();
+        }
+        None
+    }
+
+    /// The changes to apply on resume: the latest one per path, ordered by
+    /// when each path last changed
+    fn into_latest(self) -> Vec<DriveEvent> {
+        let mut latest: Vec<DriveEvent> = Vec::with_capacity(self.events.len());
+        for event in self.events {
+            latest.retain(|e| e.file_path() != event.file_path());
+            latest.push(event);
+        }
+        latest
+    }
+}
+
+/// What `drain_drive` stopped, so `resume_drive` can restart it
+struct DrainedSync {
+    /// Whether the drive had a gossip subscription (or one paused by `pause_all`)
+    subscribed: bool,
+    /// Whether a peer retry loop was running
+    retrying: bool,
+}
 
 /// Coordinates metadata sync, event broadcasting, and file transfers
 pub struct SyncEngine {
@@ -27,6 +89,24 @@ pub struct SyncEngine {
     event_tx: broadcast::Sender<(DriveId, DriveEvent)>,
     /// Last error seen per drive for diagnostics
     last_error: RwLock<HashMap<DriveId, SyncErrorInfo>>,
+    /// Drives whose gossip subscription was dropped by `pause_all`
+    paused: RwLock<Option<Vec<DriveId>>>,
+    /// Local changes that could not be announced yet
+    outbound: OutboundQueue,
+    /// Backoff for drives joined while their peers were offline
+    peer_retry_config: RwLock<PeerRetryConfig>,
+    /// Joined drives still waiting for a peer to respond
+    peer_retries: RwLock<HashMap<DriveId, PeerRetry>>,
+    /// Files sync was denied access to
+    permission_errors: Arc<PermissionErrors>,
+    /// `.gixignore` rules, kept current by the file watcher
+    ignore_rules: Arc<IgnoreRules>,
+    /// Folds bursts of local changes into directory summaries
+    coalescer: RwLock<DirectoryCoalescer>,
+    /// Drives quiesced for maintenance (see `drain_drive`)
+    drained: RwLock<HashMap<DriveId, DrainedSync>>,
+    /// Local changes held back per drive paused by `pause_sync`, oldest first
+    paused_drives: RwLock<HashMap<DriveId, HeldChanges>>,
 }
 
 impl SyncEngine {
@@ -34,8 +114,22 @@ impl SyncEngine {
     pub fn new(
         docs_manager: Arc<DocsManager>,
         event_broadcaster: Arc<EventBroadcaster>,
+        db: Arc<Database>,
     ) -> Self {
         let (event_tx, _) = broadcast::channel(512);
+        let peer_retry_config = db
+            .get_setting(PEER_RETRY_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|bytes| serde_json::from_slice::<PeerRetryConfig>(&bytes).ok())
+            .map(PeerRetryConfig::normalized)
+            .unwrap_or_default();
+        let summary_config = db
+            .get_setting(CHANGE_SUMMARY_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|bytes| serde_json::from_slice::<ChangeSummaryConfig>(&bytes).ok())
+            .unwrap_or_default();
 
         tracing::info!("SyncEngine initialized");
 
@@ -44,9 +138,28 @@ impl SyncEngine {
             event_broadcaster,
             event_tx,
             last_error: RwLock::new(HashMap::new()),
+            paused: RwLock::new(None),
+            outbound: OutboundQueue::load(db.clone()),
+            peer_retry_config: RwLock::new(peer_retry_config),
+            peer_retries: RwLock::new(HashMap::new()),
+            permission_errors: Arc::new(PermissionErrors::load(db)),
+            ignore_rules: Arc::new(IgnoreRules::new()),
+            coalescer: RwLock::new(DirectoryCoalescer::new(summary_config)),
+            drained: RwLock::new(HashMap::new()),
+            paused_drives: RwLock::new(HashMap::new()),
         }
     }
 
+    /// Files sync was denied access to
+    pub fn permission_errors(&self) -> Arc<PermissionErrors> {
+        self.permission_errors.clone()
+    }
+
+    /// `.gixignore` rules local changes are checked against
+    pub fn ignore_rules(&self) -> Arc<IgnoreRules> {
+        self.ignore_rules.clone()
+    }
+
     /// Initialize sync for an owned drive
     ///
     /// This sets up:
@@ -62,7 +175,20 @@ impl SyncEngine {
             return Err(err);
         }
 
-        // 2. Subscribe to gossip topic
+        // Keep docs sync away from peers the drive routes around
+        let excluded = drive.no_direct_sync.iter().map(|peer| *peer.as_bytes()).collect();
+        if let Err(err) = self
+            .docs_manager
+            .set_no_direct_sync(&drive_id, excluded)
+            .await
+        {
+            tracing::warn!(error = %err, "Failed to apply no-direct-sync peers for {}", drive_id);
+        }
+
+        // 2. Subscribe to gossip topic (applying the drive's peer limit first)
+        self.event_broadcaster
+            .set_max_peers(drive_id, drive.max_peers)
+            .await;
         if let Err(err) = self.event_broadcaster.subscribe(drive_id).await {
             self.record_error(drive_id, format!("gossip subscribe failed: {}", err))
                 .await;
@@ -80,7 +206,8 @@ impl SyncEngine {
     /// This sets up:
     /// 1. Import the iroh-doc from the ticket
     /// 2. Subscribe to the gossip topic
-    pub async fn join_drive(&self, drive_id: DriveId, ticket: DocTicket) -> Result<()> {
+    /// 3. Retry the ticket's peers until one responds (see `start_peer_retry`)
+    pub async fn join_drive(self: &Arc<Self>, drive_id: DriveId, ticket: DocTicket) -> Result<()> {
         // 1. Import doc from ticket
         if let Err(err) = self.docs_manager.join_doc(drive_id, ticket).await {
             self.record_error(drive_id, format!("docs join failed: {}", err))
@@ -98,22 +225,440 @@ impl SyncEngine {
         tracing::info!("Sync initialized for joined drive: {}", drive_id);
         self.clear_error(&drive_id).await;
 
+        // 3. Keep dialing the ticket's peers in case they are offline
+        self.start_peer_retry(drive_id).await;
+
         Ok(())
     }
 
+    /// Retry a joined drive's ticket peers until metadata starts arriving
+    ///
+    /// Each attempt re-dials the peers for docs sync and joins the gossip
+    /// topic through them, waiting longer after each attempt (see
+    /// `PeerRetryConfig`). A `WaitingForPeers` event is sent to the frontend
+    /// per attempt. Stops once the doc has a sync peer or any metadata, on
+    /// `cancel_peer_retry`, or when the drive stops syncing. Does nothing if
+    /// retrying is disabled or already running for the drive.
+    pub async fn start_peer_retry(self: &Arc<Self>, drive_id: DriveId) {
+        if !self.peer_retry_config.read().await.enabled {
+            return;
+        }
+
+        let token = CancellationToken::new();
+        let attempt = Arc::new(AtomicU32::new(0));
+        {
+            let mut retries = self.peer_retries.write().await;
+            if retries.contains_key(&drive_id) {
+                return;
+            }
+            retries.insert(
+                drive_id,
+                PeerRetry {
+                    token: token.clone(),
+                    attempt: attempt.clone(),
+                },
+            );
+        }
+
+        let engine = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let attempts = attempt.load(Ordering::SeqCst);
+                let delay = engine.peer_retry_config.read().await.delay_for(attempts);
+                tokio::select! {
+                    _ = token.cancelled() => return,
+                    _ = tokio::time::sleep(delay) => {}
+                }
+
+                if engine.has_remote_data(&drive_id).await {
+                    tracing::info!(
+                        "Peers responded for drive {} after {} retry attempt(s)",
+                        drive_id,
+                        attempts
+                    );
+                    break;
+                }
+                // Paused drives are resumed by resume_all; don't count these
+                if engine.is_paused().await || engine.is_drained(&drive_id).await {
+                    continue;
+                }
+
+                let attempts = attempt.fetch_add(1, Ordering::SeqCst) + 1;
+                let next = engine.peer_retry_config.read().await.delay_for(attempts);
+                engine.event_broadcaster.emit_local(
+                    &drive_id,
+                    &DriveEvent::WaitingForPeers {
+                        attempt: attempts,
+                        next_retry_secs: next.as_secs(),
+                    },
+                );
+                tracing::debug!("Waiting for peers for drive {}, attempt {}", drive_id, attempts);
+
+                if let Err(err) = engine.retry_peers(&drive_id).await {
+                    engine
+                        .record_error(drive_id, format!("peer retry failed: {}", err))
+                        .await;
+                }
+            }
+
+            engine.peer_retries.write().await.remove(&drive_id);
+        });
+    }
+
+    /// Stop retrying a drive's peers; returns false if no retry was running
+    pub async fn cancel_peer_retry(&self, drive_id: &DriveId) -> bool {
+        let Some(retry) = self.peer_retries.write().await.remove(drive_id) else {
+            return false;
+        };
+        retry.token.cancel();
+        tracing::info!("Cancelled peer retry for drive {}", drive_id);
+        true
+    }
+
+    /// Current backoff settings for peer retries
+    pub async fn peer_retry_config(&self) -> PeerRetryConfig {
+        *self.peer_retry_config.read().await
+    }
+
+    /// Replace the peer retry settings
+    ///
+    /// Running retries pick up the new delays from their next attempt;
+    /// disabling stops them.
+    pub async fn set_peer_retry_config(&self, config: PeerRetryConfig) -> PeerRetryConfig {
+        let config = config.normalized();
+        *self.peer_retry_config.write().await = config;
+
+        if !config.enabled {
+            for (_, retry) in self.peer_retries.write().await.drain() {
+                retry.token.cancel();
+            }
+        }
+        config
+    }
+
+    /// True once a joined drive has synced with a peer or holds metadata
+    async fn has_remote_data(&self, drive_id: &DriveId) -> bool {
+        let has_peers = self
+            .docs_manager
+            .get_sync_peers(drive_id)
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|peers| !peers.is_empty());
+        has_peers
+            || self
+                .docs_manager
+                .get_all_metadata(drive_id)
+                .await
+                .is_ok_and(|metadata| !metadata.is_empty())
+    }
+
+    /// Dial a drive's ticket peers for docs sync and gossip
+    async fn retry_peers(&self, drive_id: &DriveId) -> Result<()> {
+        // Docs sync first: it registers the peers' addresses with the endpoint
+        let peers = self.docs_manager.retry_bootstrap_peers(drive_id).await?;
+
+        if !self.event_broadcaster.is_subscribed(drive_id).await {
+            self.event_broadcaster.subscribe(*drive_id).await?;
+        }
+        self.event_broadcaster
+            .join_peers(drive_id, peers.iter().map(|node| node.node_id).collect())
+            .await
+    }
+
     /// Stop syncing a drive
     pub async fn stop_sync(&self, drive_id: &DriveId) {
+        self.cancel_peer_retry(drive_id).await;
         self.event_broadcaster.unsubscribe(drive_id).await;
+        // Don't let a later resume_all or resume_drive resubscribe it
+        if let Some(paused) = self.paused.write().await.as_mut() {
+            paused.retain(|id| id != drive_id);
+        }
+        if let Some(drained) = self.drained.write().await.get_mut(drive_id) {
+            drained.subscribed = false;
+            drained.retrying = false;
+        }
+        if let Some(held) = self.paused_drives.write().await.remove(drive_id) {
+            if !held.is_empty() {
+                tracing::debug!(
+                    "Discarded {} change(s) held while drive {} was paused",
+                    held.len(),
+                    drive_id
+                );
+            }
+        }
         tracing::info!("Sync stopped for drive: {}", drive_id);
     }
 
+    /// Quiesce one drive's sync until `resume_drive`
+    ///
+    /// Folded change summaries and queued changes are sent first, while the
+    /// drive still has its gossip subscription. Then peer retries stop, the
+    /// drive leaves its gossip topic and docs sync stops. Local and remote
+    /// changes for the drive are refused while it is drained. Other drives
+    /// are unaffected. Returns false if the drive was already drained.
+    pub async fn drain_drive(&self, drive_id: &DriveId) -> bool {
+        if self.is_drained(drive_id).await {
+            return false;
+        }
+
+        // Flush what is pending for the drive before it goes quiet
+        let due = self.coalescer.write().await.take_drive(drive_id);
+        self.announce_summaries(&due).await;
+        self.retry_pending_uploads(Some(drive_id), false).await;
+
+        let retrying = self.cancel_peer_retry(drive_id).await;
+        let was_paused = match self.paused.write().await.as_mut() {
+            Some(paused) => {
+                let before = paused.len();
+                paused.retain(|id| id != drive_id);
+                paused.len() < before
+            }
+            None => false,
+        };
+        let subscribed = was_paused || self.event_broadcaster.is_subscribed(drive_id).await;
+        self.drained
+            .write()
+            .await
+            .insert(*drive_id, DrainedSync { subscribed, retrying });
+
+        self.event_broadcaster.unsubscribe(drive_id).await;
+        if let Err(err) = self.docs_manager.set_doc_syncing(drive_id, false).await {
+            self.record_error(*drive_id, format!("docs sync stop failed: {}", err))
+                .await;
+        }
+        tracing::info!("Sync drained for drive: {}", drive_id);
+        true
+    }
+
+    /// Restart sync for a drive stopped by `drain_drive`
+    ///
+    /// The gossip subscription and peer retries are restored only if the
+    /// drive had them; while sync is paused the subscription is left for
+    /// `resume_all`. Returns false if the drive wasn't drained.
+    pub async fn resume_drive(self: &Arc<Self>, drive_id: &DriveId) -> bool {
+        let Some(drained) = self.drained.write().await.remove(drive_id) else {
+            return false;
+        };
+
+        if drained.subscribed {
+            if let Err(err) = self.docs_manager.set_doc_syncing(drive_id, true).await {
+                self.record_error(*drive_id, format!("docs sync restart failed: {}", err))
+                    .await;
+            }
+            let left_paused = match self.paused.write().await.as_mut() {
+                Some(paused) => {
+                    paused.push(*drive_id);
+                    true
+                }
+                None => false,
+            };
+            if !left_paused {
+                if let Err(err) = self.event_broadcaster.subscribe(*drive_id).await {
+                    self.record_error(*drive_id, format!("gossip resubscribe failed: {}", err))
+                        .await;
+                }
+            }
+        }
+        if drained.retrying {
+            self.start_peer_retry(*drive_id).await;
+        }
+        tracing::info!("Sync resumed for drive: {}", drive_id);
+        true
+    }
+
+    /// Check if a drive is drained
+    pub async fn is_drained(&self, drive_id: &DriveId) -> bool {
+        self.drained.read().await.contains_key(drive_id)
+    }
+
+    /// Pause network sync for every drive
+    ///
+    /// Gossip subscriptions are dropped and remembered so `resume_all` can
+    /// restore them. Local changes still update metadata but are not
+    /// broadcast. Returns false if sync was already paused.
+    pub async fn pause_all(&self) -> bool {
+        let mut paused = self.paused.write().await;
+        if paused.is_some() {
+            return false;
+        }
+
+        let drives = self.event_broadcaster.subscribed_drives().await;
+        for drive_id in &drives {
+            self.event_broadcaster.unsubscribe(drive_id).await;
+        }
+        tracing::info!("Sync paused for {} drive(s)", drives.len());
+        *paused = Some(drives);
+        true
+    }
+
+    /// Resume network sync for the drives paused by `pause_all`
+    ///
+    /// Returns false if sync was not paused.
+    pub async fn resume_all(&self) -> bool {
+        let Some(drives) = self.paused.write().await.take() else {
+            return false;
+        };
+
+        for drive_id in drives {
+            if let Err(err) = self.event_broadcaster.subscribe(drive_id).await {
+                self.record_error(drive_id, format!("gossip resubscribe failed: {}", err))
+                    .await;
+            }
+        }
+        tracing::info!("Sync resumed");
+        true
+    }
+
+    /// Check if network sync is paused
+    pub async fn is_paused(&self) -> bool {
+        self.paused.read().await.is_some()
+    }
+
+    /// Hold back one drive's local changes until `resume_sync`
+    ///
+    /// Unlike `stop_sync` and `drain_drive`, the gossip subscription and
+    /// docs sync stay up, so remote changes keep arriving. Local changes are
+    /// buffered instead of being written to metadata or broadcast; past
+    /// `MAX_PAUSED_CHANGES` the oldest are dropped. Returns false if the
+    /// drive was already paused.
+    pub async fn pause_sync(&self, drive_id: &DriveId) -> bool {
+        let mut paused = self.paused_drives.write().await;
+        if paused.contains_key(drive_id) {
+            return false;
+        }
+        paused.insert(*drive_id, HeldChanges::default());
+        tracing::info!("Sync paused for drive: {}", drive_id);
+        true
+    }
+
+    /// Apply and announce the changes held back by `pause_sync`
+    ///
+    /// Several changes to the same path are folded into the latest one.
+    /// Returns how many changes were applied, or None if the drive wasn't
+    /// paused.
+    pub async fn resume_sync(&self, drive_id: &DriveId) -> Option<usize> {
+        let held = self.paused_drives.write().await.remove(drive_id)?;
+
+        let mut applied = 0;
+        for event in held.into_latest() {
+            match self.on_local_change(drive_id, event).await {
+                Ok(_) => applied += 1,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to apply held change for drive {}: {}",
+                        drive_id,
+                        err
+                    );
+                }
+            }
+        }
+        tracing::info!(
+            "Sync resumed for drive {}, applied {} held change(s)",
+            drive_id,
+            applied
+        );
+        Some(applied)
+    }
+
+    /// Check if a drive is paused by `pause_sync`
+    pub async fn is_drive_paused(&self, drive_id: &DriveId) -> bool {
+        self.paused_drives.read().await.contains_key(drive_id)
+    }
+
+    /// Buffer a local change for a paused drive, returning false if the
+    /// drive isn't paused
+    async fn hold_change(&self, drive_id: &DriveId, event: &DriveEvent) -> bool {
+        let mut paused = self.paused_drives.write().await;
+        let Some(held) = paused.get_mut(drive_id) else {
+            return false;
+        };
+        if event.file_path().is_none() {
+            tracing::trace!(
+                "Dropping {} for paused drive {}",
+                event.event_type(),
+                drive_id
+            );
+            return true;
+        }
+        if held.push(event.clone()).is_some() {
+            tracing::warn!(
+                "Paused drive {} has more than {} held changes, dropped the oldest",
+                drive_id,
+                MAX_PAUSED_CHANGES
+            );
+        }
+        true
+    }
+
+    /// Selective sync filters for a drive
+    pub async fn sync_filters(&self, drive_id: &DriveId) -> SyncFilters {
+        self.docs_manager.sync_filters(drive_id).await
+    }
+
+    /// Replace a drive's selective sync filters
+    ///
+    /// Local changes to excluded paths are ignored from now on. Returns the
+    /// previously synced paths whose metadata was tombstoned.
+    pub async fn set_sync_filters(
+        &self,
+        drive_id: &DriveId,
+        filters: SyncFilters,
+    ) -> Result<Vec<String>> {
+        self.docs_manager.set_sync_filters(drive_id, filters).await
+    }
+
     /// Handle a local file change
     ///
     /// Called by the file watcher when a local change is detected.
+    /// Changes to paths excluded by the drive's sync filters or ignored by
+    /// its `.gixignore` files are dropped, and changes for a drive paused by `pause_sync` are held back.
     /// This will:
-    /// 1. Update the iroh-doc metadata
-    /// 2. Broadcast the event via gossip
-    pub async fn on_local_change(&self, drive_id: &DriveId, event: DriveEvent) -> Result<()> {
+    /// 1. Mark `FileChanged` as a creation if the path had no metadata
+    /// 2. Update the iroh-doc metadata
+    /// 3. Broadcast the event via gossip
+    ///
+    /// Changes that can't reach a peer (paused, no neighbors, or a failed
+    /// broadcast) are queued and re-sent when a peer connects.
+    ///
+    /// Returns the event as broadcast, or None if it was folded into a
+    /// directory summary (see `flush_change_summaries`), held or dropped. Deciding "created"
+    /// from metadata rather than the watcher's event kind keeps it accurate
+    /// when a create and a quick follow-up write are debounced into one event.
+    pub async fn on_local_change(
+        &self,
+        drive_id: &DriveId,
+        mut event: DriveEvent,
+    ) -> Result<Option<DriveEvent>> {
+        if self.is_drained(drive_id).await {
+            anyhow::bail!("Drive {} is drained", drive_id);
+        }
+        if let Some(path) = event.file_path() {
+            let is_dir = matches!(&event, DriveEvent::FileChanged { hash, .. } if hash.is_empty());
+            if self.ignore_rules.is_ignored(drive_id, path, is_dir) {
+                tracing::trace!(
+                    "Skipping ignored path {} in drive {}",
+                    path.display(),
+                    drive_id
+                );
+                return Ok(None);
+            }
+            let path = path.to_string_lossy();
+            if !self.docs_manager.is_path_synced(drive_id, &path).await {
+                tracing::trace!("Skipping excluded path {} in drive {}", path, drive_id);
+                return Ok(None);
+            }
+        }
+        if self.hold_change(drive_id, &event).await {
+            return Ok(None);
+        }
+        if let DriveEvent::FileChanged { path, created, .. } = &mut event {
+            *created = !self
+                .docs_manager
+                .has_file_metadata(drive_id, &path.to_string_lossy())
+                .await;
+        }
+
         // Update metadata in docs based on event type
         match &event {
             DriveEvent::FileChanged {
@@ -122,20 +667,35 @@ impl SyncEngine {
                 size,
                 modified_by: _,
                 timestamp,
+                created: _,
+                content_type,
             } => {
                 let file_name = path
                     .file_name()
                     .map(|n| n.to_string_lossy().to_string())
                     .unwrap_or_default();
 
+                let path_str = path.to_string_lossy().to_string();
+                let manifest_hash = self
+                    .docs_manager
+                    .current_manifest(drive_id, &path_str, hash)
+                    .await;
+                let previous_hash = self
+                    .docs_manager
+                    .previous_hash(drive_id, &path_str, hash)
+                    .await;
                 let meta = crate::network::docs::FileMetadata {
                     name: file_name,
-                    path: path.to_string_lossy().to_string(),
+                    path: path_str,
                     is_dir: false,
                     size: *size,
                     modified_at: timestamp.to_rfc3339(),
                     content_hash: Some(hash.clone()),
                     version: 1,
+                    protocol_version: PROTOCOL_VERSION,
+                    content_type: content_type.clone(),
+                    manifest_hash,
+                    previous_hash,
                 };
 
                 if let Err(err) = self.docs_manager.set_file_metadata(drive_id, &meta).await {
@@ -160,17 +720,88 @@ impl SyncEngine {
             }
         }
 
-        // Broadcast event via gossip
-        if let Err(err) = self.event_broadcaster.broadcast(drive_id, event.clone()).await {
+        let announce = self
+            .coalescer
+            .write()
+            .await
+            .offer(drive_id, &event, Instant::now());
+
+        // Broadcast event via gossip, deferring it when no peer can receive it.
+        // Deferred changes are queued per file even when folded, since the
+        // summary itself isn't queued.
+        if self.is_paused().await {
+            tracing::debug!("Sync paused, deferring change for drive {}", drive_id);
+            self.outbound
+                .push(*drive_id, event.clone(), DeferReason::SyncPaused)
+                .await;
+        } else if self.event_broadcaster.drive_peers(drive_id).await.peer_count == 0 {
+            tracing::debug!("No peers connected, deferring change for drive {}", drive_id);
+            self.outbound
+                .push(*drive_id, event.clone(), DeferReason::NoPeers)
+                .await;
+        } else if !announce {
+            tracing::trace!("Folded change into directory summary for drive {}", drive_id);
+        } else if let Err(err) = self.event_broadcaster.broadcast(drive_id, event.clone()).await {
             self.record_error(*drive_id, format!("gossip broadcast failed: {}", err))
                 .await;
-            return Err(err);
+            if !self
+                .outbound
+                .push(*drive_id, event.clone(), DeferReason::BroadcastFailed)
+                .await
+            {
+                return Err(err);
+            }
+        }
+
+        if !announce {
+            return Ok(None);
         }
 
         // Forward to internal channel
-        let _ = self.event_tx.send((*drive_id, event));
+        let _ = self.event_tx.send((*drive_id, event.clone()));
 
-        Ok(())
+        Ok(Some(event))
+    }
+
+    /// Announce summaries of change bursts whose window has closed
+    ///
+    /// Called periodically by the watcher forwarder. Returns the summaries
+    /// so they can be shown locally too. Summaries that can't reach a peer
+    /// are dropped; the folded changes themselves were already queued.
+    pub async fn flush_change_summaries(&self) -> Vec<(DriveId, DriveEvent)> {
+        let due = self.coalescer.write().await.take_due(Instant::now());
+        self.announce_summaries(&due).await;
+        due
+    }
+
+    async fn announce_summaries(&self, due: &[(DriveId, DriveEvent)]) {
+        if due.is_empty() {
+            return;
+        }
+
+        let paused = self.is_paused().await;
+        for (drive_id, event) in due {
+            if !paused && self.event_broadcaster.drive_peers(drive_id).await.peer_count > 0 {
+                if let Err(err) = self.event_broadcaster.broadcast(drive_id, event.clone()).await {
+                    self.record_error(*drive_id, format!("gossip broadcast failed: {}", err))
+                        .await;
+                }
+            }
+            let _ = self.event_tx.send((*drive_id, event.clone()));
+        }
+    }
+
+    /// Current directory summary configuration
+    pub async fn change_summary_config(&self) -> ChangeSummaryConfig {
+        self.coalescer.read().await.config()
+    }
+
+    /// Replace the directory summary configuration, returning it normalized
+    pub async fn set_change_summary_config(
+        &self,
+        config: ChangeSummaryConfig,
+    ) -> ChangeSummaryConfig {
+        self.coalescer.write().await.set_config(config)
     }
 
     /// Handle a remote event received via gossip
@@ -180,6 +811,11 @@ impl SyncEngine {
     /// 1. Update local state if needed
     /// 2. Forward to the internal event channel
     pub async fn on_remote_event(&self, drive_id: &DriveId, event: DriveEvent) -> Result<()> {
+        // Anything still arriving for a drained drive is dropped
+        if self.is_drained(drive_id).await {
+            return Ok(());
+        }
+
         // Update local metadata based on event
         match &event {
             DriveEvent::FileChanged {
@@ -188,20 +824,38 @@ impl SyncEngine {
                 size,
                 modified_by: _,
                 timestamp,
+                created: _,
+                content_type,
             } => {
                 let file_name = path
                     .file_name()
                     .map(|n| n.to_string_lossy().to_string())
                     .unwrap_or_default();
 
+                let path_str = path.to_string_lossy().to_string();
+                let manifest_hash = self
+                    .docs_manager
+                    .current_manifest(drive_id, &path_str, hash)
+                    .await;
+                let previous_hash = self
+                    .docs_manager
+                    .previous_hash(drive_id, &path_str, hash)
+                    .await;
                 let meta = crate::network::docs::FileMetadata {
                     name: file_name,
-                    path: path.to_string_lossy().to_string(),
+                    path: path_str,
                     is_dir: false,
                     size: *size,
                     modified_at: timestamp.to_rfc3339(),
                     content_hash: Some(hash.clone()),
                     version: 1,
+                    protocol_version: PROTOCOL_VERSION,
+                    // Peers that predate type detection don't send one
+                    content_type: content_type.clone().or_else(|| {
+                        content_type::from_extension(path).map(String::from)
+                    }),
+                    manifest_hash,
+                    previous_hash,
                 };
 
                 // Only update if we have a doc for this drive
@@ -241,6 +895,94 @@ impl SyncEngine {
         Ok(())
     }
 
+    /// Local changes waiting to be announced, oldest first
+    pub async fn pending_uploads(&self, drive_id: Option<&DriveId>) -> Vec<PendingUpload> {
+        self.outbound.list(drive_id).await
+    }
+
+    /// Drop a queued change without sending it
+    ///
+    /// Peers still pick the change up from synced metadata; only the
+    /// real-time announcement is discarded.
+    pub async fn discard_pending_upload(&self, id: &str) -> bool {
+        self.outbound.remove(id).await
+    }
+
+    /// Send queued changes, returning how many were delivered
+    ///
+    /// Drives without connected peers are skipped unless `force` is set;
+    /// drained drives always are.
+    /// Nothing is sent while sync is paused.
+    pub async fn retry_pending_uploads(&self, drive_id: Option<&DriveId>, force: bool) -> usize {
+        if self.is_paused().await {
+            return 0;
+        }
+
+        let mut has_peers: HashMap<DriveId, bool> = HashMap::new();
+        let mut sent = 0;
+        for pending in self.outbound.list(drive_id).await {
+            if self.is_drained(&pending.drive_id).await {
+                continue;
+            }
+            if !force {
+                let connected = match has_peers.get(&pending.drive_id) {
+                    Some(connected) => *connected,
+                    None => {
+                        let peers = self.event_broadcaster.drive_peers(&pending.drive_id).await;
+                        has_peers.insert(pending.drive_id, peers.peer_count > 0);
+                        peers.peer_count > 0
+                    }
+                };
+                if !connected {
+                    continue;
+                }
+            }
+
+            match self
+                .event_broadcaster
+                .broadcast(&pending.drive_id, pending.event.clone())
+                .await
+            {
+                Ok(()) => {
+                    self.outbound.record_attempt(&pending, None).await;
+                    sent += 1;
+                }
+                Err(err) => {
+                    self.outbound
+                        .record_attempt(&pending, Some(err.to_string()))
+                        .await;
+                }
+            }
+        }
+
+        if sent > 0 {
+            tracing::info!("Sent {} deferred change(s)", sent);
+        }
+        sent
+    }
+
+    /// Re-send a drive's queued changes whenever it gains a peer
+    pub fn start_outbound_retry(self: &Arc<Self>) -> JoinHandle<()> {
+        let engine = self.clone();
+        let mut neighbor_rx = self.event_broadcaster.subscribe_neighbor_up();
+        tokio::spawn(async move {
+            loop {
+                match neighbor_rx.recv().await {
+                    Ok(drive_id) => {
+                        if engine.outbound.list(Some(&drive_id)).await.is_empty() {
+                            continue;
+                        }
+                        engine.retry_pending_uploads(Some(&drive_id), false).await;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => {
+                        engine.retry_pending_uploads(None, false).await;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
     /// Get a receiver for internal sync events
     ///
     /// This can be used to listen for all events (local and remote).
@@ -257,6 +999,22 @@ impl SyncEngine {
     /// Get sync status for a drive
     pub async fn get_status(&self, drive_id: &DriveId) -> SyncStatus {
         let is_syncing = self.is_syncing(drive_id).await;
+        let paused = self.is_drive_paused(drive_id).await
+            || self
+                .paused
+                .read()
+                .await
+                .as_ref()
+                .is_some_and(|drives| drives.contains(drive_id));
+        let state = if self.is_drained(drive_id).await {
+            SyncState::Drained
+        } else if paused {
+            SyncState::Paused
+        } else if is_syncing {
+            SyncState::Syncing
+        } else {
+            SyncState::Stopped
+        };
         let connected_peers = if is_syncing {
             self.docs_manager
                 .get_sync_peers(drive_id)
@@ -271,6 +1029,7 @@ impl SyncEngine {
 
         SyncStatus {
             is_syncing,
+            state,
             connected_peers,
             last_sync: None,
         }
@@ -299,6 +1058,12 @@ impl SyncEngine {
             .flatten()
             .map(|peers| peers.len());
         let last_error = self.get_last_error(drive_id).await;
+        let peer_retry_attempt = self
+            .peer_retries
+            .read()
+            .await
+            .get(drive_id)
+            .map(|retry| retry.attempt.load(Ordering::SeqCst));
 
         SyncDiagnostics {
             is_syncing: has_doc && gossip_subscribed,
@@ -307,9 +1072,43 @@ impl SyncEngine {
             doc_namespace: namespace.map(|id| id.to_string()),
             doc_peers,
             last_error,
+            peer_retry_attempt,
+            permission_errors: self.permission_errors.count(drive_id),
         }
     }
 
+    /// `(path, content_hash)` of the files we hold in a drive rooted at `root`
+    ///
+    /// See [`held_files`] for which files count as held.
+    pub async fn held_files(
+        &self,
+        drive_id: &DriveId,
+        root: &Path,
+    ) -> Result<Vec<(String, String)>> {
+        let metadata: Vec<(String, Option<String>)> = self
+            .docs_manager
+            .get_all_metadata(drive_id)
+            .await?
+            .into_iter()
+            .filter(|meta| !meta.is_dir)
+            .map(|meta| (meta.path, meta.content_hash))
+            .collect();
+
+        let root = root.to_path_buf();
+        let local: HashSet<String> = tokio::task::spawn_blocking(move || {
+            file::index_directory(&root).map(|entries| {
+                entries
+                    .into_iter()
+                    .filter(|entry| !entry.is_dir)
+                    .map(|entry| file::path_key(&entry.path))
+                    .collect()
+            })
+        })
+        .await??;
+
+        Ok(held_files(&local, &metadata))
+    }
+
     async fn record_error(&self, drive_id: DriveId, message: String) {
         let mut errors = self.last_error.write().await;
         errors.insert(
@@ -347,6 +1146,10 @@ pub struct SyncDiagnostics {
     pub doc_peers: Option<usize>,
     /// Most recent error
     pub last_error: Option<SyncErrorInfo>,
+    /// Reconnect attempts so far if still waiting for peers after joining
+    pub peer_retry_attempt: Option<u32>,
+    /// Files skipped because sync was denied access to them
+    pub permission_errors: usize,
 }
 
 /// Last error info for diagnostics
@@ -356,11 +1159,27 @@ pub struct SyncErrorInfo {
     pub timestamp: String,
 }
 
+/// Where a drive's sync stands
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncState {
+    /// No docs replica or gossip subscription
+    Stopped,
+    /// Syncing normally
+    Syncing,
+    /// Local changes are held back (`pause_sync`, or all sync paused)
+    Paused,
+    /// Quiesced for maintenance (`drain_drive`)
+    Drained,
+}
+
 /// Status information for sync operations
 #[derive(Clone, Debug, serde::Serialize)]
 pub struct SyncStatus {
     /// Whether sync is active for this drive
     pub is_syncing: bool,
+    /// Overall sync state
+    pub state: SyncState,
     /// Number of connected peers
     pub connected_peers: usize,
     /// Last successful sync timestamp (ISO 8601)
@@ -374,10 +1193,88 @@ mod tests {
     // Note: Full tests require mocking the DocsManager and EventBroadcaster
     // which would be added in integration tests
 
+    fn held_change(path: &str, hash: &str) -> DriveEvent {
+        DriveEvent::FileChanged {
+            path: path.into(),
+            hash: hash.to_string(),
+            size: 1,
+            modified_by: NodeId([7u8; 32]),
+            timestamp: Utc::now(),
+            created: false,
+            content_type: None,
+        }
+    }
+
+    fn held_hash(event: &DriveEvent) -> &str {
+        match event {
+            DriveEvent::FileChanged { hash, .. } => hash,
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_held_changes_buffer_in_order() {
+        let mut held = HeldChanges::default();
+        assert!(held.push(held_change("a.txt", "1")).is_none());
+        assert!(held.push(held_change("b.txt", "2")).is_none());
+        assert!(held
+            .push(DriveEvent::FileDeleted {
+                path: "c.txt".into(),
+                deleted_by: NodeId([7u8; 32]),
+                timestamp: Utc::now(),
+            })
+            .is_none());
+
+        let latest = held.into_latest();
+        let paths: Vec<_> = latest.iter().filter_map(|e| e.file_path()).collect();
+        assert_eq!(
+            paths,
+            vec![Path::new("a.txt"), Path::new("b.txt"), Path::new("c.txt")]
+        );
+    }
+
+    #[test]
+    fn test_held_changes_drop_oldest_past_limit() {
+        let mut held = HeldChanges::default();
+        for i in 0..MAX_PAUSED_CHANGES {
+            assert!(held
+                .push(held_change(&format!("{}.txt", i), &i.to_string()))
+                .is_none());
+        }
+
+        let dropped = held.push(held_change("last.txt", "last")).unwrap();
+        assert_eq!(dropped.file_path(), Some(Path::new("0.txt")));
+        assert_eq!(held.events.len(), MAX_PAUSED_CHANGES);
+
+        let latest = held.into_latest();
+        assert_eq!(
+            latest.first().and_then(|e| e.file_path()),
+            Some(Path::new("1.txt"))
+        );
+        assert_eq!(held_hash(latest.last().unwrap()), "last");
+    }
+
+    #[test]
+    fn test_held_changes_coalesce_per_path() {
+        let mut held = HeldChanges::default();
+        held.push(held_change("a.txt", "1"));
+        held.push(held_change("b.txt", "2"));
+        held.push(held_change("a.txt", "3"));
+        held.push(held_change("a.txt", "4"));
+
+        // One change per path, the latest, ordered by its last change
+        let latest = held.into_latest();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].file_path(), Some(Path::new("b.txt")));
+        assert_eq!(latest[1].file_path(), Some(Path::new("a.txt")));
+        assert_eq!(held_hash(&latest[1]), "4");
+    }
+
     #[test]
     fn test_sync_status_serialization() {
         let status = SyncStatus {
             is_syncing: true,
+            state: SyncState::Syncing,
             connected_peers: 3,
             last_sync: Some("2024-01-01T00:00:00Z".to_string()),
         };
@@ -385,12 +1282,14 @@ mod tests {
         let json = serde_json::to_string(&status).unwrap();
         assert!(json.contains("is_syncing"));
         assert!(json.contains("connected_peers"));
+        assert!(json.contains(r#""state":"syncing""#));
     }
 
     #[test]
     fn test_sync_status_default_values() {
         let status = SyncStatus {
             is_syncing: false,
+            state: SyncState::Stopped,
             connected_peers: 0,
             last_sync: None,
         };
@@ -404,6 +1303,7 @@ mod tests {
     fn test_sync_status_with_last_sync() {
         let status = SyncStatus {
             is_syncing: true,
+            state: SyncState::Syncing,
             connected_peers: 5,
             last_sync: Some("2024-12-25T10:30:00Z".to_string()),
         };
@@ -417,6 +1317,7 @@ mod tests {
     fn test_sync_status_clone() {
         let status = SyncStatus {
             is_syncing: true,
+            state: SyncState::Syncing,
             connected_peers: 10,
             last_sync: Some("2024-01-01T00:00:00Z".to_string()),
         };
@@ -432,6 +1333,7 @@ mod tests {
     fn test_sync_status_debug() {
         let status = SyncStatus {
             is_syncing: true,
+            state: SyncState::Syncing,
             connected_peers: 2,
             last_sync: None,
         };
@@ -446,6 +1348,7 @@ mod tests {
     fn test_sync_status_json_structure() {
         let status = SyncStatus {
             is_syncing: false,
+            state: SyncState::Stopped,
             connected_peers: 0,
             last_sync: None,
         };
@@ -470,6 +1373,8 @@ mod tests {
                 message: "test error".to_string(),
                 timestamp: "2024-01-01T00:00:00Z".to_string(),
             }),
+            peer_retry_attempt: None,
+            permission_errors: 0,
         };
 
         let json = serde_json::to_string(&diagnostics).unwrap();
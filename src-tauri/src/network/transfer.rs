@@ -8,24 +8,132 @@
 
 #![allow(dead_code)]
 
-use crate::core::{send_with_backpressure, DriveEvent, DriveId};
+use crate::core::{send_with_backpressure, BackpressureStrategy, DriveEvent, DriveId};
 use crate::crypto::NodeId;
+use crate::network::bandwidth::BandwidthLimiter;
+use crate::network::chunking::{ChunkManifest, ChunkParams, ChunkRef, Chunker, MAX_MANIFEST_BYTES};
+use crate::network::key_proof::{KeyCheckedBlobs, KeyProofTracker};
+use crate::network::range_fetch::{self, RANGE_CHUNKS};
+use crate::network::reliability::PeerReliability;
+use crate::network::transfer_queue::{TransferLimiter, TransferLimits, TransferSlot};
+use crate::storage::Database;
 use anyhow::{Context, Result};
-use chrono::Utc;
-use iroh::Endpoint;
+use chrono::{DateTime, Utc};
+use iroh::{Endpoint, NodeAddr};
 use iroh_blobs::{
     net_protocol::Blobs,
-    store::{fs::Store as BlobStore, Map, MapEntry, Store as StoreExt},
+    rpc::client::blobs::{DownloadMode, DownloadOptions},
+    store::{fs::Store as BlobStore, Map, MapEntry, ReadableStore, Store as StoreExt},
+    util::SetTagOption,
     Hash, BlobFormat,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Notify, RwLock};
+use tokio::task::JoinSet;
 
-/// Transfer state for tracking active transfers
+/// How long blob store stats are cached (enumerating the store is not free)
+const BLOB_STATS_TTL: Duration = Duration::from_secs(30);
+
+/// Weight of the newest sample in the download throughput average
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+
+/// How long one provider gets to deliver a blob before the next is tried
+const PEER_FETCH_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often `drain_drive` checks whether a drive's transfers finished
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Minimum time between progress events while a blob is exported
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How far back the transfer throughput moving average looks
+const RATE_WINDOW: Duration = Duration::from_secs(5);
+
+/// Wait before the first retry of a failed download; doubles per attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between download attempts
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Failed resumes after which an upload is no longer kept for resuming
+const MAX_RESUME_FAILURES: u32 = 5;
+
+/// Days an unfinished upload is kept for resuming after its last attempt
+const RESUMABLE_UPLOAD_DAYS: i64 = 7;
+
+/// Settings key for a user-configured download temp directory
+pub const DOWNLOAD_TEMP_DIR_SETTING: &str = "download_temp_dir";
+
+/// Settings key for the configured transfer chunk size
+pub const TRANSFER_CHUNK_SIZE_SETTING: &str = "transfer_chunk_size";
+
+/// Bytes read from the blob store per chunk unless configured otherwise
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Smallest configurable chunk size (4KB)
+pub const MIN_CHUNK_SIZE: usize = 4 * 1024;
+
+/// Largest configurable chunk size (16MB)
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Check whether `size` is an allowed transfer chunk size
+pub fn is_valid_chunk_size(size: usize) -> bool {
+    (MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&size)
+}
+
+/// A downloaded file's content doesn't hash to what its metadata says
+///
+/// Returned (inside `anyhow::Error`) by `download_file`; the file is not
+/// moved into the drive.
 #[derive(Clone, Debug, Serialize)]
+pub struct ContentMismatch {
+    /// Path relative to the drive root
+    pub path: PathBuf,
+    /// Hex-encoded hash the content was expected to have
+    pub expected: String,
+    /// Hex-encoded hash of the content that was written
+    pub actual: String,
+}
+
+impl std::fmt::Display for ContentMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "content of {} does not match its metadata (expected {}, got {})",
+            self.path.display(),
+            self.expected,
+            self.actual
+        )
+    }
+}
+
+impl std::error::Error for ContentMismatch {}
+
+/// Hash a written file and check it against the expected content hash
+pub async fn verify_content(written: &Path, relative_path: &Path, expected: &str) -> Result<()> {
+    let path = written.to_path_buf();
+    let actual = tokio::task::spawn_blocking(move || crate::core::file::hash_file(&path))
+        .await
+        .context("Hashing task failed")??;
+
+    if actual.eq_ignore_ascii_case(expected) {
+        return Ok(());
+    }
+    Err(ContentMismatch {
+        path: relative_path.to_path_buf(),
+        expected: expected.to_string(),
+        actual,
+    }
+    .into())
+}
+
+/// Transfer state for tracking active transfers
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct TransferState {
     /// Unique transfer ID
     pub id: String,
@@ -45,6 +153,105 @@ pub struct TransferState {
     pub hash: Option<String>,
     /// Error message if failed
     pub error: Option<String>,
+    /// When the transfer was registered, then when it started running
+    pub started_at: DateTime<Utc>,
+    /// Local file an upload reads from, kept so it can be resumed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_path: Option<PathBuf>,
+    /// Times resuming this upload failed
+    #[serde(default)]
+    pub resume_failures: u32,
+    /// Throughput over the last few seconds while running
+    #[serde(default)]
+    pub bytes_per_sec: Option<u64>,
+    /// Estimated seconds until done; None if the size or speed is unknown
+    #[serde(default)]
+    pub eta_secs: Option<u64>,
+    /// Recent progress samples `bytes_per_sec` is averaged over
+    #[serde(skip)]
+    rate: RateWindow,
+}
+
+impl TransferState {
+    /// True for an upload that has not completed or been cancelled
+    ///
+    /// Uploads that failed to resume `MAX_RESUME_FAILURES` times, or were
+    /// last attempted more than `RESUMABLE_UPLOAD_DAYS` ago, are given up.
+    fn is_resumable_upload(&self) -> bool {
+        self.direction == TransferDirection::Upload
+            && !matches!(self.status, TransferStatus::Completed | TransferStatus::Cancelled)
+            && self.resume_failures < MAX_RESUME_FAILURES
+            && Utc::now() - self.started_at < chrono::Duration::days(RESUMABLE_UPLOAD_DAYS)
+    }
+
+    /// Update `bytes_per_sec` and `eta_secs` from the current progress
+    ///
+    /// The last measured speed is kept once the transfer completes.
+    fn sample_rate(&mut self, now: Instant) {
+        match self.status {
+            TransferStatus::InProgress => {
+                self.bytes_per_sec = self.rate.record(now, self.bytes_transferred);
+                self.eta_secs = match self.bytes_per_sec {
+                    Some(rate) if rate > 0 && self.total_bytes > 0 => Some(
+                        self.total_bytes
+                            .saturating_sub(self.bytes_transferred)
+                            .div_ceil(rate),
+                    ),
+                    _ => None,
+                };
+            }
+            TransferStatus::Completed => {
+                if let Some(rate) = self.rate.record(now, self.bytes_transferred) {
+                    self.bytes_per_sec = Some(rate);
+                }
+                self.eta_secs = None;
+            }
+            _ => {
+                self.bytes_per_sec = None;
+                self.eta_secs = None;
+            }
+        }
+    }
+
+    /// Start measuring throughput afresh, e.g. when the transfer starts running
+    fn reset_rate(&mut self) {
+        self.rate = RateWindow::default();
+        self.bytes_per_sec = None;
+        self.eta_secs = None;
+    }
+}
+
+/// Progress samples for a moving average of transfer throughput
+#[derive(Clone, Debug, Default)]
+struct RateWindow {
+    /// (when, bytes transferred so far), oldest first
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateWindow {
+    /// Add a sample and return bytes/sec over the last `RATE_WINDOW`
+    fn record(&mut self, now: Instant, bytes: u64) -> Option<u64> {
+        // Progress that went backwards (a range fetched again) starts over
+        if self.samples.back().is_some_and(|&(_, last)| bytes < last) {
+            self.samples.clear();
+        }
+        self.samples.push_back((now, bytes));
+        while self.samples.len() > 2
+            && self
+                .samples
+                .front()
+                .is_some_and(|&(at, _)| now.duration_since(at) > RATE_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+
+        let &(first_at, first_bytes) = self.samples.front()?;
+        let elapsed = now.duration_since(first_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+        Some(((bytes - first_bytes) as f64 / elapsed) as u64)
+    }
 }
 
 /// Transfer direction
@@ -58,7 +265,11 @@ pub enum TransferDirection {
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum TransferStatus {
     Pending,
+    /// Waiting for a free slot (see `TransferLimits`)
+    Queued,
     InProgress,
+    /// Waiting to retry after a failed attempt (see `download_with_retry`)
+    Retrying { attempt: u32, next_retry_ms: u64 },
     Completed,
     Failed,
     Cancelled,
@@ -74,12 +285,74 @@ pub struct TransferProgress {
     pub bytes_transferred: u64,
     pub total_bytes: u64,
     pub status: TransferStatus,
+    /// Throughput over the last few seconds while running
+    pub bytes_per_sec: Option<u64>,
+}
+
+/// Blob store usage summary
+#[derive(Clone, Debug, Serialize)]
+pub struct BlobStoreStats {
+    /// Number of complete blobs
+    pub blob_count: u64,
+    /// Total size of complete blobs in bytes
+    pub total_bytes: u64,
+    /// Number of partial blobs (incomplete downloads)
+    pub partial_count: u64,
+    /// Bytes claimed by partial blobs (their full size, not what is on disk)
+    pub partial_bytes: u64,
+    /// On-disk location of the store
+    pub path: String,
+    /// ISO 8601 time the stats were computed
+    pub computed_at: String,
+}
+
+/// A file that couldn't be re-imported into the blob store
+#[derive(Clone, Debug, Serialize)]
+pub struct ReimportFailure {
+    /// Path relative to the drive root
+    pub path: String,
+    pub error: String,
+}
+
+/// Result of re-importing a drive's local files into the blob store
+#[derive(Clone, Debug, Serialize)]
+pub struct ReimportReport {
+    pub drive_id: String,
+    /// Local files examined
+    pub files_checked: usize,
+    /// Files whose blobs were missing and have been imported
+    pub imported: usize,
+    /// Files whose blobs were already stored
+    pub already_stored: usize,
+    /// Files whose content doesn't hash to their metadata's content hash
+    pub mismatches: Vec<ContentMismatch>,
+    pub failed: Vec<ReimportFailure>,
+    /// True if the re-import was cancelled; remaining files were not examined
+    pub cancelled: bool,
+    /// ISO 8601 time the re-import finished
+    pub completed_at: String,
+}
+
+/// Result of `upload_file_delta`
+#[derive(Clone, Debug)]
+pub struct DeltaUpload {
+    /// Hash of the whole file
+    pub hash: Hash,
+    /// Blob hash of the imported manifest
+    pub manifest_hash: Hash,
+    pub manifest: ChunkManifest,
+    /// Chunks that differ from the previous manifest (all, without one)
+    pub chunks_changed: usize,
+    /// Chunks that weren't stored yet and had to be imported
+    pub chunks_imported: usize,
 }
 
 /// Manages file transfers using iroh-blobs
 pub struct FileTransferManager {
     /// The iroh-blobs protocol handler
     blobs: Arc<Blobs<BlobStore>>,
+    /// Endpoint used to fetch blob ranges from peers directly
+    endpoint: Endpoint,
     /// Our node ID for attribution
     node_id: NodeId,
     /// Active transfers
@@ -88,6 +361,32 @@ pub struct FileTransferManager {
     progress_tx: broadcast::Sender<TransferProgress>,
     /// Drive event channel (for sync events)
     event_tx: broadcast::Sender<(DriveId, DriveEvent)>,
+    /// Blob store directory
+    blobs_dir: PathBuf,
+    /// Last computed blob store stats
+    stats_cache: RwLock<Option<(Instant, BlobStoreStats)>>,
+    /// Default directory for partial downloads (next to the blob store)
+    default_temp_dir: PathBuf,
+    /// Directory partial downloads are written to before moving into place
+    temp_dir: RwLock<PathBuf>,
+    /// Smoothed throughput of completed downloads (bytes/sec)
+    download_rate: RwLock<Option<f64>>,
+    /// Whether network transfers are paused
+    paused: AtomicBool,
+    /// Bytes read from the blob store per chunk when exporting
+    chunk_size: AtomicUsize,
+    /// Download reliability of the peers we fetch from
+    reliability: PeerReliability,
+    /// Limits how many transfers run at once
+    limiter: TransferLimiter,
+    /// Wakes queued transfers when one is cancelled
+    cancelled: Notify,
+    /// Drives that refuse new transfers (see `drain_drive`)
+    drained: RwLock<HashSet<DriveId>>,
+    /// Caps how fast blobs are imported and exported
+    bandwidth: Arc<BandwidthLimiter>,
+    /// Database unfinished uploads are persisted in
+    db: Arc<Database>,
 }
 
 impl FileTransferManager {
@@ -97,14 +396,20 @@ impl FileTransferManager {
     /// * `endpoint` - The Iroh endpoint for P2P connections
     /// * `data_dir` - Directory to store blob data
     /// * `node_id` - Our node ID for event attribution
+    /// * `db` - Database peer reliability scores and unfinished uploads are
+    ///   persisted in
     pub async fn new(
         endpoint: &Endpoint,
         data_dir: &Path,
         node_id: NodeId,
+        db: Arc<Database>,
     ) -> Result<Self> {
         let blobs_dir = data_dir.join("blobs");
         std::fs::create_dir_all(&blobs_dir)?;
 
+        let default_temp_dir = data_dir.join("blobs-tmp");
+        std::fs::create_dir_all(&default_temp_dir)?;
+
         // Create persistent blob store
         let blobs = Blobs::persistent(&blobs_dir)
             .await
@@ -114,17 +419,202 @@ impl FileTransferManager {
         let (progress_tx, _) = broadcast::channel(256);
         let (event_tx, _) = broadcast::channel(256);
 
+        let transfers = load_unfinished_uploads(&db);
+        if !transfers.is_empty() {
+            tracing::info!("Restored {} unfinished uploads", transfers.len());
+        }
+
         tracing::info!("FileTransferManager initialized at {:?}", blobs_dir);
 
         Ok(Self {
             blobs: Arc::new(blobs),
+            endpoint: endpoint.clone(),
             node_id,
-            transfers: Arc::new(RwLock::new(HashMap::new())),
+            transfers: Arc::new(RwLock::new(transfers)),
             progress_tx,
             event_tx,
+            blobs_dir,
+            stats_cache: RwLock::new(None),
+            temp_dir: RwLock::new(default_temp_dir.clone()),
+            default_temp_dir,
+            download_rate: RwLock::new(None),
+            paused: AtomicBool::new(false),
+            chunk_size: AtomicUsize::new(DEFAULT_CHUNK_SIZE),
+            reliability: PeerReliability::load(db.clone()),
+            limiter: TransferLimiter::new(TransferLimits::default()),
+            cancelled: Notify::new(),
+            drained: RwLock::new(HashSet::new()),
+            bandwidth: Arc::new(BandwidthLimiter::new(None)),
+            db,
         })
     }
 
+    /// Protocol handler serving our blobs to peers (see `P2PEndpoint::serve`)
+    ///
+    /// Peers that failed the key check of an encrypted drive (see
+    /// `KeyProofTracker::may_serve_peer`) are refused.
+    pub fn blobs_protocol(
+        &self,
+        key_proofs: Arc<KeyProofTracker>,
+    ) -> KeyCheckedBlobs<Blobs<BlobStore>> {
+        KeyCheckedBlobs::new(self.blobs.as_ref().clone(), key_proofs)
+    }
+
+    /// Pause or resume network transfers
+    ///
+    /// While paused, downloads from peers are refused; transfers already in
+    /// flight run to completion.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    /// Check if network transfers are paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Write what the blob store holds in memory to its database
+    pub async fn flush_store(&self) -> Result<()> {
+        self.blobs
+            .store()
+            .sync()
+            .await
+            .context("Failed to flush blob store")
+    }
+
+    /// Stop a drive's transfers ahead of maintenance
+    ///
+    /// New transfers for the drive are refused and queued ones cancelled.
+    /// Transfers already in flight get up to `timeout` to finish. Returns
+    /// the number cancelled and the number still running afterwards.
+    pub async fn drain_drive(&self, drive_id: &DriveId, timeout: Duration) -> (usize, usize) {
+        self.drained.write().await.insert(*drive_id);
+
+        let drive_hex = hex::encode(drive_id.as_bytes());
+        let queued: Vec<String> = self
+            .transfers
+            .read()
+            .await
+            .values()
+            .filter(|t| t.drive_id == drive_hex)
+            .filter(|t| {
+                matches!(
+                    t.status,
+                    TransferStatus::Pending
+                        | TransferStatus::Queued
+                        | TransferStatus::Retrying { .. }
+                )
+            })
+            .map(|t| t.id.clone())
+            .collect();
+        for transfer_id in &queued {
+            let _ = self.cancel_transfer(transfer_id).await;
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let running = self
+                .transfers
+                .read()
+                .await
+                .values()
+                .filter(|t| t.drive_id == drive_hex && t.status == TransferStatus::InProgress)
+                .count();
+            if running == 0 || Instant::now() >= deadline {
+                return (queued.len(), running);
+            }
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Accept transfers for a drive again; returns false if it wasn't drained
+    pub async fn resume_drive(&self, drive_id: &DriveId) -> bool {
+        self.drained.write().await.remove(drive_id)
+    }
+
+    /// Get the number of bytes read from the blob store per chunk
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size.load(Ordering::Relaxed)
+    }
+
+    /// Set the number of bytes read from the blob store per chunk
+    ///
+    /// Pass `None` to restore the default. Sizes outside
+    /// [`MIN_CHUNK_SIZE`]..=[`MAX_CHUNK_SIZE`] are rejected.
+    pub fn set_chunk_size(&self, size: Option<usize>) -> Result<usize> {
+        let size = size.unwrap_or(DEFAULT_CHUNK_SIZE);
+        if !is_valid_chunk_size(size) {
+            anyhow::bail!(
+                "chunk size must be between {} and {} bytes",
+                MIN_CHUNK_SIZE,
+                MAX_CHUNK_SIZE
+            );
+        }
+        self.chunk_size.store(size, Ordering::Relaxed);
+        tracing::info!("Transfer chunk size set to {} bytes", size);
+        Ok(size)
+    }
+
+    /// Get the bandwidth limit in bytes/sec, or `None` if unlimited
+    pub fn bandwidth_limit(&self) -> Option<u64> {
+        self.bandwidth.limit()
+    }
+
+    /// Limit how fast blobs are imported and exported
+    ///
+    /// The limit applies to all transfers combined and takes effect
+    /// immediately, including for transfers already running. `None` or
+    /// zero means unlimited.
+    pub fn set_bandwidth_limit(&self, bytes_per_sec: Option<u64>) {
+        self.bandwidth.set_limit(bytes_per_sec);
+        match self.bandwidth.limit() {
+            Some(limit) => tracing::info!("Bandwidth limit set to {} bytes/sec", limit),
+            None => tracing::info!("Bandwidth limit removed"),
+        }
+    }
+
+    /// How many transfers may run at once
+    pub fn limits(&self) -> TransferLimits {
+        self.limiter.limits()
+    }
+
+    /// Set how many transfers may run at once, returning the limits applied
+    pub fn set_limits(&self, limits: TransferLimits) -> TransferLimits {
+        let limits = self.limiter.set_limits(limits);
+        tracing::info!(limits = ?limits, "Transfer limits updated");
+        limits
+    }
+
+    /// Download reliability scores of the peers we fetch from
+    pub fn reliability(&self) -> &PeerReliability {
+        &self.reliability
+    }
+
+    /// Get the directory partial downloads are written to
+    pub async fn temp_dir(&self) -> PathBuf {
+        self.temp_dir.read().await.clone()
+    }
+
+    /// Get the default download temp directory
+    pub fn default_temp_dir(&self) -> &Path {
+        &self.default_temp_dir
+    }
+
+    /// Set the download temp directory (None restores the default)
+    ///
+    /// The directory is created if it does not exist. Returns the directory
+    /// now in use.
+    pub async fn set_temp_dir(&self, dir: Option<PathBuf>) -> Result<PathBuf> {
+        let dir = dir.unwrap_or_else(|| self.default_temp_dir.clone());
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create temp directory {:?}", dir))?;
+
+        *self.temp_dir.write().await = dir.clone();
+        tracing::info!("Download temp directory set to {:?}", dir);
+        Ok(dir)
+    }
+
     /// Subscribe to transfer progress events
     pub fn subscribe_progress(&self) -> broadcast::Receiver<TransferProgress> {
         self.progress_tx.subscribe()
@@ -150,36 +640,31 @@ impl FileTransferManager {
         local_path: &Path,
         relative_path: &Path,
     ) -> Result<Hash> {
-        let transfer_id = generate_transfer_id();
-        let drive_id_str = hex::encode(drive_id.as_bytes());
-
         // Get file size for progress tracking
         let metadata = tokio::fs::metadata(local_path)
             .await
             .context("Failed to get file metadata")?;
         let total_bytes = metadata.len();
 
-        // Create transfer state
-        let state = TransferState {
-            id: transfer_id.clone(),
-            drive_id: drive_id_str.clone(),
-            path: relative_path.to_string_lossy().to_string(),
-            direction: TransferDirection::Upload,
-            status: TransferStatus::InProgress,
-            bytes_transferred: 0,
-            total_bytes,
-            hash: None,
-            error: None,
-        };
-
-        // Store transfer state
-        self.transfers.write().await.insert(transfer_id.clone(), state);
-
-        // Emit initial progress
-        self.emit_progress(&transfer_id).await;
+        let (transfer_id, _slot) = self
+            .start_transfer(
+                drive_id,
+                relative_path,
+                TransferDirection::Upload,
+                total_bytes,
+                None,
+                Some(local_path),
+            )
+            .await?;
 
         // Import file into blob store
-        let outcome = self.import_file(local_path).await?;
+        let outcome = match self.import_file(local_path).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                self.fail_transfer(&transfer_id, &e).await;
+                return Err(e);
+            }
+        };
 
         // Update transfer state with hash
         {
@@ -199,7 +684,8 @@ impl FileTransferManager {
             path: relative_path.to_path_buf(),
             hash: outcome.to_hex().to_string(),
         };
-        send_with_backpressure(&self.event_tx, (*drive_id, event), "transfer_events");
+        let strategy = event.backpressure_strategy(None);
+        send_with_backpressure(&self.event_tx, (*drive_id, event), "transfer_events", strategy);
 
         tracing::info!(
             "Uploaded file {} -> hash {}",
@@ -210,158 +696,1442 @@ impl FileTransferManager {
         Ok(outcome)
     }
 
-    /// Download a file from the blob store to local filesystem
+    /// Upload a large file as content-defined chunks
     ///
-    /// This exports a blob from the store to a local file path.
-    /// Uses atomic writes (temp file → rename) to prevent partial writes.
-    pub async fn download_file(
+    /// The file is split into chunks (see `chunking`) and only chunks that
+    /// aren't stored yet are imported. Chunks unchanged since the last
+    /// upload are already stored, so after a small edit to a large file
+    /// peers holding the old version fetch one or two chunks instead of
+    /// the whole file; `chunks_changed` counts chunks that differ from
+    /// `previous`, that upload's manifest.
+    /// The chunk list is imported as a manifest blob whose hash goes into
+    /// `FileMetadata::manifest_hash`. The whole file is not imported as one
+    /// blob; readers put the content together from the manifest (see
+    /// `stream_content`).
+    pub async fn upload_file_delta(
         &self,
         drive_id: &DriveId,
-        hash: Hash,
         local_path: &Path,
         relative_path: &Path,
-    ) -> Result<()> {
-        let transfer_id = generate_transfer_id();
-        let drive_id_str = hex::encode(drive_id.as_bytes());
-
-        // Get blob size for progress tracking
-        let store = self.blobs.store();
-        let entry = store
-            .get(&hash)
-            .await?
-            .context("Blob not found in store")?;
-        let total_bytes = entry.size().value();
+        previous: Option<&ChunkManifest>,
+    ) -> Result<DeltaUpload> {
+        let total_bytes = tokio::fs::metadata(local_path)
+            .await
+            .context("Failed to get file metadata")?
+            .len();
+
+        let (transfer_id, _slot) = self
+            .start_transfer(
+                drive_id,
+                relative_path,
+                TransferDirection::Upload,
+                total_bytes,
+                None,
+                Some(local_path),
+            )
+            .await?;
 
-        // Create transfer state
-        let state = TransferState {
-            id: transfer_id.clone(),
-            drive_id: drive_id_str.clone(),
-            path: relative_path.to_string_lossy().to_string(),
-            direction: TransferDirection::Download,
-            status: TransferStatus::InProgress,
-            bytes_transferred: 0,
-            total_bytes,
-            hash: Some(hash.to_hex().to_string()),
-            error: None,
+        let upload = match self.import_chunked(&transfer_id, local_path, previous).await {
+            Ok(upload) => upload,
+            Err(e) => {
+                self.fail_transfer(&transfer_id, &e).await;
+                return Err(e);
+            }
         };
+        *self.stats_cache.write().await = None;
 
-        self.transfers.write().await.insert(transfer_id.clone(), state);
+        {
+            let mut transfers = self.transfers.write().await;
+            if let Some(state) = transfers.get_mut(&transfer_id) {
+                state.status = TransferStatus::Completed;
+                state.bytes_transferred = total_bytes;
+                state.hash = Some(upload.hash.to_hex().to_string());
+            }
+        }
         self.emit_progress(&transfer_id).await;
 
-        // Create parent directories if needed
-        if let Some(parent) = local_path.parent() {
-            tokio::fs::create_dir_all(parent).await?;
+        let event = DriveEvent::SyncComplete {
+            path: relative_path.to_path_buf(),
+            hash: upload.hash.to_hex().to_string(),
+        };
+        let strategy = event.backpressure_strategy(None);
+        send_with_backpressure(&self.event_tx, (*drive_id, event), "transfer_events", strategy);
+
+        tracing::info!(
+            "Uploaded file {} in {} chunks ({} changed, {} imported) -> hash {}",
+            local_path.display(),
+            upload.manifest.chunks.len(),
+            upload.chunks_changed,
+            upload.chunks_imported,
+            upload.hash.to_hex()
+        );
+
+        Ok(upload)
+    }
+
+    /// Split a file into chunks and import the new ones, then the manifest
+    async fn import_chunked(
+        &self,
+        transfer_id: &str,
+        local_path: &Path,
+        previous: Option<&ChunkManifest>,
+    ) -> Result<DeltaUpload> {
+        use tokio::io::AsyncReadExt;
+
+        let mut file = tokio::fs::File::open(local_path)
+            .await
+            .context("Failed to open file for import")?;
+        let mut chunker = Chunker::new(ChunkParams::default());
+        let mut whole = blake3::Hasher::new();
+        let mut manifest = ChunkManifest::default();
+        let mut chunks_imported = 0;
+        let mut pending = Vec::new();
+        let mut buffer = vec![0u8; self.chunk_size()];
+        let mut reported_at = Instant::now();
+
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            self.bandwidth.consume(read).await;
+            whole.update(&buffer[..read]);
+            manifest.size += read as u64;
+
+            let mut rest = &buffer[..read];
+            while let Some(cut) = chunker.next_cut(rest) {
+                pending.extend_from_slice(&rest[..cut]);
+                let chunk = std::mem::take(&mut pending);
+                if self.import_chunk(chunk, &mut manifest).await? {
+                    chunks_imported += 1;
+                }
+                rest = &rest[cut..];
+            }
+            pending.extend_from_slice(rest);
+
+            if reported_at.elapsed() >= PROGRESS_INTERVAL {
+                reported_at = Instant::now();
+                if let Some(state) = self.transfers.write().await.get_mut(transfer_id) {
+                    state.bytes_transferred = manifest.size;
+                }
+                self.emit_progress(transfer_id).await;
+            }
+        }
+        if !pending.is_empty() && self.import_chunk(pending, &mut manifest).await? {
+            chunks_imported += 1;
+        }
+
+        let hash = Hash::from_bytes(*whole.finalize().as_bytes());
+        manifest.hash = hash.to_hex().to_string();
+        // An empty file has no chunks, so its manifest stands for a whole blob
+        if manifest.is_single_blob() && !self.has_blob(&manifest.hash).await {
+            self.blobs
+                .store()
+                .import_bytes(Vec::new().into(), BlobFormat::Raw)
+                .await
+                .context("Failed to import empty file")?;
+        }
+        let tag = self
+            .blobs
+            .store()
+            .import_bytes(serde_json::to_vec(&manifest)?.into(), BlobFormat::Raw)
+            .await
+            .context("Failed to import chunk manifest")?;
+
+        let chunks_changed = match previous {
+            Some(previous) => manifest.changed_chunks(previous).len(),
+            None => manifest.chunks.len(),
+        };
+        Ok(DeltaUpload {
+            hash,
+            manifest_hash: *tag.hash(),
+            manifest,
+            chunks_changed,
+            chunks_imported,
+        })
+    }
+
+    /// Add a chunk to `manifest`, importing it unless it is already stored
+    ///
+    /// Returns whether the chunk was imported.
+    async fn import_chunk(&self, data: Vec<u8>, manifest: &mut ChunkManifest) -> Result<bool> {
+        let hash = blake3::hash(&data).to_hex().to_string();
+        let size = data.len() as u64;
+        let imported = !self.has_blob(&hash).await;
+        if imported {
+            self.blobs
+                .store()
+                .import_bytes(data.into(), BlobFormat::Raw)
+                .await
+                .context("Failed to import chunk")?;
+        }
+        manifest.chunks.push(ChunkRef { hash, size });
+        Ok(imported)
+    }
+
+    /// Read a chunk manifest from the blob store
+    pub async fn read_manifest(&self, manifest_hash: Hash) -> Result<ChunkManifest> {
+        let (bytes, size) = self.read_blob_prefix(manifest_hash, MAX_MANIFEST_BYTES).await?;
+        if size > MAX_MANIFEST_BYTES {
+            anyhow::bail!("Chunk manifest {} is too large", manifest_hash.to_hex());
+        }
+        serde_json::from_slice(&bytes).context("Invalid chunk manifest")
+    }
+
+    /// Download a file uploaded in chunks
+    ///
+    /// Fetches the manifest and any chunks not already stored from
+    /// `providers`, then writes the chunks in order to the download temp
+    /// file. The result is checked against `expected_hash` (or the hash in
+    /// the manifest) before it is moved to `local_path`, like
+    /// `download_file`. A manifest without chunks stands for a whole blob,
+    /// which is fetched as usual.
+    pub async fn download_chunked(
+        &self,
+        drive_id: &DriveId,
+        manifest_hash: Hash,
+        providers: &[iroh::NodeId],
+        local_path: &Path,
+        relative_path: &Path,
+        expected_hash: Option<&str>,
+    ) -> Result<()> {
+        if self.is_paused() {
+            anyhow::bail!("Transfers are paused");
+        }
+
+        let (transfer_id, _slot) = self
+            .start_transfer(
+                drive_id,
+                relative_path,
+                TransferDirection::Download,
+                0,
+                expected_hash.map(str::to_string),
+                None,
+            )
+            .await?;
+
+        let started = Instant::now();
+        let temp_path = self
+            .temp_dir()
+            .await
+            .join(format!("{}.download", transfer_id));
+        let result: Result<(Hash, Option<u64>)> = async {
+            self.fetch_any(manifest_hash, providers).await?;
+            let manifest = self.read_manifest(manifest_hash).await?;
+            let hash: Hash = manifest.hash.parse().context("Invalid hash in manifest")?;
+            if manifest.is_single_blob() {
+                self.fetch_any(hash, providers).await?;
+                return Ok((hash, None));
+            }
+
+            if let Some(state) = self.transfers.write().await.get_mut(&transfer_id) {
+                state.total_bytes = manifest.size;
+            }
+            self.emit_progress(&transfer_id).await;
+
+            let mut fetched = 0;
+            for chunk in manifest.unique_chunks() {
+                if self.has_blob(chunk).await {
+                    continue;
+                }
+                if self.is_transfer_cancelled(&transfer_id).await {
+                    anyhow::bail!("Transfer {} was cancelled", transfer_id);
+                }
+                self.fetch_any(chunk.parse()?, providers).await?;
+                fetched += 1;
+            }
+            tracing::debug!(
+                "Fetched {} of {} chunks for {}",
+                fetched,
+                manifest.chunks.len(),
+                relative_path.display()
+            );
+
+            if let Some(parent) = local_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            self.assemble_chunks(&transfer_id, &manifest, &temp_path).await?;
+            let expected = expected_hash.unwrap_or(&manifest.hash);
+            verify_content(&temp_path, relative_path, expected).await?;
+            move_into_place(&temp_path, local_path)
+                .await
+                .context("Failed to move download into place")?;
+            Ok((hash, Some(manifest.size)))
+        }
+        .await;
+
+        match result {
+            Ok((hash, None)) => {
+                self.write_download(
+                    &transfer_id,
+                    drive_id,
+                    hash,
+                    local_path,
+                    relative_path,
+                    expected_hash,
+                )
+                .await
+            }
+            Ok((hash, Some(total_bytes))) => {
+                {
+                    let mut transfers = self.transfers.write().await;
+                    if let Some(state) = transfers.get_mut(&transfer_id) {
+                        state.status = TransferStatus::Completed;
+                        state.bytes_transferred = total_bytes;
+                    }
+                }
+                self.emit_progress(&transfer_id).await;
+                self.record_download_rate(total_bytes, started.elapsed()).await;
+                self.announce_download(drive_id, hash, local_path, relative_path, total_bytes);
+                Ok(())
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                self.fail_transfer(&transfer_id, &e).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Write a manifest's chunks in order to `path`
+    async fn assemble_chunks(
+        &self,
+        transfer_id: &str,
+        manifest: &ChunkManifest,
+        path: &Path,
+    ) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        let mut file = tokio::fs::File::create(path).await?;
+        self.write_chunks(Some(transfer_id), manifest, &mut file)
+            .await?;
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Write a manifest's chunks in order to `writer`
+    ///
+    /// Progress is reported to `transfer_id`'s transfer, if given. Returns
+    /// the number of bytes written.
+    async fn write_chunks(
+        &self,
+        transfer_id: Option<&str>,
+        manifest: &ChunkManifest,
+        writer: &mut (impl tokio::io::AsyncWrite + Unpin),
+    ) -> Result<u64> {
+        use iroh_io::AsyncSliceReader;
+        use tokio::io::AsyncWriteExt;
+
+        let store = self.blobs.store();
+        let mut written = 0u64;
+        let max_chunk = self.chunk_size() as u64;
+        let mut reported_at = Instant::now();
+
+        for chunk in &manifest.chunks {
+            let hash: Hash = chunk.hash.parse()?;
+            let entry = store
+                .get(&hash)
+                .await?
+                .with_context(|| format!("Chunk {} not found", chunk.hash))?;
+            let mut reader = entry.data_reader();
+            let mut offset = 0u64;
+            while offset < chunk.size {
+                let len = max_chunk.min(chunk.size - offset) as usize;
+                let data = reader.read_at(offset, len).await?;
+                if data.is_empty() {
+                    anyhow::bail!("Chunk {} is shorter than its manifest says", chunk.hash);
+                }
+                self.bandwidth.consume(data.len()).await;
+                writer.write_all(&data).await?;
+                offset += data.len() as u64;
+                written += data.len() as u64;
+            }
+
+            if let Some(transfer_id) = transfer_id {
+                if reported_at.elapsed() >= PROGRESS_INTERVAL {
+                    reported_at = Instant::now();
+                    if let Some(state) = self.transfers.write().await.get_mut(transfer_id) {
+                        state.bytes_transferred = written;
+                    }
+                    self.emit_progress(transfer_id).await;
+                }
+            }
+        }
+
+        Ok(written)
+    }
+
+    /// Manifest `hash`'s content can be put together from, if every chunk
+    /// is stored
+    ///
+    /// None if there is no manifest, it is for other content or stands for
+    /// a whole blob, or a chunk is missing.
+    async fn stored_manifest(
+        &self,
+        hash: Hash,
+        manifest_hash: Option<Hash>,
+    ) -> Option<ChunkManifest> {
+        let manifest = self.read_manifest(manifest_hash?).await.ok()?;
+        if manifest.is_single_blob() || !manifest.hash.eq_ignore_ascii_case(&hash.to_hex()) {
+            return None;
+        }
+        for chunk in manifest.unique_chunks() {
+            if !self.has_blob(chunk).await {
+                return None;
+            }
+        }
+        Some(manifest)
+    }
+
+    /// Whether a file's content is stored, as a whole blob or as the chunks
+    /// of `manifest_hash`
+    pub async fn has_content(&self, hash: &str, manifest_hash: Option<&str>) -> bool {
+        let Ok(hash) = hash.parse::<Hash>() else {
+            return false;
+        };
+        let manifest_hash = manifest_hash.and_then(|m| m.parse::<Hash>().ok());
+        self.content_size(hash, manifest_hash).await.is_some()
+    }
+
+    /// Size of a file's stored content, as a whole blob or as the chunks of
+    /// `manifest_hash`; None if it isn't stored
+    pub async fn content_size(&self, hash: Hash, manifest_hash: Option<Hash>) -> Option<u64> {
+        if self.has_blob(&hash.to_hex()).await {
+            return self.blob_size(&hash).await.ok().flatten();
+        }
+        self.stored_manifest(hash, manifest_hash)
+            .await
+            .map(|manifest| manifest.size)
+    }
+
+    /// Write a file's stored content to `writer`
+    ///
+    /// Content uploaded in chunks is put together from `manifest_hash`, so
+    /// callers that only know the content hash of such a file need the
+    /// manifest too. Returns the number of bytes written.
+    pub async fn stream_content(
+        &self,
+        hash: Hash,
+        manifest_hash: Option<Hash>,
+        writer: &mut (impl tokio::io::AsyncWrite + Unpin),
+    ) -> Result<u64> {
+        if self.has_blob(&hash.to_hex()).await {
+            return self.stream_blob(hash, writer).await;
+        }
+        let manifest = self
+            .stored_manifest(hash, manifest_hash)
+            .await
+            .with_context(|| format!("Content {} not available locally", hash.to_hex()))?;
+        self.write_chunks(None, &manifest, writer).await
+    }
+
+    /// Export a file's stored content to a path outside any drive
+    ///
+    /// Like `export_blob`, but content uploaded in chunks is put together
+    /// from `manifest_hash` and checked against `hash` before it is moved
+    /// into place.
+    pub async fn export_content(
+        &self,
+        hash: Hash,
+        manifest_hash: Option<Hash>,
+        dest: &Path,
+    ) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        if self.has_blob(&hash.to_hex()).await {
+            return self.export_blob(hash, dest).await;
+        }
+        let temp_path = self
+            .temp_dir()
+            .await
+            .join(format!("{}.export", generate_transfer_id()));
+        let result = async {
+            let mut file = tokio::fs::File::create(&temp_path).await?;
+            self.stream_content(hash, manifest_hash, &mut file).await?;
+            file.flush().await?;
+            verify_content(&temp_path, dest, &hash.to_hex()).await?;
+            move_into_place(&temp_path, dest)
+                .await
+                .context("Failed to move export into place")
+        }
+        .await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+        }
+        result
+    }
+
+    /// Read up to `max_bytes` of a file's stored content into memory
+    ///
+    /// Like `read_blob_prefix`, but content uploaded in chunks is read from
+    /// the chunks of `manifest_hash`.
+    pub async fn read_content_prefix(
+        &self,
+        hash: Hash,
+        manifest_hash: Option<Hash>,
+        max_bytes: u64,
+    ) -> Result<(Vec<u8>, u64)> {
+        if self.has_blob(&hash.to_hex()).await {
+            return self.read_blob_prefix(hash, max_bytes).await;
+        }
+        let manifest = self
+            .stored_manifest(hash, manifest_hash)
+            .await
+            .with_context(|| format!("Content {} not available locally", hash.to_hex()))?;
+
+        let mut buffer = Vec::new();
+        for chunk in &manifest.chunks {
+            let remaining = max_bytes.saturating_sub(buffer.len() as u64);
+            if remaining == 0 {
+                break;
+            }
+            let (data, _) = self
+                .read_blob_prefix(chunk.hash.parse()?, remaining)
+                .await?;
+            buffer.extend_from_slice(&data);
+        }
+        Ok((buffer, manifest.size))
+    }
+
+    /// Check that a file's stored content still hashes as recorded
+    ///
+    /// Like `verify_blob`, but content uploaded in chunks is checked chunk
+    /// by chunk against `manifest_hash`. False if the content isn't stored.
+    pub async fn verify_content_blobs(
+        &self,
+        hash: Hash,
+        manifest_hash: Option<Hash>,
+    ) -> Result<bool> {
+        if self.has_blob(&hash.to_hex()).await {
+            return self.verify_blob(hash).await;
+        }
+        let Some(manifest) = self.stored_manifest(hash, manifest_hash).await else {
+            return Ok(false);
+        };
+        for chunk in manifest.unique_chunks() {
+            if !self.verify_blob(chunk.parse()?).await? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Resume an upload that was interrupted or failed
+    ///
+    /// The local file is hashed and checked against the hash recorded by
+    /// an earlier attempt; if it changed, the upload takes the new content.
+    /// A complete, intact blob already in the store is not imported again.
+    /// Otherwise this is a restart: iroh-blobs imports files in one step,
+    /// so the file is imported again from byte 0. Each failure counts
+    /// towards `MAX_RESUME_FAILURES`.
+    pub async fn resume_transfer(&self, transfer_id: &str) -> Result<Hash> {
+        let state = self
+            .get_transfer(transfer_id)
+            .await
+            .with_context(|| format!("Transfer {} not found", transfer_id))?;
+        if !state.is_resumable_upload()
+            || !matches!(state.status, TransferStatus::Pending | TransferStatus::Failed)
+        {
+            anyhow::bail!("Transfer {} cannot be resumed", transfer_id);
+        }
+        let local_path = state
+            .local_path
+            .clone()
+            .with_context(|| format!("Transfer {} has no local file", transfer_id))?;
+        let drive_id = DriveId::from_hex(&state.drive_id).context("Invalid drive ID")?;
+        if self.drained.read().await.contains(&drive_id) {
+            anyhow::bail!("Drive {} is drained", drive_id);
+        }
+
+        {
+            let mut transfers = self.transfers.write().await;
+            if let Some(state) = transfers.get_mut(transfer_id) {
+                state.status = TransferStatus::Queued;
+                state.error = None;
+            }
+        }
+        self.emit_progress(transfer_id).await;
+        let _slot = self.wait_for_slot(transfer_id, &drive_id).await?;
+
+        let outcome = match self.resume_import(transfer_id, &state, &local_path).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                if let Some(state) = self.transfers.write().await.get_mut(transfer_id) {
+                    state.resume_failures += 1;
+                }
+                self.fail_transfer(transfer_id, &e).await;
+                return Err(e);
+            }
+        };
+
+        {
+            let mut transfers = self.transfers.write().await;
+            if let Some(state) = transfers.get_mut(transfer_id) {
+                state.status = TransferStatus::Completed;
+                state.bytes_transferred = state.total_bytes;
+                state.hash = Some(outcome.to_hex().to_string());
+            }
+        }
+        self.emit_progress(transfer_id).await;
+
+        let event = DriveEvent::SyncComplete {
+            path: PathBuf::from(&state.path),
+            hash: outcome.to_hex().to_string(),
+        };
+        let strategy = event.backpressure_strategy(None);
+        send_with_backpressure(&self.event_tx, (drive_id, event), "transfer_events", strategy);
+
+        tracing::info!(
+            "Resumed upload {} -> hash {}",
+            local_path.display(),
+            outcome.to_hex()
+        );
+
+        Ok(outcome)
+    }
+
+    /// Bring the blob store up to date with a resumed upload's file
+    ///
+    /// Progress restarts at 0 since nothing but the recorded hash carries
+    /// over from the earlier attempt.
+    async fn resume_import(
+        &self,
+        transfer_id: &str,
+        state: &TransferState,
+        local_path: &Path,
+    ) -> Result<Hash> {
+        let total_bytes = tokio::fs::metadata(local_path)
+            .await
+            .context("Failed to get file metadata")?
+            .len();
+        let path = local_path.to_path_buf();
+        let hex = tokio::task::spawn_blocking(move || crate::core::file::hash_file(&path))
+            .await??;
+        let hash: Hash = hex.parse()?;
+
+        let changed = state
+            .hash
+            .as_deref()
+            .is_some_and(|recorded| !recorded.eq_ignore_ascii_case(&hex));
+        if changed {
+            tracing::info!(
+                "File {} changed since upload {} began, uploading the new content",
+                local_path.display(),
+                transfer_id
+            );
+        }
+        {
+            let mut transfers = self.transfers.write().await;
+            if let Some(state) = transfers.get_mut(transfer_id) {
+                state.bytes_transferred = 0;
+                state.total_bytes = total_bytes;
+                state.hash = Some(hex);
+            }
+        }
+        self.emit_progress(transfer_id).await;
+
+        let stored = self.blobs.store().get(&hash).await?;
+        match stored {
+            Some(entry) if entry.is_complete() => {
+                if self.verify_blob(hash).await? {
+                    return Ok(hash);
+                }
+                tracing::warn!("Stored blob {} is corrupted, importing again", hash.to_hex());
+                self.replace_blob(local_path).await
+            }
+            _ => {
+                let imported = self.import_file(local_path).await?;
+                *self.stats_cache.write().await = None;
+                Ok(imported)
+            }
+        }
+    }
+
+    /// Download a file from the blob store to local filesystem
+    ///
+    /// This exports a blob from the store to a local file path.
+    /// The blob is written to the download temp directory first and then
+    /// moved into place, so partial files never appear inside the drive.
+    ///
+    /// The written content is hashed and compared against `expected_hash`
+    /// (the metadata's content hash), or the blob hash if none is given. On
+    /// a mismatch the file is discarded, no change event is emitted, and a
+    /// `ContentMismatch` error is returned.
+    pub async fn download_file(
+        &self,
+        drive_id: &DriveId,
+        hash: Hash,
+        local_path: &Path,
+        relative_path: &Path,
+        expected_hash: Option<&str>,
+    ) -> Result<()> {
+        let (transfer_id, _slot) = self
+            .start_transfer(
+                drive_id,
+                relative_path,
+                TransferDirection::Download,
+                0,
+                Some(hash.to_hex().to_string()),
+                None,
+            )
+            .await?;
+        self.write_download(&transfer_id, drive_id, hash, local_path, relative_path, expected_hash)
+            .await
+    }
+
+    /// Write a stored blob into the drive for a started download transfer
+    async fn write_download(
+        &self,
+        transfer_id: &str,
+        drive_id: &DriveId,
+        hash: Hash,
+        local_path: &Path,
+        relative_path: &Path,
+        expected_hash: Option<&str>,
+    ) -> Result<()> {
+        let transfer_id = transfer_id.to_string();
+
+        // Get blob size for progress tracking, and create parent directories
+        let prepared = async {
+            let entry = self
+                .blobs
+                .store()
+                .get(&hash)
+                .await?
+                .context("Blob not found in store")?;
+            if let Some(parent) = local_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            anyhow::Ok(entry.size().value())
+        }
+        .await;
+        let total_bytes = match prepared {
+            Ok(total_bytes) => total_bytes,
+            Err(e) => {
+                self.fail_transfer(&transfer_id, &e).await;
+                return Err(e);
+            }
+        };
+        if let Some(state) = self.transfers.write().await.get_mut(&transfer_id) {
+            state.total_bytes = total_bytes;
+        }
+        self.emit_progress(&transfer_id).await;
+
+        // Write to the temp directory, then move into place
+        let temp_path = self
+            .temp_dir()
+            .await
+            .join(format!("{}.download", transfer_id));
+
+        let expected = expected_hash
+            .map(str::to_string)
+            .unwrap_or_else(|| hash.to_hex().to_string());
+
+        let started = Instant::now();
+        let result = async {
+            self.export_file(hash, &temp_path, Some(&transfer_id)).await?;
+            verify_content(&temp_path, relative_path, &expected).await?;
+            move_into_place(&temp_path, local_path)
+                .await
+                .context("Failed to move download into place")
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                // Update transfer state
+                {
+                    let mut transfers = self.transfers.write().await;
+                    if let Some(state) = transfers.get_mut(&transfer_id) {
+                        state.status = TransferStatus::Completed;
+                        state.bytes_transferred = total_bytes;
+                    }
+                }
+
+                self.emit_progress(&transfer_id).await;
+                self.record_download_rate(total_bytes, started.elapsed()).await;
+                self.announce_download(drive_id, hash, local_path, relative_path, total_bytes);
+
+                Ok(())
+            }
+            Err(e) => {
+                // Clean up temp file on error
+                let _ = tokio::fs::remove_file(&temp_path).await;
+
+                self.fail_transfer(&transfer_id, &e).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Emit the file changed event for a completed download
+    fn announce_download(
+        &self,
+        drive_id: &DriveId,
+        hash: Hash,
+        local_path: &Path,
+        relative_path: &Path,
+        size: u64,
+    ) {
+        let event = DriveEvent::FileChanged {
+            path: relative_path.to_path_buf(),
+            hash: hash.to_hex().to_string(),
+            size,
+            modified_by: self.node_id,
+            timestamp: Utc::now(),
+            created: false,
+            content_type: crate::core::content_type::detect_file(local_path),
+        };
+        let strategy = event.backpressure_strategy(None);
+        send_with_backpressure(&self.event_tx, (*drive_id, event), "transfer_events", strategy);
+
+        tracing::info!(
+            "Downloaded hash {} -> {}",
+            hash.to_hex(),
+            local_path.display()
+        );
+    }
+
+    /// Download a blob from several peers at once
+    ///
+    /// The blob is split into ranges (see `range_fetch`) that are fetched
+    /// concurrently, one per peer, straight into the download's temp file.
+    /// A peer that fails or stalls gets no more ranges and its range goes
+    /// to a peer that has finished one. The file is checked against `hash`
+    /// before it is moved to `local_path`, and imported into the blob
+    /// store so other peers can fetch it from us.
+    pub async fn download_multi_peer(
+        &self,
+        drive_id: &DriveId,
+        hash: Hash,
+        providers: Vec<iroh::NodeId>,
+        local_path: &Path,
+        relative_path: &Path,
+    ) -> Result<()> {
+        if self.is_paused() {
+            anyhow::bail!("Transfers are paused");
+        }
+        if providers.is_empty() {
+            anyhow::bail!("No peers to download blob {} from", hash.to_hex());
+        }
+
+        let (transfer_id, _slot) = self
+            .start_transfer(
+                drive_id,
+                relative_path,
+                TransferDirection::Download,
+                0,
+                Some(hash.to_hex().to_string()),
+                None,
+            )
+            .await?;
+        let temp_path = self
+            .temp_dir()
+            .await
+            .join(format!("{}.download", transfer_id));
+
+        let started = Instant::now();
+        let result = async {
+            let total_bytes = tokio::select! {
+                result = self.fetch_ranges(&transfer_id, hash, &providers, &temp_path) => result?,
+                _ = self.wait_cancelled(&transfer_id) => {
+                    anyhow::bail!("Transfer {} was cancelled", transfer_id)
+                }
+            };
+            verify_content(&temp_path, relative_path, &hash.to_hex()).await?;
+            self.import_file(&temp_path).await?;
+            *self.stats_cache.write().await = None;
+            if let Some(parent) = local_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            move_into_place(&temp_path, local_path)
+                .await
+                .context("Failed to move download into place")?;
+            anyhow::Ok(total_bytes)
+        }
+        .await;
+
+        match result {
+            Ok(total_bytes) => {
+                {
+                    let mut transfers = self.transfers.write().await;
+                    if let Some(state) = transfers.get_mut(&transfer_id) {
+                        state.status = TransferStatus::Completed;
+                        state.bytes_transferred = total_bytes;
+                    }
+                }
+                self.emit_progress(&transfer_id).await;
+                self.record_download_rate(total_bytes, started.elapsed()).await;
+                self.announce_download(drive_id, hash, local_path, relative_path, total_bytes);
+                Ok(())
+            }
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp_path).await;
+                if !self.is_transfer_cancelled(&transfer_id).await {
+                    self.fail_transfer(&transfer_id, &e).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Fetch a blob's ranges from `providers` into `path`, returning its size
+    async fn fetch_ranges(
+        &self,
+        transfer_id: &str,
+        hash: Hash,
+        providers: &[iroh::NodeId],
+        path: &Path,
+    ) -> Result<u64> {
+        let peers = self.reliability.rank(providers).await;
+
+        // Learn the size from the most reliable peer that answers
+        let mut connections = HashMap::new();
+        let mut idle = VecDeque::new();
+        let mut size = None;
+        let mut last_error = None;
+        for peer in peers {
+            if size.is_some() {
+                idle.push_back(peer);
+                continue;
+            }
+            let probe = async {
+                let connection = range_fetch::connect(&self.endpoint, peer).await?;
+                let size = range_fetch::fetch_size(&connection, &hash).await?;
+                anyhow::Ok((connection, size))
+            };
+            match probe.await {
+                Ok((connection, blob_size)) => {
+                    connections.insert(peer, connection);
+                    idle.push_back(peer);
+                    size = Some(blob_size);
+                }
+                Err(e) => {
+                    self.reliability.record_failure(peer).await;
+                    last_error = Some(e);
+                }
+            }
+        }
+        let Some(size) = size else {
+            return Err(last_error
+                .unwrap_or_else(|| anyhow::anyhow!("no providers tried"))
+                .context(format!("No peer could provide blob {}", hash.to_hex())));
+        };
+
+        if let Some(state) = self.transfers.write().await.get_mut(transfer_id) {
+            state.total_bytes = size;
+        }
+        self.emit_progress(transfer_id).await;
+
+        let file = tokio::fs::File::create(path).await?;
+        file.set_len(size).await?;
+        drop(file);
+
+        let mut pending: VecDeque<_> = range_fetch::plan_ranges(size, RANGE_CHUNKS).into();
+        let received = Arc::new(AtomicU64::new(0));
+        let mut tasks = JoinSet::new();
+        let mut progress = tokio::time::interval(PROGRESS_INTERVAL);
+
+        loop {
+            // Every idle peer gets the next range
+            while !pending.is_empty() && !idle.is_empty() {
+                let (Some(peer), Some(range)) = (idle.pop_front(), pending.pop_front()) else {
+                    break;
+                };
+                let connection = connections.get(&peer).cloned();
+                let endpoint = self.endpoint.clone();
+                let path = path.to_path_buf();
+                let received = received.clone();
+                tasks.spawn(async move {
+                    let started = Instant::now();
+                    let result = async {
+                        let connection = match connection {
+                            Some(connection) => connection,
+                            None => range_fetch::connect(&endpoint, peer).await?,
+                        };
+                        range_fetch::fetch_range(
+                            connection.clone(),
+                            hash,
+                            range.clone(),
+                            path,
+                            received,
+                        )
+                        .await?;
+                        anyhow::Ok(connection)
+                    }
+                    .await;
+                    (peer, range, started.elapsed(), result)
+                });
+            }
+
+            if tasks.is_empty() {
+                if pending.is_empty() {
+                    break;
+                }
+                anyhow::bail!("No peer could provide the rest of blob {}", hash.to_hex());
+            }
+
+            tokio::select! {
+                joined = tasks.join_next() => {
+                    let Some(joined) = joined else { continue };
+                    let (peer, range, elapsed, result) = joined.context("Range fetch panicked")?;
+                    match result {
+                        Ok(connection) => {
+                            self.reliability.record_success(peer, elapsed).await;
+                            connections.insert(peer, connection);
+                            idle.push_back(peer);
+                        }
+                        Err(e) => {
+                            tracing::debug!(
+                                "Fetching chunks {:?} of blob {} from {} failed: {:#}",
+                                range,
+                                hash.to_hex(),
+                                peer.fmt_short(),
+                                e
+                            );
+                            self.reliability.record_failure(peer).await;
+                            connections.remove(&peer);
+                            pending.push_front(range);
+                        }
+                    }
+                }
+                _ = progress.tick() => {
+                    if let Some(state) = self.transfers.write().await.get_mut(transfer_id) {
+                        state.bytes_transferred = received.load(Ordering::Relaxed);
+                    }
+                    self.emit_progress(transfer_id).await;
+                }
+            }
+        }
+
+        Ok(size)
+    }
+
+    /// Download a blob from whichever of `providers` can serve it
+    ///
+    /// Providers are tried one at a time, most reliable first (see
+    /// `PeerReliability`); each attempt's outcome and duration feed back
+    /// into that peer's score. Once the blob is stored it is written to
+    /// `local_path` like `download_file`.
+    pub async fn download_from_peer(
+        &self,
+        drive_id: &DriveId,
+        hash: Hash,
+        providers: &[iroh::NodeId],
+        local_path: &Path,
+        relative_path: &Path,
+    ) -> Result<()> {
+        self.download_with_retry(drive_id, hash, providers, local_path, relative_path, None, 1)
+            .await
+    }
+
+    /// Download a blob from `providers`, retrying failed fetches
+    ///
+    /// Like `download_from_peer`, but a fetch that fails is retried up to
+    /// `max_attempts` attempts in total, waiting 1s, 2s, 4s... (at most 30s)
+    /// in between. The transfer is `Retrying` while it waits and its error
+    /// lists every failed attempt. Cancelling it stops the retries. The
+    /// written content is checked against `expected_hash` like
+    /// `download_file`.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn download_with_retry(
+        &self,
+        drive_id: &DriveId,
+        hash: Hash,
+        providers: &[iroh::NodeId],
+        local_path: &Path,
+        relative_path: &Path,
+        expected_hash: Option<&str>,
+        max_attempts: u32,
+    ) -> Result<()> {
+        if self.is_paused() {
+            anyhow::bail!("Transfers are paused");
+        }
+
+        let (transfer_id, _slot) = self
+            .start_transfer(
+                drive_id,
+                relative_path,
+                TransferDirection::Download,
+                0,
+                Some(hash.to_hex().to_string()),
+                None,
+            )
+            .await?;
+        if let Err(e) = self.fetch_with_retry(&transfer_id, hash, providers, max_attempts).await {
+            // Every failed attempt is already in the error history
+            self.set_status(&transfer_id, TransferStatus::Failed).await;
+            return Err(e);
+        }
+        self.write_download(&transfer_id, drive_id, hash, local_path, relative_path, expected_hash)
+            .await
+    }
+
+    /// Fetch a blob for a running transfer, retrying with backoff
+    async fn fetch_with_retry(
+        &self,
+        transfer_id: &str,
+        hash: Hash,
+        providers: &[iroh::NodeId],
+        max_attempts: u32,
+    ) -> Result<()> {
+        let max_attempts = max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            let result = tokio::select! {
+                result = self.fetch_any(hash, providers) => result,
+                _ = self.wait_cancelled(transfer_id) => {
+                    anyhow::bail!("Transfer {} was cancelled", transfer_id)
+                }
+            };
+            let error = match result {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            self.record_failed_attempt(transfer_id, attempt, &error).await;
+            if attempt >= max_attempts {
+                return Err(error);
+            }
+            if self.is_paused() {
+                return Err(error.context("Transfers are paused"));
+            }
+
+            let delay = retry_delay(attempt);
+            tracing::debug!(
+                "Retrying transfer {} in {:?} (attempt {} of {})",
+                transfer_id,
+                delay,
+                attempt + 1,
+                max_attempts
+            );
+            if !self
+                .set_status(
+                    transfer_id,
+                    TransferStatus::Retrying {
+                        attempt,
+                        next_retry_ms: delay.as_millis() as u64,
+                    },
+                )
+                .await
+            {
+                anyhow::bail!("Transfer {} was cancelled", transfer_id);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = self.wait_cancelled(transfer_id) => {
+                    anyhow::bail!("Transfer {} was cancelled", transfer_id)
+                }
+            }
+            if !self.set_status(transfer_id, TransferStatus::InProgress).await {
+                anyhow::bail!("Transfer {} was cancelled", transfer_id);
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Append a failed attempt to a transfer's error history
+    async fn record_failed_attempt(&self, transfer_id: &str, attempt: u32, error: &anyhow::Error) {
+        let mut transfers = self.transfers.write().await;
+        if let Some(state) = transfers.get_mut(transfer_id) {
+            let line = format!("Attempt {}: {:#}", attempt, error);
+            state.error = Some(match state.error.take() {
+                Some(history) => format!("{}\n{}", history, line),
+                None => line,
+            });
+        }
+    }
+
+    /// Set a transfer's status unless it was cancelled; false if it was
+    async fn set_status(&self, transfer_id: &str, status: TransferStatus) -> bool {
+        {
+            let mut transfers = self.transfers.write().await;
+            match transfers.get_mut(transfer_id) {
+                Some(state) if state.status != TransferStatus::Cancelled => {
+                    state.status = status;
+                }
+                _ => return false,
+            }
+        }
+        self.emit_progress(transfer_id).await;
+        true
+    }
+
+    /// True if a transfer was cancelled (or is gone)
+    async fn is_transfer_cancelled(&self, transfer_id: &str) -> bool {
+        !matches!(
+            self.transfers.read().await.get(transfer_id),
+            Some(state) if state.status != TransferStatus::Cancelled
+        )
+    }
+
+    /// Resolve once a transfer is cancelled
+    async fn wait_cancelled(&self, transfer_id: &str) {
+        loop {
+            let notified = self.cancelled.notified();
+            if self.is_transfer_cancelled(transfer_id).await {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Make sure a blob is stored locally, fetching it from `providers`
+    async fn fetch_any(&self, hash: Hash, providers: &[iroh::NodeId]) -> Result<()> {
+        // Nothing to fetch if the blob is already stored
+        let store = self.blobs.store();
+        if let Some(entry) = store.get(&hash).await? {
+            if entry.is_complete() {
+                return Ok(());
+            }
+        }
+
+        if providers.is_empty() {
+            anyhow::bail!("No peers to download blob {} from", hash.to_hex());
+        }
+
+        let mut last_error = None;
+        for peer in self.reliability.rank(providers).await {
+            let started = Instant::now();
+            match self.fetch_from(hash, peer).await {
+                Ok(()) => {
+                    self.reliability
+                        .record_success(peer, started.elapsed())
+                        .await;
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::debug!(
+                        "Fetching blob {} from {} failed: {:#}",
+                        hash.to_hex(),
+                        peer.fmt_short(),
+                        e
+                    );
+                    self.reliability.record_failure(peer).await;
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error
+            .unwrap_or_else(|| anyhow::anyhow!("no providers tried"))
+            .context(format!("No peer could provide blob {}", hash.to_hex())))
+    }
+
+    /// Fetch a blob from one peer into the local store
+    async fn fetch_from(&self, hash: Hash, peer: iroh::NodeId) -> Result<()> {
+        let options = DownloadOptions {
+            format: BlobFormat::Raw,
+            nodes: vec![NodeAddr::new(peer)],
+            tag: SetTagOption::Auto,
+            mode: DownloadMode::Direct,
+        };
+        let progress = self
+            .blobs
+            .client()
+            .download_with_opts(hash, options)
+            .await?;
+        tokio::time::timeout(PEER_FETCH_TIMEOUT, progress.finish())
+            .await
+            .context("Timed out")??;
+        Ok(())
+    }
+
+    /// Export a locally stored blob to a path outside any drive
+    ///
+    /// Unlike `download_file` this is not tracked as a transfer and emits no
+    /// drive events. The blob is staged in the temp directory and moved into
+    /// place, so a partial file never appears at `dest`.
+    pub async fn export_blob(&self, hash: Hash, dest: &Path) -> Result<()> {
+        let temp_path = self
+            .temp_dir()
+            .await
+            .join(format!("{}.export", generate_transfer_id()));
+
+        let result = match self.export_file(hash, &temp_path, None).await {
+            Ok(()) => move_into_place(&temp_path, dest)
+                .await
+                .context("Failed to move export into place"),
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+        }
+        result
+    }
+
+    /// Write a locally stored blob's content to `writer`
+    ///
+    /// Reads in chunks of the configured size, no faster than the bandwidth
+    /// limit. Returns the number of bytes written.
+    pub async fn stream_blob(
+        &self,
+        hash: Hash,
+        writer: &mut (impl tokio::io::AsyncWrite + Unpin),
+    ) -> Result<u64> {
+        use iroh_io::AsyncSliceReader;
+        use tokio::io::AsyncWriteExt;
+
+        let store = self.blobs.store();
+        let entry = store
+            .get(&hash)
+            .await?
+            .filter(|entry| entry.is_complete())
+            .with_context(|| format!("Blob {} not available locally", hash.to_hex()))?;
+        let total_size = entry.size().value();
+
+        let mut reader = entry.data_reader();
+        let max_chunk = self.chunk_size() as u64;
+        let mut written = 0u64;
+        while written < total_size {
+            let chunk_size = max_chunk.min(total_size - written) as usize;
+            let data = reader.read_at(written, chunk_size).await?;
+            if data.is_empty() {
+                break;
+            }
+            self.bandwidth.consume(data.len()).await;
+            writer.write_all(&data).await?;
+            written += data.len() as u64;
         }
+        Ok(written)
+    }
 
-        // Use atomic write: write to temp file, then rename
-        let temp_path = local_path.with_extension("tmp.download");
-
-        // Export blob to temp file
-        match self.export_file(hash, &temp_path).await {
-            Ok(()) => {
-                // Atomic rename
-                tokio::fs::rename(&temp_path, local_path).await?;
+    /// Save `size` bytes read from `reader` to `dest` if they hash to `hash`
+    ///
+    /// Like `export_blob`, the content is staged in the temp directory and
+    /// only moved into place once it checked out, so `dest` never holds
+    /// partial or wrong content. Nothing is added to the blob store.
+    pub async fn save_verified(
+        &self,
+        reader: &mut (impl tokio::io::AsyncRead + Unpin),
+        size: u64,
+        hash: Hash,
+        dest: &Path,
+    ) -> Result<()> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-                // Update transfer state
-                {
-                    let mut transfers = self.transfers.write().await;
-                    if let Some(state) = transfers.get_mut(&transfer_id) {
-                        state.status = TransferStatus::Completed;
-                        state.bytes_transferred = total_bytes;
-                    }
+        let temp_path = self
+            .temp_dir()
+            .await
+            .join(format!("{}.download", generate_transfer_id()));
+        let result = async {
+            let mut file = tokio::fs::File::create(&temp_path).await?;
+            let mut hasher = blake3::Hasher::new();
+            let mut buffer = vec![0u8; self.chunk_size()];
+            let mut received = 0u64;
+            while received < size {
+                let want = buffer.len().min((size - received) as usize);
+                let read = reader.read(&mut buffer[..want]).await?;
+                if read == 0 {
+                    anyhow::bail!("Content ended after {} of {} bytes", received, size);
                 }
+                self.bandwidth.consume(read).await;
+                hasher.update(&buffer[..read]);
+                file.write_all(&buffer[..read]).await?;
+                received += read as u64;
+            }
+            file.flush().await?;
+            file.sync_all().await?;
+
+            let actual = Hash::from_bytes(*hasher.finalize().as_bytes());
+            anyhow::ensure!(
+                actual == hash,
+                "Content hash {} does not match {}",
+                actual.to_hex(),
+                hash.to_hex()
+            );
+            move_into_place(&temp_path, dest)
+                .await
+                .context("Failed to move file into place")
+        }
+        .await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+        }
+        result
+    }
 
-                self.emit_progress(&transfer_id).await;
-
-                // Emit file changed event
-                let event = DriveEvent::FileChanged {
-                    path: relative_path.to_path_buf(),
-                    hash: hash.to_hex().to_string(),
-                    size: total_bytes,
-                    modified_by: self.node_id,
-                    timestamp: Utc::now(),
-                };
-                send_with_backpressure(&self.event_tx, (*drive_id, event), "transfer_events");
-
-                tracing::info!(
-                    "Downloaded hash {} -> {}",
-                    hash.to_hex(),
-                    local_path.display()
-                );
+    /// Read up to `max_bytes` of a locally stored blob into memory
+    ///
+    /// Nothing is written to disk. Returns the bytes read and the blob's
+    /// total size, so callers can tell whether the content was cut short.
+    pub async fn read_blob_prefix(&self, hash: Hash, max_bytes: u64) -> Result<(Vec<u8>, u64)> {
+        use iroh_io::AsyncSliceReader;
 
-                Ok(())
-            }
-            Err(e) => {
-                // Clean up temp file on error
-                let _ = tokio::fs::remove_file(&temp_path).await;
+        let store = self.blobs.store();
+        let entry = store
+            .get(&hash)
+            .await?
+            .filter(|entry| entry.is_complete())
+            .with_context(|| format!("Blob {} not available locally", hash.to_hex()))?;
+        let total_size = entry.size().value();
+        let limit = total_size.min(max_bytes);
 
-                // Update transfer state
-                {
-                    let mut transfers = self.transfers.write().await;
-                    if let Some(state) = transfers.get_mut(&transfer_id) {
-                        state.status = TransferStatus::Failed;
-                        state.error = Some(e.to_string());
-                    }
-                }
+        let mut reader = entry.data_reader();
+        let mut buffer = Vec::with_capacity(limit as usize);
+        let max_chunk = self.chunk_size() as u64;
 
-                self.emit_progress(&transfer_id).await;
-                Err(e)
+        while (buffer.len() as u64) < limit {
+            let offset = buffer.len() as u64;
+            let chunk_size = max_chunk.min(limit - offset) as usize;
+            let data = reader.read_at(offset, chunk_size).await?;
+            if data.is_empty() {
+                break;
             }
+            buffer.extend_from_slice(&data);
         }
+
+        Ok((buffer, total_size))
     }
 
-    /// Download a blob from a remote peer
+    /// Check that a stored blob's content still hashes to `hash`
     ///
-    /// Fetches the blob from the specified peer and adds it to local store.
-    /// Note: This is a simplified implementation. Full peer-to-peer download
-    /// would require more complex coordination with the downloader.
-    pub async fn download_from_peer(
-        &self,
-        drive_id: &DriveId,
-        hash: Hash,
-        _peer_node_id: iroh::NodeId,
-        local_path: &Path,
-        relative_path: &Path,
-    ) -> Result<()> {
-        let transfer_id = generate_transfer_id();
-        let drive_id_str = hex::encode(drive_id.as_bytes());
+    /// Returns false if the blob is missing, incomplete or was corrupted on
+    /// disk. Reads the whole blob, in chunks of the configured size.
+    pub async fn verify_blob(&self, hash: Hash) -> Result<bool> {
+        use iroh_io::AsyncSliceReader;
 
-        // Create transfer state (size unknown until we start downloading)
-        let state = TransferState {
-            id: transfer_id.clone(),
-            drive_id: drive_id_str,
-            path: relative_path.to_string_lossy().to_string(),
-            direction: TransferDirection::Download,
-            status: TransferStatus::InProgress,
-            bytes_transferred: 0,
-            total_bytes: 0, // Unknown until we get the blob
-            hash: Some(hash.to_hex().to_string()),
-            error: None,
+        let store = self.blobs.store();
+        let Some(entry) = store.get(&hash).await?.filter(|entry| entry.is_complete()) else {
+            return Ok(false);
         };
+        let total_size = entry.size().value();
 
-        self.transfers.write().await.insert(transfer_id.clone(), state);
-        self.emit_progress(&transfer_id).await;
+        let mut reader = entry.data_reader();
+        let mut hasher = blake3::Hasher::new();
+        let mut offset = 0u64;
+        let max_chunk = self.chunk_size() as u64;
 
-        // Check if blob already exists in local store
-        let store = self.blobs.store();
-        if let Some(entry) = store.get(&hash).await? {
-            if entry.is_complete() {
-                // Blob already available locally, just export it
-                return self.download_file(drive_id, hash, local_path, relative_path).await;
+        while offset < total_size {
+            let chunk_size = max_chunk.min(total_size - offset) as usize;
+            let data = reader.read_at(offset, chunk_size).await?;
+            if data.is_empty() {
+                return Ok(false);
             }
+            hasher.update(&data);
+            offset += data.len() as u64;
         }
 
-        // TODO: Implement full peer-to-peer download using downloader
-        // For now, we require the blob to be available locally
-        // The full implementation would use:
-        // let request = DownloadRequest::new(HashAndFormat { hash, format: BlobFormat::Raw }, vec![peer_node_id.into()]);
-        // let handle = downloader.queue(request).await;
-        // handle.await?;
+        Ok(hasher.finalize().as_bytes() == hash.as_bytes())
+    }
 
-        anyhow::bail!(
-            "Blob {} not found locally. Peer-to-peer download not yet fully implemented.",
-            hash.to_hex()
-        )
+    /// Import a local file, replacing a stored blob with the same hash
+    ///
+    /// Unlike `reimport_file` this imports even if the blob is already
+    /// stored, for when `verify_blob` found the stored copy corrupted.
+    pub async fn replace_blob(&self, local_path: &Path) -> Result<Hash> {
+        let path = local_path.to_path_buf();
+        let hex = tokio::task::spawn_blocking(move || crate::core::file::hash_file(&path))
+            .await??;
+        let hash: Hash = hex.parse()?;
+
+        self.blobs
+            .store()
+            .delete(vec![hash])
+            .await
+            .context("Failed to remove stored blob")?;
+        let imported = self.import_file(local_path).await?;
+        *self.stats_cache.write().await = None;
+        Ok(imported)
     }
 
     /// Import a file into the blob store (internal helper)
@@ -369,6 +2139,10 @@ impl FileTransferManager {
     /// Uses iroh's import_file which computes the hash internally,
     /// avoiding the need to read the entire file into memory.
     async fn import_file(&self, path: &Path) -> Result<Hash> {
+        if self.bandwidth.limit().is_some() {
+            return self.import_file_throttled(path).await;
+        }
+
         let store = self.blobs.store();
         let path_buf = path.to_path_buf();
 
@@ -391,11 +2165,62 @@ impl FileTransferManager {
         Ok(*tag.hash())
     }
 
+    /// Import a file as a stream of chunks paced by the bandwidth limit
+    ///
+    /// `import_file` lets iroh read the file itself, which can't be slowed
+    /// down, so this is used instead while a limit is set.
+    async fn import_file_throttled(&self, path: &Path) -> Result<Hash> {
+        use futures_lite::StreamExt;
+        use iroh_blobs::util::progress::IgnoreProgressSender;
+        use tokio_util::io::ReaderStream;
+
+        let file = tokio::fs::File::open(path)
+            .await
+            .context("Failed to open file for import")?;
+        let bandwidth = self.bandwidth.clone();
+        let chunks = ReaderStream::with_capacity(file, self.chunk_size()).then(move |chunk| {
+            let bandwidth = bandwidth.clone();
+            async move {
+                if let Ok(data) = &chunk {
+                    bandwidth.consume(data.len()).await;
+                }
+                chunk
+            }
+        });
+
+        let (tag, _size) = self
+            .blobs
+            .store()
+            .import_stream(Box::pin(chunks), BlobFormat::Raw, IgnoreProgressSender::default())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to import file: {}", e))?;
+        Ok(*tag.hash())
+    }
+
+    /// Make sure a local file's content is in the blob store
+    ///
+    /// Hashes the file and imports it only if no complete blob with that
+    /// hash is stored. Returns the hash and whether the file was imported.
+    pub async fn reimport_file(&self, local_path: &Path) -> Result<(Hash, bool)> {
+        let path = local_path.to_path_buf();
+        let hex = tokio::task::spawn_blocking(move || crate::core::file::hash_file(&path))
+            .await??;
+        if self.has_blob(&hex).await {
+            return Ok((hex.parse()?, false));
+        }
+
+        let hash = self.import_file(local_path).await?;
+        *self.stats_cache.write().await = None;
+        Ok((hash, true))
+    }
+
     /// Export a blob to a file (internal helper)
     ///
     /// Uses streaming to avoid loading the entire blob into memory.
-    /// Reads in 64KB chunks and writes directly to disk.
-    async fn export_file(&self, hash: Hash, path: &Path) -> Result<()> {
+    /// Reads in chunks of the configured size (64KB by default) and writes
+    /// directly to disk, no faster than the bandwidth limit. Progress is
+    /// reported for `transfer_id`, if given.
+    async fn export_file(&self, hash: Hash, path: &Path, transfer_id: Option<&str>) -> Result<()> {
         use iroh_io::AsyncSliceReader;
         use tokio::io::AsyncWriteExt;
 
@@ -407,11 +2232,12 @@ impl FileTransferManager {
         let mut reader = entry.data_reader();
         let mut file = tokio::fs::File::create(path).await?;
         let mut written = 0u64;
-        const CHUNK_SIZE: usize = 64 * 1024; // 64KB chunks
+        let max_chunk = self.chunk_size() as u64;
+        let mut reported_at = Instant::now();
 
         while written < total_size {
             let remaining = total_size - written;
-            let chunk_size = std::cmp::min(CHUNK_SIZE as u64, remaining) as usize;
+            let chunk_size = std::cmp::min(max_chunk, remaining) as usize;
 
             // Read chunk from blob at current offset
             let data = reader.read_at(written, chunk_size).await?;
@@ -419,8 +2245,19 @@ impl FileTransferManager {
                 break;
             }
 
+            self.bandwidth.consume(data.len()).await;
             file.write_all(&data).await?;
             written += data.len() as u64;
+
+            if let Some(transfer_id) = transfer_id {
+                if reported_at.elapsed() >= PROGRESS_INTERVAL {
+                    reported_at = Instant::now();
+                    if let Some(state) = self.transfers.write().await.get_mut(transfer_id) {
+                        state.bytes_transferred = written;
+                    }
+                    self.emit_progress(transfer_id).await;
+                }
+            }
         }
 
         file.flush().await?;
@@ -428,9 +2265,14 @@ impl FileTransferManager {
     }
 
     /// Emit progress event for a transfer
+    ///
+    /// Uploads are persisted on every update so they can be resumed after
+    /// a restart.
     async fn emit_progress(&self, transfer_id: &str) {
-        let transfers = self.transfers.read().await;
-        if let Some(state) = transfers.get(transfer_id) {
+        let mut transfers = self.transfers.write().await;
+        if let Some(state) = transfers.get_mut(transfer_id) {
+            state.sample_rate(Instant::now());
+            self.persist_upload(state);
             let progress = TransferProgress {
                 transfer_id: state.id.clone(),
                 drive_id: state.drive_id.clone(),
@@ -439,8 +2281,31 @@ impl FileTransferManager {
                 bytes_transferred: state.bytes_transferred,
                 total_bytes: state.total_bytes,
                 status: state.status.clone(),
+                bytes_per_sec: state.bytes_per_sec,
             };
-            send_with_backpressure(&self.progress_tx, progress, "transfer_progress");
+            send_with_backpressure(
+                &self.progress_tx,
+                progress,
+                "transfer_progress",
+                BackpressureStrategy::DropOldest,
+            );
+        }
+    }
+
+    /// Save an unfinished upload, or forget it once it is done
+    fn persist_upload(&self, state: &TransferState) {
+        if state.direction != TransferDirection::Upload {
+            return;
+        }
+        let result = if state.is_resumable_upload() {
+            serde_json::to_vec(state)
+                .map_err(anyhow::Error::from)
+                .and_then(|data| self.db.save_transfer(&state.id, &data))
+        } else {
+            self.db.delete_transfer(&state.id).map(|_| ())
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to persist transfer {}: {}", state.id, e);
         }
     }
 
@@ -455,24 +2320,141 @@ impl FileTransferManager {
     }
 
     /// Cancel a transfer
+    ///
+    /// A queued transfer leaves the queue and never starts.
     pub async fn cancel_transfer(&self, transfer_id: &str) -> Result<()> {
         let mut transfers = self.transfers.write().await;
         if let Some(state) = transfers.get_mut(transfer_id) {
-            if state.status == TransferStatus::InProgress || state.status == TransferStatus::Pending {
+            if matches!(
+                state.status,
+                TransferStatus::InProgress
+                    | TransferStatus::Pending
+                    | TransferStatus::Queued
+                    | TransferStatus::Retrying { .. }
+            ) {
                 state.status = TransferStatus::Cancelled;
                 tracing::info!("Cancelled transfer: {}", transfer_id);
             }
+            self.persist_upload(state);
         }
+        drop(transfers);
+        self.cancelled.notify_waiters();
         Ok(())
     }
 
+    /// Register a transfer and wait for a slot to run it in
+    ///
+    /// The transfer is listed as `Queued` while it waits and `InProgress`
+    /// once it has a slot. Fails if it is cancelled before then.
+    async fn start_transfer(
+        &self,
+        drive_id: &DriveId,
+        relative_path: &Path,
+        direction: TransferDirection,
+        total_bytes: u64,
+        hash: Option<String>,
+        local_path: Option<&Path>,
+    ) -> Result<(String, TransferSlot)> {
+        if self.drained.read().await.contains(drive_id) {
+            anyhow::bail!("Drive {} is drained", drive_id);
+        }
+        let transfer_id = generate_transfer_id();
+        let state = TransferState {
+            id: transfer_id.clone(),
+            drive_id: hex::encode(drive_id.as_bytes()),
+            path: relative_path.to_string_lossy().to_string(),
+            direction,
+            status: TransferStatus::Queued,
+            bytes_transferred: 0,
+            total_bytes,
+            hash,
+            error: None,
+            started_at: Utc::now(),
+            local_path: local_path.map(Path::to_path_buf),
+            resume_failures: 0,
+            bytes_per_sec: None,
+            eta_secs: None,
+            rate: RateWindow::default(),
+        };
+        self.transfers.write().await.insert(transfer_id.clone(), state);
+        self.emit_progress(&transfer_id).await;
+        let slot = self.wait_for_slot(&transfer_id, drive_id).await?;
+        Ok((transfer_id, slot))
+    }
+
+    /// Wait for a slot to run a `Queued` transfer in
+    ///
+    /// Moves the transfer to `InProgress` once it has one. Fails if it is
+    /// cancelled before then.
+    async fn wait_for_slot(&self, transfer_id: &str, drive_id: &DriveId) -> Result<TransferSlot> {
+        let acquire = self.limiter.acquire(*drive_id);
+        tokio::pin!(acquire);
+        let slot = loop {
+            tokio::select! {
+                slot = &mut acquire => break slot?,
+                _ = self.cancelled.notified() => {
+                    if self.is_cancelled(transfer_id).await {
+                        anyhow::bail!("Transfer {} was cancelled before it started", transfer_id);
+                    }
+                }
+            }
+        };
+
+        {
+            let mut transfers = self.transfers.write().await;
+            match transfers.get_mut(transfer_id) {
+                Some(state) if state.status == TransferStatus::Queued => {
+                    state.status = TransferStatus::InProgress;
+                    state.started_at = Utc::now();
+                    state.reset_rate();
+                }
+                _ => anyhow::bail!("Transfer {} was cancelled before it started", transfer_id),
+            }
+        }
+        self.emit_progress(transfer_id).await;
+        Ok(slot)
+    }
+
+    /// True if a queued transfer was cancelled (or is gone)
+    async fn is_cancelled(&self, transfer_id: &str) -> bool {
+        !matches!(
+            self.transfers.read().await.get(transfer_id),
+            Some(state) if state.status == TransferStatus::Queued
+        )
+    }
+
+    /// Mark a transfer as failed with `error`
+    ///
+    /// Appended to the attempt history recorded by `download_with_retry`,
+    /// if there is one.
+    async fn fail_transfer(&self, transfer_id: &str, error: &anyhow::Error) {
+        {
+            let mut transfers = self.transfers.write().await;
+            if let Some(state) = transfers.get_mut(transfer_id) {
+                state.status = TransferStatus::Failed;
+                state.error = Some(match state.error.take() {
+                    Some(history) => format!("{}\n{}", history, error),
+                    None => error.to_string(),
+                });
+            }
+        }
+        self.emit_progress(transfer_id).await;
+    }
+
     /// Clean up completed/failed transfers older than the specified duration
     pub async fn cleanup_old_transfers(&self, _max_age: std::time::Duration) {
         // For now, just clear completed transfers
         // In a real implementation, we'd track timestamps
+        // Failed uploads stay listed so they can be resumed
         let mut transfers = self.transfers.write().await;
         transfers.retain(|_, state| {
-            state.status == TransferStatus::InProgress || state.status == TransferStatus::Pending
+            matches!(
+                state.status,
+                TransferStatus::InProgress
+                    | TransferStatus::Pending
+                    | TransferStatus::Queued
+                    | TransferStatus::Retrying { .. }
+            ) || state.is_resumable_upload()
         });
     }
 
@@ -481,6 +2463,85 @@ impl FileTransferManager {
         self.blobs.store()
     }
 
+    /// Smoothed throughput of completed downloads in bytes/sec
+    ///
+    /// None until a download large enough to measure has completed.
+    pub async fn download_throughput(&self) -> Option<f64> {
+        *self.download_rate.read().await
+    }
+
+    /// Fold a completed download into the throughput average
+    async fn record_download_rate(&self, bytes: u64, elapsed: Duration) {
+        // Tiny transfers are dominated by overhead and would skew the average
+        if bytes < 64 * 1024 || elapsed.is_zero() {
+            return;
+        }
+        let sample = bytes as f64 / elapsed.as_secs_f64();
+        let mut rate = self.download_rate.write().await;
+        *rate = Some(match *rate {
+            Some(avg) => avg + THROUGHPUT_SMOOTHING * (sample - avg),
+            None => sample,
+        });
+    }
+
+    /// Check if a complete blob with this hash (hex string) is stored locally
+    pub async fn has_blob(&self, hash: &str) -> bool {
+        let Ok(hash) = hash.parse::<Hash>() else {
+            return false;
+        };
+        matches!(self.blobs.store().get(&hash).await, Ok(Some(entry)) if entry.is_complete())
+    }
+
+    /// Get the size of a blob in the local store, if it is there
+    pub async fn blob_size(&self, hash: &Hash) -> Result<Option<u64>> {
+        Ok(self
+            .blobs
+            .store()
+            .get(hash)
+            .await?
+            .map(|entry| entry.size().value()))
+    }
+
+    /// Get blob count, size, and partial blobs for the store
+    ///
+    /// Results are cached for a short time since this walks every blob.
+    pub async fn blob_store_stats(&self) -> Result<BlobStoreStats> {
+        if let Some((at, stats)) = self.stats_cache.read().await.as_ref() {
+            if at.elapsed() < BLOB_STATS_TTL {
+                return Ok(stats.clone());
+            }
+        }
+
+        let store = self.blobs.store();
+        let mut stats = BlobStoreStats {
+            blob_count: 0,
+            total_bytes: 0,
+            partial_count: 0,
+            partial_bytes: 0,
+            path: self.blobs_dir.to_string_lossy().to_string(),
+            computed_at: Utc::now().to_rfc3339(),
+        };
+
+        for hash in store.blobs().await? {
+            let hash = hash?;
+            stats.blob_count += 1;
+            if let Some(entry) = store.get(&hash).await? {
+                stats.total_bytes += entry.size().value();
+            }
+        }
+
+        for hash in store.partial_blobs().await? {
+            let hash = hash?;
+            stats.partial_count += 1;
+            if let Some(entry) = store.get(&hash).await? {
+                stats.partial_bytes += entry.size().value();
+            }
+        }
+
+        *self.stats_cache.write().await = Some((Instant::now(), stats.clone()));
+        Ok(stats)
+    }
+
     /// Get blob hash for a file path (if it exists in store)
     ///
     /// Uses streaming BLAKE3 hasher to compute hash without loading
@@ -516,7 +2577,86 @@ impl FileTransferManager {
     }
 }
 
+/// Move a finished download from the temp directory to its destination
+///
+/// Tries a plain rename first. If that fails (typically because the temp
+/// directory is on another filesystem), copies to a hidden `.tmp` file next to
+/// the destination - which the watcher ignores - and renames that into place,
+/// so the destination still only ever sees the complete file.
+async fn move_into_place(temp_path: &Path, dest: &Path) -> std::io::Result<()> {
+    if tokio::fs::rename(temp_path, dest).await.is_ok() {
+        return Ok(());
+    }
+
+    let file_name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let staging = dest.with_file_name(format!(".{}.tmp", file_name));
+
+    let copied = async {
+        tokio::fs::copy(temp_path, &staging).await?;
+        tokio::fs::rename(&staging, dest).await
+    }
+    .await;
+
+    if let Err(e) = copied {
+        let _ = tokio::fs::remove_file(&staging).await;
+        return Err(e);
+    }
+
+    if let Err(e) = tokio::fs::remove_file(temp_path).await {
+        tracing::warn!("Failed to remove download temp file {:?}: {}", temp_path, e);
+    }
+    Ok(())
+}
+
 /// Generate a unique transfer ID
+/// Delay before retrying after failed attempt number `attempt` (from 1)
+fn retry_delay(attempt: u32) -> Duration {
+    let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+    RETRY_BASE_DELAY.saturating_mul(factor).min(RETRY_MAX_DELAY)
+}
+
+/// Load uploads left unfinished by a previous run
+///
+/// Uploads that were running or queued when the app stopped are listed as
+/// `Pending` until they are resumed.
+fn load_unfinished_uploads(db: &Database) -> HashMap<String, TransferState> {
+    let entries = match db.list_transfers() {
+        Ok(entries) => entries,
+        Err(e) => {
+            tracing::warn!("Failed to load unfinished transfers: {}", e);
+            return HashMap::new();
+        }
+    };
+
+    let mut transfers = HashMap::new();
+    for (id, data) in entries {
+        match serde_json::from_slice::<TransferState>(&data) {
+            Ok(state) if !state.is_resumable_upload() => {
+                tracing::info!("Dropping expired upload {} of {}", id, state.path);
+                if let Err(e) = db.delete_transfer(&id) {
+                    tracing::warn!("Failed to delete transfer {}: {}", id, e);
+                }
+            }
+            Ok(state) => {
+                transfers.insert(id, restore_interrupted(state));
+            }
+            Err(e) => tracing::warn!("Skipping unreadable transfer {}: {}", id, e),
+        }
+    }
+    transfers
+}
+
+/// Mark a transfer that was running or queued at shutdown as `Pending`
+fn restore_interrupted(mut state: TransferState) -> TransferState {
+    if matches!(state.status, TransferStatus::InProgress | TransferStatus::Queued) {
+        state.status = TransferStatus::Pending;
+    }
+    state
+}
+
 fn generate_transfer_id() -> String {
     use std::time::{SystemTime, UNIX_EPOCH};
     let timestamp = SystemTime::now()
@@ -530,6 +2670,35 @@ fn generate_transfer_id() -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_chunk_size_bounds() {
+        assert!(is_valid_chunk_size(DEFAULT_CHUNK_SIZE));
+        assert!(is_valid_chunk_size(MIN_CHUNK_SIZE));
+        assert!(is_valid_chunk_size(MAX_CHUNK_SIZE));
+        assert!(!is_valid_chunk_size(0));
+        assert!(!is_valid_chunk_size(MIN_CHUNK_SIZE - 1));
+        assert!(!is_valid_chunk_size(MAX_CHUNK_SIZE + 1));
+    }
+
+    #[tokio::test]
+    async fn test_verify_content_detects_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let written = dir.path().join("download.tmp");
+        std::fs::write(&written, b"blob content").unwrap();
+        let relative = Path::new("docs/report.txt");
+
+        let blob_hash = blake3::hash(b"blob content").to_hex().to_string();
+        assert!(verify_content(&written, relative, &blob_hash).await.is_ok());
+
+        // Metadata claims different content than the blob holds
+        let meta_hash = blake3::hash(b"metadata content").to_hex().to_string();
+        let err = verify_content(&written, relative, &meta_hash).await.unwrap_err();
+        let mismatch = err.downcast_ref::<ContentMismatch>().unwrap();
+        assert_eq!(mismatch.path, relative);
+        assert_eq!(mismatch.expected, meta_hash);
+        assert_eq!(mismatch.actual, blob_hash);
+    }
+
     #[test]
     fn test_generate_transfer_id() {
         let id1 = generate_transfer_id();
@@ -564,6 +2733,12 @@ mod tests {
             total_bytes: 1024,
             hash: Some("deadbeef".to_string()),
             error: None,
+            started_at: Utc::now(),
+            local_path: None,
+            resume_failures: 0,
+            bytes_per_sec: None,
+            eta_secs: None,
+            rate: RateWindow::default(),
         };
 
         let json = serde_json::to_string(&state).unwrap();
@@ -595,7 +2770,12 @@ mod tests {
     fn test_transfer_status_serialization() {
         let statuses = vec![
             TransferStatus::Pending,
+            TransferStatus::Queued,
             TransferStatus::InProgress,
+            TransferStatus::Retrying {
+                attempt: 1,
+                next_retry_ms: 1000,
+            },
             TransferStatus::Completed,
             TransferStatus::Failed,
             TransferStatus::Cancelled,
@@ -630,6 +2810,12 @@ mod tests {
             total_bytes: 1000,
             hash: None,
             error: Some("Connection timeout".to_string()),
+            started_at: Utc::now(),
+            local_path: None,
+            resume_failures: 0,
+            bytes_per_sec: None,
+            eta_secs: None,
+            rate: RateWindow::default(),
         };
 
         let json = serde_json::to_string(&state).unwrap();
@@ -649,6 +2835,12 @@ mod tests {
             total_bytes: 1024,
             hash: None,
             error: None,
+            started_at: Utc::now(),
+            local_path: None,
+            resume_failures: 0,
+            bytes_per_sec: None,
+            eta_secs: None,
+            rate: RateWindow::default(),
         };
 
         let cloned = state.clone();
@@ -669,6 +2861,7 @@ mod tests {
             bytes_transferred: 4096,
             total_bytes: 8192,
             status: TransferStatus::InProgress,
+            bytes_per_sec: Some(2048),
         };
 
         let json = serde_json::to_string(&progress).unwrap();
@@ -690,6 +2883,12 @@ mod tests {
             total_bytes: 2048,
             hash: Some("abc123".to_string()),
             error: None,
+            started_at: Utc::now(),
+            local_path: None,
+            resume_failures: 0,
+            bytes_per_sec: None,
+            eta_secs: None,
+            rate: RateWindow::default(),
         };
 
         let debug_str = format!("{:?}", state);
@@ -707,6 +2906,7 @@ mod tests {
             bytes_transferred: 100,
             total_bytes: 200,
             status: TransferStatus::InProgress,
+            bytes_per_sec: Some(2048),
         };
 
         let cloned = progress.clone();
@@ -728,6 +2928,12 @@ mod tests {
             total_bytes: 5000,
             hash: Some("finalhash".to_string()),
             error: None,
+            started_at: Utc::now(),
+            local_path: None,
+            resume_failures: 0,
+            bytes_per_sec: None,
+            eta_secs: None,
+            rate: RateWindow::default(),
         };
 
         let json: serde_json::Value = serde_json::to_value(&state).unwrap();
@@ -744,6 +2950,133 @@ mod tests {
         assert!(json.get("hash").is_some());
     }
 
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay(1), Duration::from_secs(1));
+        assert_eq!(retry_delay(2), Duration::from_secs(2));
+        assert_eq!(retry_delay(3), Duration::from_secs(4));
+        assert_eq!(retry_delay(5), Duration::from_secs(16));
+        assert_eq!(retry_delay(6), RETRY_MAX_DELAY);
+        assert_eq!(retry_delay(40), RETRY_MAX_DELAY);
+    }
+
+    #[test]
+    fn test_rate_window_moving_average() {
+        let mut window = RateWindow::default();
+        let start = Instant::now();
+        assert_eq!(window.record(start, 0), None);
+        assert_eq!(window.record(start + Duration::from_secs(2), 4096), Some(2048));
+
+        // Samples older than the window stop counting
+        window.record(start + Duration::from_secs(6), 8192);
+        assert_eq!(window.record(start + Duration::from_secs(8), 16384), Some(4096));
+
+        // Progress going backwards starts over
+        assert_eq!(window.record(start + Duration::from_secs(9), 0), None);
+    }
+
+    #[test]
+    fn test_sample_rate_eta() {
+        let mut state = TransferState {
+            id: "xfer_rate".to_string(),
+            drive_id: "drive_rate".to_string(),
+            path: "rate/file.bin".to_string(),
+            direction: TransferDirection::Download,
+            status: TransferStatus::InProgress,
+            bytes_transferred: 0,
+            total_bytes: 8192,
+            hash: None,
+            error: None,
+            started_at: Utc::now(),
+            local_path: None,
+            resume_failures: 0,
+            bytes_per_sec: None,
+            eta_secs: None,
+            rate: RateWindow::default(),
+        };
+        let start = Instant::now();
+        state.sample_rate(start);
+        assert_eq!(state.eta_secs, None);
+
+        state.bytes_transferred = 2048;
+        state.sample_rate(start + Duration::from_secs(2));
+        assert_eq!(state.bytes_per_sec, Some(1024));
+        assert_eq!(state.eta_secs, Some(6));
+
+        // Unknown size: speed but no ETA
+        state.total_bytes = 0;
+        state.bytes_transferred = 4096;
+        state.sample_rate(start + Duration::from_secs(4));
+        assert_eq!(state.bytes_per_sec, Some(1024));
+        assert_eq!(state.eta_secs, None);
+
+        state.reset_rate();
+        assert_eq!(state.bytes_per_sec, None);
+        state.status = TransferStatus::Queued;
+        state.sample_rate(start + Duration::from_secs(5));
+        assert_eq!(state.bytes_per_sec, None);
+    }
+
+    #[test]
+    fn test_unfinished_uploads_restored_as_pending() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(dir.path().join("test.redb")).unwrap();
+        let upload = TransferState {
+            id: "xfer_upload".to_string(),
+            drive_id: "drive_up".to_string(),
+            path: "docs/report.pdf".to_string(),
+            direction: TransferDirection::Upload,
+            status: TransferStatus::InProgress,
+            bytes_transferred: 0,
+            total_bytes: 2048,
+            hash: None,
+            error: None,
+            started_at: Utc::now(),
+            local_path: Some(PathBuf::from("/drive/docs/report.pdf")),
+            resume_failures: 0,
+            bytes_per_sec: None,
+            eta_secs: None,
+            rate: RateWindow::default(),
+        };
+        assert!(upload.is_resumable_upload());
+        db.save_transfer(&upload.id, &serde_json::to_vec(&upload).unwrap())
+            .unwrap();
+        db.save_transfer("xfer_garbage", b"not json").unwrap();
+
+        let restored = load_unfinished_uploads(&db);
+        assert_eq!(restored.len(), 1);
+        let state = &restored["xfer_upload"];
+        assert_eq!(state.status, TransferStatus::Pending);
+        assert_eq!(state.total_bytes, 2048);
+        assert_eq!(state.local_path, upload.local_path);
+
+        let completed = TransferState {
+            status: TransferStatus::Completed,
+            ..upload.clone()
+        };
+        assert!(!completed.is_resumable_upload());
+
+        // Uploads that keep failing or were abandoned are given up
+        let exhausted = TransferState {
+            id: "xfer_exhausted".to_string(),
+            status: TransferStatus::Failed,
+            resume_failures: MAX_RESUME_FAILURES,
+            ..upload.clone()
+        };
+        let stale = TransferState {
+            id: "xfer_stale".to_string(),
+            started_at: Utc::now() - chrono::Duration::days(RESUMABLE_UPLOAD_DAYS + 1),
+            ..upload
+        };
+        for expired in [&exhausted, &stale] {
+            assert!(!expired.is_resumable_upload());
+            db.save_transfer(&expired.id, &serde_json::to_vec(expired).unwrap())
+                .unwrap();
+        }
+        assert_eq!(load_unfinished_uploads(&db).len(), 1);
+        assert_eq!(db.list_transfers().unwrap().len(), 2);
+    }
+
     #[test]
     fn test_transfer_id_uniqueness() {
         let mut ids = std::collections::HashSet::new();
@@ -758,4 +3091,21 @@ mod tests {
         // Should have generated unique IDs
         assert!(ids.len() > 90, "Expected mostly unique IDs, got {}", ids.len());
     }
+
+    #[tokio::test]
+    async fn test_move_into_place() {
+        let temp = tempfile::tempdir().unwrap();
+        let drive = tempfile::tempdir().unwrap();
+
+        let temp_path = temp.path().join("xfer_1.download");
+        let dest = drive.path().join("doc.txt");
+        std::fs::write(&temp_path, b"finished").unwrap();
+        std::fs::write(&dest, b"old").unwrap();
+
+        move_into_place(&temp_path, &dest).await.unwrap();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), b"finished");
+        assert!(!temp_path.exists());
+        assert!(!drive.path().join(".doc.txt.tmp").exists());
+    }
 }
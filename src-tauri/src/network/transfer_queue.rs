@@ -0,0 +1,230 @@
+//! Concurrency limits for file transfers
+//!
+//! Starting every queued download and upload at once thrashes the disk and
+//! the network. Each transfer takes a slot from a global semaphore, and
+//! optionally from a per-drive one, before it starts; the rest wait their
+//! turn in order with status `Queued`.
+
+use crate::core::DriveId;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Setting holding the transfer limits
+pub const TRANSFER_LIMITS_SETTING: &str = "transfer_limits";
+
+/// Bounds for either limit
+pub const MIN_CONCURRENT_TRANSFERS: usize = 1;
+pub const MAX_CONCURRENT_TRANSFERS: usize = 64;
+
+/// How many transfers may run at once
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TransferLimits {
+    /// Transfers running at once across all drives
+    pub max_concurrent: usize,
+    /// Transfers running at once within one drive (None = only the global limit)
+    pub max_per_drive: Option<usize>,
+}
+
+impl Default for TransferLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            max_per_drive: None,
+        }
+    }
+}
+
+impl TransferLimits {
+    /// Clamp both limits to supported bounds, keeping per-drive <= global
+    pub fn normalized(mut self) -> Self {
+        self.max_concurrent = self
+            .max_concurrent
+            .clamp(MIN_CONCURRENT_TRANSFERS, MAX_CONCURRENT_TRANSFERS);
+        self.max_per_drive = self
+            .max_per_drive
+            .map(|max| max.clamp(MIN_CONCURRENT_TRANSFERS, self.max_concurrent));
+        self
+    }
+}
+
+/// Permission for one transfer to run; the slot frees when dropped
+pub struct TransferSlot {
+    _global: OwnedSemaphorePermit,
+    _drive: Option<OwnedSemaphorePermit>,
+}
+
+/// Hands out transfer slots within the configured limits
+pub struct TransferLimiter {
+    limits: Mutex<TransferLimits>,
+    global: Arc<Semaphore>,
+    /// Per-drive semaphores, created on first use while a sub-limit is set
+    per_drive: Mutex<HashMap<DriveId, Arc<Semaphore>>>,
+}
+
+impl TransferLimiter {
+    pub fn new(limits: TransferLimits) -> Self {
+        let limits = limits.normalized();
+        Self {
+            global: Arc::new(Semaphore::new(limits.max_concurrent)),
+            limits: Mutex::new(limits),
+            per_drive: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn limits(&self) -> TransferLimits {
+        self.limits.lock().map(|l| *l).unwrap_or_default()
+    }
+
+    /// Apply new limits, returning them normalized
+    ///
+    /// Lowering the global limit takes effect as running transfers finish.
+    /// A new per-drive limit applies to transfers that start afterwards.
+    pub fn set_limits(&self, limits: TransferLimits) -> TransferLimits {
+        let limits = limits.normalized();
+        let old = {
+            let Ok(mut current) = self.limits.lock() else {
+                return limits;
+            };
+            std::mem::replace(&mut *current, limits)
+        };
+
+        if limits.max_concurrent > old.max_concurrent {
+            self.global
+                .add_permits(limits.max_concurrent - old.max_concurrent);
+        } else if limits.max_concurrent < old.max_concurrent {
+            // Take the surplus permits out of circulation once they're free
+            let global = self.global.clone();
+            let surplus = (old.max_concurrent - limits.max_concurrent) as u32;
+            tokio::spawn(async move {
+                if let Ok(permits) = global.acquire_many_owned(surplus).await {
+                    permits.forget();
+                }
+            });
+        }
+        if limits.max_per_drive != old.max_per_drive {
+            if let Ok(mut per_drive) = self.per_drive.lock() {
+                per_drive.clear();
+            }
+        }
+        limits
+    }
+
+    /// Wait for a slot for a transfer in `drive_id`
+    ///
+    /// Waiters are served in order. The drive's slot is taken first, so a
+    /// drive at its sub-limit doesn't hold global slots other drives could use.
+    pub async fn acquire(&self, drive_id: DriveId) -> Result<TransferSlot> {
+        let drive = match self.drive_semaphore(drive_id) {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire_owned()
+                    .await
+                    .context("Transfer limiter closed")?,
+            ),
+            None => None,
+        };
+        let global = self
+            .global
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Transfer limiter closed")?;
+        Ok(TransferSlot {
+            _global: global,
+            _drive: drive,
+        })
+    }
+
+    fn drive_semaphore(&self, drive_id: DriveId) -> Option<Arc<Semaphore>> {
+        let max = self.limits().max_per_drive?;
+        let mut per_drive = self.per_drive.lock().ok()?;
+        Some(
+            per_drive
+                .entry(drive_id)
+                .or_insert_with(|| Arc::new(Semaphore::new(max)))
+                .clone(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn acquires_within(limiter: &TransferLimiter, drive: DriveId) -> bool {
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire(drive))
+            .await
+            .is_ok()
+    }
+
+    #[test]
+    fn test_limits_normalized() {
+        let limits = TransferLimits {
+            max_concurrent: 0,
+            max_per_drive: Some(10),
+        }
+        .normalized();
+        assert_eq!(limits.max_concurrent, MIN_CONCURRENT_TRANSFERS);
+        assert_eq!(limits.max_per_drive, Some(MIN_CONCURRENT_TRANSFERS));
+
+        let limits = TransferLimits {
+            max_concurrent: 1_000,
+            max_per_drive: None,
+        }
+        .normalized();
+        assert_eq!(limits.max_concurrent, MAX_CONCURRENT_TRANSFERS);
+    }
+
+    #[tokio::test]
+    async fn test_global_and_per_drive_limits() {
+        let a = DriveId([1u8; 32]);
+        let b = DriveId([2u8; 32]);
+        let limiter = TransferLimiter::new(TransferLimits {
+            max_concurrent: 2,
+            max_per_drive: Some(1),
+        });
+
+        let first = limiter.acquire(a).await.unwrap();
+        // Drive A is at its sub-limit, but drive B still gets a slot
+        assert!(!acquires_within(&limiter, a).await);
+        let second = limiter.acquire(b).await.unwrap();
+        // Both global slots are taken now
+        assert!(!acquires_within(&limiter, b).await);
+
+        drop(first);
+        assert!(acquires_within(&limiter, a).await);
+        drop(second);
+    }
+
+    #[tokio::test]
+    async fn test_set_limits_resizes() {
+        let drive = DriveId([1u8; 32]);
+        let limiter = TransferLimiter::new(TransferLimits {
+            max_concurrent: 1,
+            max_per_drive: None,
+        });
+
+        let held = limiter.acquire(drive).await.unwrap();
+        assert!(!acquires_within(&limiter, drive).await);
+        limiter.set_limits(TransferLimits {
+            max_concurrent: 2,
+            max_per_drive: None,
+        });
+        let extra = limiter.acquire(drive).await.unwrap();
+
+        // Lowering waits for running transfers to free their slots
+        limiter.set_limits(TransferLimits {
+            max_concurrent: 1,
+            max_per_drive: None,
+        });
+        drop(held);
+        assert!(!acquires_within(&limiter, drive).await);
+        drop(extra);
+        assert!(acquires_within(&limiter, drive).await);
+    }
+}
@@ -0,0 +1,419 @@
+//! Webhook delivery of drive events
+//!
+//! Selected drive events are POSTed as JSON to a configured URL so external
+//! systems (chat, CI) can react to them. A webhook can be set globally or
+//! per drive; a drive's own webhook replaces the global one for that drive.
+//! With a secret set, each body is signed with a keyed BLAKE3 hash sent in
+//! the `X-Gix-Signature` header.
+//!
+//! Deliveries are queued and retried with backoff off the event pipeline. A
+//! slow or failing endpoint costs dropped deliveries, never events or sync.
+
+use crate::core::{AppError, EVENT_TYPES};
+use crate::network::EventBroadcaster;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc, RwLock, Semaphore};
+use tokio::task::JoinHandle;
+
+/// Setting holding the global and per-drive webhooks
+pub const WEBHOOK_SETTING: &str = "webhooks";
+
+/// Header carrying the body's signature (`blake3=<hex>`)
+pub const SIGNATURE_HEADER: &str = "X-Gix-Signature";
+
+/// Key derivation context for signing keys
+const SIGNATURE_CONTEXT: &str = "gix-portal 2025 webhook signature v1";
+
+/// Event types a webhook fires on unless configured otherwise
+pub const DEFAULT_WEBHOOK_EVENTS: &[&str] = &[
+    "FileChanged",
+    "FileDeleted",
+    "FilesChangedSummary",
+    "ConflictResolved",
+    "ConflictAutoResolved",
+    "UserJoined",
+    "InviteAccepted",
+    "UserLeft",
+];
+
+/// Attempts per delivery, including the first
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry; doubles per attempt up to `MAX_BACKOFF`
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long one request may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deliveries waiting to be sent before new ones are dropped
+const QUEUE_CAPACITY: usize = 256;
+
+/// Deliveries sent (or backing off) at once
+const MAX_IN_FLIGHT: usize = 4;
+
+/// Where to send events and which ones
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// http(s) URL events are POSTed to
+    pub url: String,
+    /// Shared secret for the signature header (None = unsigned)
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// Event types that fire the webhook (empty = `DEFAULT_WEBHOOK_EVENTS`)
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+impl WebhookConfig {
+    /// Check the URL and event types
+    pub fn validate(&self) -> Result<(), AppError> {
+        let url = self.url.trim();
+        let rest = url
+            .strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"));
+        if rest.filter(|host| !host.is_empty()).is_none() {
+            return Err(AppError::ValidationFailed {
+                field: "url".to_string(),
+                reason: "must be an http:// or https:// URL".to_string(),
+            });
+        }
+        if let Some(unknown) = self
+            .event_types
+            .iter()
+            .find(|t| !EVENT_TYPES.contains(&t.as_str()))
+        {
+            return Err(AppError::ValidationFailed {
+                field: "event_types".to_string(),
+                reason: format!("unknown event type {}", unknown),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether an event of `event_type` fires this webhook
+    pub fn fires_on(&self, event_type: &str) -> bool {
+        if self.event_types.is_empty() {
+            DEFAULT_WEBHOOK_EVENTS.contains(&event_type)
+        } else {
+            self.event_types.iter().any(|t| t == event_type)
+        }
+    }
+}
+
+/// The global webhook and per-drive overrides
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    /// Used for drives without a webhook of their own
+    pub global: Option<WebhookConfig>,
+    /// Webhooks by drive ID (hex)
+    pub drives: HashMap<String, WebhookConfig>,
+}
+
+impl WebhookSettings {
+    /// Webhook that applies to a drive, if any
+    pub fn for_drive(&self, drive_id: &str) -> Option<&WebhookConfig> {
+        self.drives.get(drive_id).or(self.global.as_ref())
+    }
+}
+
+/// JSON body POSTed for each event
+#[derive(Clone, Debug, Serialize)]
+pub struct WebhookPayload {
+    /// Unique per delivery; retries reuse it so receivers can deduplicate
+    pub delivery_id: String,
+    pub drive_id: String,
+    pub event_type: String,
+    /// ISO 8601 timestamp of the event
+    pub timestamp: String,
+    /// Full event, as sent to the frontend
+    pub event: serde_json::Value,
+}
+
+/// Outcome of a `test_webhook` delivery
+#[derive(Clone, Debug, Serialize)]
+pub struct WebhookTestResult {
+    /// HTTP status the endpoint answered with
+    pub status: u16,
+    pub duration_ms: u64,
+}
+
+/// Signature of `body` under `secret`, as sent in `SIGNATURE_HEADER`
+///
+/// Receivers derive the key with `blake3::derive_key` and the context
+/// `"gix-portal 2025 webhook signature v1"`, then compare the keyed hash.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let key = blake3::derive_key(SIGNATURE_CONTEXT, secret.as_bytes());
+    format!("blake3={}", blake3::keyed_hash(&key, body).to_hex())
+}
+
+/// Wait before retrying after `attempt` failed attempts
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(1u32 << attempt.saturating_sub(1).min(16))
+        .min(MAX_BACKOFF)
+}
+
+fn new_delivery_id() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+struct Delivery {
+    config: WebhookConfig,
+    payload: WebhookPayload,
+}
+
+/// Sends drive events to the configured webhooks
+pub struct WebhookDispatcher {
+    settings: RwLock<WebhookSettings>,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(settings: WebhookSettings) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .context("Failed to create HTTP client")?;
+        Ok(Self {
+            settings: RwLock::new(settings),
+            client,
+        })
+    }
+
+    pub async fn settings(&self) -> WebhookSettings {
+        self.settings.read().await.clone()
+    }
+
+    /// Set or clear (`config` None) the webhook for a drive, or the global
+    /// one if `drive_id` is None; returns the updated settings
+    pub async fn set(
+        &self,
+        drive_id: Option<&str>,
+        config: Option<WebhookConfig>,
+    ) -> WebhookSettings {
+        let mut settings = self.settings.write().await;
+        match (drive_id, config) {
+            (Some(drive_id), Some(config)) => {
+                settings.drives.insert(drive_id.to_string(), config);
+            }
+            (Some(drive_id), None) => {
+                settings.drives.remove(drive_id);
+            }
+            (None, config) => settings.global = config,
+        }
+        settings.clone()
+    }
+
+    /// Forward events from `broadcaster` to the matching webhooks
+    pub fn start(self: &Arc<Self>, broadcaster: &EventBroadcaster) -> JoinHandle<()> {
+        let (queue_tx, queue_rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(self.clone().run_deliveries(queue_rx));
+
+        let dispatcher = self.clone();
+        let mut rx = broadcaster.subscribe_frontend();
+        tokio::spawn(async move {
+            loop {
+                let dto = match rx.recv().await {
+                    Ok(dto) => dto,
+                    Err(broadcast::error::RecvError::Lagged(count)) => {
+                        tracing::warn!("Webhook dispatcher lagged, missed {} events", count);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let config = {
+                    let settings = dispatcher.settings.read().await;
+                    match settings.for_drive(&dto.drive_id) {
+                        Some(config) if config.fires_on(&dto.event_type) => config.clone(),
+                        _ => continue,
+                    }
+                };
+                let delivery = Delivery {
+                    config,
+                    payload: WebhookPayload {
+                        delivery_id: new_delivery_id(),
+                        drive_id: dto.drive_id,
+                        event_type: dto.event_type,
+                        timestamp: dto.timestamp,
+                        event: dto.payload,
+                    },
+                };
+                // Never wait on the queue; a stuck endpoint must not hold events up
+                if let Err(mpsc::error::TrySendError::Full(delivery)) = queue_tx.try_send(delivery)
+                {
+                    tracing::warn!(
+                        drive_id = %delivery.payload.drive_id,
+                        event_type = %delivery.payload.event_type,
+                        "Webhook queue full, dropping delivery"
+                    );
+                }
+            }
+        })
+    }
+
+    async fn run_deliveries(self: Arc<Self>, mut queue: mpsc::Receiver<Delivery>) {
+        let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+        while let Some(delivery) = queue.recv().await {
+            let Ok(permit) = in_flight.clone().acquire_owned().await else {
+                break;
+            };
+            let dispatcher = self.clone();
+            tokio::spawn(async move {
+                dispatcher.deliver_with_retry(&delivery).await;
+                drop(permit);
+            });
+        }
+    }
+
+    async fn deliver_with_retry(&self, delivery: &Delivery) {
+        let mut attempt = 1;
+        loop {
+            let error = match self.deliver(&delivery.config, &delivery.payload).await {
+                Ok(_) => return,
+                Err(e) => e,
+            };
+            // Connection errors and timeouts have no status and are retried
+            let retryable = match error
+                .downcast_ref::<reqwest::Error>()
+                .and_then(|e| e.status())
+            {
+                Some(status) => {
+                    status.is_server_error()
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                }
+                None => true,
+            };
+            if !retryable || attempt >= MAX_ATTEMPTS {
+                tracing::warn!(
+                    drive_id = %delivery.payload.drive_id,
+                    event_type = %delivery.payload.event_type,
+                    attempts = attempt,
+                    "Webhook delivery failed: {}",
+                    error
+                );
+                return;
+            }
+            tokio::time::sleep(backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    /// POST `payload` once, failing on anything but a 2xx answer
+    async fn deliver(
+        &self,
+        config: &WebhookConfig,
+        payload: &WebhookPayload,
+    ) -> Result<reqwest::StatusCode> {
+        let body = serde_json::to_vec(payload)?;
+        let mut request = self
+            .client
+            .post(config.url.trim())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Gix-Event", &payload.event_type)
+            .header("X-Gix-Delivery", &payload.delivery_id);
+        if let Some(secret) = config.secret.as_deref().filter(|s| !s.is_empty()) {
+            request = request.header(SIGNATURE_HEADER, sign_payload(secret, &body));
+        }
+        let response = request.body(body).send().await?.error_for_status()?;
+        Ok(response.status())
+    }
+
+    /// Send a `WebhookTest` event to the webhook that applies to `drive_id`
+    /// (or the global one), without retrying
+    pub async fn test(&self, drive_id: Option<&str>) -> Result<WebhookTestResult> {
+        let config = {
+            let settings = self.settings.read().await;
+            match drive_id {
+                Some(drive_id) => settings.for_drive(drive_id).cloned(),
+                None => settings.global.clone(),
+            }
+        }
+        .context("No webhook configured")?;
+
+        let payload = WebhookPayload {
+            delivery_id: new_delivery_id(),
+            drive_id: drive_id.unwrap_or_default().to_string(),
+            event_type: "WebhookTest".to_string(),
+            timestamp: Utc::now().to_rfc3339(),
+            event: serde_json::json!({ "type": "WebhookTest" }),
+        };
+        let started = Instant::now();
+        let status = self.deliver(&config, &payload).await?;
+        Ok(WebhookTestResult {
+            status: status.as_u16(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(url: &str) -> WebhookConfig {
+        WebhookConfig {
+            url: url.to_string(),
+            secret: None,
+            event_types: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_validate_and_filter() {
+        assert!(config("https://example.com/hook").validate().is_ok());
+        assert!(config("ftp://example.com").validate().is_err());
+        assert!(config("https://").validate().is_err());
+
+        let mut hook = config("http://localhost:8080");
+        assert!(hook.fires_on("FileChanged"));
+        assert!(!hook.fires_on("FileEditStarted"));
+
+        hook.event_types = vec!["FileEditStarted".to_string()];
+        assert!(hook.validate().is_ok());
+        assert!(hook.fires_on("FileEditStarted"));
+        assert!(!hook.fires_on("FileChanged"));
+
+        hook.event_types = vec!["NoSuchEvent".to_string()];
+        assert!(hook.validate().is_err());
+    }
+
+    #[test]
+    fn test_drive_webhook_replaces_global() {
+        let settings = WebhookSettings {
+            global: Some(config("https://global.example")),
+            drives: [("aa".to_string(), config("https://drive.example"))]
+                .into_iter()
+                .collect(),
+        };
+        assert_eq!(
+            settings.for_drive("aa").unwrap().url,
+            "https://drive.example"
+        );
+        assert_eq!(
+            settings.for_drive("bb").unwrap().url,
+            "https://global.example"
+        );
+        assert!(WebhookSettings::default().for_drive("aa").is_none());
+    }
+
+    #[test]
+    fn test_sign_payload_and_backoff() {
+        let signature = sign_payload("secret", b"{}");
+        assert!(signature.starts_with("blake3="));
+        assert_eq!(signature, sign_payload("secret", b"{}"));
+        assert_ne!(signature, sign_payload("other", b"{}"));
+        assert_ne!(signature, sign_payload("secret", b"{ }"));
+
+        assert_eq!(backoff(1), INITIAL_BACKOFF);
+        assert_eq!(backoff(2), INITIAL_BACKOFF * 2);
+        assert_eq!(backoff(30), MAX_BACKOFF);
+    }
+}
@@ -1,6 +1,10 @@
-use crate::core::{FileWatcherManager, IdentityManager, SharedDrive};
+use crate::core::{FileWatcherManager, IdentityManager, SharedDrive, WATCH_CONFIG_SETTING};
 use crate::crypto::EncryptionManager;
-use crate::network::{DocsManager, EventBroadcaster, FileTransferManager, P2PEndpoint, SyncEngine};
+use crate::network::{
+    DocsManager, EndpointConfig, EventBroadcaster, FileTransferManager, P2PEndpoint, SyncEngine,
+    TransferLimits, BANDWIDTH_LIMIT_SETTING, DOWNLOAD_TEMP_DIR_SETTING, ENDPOINT_CONFIG_SETTING,
+    TRANSFER_CHUNK_SIZE_SETTING, TRANSFER_LIMITS_SETTING,
+};
 use crate::storage::Database;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -43,7 +47,7 @@ impl AppState {
 
         // Open database
         let db_path = data_dir.join("gix.redb");
-        let db = Arc::new(Database::open(&db_path)?);
+        let db = Arc::new(Database::open_verified(&db_path)?);
         tracing::info!("Database opened at: {:?}", db_path);
 
         // Initialize identity manager and load/generate identity
@@ -57,8 +61,15 @@ impl AppState {
             .await
             .ok_or_else(|| anyhow::anyhow!("Identity not initialized after initialization"))?;
 
-        // Initialize P2P endpoint
-        let endpoint = Arc::new(P2PEndpoint::new(&secret_key_bytes));
+        // Initialize P2P endpoint with the configured bind address/ports, if any
+        let endpoint_config = db
+            .get_setting(ENDPOINT_CONFIG_SETTING)
+            .ok()
+            .flatten()
+            .and_then(|bytes| serde_json::from_slice::<EndpointConfig>(&bytes).ok())
+            .filter(|config| config.validate().is_ok())
+            .unwrap_or_default();
+        let endpoint = Arc::new(P2PEndpoint::new(&secret_key_bytes).with_config(endpoint_config));
         endpoint.start().await?;
         tracing::info!("P2P endpoint started");
 
@@ -68,6 +79,10 @@ impl AppState {
             let mut drives_guard = drives.write().await;
             for (id, data) in db.list_drives()? {
                 match serde_json::from_slice::<SharedDrive>(&data) {
+                    // Deleted drives stay in the database until purged
+                    Ok(drive) if drive.is_deleted() => {
+                        tracing::debug!("Skipped deleted drive: {} ({})", drive.name, drive.id);
+                    }
                     Ok(drive) => {
                         tracing::debug!("Loaded drive: {} ({})", drive.name, drive.id);
                         drives_guard.insert(id, drive);
@@ -79,10 +94,23 @@ impl AppState {
             }
             tracing::info!("Loaded {} drives from database", drives_guard.len());
         }
+        let max_peers = drives
+            .read()
+            .await
+            .values()
+            .filter_map(|d| d.max_peers)
+            .max();
 
         // Initialize Phase 2 components (gossip, docs, sync, watcher, transfer)
         let (sync_engine, event_broadcaster, docs_manager, file_watcher, file_transfer) =
-            Self::initialize_sync_components(&endpoint, &identity_manager, &data_dir, db.clone()).await;
+            Self::initialize_sync_components(
+                &endpoint,
+                &identity_manager,
+                &data_dir,
+                db.clone(),
+                max_peers,
+            )
+            .await;
 
         // Initialize EncryptionManager for E2E file encryption
         let encryption_manager = match EncryptionManager::new(db.clone()) {
@@ -119,6 +147,7 @@ impl AppState {
         identity_manager: &Arc<IdentityManager>,
         data_dir: &std::path::Path,
         db: Arc<Database>,
+        max_peers: Option<usize>,
     ) -> (
         Option<Arc<SyncEngine>>,
         Option<Arc<EventBroadcaster>>,
@@ -154,39 +183,87 @@ impl AppState {
         };
 
         // Initialize EventBroadcaster with identity for message signing
-        let event_broadcaster = match EventBroadcaster::new(&iroh_endpoint, identity).await {
-            Ok(eb) => Arc::new(eb),
-            Err(e) => {
-                tracing::error!("Failed to initialize EventBroadcaster: {}", e);
-                return (None, None, None, None, None);
-            }
-        };
+        let event_broadcaster =
+            match EventBroadcaster::new(&iroh_endpoint, identity, max_peers).await {
+                Ok(eb) => Arc::new(eb),
+                Err(e) => {
+                    tracing::error!("Failed to initialize EventBroadcaster: {}", e);
+                    return (None, None, None, None, None);
+                }
+            };
 
         // Initialize FileWatcherManager
         let file_watcher = {
             let watcher = FileWatcherManager::new(node_id);
-            tracing::info!("FileWatcherManager initialized");
+            if let Some(config) = db
+                .get_setting(WATCH_CONFIG_SETTING)
+                .ok()
+                .flatten()
+                .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            {
+                watcher.set_config(config);
+            }
+            tracing::info!("FileWatcherManager initialized ({:?})", watcher.config().mode);
             Some(Arc::new(watcher))
         };
 
         // Initialize FileTransferManager
-        let file_transfer = match FileTransferManager::new(&iroh_endpoint, data_dir, node_id).await
-        {
-            Ok(ftm) => {
-                tracing::info!("FileTransferManager initialized");
-                Some(Arc::new(ftm))
+        let file_transfer =
+            match FileTransferManager::new(&iroh_endpoint, data_dir, node_id, db.clone()).await {
+                Ok(ftm) => {
+                    tracing::info!("FileTransferManager initialized");
+                    Some(Arc::new(ftm))
+                }
+                Err(e) => {
+                    tracing::error!("Failed to initialize FileTransferManager: {}", e);
+                    None
+                }
+            };
+
+        // Apply the configured download temp directory and keep the watcher out of it
+        if let Some(transfer) = file_transfer.as_ref() {
+            if let Ok(Some(bytes)) = db.get_setting(DOWNLOAD_TEMP_DIR_SETTING) {
+                let dir = PathBuf::from(String::from_utf8_lossy(&bytes).to_string());
+                if let Err(e) = transfer.set_temp_dir(Some(dir)).await {
+                    tracing::warn!("Using default download temp directory: {}", e);
+                }
             }
-            Err(e) => {
-                tracing::error!("Failed to initialize FileTransferManager: {}", e);
-                None
+            if let Some(watcher) = file_watcher.as_ref() {
+                watcher.set_ignored_dirs(vec![transfer.temp_dir().await]);
             }
-        };
+            if let Some(size) = db
+                .get_setting(TRANSFER_CHUNK_SIZE_SETTING)
+                .ok()
+                .flatten()
+                .and_then(|bytes| serde_json::from_slice::<usize>(&bytes).ok())
+            {
+                if let Err(e) = transfer.set_chunk_size(Some(size)) {
+                    tracing::warn!("Using default transfer chunk size: {}", e);
+                }
+            }
+            if let Some(limits) = db
+                .get_setting(TRANSFER_LIMITS_SETTING)
+                .ok()
+                .flatten()
+                .and_then(|bytes| serde_json::from_slice::<TransferLimits>(&bytes).ok())
+            {
+                transfer.set_limits(limits);
+            }
+            if let Some(limit) = db
+                .get_setting(BANDWIDTH_LIMIT_SETTING)
+                .ok()
+                .flatten()
+                .and_then(|bytes| serde_json::from_slice::<u64>(&bytes).ok())
+            {
+                transfer.set_bandwidth_limit(Some(limit));
+            }
+        }
 
         // Initialize DocsManager
         let docs_manager = match (event_broadcaster.gossip().await, file_transfer.as_ref()) {
             (Some(gossip), Some(transfer)) => match DocsManager::new(
                 data_dir,
-                db,
+                db.clone(),
                 transfer.blobs(),
                 gossip,
             )
@@ -207,7 +284,11 @@ impl AppState {
         // Initialize SyncEngine
         let sync_engine = docs_manager
             .as_ref()
-            .map(|dm| Arc::new(SyncEngine::new(dm.clone(), event_broadcaster.clone())));
+            .map(|dm| Arc::new(SyncEngine::new(dm.clone(), event_broadcaster.clone(), db)));
+        if let (Some(engine), Some(watcher)) = (sync_engine.as_ref(), file_watcher.as_ref()) {
+            watcher.set_permission_errors(engine.permission_errors());
+            watcher.set_ignore_rules(engine.ignore_rules());
+        }
 
         tracing::info!("Phase 2 sync components initialized successfully");
 
@@ -0,0 +1,685 @@
+//! Encrypted backups of the whole data directory
+//!
+//! A backup holds a consistent snapshot of the database (identity, drives,
+//! ACLs, keys, metadata and settings) together with the docs and blob
+//! stores, so a restored data directory brings back ownership of every
+//! drive. Transient state such as downloads in progress is left out. The
+//! docs and blob stores keep their own databases open, so those are copied
+//! until two copies in a row match rather than read while in use.
+//!
+//! Archive layout:
+//! - Header (plaintext): magic, format version, Argon2id parameters, salt
+//!   and a random nonce prefix.
+//! - Chunks: `len: u32 LE | last: u8 | ciphertext`, each sealed with
+//!   XChaCha20-Poly1305 under a key derived from the passphrase. The nonce
+//!   is the prefix followed by the chunk counter, and the header and `last`
+//!   flag are authenticated, so reordered, truncated or tampered archives
+//!   fail to open.
+//!
+//! Decrypted, the chunks form a stream of records: a JSON manifest, then
+//! one `path_len: u32 | path | size: u64 | contents` record per file, ended
+//! by a zero `path_len`. Nothing is written outside the encrypted stream,
+//! so secret keys never appear in plaintext.
+
+use crate::crypto::passphrase::{KDF_ITERATIONS, KDF_MEMORY_KIB, KDF_PARALLELISM};
+use anyhow::{anyhow, bail, Context, Result};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+/// File name of the database inside the data directory
+pub const DATABASE_FILE: &str = "gix.redb";
+
+const MAGIC: &[u8; 8] = b"GIXBAK01";
+const FORMAT_VERSION: u8 = 1;
+
+/// Plaintext bytes per encrypted chunk
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Largest chunk accepted when reading (plaintext plus tag)
+const MAX_CHUNK_LEN: usize = CHUNK_SIZE + 16;
+
+const SALT_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 12 + SALT_LEN + NONCE_PREFIX_LEN;
+
+/// Data directory entry holding the plaintext snapshots a backup is built from
+const STAGING_DIR: &str = "backup-staging";
+
+/// Copies of an open store database taken before giving up on a stable one
+const STORE_SNAPSHOT_ATTEMPTS: usize = 5;
+
+/// Data directory entries never backed up, relative to the data directory
+///
+/// The database is added from a snapshot instead of copied while open;
+/// `blobs-tmp` and `blobs/temp` hold downloads and imports in progress.
+const EXCLUDED_PATHS: &[&str] = &[
+    DATABASE_FILE,
+    STAGING_DIR,
+    "gix.redb.bak",
+    "gix.redb.corrupt",
+    "blobs-tmp",
+    "blobs/temp",
+];
+
+/// Describes a backup; stored first in the encrypted stream
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupManifest {
+    /// Hex-encoded node ID of the backed up identity
+    pub node_id: String,
+    /// ISO 8601 time the backup was taken
+    pub created_at: String,
+    /// App version that wrote the backup
+    pub app_version: String,
+    /// Number of files in the archive
+    pub file_count: usize,
+    /// Total size of the files, in bytes
+    pub total_bytes: u64,
+}
+
+/// Create a private directory for a backup's plaintext snapshots
+///
+/// It lives inside `data_dir`, next to the files it copies, and is only
+/// accessible to the current user. The caller removes it once the backup
+/// is written.
+pub fn create_staging_dir(data_dir: &Path) -> Result<PathBuf> {
+    let dir = data_dir
+        .join(STAGING_DIR)
+        .join(hex::encode(rand::random::<[u8; 8]>()));
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder
+        .create(&dir)
+        .with_context(|| format!("Failed to create backup staging directory {:?}", dir))?;
+    Ok(dir)
+}
+
+/// Write an encrypted backup of `data_dir` to `out`
+///
+/// `db_snapshot` is a copy of the database (see `Database::snapshot_to`)
+/// stored in place of the live file, and the docs and blob store databases
+/// are snapshotted into `staging` (see `create_staging_dir` and
+/// `snapshot_store`). The archive is written next to `out` and renamed into
+/// place once complete, so a failed backup never leaves a truncated archive
+/// behind.
+pub fn write_backup(
+    data_dir: &Path,
+    staging: &Path,
+    db_snapshot: &Path,
+    node_id: &str,
+    out: &Path,
+    passphrase: &str,
+) -> Result<BackupManifest> {
+    if out.starts_with(data_dir) {
+        bail!("Backups can't be written inside the data directory");
+    }
+
+    let mut files = vec![(DATABASE_FILE.to_string(), db_snapshot.to_path_buf())];
+    collect_files(data_dir, &mut files)?;
+
+    let snapshots = staging.join("stores");
+    let result = snapshot_stores(&mut files, &snapshots)
+        .and_then(|_| write_archive(&files, node_id, out, passphrase));
+    let _ = std::fs::remove_dir_all(&snapshots);
+    result
+}
+
+/// Replace the open store databases in `files` with snapshots in `dir`
+fn snapshot_stores(files: &mut [(String, PathBuf)], dir: &Path) -> Result<()> {
+    for (index, (relative, path)) in files.iter_mut().enumerate() {
+        if !is_store_database(relative) {
+            continue;
+        }
+        std::fs::create_dir_all(dir)?;
+        let snapshot = dir.join(format!("{}.snapshot", index));
+        snapshot_store(path, &snapshot)?;
+        *path = snapshot;
+    }
+    Ok(())
+}
+
+/// Whether a data directory file is a database the docs or blob store
+/// keeps open
+fn is_store_database(relative: &str) -> bool {
+    (relative.starts_with("docs/") || relative.starts_with("blobs/"))
+        && Path::new(relative)
+            .extension()
+            .is_some_and(|ext| ext == "redb" || ext == "db")
+}
+
+/// Copy a database that may be written to until two copies in a row match
+///
+/// A write landing mid-copy shows up as a mismatch, so a torn copy never
+/// makes it into the archive.
+fn snapshot_store(source: &Path, dest: &Path) -> Result<()> {
+    let mut previous = None;
+    for _ in 0..STORE_SNAPSHOT_ATTEMPTS {
+        std::fs::copy(source, dest)
+            .with_context(|| format!("Failed to copy store database {:?}", source))?;
+        let mut hasher = blake3::Hasher::new();
+        std::io::copy(&mut File::open(dest)?, &mut hasher)?;
+        let digest = hasher.finalize();
+        if previous == Some(digest) {
+            return Ok(());
+        }
+        previous = Some(digest);
+    }
+    bail!(
+        "Store database {:?} kept changing while it was copied",
+        source
+    )
+}
+
+/// Write `files` into an encrypted archive at `out`
+fn write_archive(
+    files: &[(String, PathBuf)],
+    node_id: &str,
+    out: &Path,
+    passphrase: &str,
+) -> Result<BackupManifest> {
+    let mut total_bytes = 0;
+    for (_, path) in files {
+        total_bytes += std::fs::metadata(path)?.len();
+    }
+
+    let manifest = BackupManifest {
+        node_id: node_id.to_string(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        file_count: files.len(),
+        total_bytes,
+    };
+
+    let partial = out.with_extension("partial");
+    let result = (|| -> Result<()> {
+        let file = File::create(&partial)
+            .with_context(|| format!("Failed to create backup file {:?}", partial))?;
+        let mut writer = EncryptedWriter::new(BufWriter::new(file), passphrase)?;
+
+        let manifest_json = serde_json::to_vec(&manifest)?;
+        writer.write_all(&(manifest_json.len() as u32).to_le_bytes())?;
+        writer.write_all(&manifest_json)?;
+
+        for (relative, path) in files {
+            write_file_record(&mut writer, relative, path)?;
+        }
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        let mut inner = writer.finish()?;
+        inner.flush()?;
+        inner.get_ref().sync_all()?;
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    std::fs::rename(&partial, out)
+        .with_context(|| format!("Failed to move backup into place at {:?}", out))?;
+    Ok(manifest)
+}
+
+/// Restore an encrypted backup into a new data directory
+///
+/// `target` must not exist or be empty. A wrong passphrase is detected
+/// before anything is written. If restoring fails part way, whatever was
+/// written to `target` is removed again.
+pub fn restore_backup(archive: &Path, passphrase: &str, target: &Path) -> Result<BackupManifest> {
+    if target.exists() && std::fs::read_dir(target)?.next().is_some() {
+        bail!("Restore target {:?} is not empty", target);
+    }
+
+    let file =
+        File::open(archive).with_context(|| format!("Failed to open backup {:?}", archive))?;
+    let mut reader = DecryptedReader::new(BufReader::new(file), passphrase)?;
+
+    let manifest_len = read_u32(&mut reader)? as usize;
+    if manifest_len > MAX_CHUNK_LEN {
+        bail!("Backup manifest is too large");
+    }
+    let mut manifest_json = vec![0u8; manifest_len];
+    reader.read_exact(&mut manifest_json)?;
+    let manifest: BackupManifest =
+        serde_json::from_slice(&manifest_json).context("Backup manifest is unreadable")?;
+
+    std::fs::create_dir_all(target)?;
+    let result = (|| -> Result<()> {
+        let mut restored = 0;
+        loop {
+            let path_len = read_u32(&mut reader)? as usize;
+            if path_len == 0 {
+                break;
+            }
+            if path_len > 4096 {
+                bail!("Backup entry path is too long");
+            }
+            let mut path = vec![0u8; path_len];
+            reader.read_exact(&mut path)?;
+            let relative = String::from_utf8(path).context("Backup entry path is not UTF-8")?;
+            let dest = safe_join(target, &relative)?;
+            let size = read_u64(&mut reader)?;
+
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut out = BufWriter::new(File::create(&dest)?);
+            let copied = std::io::copy(&mut (&mut reader).take(size), &mut out)?;
+            if copied != size {
+                bail!("Backup ended inside {}", relative);
+            }
+            out.flush()?;
+            restored += 1;
+        }
+
+        if restored != manifest.file_count {
+            bail!(
+                "Backup holds {} files but its manifest lists {}",
+                restored,
+                manifest.file_count
+            );
+        }
+        reader.expect_end()
+    })();
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(target);
+        return Err(e);
+    }
+    Ok(manifest)
+}
+
+/// Collect the files of the data directory to back up, as
+/// (relative path with `/` separators, absolute path)
+fn collect_files(data_dir: &Path, files: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    let walker = walkdir::WalkDir::new(data_dir)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| {
+            let relative = relative_path(data_dir, entry.path());
+            !EXCLUDED_PATHS.contains(&relative.as_str())
+        });
+
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = relative_path(data_dir, entry.path());
+        files.push((relative, entry.path().to_path_buf()));
+    }
+    files[1..].sort();
+    Ok(())
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Join an archive path onto the restore target, rejecting anything that
+/// could escape it
+fn safe_join(root: &Path, relative: &str) -> Result<PathBuf> {
+    let path = Path::new(relative);
+    let is_safe = !relative.is_empty()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if !is_safe {
+        bail!("Backup contains an unsafe path: {}", relative);
+    }
+    Ok(root.join(path))
+}
+
+fn write_file_record<W: Write>(writer: &mut W, relative: &str, path: &Path) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let size = file.metadata()?.len();
+
+    writer.write_all(&(relative.len() as u32).to_le_bytes())?;
+    writer.write_all(relative.as_bytes())?;
+    writer.write_all(&size.to_le_bytes())?;
+
+    // Files still being appended to are cut at the size recorded above
+    let copied = std::io::copy(&mut BufReader::new(file).take(size), writer)?;
+    if copied != size {
+        bail!("{} changed while it was being backed up", relative);
+    }
+    Ok(())
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    memory: u32,
+    iterations: u32,
+    lanes: u32,
+) -> Result<XChaCha20Poly1305> {
+    let mut key =
+        crate::crypto::passphrase::derive_key(passphrase, salt, memory, iterations, lanes)
+            .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    let cipher = XChaCha20Poly1305::new((&key).into());
+    key.fill(0);
+    Ok(cipher)
+}
+
+fn chunk_nonce(prefix: &[u8; NONCE_PREFIX_LEN], counter: u64) -> XNonce {
+    let mut nonce = [0u8; 24];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_LEN..].copy_from_slice(&counter.to_le_bytes());
+    nonce.into()
+}
+
+fn chunk_aad(header: &[u8], last: bool) -> Vec<u8> {
+    let mut aad = header.to_vec();
+    aad.push(last as u8);
+    aad
+}
+
+/// Encrypts everything written to it into archive chunks
+struct EncryptedWriter<W: Write> {
+    inner: W,
+    cipher: XChaCha20Poly1305,
+    header: Vec<u8>,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u64,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> EncryptedWriter<W> {
+    fn new(mut inner: W, passphrase: &str) -> Result<Self> {
+        let salt: [u8; SALT_LEN] = rand::random();
+        let nonce_prefix: [u8; NONCE_PREFIX_LEN] = rand::random();
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(FORMAT_VERSION);
+        header.extend_from_slice(&KDF_MEMORY_KIB.to_le_bytes());
+        header.extend_from_slice(&KDF_ITERATIONS.to_le_bytes());
+        header.extend_from_slice(&KDF_PARALLELISM.to_le_bytes());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce_prefix);
+        inner.write_all(&header)?;
+
+        let cipher =
+            derive_key(passphrase, &salt, KDF_MEMORY_KIB, KDF_ITERATIONS, KDF_PARALLELISM)?;
+        Ok(Self {
+            inner,
+            cipher,
+            header,
+            nonce_prefix,
+            counter: 0,
+            buf: Vec::with_capacity(CHUNK_SIZE),
+        })
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> std::io::Result<()> {
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter);
+        let aad = chunk_aad(&self.header, last);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: &self.buf, aad: &aad })
+            .map_err(|_| std::io::Error::other("Failed to encrypt backup chunk"))?;
+
+        self.inner.write_all(&(ciphertext.len() as u32).to_le_bytes())?;
+        self.inner.write_all(&[last as u8])?;
+        self.inner.write_all(&ciphertext)?;
+        self.counter += 1;
+        self.buf.clear();
+        Ok(())
+    }
+
+    /// Seal the final chunk and return the underlying writer
+    fn finish(mut self) -> std::io::Result<W> {
+        self.seal_chunk(true)?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptedWriter<W> {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        let n = data.len().min(CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == CHUNK_SIZE {
+            self.seal_chunk(false)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts archive chunks, failing on any tampering or truncation
+struct DecryptedReader<R: Read> {
+    inner: R,
+    cipher: XChaCha20Poly1305,
+    header: Vec<u8>,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u64,
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> DecryptedReader<R> {
+    /// Read the header and check the passphrase against the first chunk
+    fn new(mut inner: R, passphrase: &str) -> Result<Self> {
+        let mut header = vec![0u8; HEADER_LEN];
+        inner
+            .read_exact(&mut header)
+            .map_err(|_| anyhow!("Not a backup archive"))?;
+        if &header[..MAGIC.len()] != MAGIC {
+            bail!("Not a backup archive");
+        }
+        let mut pos = MAGIC.len();
+        if header[pos] != FORMAT_VERSION {
+            bail!("Unsupported backup format version {}", header[pos]);
+        }
+        pos += 1;
+
+        let param = |pos: &mut usize| {
+            let value = u32::from_le_bytes(header[*pos..*pos + 4].try_into().unwrap_or_default());
+            *pos += 4;
+            value
+        };
+        let memory = param(&mut pos);
+        let iterations = param(&mut pos);
+        let lanes = param(&mut pos);
+        let salt = header[pos..pos + SALT_LEN].to_vec();
+        pos += SALT_LEN;
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        nonce_prefix.copy_from_slice(&header[pos..pos + NONCE_PREFIX_LEN]);
+
+        let cipher = derive_key(passphrase, &salt, memory, iterations, lanes)?;
+        let mut reader = Self {
+            inner,
+            cipher,
+            header,
+            nonce_prefix,
+            counter: 0,
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        };
+
+        // The first chunk only opens with the right passphrase
+        reader
+            .open_chunk()
+            .map_err(|_| anyhow!("Wrong passphrase or corrupted backup"))?;
+        Ok(reader)
+    }
+
+    fn open_chunk(&mut self) -> std::io::Result<()> {
+        let mut prefix = [0u8; 5];
+        self.inner.read_exact(&mut prefix).map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Backup is truncated")
+        })?;
+        let len = u32::from_le_bytes([prefix[0], prefix[1], prefix[2], prefix[3]]) as usize;
+        let last = prefix[4] == 1;
+        if len > MAX_CHUNK_LEN || prefix[4] > 1 {
+            return Err(std::io::Error::other("Backup chunk is malformed"));
+        }
+
+        let mut ciphertext = vec![0u8; len];
+        self.inner.read_exact(&mut ciphertext)?;
+        let nonce = chunk_nonce(&self.nonce_prefix, self.counter);
+        let aad = chunk_aad(&self.header, last);
+        self.buf = self
+            .cipher
+            .decrypt(&nonce, Payload { msg: &ciphertext, aad: &aad })
+            .map_err(|_| std::io::Error::other("Backup chunk failed authentication"))?;
+        self.pos = 0;
+        self.counter += 1;
+        self.done = last;
+        Ok(())
+    }
+
+    /// Check that the archive ends right after the records
+    fn expect_end(&mut self) -> Result<()> {
+        if self.pos < self.buf.len() || !self.done {
+            bail!("Backup has unexpected data after its last entry");
+        }
+        let mut trailing = [0u8; 1];
+        if self.inner.read(&mut trailing)? != 0 {
+            bail!("Backup has unexpected data after its last chunk");
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptedReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.open_chunk()?;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_backup_roundtrip() {
+        let data = tempdir().unwrap();
+        std::fs::create_dir_all(data.path().join("docs")).unwrap();
+        std::fs::create_dir_all(data.path().join("blobs-tmp")).unwrap();
+        std::fs::write(data.path().join("docs/store.db"), vec![7u8; CHUNK_SIZE * 2 + 5]).unwrap();
+        std::fs::write(data.path().join("blobs-tmp/partial"), b"in progress").unwrap();
+        std::fs::write(data.path().join(DATABASE_FILE), b"live database").unwrap();
+
+        let out = tempdir().unwrap();
+        let staging = create_staging_dir(data.path()).unwrap();
+        assert!(staging.starts_with(data.path()));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&staging).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+        let snapshot = staging.join(DATABASE_FILE);
+        std::fs::write(&snapshot, b"snapshot with secret key").unwrap();
+        let archive = out.path().join("backup.gixbak");
+        let manifest = write_backup(
+            data.path(),
+            &staging,
+            &snapshot,
+            "abcd",
+            &archive,
+            "correct horse",
+        )
+        .unwrap();
+        // The staging directory itself isn't backed up
+        assert_eq!(manifest.file_count, 2);
+        // The store database went in as a snapshot, which is cleaned up
+        assert!(is_store_database("docs/store.db"));
+        assert!(!staging.join("stores").exists());
+        assert_eq!(std::fs::read_dir(out.path()).unwrap().count(), 1);
+
+        // Neither the passphrase nor any contents appear in the archive
+        let bytes = std::fs::read(&archive).unwrap();
+        assert!(!bytes.windows(6).any(|w| w == b"secret"));
+        assert!(!bytes.windows(7).any(|w| w == b"correct"));
+
+        let target = out.path().join("restored");
+        assert!(restore_backup(&archive, "wrong passphrase", &target).is_err());
+        assert!(!target.exists());
+
+        let restored = restore_backup(&archive, "correct horse", &target).unwrap();
+        assert_eq!(restored.node_id, "abcd");
+        assert_eq!(
+            std::fs::read(target.join(DATABASE_FILE)).unwrap(),
+            b"snapshot with secret key"
+        );
+        assert_eq!(std::fs::read(target.join("docs/store.db")).unwrap().len(), CHUNK_SIZE * 2 + 5);
+        assert!(!target.join("blobs-tmp").exists());
+    }
+
+    #[test]
+    fn test_restore_rejects_tampering() {
+        let data = tempdir().unwrap();
+        std::fs::write(data.path().join("settings.json"), b"{}").unwrap();
+        let out = tempdir().unwrap();
+        let staging = create_staging_dir(data.path()).unwrap();
+        let snapshot = staging.join(DATABASE_FILE);
+        std::fs::write(&snapshot, b"db").unwrap();
+        let archive = out.path().join("backup.gixbak");
+        write_backup(
+            data.path(),
+            &staging,
+            &snapshot,
+            "abcd",
+            &archive,
+            "correct horse",
+        )
+        .unwrap();
+
+        let bytes = std::fs::read(&archive).unwrap();
+        let truncated = out.path().join("truncated.gixbak");
+        std::fs::write(&truncated, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(restore_backup(&truncated, "correct horse", &out.path().join("a")).is_err());
+
+        let mut flipped = bytes.clone();
+        let last = flipped.len() - 1;
+        flipped[last] ^= 1;
+        let tampered = out.path().join("tampered.gixbak");
+        std::fs::write(&tampered, &flipped).unwrap();
+        assert!(restore_backup(&tampered, "correct horse", &out.path().join("b")).is_err());
+
+        assert!(safe_join(out.path(), "../escape").is_err());
+        assert!(safe_join(out.path(), "/etc/passwd").is_err());
+        assert!(safe_join(out.path(), "docs/store.db").is_ok());
+    }
+}
@@ -1,6 +1,10 @@
-use anyhow::Result;
-use redb::{Database as RedbDatabase, ReadableTable, ReadableTableMetadata, TableDefinition};
-use std::path::Path;
+use anyhow::{Context, Result};
+use redb::{
+    Database as RedbDatabase, DatabaseError, Key, ReadTransaction, ReadableTable,
+    ReadableTableMetadata, StorageError, TableDefinition, Value, WriteTransaction,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 
 // Table definitions
 const IDENTITY_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("identity");
@@ -9,23 +13,61 @@ const ACLS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("acls");
 const TOKEN_TRACKERS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("token_trackers");
 const KEY_EXCHANGE_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("key_exchange");
 const DRIVE_KEYS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("drive_keys");
+/// Passphrase-sealed drive keys - key: drive ID, value: serialized ProtectedDriveKey
+const PROTECTED_DRIVE_KEYS_TABLE: TableDefinition<&str, &[u8]> =
+    TableDefinition::new("protected_drive_keys");
 const AUDIT_LOG_TABLE: TableDefinition<u64, &[u8]> = TableDefinition::new("audit_log");
 const AUDIT_COUNTER_TABLE: TableDefinition<&str, u64> = TableDefinition::new("audit_counter");
 const REVOKED_TOKENS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("revoked_tokens");
+/// Share links table - key: drive ID (hex), value: serialized ShareLinkRecords
+const SHARE_LINKS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("share_links");
 /// Drive namespace table - key: drive_id bytes, value: NamespaceId bytes
 const DOC_NAMESPACE_TABLE: TableDefinition<&[u8], &[u8]> = TableDefinition::new("doc_namespaces");
 /// File metadata table - key: "drive_id:file_path", value: serialized FileMetadata
 const FILE_METADATA_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("file_metadata");
+/// App settings table - key: setting name, value: serialized setting
+const SETTINGS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("settings");
+const ISSUED_INVITES_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("issued_invites");
+const READ_RECEIPTS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("read_receipts");
+/// Unfinished transfers table - key: transfer ID, value: serialized TransferState
+const TRANSFERS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("transfers");
+/// Outbound queue table - key: entry ID, value: serialized PendingUpload
+const OUTBOUND_QUEUE_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("outbound_queue");
+/// Selective sync filters table - key: drive ID (hex), value: serialized SyncFilters
+const SYNC_FILTERS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("sync_filters");
+/// File version history table - key: "drive_id:file_path", value: serialized FileHistory
+const FILE_HISTORY_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("file_history");
+
+/// Setting that enables the integrity check when the database is opened
+pub const STARTUP_INTEGRITY_CHECK_SETTING: &str = "startup_integrity_check";
+
+/// Extension appended to the database file name for the last verified copy
+const BACKUP_EXTENSION: &str = "bak";
+
+/// Extension appended to a corrupt database file set aside during recovery
+const CORRUPT_EXTENSION: &str = "corrupt";
+
+/// Result of reading every entry of one table
+#[derive(Clone, Debug, Serialize)]
+pub struct TableCheck {
+    pub table: String,
+    /// Entries read before the scan finished or failed
+    pub entries: u64,
+    /// Error hit while reading, if any
+    pub error: Option<String>,
+}
 
 /// Database wrapper for persistent storage using redb
 pub struct Database {
     db: RedbDatabase,
+    path: PathBuf,
 }
 
 impl Database {
     /// Open or create database at the given path
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
-        let db = RedbDatabase::create(path)?;
+        let path = path.as_ref().to_path_buf();
+        let db = RedbDatabase::create(&path)?;
 
         // Initialize tables
         let write_txn = db.begin_write()?;
@@ -36,15 +78,174 @@ impl Database {
             let _ = write_txn.open_table(TOKEN_TRACKERS_TABLE)?;
             let _ = write_txn.open_table(KEY_EXCHANGE_TABLE)?;
             let _ = write_txn.open_table(DRIVE_KEYS_TABLE)?;
+            let _ = write_txn.open_table(PROTECTED_DRIVE_KEYS_TABLE)?;
             let _ = write_txn.open_table(AUDIT_LOG_TABLE)?;
             let _ = write_txn.open_table(AUDIT_COUNTER_TABLE)?;
             let _ = write_txn.open_table(REVOKED_TOKENS_TABLE)?;
+            let _ = write_txn.open_table(SHARE_LINKS_TABLE)?;
             let _ = write_txn.open_table(DOC_NAMESPACE_TABLE)?;
             let _ = write_txn.open_table(FILE_METADATA_TABLE)?;
+            let _ = write_txn.open_table(SETTINGS_TABLE)?;
+            let _ = write_txn.open_table(ISSUED_INVITES_TABLE)?;
+            let _ = write_txn.open_table(READ_RECEIPTS_TABLE)?;
+            let _ = write_txn.open_table(TRANSFERS_TABLE)?;
+            let _ = write_txn.open_table(OUTBOUND_QUEUE_TABLE)?;
+            let _ = write_txn.open_table(SYNC_FILTERS_TABLE)?;
+            let _ = write_txn.open_table(FILE_HISTORY_TABLE)?;
         }
         write_txn.commit()?;
 
-        Ok(Self { db })
+        Ok(Self { db, path })
+    }
+
+    /// Open the database, verifying it first unless the user opted out
+    ///
+    /// A database that passes the check is copied to `<file>.bak`. If the
+    /// store is corrupt and cannot be repaired, the corrupt file is moved to
+    /// `<file>.corrupt` and the backup restored; without a backup a clear
+    /// error is returned instead of carrying on with a damaged store.
+    pub fn open_verified(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+
+        let err = match Self::open_and_check(path) {
+            Ok(db) => return Ok(db),
+            Err(e) if is_corruption(&e) => e,
+            Err(e) => return Err(e),
+        };
+
+        let backup = sibling_path(path, BACKUP_EXTENSION);
+        if !backup.exists() {
+            return Err(err.context(format!(
+                "Database at {:?} is corrupted and no backup is available",
+                path
+            )));
+        }
+
+        tracing::error!(
+            path = ?path,
+            error = %err,
+            "Database is corrupted, restoring from backup"
+        );
+
+        let corrupt = sibling_path(path, CORRUPT_EXTENSION);
+        std::fs::rename(path, &corrupt)
+            .with_context(|| format!("Failed to move corrupt database to {:?}", corrupt))?;
+        std::fs::copy(&backup, path)
+            .with_context(|| format!("Failed to restore database from {:?}", backup))?;
+
+        let db = Self::open_and_check(path).with_context(|| {
+            format!("Database at {:?} is corrupted and its backup is unusable", path)
+        })?;
+        tracing::warn!(
+            path = ?path,
+            corrupt = ?corrupt,
+            "Database restored from backup; changes since the backup are lost"
+        );
+        Ok(db)
+    }
+
+    fn open_and_check(path: &Path) -> Result<Self> {
+        let mut db = Self::open(path)?;
+        if !db.startup_integrity_check_enabled() {
+            return Ok(db);
+        }
+
+        if !db.db.check_integrity()? {
+            tracing::warn!(path = ?path, "Database integrity check repaired the store");
+        }
+
+        let backup = sibling_path(path, BACKUP_EXTENSION);
+        if let Err(e) = std::fs::copy(path, &backup) {
+            tracing::warn!(backup = ?backup, error = %e, "Failed to back up database");
+        }
+
+        Ok(db)
+    }
+
+    /// Whether the database is verified when opened (default: true)
+    pub fn startup_integrity_check_enabled(&self) -> bool {
+        match self.get_setting(STARTUP_INTEGRITY_CHECK_SETTING) {
+            Ok(Some(bytes)) => serde_json::from_slice(&bytes).unwrap_or(true),
+            _ => true,
+        }
+    }
+
+    /// Enable or disable the integrity check when the database is opened
+    pub fn set_startup_integrity_check(&self, enabled: bool) -> Result<()> {
+        self.save_setting(STARTUP_INTEGRITY_CHECK_SETTING, &serde_json::to_vec(&enabled)?)
+    }
+
+    /// Path of the last verified copy of the database, if one exists
+    pub fn backup_path(&self) -> Option<PathBuf> {
+        Some(sibling_path(&self.path, BACKUP_EXTENSION)).filter(|p| p.exists())
+    }
+
+    /// Read every entry of every table
+    ///
+    /// The page-level check needs exclusive access and only runs at open;
+    /// this scan can run while the app is in use and catches entries that
+    /// can no longer be read.
+    pub fn verify_tables(&self) -> Result<Vec<TableCheck>> {
+        let txn = self.db.begin_read()?;
+        Ok(vec![
+            scan_table(&txn, "identity", IDENTITY_TABLE),
+            scan_table(&txn, "drives", DRIVES_TABLE),
+            scan_table(&txn, "acls", ACLS_TABLE),
+            scan_table(&txn, "token_trackers", TOKEN_TRACKERS_TABLE),
+            scan_table(&txn, "key_exchange", KEY_EXCHANGE_TABLE),
+            scan_table(&txn, "drive_keys", DRIVE_KEYS_TABLE),
+            scan_table(&txn, "protected_drive_keys", PROTECTED_DRIVE_KEYS_TABLE),
+            scan_table(&txn, "audit_log", AUDIT_LOG_TABLE),
+            scan_table(&txn, "audit_counter", AUDIT_COUNTER_TABLE),
+            scan_table(&txn, "revoked_tokens", REVOKED_TOKENS_TABLE),
+            scan_table(&txn, "share_links", SHARE_LINKS_TABLE),
+            scan_table(&txn, "doc_namespaces", DOC_NAMESPACE_TABLE),
+            scan_table(&txn, "file_metadata", FILE_METADATA_TABLE),
+            scan_table(&txn, "settings", SETTINGS_TABLE),
+            scan_table(&txn, "issued_invites", ISSUED_INVITES_TABLE),
+            scan_table(&txn, "read_receipts", READ_RECEIPTS_TABLE),
+            scan_table(&txn, "transfers", TRANSFERS_TABLE),
+            scan_table(&txn, "outbound_queue", OUTBOUND_QUEUE_TABLE),
+            scan_table(&txn, "sync_filters", SYNC_FILTERS_TABLE),
+            scan_table(&txn, "file_history", FILE_HISTORY_TABLE),
+        ])
+    }
+
+    /// Write a consistent copy of every table to a new database file
+    ///
+    /// All tables are read in one transaction, so the copy reflects a single
+    /// point in time even while the app keeps writing. Fails if `path`
+    /// already exists.
+    pub fn snapshot_to(&self, path: &Path) -> Result<()> {
+        if path.exists() {
+            anyhow::bail!("Snapshot target {:?} already exists", path);
+        }
+
+        let src = self.db.begin_read()?;
+        let target = RedbDatabase::create(path)?;
+        let dst = target.begin_write()?;
+        copy_table(&src, &dst, IDENTITY_TABLE)?;
+        copy_table(&src, &dst, DRIVES_TABLE)?;
+        copy_table(&src, &dst, ACLS_TABLE)?;
+        copy_table(&src, &dst, TOKEN_TRACKERS_TABLE)?;
+        copy_table(&src, &dst, KEY_EXCHANGE_TABLE)?;
+        copy_table(&src, &dst, DRIVE_KEYS_TABLE)?;
+        copy_table(&src, &dst, PROTECTED_DRIVE_KEYS_TABLE)?;
+        copy_table(&src, &dst, AUDIT_LOG_TABLE)?;
+        copy_table(&src, &dst, AUDIT_COUNTER_TABLE)?;
+        copy_table(&src, &dst, REVOKED_TOKENS_TABLE)?;
+        copy_table(&src, &dst, SHARE_LINKS_TABLE)?;
+        copy_table(&src, &dst, DOC_NAMESPACE_TABLE)?;
+        copy_table(&src, &dst, FILE_METADATA_TABLE)?;
+        copy_table(&src, &dst, SETTINGS_TABLE)?;
+        copy_table(&src, &dst, ISSUED_INVITES_TABLE)?;
+        copy_table(&src, &dst, READ_RECEIPTS_TABLE)?;
+        copy_table(&src, &dst, TRANSFERS_TABLE)?;
+        copy_table(&src, &dst, OUTBOUND_QUEUE_TABLE)?;
+        copy_table(&src, &dst, SYNC_FILTERS_TABLE)?;
+        copy_table(&src, &dst, FILE_HISTORY_TABLE)?;
+        dst.commit()?;
+        Ok(())
     }
 
     /// Get stored identity secret key bytes
@@ -286,7 +487,6 @@ impl Database {
     }
 
     /// Delete a drive key
-    #[allow(dead_code)]
     pub fn delete_drive_key(&self, drive_id: &str) -> Result<bool> {
         let write_txn = self.db.begin_write()?;
         let removed = {
@@ -298,6 +498,41 @@ impl Database {
         Ok(removed)
     }
 
+    /// Load all wrapped drive keys from database
+    pub fn list_drive_keys(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(DRIVE_KEYS_TABLE)?;
+
+        let mut keys = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            keys.push((key.value().to_string(), value.value().to_vec()));
+        }
+        Ok(keys)
+    }
+
+    /// Save a drive key sealed under the passphrase master key
+    pub fn save_protected_drive_key(&self, drive_id: &str, sealed_key: &[u8]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(PROTECTED_DRIVE_KEYS_TABLE)?;
+            table.insert(drive_id, sealed_key)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get a passphrase-sealed drive key
+    pub fn get_protected_drive_key(&self, drive_id: &str) -> Result<Option<Vec<u8>>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(PROTECTED_DRIVE_KEYS_TABLE)?;
+
+        match table.get(drive_id)? {
+            Some(guard) => Ok(Some(guard.value().to_vec())),
+            None => Ok(None),
+        }
+    }
+
     // ============================================================================
     // Audit Log Operations
     // ============================================================================
@@ -431,6 +666,34 @@ impl Database {
         Ok(tokens)
     }
 
+    // ============================================================================
+    // Share Link Operations
+    // ============================================================================
+
+    /// Save the issued and revoked share links for a drive
+    pub fn save_share_links(&self, drive_id: &str, data: &[u8]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(SHARE_LINKS_TABLE)?;
+            table.insert(drive_id, data)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load all share link records from database
+    pub fn list_share_links(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SHARE_LINKS_TABLE)?;
+
+        let mut links = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            links.push((key.value().to_string(), value.value().to_vec()));
+        }
+        Ok(links)
+    }
+
     // ============================================================================
     // Doc Namespace Operations
     // ============================================================================
@@ -467,6 +730,13 @@ impl Database {
         }
     }
 
+    /// Get the raw stored namespace for a drive, even if it is malformed
+    pub fn get_doc_namespace_bytes(&self, drive_id: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(DOC_NAMESPACE_TABLE)?;
+        Ok(table.get(drive_id.as_slice())?.map(|guard| guard.value().to_vec()))
+    }
+
     /// Load all document namespaces from database
     pub fn list_doc_namespaces(&self) -> Result<Vec<([u8; 32], [u8; 32])>> {
         let read_txn = self.db.begin_read()?;
@@ -598,6 +868,349 @@ impl Database {
         write_txn.commit()?;
         Ok(deleted)
     }
+
+    // ============================================================================
+    // Settings Operations
+    // ============================================================================
+
+    /// Save an app setting
+    pub fn save_setting(&self, key: &str, data: &[u8]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(SETTINGS_TABLE)?;
+            table.insert(key, data)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get an app setting
+    pub fn get_setting(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SETTINGS_TABLE)?;
+
+        match table.get(key)? {
+            Some(guard) => Ok(Some(guard.value().to_vec())),
+            None => Ok(None),
+        }
+    }
+
+    /// Delete an app setting (reverting it to its default)
+    pub fn delete_setting(&self, key: &str) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_txn.open_table(SETTINGS_TABLE)?;
+            let result = table.remove(key)?;
+            result.is_some()
+        };
+        write_txn.commit()?;
+        Ok(removed)
+    }
+
+    // ============================================================================
+    // Issued Invite Operations
+    // ============================================================================
+
+    /// Save issued invite records for a drive
+    pub fn save_issued_invites(&self, drive_id: &str, data: &[u8]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(ISSUED_INVITES_TABLE)?;
+            table.insert(drive_id, data)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load all issued invite records from database
+    pub fn list_issued_invites(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(ISSUED_INVITES_TABLE)?;
+
+        let mut invites = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            invites.push((key.value().to_string(), value.value().to_vec()));
+        }
+        Ok(invites)
+    }
+
+    // ============================================================================
+    // Read Receipt Operations
+    // ============================================================================
+
+    /// Save read receipts for a drive
+    pub fn save_read_receipts(&self, drive_id: &str, data: &[u8]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(READ_RECEIPTS_TABLE)?;
+            table.insert(drive_id, data)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load all read receipts from database
+    pub fn list_read_receipts(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(READ_RECEIPTS_TABLE)?;
+
+        let mut receipts = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            receipts.push((key.value().to_string(), value.value().to_vec()));
+        }
+        Ok(receipts)
+    }
+
+    // ============================================================================
+    // Transfer Operations
+    // ============================================================================
+
+    /// Save the state of an unfinished transfer
+    pub fn save_transfer(&self, transfer_id: &str, data: &[u8]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(TRANSFERS_TABLE)?;
+            table.insert(transfer_id, data)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load all unfinished transfers from database
+    pub fn list_transfers(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(TRANSFERS_TABLE)?;
+
+        let mut transfers = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            transfers.push((key.value().to_string(), value.value().to_vec()));
+        }
+        Ok(transfers)
+    }
+
+    /// Delete a transfer's saved state, returning whether one existed
+    pub fn delete_transfer(&self, transfer_id: &str) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_txn.open_table(TRANSFERS_TABLE)?;
+            let result = table.remove(transfer_id)?;
+            result.is_some()
+        };
+        write_txn.commit()?;
+        Ok(removed)
+    }
+
+    // ============================================================================
+    // Outbound Queue Operations
+    // ============================================================================
+
+    /// Save one queued outbound change
+    pub fn save_pending_upload(&self, id: &str, data: &[u8]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(OUTBOUND_QUEUE_TABLE)?;
+            table.insert(id, data)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load all queued outbound changes from database
+    pub fn list_pending_uploads(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(OUTBOUND_QUEUE_TABLE)?;
+
+        let mut pending = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            pending.push((key.value().to_string(), value.value().to_vec()));
+        }
+        Ok(pending)
+    }
+
+    /// Delete queued outbound changes in one transaction
+    pub fn delete_pending_uploads(&self, ids: &[String]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(OUTBOUND_QUEUE_TABLE)?;
+            for id in ids {
+                table.remove(id.as_str())?;
+            }
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Sync Filter Operations
+    // ============================================================================
+
+    /// Save selective sync filters for a drive
+    pub fn save_sync_filters(&self, drive_id: &str, data: &[u8]) -> Result<()> {
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(SYNC_FILTERS_TABLE)?;
+            table.insert(drive_id, data)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load all selective sync filters from database
+    pub fn list_sync_filters(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SYNC_FILTERS_TABLE)?;
+
+        let mut filters = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            filters.push((key.value().to_string(), value.value().to_vec()));
+        }
+        Ok(filters)
+    }
+
+    /// Delete a drive's sync filters, returning whether any were saved
+    pub fn delete_sync_filters(&self, drive_id: &str) -> Result<bool> {
+        let write_txn = self.db.begin_write()?;
+        let removed = {
+            let mut table = write_txn.open_table(SYNC_FILTERS_TABLE)?;
+            let result = table.remove(drive_id)?;
+            result.is_some()
+        };
+        write_txn.commit()?;
+        Ok(removed)
+    }
+
+    // ============================================================================
+    // File History Operations
+    // ============================================================================
+
+    /// Save the version history of a file
+    pub fn save_file_history(&self, drive_id: &str, path: &str, data: &[u8]) -> Result<()> {
+        let key = Self::file_metadata_key(drive_id, path);
+        let write_txn = self.db.begin_write()?;
+        {
+            let mut table = write_txn.open_table(FILE_HISTORY_TABLE)?;
+            table.insert(key.as_str(), data)?;
+        }
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Get the version history of a file
+    pub fn get_file_history(&self, drive_id: &str, path: &str) -> Result<Option<Vec<u8>>> {
+        let key = Self::file_metadata_key(drive_id, path);
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(FILE_HISTORY_TABLE)?;
+
+        match table.get(key.as_str())? {
+            Some(guard) => Ok(Some(guard.value().to_vec())),
+            None => Ok(None),
+        }
+    }
+
+    /// List the version histories of a drive's files (returns path and serialized history)
+    pub fn list_file_history(&self, drive_id: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let prefix = format!("{}:", drive_id);
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(FILE_HISTORY_TABLE)?;
+
+        let mut histories = Vec::new();
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            if let Some(path) = key.value().strip_prefix(&prefix) {
+                histories.push((path.to_string(), value.value().to_vec()));
+            }
+        }
+        Ok(histories)
+    }
+
+    /// Delete the version history of every file in a drive
+    pub fn delete_drive_history(&self, drive_id: &str) -> Result<usize> {
+        let prefix = format!("{}:", drive_id);
+        let write_txn = self.db.begin_write()?;
+        let deleted = {
+            let mut table = write_txn.open_table(FILE_HISTORY_TABLE)?;
+            let keys: Vec<String> = table
+                .iter()?
+                .filter_map(|entry| entry.ok())
+                .map(|(key, _)| key.value().to_string())
+                .filter(|key| key.starts_with(&prefix))
+                .collect();
+            for key in &keys {
+                table.remove(key.as_str())?;
+            }
+            keys.len()
+        };
+        write_txn.commit()?;
+        Ok(deleted)
+    }
+}
+
+/// `<file>.<extension>` next to the database file
+fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+/// Whether an open error means the file itself is damaged
+fn is_corruption(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<DatabaseError>(),
+            Some(DatabaseError::Storage(StorageError::Corrupted(_)))
+        ) || matches!(
+            cause.downcast_ref::<StorageError>(),
+            Some(StorageError::Corrupted(_))
+        )
+    })
+}
+
+fn scan_table<K: Key + 'static, V: Value + 'static>(
+    txn: &ReadTransaction,
+    name: &str,
+    definition: TableDefinition<K, V>,
+) -> TableCheck {
+    let mut check = TableCheck {
+        table: name.to_string(),
+        entries: 0,
+        error: None,
+    };
+
+    let result = (|| -> Result<()> {
+        let table = txn.open_table(definition)?;
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            let _ = (key.value(), value.value());
+            check.entries += 1;
+        }
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        check.error = Some(e.to_string());
+    }
+    check
+}
+
+/// Copy every entry of a table into the same table of another database
+fn copy_table<K: Key + 'static, V: Value + 'static>(
+    src: &ReadTransaction,
+    dst: &WriteTransaction,
+    definition: TableDefinition<K, V>,
+) -> Result<()> {
+    let source = src.open_table(definition)?;
+    let mut target = dst.open_table(definition)?;
+    for entry in source.iter()? {
+        let (key, value) = entry?;
+        target.insert(key.value(), value.value())?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -605,6 +1218,27 @@ mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_open_verified_backup() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("test.redb");
+        let backup = sibling_path(&db_path, BACKUP_EXTENSION);
+
+        let db = Database::open_verified(&db_path).unwrap();
+        assert!(db.startup_integrity_check_enabled());
+        assert_eq!(db.backup_path(), Some(backup.clone()));
+        db.save_setting("k", b"v").unwrap();
+        assert!(db.verify_tables().unwrap().iter().all(|t| t.error.is_none()));
+
+        db.set_startup_integrity_check(false).unwrap();
+        drop(db);
+        std::fs::remove_file(&backup).unwrap();
+
+        let db = Database::open_verified(&db_path).unwrap();
+        assert!(!db.startup_integrity_check_enabled());
+        assert_eq!(db.backup_path(), None);
+    }
+
     #[test]
     fn test_doc_namespace_roundtrip() {
         let dir = tempdir().unwrap();
@@ -618,6 +1252,10 @@ mod tests {
 
         let loaded = db.get_doc_namespace(&drive_id).unwrap();
         assert_eq!(loaded, Some(namespace));
+        assert_eq!(
+            db.get_doc_namespace_bytes(&drive_id).unwrap(),
+            Some(namespace.to_vec())
+        );
 
         let list = db.list_doc_namespaces().unwrap();
         assert!(list
@@ -628,5 +1266,63 @@ mod tests {
         assert!(removed);
         let loaded_after = db.get_doc_namespace(&drive_id).unwrap();
         assert!(loaded_after.is_none());
+        assert!(db.get_doc_namespace_bytes(&drive_id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_to() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path().join("test.redb")).unwrap();
+        db.save_identity(&[7u8; 32]).unwrap();
+        db.save_drive(&[1u8; 32], b"drive").unwrap();
+        db.append_audit_log(b"entry").unwrap();
+
+        let copy_path = dir.path().join("copy.redb");
+        db.snapshot_to(&copy_path).unwrap();
+        assert!(db.snapshot_to(&copy_path).is_err());
+
+        let copy = Database::open(&copy_path).unwrap();
+        assert_eq!(copy.get_identity().unwrap(), Some([7u8; 32]));
+        assert_eq!(copy.get_drive(&[1u8; 32]).unwrap(), Some(b"drive".to_vec()));
+        assert_eq!(copy.count_audit_log().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_file_history_roundtrip() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path().join("test.redb")).unwrap();
+
+        db.save_file_history("drive1", "a.txt", b"first").unwrap();
+        db.save_file_history("drive1", "a.txt", b"second").unwrap();
+        db.save_file_history("drive2", "b.txt", b"other").unwrap();
+        assert_eq!(
+            db.get_file_history("drive1", "a.txt").unwrap(),
+            Some(b"second".to_vec())
+        );
+        assert_eq!(
+            db.list_file_history("drive1").unwrap(),
+            vec![("a.txt".to_string(), b"second".to_vec())]
+        );
+
+        assert_eq!(db.delete_drive_history("drive1").unwrap(), 1);
+        assert!(db.get_file_history("drive1", "a.txt").unwrap().is_none());
+        assert_eq!(db.list_file_history("drive2").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_transfer_roundtrip() {
+        let dir = tempdir().unwrap();
+        let db = Database::open(dir.path().join("test.redb")).unwrap();
+
+        db.save_transfer("upload-1", b"first").unwrap();
+        db.save_transfer("upload-1", b"second").unwrap();
+        assert_eq!(
+            db.list_transfers().unwrap(),
+            vec![("upload-1".to_string(), b"second".to_vec())]
+        );
+
+        assert!(db.delete_transfer("upload-1").unwrap());
+        assert!(!db.delete_transfer("upload-1").unwrap());
+        assert!(db.list_transfers().unwrap().is_empty());
     }
 }
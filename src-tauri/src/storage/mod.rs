@@ -1,3 +1,5 @@
+pub mod backup;
 pub mod db;
 
-pub use db::Database;
+pub use backup::BackupManifest;
+pub use db::{Database, TableCheck};
@@ -0,0 +1,259 @@
+//! In-process test peers for exercising sync on one machine
+//!
+//! Debug builds only. A test peer is a second `AppState` with its own
+//! identity, database, endpoint and drive folder under the system temp
+//! directory. It joins one of our drives with a write ticket and is granted
+//! write access, so sync and conflicts can be reproduced without a second
+//! device. Stopping a peer shuts it down, revokes its access and deletes
+//! its data.
+
+use crate::commands::SecurityStore;
+use crate::core::{DriveId, SharedDrive};
+use crate::crypto::{AccessRule, Permission};
+use crate::state::AppState;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+
+/// Directory under the system temp dir holding test peer profiles
+const TEST_PEER_DIR: &str = "gix-test-peers";
+
+/// A running test peer, as reported to the frontend
+#[derive(Clone, Debug, Serialize)]
+pub struct TestPeerInfo {
+    /// Hex-encoded node ID of the test peer
+    pub node_id: String,
+    pub drive_id: String,
+    /// Folder the peer syncs the drive into; edit files here to act as the peer
+    pub local_path: String,
+    /// Profile directory, deleted when the peer stops
+    pub data_dir: String,
+}
+
+struct TestPeer {
+    state: AppState,
+    info: TestPeerInfo,
+    drive_id: DriveId,
+    /// Drive owner, for revoking the peer's ACL entry
+    owner_hex: String,
+    forwarder: tokio::task::JoinHandle<()>,
+}
+
+/// Spawns and tears down test peers
+pub struct TestPeerManager {
+    peers: RwLock<HashMap<String, TestPeer>>,
+    base_dir: PathBuf,
+}
+
+impl TestPeerManager {
+    pub fn new() -> Self {
+        Self {
+            peers: RwLock::new(HashMap::new()),
+            base_dir: std::env::temp_dir().join(TEST_PEER_DIR),
+        }
+    }
+
+    /// True if test peers can be spawned in this build
+    pub fn is_available() -> bool {
+        cfg!(debug_assertions)
+    }
+
+    /// Start a test peer and join it to one of our drives with write access
+    pub async fn spawn(
+        &self,
+        main: &AppState,
+        security: &SecurityStore,
+        drive_id: DriveId,
+    ) -> Result<TestPeerInfo> {
+        if !Self::is_available() {
+            bail!("Test peers are only available in debug builds");
+        }
+
+        let drive = main
+            .drives
+            .read()
+            .await
+            .get(drive_id.as_bytes())
+            .cloned()
+            .ok_or_else(|| anyhow!("Drive not found: {}", drive_id))?;
+        drive.ensure_active()?;
+
+        let ticket = main
+            .docs_manager
+            .as_ref()
+            .ok_or_else(|| anyhow!("Sync not initialized"))?
+            .get_ticket(&drive_id, Permission::Write)
+            .await
+            .context("Drive has no sync document; start sync first")?;
+        let main_node = main
+            .identity_manager
+            .node_id()
+            .await
+            .ok_or_else(|| anyhow!("Identity not initialized"))?;
+
+        let data_dir = self
+            .base_dir
+            .join(format!("peer-{}", hex::encode(rand::random::<[u8; 8]>())));
+        let owner_hex = drive.owner.to_hex();
+        let state = AppState::initialize(data_dir.clone()).await?;
+
+        match self.join(&state, security, drive, ticket, main_node, &data_dir).await {
+            Ok((info, forwarder)) => {
+                tracing::info!(
+                    node_id = %info.node_id,
+                    drive_id = %info.drive_id,
+                    "Spawned test peer"
+                );
+                self.peers.write().await.insert(
+                    info.node_id.clone(),
+                    TestPeer {
+                        state,
+                        info: info.clone(),
+                        drive_id,
+                        owner_hex,
+                        forwarder,
+                    },
+                );
+                Ok(info)
+            }
+            Err(e) => {
+                state.shutdown().await;
+                let _ = tokio::fs::remove_dir_all(&data_dir).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn join(
+        &self,
+        state: &AppState,
+        security: &SecurityStore,
+        drive: SharedDrive,
+        ticket: iroh_docs::DocTicket,
+        main_node: crate::crypto::NodeId,
+        data_dir: &std::path::Path,
+    ) -> Result<(TestPeerInfo, tokio::task::JoinHandle<()>)> {
+        let node_id = state
+            .identity_manager
+            .node_id()
+            .await
+            .ok_or_else(|| anyhow!("Test peer identity not initialized"))?;
+        let (Some(sync_engine), Some(watcher)) = (&state.sync_engine, &state.file_watcher) else {
+            bail!("Test peer sync components failed to start");
+        };
+
+        // Let the peer's events through our gossip authorization
+        let drive_hex = drive.id.to_hex();
+        let mut acl = security
+            .get_or_create_acl(&drive_hex, &drive.owner.to_hex())
+            .await;
+        let rule = AccessRule::new(Permission::Write, &main_node.to_hex()).with_note("test peer");
+        acl.grant(&node_id.to_hex(), rule);
+        security.update_acl(&drive_hex, acl).await;
+
+        sync_engine.join_drive(drive.id, ticket).await?;
+
+        let local_path = data_dir.join("drive");
+        std::fs::create_dir_all(&local_path)?;
+        let mut peer_drive = drive;
+        peer_drive.local_path = local_path.clone();
+        peer_drive.update_stats(0, 0);
+        state
+            .db
+            .save_drive(peer_drive.id.as_bytes(), &serde_json::to_vec(&peer_drive)?)?;
+        state
+            .drives
+            .write()
+            .await
+            .insert(*peer_drive.id.as_bytes(), peer_drive.clone());
+
+        // Local edits in the peer's folder sync like any other member's
+        watcher.watch(peer_drive.id, local_path.clone()).await?;
+        let watcher_rx = watcher.subscribe();
+        let forwarder = tokio::spawn(forward_local_changes(watcher_rx, sync_engine.clone()));
+
+        let info = TestPeerInfo {
+            node_id: node_id.to_hex(),
+            drive_id: drive_hex,
+            local_path: local_path.to_string_lossy().to_string(),
+            data_dir: data_dir.to_string_lossy().to_string(),
+        };
+        Ok((info, forwarder))
+    }
+
+    /// List running test peers
+    pub async fn list(&self) -> Vec<TestPeerInfo> {
+        self.peers
+            .read()
+            .await
+            .values()
+            .map(|peer| peer.info.clone())
+            .collect()
+    }
+
+    /// Stop a test peer, revoke its access and delete its data
+    ///
+    /// Returns false if no test peer has that node ID.
+    pub async fn stop(&self, node_id: &str, security: &SecurityStore) -> bool {
+        let Some(peer) = self.peers.write().await.remove(node_id) else {
+            return false;
+        };
+
+        peer.forwarder.abort();
+        if let Some(ref watcher) = peer.state.file_watcher {
+            watcher.unwatch(&peer.drive_id).await;
+        }
+        if let Some(ref sync_engine) = peer.state.sync_engine {
+            sync_engine.stop_sync(&peer.drive_id).await;
+        }
+        peer.state.shutdown().await;
+
+        let drive_hex = &peer.info.drive_id;
+        let mut acl = security.get_or_create_acl(drive_hex, &peer.owner_hex).await;
+        acl.revoke(node_id);
+        security.update_acl(drive_hex, acl).await;
+
+        if let Err(e) = tokio::fs::remove_dir_all(&peer.info.data_dir).await {
+            tracing::warn!(error = %e, dir = %peer.info.data_dir, "Failed to remove peer data");
+        }
+        tracing::info!(node_id = %node_id, "Stopped test peer");
+        true
+    }
+
+    /// Stop every test peer (on app exit)
+    pub async fn stop_all(&self, security: &SecurityStore) {
+        let ids: Vec<String> = self.peers.read().await.keys().cloned().collect();
+        for id in ids {
+            self.stop(&id, security).await;
+        }
+    }
+}
+
+impl Default for TestPeerManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Feed the test peer's watcher events into its sync engine
+async fn forward_local_changes(
+    mut rx: broadcast::Receiver<(DriveId, crate::core::DriveEvent)>,
+    sync_engine: Arc<crate::network::SyncEngine>,
+) {
+    loop {
+        match rx.recv().await {
+            Ok((drive_id, event)) => {
+                if let Err(e) = sync_engine.on_local_change(&drive_id, event).await {
+                    tracing::warn!("Test peer failed to process local change: {}", e);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                tracing::warn!("Test peer watcher lagged, missed {} events", count);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
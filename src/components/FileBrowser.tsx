@@ -71,7 +71,7 @@ function getFileIconComponent(entry: FileEntry) {
     return <Folder size={16} />;
   }
 
-  const category = getFileCategory(entry.name);
+  const category = getFileCategory(entry.name, entry.content_type);
   const iconMap: Record<FileCategory, React.ReactNode> = {
     folder: <Folder size={16} />,
     document: <FileText size={16} />,
@@ -198,7 +198,7 @@ const FileRow = memo(function FileRow({
                 <Lock size={12} className="lock-icon other" />
               )}
               <span className={`lock-type-badge ${lock.lock_type}`}>
-                {lock.lock_type === "exclusive" ? "E" : "A"}
+                {lock.lock_type === "write" ? "W" : lock.lock_type === "read" ? "R" : "A"}
               </span>
             </span>
           )}
@@ -281,7 +281,7 @@ const GridItem = memo(function GridItem({
         )}
         {lock && !file.is_dir && (
           <span className={`grid-lock-badge ${lock.lock_type}`} title={LOCK_TYPE_DESCRIPTIONS[lock.lock_type as LockType]}>
-            {lock.lock_type === "exclusive" ? <Lock size={10} /> : <LockOpen size={10} />}
+            {lock.lock_type === "advisory" ? <LockOpen size={10} /> : <Lock size={10} />}
           </span>
         )}
       </div>
@@ -1228,7 +1228,7 @@ export function FileBrowser({ drive }: FileBrowserProps) {
                       Lock File
                       <button
                         className="btn-icon btn-info"
-                        title="Advisory: Warns others but doesn't prevent access. Exclusive: Prevents others from editing."
+                        title="Advisory: Warns others but doesn't prevent access. Read: Shared with other readers, blocks writers. Write: Prevents others from reading or editing."
                       >
                         <Info size={10} />
                       </button>
@@ -1238,10 +1238,15 @@ export function FileBrowser({ drive }: FileBrowserProps) {
                       Advisory Lock
                       <span className="lock-hint">Warns others</span>
                     </button>
-                    <button onClick={() => { acquireLock(contextMenu.file.path, "exclusive"); closeContextMenu(); }}>
+                    <button onClick={() => { acquireLock(contextMenu.file.path, "read"); closeContextMenu(); }}>
                       <Lock size={14} />
-                      Exclusive Lock
-                      <span className="lock-hint">Blocks editing</span>
+                      Read Lock
+                      <span className="lock-hint">Blocks writers</span>
+                    </button>
+                    <button onClick={() => { acquireLock(contextMenu.file.path, "write"); closeContextMenu(); }}>
+                      <Lock size={14} />
+                      Write Lock
+                      <span className="lock-hint">Blocks everyone</span>
                     </button>
                   </>
                 ) : (
@@ -99,7 +99,7 @@ export function FilePreview({
     if (!file) return null;
 
     const ext = file.name.split(".").pop()?.toLowerCase() || "";
-    const category = getFileCategory(file.name);
+    const category = getFileCategory(file.name, file.content_type);
     const isText = TEXT_EXTENSIONS.includes(ext);
     const isImage = IMAGE_EXTENSIONS.includes(ext);
 
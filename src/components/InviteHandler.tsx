@@ -32,8 +32,16 @@ export function InviteHandler({ onDriveJoined }: InviteHandlerProps) {
       if (!info.valid) {
         setError(info.error || "Invalid invite token");
         setInviteInfo(null);
-      } else {
-        setInviteInfo(info);
+        return;
+      }
+      setInviteInfo(info);
+
+      // Invites from trusted inviters skip the confirmation when enabled
+      const autoResult = await invoke<AcceptInviteResult | null>("auto_accept_invite", {
+        tokenString: invite.token,
+      });
+      if (autoResult) {
+        await finishJoin(autoResult);
       }
     } catch (err) {
       const message = err instanceof Error ? err.message : "Invalid or expired invite";
@@ -41,9 +49,33 @@ export function InviteHandler({ onDriveJoined }: InviteHandlerProps) {
     } finally {
       setLoading(false);
     }
-  }, []);
+  }, [onDriveJoined]);
+
+  const { inviteLink, error: linkError, clearInvite } = useDeepLink(handleInvite);
+
+  async function finishJoin(result: AcceptInviteResult) {
+    if (!result.success) {
+      setError(result.error || "Failed to join drive");
+      return;
+    }
+    setSuccess(true);
 
-  const { inviteLink, clearInvite } = useDeepLink(handleInvite);
+    // Start sync and watching for the newly joined drive
+    try {
+      await invoke("start_sync", { driveId: result.drive_id });
+      await invoke("start_watching", { driveId: result.drive_id });
+    } catch (syncErr) {
+      console.warn("Failed to start sync after joining:", syncErr);
+      // Don't fail the join - sync can be started manually
+    }
+
+    onDriveJoined?.(result.drive_id);
+
+    // Auto-close after success
+    setTimeout(() => {
+      handleClose();
+    }, 2000);
+  }
 
   const handleAccept = async () => {
     if (!currentToken || !inviteInfo) return;
@@ -57,27 +89,7 @@ export function InviteHandler({ onDriveJoined }: InviteHandlerProps) {
         tokenString: currentToken,
       });
 
-      if (result.success) {
-        setSuccess(true);
-        
-        // Start sync and watching for the newly joined drive
-        try {
-          await invoke("start_sync", { driveId: result.drive_id });
-          await invoke("start_watching", { driveId: result.drive_id });
-        } catch (syncErr) {
-          console.warn("Failed to start sync after joining:", syncErr);
-          // Don't fail the join - sync can be started manually
-        }
-        
-        onDriveJoined?.(result.drive_id);
-
-        // Auto-close after success
-        setTimeout(() => {
-          handleClose();
-        }, 2000);
-      } else {
-        setError(result.error || "Failed to join drive");
-      }
+      await finishJoin(result);
     } catch (err) {
       const message = err instanceof Error ? err.message : "Failed to join drive";
       setError(message);
@@ -94,8 +106,10 @@ export function InviteHandler({ onDriveJoined }: InviteHandlerProps) {
     clearInvite();
   };
 
+  const shownError = error ?? linkError;
+
   // Don't render if no invite is being processed
-  if (!inviteLink && !loading && !inviteInfo && !error) {
+  if (!inviteLink && !loading && !inviteInfo && !shownError) {
     return null;
   }
 
@@ -119,10 +133,10 @@ export function InviteHandler({ onDriveJoined }: InviteHandlerProps) {
               <Loader2 size={24} className="spinning" />
               <span>Verifying invite...</span>
             </div>
-          ) : error ? (
+          ) : shownError ? (
             <div className="invite-error">
               <AlertCircle size={24} />
-              <span>{error}</span>
+              <span>{shownError}</span>
               <button className="btn-text" onClick={handleClose}>
                 Dismiss
               </button>
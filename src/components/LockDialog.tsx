@@ -114,9 +114,13 @@ export function LockDialog({
                                         <span>{formatLockExpiry(existingLock.expires_at)}</span>
                                     </div>
                                 </div>
-                                {existingLock.lock_type === "exclusive" ? (
+                                {existingLock.lock_type === "write" ? (
                                     <p className="warning-message">
-                                        You cannot edit this file while it has an exclusive lock.
+                                        You cannot edit this file while it has a write lock.
+                                    </p>
+                                ) : existingLock.lock_type === "read" ? (
+                                    <p className="warning-message">
+                                        Others are reading this file. You can't edit it until they're done.
                                     </p>
                                 ) : (
                                     <p className="warning-message">
@@ -164,21 +168,39 @@ export function LockDialog({
                                         </p>
                                     </div>
                                 </label>
-                                <label className={`lock-option ${selectedLockType === "exclusive" ? "selected" : ""}`}>
+                                <label className={`lock-option ${selectedLockType === "read" ? "selected" : ""}`}>
+                                    <input
+                                        type="radio"
+                                        name="lockType"
+                                        value="read"
+                                        checked={selectedLockType === "read"}
+                                        onChange={() => setSelectedLockType("read")}
+                                    />
+                                    <div className="option-content">
+                                        <div className="option-header">
+                                            <Lock size={14} />
+                                            <span>{LOCK_TYPE_LABELS.read}</span>
+                                        </div>
+                                        <p className="option-description">
+                                            {LOCK_TYPE_DESCRIPTIONS.read}
+                                        </p>
+                                    </div>
+                                </label>
+                                <label className={`lock-option ${selectedLockType === "write" ? "selected" : ""}`}>
                                     <input
                                         type="radio"
                                         name="lockType"
-                                        value="exclusive"
-                                        checked={selectedLockType === "exclusive"}
-                                        onChange={() => setSelectedLockType("exclusive")}
+                                        value="write"
+                                        checked={selectedLockType === "write"}
+                                        onChange={() => setSelectedLockType("write")}
                                     />
                                     <div className="option-content">
                                         <div className="option-header">
                                             <Lock size={14} />
-                                            <span>{LOCK_TYPE_LABELS.exclusive}</span>
+                                            <span>{LOCK_TYPE_LABELS.write}</span>
                                         </div>
                                         <p className="option-description">
-                                            {LOCK_TYPE_DESCRIPTIONS.exclusive}
+                                            {LOCK_TYPE_DESCRIPTIONS.write}
                                         </p>
                                     </div>
                                 </label>
@@ -209,7 +231,7 @@ export function LockDialog({
                         </>
                     )}
 
-                    {isLockedByOther && existingLock?.lock_type === "exclusive" && (
+                    {isLockedByOther && existingLock && existingLock.lock_type !== "advisory" && (
                         <button className="btn-secondary" onClick={onCancel}>
                             Close
                         </button>
@@ -7,7 +7,7 @@ import { mockDrive } from '../test/mocks';
 // Mock the useDriveEvents hook
 vi.mock('../hooks', () => ({
     useDriveEvents: vi.fn(() => ({
-        syncStatus: { is_syncing: false, connected_peers: 0, last_sync: null },
+        syncStatus: { is_syncing: false, state: 'stopped', connected_peers: 0, last_sync: null },
         isSyncing: false,
         startSync: vi.fn(),
         stopSync: vi.fn(),
@@ -37,7 +37,7 @@ describe('SyncStatusBar', () => {
     it('shows syncing state when sync is active', async () => {
         const { useDriveEvents } = await import('../hooks');
         vi.mocked(useDriveEvents).mockReturnValue({
-            syncStatus: { is_syncing: true, connected_peers: 3, last_sync: null },
+            syncStatus: { is_syncing: true, state: 'syncing', connected_peers: 3, last_sync: null },
             isSyncing: true,
             startSync: vi.fn(),
             stopSync: vi.fn(),
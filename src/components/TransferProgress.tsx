@@ -10,7 +10,7 @@ import {
     Loader2,
 } from "lucide-react";
 import type { DriveInfo, TransferState } from "../types";
-import { formatBytes, getTransferProgress } from "../types";
+import { formatBytes, getTransferProgress, isRetrying } from "../types";
 import { useFileTransfer } from "../hooks";
 
 interface TransferProgressProps {
@@ -26,7 +26,11 @@ export function TransferProgress({ drive }: TransferProgressProps) {
 
     // Filter to show only active or recent transfers
     const activeTransfers = transfers.filter(
-        (t) => t.status === "Pending" || t.status === "InProgress"
+        (t) =>
+            t.status === "Pending" ||
+            t.status === "Queued" ||
+            t.status === "InProgress" ||
+            isRetrying(t.status)
     );
     const recentCompleted = transfers
         .filter((t) => t.status === "Completed" || t.status === "Failed")
@@ -85,9 +89,12 @@ function TransferItem({ transfer, onCancel }: TransferItemProps) {
     const progress = getTransferProgress(transfer);
     const fileName = transfer.path.split(/[/\\]/).pop() || transfer.path;
     const isActive = transfer.status === "Pending" || transfer.status === "InProgress";
+    const isQueued = transfer.status === "Queued";
+    const retrying = isRetrying(transfer.status) ? transfer.status.Retrying : null;
+    const statusClass = retrying ? "retrying" : String(transfer.status).toLowerCase();
 
     return (
-        <div className={`transfer-item ${transfer.status.toLowerCase()}`}>
+        <div className={`transfer-item ${statusClass}`}>
             <div className="transfer-icon">
                 {transfer.direction === "Upload" ? (
                     <Upload size={14} />
@@ -101,7 +108,14 @@ function TransferItem({ transfer, onCancel }: TransferItemProps) {
                     {fileName}
                 </div>
                 <div className="transfer-details">
-                    {isActive ? (
+                    {isQueued ? (
+                        <span className="transfer-queued">Queued</span>
+                    ) : retrying ? (
+                        <span className="transfer-queued" title={transfer.error ?? undefined}>
+                            Retrying in {Math.ceil(retrying.next_retry_ms / 1000)}s (attempt{" "}
+                            {retrying.attempt + 1})
+                        </span>
+                    ) : isActive ? (
                         <>
                             <span className="transfer-size">
                                 {formatBytes(transfer.bytes_transferred)} / {formatBytes(transfer.total_bytes)}
@@ -130,7 +144,7 @@ function TransferItem({ transfer, onCancel }: TransferItemProps) {
                 </div>
             </div>
 
-            {isActive && (
+            {(isActive || isQueued || retrying) && (
                 <button
                     className="btn-icon btn-cancel"
                     onClick={onCancel}
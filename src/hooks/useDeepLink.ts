@@ -1,4 +1,5 @@
 import { useState, useEffect, useCallback } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { onOpenUrl, getCurrent } from '@tauri-apps/plugin-deep-link';
 
 interface InviteLink {
@@ -15,28 +16,23 @@ interface DeepLinkState {
 /**
  * Parse a gix:// deep link URL to extract invite information.
  *
+ * The token is validated by the backend (`parse_invite_link`), which
+ * rejects other schemes and malformed tokens.
+ *
  * Supported formats:
  * - gix://invite/{token}
  * - gix://invite/{token}?drive={driveId}
  */
-function parseDeepLink(url: string): InviteLink | null {
+async function parseDeepLink(url: string): Promise<InviteLink> {
+  const token = await invoke<string>('parse_invite_link', { url });
+  let driveId: string | undefined;
   try {
-    // Handle gix:// scheme
-    const urlObj = new URL(url.replace('gix://', 'https://gix.local/'));
-
-    // Check for invite path
-    const pathParts = urlObj.pathname.split('/').filter(Boolean);
-    if (pathParts[0] === 'invite' && pathParts[1]) {
-      return {
-        token: pathParts[1],
-        driveId: urlObj.searchParams.get('drive') ?? undefined,
-      };
-    }
-
-    return null;
+    const urlObj = new URL(url.replace(/^gix:\/\//i, 'https://gix.local/'));
+    driveId = urlObj.searchParams.get('drive') ?? undefined;
   } catch {
-    return null;
+    driveId = undefined;
   }
+  return { token, driveId };
 }
 
 /**
@@ -54,20 +50,26 @@ export function useDeepLink(onInvite?: (invite: InviteLink) => void) {
   });
 
   const handleUrl = useCallback(
-    (url: string) => {
+    async (url: string) => {
       setState(prev => ({
         ...prev,
         lastUrl: url,
         error: null,
       }));
 
-      const invite = parseDeepLink(url);
-      if (invite) {
+      try {
+        const invite = await parseDeepLink(url);
         setState(prev => ({
           ...prev,
           inviteLink: invite,
         }));
         onInvite?.(invite);
+      } catch (error) {
+        setState(prev => ({
+          ...prev,
+          inviteLink: null,
+          error: error instanceof Error ? error.message : String(error),
+        }));
       }
     },
     [onInvite]
@@ -77,6 +79,7 @@ export function useDeepLink(onInvite?: (invite: InviteLink) => void) {
     setState(prev => ({
       ...prev,
       inviteLink: null,
+      error: null,
     }));
   }, []);
 
@@ -54,6 +54,8 @@ export function useDriveEvents({
     // Subscribe to Tauri events
     useEffect(() => {
         let unlisten: UnlistenFn | null = null;
+        let subscriptionId: string | null = null;
+        let cancelled = false;
 
         const setup = async () => {
             // Listen for drive events from backend
@@ -76,7 +78,12 @@ export function useDriveEvents({
 
             // Notify backend that we're subscribed
             try {
-                await invoke("subscribe_drive_events", { driveId });
+                const id = await invoke<string>("subscribe_drive_events", { driveId });
+                if (cancelled) {
+                    await invoke("unsubscribe_drive_events", { subscriptionId: id });
+                } else {
+                    subscriptionId = id;
+                }
             } catch (err) {
                 console.warn("Failed to subscribe to drive events:", err);
             }
@@ -85,7 +92,11 @@ export function useDriveEvents({
         setup();
 
         return () => {
+            cancelled = true;
             unlisten?.();
+            if (subscriptionId) {
+                invoke("unsubscribe_drive_events", { subscriptionId }).catch(() => {});
+            }
         };
     }, [driveId, maxEvents, onEvent]);
 
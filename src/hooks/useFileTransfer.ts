@@ -2,6 +2,7 @@ import { useCallback, useEffect, useState, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import type { TransferState, TransferProgress } from "../types";
+import { isRetrying } from "../types";
 
 /** Options for the useFileTransfer hook */
 interface UseFileTransferOptions {
@@ -208,7 +209,11 @@ export function useFileTransfer(
 
     // Check if any transfer is in progress
     const isTransferring = transfers.some(
-        (t) => t.status === "InProgress" || t.status === "Pending"
+        (t) =>
+            t.status === "InProgress" ||
+            t.status === "Pending" ||
+            t.status === "Queued" ||
+            isRetrying(t.status)
     );
 
     return {
@@ -50,7 +50,7 @@ interface UseLockingResult {
  * }
  *
  * // Acquire a lock before editing
- * const result = await acquireLock(file.path, 'exclusive');
+ * const result = await acquireLock(file.path, 'write');
  * if (!result.success) {
  *   console.error(result.error);
  * }
@@ -95,6 +95,7 @@ export function useLocking({
     // Subscribe to lock events - only re-subscribe when driveId changes
     useEffect(() => {
         let unlisten: UnlistenFn | null = null;
+        let subscriptionId: string | null = null;
         let mounted = true;
 
         const setup = async () => {
@@ -160,6 +161,18 @@ export function useLocking({
                 }
             });
 
+            // Register the listener so the backend keeps emitting globally
+            try {
+                const id = await invoke<string>("subscribe_drive_events", { driveId });
+                if (!mounted) {
+                    await invoke("unsubscribe_drive_events", { subscriptionId: id });
+                } else {
+                    subscriptionId = id;
+                }
+            } catch (err) {
+                console.warn("Failed to subscribe to lock events:", err);
+            }
+
             // Initial fetch
             if (mounted) {
                 await refreshLocks();
@@ -171,6 +184,9 @@ export function useLocking({
         return () => {
             mounted = false;
             unlisten?.();
+            if (subscriptionId) {
+                invoke("unsubscribe_drive_events", { subscriptionId }).catch(() => {});
+            }
         };
     }, [driveId, refreshLocks]);
 
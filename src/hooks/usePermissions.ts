@@ -114,35 +114,15 @@ export function usePermissions({
                 setPermissionLevel(myPermission.permission);
                 setIsOwner(myPermission.is_owner);
             } else {
-                // Try to get our specific permission
+                // Ask the backend which permission we resolve to on the drive root
                 try {
-                    const hasRead = await invoke<boolean>("check_permission", {
-                        driveId,
-                        operation: "read",
-                    });
-                    
-                    if (hasRead) {
-                        // Determine level by checking each operation
-                        const hasWrite = await invoke<boolean>("check_permission", {
-                            driveId,
-                            operation: "write",
-                        });
-                        const hasManage = await invoke<boolean>("check_permission", {
-                            driveId,
-                            operation: "manage_users",
-                        });
-                        const hasAdmin = await invoke<boolean>("check_permission", {
-                            driveId,
-                            operation: "admin",
-                        });
-
-                        if (hasAdmin) setPermissionLevel("admin");
-                        else if (hasManage) setPermissionLevel("manage");
-                        else if (hasWrite) setPermissionLevel("write");
-                        else setPermissionLevel("read");
-                    }
+                    const level = await invoke<PermissionLevel | null>(
+                        "get_effective_permission",
+                        { driveId, path: "/" },
+                    );
+                    if (level) setPermissionLevel(level);
                 } catch {
-                    // If check_permission fails, assume read access since we can see the drive
+                    // If resolution fails, assume read access since we can see the drive
                     setPermissionLevel("read");
                 }
             }
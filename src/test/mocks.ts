@@ -29,13 +29,17 @@ export const mockTransfer: TransferState = {
     total_bytes: 1024000,
     hash: 'abc123',
     error: null,
+    started_at: '2024-01-01T00:00:00Z',
+    bytes_per_sec: 256000,
+    eta_secs: 2,
+    resume_failures: 0,
 };
 
 // Mock file lock
 export const mockFileLock: FileLockInfo = {
     path: '/documents/file.txt',
     holder: 'node456',
-    lock_type: 'exclusive',
+    lock_type: 'write',
     acquired_at: '2024-01-01T10:00:00Z',
     expires_at: '2024-01-01T11:00:00Z',
     reason: null,
@@ -36,6 +36,10 @@ export interface FileEntry {
     is_local: boolean;
     /** BLAKE3 content hash for file transfer (undefined for directories) */
     content_hash?: string;
+    /** Why sync is skipping this file, if it was denied access to it */
+    permission_error?: string;
+    /** Detected content type, e.g. "image/png" (undefined if unknown) */
+    content_type?: string;
 }
 
 /** File type categories for icon mapping */
@@ -51,9 +55,10 @@ export type FileCategory =
     | "unknown";
 
 /**
- * Get file category from file extension
+ * Get file category from file extension, falling back to the detected
+ * content type
  */
-export function getFileCategory(filename: string): FileCategory {
+export function getFileCategory(filename: string, contentType?: string): FileCategory {
     const ext = filename.split(".").pop()?.toLowerCase() || "";
 
     const categories: Record<string, FileCategory> = {
@@ -127,7 +132,19 @@ export function getFileCategory(filename: string): FileCategory {
         sql: "data",
     };
 
-    return categories[ext] || "unknown";
+    return categories[ext] || categoryForContentType(contentType);
+}
+
+/** Fall back to the detected content type for unrecognized extensions */
+function categoryForContentType(contentType?: string): FileCategory {
+    if (!contentType) return "unknown";
+    const [kind, subtype] = contentType.split("/");
+    if (kind === "image" || kind === "video" || kind === "audio") return kind;
+    if (kind === "text") return "document";
+    if (contentType === "application/pdf") return "document";
+    if (/zip|gzip|7z|rar|xz|bzip2|zstd/.test(subtype ?? "")) return "archive";
+    if (contentType === "application/vnd.sqlite3") return "data";
+    return "unknown";
 }
 
 /**
@@ -148,9 +165,13 @@ export function formatBytes(bytes: number): string {
 // Phase 2: Sync Types
 // ============================================
 
+/** Where a drive's sync stands */
+export type SyncState = "stopped" | "syncing" | "paused" | "drained";
+
 /** Sync status for a drive */
 export interface SyncStatus {
     is_syncing: boolean;
+    state: SyncState;
     connected_peers: number;
     last_sync: string | null;
 }
@@ -267,11 +288,20 @@ export type TransferDirection = "Upload" | "Download";
 /** Transfer status */
 export type TransferStatus =
     | "Pending"
+    | "Queued"
     | "InProgress"
+    | { Retrying: { attempt: number; next_retry_ms: number } }
     | "Completed"
     | "Failed"
     | "Cancelled";
 
+/** Whether a transfer is waiting to retry after a failed attempt */
+export function isRetrying(
+    status: TransferStatus
+): status is { Retrying: { attempt: number; next_retry_ms: number } } {
+    return typeof status === "object" && "Retrying" in status;
+}
+
 /** Transfer state for tracking active transfers */
 export interface TransferState {
     /** Unique transfer ID */
@@ -292,6 +322,14 @@ export interface TransferState {
     hash: string | null;
     /** Error message if failed */
     error: string | null;
+    /** ISO 8601 time the transfer started running */
+    started_at: string;
+    /** Throughput over the last few seconds while running */
+    bytes_per_sec: number | null;
+    /** Estimated seconds until done; null if the size or speed is unknown */
+    eta_secs: number | null;
+    /** Times resuming this upload failed */
+    resume_failures: number;
 }
 
 /** Progress event for transfers */
@@ -303,6 +341,8 @@ export interface TransferProgress {
     bytes_transferred: number;
     total_bytes: number;
     status: TransferStatus;
+    /** Throughput over the last few seconds while running */
+    bytes_per_sec: number | null;
 }
 
 /**
@@ -400,6 +440,8 @@ export interface CreateInviteRequest {
 /** Generated invite token info */
 export interface InviteInfo {
     token: string;
+    /** gix://invite/<token> deep link */
+    link: string;
     drive_id: string;
     permission: PermissionLevel;
     expires_at: string;
@@ -450,18 +492,20 @@ export interface FileContent {
 // ============================================
 
 /** Lock type for files */
-export type LockType = "advisory" | "exclusive";
+export type LockType = "advisory" | "read" | "write";
 
 /** Lock type display names */
 export const LOCK_TYPE_LABELS: Record<LockType, string> = {
     advisory: "Advisory",
-    exclusive: "Exclusive",
+    read: "Read",
+    write: "Write",
 };
 
 /** Lock type descriptions */
 export const LOCK_TYPE_DESCRIPTIONS: Record<LockType, string> = {
     advisory: "Warns others but doesn't prevent access",
-    exclusive: "Prevents others from editing the file",
+    read: "Shared with other readers, blocks writers",
+    write: "Prevents others from reading or editing the file",
 };
 
 /** File lock information */